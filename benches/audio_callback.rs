@@ -0,0 +1,125 @@
+//! Cost of the capture callback path (convert, downmix, resample to
+//! 16kHz) for 48kHz stereo input.
+//!
+//!     cargo bench --bench audio_callback
+//!
+//! That the path doesn't allocate is a unit test in src/audio/process.rs; this
+//! measures time. With TOMCHAT_PERF_GATE=1 it fails if a callback takes longer than
+//! PERF_GATE_MAX_PER_CALLBACK, so additions like denoising can't quietly eat
+//! the real-time budget (10ms of audio per 480-frame callback).
+//!
+//! Noise suppression runs once per recording rather than per callback; it is
+//! benched on a 10-second utterance and gated at DENOISE_GATE_MAX_10S; its
+//! output is checked by the tests in src/audio/denoise.rs.
+
+use criterion::{black_box, BenchmarkId, Criterion};
+use std::time::{Duration, Instant};
+
+#[allow(dead_code)]
+#[path = "../src/audio/process.rs"]
+mod process;
+#[allow(dead_code)]
+#[path = "../src/audio/denoise.rs"]
+mod denoise;
+
+use process::{CallbackProcessor, ChannelMode};
+
+/// Generous: a 1024-frame callback carries 21ms of audio
+const PERF_GATE_MAX_PER_CALLBACK: Duration = Duration::from_millis(1);
+const CALLBACK_FRAMES: &[usize] = &[480, 1024];
+/// Noise suppression is added to the latency after the recording stops
+const DENOISE_GATE_MAX_10S: Duration = Duration::from_millis(50);
+
+fn stereo_f32(frames: usize) -> Vec<f32> {
+    (0..frames * 2).map(|i| ((i as f32) * 0.01).sin() * 0.5).collect()
+}
+
+fn stereo_i16(frames: usize) -> Vec<i16> {
+    stereo_f32(frames).iter().map(|s| (s * i16::MAX as f32) as i16).collect()
+}
+
+fn bench_callbacks(criterion: &mut Criterion) {
+    let mut processor = CallbackProcessor::new(2, 48000, ChannelMode::Mix);
+    let mut group = criterion.benchmark_group("callback_48k_stereo");
+    // Reused like the callback's pooled buffers
+    let mut out = Vec::new();
+    for &frames in CALLBACK_FRAMES {
+        let f32_input = stereo_f32(frames);
+        let i16_input = stereo_i16(frames);
+        group.bench_with_input(BenchmarkId::new("f32", frames), &f32_input, |b, input| {
+            b.iter(|| {
+                out.clear();
+                processor.process_into(black_box(input.as_slice()), &mut out)
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("i16", frames), &i16_input, |b, input| {
+            b.iter(|| {
+                out.clear();
+                processor.process_into(black_box(input.as_slice()), &mut out)
+            })
+        });
+    }
+    group.finish();
+}
+
+/// 10s at 16kHz: a 300Hz tone every other half second over white noise
+fn noisy_utterance() -> Vec<f32> {
+    let mut seed = 1u64;
+    (0..160_000)
+        .map(|i| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let noise = ((seed >> 33) as f32 / (1u64 << 31) as f32 * 2.0 - 1.0) * 0.03;
+            let t = i as f32 / 16000.0;
+            let tone = if ((t * 2.0) as usize).is_multiple_of(2) { 0.3 * (2.0 * std::f32::consts::PI * 300.0 * t).sin() } else { 0.0 };
+            tone + noise
+        })
+        .collect()
+}
+
+fn bench_denoise(criterion: &mut Criterion) {
+    let input = noisy_utterance();
+    criterion.bench_function("denoise_10s", |b| {
+        b.iter(|| {
+            let mut samples = input.clone();
+            denoise::suppress(black_box(&mut samples))
+        })
+    });
+}
+
+fn perf_gate() {
+    let mut processor = CallbackProcessor::new(2, 48000, ChannelMode::Mix);
+    let input = stereo_f32(1024);
+    let callbacks = 10_000;
+
+    let started = Instant::now();
+    for _ in 0..callbacks {
+        black_box(processor.process(black_box(input.as_slice())));
+    }
+    let per_callback = started.elapsed() / callbacks;
+
+    println!("perf gate: {:?} per 1024-frame callback (limit {:?})", per_callback, PERF_GATE_MAX_PER_CALLBACK);
+    assert!(
+        per_callback <= PERF_GATE_MAX_PER_CALLBACK,
+        "callback path takes {:?}, over the {:?} budget",
+        per_callback,
+        PERF_GATE_MAX_PER_CALLBACK
+    );
+
+    let mut samples = noisy_utterance();
+    let started = Instant::now();
+    black_box(denoise::suppress(black_box(&mut samples)));
+    let elapsed = started.elapsed();
+    println!("perf gate: {:?} to denoise 10s (limit {:?})", elapsed, DENOISE_GATE_MAX_10S);
+    assert!(elapsed <= DENOISE_GATE_MAX_10S, "noise suppression takes {:?} for 10s, over the {:?} budget", elapsed, DENOISE_GATE_MAX_10S);
+}
+
+fn main() {
+    let mut criterion = Criterion::default().configure_from_args();
+    bench_callbacks(&mut criterion);
+    bench_denoise(&mut criterion);
+    criterion.final_summary();
+
+    if std::env::var_os("TOMCHAT_PERF_GATE").is_some() {
+        perf_gate();
+    }
+}
@@ -1,76 +1,305 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use futures_util::{future::{BoxFuture, Shared}, FutureExt};
 use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::{mpsc, Mutex};
+use tokio::sync::{mpsc, oneshot, watch, Mutex};
 use tracing::{error, info, debug, warn};
 
-use crate::audio::{AudioCapture, VoiceActivityDetector, VadResult};
+use crate::audio::{denoise, wav, AudioCapture, AudioChunk, GainDecision, GainStage, GainTracker, LevelMeter, NoiseAdapter, LEVEL_INTERVAL, PreRoll, Reconnect, SpeechEdge, SpeechEdges, VoiceActivityDetector, VadResult, AUDIO_CHANNEL_CHUNKS, TARGET_RATE};
+use crate::cancel::{self, CancellationToken, JobStage};
+use crate::compose::{CancelOutcome, ComposeSession};
 use crate::config::Config;
-use crate::input::{HotkeyEvent, HotkeyManager, TextInjector};
-use crate::speech::SpeechTranscriber;
-use crate::text_refinement::TextRefiner;
+use crate::config_reload;
+use crate::correction;
+use crate::decode_queue::{DecodeQueue, QueueFull, Queued};
+use crate::download;
+use crate::endpoint::{DictationMode, LimitAction, OverflowPolicy, RecordingLimit, RecordingState, Segments, StopReason, RECORDING_STALL};
+use crate::error::{PipelineError, Recovery};
+use crate::events::{self, BusEvent, EmitData, EmitStatus, EmitText};
+use crate::gui_writer::GuiWriter;
+use crate::history::{self, HistoryEntry, SessionHistory, StageTimings};
+use crate::indicator;
+use crate::numbers::NumberNormalizer;
+use crate::partials::PartialSchedule;
+use crate::form_fill;
+use crate::input::hold::HotkeyMode;
+use crate::input::gesture::{DoubleTap, TapOutcome};
+use crate::input::{self, HotkeyAction, HotkeyEvent, HotkeyManager, HotkeyRouter, RecordGesture, RecordKey, TextInjector};
+use crate::ipc::{self, IpcCommand};
+use crate::pipeline_state::{self, Job, PipelineState, STATE_INTERVAL};
+use crate::output::{clipboard, DedupGuard, OutputJob, SinkConfig, SinkKind, TextVariant, UtteranceMeta};
+use crate::push;
+use crate::retained::{RecordingRetainer, RedecodeModel, RedecodeOverrides};
+use crate::segments;
+use crate::speech::{self, backend, DecodePolicy, RemoteTranscriber, SpeechTranscriber, TranscriberBackend};
+use crate::spelling;
+use crate::text_diff;
+use crate::vocabulary::Vocabulary;
+use crate::spill::{self, Spill};
+use crate::supervisor;
+use crate::systemd;
+use crate::text_refinement::{PunctuateMode, TextRefinementConfig, TextRefiner};
+
+/// How long a newly selected input device gets to deliver audio before we keep the old one
+const AUDIO_DEVICE_PROBE: std::time::Duration = std::time::Duration::from_millis(1500);
+
+/// Output of the same recording with the same text within this window is dropped
+const DEDUP_WINDOW: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Times the audio task is started again after it exits or fails before the app gives up
+const MAX_AUDIO_RESTARTS: usize = 3;
+
+/// The transcription backend as it finishes loading. Decodes await it, so recordings made
+/// while the model loads queue up rather than fail.
+type LoadedModel = Shared<BoxFuture<'static, Result<Arc<dyn TranscriberBackend>, Arc<anyhow::Error>>>>;
+
+/// A saved recording played into the audio task in place of the microphone (`tomchat replay`).
+/// Nothing from a replay is written to the history, session or status files.
+pub struct ReplayFeed {
+    /// 16kHz mono, sent in buffer_duration_ms chunks as fast as the audio task takes them
+    pub audio: Vec<f32>,
+    /// Each finished dictation, before it goes to the sinks
+    pub results: mpsc::Sender<HistoryEntry>,
+    /// Fires once the recording has left the pipeline, whether or not it produced a result
+    pub done: oneshot::Sender<()>,
+}
 
 pub struct TomChatApp {
     config: Config,
     audio_capture: AudioCapture,
     vad: VoiceActivityDetector,
-    transcriber: SpeechTranscriber,
+    transcriber: LoadedModel,
     text_refiner: Option<TextRefiner>,
+    punctuator: Option<TextRefiner>,
     text_injector: TextInjector,
     hotkey_manager: HotkeyManager,
+    sinks: Vec<SinkConfig>,
+    gui_writer: GuiWriter,
     gui_mode: bool,
     test_mode: bool,
+    /// Off with --no-hotkeys, for a GUI that handles its own shortcuts
+    hotkeys_enabled: bool,
+    replay: Option<ReplayFeed>,
 }
 
 impl TomChatApp {
-    pub async fn new(config: Config) -> Result<Self> {
+    pub async fn new(config: Config, gui_mode: bool) -> Result<Self> {
         info!("Initializing TomChat...");
+        let init_start = std::time::Instant::now();
+        let gui_writer = GuiWriter::spawn();
 
-        // Initialize audio capture
-        let audio_capture = AudioCapture::new()?;
+        // Staged splash progress for the GUI
+        let progress = |component: &str, status: &str| {
+            debug!("Init {}: {}", component, status);
+            if gui_mode {
+                gui_writer.emit("init_progress", serde_json::json!({
+                    "data": { "component": component, "status": status },
+                }));
+            }
+        };
 
-        // Initialize Silero VAD
-        let vad = VoiceActivityDetector::new(
-            &config.vad.model_path,
-            config.audio.sample_rate,
-            config.vad.sensitivity.to_webrtc_mode(),
-            config.vad.timeout_ms,
-        )?;
-
-        // Initialize Parakeet transcriber
-        let transcriber = SpeechTranscriber::new(
-            &config.speech.model_dir,
-            Some(&config.speech.language),
-        )?;
-
-        // Initialize text refiner (optional)
-        let text_refiner = if let Some(ref refinement_config) = config.text_refinement {
-            if refinement_config.enabled {
-                match TextRefiner::new(refinement_config.clone()).await {
-                    Ok(refiner) => {
-                        info!("Text refinement initialized");
-                        Some(refiner)
+        // Opt-in: fetch a missing model first, with progress in the log and the GUI
+        let uses_local_model = backend::uses_local_model(&config.speech, &config.remote_transcription);
+        if config.speech.auto_download && uses_local_model {
+            progress("speech_model", "downloading");
+            let (tx, mut rx) = mpsc::unbounded_channel::<download::DownloadProgress>();
+            let download_writer = gui_writer.clone();
+            let renderer = tokio::spawn(async move {
+                let mut logged_decile = None;
+                while let Some(update) = rx.recv().await {
+                    let decile = update.percent.map(|percent| (percent / 10.0) as u32);
+                    if decile.is_some() && decile != logged_decile {
+                        logged_decile = decile;
+                        info!("📥 {}: {:.0}% of {:.0} MB", update.model, update.percent.unwrap_or(0.0), update.total.unwrap_or(0) as f64 / 1_048_576.0);
                     }
-                    Err(e) => {
-                        warn!("Text refinement failed: {}, continuing without", e);
-                        None
+                    if gui_mode {
+                        download_writer.emit("model_download_progress", serde_json::json!({ "data": update }));
                     }
                 }
-            } else {
-                debug!("Text refinement disabled");
-                None
+            });
+            let fetched = download::fetch_missing_speech_model(&config.speech.model_dir, config.speech.model_sha256.as_deref(), Some(tx)).await;
+            let _ = renderer.await;
+            fetched.context("speech.auto_download failed")?;
+        }
+
+        // Model loads are slow and independent - run them on the blocking pool
+        progress("speech_model", "loading");
+        let speech_config = (
+            config.speech.model_dir.clone(),
+            config.speech.language.clone(),
+            config.speech.min_memory_headroom_mb,
+            config.speech.decode_policy(),
+            config.speech.hallucination_blocklist.clone(),
+        );
+        let remote = RemoteTranscriber::from_config(&config.speech, &config.remote_transcription)?;
+        let fallback_to_local = config.remote_transcription.fallback_to_local;
+        let transcriber_task = tokio::task::spawn_blocking(move || {
+            let transcriber = backend::select(remote, fallback_to_local, || {
+                let (model_dir, language, min_memory_headroom_mb, decode_policy, blocklist) = speech_config;
+                let load_start = std::time::Instant::now();
+                let mut transcriber = SpeechTranscriber::new(&model_dir, Some(&language), min_memory_headroom_mb, decode_policy)?;
+                transcriber.set_hallucination_blocklist(&blocklist);
+                info!("Speech model loaded in {:.2}s", load_start.elapsed().as_secs_f32());
+                Ok(transcriber)
+            })?;
+            Ok::<_, anyhow::Error>(Arc::from(transcriber))
+        });
+        // Not awaited here: the hotkeys are usable while the model loads
+        let transcriber: LoadedModel = async move {
+            match transcriber_task.await {
+                Ok(loaded) => loaded.context("Speech model initialization failed").map_err(Arc::new),
+                Err(e) => Err(Arc::new(anyhow::anyhow!("Speech model initialization panicked: {}", e))),
             }
-        } else {
-            None
+        }
+        .boxed()
+        .shared();
+
+        progress("vad", "loading");
+        // Capture hands over TARGET_RATE audio whatever the device runs at
+        let vad_config = (
+            config.vad.model_path.clone(),
+            TARGET_RATE,
+            config.vad.sensitivity.to_threshold(),
+            config.vad.timeout_ms,
+            config.vad.min_speech_ms,
+            config.vad.hangover_ms,
+            config.vad.engine,
+            config.vad.frame_ms,
+        );
+        let vad_task = tokio::task::spawn_blocking(move || {
+            let (model_path, sample_rate, threshold, timeout_ms, min_speech_ms, hangover_ms, engine, frame_ms) = vad_config;
+            VoiceActivityDetector::new(&model_path, sample_rate, threshold, timeout_ms, min_speech_ms, hangover_ms, engine, frame_ms)
+        });
+
+        // Model pull progress goes straight to the GUI; the event bus doesn't exist yet
+        let pull_writer = gui_writer.clone();
+        let pull_events: EmitStatus = Arc::new(move |event: &str, message: &str| {
+            if gui_mode {
+                pull_writer.emit(event, serde_json::json!({ "message": message }));
+            }
+        });
+
+        // Initialize text refiner (optional) - the Ollama health check runs concurrently
+        let refiner_future = async {
+            match config.text_refinement {
+                // record_refined needs the refiner even with refinement off for everything else
+                Some(ref refinement_config) if refinement_config.enabled || config.hotkeys.record_refined.is_some() => {
+                    progress("text_refinement", "connecting");
+                    let refinement_config = TextRefinementConfig { enabled: true, ..refinement_config.clone() };
+                    match TextRefiner::new(refinement_config, Some(pull_events)).await {
+                        Ok(refiner) => {
+                            info!("Text refinement initialized");
+                            progress("text_refinement", "ready");
+                            Some(refiner)
+                        }
+                        Err(e) => {
+                            warn!("Text refinement failed: {}, continuing without", e);
+                            progress("text_refinement", "unavailable");
+                            None
+                        }
+                    }
+                }
+                Some(_) => {
+                    debug!("Text refinement disabled");
+                    None
+                }
+                None => None,
+            }
+        };
+
+        // Punctuation uses the refinement model, but its own connection so it works with refinement off
+        let punctuator_future = async {
+            if config.text.punctuate != PunctuateMode::Llm {
+                return None;
+            }
+            let punctuation_config = TextRefinementConfig {
+                enabled: true,
+                ..config.text_refinement.clone().unwrap_or_default()
+            };
+            match TextRefiner::new(punctuation_config, None).await {
+                Ok(punctuator) => Some(punctuator),
+                Err(e) => {
+                    warn!("Punctuation restoration unavailable: {}, continuing without", e);
+                    None
+                }
+            }
+        };
+
+        // Audio, injector and hotkeys aren't Send-friendly, so they stay on this thread
+        // while the models load
+        let local_init = async {
+            progress("audio", "probing");
+            // A missing preferred device shouldn't stop dictation altogether
+            let mut audio_capture = match AudioCapture::with_device(config.audio.device.as_deref()) {
+                Ok(audio_capture) => audio_capture,
+                Err(e) if config.audio.device.is_some() => {
+                    warn!("⚠️  {} - falling back to the default input device", e);
+                    AudioCapture::new().context("Audio capture initialization failed")?
+                }
+                Err(e) => return Err(e.context("Audio capture initialization failed")),
+            };
+            audio_capture.set_buffer_duration_ms(config.audio.buffer_duration_ms);
+            config.audio.check_channel_mode(&audio_capture.device_name(), audio_capture.channels())?;
+            audio_capture.set_channel_mode(config.audio.channel_mode);
+            progress("audio", "ready");
+
+            let text_injector = TextInjector::new(config.text.typing_delay_ms, config.text.backend, config.text.dry_run)
+                .context("Text injector initialization failed")?;
+            progress("text_injector", "ready");
+
+            let hotkey_manager = HotkeyManager::new(config.hotkeys.backend, config.hotkeys.cmd_is_super)
+                .context("Hotkey manager initialization failed")?;
+            progress("hotkeys", "ready");
+
+            Ok::<_, anyhow::Error>((audio_capture, text_injector, hotkey_manager))
+        };
+
+        // The punctuation pass borrows the refiner's connection when there is one
+        let refiners_future = async {
+            let refiner = refiner_future.await;
+            let punctuator = match refiner {
+                Some(ref refiner) if config.text.punctuate == PunctuateMode::Llm => Some(refiner.punctuator()),
+                _ => punctuator_future.await,
+            };
+            (refiner, punctuator)
         };
 
-        // Initialize text injector
-        let text_injector = TextInjector::new(config.text.typing_delay_ms)?;
+        let (local, (text_refiner, punctuator), vad) = tokio::join!(local_init, refiners_future, vad_task);
+
+        let (audio_capture, text_injector, hotkey_manager) = local?;
+        let vad = vad?.context("VAD initialization failed")?;
+        match vad.fallback_reason() {
+            Some(reason) => {
+                progress("vad", "energy_fallback");
+                if gui_mode {
+                    gui_writer.emit("vad_degraded", serde_json::json!({
+                        "message": "Silero VAD unavailable, using energy detection",
+                        "reason": reason,
+                    }));
+                }
+            }
+            None => progress("vad", "ready"),
+        }
+
+        // Make sure every sink asks for a text variant that will exist
+        let sinks = config
+            .output
+            .sinks
+            .iter()
+            .map(|sink| SinkConfig {
+                variant: sink.variant.resolve(text_refiner.is_some(), config.output.template.is_some()),
+                ..sink.clone()
+            })
+            .collect::<Vec<_>>();
 
-        // Initialize hotkey manager
-        let hotkey_manager = HotkeyManager::new()?;
+        // GUI mode owns stdout for its JSON events
+        if gui_mode && sinks.iter().any(|sink| sink.kind == SinkKind::Stdout) {
+            return Err(anyhow::anyhow!("The stdout output sink can't be used with --gui-mode; use a file sink instead"));
+        }
 
-        info!("All components initialized successfully");
+        info!("All components initialized in {:.2}s, speech model still loading", init_start.elapsed().as_secs_f32());
 
         Ok(Self {
             config,
@@ -78,52 +307,398 @@ impl TomChatApp {
             vad,
             transcriber,
             text_refiner,
+            punctuator,
             text_injector,
             hotkey_manager,
-            gui_mode: false,
+            sinks,
+            gui_writer,
+            gui_mode,
             test_mode: false,
+            hotkeys_enabled: true,
+            replay: None,
         })
     }
 
-    pub fn set_gui_mode(&mut self, gui_mode: bool) {
-        self.gui_mode = gui_mode;
-    }
-
     pub fn set_test_mode(&mut self, test_mode: bool) {
         self.test_mode = test_mode;
     }
 
-    // Emit JSON status event to stdout when in GUI mode
-    fn emit_status(&self, event: &str, message: &str) {
-        if self.gui_mode {
-            let json = serde_json::json!({
-                "event": event,
-                "message": message,
-                "timestamp": std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs()
-            });
-            println!("{}", json);
-        }
+    pub fn set_hotkeys_enabled(&mut self, hotkeys_enabled: bool) {
+        self.hotkeys_enabled = hotkeys_enabled;
+    }
+
+    /// Record from `feed` instead of the input device
+    pub fn set_replay(&mut self, feed: ReplayFeed) {
+        self.replay = Some(feed);
     }
 
-    fn notify_state_change(recording: bool) {
+    fn notify_state_change(state_tx: &watch::Sender<bool>, emit_data: &EmitData, recording: bool) {
         info!("State change: recording={}", recording);
 
-        // Write state to file for Tauri app to read
-        let state_update = serde_json::json!({
-            "recording": recording,
-            "timestamp": std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs()
-        });
+        // Broadcast to in-process listeners (indicators)
+        state_tx.send_replace(recording);
+
+        // Push subscribers (the bubble's state file among them) pick this up from the bus
+        emit_data("state_changed", serde_json::json!({ "recording": recording }));
+    }
+
+    /// Log the gain chosen for a recording and report it to the GUI, for debugging mic levels
+    fn report_gain(emit_data: &EmitData, recording_id: u64, gain: &GainDecision) {
+        info!("🔊 Gain {:+.1} dB (level {:.1} dBFS, peak {:.1} dBFS{})",
+              gain.gain_db, gain.rms_dbfs, gain.peak_dbfs,
+              if gain.limited { ", limited to avoid clipping" } else { "" });
+        emit_data("audio_gain", serde_json::json!({
+            "recording_id": recording_id,
+            "gain": gain,
+        }));
+    }
+
+    /// Log a pipeline error with its remediation hint and report it to the GUI
+    fn report_error<F: Fn(&str, serde_json::Value) + ?Sized>(emit_data: &F, error: &PipelineError) {
+        let policy = error.policy();
+        match policy.recovery {
+            Recovery::Abort | Recovery::KeepAudio => error!("❌ {} ({})", error, policy.hint),
+            Recovery::UseUnrefined | Recovery::SkipSink => warn!("⚠️  {} ({})", error, policy.hint),
+        }
+        emit_data("pipeline_error", error.to_event());
+    }
+
+    /// Cancel the latest recording's job. A recording in progress is stopped and handed to
+    /// the audio task to discard; a job further down the pipeline stops at its next check.
+    /// Its audio is dropped from the retainer, so it can't be re-decoded either.
+    async fn cancel_latest(
+        recording_state: &Mutex<RecordingState>,
+        pipeline: &PipelineState,
+        retainer: &Mutex<RecordingRetainer>,
+        process_tx: &mpsc::Sender<ProcessSignal>,
+        state_tx: &watch::Sender<bool>,
+        emit_data: &EmitData,
+    ) {
+        let mut state = recording_state.lock().await;
+        let token = state.cancel_token();
+        // The latest job may have finished already; its token is left alone then
+        if token.is_cancelled() || (!state.is_recording && pipeline.snapshot().is_idle()) {
+            info!("Nothing to cancel");
+            return;
+        }
+        token.cancel();
+        // Nor is a cancelled recording kept for a re-decode
+        retainer.lock().await.clear();
+
+        if state.request_stop(StopReason::Cancelled, std::time::Instant::now()) {
+            TomChatApp::notify_state_change(state_tx, emit_data, false);
+            if process_tx.send((StopReason::Cancelled, token, state.trigger)).await.is_err() {
+                TomChatApp::report_error(&**emit_data, &PipelineError::PipelineUnavailable { task: "audio" });
+            }
+        } else {
+            info!("Cancel requested for the latest job");
+        }
+    }
+
+    /// Start compose mode, unless it's on already
+    async fn start_compose(compose: &Mutex<ComposeSession>, emit_status: &EmitStatus) {
+        let mut compose = compose.lock().await;
+        if compose.is_active() {
+            info!("Compose mode is on already");
+            return;
+        }
+        compose.start();
+        emit_status("compose_started", "Compose mode started");
+    }
+
+    /// End compose mode and hand the draft on to the sinks
+    async fn finish_compose(compose: &Mutex<ComposeSession>, draft_tx: &mpsc::Sender<FinishedText>, emit_status: &EmitStatus) {
+        let finished = {
+            let mut compose = compose.lock().await;
+            if !compose.is_active() {
+                info!("Compose mode isn't on");
+                return;
+            }
+            compose.finish()
+        };
+        emit_status("compose_finished", "Compose mode finished");
+        if let Some((text, meta)) = finished {
+            if draft_tx.send(FinishedText::ComposeDraft { text, meta }).await.is_err() {
+                error!("Failed to send compose draft");
+            }
+        }
+    }
+
+    /// A compose cancel press: the first arms it, a second in time discards the draft
+    async fn cancel_compose(compose: &Mutex<ComposeSession>, emit_status: &EmitStatus) {
+        match compose.lock().await.cancel(std::time::Instant::now()) {
+            CancelOutcome::Armed => {
+                info!("Press cancel again to discard the compose draft");
+                emit_status("compose_cancel_armed", "Press again to discard draft");
+            }
+            CancelOutcome::Discarded => {
+                emit_status("compose_cancelled", "Compose draft discarded");
+            }
+            CancelOutcome::Inactive => {}
+        }
+    }
+
+    /// Start or stop a recording for a press or release of a recording key (toggle_recording,
+    /// correction, record_raw, record_refined); the same in every mode, GUI mode included
+    #[allow(clippy::too_many_arguments)]
+    async fn record_key_event(
+        action: HotkeyAction,
+        pressed: bool,
+        record_key: &mut RecordKey,
+        recording_state: &Mutex<RecordingState>,
+        vad: &Mutex<VoiceActivityDetector>,
+        correction_armed: &AtomicBool,
+        process_tx: &mpsc::Sender<ProcessSignal>,
+        state_tx: &watch::Sender<bool>,
+        emit_status: &EmitStatus,
+        emit_data: &EmitData,
+    ) {
+        let mut state = recording_state.lock().await;
+        let now = std::time::Instant::now();
+        let gesture = record_key.event(action, pressed, state.is_recording, now);
+
+        if gesture == RecordGesture::Ignore {
+            return;
+        }
+        if let RecordGesture::Discard(held) = gesture {
+            // An auto-stop during the hold already handed the recording on; leave it be
+            let token = state.cancel_token();
+            if state.request_stop(StopReason::Cancelled, now) {
+                info!("Hotkey held for only {}ms, discarding the recording", held.as_millis());
+                token.cancel();
+                TomChatApp::notify_state_change(state_tx, emit_data, false);
+                if process_tx.send((StopReason::Cancelled, token, state.trigger)).await.is_err() {
+                    TomChatApp::report_error(&**emit_data, &PipelineError::PipelineUnavailable { task: "audio" });
+                }
+            }
+        } else if let Some(reason) = state.late_stop(now).filter(|_| gesture != RecordGesture::Start) {
+            // Pressed to stop just as auto-stop fired: that recording is already being processed
+            info!("Recording already stopped ({}), ignoring the stop press", reason.as_str());
+        } else if !state.is_recording && gesture != RecordGesture::Stop {
+            let correcting = action == HotkeyAction::Correction;
+            correction_armed.store(correcting, Ordering::SeqCst);
+            if correcting {
+                emit_status("correction_started", "Correction take started");
+            }
+            info!("Recording started by hotkey");
+            emit_status("recording_started", "Recording started");
+
+            // Reset VAD for new session
+            {
+                let mut vad = vad.lock().await;
+                vad.reset();
+            }
+
+            state.start();
+            state.trigger = Some(action);
+
+            // Notify bubble of state change
+            TomChatApp::notify_state_change(state_tx, emit_data, true);
+        } else if state.request_stop(StopReason::Hotkey, now) {
+            info!("Recording stopped by hotkey");
+            emit_data("recording_stopped", serde_json::json!({
+                "message": "Recording stopped",
+                "reason": StopReason::Hotkey,
+            }));
+
+            // Notify bubble of state change
+            TomChatApp::notify_state_change(state_tx, emit_data, false);
+
+            // Signal audio processing to transcribe accumulated audio
+            // Only fails once the audio task is gone for good; the recording stays buffered
+            if process_tx.send((StopReason::Hotkey, state.cancel_token(), state.trigger)).await.is_err() {
+                TomChatApp::report_error(&**emit_data, &PipelineError::PipelineUnavailable { task: "audio" });
+            }
+        }
+    }
+
+    /// The transcriber for a re-decode, reusing the last one loaded if it was set up the same
+    /// way. Only that one is kept, so it's freed before a differently set up one loads.
+    async fn load_redecode_model(
+        cache: &Mutex<Option<(RedecodeModel, Arc<SpeechTranscriber>)>>,
+        model: RedecodeModel,
+        min_memory_headroom_mb: u64,
+        decode_policy: &DecodePolicy,
+        blocklist: &[String],
+    ) -> Result<Arc<SpeechTranscriber>> {
+        let mut cache = cache.lock().await;
+        if let Some((ref loaded, ref transcriber)) = *cache {
+            if *loaded == model {
+                return Ok(transcriber.clone());
+            }
+        }
+        *cache = None;
+
+        info!("Loading re-decode model from {:?} ({}, beam {})", model.model_dir, model.language, model.beam_size);
+        let decode_policy = DecodePolicy { beam_size: model.beam_size, ..decode_policy.clone() };
+        let (loading, blocklist) = (model.clone(), blocklist.to_vec());
+        // Loading reads and parses the model files, so keep it off the runtime threads
+        let transcriber = tokio::task::spawn_blocking(move || {
+            SpeechTranscriber::new(&loading.model_dir, Some(&loading.language), min_memory_headroom_mb, decode_policy).map(|mut transcriber| {
+                transcriber.set_hallucination_blocklist(&blocklist);
+                Arc::new(transcriber)
+            })
+        })
+        .await??;
+        *cache = Some((model, transcriber.clone()));
+        Ok(transcriber)
+    }
+
+    /// Queue a recording for decoding and pass the text on for output
+    #[allow(clippy::too_many_arguments)]
+    async fn queue_transcription(
+        queue: &DecodeQueue,
+        transcriber: LoadedModel,
+        audio: RecordedAudio,
+        sample_rate: f64,
+        recording_id: u64,
+        redecode_of: Option<u64>,
+        stop_reason: Option<StopReason>,
+        segment: Option<u32>,
+        trigger: Option<HotkeyAction>,
+        cancel: CancellationToken,
+        job: Job,
+        saved_audio: Option<SavedAudio>,
+        tx: mpsc::Sender<Transcription>,
+        emit_text: EmitText,
+        emit_data: EmitData,
+    ) {
+        // At the rate capture actually delivered, which may be a little off 16kHz
+        let duration_ms = (audio.samples() as f64 * 1000.0 / sample_rate).round() as u64;
+        let ended_at_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+
+        let queued_events = emit_data.clone();
+        let decode = async move {
+            // The app shuts down if the model fails to load
+            let Ok(transcriber) = transcriber.await else { return };
+            job.enter(JobStage::Decoding);
+            let decode_started = std::time::Instant::now();
+            // A cancelled decode finishes in the background, its result is dropped
+            let decoded = match audio {
+                RecordedAudio::Memory(audio_data) => {
+                    let decoded = cancel::unless_cancelled(&cancel, backend::transcribe_recording(&*transcriber, &audio_data)).await;
+                    let saved_audio = match saved_audio {
+                        Some(saving) => saving.await.ok().flatten(),
+                        None => None,
+                    };
+                    decoded.map(|result| (result, saved_audio))
+                }
+                RecordedAudio::Spilled { spill, conditioning, save_to } => {
+                    TomChatApp::decode_spill(&*transcriber, spill, conditioning, save_to, &cancel).await
+                }
+            };
+            let Some((result, saved_audio)) = decoded else {
+                cancel::report(&*emit_data, Some(recording_id), JobStage::Decoding);
+                return;
+            };
+            // The GUI offers playback of the saved recording, if any
+            let audio = serde_json::json!({
+                "audio_path": saved_audio,
+                "action": trigger.map(HotkeyAction::as_str),
+            });
+            match result {
+                Ok(text) if !text.is_empty() => {
+                    emit_text("transcription_complete", "Transcription", &text, audio);
+                    emit_data("transcription_result", serde_json::json!({
+                        "recording_id": recording_id,
+                        "redecode_of": redecode_of,
+                    }));
+                    let transcription = Transcription {
+                        recording_id,
+                        text,
+                        redecode_of,
+                        ended_at_ms,
+                        duration_ms,
+                        stop_reason,
+                        segment,
+                        decode_ms: decode_started.elapsed().as_millis() as u64,
+                        audio_path: saved_audio,
+                        trigger,
+                        cancel,
+                        job,
+                    };
+                    transcription.job.wait();
+                    if tx.send(transcription).await.is_err() {
+                        error!("Failed to send transcription");
+                    }
+                }
+                Ok(_) => {
+                    emit_text("transcription_complete", "Empty transcription result", "", audio);
+                    debug!("Empty transcription result");
+                }
+                Err(e) => TomChatApp::report_error(&*emit_data, &e),
+            }
+        };
+        TomChatApp::submit_decode(queue, recording_id, decode.boxed(), &queued_events);
+    }
+
+    /// Decode a spilled recording from its file, then move the file to debug.save_audio_dir
+    /// or delete it; a failed decode leaves it for `tomchat recover`. None when cancelled.
+    async fn decode_spill(
+        transcriber: &dyn TranscriberBackend,
+        spill: Spill,
+        conditioning: Conditioning,
+        save_to: Option<(PathBuf, usize)>,
+        cancel: &CancellationToken,
+    ) -> Option<(Result<String, PipelineError>, Option<PathBuf>)> {
+        let samples = spill.samples();
+        let path = match tokio::task::spawn_blocking(move || spill.finish()).await.map_err(anyhow::Error::from) {
+            Ok(Ok(path)) => path,
+            Ok(Err(source)) | Err(source) => return Some((Err(PipelineError::ModelDecode { source }), None)),
+        };
+
+        let decode = spill::transcribe(transcriber, &path, move |chunk| conditioning.apply(chunk));
+        let Some(result) = cancel::unless_cancelled(cancel, decode).await else {
+            if let Err(e) = tokio::fs::remove_file(&path).await {
+                warn!("Failed to remove spill file {:?}: {}", path, e);
+            }
+            return None;
+        };
+        if let Err(ref e) = result {
+            error!("Long recording transcription failed, run `tomchat recover` to retry: {:#}", e);
+            return Some((result.map_err(|source| PipelineError::ModelDecode { source }), None));
+        }
+
+        let saved_audio = match save_to {
+            Some((dir, max_saved_files)) => {
+                let spill_path = path.clone();
+                tokio::task::spawn_blocking(move || {
+                    save_recording(&dir, max_saved_files, |dir| wav::adopt(dir, &spill_path, samples))
+                })
+                .await
+                .ok()
+                .flatten()
+            }
+            None => None,
+        };
+        if saved_audio.is_none() {
+            if let Err(e) = tokio::fs::remove_file(&path).await {
+                warn!("Failed to remove spill file {:?}: {}", path, e);
+            }
+        }
+        Some((result.map_err(|source| PipelineError::ModelDecode { source }), saved_audio))
+    }
 
-        let state_file = "/tmp/tomchat_bubble_state.json";
-        match std::fs::write(state_file, state_update.to_string()) {
-            Ok(_) => debug!("State update written to file: recording={}", recording),
-            Err(e) => error!("Failed to write state file: {}", e),
+    /// Hand a decode to the worker, telling the GUI when it has to wait its turn
+    fn submit_decode(queue: &DecodeQueue, recording_id: u64, job: BoxFuture<'static, ()>, emit_data: &EmitData) {
+        // Never waits: the audio task calls this, and has capture to keep up with
+        match queue.try_submit(recording_id, job) {
+            Ok(Queued { ahead, dropped }) => {
+                if let Some(dropped) = dropped {
+                    TomChatApp::report_error(&**emit_data, &PipelineError::DecodeQueueFull { recording_id: dropped });
+                }
+                if ahead > 0 {
+                    info!("Recording {} queued behind {} decode(s)", recording_id, ahead);
+                    emit_data("transcription_queued", serde_json::json!({
+                        "recording_id": recording_id,
+                        "ahead": ahead,
+                    }));
+                }
+            }
+            Err(QueueFull) => TomChatApp::report_error(&**emit_data, &PipelineError::DecodeQueueFull { recording_id }),
         }
     }
 
@@ -132,287 +707,2276 @@ impl TomChatApp {
 
         let gui_mode = self.gui_mode;
         let vad_auto_stop = self.config.vad.auto_stop;
+        let continuous = self.config.vad.mode == DictationMode::Continuous;
+        let min_speech_ratio = self.config.vad.min_speech_ratio;
+        let replay = self.replay.take();
+        let replaying = replay.is_some();
+
+        // All events go onto the bus; in GUI mode they're also printed as JSON lines
+        let bus = events::event_bus();
+        let gui_writer = self.gui_writer.clone();
 
         // Helper function to emit status events (shareable)
-        let emit_status = Arc::new(move |event: &str, message: &str| {
+        let bus_status = bus.clone();
+        let writer_status = gui_writer.clone();
+        let emit_status: EmitStatus = Arc::new(move |event: &str, message: &str| {
+            let bus_event = BusEvent::new(event, serde_json::json!({ "message": message }));
+            if gui_mode {
+                writer_status.emit(event, serde_json::json!({ "message": message }));
+            }
+            let _ = bus_status.send(bus_event);
+        });
+
+        // Like emit_status, but carries a structured payload instead of a message
+        let bus_data = bus.clone();
+        let writer_data = gui_writer.clone();
+        let emit_data: EmitData = Arc::new(move |event: &str, data: serde_json::Value| {
+            let bus_event = BusEvent::new(event, data);
+            if gui_mode {
+                writer_data.emit(event, serde_json::json!({ "data": bus_event.payload }));
+            }
+            let _ = bus_data.send(bus_event);
+        });
+
+        // Text-bearing events: readable preview in the message, full text alongside if opted in
+        let bus_text = bus.clone();
+        let writer_text = gui_writer.clone();
+        let preview_graphemes = self.config.gui.preview_graphemes;
+        let full_text = self.config.gui.full_text;
+        let emit_text: EmitText = Arc::new(move |event: &str, label: &str, text: &str, extra: serde_json::Value| {
+            let preview = events::preview(text, preview_graphemes);
+            let message = if text.is_empty() { label.to_string() } else { format!("{}: {}", label, preview) };
+            let mut payload = serde_json::json!({
+                "message": message,
+                "preview": preview,
+            });
+            if full_text {
+                payload["text"] = serde_json::json!(text);
+            }
+            if let (Some(payload), serde_json::Value::Object(extra)) = (payload.as_object_mut(), extra) {
+                payload.extend(extra);
+            }
+            let bus_event = BusEvent::new(event, payload);
             if gui_mode {
-                let json = serde_json::json!({
-                    "event": event,
-                    "message": message,
-                    "timestamp": std::time::SystemTime::now()
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .unwrap()
-                        .as_secs()
-                });
-                println!("{}", json);
+                writer_text.emit(event, bus_event.payload.clone());
             }
+            let _ = bus_text.send(bus_event);
         });
 
+        // Heartbeats let the GUI know we're alive and how many events it missed, and keep
+        // the systemd watchdog from restarting us
+        let watchdog = systemd::watchdog_interval();
+        if gui_mode || watchdog.is_some() {
+            let writer = gui_writer.clone();
+            tokio::spawn(async move {
+                let mut heartbeat = tokio::time::interval(std::time::Duration::from_secs(5));
+                let mut watchdog_ping = tokio::time::interval(watchdog.unwrap_or(std::time::Duration::from_secs(5)));
+                loop {
+                    tokio::select! {
+                        _ = heartbeat.tick(), if gui_mode => writer.heartbeat(),
+                        _ = watchdog_ping.tick(), if watchdog.is_some() => systemd::watchdog_ping(),
+                    }
+                }
+            });
+        }
+        tokio::spawn(systemd::follow_status(bus.clone()));
+
+        // Replicate events to push subscribers
+        tokio::spawn(push::run_push(self.config.gui.push.clone(), bus.clone(), emit_data.clone()));
+
+        // Busy indicator: sent on every change, and every second while anything is in flight
+        let pipeline = PipelineState::default();
+        let status_writer = {
+            let pipeline = pipeline.clone();
+            let emit_data = emit_data.clone();
+            tokio::spawn(async move {
+                let mut refresh = tokio::time::interval(STATE_INTERVAL);
+                loop {
+                    tokio::select! {
+                        _ = pipeline.changed() => {}
+                        _ = refresh.tick() => {
+                            if pipeline.snapshot().is_idle() {
+                                continue;
+                            }
+                        }
+                    }
+                    let snapshot = pipeline.snapshot();
+                    // A replay isn't the running instance `tomchat status` asks about
+                    if !replaying {
+                        if let Err(e) = pipeline_state::write_status(&snapshot).await {
+                            debug!("Failed to write pipeline state: {}", e);
+                        }
+                    }
+                    emit_data("pipeline_state", serde_json::json!(snapshot));
+                }
+            })
+        };
+
+        emit_data("privacy", serde_json::json!({ "local_only": self.config.privacy.local_only }));
+        let orphans = spill::orphans();
+        if !orphans.is_empty() {
+            warn!(
+                "⚠️  Found {} long recording(s) left on disk by a previous session; run `tomchat recover` to transcribe them or `tomchat recover --delete` to discard them",
+                orphans.len()
+            );
+            emit_data("orphaned_recordings", serde_json::json!({ "paths": orphans }));
+        }
+
         // Create communication channels
-        let (audio_tx, mut audio_rx) = mpsc::unbounded_channel::<Vec<f32>>();
+        let (audio_tx, audio_rx) = mpsc::channel::<AudioChunk>(AUDIO_CHANNEL_CHUNKS);
         let (hotkey_tx, mut hotkey_rx) = mpsc::channel::<HotkeyEvent>(100);
-        let (transcription_tx, mut transcription_rx) = mpsc::channel::<String>(100);
-        let (process_tx, mut process_rx) = mpsc::channel::<()>(10);
+        // Presses that didn't come from a key, so skip the router and the double-tap wait
+        let (action_tx, mut action_rx) = mpsc::channel::<HotkeyAction>(4);
+        let (transcription_tx, mut transcription_rx) = mpsc::channel::<Transcription>(100);
+        let (process_tx, process_rx) = mpsc::channel::<ProcessSignal>(10);
+        let (state_tx, state_rx) = watch::channel(false);
+        let state_tx = Arc::new(state_tx);
+
+        // Recording indicators follow state changes
+        let indicators = indicator::build_indicators(&self.config.indicator);
+        tokio::spawn(indicator::run_indicators(indicators, state_rx));
 
         // Shared state for recording
         let recording_state = Arc::new(Mutex::new(RecordingState::default()));
         let audio_buffer = Arc::new(Mutex::new(VecDeque::<f32>::new()));
         let vad = Arc::new(Mutex::new(self.vad));
 
-        // Register hotkey
-        let id = self.hotkey_manager.register_hotkey(&self.config.hotkey.combination)?;
-        info!("Hotkey registered: {}", self.config.hotkey.combination);
-        let hotkey_id = id;
+        self.hotkey_manager.set_event_callback(emit_status.clone());
+
+        // Register hotkeys; presses are routed by action, ids only mean something for this run
+        let toggle_hotkey = self.config.hotkeys.toggle_recording().to_string();
+        let mut router = HotkeyRouter::default();
+        let hotkeys_enabled = self.hotkeys_enabled;
+        if hotkeys_enabled {
+            for (action, combination) in self.config.hotkeys.bindings() {
+                let id = self.hotkey_manager.register_hotkey(combination)?;
+                router.bind(id, action, combination)?;
+            }
+            info!("Hotkey registered: {}", toggle_hotkey);
+        } else {
+            info!("⌨️  Global hotkeys disabled (--no-hotkeys)");
+        }
+        // Shared so a config reload can rebind while the listener runs
+        let hotkey_manager = Arc::new(Mutex::new(self.hotkey_manager));
+        let router = Arc::new(Mutex::new(router));
+
+        // Compose mode drafts
+        let compose = Arc::new(Mutex::new(ComposeSession::default()));
+        let (draft_tx, mut draft_rx) = mpsc::channel::<FinishedText>(10);
 
-        // Start audio capture
-        self.audio_capture.start_capture(audio_tx).await?;
+        // Optional re-decode of the last recording
+        let (retranscribe_tx, retranscribe_rx) = mpsc::channel::<RedecodeOverrides>(10);
+
+        // Optional correction takes, flagged when their recording starts
+        let correction_armed = Arc::new(AtomicBool::new(false));
+
+        // Recent dictations, restored from the last run
+        let session_history = Arc::new(Mutex::new(match replaying {
+            true => SessionHistory::new(self.config.history.session_entries, self.config.history.redact_session_state),
+            false => SessionHistory::restore(self.config.history.session_entries, self.config.history.redact_session_state),
+        }));
+
+        let retainer = Arc::new(Mutex::new(RecordingRetainer::new(
+            self.config.retranscribe.max_secs as usize * 16000,
+            std::time::Duration::from_secs(self.config.retranscribe.ttl_secs),
+        )));
+        // How re-decodes load a model of their own; without overrides or retranscribe.model_dir they use the main transcriber
+        let main_model = RedecodeModel {
+            model_dir: self.config.speech.model_dir.clone(),
+            language: self.config.speech.language.clone(),
+            beam_size: 0,
+        };
+        let configured_redecode = self.config.retranscribe.model_dir.clone().map(|model_dir| RedecodeModel { model_dir, ..main_model.clone() });
+        let redecode_transcriber = Arc::new(Mutex::new(None));
+        let min_memory_headroom_mb = self.config.speech.min_memory_headroom_mb;
+        let decode_policy = self.config.speech.decode_policy();
+        let hallucination_blocklist = self.config.speech.hallucination_blocklist.clone();
+        // Partials decode on their own copy of the model, loaded on first use, so a
+        // remote backend without the local fallback goes without
+        let uses_local_model = backend::uses_local_model(&self.config.speech, &self.config.remote_transcription);
+        if self.config.speech.partials && !uses_local_model {
+            info!("speech.partials needs the local model, which remote transcription without fallback_to_local doesn't load");
+        }
+        let partial_interval = (self.config.speech.partials && uses_local_model)
+            .then_some(self.config.speech.partial_interval_ms as usize * 16);
+        let partial_transcriber = Arc::new(tokio::sync::OnceCell::<Arc<SpeechTranscriber>>::new());
+        let partial_model_dir = self.config.speech.model_dir.clone();
+        let partial_language = self.config.speech.language.clone();
+
+        // Start audio capture; stream errors (device unplugged) come back for reconnection
+        let (audio_error_tx, mut audio_error_rx) = mpsc::unbounded_channel::<String>();
+        self.audio_capture.set_error_sender(audio_error_tx);
+        let (replay_audio, replay_results, replay_done) = match replay {
+            Some(ReplayFeed { audio, results, done }) => (Some(audio), Some(results), Some(done)),
+            None => (None, None, None),
+        };
+        if replay_audio.is_none() {
+            self.audio_capture.start_capture(audio_tx.clone()).await?;
+        }
+        if let Some(negotiation) = self.audio_capture.buffer_negotiation() {
+            emit_data("audio_buffer", serde_json::json!(negotiation));
+        }
+        emit_data("audio_device", serde_json::json!({ "device": self.audio_capture.device_name() }));
 
         // Clone references for async tasks
-        let transcriber_clone = Arc::new(self.transcriber);
+        let model = self.transcriber;
+        let transcriber_clone = model.clone();
+        let decode_queue = DecodeQueue::spawn(self.config.speech.decode_queue_depth, self.config.speech.decode_queue_overflow);
         let recording_state_clone = recording_state.clone();
         let audio_buffer_clone = audio_buffer.clone();
         let transcription_tx_clone = transcription_tx.clone();
         let emit_status_audio = emit_status.clone();
+        let emit_data_audio = emit_data.clone();
+        let emit_text_audio = emit_text.clone();
         let vad_clone = vad.clone();
         let process_tx_clone = process_tx.clone();
+        let state_tx_audio = state_tx.clone();
+        let retainer_audio = retainer.clone();
+        let pipeline_audio = pipeline.clone();
+        let recording_limit = RecordingLimit::new(
+            self.config.audio.max_recording_secs as usize * 16000,
+            self.config.audio.overflow_policy,
+        );
+        // A rolling recording is bounded already, and spilled audio can't be dropped.
+        // Continuous sessions are cut into segments at every pause instead.
+        let spill_after = match recording_limit {
+            Some(limit) if limit.policy() == OverflowPolicy::Rolling => 0,
+            _ if continuous => 0,
+            _ => self.config.audio.spill_after_secs as usize * 16000,
+        };
+        // The VAD only ends an utterance after timeout_ms of silence, which doesn't count as speech
+        let segments = Segments::new(self.config.vad.min_segment_ms as usize * 16, self.config.vad.timeout_ms as usize * 16);
+        let max_recording_secs = self.config.audio.max_recording_secs;
+        let preroll_samples = self.config.audio.preroll_ms as usize * 16;
+        let gain_stage = GainStage::from_config(&self.config.audio);
+        let noise_suppression = self.config.audio.noise_suppression;
+        let output_rate = self.audio_capture.output_rate();
+        let save_audio_dir = self.config.debug.save_audio_dir.clone();
+        let max_saved_files = self.config.debug.max_saved_files;
+        let noise_levels = self.config.vad.adaptive
+            .then_some((self.config.vad.sensitivity, self.config.vad.adaptive_min, self.config.vad.adaptive_max));
 
-        // Audio processing task with VAD auto-stop
-        let audio_task = tokio::spawn(async move {
-            loop {
-                tokio::select! {
-                    // Handle audio chunks
-                    Some(audio_chunk) = audio_rx.recv() => {
-                        let mut state = recording_state_clone.lock().await;
+        // Opt-in: reload the speech model when its files change, between recordings
+        if self.config.speech.watch_model && uses_local_model {
+            let mut model_changed_rx = speech::spawn_model_watcher(self.config.speech.model_dir.clone());
+            let loaded = transcriber_clone.clone();
+            let recording_state = recording_state.clone();
+            let emit_status = emit_status.clone();
+            let emit_data = emit_data.clone();
+            tokio::spawn(async move {
+                let Ok(transcriber) = loaded.await else { return };
+                while model_changed_rx.recv().await.is_some() {
+                    // A decode in flight holds the model lock, so only recordings need waiting out
+                    while recording_state.lock().await.is_recording {
+                        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+                    }
+
+                    // The current model keeps serving until the new one is in place, and
+                    // stays in use if loading fails
+                    emit_status("model_loading", "Loading the new speech model");
+                    match transcriber.reload().await {
+                        Ok(model_info) => {
+                            emit_status("model_unloaded", "Previous speech model unloaded");
+                            emit_data("model_info", serde_json::json!(model_info));
+                            info!("✅ Speech model reloaded");
+                            emit_status("ready", "Speech model reloaded");
+                        }
+                        Err(e) => {
+                            error!("❌ Failed to reload speech model, keeping the current one: {}", e);
+                            emit_status("model_reload_failed", &e.to_string());
+                        }
+                    }
+                }
+            });
+        }
 
-                        if !state.is_recording {
-                            continue; // Skip processing when not recording
+        // Opt-in: apply edits to config.toml, between recordings
+        // What's running is the baseline reloads are compared against; without it there's nothing to watch
+        let watched = if self.config.reload.watch {
+            Config::read()
+                .map_err(|e| warn!("⚠️  Not watching config.toml, it can't be read: {}", e))
+                .ok()
+        } else {
+            None
+        };
+        if let Some(mut current) = watched {
+            let config_path = std::env::current_dir()?.join("config.toml");
+            let mut config_changed_rx = config_reload::spawn_config_watcher(
+                config_path,
+                std::time::Duration::from_millis(self.config.reload.quiet_ms),
+            );
+            let hotkey_manager = hotkey_manager.clone();
+            let router = router.clone();
+            let vad = vad.clone();
+            let recording_state = recording_state.clone();
+            let emit_data = emit_data.clone();
+            tokio::spawn(async move {
+                let steps = config_reload::runtime_steps(hotkeys_enabled);
+                while config_changed_rx.recv().await.is_some() {
+                    // Parsed and validated in full before anything is touched
+                    let new = match Config::read() {
+                        Ok(config) => config,
+                        Err(e) => {
+                            warn!("⚠️  Ignoring config.toml change: {}", e);
+                            emit_data("config_reload_failed", serde_json::json!({
+                                "message": "Config not reloaded",
+                                "error": e.to_string(),
+                            }));
+                            continue;
                         }
+                    };
+
+                    // Rebuilding the VAD mid-recording would lose its state
+                    while recording_state.lock().await.is_recording {
+                        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+                    }
 
-                        // Add to audio buffer
-                        {
-                            let mut buffer = audio_buffer_clone.lock().await;
-                            buffer.extend(&audio_chunk);
+                    let mut live = config_reload::Live {
+                        hotkeys: hotkey_manager.clone().lock_owned().await,
+                        router: router.clone().lock_owned().await,
+                        vad: vad.clone().lock_owned().await,
+                    };
+                    match config_reload::apply(&mut live, &steps, &current, &new) {
+                        Ok(applied) => {
+                            let restart = config_reload::restart_required(&current, &new, &applied);
+                            info!("🔄 Config reloaded: {} applied, {} need a restart", applied.len(), restart.len());
+                            if !restart.is_empty() {
+                                warn!("⚠️  Restart to apply: {}", restart.join(", "));
+                            }
+                            emit_data("config_reloaded", serde_json::json!({
+                                "message": "Config reloaded",
+                                "applied": applied,
+                                "restart_required": restart,
+                            }));
+                            current = new;
                         }
+                        Err(rolled_back) => {
+                            error!("❌ Config reload failed at {}, previous settings kept: {}", rolled_back.key, rolled_back.error);
+                            emit_data("config_reload_rolled_back", serde_json::json!({
+                                "message": "Config reload rolled back",
+                                "key": rolled_back.key,
+                                "error": rolled_back.error.to_string(),
+                            }));
+                        }
+                    }
+                }
+            });
+        }
+
+        // Audio processing task with VAD auto-stop, started again if it exits or fails. Its
+        // channels and the recording buffer live out here, so a new task picks up the stops
+        // sent while none was running and redoes the one the last was handling
+        let audio_rx = Arc::new(Mutex::new(audio_rx));
+        let retranscribe_rx = Arc::new(Mutex::new(retranscribe_rx));
+        let process_inbox = supervisor::Inbox::new(process_rx);
+        // Shared, so recording ids stay unique across restarts
+        let recording_ids = Arc::new(AtomicU64::new(1));
+        let spawn_audio_task = move || {
+            let audio_rx = audio_rx.clone();
+            let retranscribe_rx = retranscribe_rx.clone();
+            let process_inbox = process_inbox.clone();
+            let recording_ids = recording_ids.clone();
+            let audio_buffer_clone = audio_buffer_clone.clone();
+            let recording_state_clone = recording_state_clone.clone();
+            let vad_clone = vad_clone.clone();
+            let process_tx_clone = process_tx_clone.clone();
+            let state_tx_audio = state_tx_audio.clone();
+            let transcriber_clone = transcriber_clone.clone();
+            let transcription_tx_clone = transcription_tx_clone.clone();
+            let decode_queue = decode_queue.clone();
+            let retainer_audio = retainer_audio.clone();
+            let pipeline_audio = pipeline_audio.clone();
+            let emit_status_audio = emit_status_audio.clone();
+            let emit_data_audio = emit_data_audio.clone();
+            let emit_text_audio = emit_text_audio.clone();
+            let partial_transcriber = partial_transcriber.clone();
+            let partial_model_dir = partial_model_dir.clone();
+            let partial_language = partial_language.clone();
+            let redecode_transcriber = redecode_transcriber.clone();
+            let main_model = main_model.clone();
+            let configured_redecode = configured_redecode.clone();
+            let decode_policy = decode_policy.clone();
+            let hallucination_blocklist = hallucination_blocklist.clone();
+            let gain_stage = gain_stage.clone();
+            let output_rate = output_rate.clone();
+            let save_audio_dir = save_audio_dir.clone();
+            let segments = segments.clone();
+            tokio::spawn(async move {
+                let mut audio_rx = audio_rx.lock_owned().await;
+                let mut retranscribe_rx = retranscribe_rx.lock_owned().await;
+                let mut process_rx = process_inbox.open().await;
+                // The rest starts over; a recording the last task was spilling stays on disk for `tomchat recover`
+                // Numbering of the current continuous session
+                let mut segments = segments;
+                let mut partials = partial_interval.map(PartialSchedule::new);
+                let mut noise_adapter = noise_levels.map(|(level, min, max)| NoiseAdapter::new(level, min, max));
+                let mut retain_expiry = tokio::time::interval(std::time::Duration::from_secs(10));
+                let mut stall_check = tokio::time::interval(std::time::Duration::from_secs(1));
+                let mut last_audio = std::time::Instant::now();
+                let mut spill: Option<Spill> = None;
+                let mut spill_failed = false;
+                let mut gain_tracker = GainTracker::default();
+                let mut limit_reported = false;
+                // The rolling limit dropped the start of the recording since it began
+                let mut rolled_over = false;
+                let mut speech_edges = SpeechEdges::default();
+                let mut preroll = PreRoll::new(preroll_samples);
+                // The bubble's mic meter; nothing else reads it
+                let mut level_meter = gui_mode.then(|| LevelMeter::new(LEVEL_INTERVAL));
+
+                loop {
+                    // The last stop has been handed on, so its audio can go
+                    if process_rx.finish() {
+                        audio_buffer_clone.lock().await.clear();
+                    }
+
+                    tokio::select! {
+                        // Handle audio chunks
+                        Some(audio_chunk) = audio_rx.recv() => {
+                            last_audio = std::time::Instant::now();
+                            let mut state = recording_state_clone.lock().await;
 
-                        // Process VAD for auto-stop
-                        if vad_auto_stop {
-                            let mut vad = vad_clone.lock().await;
-                            let vad_result = vad.process_audio(&audio_chunk);
+                            if !state.is_recording {
+                                preroll.push(&audio_chunk);
+                                speech_edges.reset();
 
-                            match vad_result {
-                                VadResult::SpeechDetected => {
-                                    if !state.speech_detected {
-                                        debug!("Speech started");
-                                        state.speech_detected = true;
+                                // A rebuild below can take a while; don't keep a hotkey press waiting on it
+                                drop(state);
+
+                                // Measure ambient noise only between recordings, so speech doesn't raise the floor
+                                if let Some(ref mut adapter) = noise_adapter {
+                                    if let Some(level) = adapter.observe(&audio_chunk, std::time::Instant::now()) {
+                                        // Build the new model without holding the VAD, then swap it in
+                                        let spec = vad_clone.lock().await.rebuild_spec();
+                                        let threshold = level.to_threshold();
+                                        let prepared = tokio::task::spawn_blocking(move || spec.prepare(threshold))
+                                            .await
+                                            .map_err(anyhow::Error::from)
+                                            .and_then(|prepared| prepared);
+                                        match prepared {
+                                            Err(e) => warn!("Failed to adapt VAD sensitivity: {}", e),
+                                            Ok(prepared) => {
+                                                vad_clone.lock().await.apply_threshold(prepared);
+                                                emit_data_audio("vad_sensitivity_changed", serde_json::json!({
+                                                    "sensitivity": level,
+                                                    "threshold": threshold,
+                                                    "noise_floor": adapter.floor(),
+                                                }));
+                                            }
+                                        }
+                                    }
+                                }
+                                continue; // Skip processing when not recording
+                            }
+
+                            // Add to audio buffer
+                            let limit_action;
+                            {
+                                let mut buffer = audio_buffer_clone.lock().await;
+                                if buffer.is_empty() && spill.is_none() {
+                                    gain_tracker.reset();
+                                    limit_reported = false;
+                                    rolled_over = false;
+                                    if let Some(ref mut meter) = level_meter {
+                                        meter.start(std::time::Instant::now());
                                     }
+                                    // Speech from just before the press
+                                    let before = preroll.take();
+                                    gain_tracker.observe(&before);
+                                    buffer.extend(before);
                                 }
-                                VadResult::SilenceDetected => {
-                                    // Auto-stop: silence timeout reached after speech
-                                    if state.speech_detected {
-                                        info!("Auto-stopping: silence detected after speech");
-                                        state.is_recording = false;
-                                        state.speech_detected = false;
-
-                                        // Notify state change
-                                        TomChatApp::notify_state_change(false);
-
-                                        // Trigger transcription
-                                        let _ = process_tx_clone.send(()).await;
+                                buffer.extend(audio_chunk.iter());
+                                gain_tracker.observe(&audio_chunk);
+
+                                // Long recording: move what's buffered to disk (written by the spill's own thread)
+                                if spill_after > 0 && !spill_failed && buffer.len() > spill_after {
+                                    if spill.is_none() {
+                                        match Spill::create(recording_ids.load(Ordering::SeqCst)) {
+                                            Ok(created) => spill = Some(created),
+                                            Err(e) => {
+                                                error!("Failed to start spilling to disk, keeping audio in memory: {}", e);
+                                                spill_failed = true;
+                                            }
+                                        }
+                                    }
+                                    if let Some(ref mut active) = spill {
+                                        if let Err(e) = active.append(buffer.drain(..)) {
+                                            error!("Failed to spill audio to disk, keeping the rest in memory: {}", e);
+                                            spill_failed = true;
+                                        }
                                     }
                                 }
-                                VadResult::Silence => {
-                                    // Still waiting for speech or in between words
+
+                                let recorded = buffer.len() + spill.as_ref().map_or(0, |active| active.samples());
+                                limit_action = recording_limit.map_or(LimitAction::Within, |limit| limit.check(recorded));
+                                if let LimitAction::Drop(excess) = limit_action {
+                                    let excess = excess.min(buffer.len());
+                                    buffer.drain(..excess);
+                                    rolled_over = true;
                                 }
                             }
-                        }
-                    }
 
-                    // Handle process signal (when recording stops)
-                    Some(_) = process_rx.recv() => {
-                        info!("Processing audio...");
+                            if limit_action != LimitAction::Within && !limit_reported {
+                                limit_reported = true;
+                                let policy = recording_limit.map(|limit| limit.policy()).unwrap_or_default();
+                                warn!("⏱️ Recording reached audio.max_recording_secs ({}s), policy {}", max_recording_secs, policy.as_str());
+                                emit_data_audio("recording_limit_reached", serde_json::json!({
+                                    "max_secs": max_recording_secs,
+                                    "policy": policy,
+                                }));
+                            }
+                            if limit_action == LimitAction::Stop && state.request_stop(StopReason::MaxDuration, std::time::Instant::now()) {
+                                emit_data_audio("recording_stopped", serde_json::json!({
+                                    "message": "Recording stopped",
+                                    "reason": StopReason::MaxDuration,
+                                }));
+                                TomChatApp::notify_state_change(&state_tx_audio, &emit_data_audio, false);
+                                let _ = process_tx_clone.send((StopReason::MaxDuration, state.cancel_token(), state.trigger)).await;
+                                continue;
+                            }
 
-                        // Reset VAD for next session
-                        {
-                            let mut vad = vad_clone.lock().await;
-                            vad.reset();
-                        }
+                            // Only while recording, so the meter stops with the recording
+                            if let Some(ref mut meter) = level_meter {
+                                if let Some(level) = meter.observe(&audio_chunk, std::time::Instant::now()) {
+                                    emit_data_audio("audio_level", serde_json::json!(level));
+                                }
+                            }
 
-                        // Get accumulated audio
-                        let audio_data = {
-                            let mut buffer = audio_buffer_clone.lock().await;
-                            let data: Vec<f32> = buffer.iter().cloned().collect();
-                            buffer.clear();
-                            data
-                        };
+                            // Interim text for the GUI; spilled recordings are too long to snapshot
+                            if let Some(ref mut schedule) = partials {
+                                let due = match spill {
+                                    Some(_) => None,
+                                    None => schedule.poll(audio_chunk.len()),
+                                };
+                                if let Some(ticket) = due {
+                                    let snapshot: Vec<f32> = audio_buffer_clone.lock().await.iter().cloned().collect();
+                                    let loaded = partial_transcriber.clone();
+                                    let (model_dir, language, decode_policy, blocklist) =
+                                        (partial_model_dir.clone(), partial_language.clone(), decode_policy.clone(), hallucination_blocklist.clone());
+                                    let emit_text = emit_text_audio.clone();
+                                    let recording_id = recording_ids.load(Ordering::SeqCst);
+                                    tokio::spawn(async move {
+                                        // Loading reads and parses the model files, so keep it off the runtime threads
+                                        let transcriber = match loaded.get_or_try_init(|| async move {
+                                            tokio::task::spawn_blocking(move || {
+                                                SpeechTranscriber::new(&model_dir, Some(&language), min_memory_headroom_mb, decode_policy).map(|mut transcriber| {
+                                                    transcriber.set_hallucination_blocklist(&blocklist);
+                                                    Arc::new(transcriber)
+                                                })
+                                            })
+                                            .await?
+                                        }).await {
+                                            Ok(transcriber) => transcriber.clone(),
+                                            Err(e) => {
+                                                warn!("Failed to load the partial transcription model: {}", e);
+                                                return;
+                                            }
+                                        };
+                                        match transcriber.transcribe_audio(&snapshot).await {
+                                            // Stopped meanwhile: the final transcript supersedes it
+                                            Ok(text) if ticket.is_current() && !text.is_empty() => {
+                                                emit_text("partial_transcription", "Partial", &text, serde_json::json!({
+                                                    "recording_id": recording_id,
+                                                    "audio_ms": ticket.audio_ms(),
+                                                }));
+                                            }
+                                            Ok(_) => {}
+                                            Err(e) => debug!("Partial transcription failed: {}", e),
+                                        }
+                                    });
+                                }
+                            }
+
+                            // Process VAD for auto-stop, the end of an utterance in continuous mode, or the speech ratio
+                            if vad_auto_stop || continuous || min_speech_ratio > 0.0 {
+                                let mut vad = vad_clone.lock().await;
+                                let vad_result = vad.process_audio(&audio_chunk);
+                                if let Some((edge, offset_ms)) = speech_edges.observe(vad.is_speaking(), audio_chunk.len()) {
+                                    emit_data_audio(edge.event(), serde_json::json!({
+                                        "message": if edge == SpeechEdge::Started { "Speech started" } else { "Speech ended" },
+                                        "offset_ms": offset_ms,
+                                    }));
+                                }
 
-                        // Send for transcription
-                        if !audio_data.is_empty() {
-                            info!("Transcribing {} audio samples ({:.1}s)",
-                                  audio_data.len(),
-                                  audio_data.len() as f32 / 16000.0);
-                            emit_status_audio("transcribing", "Transcribing audio");
-
-                            let transcriber = transcriber_clone.clone();
-                            let tx = transcription_tx_clone.clone();
-                            let emit_clone = emit_status_audio.clone();
-
-                            tokio::spawn(async move {
-                                match transcriber.transcribe_audio(&audio_data).await {
-                                    Ok(text) if !text.is_empty() => {
-                                        emit_clone("transcription_complete", &format!("Transcription: {}", text));
-                                        if let Err(_) = tx.send(text).await {
-                                            error!("Failed to send transcription");
+                                match vad_result {
+                                    VadResult::SpeechDetected => {
+                                        if !state.speech_detected {
+                                            debug!("Speech started");
+                                            state.speech_detected = true;
                                         }
                                     }
-                                    Ok(_) => {
-                                        emit_clone("transcription_complete", "Empty transcription result");
-                                        debug!("Empty transcription result");
+                                    VadResult::SilenceDetected if continuous => {
+                                        // The session stays open; the process branch takes the utterance off the buffer
+                                        if state.speech_detected {
+                                            state.speech_detected = false;
+                                            debug!("Utterance ended, sending a segment");
+                                            let _ = process_tx_clone.send((StopReason::Segment, state.cancel_token(), state.trigger)).await;
+                                        }
+                                    }
+                                    VadResult::SilenceDetected if vad_auto_stop => {
+                                        // Auto-stop: silence timeout reached after speech, unless a hotkey stop won the race
+                                        if state.speech_detected && state.request_stop(StopReason::VadTimeout, std::time::Instant::now()) {
+                                            info!("Auto-stopping: silence detected after speech");
+                                            emit_data_audio("recording_stopped", serde_json::json!({
+                                                "message": "Recording stopped",
+                                                "reason": StopReason::VadTimeout,
+                                            }));
+
+                                            // Notify state change
+                                            TomChatApp::notify_state_change(&state_tx_audio, &emit_data_audio, false);
+
+                                            // Trigger transcription
+                                            let _ = process_tx_clone.send((StopReason::VadTimeout, state.cancel_token(), state.trigger)).await;
+                                        }
                                     }
-                                    Err(e) => {
-                                        emit_clone("transcription_error", &format!("Transcription failed: {}", e));
-                                        error!("Transcription failed: {}", e);
+                                    VadResult::Silence | VadResult::SilenceDetected => {
+                                        // Still waiting for speech or in between words
                                     }
                                 }
-                            });
-                        } else {
-                            info!("No audio data to transcribe");
+                            }
                         }
-                    }
-                }
-            }
-        });
 
-        // Transcription handling task
-        let mut text_injector = self.text_injector;
-        let text_refiner_clone = self.text_refiner;
-        let transcription_task = tokio::spawn(async move {
-            while let Some(raw_text) = transcription_rx.recv().await {
-                info!("Transcribed: \"{}\"", raw_text);
-
-                // Apply text refinement if enabled
-                let final_text = if let Some(ref refiner) = text_refiner_clone {
-                    match refiner.refine_text(&raw_text).await {
-                        Ok(refined_text) => {
-                            if refined_text != raw_text {
-                                info!("Refined: \"{}\" -> \"{}\"", raw_text, refined_text);
+                        // Handle process signal (when recording stops)
+                        Some((stop_reason, job_cancel, trigger)) = process_rx.recv() => {
+                            info!("Processing audio (stopped by {})...", stop_reason.as_str());
+                            if let Some(ref mut schedule) = partials {
+                                schedule.stop();
+                            }
+
+                            // Reset VAD for next session
+                            let vad_stats = {
+                                let mut vad = vad_clone.lock().await;
+                                let stats = vad.take_stats();
+                                vad.reset();
+                                stats
+                            };
+
+                                // Get accumulated audio; it stays buffered until this stop is done with
+                            let audio_data: Vec<f32> = audio_buffer_clone.lock().await.iter().cloned().collect();
+
+                            // Cancelled while recording or before decoding started: drop the audio, spilled part included
+                            if job_cancel.is_cancelled() {
+                                gain_tracker.reset();
+                                segments.skip(stop_reason);
+                                spill_failed = false;
+                                if let Some(active) = spill.take() {
+                                    active.discard();
+                                }
+                                let stage = if stop_reason == StopReason::Cancelled { JobStage::Recording } else { JobStage::Decoding };
+                                cancel::report(&*emit_data_audio, None, stage);
+                                continue;
+                            }
+
+                            // An accidental press that caught only silence: decoders hallucinate on dead air
+                            if vad_stats.is_dead_air(min_speech_ratio) {
+                                info!("🔇 Skipping a recording with {:.0}% speech, below vad.min_speech_ratio", vad_stats.speech_ratio() * 100.0);
+                                gain_tracker.reset();
+                                segments.skip(stop_reason);
+                                spill_failed = false;
+                                if let Some(active) = spill.take() {
+                                    active.discard();
+                                }
+                                emit_data_audio("no_speech_detected", serde_json::json!({
+                                    "message": "No speech detected, nothing transcribed",
+                                    "speech_ratio": vad_stats.speech_ratio(),
+                                    "stats": vad_stats,
+                                }));
+                                continue;
+                            }
+
+                            // Continuous sessions number their segments, the piece left at the final stop included.
+                            // The decode queue runs them one at a time in submission order, so segments reach output in order.
+                            let segment = if continuous {
+                                let Some(index) = segments.cut(audio_data.len(), stop_reason) else {
+                                    info!("Dropping a {:.1}s segment, shorter than vad.min_segment_ms", audio_data.len() as f32 / 16000.0);
+                                    gain_tracker.reset();
+                                    continue;
+                                };
+                                Some(index)
+                            } else {
+                                None
+                            };
+
+                            // Gain is decided over the whole recording, spilled part included. The rolling
+                            // limit never spills, so what it dropped is measured out by starting over
+                            if rolled_over {
+                                gain_tracker.reset();
+                                gain_tracker.observe(&audio_data);
+                                rolled_over = false;
+                            }
+                            let gain = gain_stage.as_ref().map(|stage| stage.decide(&gain_tracker));
+                            gain_tracker.reset();
+                            let transcribing_message = match gain {
+                                Some(ref gain) if spill.is_some() || !audio_data.is_empty() => {
+                                    TomChatApp::report_gain(&emit_data_audio, recording_ids.load(Ordering::SeqCst), gain);
+                                    format!("Transcribing audio (gain {:+.1} dB)", gain.gain_db)
+                                }
+                                _ => "Transcribing audio".to_string(),
+                            };
+                            let conditioning = Conditioning { gain, noise_suppression };
+
+                            spill_failed = false;
+                            if let Some(mut active) = spill.take() {
+                                let recording_id = recording_ids.fetch_add(1, Ordering::SeqCst);
+                                info!("Transcribing long recording ({:.1}s on disk + {:.1}s in memory)",
+                                      active.samples() as f32 / 16000.0,
+                                      audio_data.len() as f32 / 16000.0);
+                                emit_status_audio("transcribing", &transcribing_message);
+
+                                // The tail joins the file, so it holds the whole recording as captured
+                                if let Err(e) = active.append(audio_data) {
+                                    error!("Failed to spill the end of the recording: {}", e);
+                                }
+                                let audio = RecordedAudio::Spilled {
+                                    spill: active,
+                                    conditioning,
+                                    save_to: save_audio_dir.clone().map(|dir| (dir, max_saved_files)),
+                                };
+
+                                // Too long to retain for re-decode
+                                TomChatApp::queue_transcription(
+                                    &decode_queue,
+                                    transcriber_clone.clone(),
+                                    audio,
+                                    output_rate.get(),
+                                    recording_id,
+                                    None,
+                                    Some(stop_reason),
+                                    segment,
+                                    trigger,
+                                    job_cancel,
+                                    pipeline_audio.track(recording_id),
+                                    None,
+                                    transcription_tx_clone.clone(),
+                                    emit_text_audio.clone(),
+                                    emit_data_audio.clone(),
+                                ).await;
+                                continue;
+                            }
+
+                            // Send for transcription
+                            if !audio_data.is_empty() {
+                                info!("Transcribing {} audio samples ({:.1}s)",
+                                      audio_data.len(),
+                                      audio_data.len() as f32 / 16000.0);
+                                emit_status_audio("transcribing", &transcribing_message);
+
+                                // What was captured, before any conditioning; the decode picks up the path
+                                let saved_audio = save_audio_dir.clone().map(|dir| {
+                                    let captured = audio_data.clone();
+                                    tokio::task::spawn_blocking(move || {
+                                        save_recording(&dir, max_saved_files, |dir| wav::save(dir, &captured))
+                                    })
+                                });
+                                let audio_data = conditioning.run(audio_data).await;
+
+                                let recording_id = recording_ids.fetch_add(1, Ordering::SeqCst);
+                                {
+                                    // Checked under the lock, as a cancel clears the retainer after cancelling
+                                    let mut retainer = retainer_audio.lock().await;
+                                    if !job_cancel.is_cancelled() {
+                                        retainer.retain(recording_id, &audio_data);
+                                    }
+                                }
+
+                                TomChatApp::queue_transcription(
+                                    &decode_queue,
+                                    transcriber_clone.clone(),
+                                    RecordedAudio::Memory(audio_data),
+                                    output_rate.get(),
+                                    recording_id,
+                                    None,
+                                    Some(stop_reason),
+                                    segment,
+                                    trigger,
+                                    job_cancel,
+                                    pipeline_audio.track(recording_id),
+                                    saved_audio,
+                                    transcription_tx_clone.clone(),
+                                    emit_text_audio.clone(),
+                                    emit_data_audio.clone(),
+                                ).await;
+                            } else {
+                                info!("No audio data to transcribe");
+                            }
+                        }
+
+                        // Re-decode the retained recording
+                        Some(overrides) = retranscribe_rx.recv() => {
+                            let retained = retainer_audio
+                                .lock()
+                                .await
+                                .get()
+                                .map(|r| (r.recording_id, r.audio.clone()));
+
+                            let Some((original_id, audio_data)) = retained else {
+                                info!("No recent recording to re-decode");
+                                emit_status_audio("retranscribe_unavailable", "No recent recording to re-decode");
+                                continue;
+                            };
+
+                            // A model of its own if asked for or configured, loaded on first use
+                            let transcriber = match overrides.model(configured_redecode.as_ref(), &main_model) {
+                                Ok(None) => transcriber_clone.clone(),
+                                Ok(Some(model)) => {
+                                    let loaded = TomChatApp::load_redecode_model(
+                                        &redecode_transcriber,
+                                        model,
+                                        min_memory_headroom_mb,
+                                        &decode_policy,
+                                        &hallucination_blocklist,
+                                    ).await;
+                                    match loaded {
+                                        Ok(transcriber) => {
+                                            let transcriber: Arc<dyn TranscriberBackend> = transcriber;
+                                            futures_util::future::ready(Ok(transcriber)).boxed().shared()
+                                        }
+                                        Err(e) => {
+                                            error!("Failed to load re-decode model: {}", e);
+                                            emit_status_audio("retranscribe_unavailable", &format!("Couldn't load the re-decode model: {}", e));
+                                            continue;
+                                        }
+                                    }
+                                }
+                                Err(e) => {
+                                    warn!("⚠️  Can't re-decode: {}", e);
+                                    emit_status_audio("retranscribe_unavailable", &e.to_string());
+                                    continue;
+                                }
+                            };
+
+                            let recording_id = recording_ids.fetch_add(1, Ordering::SeqCst);
+                            info!("Re-decoding recording {} as {}", original_id, recording_id);
+                            emit_status_audio("transcribing", "Re-decoding last recording");
+
+                            TomChatApp::queue_transcription(
+                                &decode_queue,
+                                transcriber,
+                                RecordedAudio::Memory(audio_data),
+                                output_rate.get(),
+                                recording_id,
+                                Some(original_id),
+                                None,
+                                None,
+                                None,
+                                recording_state_clone.lock().await.begin_job(),
+                                pipeline_audio.track(recording_id),
+                                None,
+                                transcription_tx_clone.clone(),
+                                emit_text_audio.clone(),
+                                emit_data_audio.clone(),
+                            ).await;
+                        }
+
+                        // Forget the retained recording once its TTL passes
+                        _ = retain_expiry.tick() => {
+                            retainer_audio.lock().await.expire();
+                        }
+
+                        // The input went dead mid-recording (a stalled driver, not silence): keep what came in
+                        _ = stall_check.tick() => {
+                            if last_audio.elapsed() < RECORDING_STALL {
+                                continue;
+                            }
+                            let mut state = recording_state_clone.lock().await;
+                            if state.request_stop(StopReason::Watchdog, std::time::Instant::now()) {
+                                warn!("🎙️ No audio for {}s while recording, stopping", RECORDING_STALL.as_secs());
+                                emit_data_audio("recording_stopped", serde_json::json!({
+                                    "message": "Recording stopped",
+                                    "reason": StopReason::Watchdog,
+                                }));
+                                TomChatApp::notify_state_change(&state_tx_audio, &emit_data_audio, false);
+                                let _ = process_tx_clone.send((StopReason::Watchdog, state.cancel_token(), state.trigger)).await;
                             }
-                            refined_text
-                        }
-                        Err(e) => {
-                            warn!("Text refinement failed: {}, using original", e);
-                            raw_text
                         }
                     }
-                } else {
-                    raw_text
-                };
+                }
+            })
+        };
+        let emit_data_restart = emit_data.clone();
+        let mut audio_task = tokio::spawn(supervisor::supervise("audio", MAX_AUDIO_RESTARTS, spawn_audio_task, move |reason, restarts| {
+            emit_data_restart("pipeline_restarted", serde_json::json!({
+                "task": "audio",
+                "message": reason,
+                "restarts": restarts,
+            }));
+        }));
+
+        // Transcription handling task
+        let mut text_injector = self.text_injector;
+        text_injector.set_event_callback(emit_text.clone());
+        let mut text_refiner_clone = self.text_refiner;
+        let refine_by_default = self.config.text_refinement.as_ref().is_some_and(|config| config.enabled);
+        let fall_back_to_unrefined = self.config.text_refinement.as_ref().is_none_or(|config| config.fallback_on_timeout);
+        if let Some(ref mut refiner) = text_refiner_clone {
+            refiner.set_event_callback(emit_status.clone());
+        }
+        let punctuator = self.punctuator;
+        let spelling = self.config.text.spelling;
+        let mut vocabulary = Vocabulary::new(self.config.text.vocabulary.clone(), self.config.text.vocabulary_file.clone());
+        let number_normalizer = self.config.text.normalize_numbers.then(|| {
+            let locale = self.config.text.number_locale.as_deref().unwrap_or(&self.config.speech.language);
+            NumberNormalizer::new(locale)
+        });
+        let replace_previous = self.config.retranscribe.replace_previous;
+        let compose_transcription = compose.clone();
+        let output_template = self.config.output.template.clone();
+        let correction_transcription = correction_armed.clone();
+        let session_transcription = session_history.clone();
+        let language = self.config.speech.language.clone();
+        let form_fill = match self.config.form_fill.for_profile(self.config.preset) {
+            Some((separator, key)) => {
+                let key = input::parse_key_name(key).ok_or_else(|| anyhow::anyhow!("Unknown form_fill.key '{}'", key))?;
+                Some((separator.to_string(), key))
+            }
+            None => None,
+        };
+        let mut delivery = Delivery {
+            sinks: self.sinks,
+            injection_backend: text_injector.backend_name(),
+            text_injector,
+            form_fill,
+            similarity_threshold: self.config.correction.similarity_threshold,
+            last_injection: None,
+        };
+        let profile = self.config.preset.map(|preset| preset.name().to_string());
+        let rich_transcription = self.config.gui.rich_transcription;
+        let (preview_graphemes, full_text) = (self.config.gui.preview_graphemes, self.config.gui.full_text);
+        let emit_text_transcription = emit_text.clone();
+        let emit_data_transcription = emit_data.clone();
+        let mut transcription_task = tokio::spawn(async move {
+            let mut dedup = DedupGuard::new(DEDUP_WINDOW);
+
+            loop {
+                tokio::select! {
+                    Some(transcription) = transcription_rx.recv() => {
+                        transcription.job.enter(JobStage::Refining);
+                        let refine_started = std::time::Instant::now();
+                        let raw_text = transcription.text;
+                        let job_cancel = transcription.cancel;
+                        info!("Transcribed: \"{}\"", raw_text);
+
+                        // Apply text refinement if enabled, or if the recording's hotkey asks for it
+                        let refiner = refiner_for(transcription.trigger, text_refiner_clone.as_ref(), refine_by_default);
+
+                        // Rewrites work on a copy, the raw variant stays what the model decoded.
+                        // Spelled strings first, so nothing after can rewrite them
+                        let text = if spelling { spelling::apply(&raw_text) } else { raw_text.clone() };
+                        let text = vocabulary.apply(&text);
+                        // Refinement restores punctuation itself, so one model call (and one timeout) per take
+                        let text = match punctuator.as_ref().filter(|_| refiner.is_none()) {
+                            Some(punctuator) => match cancel::unless_cancelled(&job_cancel, punctuator.punctuate(&text)).await {
+                                Some(punctuated) => punctuated,
+                                None => {
+                                    cancel::report(&*emit_data_transcription, Some(transcription.recording_id), JobStage::Refining);
+                                    continue;
+                                }
+                            },
+                            None => text,
+                        };
+                        let text = match number_normalizer {
+                            Some(ref normalizer) => normalizer.normalize(&text),
+                            None => text,
+                        };
+
+                        let refined_text = if let Some(refiner) = refiner {
+                            match cancel::unless_cancelled(&job_cancel, refiner.refine_text(&text)).await {
+                                // Reported just below
+                                None => None,
+                                Some(Ok(refined_text)) => {
+                                    if refined_text != text {
+                                        info!("Refined: \"{}\" -> \"{}\"", text, refined_text);
+                                    }
+                                    Some(refined_text)
+                                }
+                                Some(Err(e)) => {
+                                    TomChatApp::report_error(&*emit_data_transcription, &e);
+                                    match e.policy().recovery {
+                                        Recovery::UseUnrefined if fall_back_to_unrefined => None,
+                                        Recovery::UseUnrefined => {
+                                            info!("text_refinement.fallback_on_timeout is off, dropping the transcription");
+                                            continue;
+                                        }
+                                        Recovery::Abort | Recovery::SkipSink | Recovery::KeepAudio => continue,
+                                    }
+                                }
+                            }
+                        } else {
+                            None
+                        };
+                        if job_cancel.is_cancelled() {
+                            cancel::report(&*emit_data_transcription, Some(transcription.recording_id), JobStage::Refining);
+                            continue;
+                        }
+
+                        let mut job = OutputJob::post_processed(raw_text, text.clone(), refined_text);
+
+                        // Safety net against the same recording reaching output twice
+                        if !dedup.admit(transcription.recording_id, &job.processed, std::time::Instant::now()) {
+                            continue;
+                        }
 
-                if let Err(e) = text_injector.inject_with_formatting(&final_text).await {
-                    error!("Failed to inject text: {}", e);
-                } else {
-                    info!("Text injected successfully");
+                        // What the refiner changed; nothing when it didn't run
+                        let refinement_diff = job.refined.as_ref().map(|refined| {
+                            let diff = text_diff::diff(&text, refined);
+                            if let text_diff::TextDiff::Edits { ref ops } = diff {
+                                debug_assert_eq!(text_diff::apply(&text, ops), *refined);
+                            }
+                            diff
+                        });
+                        if let Some(ref diff) = refinement_diff {
+                            emit_data_transcription("refinement_diff", serde_json::json!({
+                                "recording_id": transcription.recording_id,
+                                "diff": diff,
+                            }));
+                        }
+
+                        if rich_transcription {
+                            emit_data_transcription("transcription_rich", serde_json::json!({
+                                "recording_id": transcription.recording_id,
+                                "text": job.processed,
+                                "sentences": segments::sentences(&job.processed),
+                                "diff": refinement_diff,
+                            }));
+                        }
+
+                        // Last chance before anything is kept or output
+                        if job_cancel.is_cancelled() {
+                            cancel::report(&*emit_data_transcription, Some(transcription.recording_id), JobStage::Output);
+                            continue;
+                        }
+                        transcription.job.enter(JobStage::Output);
+
+                        // Keep the last dictation around for bug report export
+                        let entry = HistoryEntry {
+                            recording_id: transcription.recording_id,
+                            timestamp: std::time::SystemTime::now()
+                                .duration_since(std::time::UNIX_EPOCH)
+                                .unwrap()
+                                .as_secs(),
+                            raw: job.raw.clone(),
+                            refined: job.refined.clone(),
+                            processed: job.processed.clone(),
+                            stop_reason: transcription.stop_reason,
+                            timings: Some(StageTimings {
+                                audio_ms: transcription.duration_ms,
+                                decode_ms: transcription.decode_ms,
+                                refine_ms: refine_started.elapsed().as_millis() as u64,
+                            }),
+                            audio_path: transcription.audio_path.clone(),
+                        };
+                        if let Some(ref results) = replay_results {
+                            let _ = results.send(entry.clone()).await;
+                        } else if let Err(e) = history::save_last(&entry) {
+                            debug!("Failed to save last dictation: {}", e);
+                        }
+                        // Written right away: a crash shouldn't lose what repeat_last would replay
+                        let mut session = session_transcription.lock().await;
+                        session.push(entry);
+                        if replay_results.is_none() {
+                            if let Err(e) = session.persist() {
+                                warn!("Failed to persist session history: {}", e);
+                            }
+                        }
+                        drop(session);
+
+                        let meta = UtteranceMeta {
+                            recording_id: transcription.recording_id,
+                            language: language.clone(),
+                            profile: profile.clone(),
+                            started_at_ms: transcription.ended_at_ms.saturating_sub(transcription.duration_ms),
+                            ended_at_ms: transcription.ended_at_ms,
+                            duration_ms: transcription.duration_ms,
+                        };
+
+                        // In compose mode the take goes into the draft instead of being output
+                        if let Some(draft) = compose_transcription.lock().await.append(job.variant(TextVariant::Processed), &meta) {
+                            emit_text_transcription("compose_updated", "Draft", &draft, serde_json::Value::Null);
+                            continue;
+                        }
+
+                        // A re-decode can replace the text typed for the original recording
+                        let replace_chars = match (transcription.redecode_of, &delivery.last_injection) {
+                            (Some(original), Some((injected, text))) if replace_previous && original == *injected => {
+                                text.chars().count()
+                            }
+                            _ => 0,
+                        };
+                        let typing = Typing {
+                            // Track re-decodes under the original id so they can be replaced again
+                            source_id: transcription.redecode_of.unwrap_or(transcription.recording_id),
+                            replace_chars,
+                            is_correction: transcription.redecode_of.is_none()
+                                && correction_transcription.swap(false, Ordering::SeqCst),
+                            continues: transcription.segment.is_some_and(|index| index > 0),
+                        };
+                        if let Some(ref template) = output_template {
+                            job.apply_template(template, &meta);
+                        }
+
+                        if !delivery.deliver(&job, &meta, &typing, &job_cancel, &emit_data_transcription).await {
+                            cancel::report(&*emit_data_transcription, Some(transcription.recording_id), JobStage::Output);
+                        } else if let Some(index) = transcription.segment {
+                            let mut segment = serde_json::json!({
+                                "segment": index,
+                                "recording_id": transcription.recording_id,
+                                "preview": events::preview(&job.processed, preview_graphemes),
+                            });
+                            if full_text {
+                                segment["text"] = serde_json::json!(job.processed);
+                            }
+                            emit_data_transcription("segment_complete", segment);
+                        }
+                    }
+
+                    // Finished compose drafts and repeats went through the pipeline already; only the sinks are left
+                    Some(finished) = draft_rx.recv() => {
+                        let (mut job, meta) = match finished {
+                            FinishedText::ComposeDraft { text, meta } => {
+                                info!("Outputting compose draft");
+                                (OutputJob::replayed(text.clone(), None, text), meta)
+                            }
+                            FinishedText::Repeat(entry) => {
+                                info!("Repeating last dictation");
+                                let duration_ms = entry.timings.map_or(0, |timings| timings.audio_ms);
+                                let ended_at_ms = entry.timestamp * 1000;
+                                let meta = UtteranceMeta {
+                                    recording_id: entry.recording_id,
+                                    language: language.clone(),
+                                    profile: profile.clone(),
+                                    started_at_ms: ended_at_ms.saturating_sub(duration_ms),
+                                    ended_at_ms,
+                                    duration_ms,
+                                };
+                                (OutputJob::replayed(entry.raw, entry.refined, entry.processed), meta)
+                            }
+                        };
+                        if let Some(ref template) = output_template {
+                            job.apply_template(template, &meta);
+                        }
+                        let typing = Typing { source_id: meta.recording_id, replace_chars: 0, is_correction: false, continues: false };
+                        delivery.deliver(&job, &meta, &typing, &CancellationToken::new(), &emit_data_transcription).await;
+                    }
+
+                    else => break,
                 }
             }
         });
 
         // Hotkey handling task
         let recording_state_hotkey = recording_state.clone();
-        let hotkey_task = if self.gui_mode {
-            // In GUI mode, create a dummy task that does nothing
+        let hotkey_shutdown = CancellationToken::new();
+        // Cancelled however run() ends, dropped on Ctrl+C included, so the listener thread exits
+        let _hotkey_shutdown_guard = hotkey_shutdown.clone().drop_guard();
+        let mut hotkey_task = if !hotkeys_enabled {
+            // Nothing to listen for; a task that never finishes keeps the main loop waiting
             tokio::spawn(async move {
                 tokio::time::sleep(std::time::Duration::from_secs(u64::MAX)).await;
                 Ok(())
             })
         } else {
-            tokio::spawn(async move {
-                self.hotkey_manager.start_listening(hotkey_tx).await
-            })
+            hotkey_manager.lock().await.start_listening(hotkey_tx, hotkey_shutdown.clone())
         };
 
         // Clone emit_status for main loop
         let emit_status_hotkey = emit_status.clone();
         let vad_main = vad.clone();
+        let state_tx_main = state_tx.clone();
+        let emit_data_main = emit_data.clone();
+        let compose_main = compose.clone();
+        let correction_main = correction_armed.clone();
+        let session_main = session_history.clone();
+        let process_tx_ipc = process_tx.clone();
+        let draft_tx_ipc = draft_tx.clone();
+        let retranscribe_tx_ipc = retranscribe_tx.clone();
+        let router_main = router.clone();
+        let pipeline_main = pipeline.clone();
+        let retainer_main = retainer.clone();
+        let mut record_key = RecordKey::new(
+            self.config.hotkeys.mode,
+            std::time::Duration::from_millis(self.config.hotkeys.min_hold_ms),
+        );
+        let mut double_tap = DoubleTap::new(match self.config.hotkeys.mode {
+            HotkeyMode::Toggle => std::time::Duration::from_millis(self.config.hotkeys.double_tap_ms),
+            HotkeyMode::Hold => std::time::Duration::ZERO,
+        });
 
-        // Main event loop
-        let main_task = tokio::spawn(async move {
-            while let Some(hotkey_event) = hotkey_rx.recv().await {
-                if hotkey_event.pressed && hotkey_event.id == hotkey_id {
-                    let mut state = recording_state_hotkey.lock().await;
-
-                    if !state.is_recording {
-                        info!("Recording started by hotkey");
-                        emit_status_hotkey("recording_started", "Recording started");
-
-                        // Reset VAD for new session
-                        {
-                            let mut vad = vad_main.lock().await;
-                            vad.reset();
+        // A replay presses the toggle key, plays the recording in device-sized chunks, presses
+        // it again, then waits out the pipeline. The VAD times silence by the wall clock, so the
+        // recording runs to its end rather than stopping at a pause.
+        if let (Some(audio), Some(done)) = (replay_audio, replay_done) {
+            let chunk_samples = (self.config.audio.buffer_duration_ms as usize * 16).max(1);
+            let recording_state = recording_state.clone();
+            let pipeline = pipeline.clone();
+            let audio_tx = audio_tx.clone();
+            let process_tx = process_tx.clone();
+            tokio::spawn(async move {
+                let poll = || tokio::time::sleep(std::time::Duration::from_millis(10));
+                if action_tx.send(HotkeyAction::ToggleRecording).await.is_err() {
+                    return;
+                }
+                while !recording_state.lock().await.is_recording {
+                    poll().await;
+                }
+                for chunk in audio.chunks(chunk_samples) {
+                    if audio_tx.send(AudioChunk::from(chunk.to_vec())).await.is_err() {
+                        return;
+                    }
+                }
+                // Every chunk has to be in before the stop, which the audio task may take first
+                while audio_tx.capacity() < audio_tx.max_capacity() {
+                    poll().await;
+                }
+                // An auto-stop may have ended the recording already
+                if recording_state.lock().await.is_recording && action_tx.send(HotkeyAction::ToggleRecording).await.is_err() {
+                    return;
+                }
+                while recording_state.lock().await.is_recording || process_tx.capacity() < process_tx.max_capacity() {
+                    poll().await;
+                }
+                // Idle twice in a row: the audio task may not have queued the decode yet
+                loop {
+                    poll().await;
+                    if pipeline.snapshot().is_idle() {
+                        poll().await;
+                        if pipeline.snapshot().is_idle() {
+                            break;
                         }
+                    }
+                }
+                let _ = done.send(());
+            });
+        }
 
-                        state.is_recording = true;
-                        state.speech_detected = false;
-
-                        // Notify bubble of state change
-                        TomChatApp::notify_state_change(true);
+        // Main event loop
+        let mut main_task = tokio::spawn(async move {
+            loop {
+                let deadline = double_tap.deadline();
+                let (action, pressed) = tokio::select! {
+                    biased;
+                    _ = tokio::time::sleep_until(tokio::time::Instant::from_std(deadline.unwrap_or_else(std::time::Instant::now))), if deadline.is_some() => {
+                        if double_tap.expire(std::time::Instant::now()).is_none() {
+                            continue;
+                        }
+                        (HotkeyAction::ToggleRecording, true)
+                    }
+                    Some(action) = action_rx.recv() => (action, true),
+                    hotkey_event = hotkey_rx.recv() => {
+                        let Some(hotkey_event) = hotkey_event else {
+                            break;
+                        };
+                        let Some(action) = router_main.lock().await.route(hotkey_event.id) else {
+                            continue;
+                        };
+                        // An idle press of the toggle key waits out the double-tap window; a second press repeats the last dictation
+                        if action == HotkeyAction::ToggleRecording && hotkey_event.pressed && !recording_state_hotkey.lock().await.is_recording {
+                            match double_tap.press(std::time::Instant::now()) {
+                                TapOutcome::Pending => continue,
+                                TapOutcome::Double => (HotkeyAction::RepeatLast, true),
+                                TapOutcome::Single => (action, true),
+                            }
+                        } else {
+                            (action, hotkey_event.pressed)
+                        }
+                    }
+                };
+                let records = matches!(
+                    action,
+                    HotkeyAction::ToggleRecording | HotkeyAction::Correction | HotkeyAction::RecordRaw | HotkeyAction::RecordRefined
+                );
+                // Releases only matter to the recording keys, in hold mode
+                if !pressed && !records {
+                    continue;
+                }
+                if action == HotkeyAction::Compose {
+                    if compose_main.lock().await.is_active() {
+                        TomChatApp::finish_compose(&compose_main, &draft_tx, &emit_status_hotkey).await;
                     } else {
-                        info!("Recording stopped by hotkey");
-                        emit_status_hotkey("recording_stopped", "Recording stopped");
-                        state.is_recording = false;
-                        state.speech_detected = false;
-
-                        // Notify bubble of state change
-                        TomChatApp::notify_state_change(false);
-
-                        // Signal audio processing to transcribe accumulated audio
-                        if let Err(_) = process_tx.send(()).await {
-                            error!("Failed to send process signal");
+                        TomChatApp::start_compose(&compose_main, &emit_status_hotkey).await;
+                    }
+                } else if action == HotkeyAction::ComposeCancel {
+                    TomChatApp::cancel_compose(&compose_main, &emit_status_hotkey).await;
+                } else if action == HotkeyAction::RepeatLast {
+                    // Replayed text went through the pipeline already, output it like a draft
+                    let last = session_main.lock().await.last().cloned();
+                    match last {
+                        Some(entry) => {
+                            if draft_tx.send(FinishedText::Repeat(entry)).await.is_err() {
+                                error!("Failed to send repeat text");
+                            }
                         }
+                        None => info!("Nothing to repeat yet"),
+                    }
+                } else if action == HotkeyAction::Cancel {
+                    TomChatApp::cancel_latest(&recording_state_hotkey, &pipeline_main, &retainer_main, &process_tx, &state_tx_main, &emit_data_main).await;
+                } else if action == HotkeyAction::Retranscribe {
+                    info!("Re-decode requested by hotkey");
+                    if retranscribe_tx.send(RedecodeOverrides::default()).await.is_err() {
+                        error!("Failed to send re-decode signal");
                     }
+                } else if records {
+                    TomChatApp::record_key_event(
+                        action,
+                        pressed,
+                        &mut record_key,
+                        &recording_state_hotkey,
+                        &vad_main,
+                        &correction_main,
+                        &process_tx,
+                        &state_tx_main,
+                        &emit_status_hotkey,
+                        &emit_data_main,
+                    )
+                    .await;
                 }
             }
         });
 
         info!("TomChat is ready!");
-        info!("Press {} to start recording", self.config.hotkey.combination);
-        if vad_auto_stop {
-            info!("Auto-stop enabled: recording will stop after {}ms of silence",
-                  self.config.vad.timeout_ms);
-        } else {
-            info!("Press {} again to stop recording", self.config.hotkey.combination);
+        systemd::ready();
+        let hold = self.config.hotkeys.mode == HotkeyMode::Hold;
+        if hotkeys_enabled {
+            if hold {
+                info!("Hold {} to record, release it to transcribe", toggle_hotkey);
+            } else {
+                info!("Press {} to start recording", toggle_hotkey);
+            }
+            if continuous {
+                info!("Continuous mode: each utterance is typed after {}ms of silence, press {} again to end the session",
+                      self.config.vad.timeout_ms, toggle_hotkey);
+            } else if vad_auto_stop {
+                info!("Auto-stop enabled: recording will stop after {}ms of silence",
+                      self.config.vad.timeout_ms);
+            } else if !hold {
+                info!("Press {} again to stop recording", toggle_hotkey);
+            }
         }
         info!("Press Ctrl+C to exit");
 
-        // Wait for any task to complete (or error)
-        tokio::select! {
-            result = audio_task => {
-                if let Err(e) = result {
-                    error!("Audio task failed: {}", e);
+        // GUI commands arrive on stdin; outside GUI mode the channel just stays empty
+        let (_ipc_idle_tx, idle_rx) = mpsc::channel::<IpcCommand>(1);
+        let mut ipc_rx = if gui_mode { ipc::spawn_stdin_reader() } else { idle_rx };
+
+        let mut reconnect = Reconnect::default();
+        let reconnect_sleep = tokio::time::sleep(std::time::Duration::ZERO);
+        tokio::pin!(reconnect_sleep);
+
+        // Wait for any task to complete (or error), handling GUI commands and device loss meanwhile
+        let mut model_ready = false;
+        let mut model_error = None;
+        // A pipeline task that ended while the app was running; nothing would be transcribed anymore
+        let mut exited_task = None;
+        loop {
+            tokio::select! {
+                loaded = model.clone(), if !model_ready => match loaded {
+                    Ok(transcriber) => {
+                        model_ready = true;
+                        match transcriber.model_info() {
+                            Some(model_info) => {
+                                if !gui_mode {
+                                    info!("🧠 Speech model: {} ({})", model_info.name, model_info.model_dir.display());
+                                    info!(
+                                        "   {:.0} MB, {} tokens, {}, {}",
+                                        model_info.size_bytes as f64 / (1024.0 * 1024.0),
+                                        model_info.vocab_size,
+                                        if model_info.multilingual { "multilingual" } else { "English only" },
+                                        model_info.gpu_provider.map_or("CPU".to_string(), |provider| format!("GPU ({})", provider))
+                                    );
+                                }
+                                emit_data("model_info", serde_json::json!(model_info));
+                            }
+                            None => info!("🧠 Transcribing with the {} backend", transcriber.name()),
+                        }
+                        emit_data("init_progress", serde_json::json!({ "component": "speech_model", "status": "ready" }));
+                        if let Some(warning) = transcriber.low_memory_warning() {
+                            emit_status("low_memory_warning", &warning);
+                        }
+                        pipeline.set_model_loaded(true);
+                    }
+                    Err(e) => {
+                        // Running out of memory has its own code and hint for the GUI
+                        if let Some(e) = e.downcast_ref::<PipelineError>() {
+                            TomChatApp::report_error(&*emit_data, e);
+                        }
+                        model_error = Some(e);
+                        break;
+                    }
+                },
+                Some(message) = audio_error_rx.recv() => {
+                    if !reconnect.lost() {
+                        continue;
+                    }
+                    warn!("🎙️ Input device lost ({}), reconnecting", message);
+                    emit_data("device_lost", serde_json::json!({
+                        "device": self.audio_capture.device_name(),
+                        "message": message,
+                    }));
+                    self.audio_capture.stop_capture();
+
+                    // Whatever was being recorded is gone with the device
+                    let mut state = recording_state.lock().await;
+                    if state.is_recording {
+                        info!("Recording abandoned: input device lost");
+                        state.reset();
+                        audio_buffer.lock().await.clear();
+                        TomChatApp::notify_state_change(&state_tx, &emit_data, false);
+                    }
+                    drop(state);
+
+                    reconnect_sleep.as_mut().reset(tokio::time::Instant::now() + reconnect.next_delay());
                 }
-            }
-            result = transcription_task => {
-                if let Err(e) = result {
-                    error!("Transcription task failed: {}", e);
+                _ = &mut reconnect_sleep, if reconnect.is_active() => {
+                    let preferred = self.config.audio.device.clone();
+                    match self.audio_capture.reconnect(preferred.as_deref(), audio_tx.clone()).await {
+                        Ok(()) => {
+                            let attempts = reconnect.succeeded();
+                            // Errors from the dead stream may have queued up meanwhile
+                            while audio_error_rx.try_recv().is_ok() {}
+                            info!("🎙️ Reconnected to '{}' after {} attempt(s)", self.audio_capture.device_name(), attempts);
+                            emit_data("device_reconnected", serde_json::json!({
+                                "device": self.audio_capture.device_name(),
+                                "attempts": attempts,
+                            }));
+                        }
+                        Err(e) => {
+                            reconnect.failed();
+                            debug!("Reconnect attempt failed: {}", e);
+                            reconnect_sleep.as_mut().reset(tokio::time::Instant::now() + reconnect.next_delay());
+                        }
+                    }
                 }
-            }
-            result = hotkey_task => {
-                if let Err(e) = result {
-                    error!("Hotkey task failed: {}", e);
+                Some(command) = ipc_rx.recv() => match command {
+                    IpcCommand::SetAudioDevice { device } => {
+                        let previous = self.audio_capture.device_name();
+                        match self.audio_capture.restart(&device, audio_tx.clone(), AUDIO_DEVICE_PROBE).await {
+                            Ok(()) => {
+                                let name = self.audio_capture.device_name();
+                                if let Err(e) = crate::config::save_setting("audio", "device", &name) {
+                                    warn!("Failed to save audio device: {}", e);
+                                }
+                                emit_data("audio_device", serde_json::json!({ "device": name, "previous": previous }));
+                            }
+                            Err(e) => {
+                                emit_data("audio_device_error", serde_json::json!({
+                                    "requested": device,
+                                    "device": previous,
+                                    "message": e.to_string(),
+                                }));
+                            }
+                        }
+                    }
+                    IpcCommand::Cancel => {
+                        TomChatApp::cancel_latest(&recording_state, &pipeline, &retainer, &process_tx_ipc, &state_tx, &emit_data).await;
+                    }
+                    IpcCommand::RebindHotkey { action, combination } => {
+                        // A recording started by the old key couldn't be stopped with it
+                        let rebound = if !hotkeys_enabled {
+                            Err(anyhow::anyhow!("Global hotkeys are disabled (--no-hotkeys)"))
+                        } else if recording_state.lock().await.is_recording {
+                            Err(anyhow::anyhow!("Can't rebind hotkeys while recording"))
+                        } else {
+                            config_reload::rebind(
+                                &mut *hotkey_manager.lock().await,
+                                &mut *router.lock().await,
+                                action,
+                                Some(&combination),
+                            )
+                        };
+                        match rebound {
+                            Ok(()) => {
+                                if let Err(e) = crate::config::save_setting("hotkeys", action.as_str(), &combination) {
+                                    warn!("Failed to save hotkey: {}", e);
+                                }
+                                emit_data("hotkey_rebound", serde_json::json!({ "action": action, "combination": combination }));
+                            }
+                            Err(e) => {
+                                warn!("⚠️  Couldn't rebind {} to {}: {}", action.as_str(), combination, e);
+                                emit_data("hotkey_rebind_error", serde_json::json!({
+                                    "action": action,
+                                    "combination": combination,
+                                    "message": e.to_string(),
+                                }));
+                            }
+                        }
+                    }
+                    IpcCommand::Status => {
+                        emit_data("pipeline_state", serde_json::json!(pipeline.snapshot()));
+                    }
+                    IpcCommand::ComposeStart => TomChatApp::start_compose(&compose, &emit_status).await,
+                    IpcCommand::ComposeEnd => TomChatApp::finish_compose(&compose, &draft_tx_ipc, &emit_status).await,
+                    IpcCommand::ComposeCancel => TomChatApp::cancel_compose(&compose, &emit_status).await,
+                    IpcCommand::Retranscribe { model, beam_size, language } => {
+                        info!("Re-decode requested over IPC");
+                        if retranscribe_tx_ipc.send(RedecodeOverrides { model, beam_size, language }).await.is_err() {
+                            error!("Failed to send re-decode signal");
+                        }
+                    }
+                },
+                result = &mut audio_task => {
+                    if let Err(e) = result {
+                        error!("Audio task failed: {}", e);
+                    }
+                    exited_task = Some("audio");
+                    break;
                 }
-            }
-            result = main_task => {
-                if let Err(e) = result {
-                    error!("Main task failed: {}", e);
+                result = &mut transcription_task => {
+                    if let Err(e) = result {
+                        error!("Transcription task failed: {}", e);
+                    }
+                    exited_task = Some("transcription");
+                    break;
+                }
+                result = &mut hotkey_task => {
+                    if let Err(e) = result {
+                        error!("Hotkey task failed: {}", e);
+                    }
+                    break;
+                }
+                _ = gui_writer.disconnected() => {
+                    info!("GUI consumer disconnected");
+                    break;
                 }
+                result = &mut main_task => {
+                    if let Err(e) = result {
+                        error!("Main task failed: {}", e);
+                    }
+                    break;
+                }
+            }
+        }
+
+        // Nothing is running anymore; `tomchat status` shouldn't report otherwise
+        status_writer.abort();
+        if !replaying {
+            if let Err(e) = session_history.lock().await.persist() {
+                warn!("Failed to persist session history: {}", e);
+            }
+            if let Err(e) = pipeline_state::remove_status().await {
+                debug!("Failed to remove pipeline state: {}", e);
             }
         }
 
+        if let Some(e) = model_error {
+            return Err(anyhow::anyhow!("{:#}", e));
+        }
+        if let Some(task) = exited_task {
+            let e = PipelineError::PipelineUnavailable { task };
+            TomChatApp::report_error(&*emit_data, &e);
+            return Err(e.into());
+        }
+
         info!("TomChat shutting down gracefully...");
         Ok(())
     }
 }
 
-#[derive(Debug, Default)]
-struct RecordingState {
-    is_recording: bool,
-    speech_detected: bool,
+/// Text output again as a whole rather than dictated: it went through the pipeline already
+enum FinishedText {
+    /// Compose mode ended; the draft spans every take in it
+    ComposeDraft { text: String, meta: UtteranceMeta },
+    /// repeat_last
+    Repeat(HistoryEntry),
+}
+
+/// The configured sinks, and what typing into them has to remember from one job to the next
+struct Delivery {
+    sinks: Vec<SinkConfig>,
+    text_injector: TextInjector,
+    injection_backend: &'static str,
+    /// form_fill's separator and the key that moves to the next field
+    form_fill: Option<(String, enigo::Key)>,
+    similarity_threshold: f32,
+    /// Recording id and text of the last injection, for replace_previous and corrections
+    last_injection: Option<(u64, String)>,
+}
+
+/// How one job is typed
+struct Typing {
+    /// The recording the text is remembered under; a re-decode's original
+    source_id: u64,
+    /// Characters of the last injection to delete first (retranscribe.replace_previous)
+    replace_chars: usize,
+    /// A correction take retypes only from the first changed word, and never fills forms
+    is_correction: bool,
+    /// A continuous session's segments after the first continue the same text
+    continues: bool,
+}
+
+impl Delivery {
+    /// Hand `job` to each sink in turn; false when it was cancelled part way
+    async fn deliver(&mut self, job: &OutputJob, meta: &UtteranceMeta, typing: &Typing, cancel: &CancellationToken, emit_data: &EmitData) -> bool {
+        let backend = self.injection_backend;
+        for sink in &self.sinks {
+            if cancel.is_cancelled() {
+                return false;
+            }
+            let text = job.variant(sink.variant);
+            // Form fill splits typed text into fields, unless it's a correction take
+            let form_fields = match (&self.form_fill, sink.kind) {
+                (Some((separator, key)), SinkKind::Typing) if !typing.is_correction => {
+                    form_fill::split_fields(text, separator).map(|fields| (fields, *key))
+                }
+                _ => None,
+            };
+            let result = match (sink.kind, form_fields) {
+                (SinkKind::Typing, Some((fields, key))) => {
+                    info!("Form fill: {} field(s)", fields.len());
+                    // Text spread over several fields can't be replaced later
+                    self.last_injection = None;
+                    self.text_injector.inject_fields(&fields, key, cancel).await.map_err(|source| PipelineError::InjectionBackend { backend, source })
+                }
+                (SinkKind::Typing, None) => {
+                    // Correction takes retype only from the first changed word
+                    let plan = match &self.last_injection {
+                        Some((_, previous)) if typing.is_correction => {
+                            correction::plan_correction(previous, text, self.similarity_threshold)
+                        }
+                        _ => None,
+                    };
+                    if typing.is_correction {
+                        match plan {
+                            Some(ref plan) => info!("Correcting last injection: {} backspaces, retyping \"{}\"", plan.delete, plan.insert),
+                            None => info!("Correction take differs too much, injecting normally"),
+                        }
+                    }
+                    let (delete, insert) = match plan {
+                        Some(ref plan) => (plan.delete, plan.insert.as_str()),
+                        None => (typing.replace_chars, text),
+                    };
+                    let spaced;
+                    let insert = if typing.continues && delete == 0 {
+                        spaced = format!(" {}", insert);
+                        spaced.as_str()
+                    } else {
+                        insert
+                    };
+
+                    let text_injector = &mut self.text_injector;
+                    let typed = async {
+                        text_injector.settle().await;
+                        // A cancel during the settle delay types nothing; one while typing stops between segments
+                        if cancel.is_cancelled() {
+                            return Ok(false);
+                        }
+                        text_injector.delete_chars(delete).await?;
+                        text_injector.inject_text(insert, cancel).await
+                    };
+                    match typed.await {
+                        Ok(false) => {
+                            // What's on screen may not match the last injection any more
+                            self.last_injection = None;
+                            return false;
+                        }
+                        Ok(true) => {
+                            self.last_injection = Some((typing.source_id, text.to_string()));
+                            Ok(())
+                        }
+                        Err(source) => Err(PipelineError::InjectionBackend { backend, source }),
+                    }
+                }
+                (SinkKind::Stdout | SinkKind::File | SinkKind::Clipboard, _) => write_sink(sink, job, meta).await,
+            };
+
+            match result {
+                Ok(()) => info!("Text output to {:?} sink", sink.kind),
+                Err(e) => {
+                    TomChatApp::report_error(&**emit_data, &e);
+                    if e.policy().recovery == Recovery::Abort {
+                        break;
+                    }
+                }
+            }
+        }
+        true
+    }
+}
+
+/// Hand `job` to a sink that doesn't type: stdout, a file or the clipboard
+async fn write_sink(sink: &SinkConfig, job: &OutputJob, meta: &UtteranceMeta) -> Result<(), PipelineError> {
+    match sink.kind {
+        // A reader that went away (EPIPE) fails this sink, not the daemon
+        SinkKind::Stdout => write_line(&mut tokio::io::stdout(), &job.render_line(sink, meta))
+            .await
+            .map_err(|source| PipelineError::SinkWrite { sink: "stdout", path: PathBuf::from("<stdout>"), source }),
+        SinkKind::File => {
+            let path = sink.path.clone().unwrap_or_default();
+            append_line(&path, &job.render_line(sink, meta))
+                .await
+                .map_err(|source| PipelineError::SinkWrite { sink: "file", path, source })
+        }
+        SinkKind::Clipboard => clipboard::copy(job.variant(sink.variant))
+            .map(|tool| debug!("Copied to the clipboard with {}", tool))
+            .map_err(|source| PipelineError::Clipboard { source }),
+        SinkKind::Typing => unreachable!("typing goes through the text injector"),
+    }
+}
+
+/// Append one line to a sink file, creating it if needed
+async fn append_line(path: &std::path::Path, line: &str) -> std::io::Result<()> {
+    let mut file = tokio::fs::OpenOptions::new().create(true).append(true).open(path).await?;
+    write_line(&mut file, line).await
+}
+
+/// Write `line` and a newline, and flush so a reader downstream sees it now
+async fn write_line(out: &mut (impl tokio::io::AsyncWrite + Unpin), line: &str) -> std::io::Result<()> {
+    use tokio::io::AsyncWriteExt;
+    out.write_all(format!("{}\n", line).as_bytes()).await?;
+    out.flush().await
+}
+
+/// A stopped recording's audio on its way to the decode queue
+enum RecordedAudio {
+    /// Conditioned and ready to decode
+    Memory(Vec<f32>),
+    /// The whole recording as captured in a spill file, conditioned chunk by chunk while
+    /// decoding, then kept in `save_to` (debug.save_audio_dir and its prune limit) if set
+    Spilled {
+        spill: Spill,
+        conditioning: Conditioning,
+        save_to: Option<(PathBuf, usize)>,
+    },
+}
+
+impl RecordedAudio {
+    fn samples(&self) -> usize {
+        match self {
+            RecordedAudio::Memory(audio_data) => audio_data.len(),
+            RecordedAudio::Spilled { spill, .. } => spill.samples(),
+        }
+    }
+}
+
+/// A debug copy of a recording being written on the blocking pool
+type SavedAudio = tokio::task::JoinHandle<Option<PathBuf>>;
+
+/// A stopped recording handed to the audio task: why it stopped, its job's cancel token,
+/// and the hotkey that started it. Sent along because RecordingState may already hold
+/// the next recording by the time the signal is handled.
+type ProcessSignal = (StopReason, CancellationToken, Option<HotkeyAction>);
+
+/// The refiner a recording's text goes through, if any: record_raw never refines,
+/// record_refined always does, everything else follows text_refinement.enabled
+fn refiner_for(trigger: Option<HotkeyAction>, refiner: Option<&TextRefiner>, refine_by_default: bool) -> Option<&TextRefiner> {
+    let refine = match trigger {
+        Some(HotkeyAction::RecordRaw) => false,
+        Some(HotkeyAction::RecordRefined) => true,
+        _ => refine_by_default,
+    };
+    refiner.filter(|_| refine)
+}
+
+/// Write a debug copy into debug.save_audio_dir with `write`, then prune the oldest
+/// beyond `max_saved_files`. Blocking; None if the copy couldn't be written.
+fn save_recording(
+    dir: &std::path::Path,
+    max_saved_files: usize,
+    write: impl FnOnce(&std::path::Path) -> Result<PathBuf>,
+) -> Option<PathBuf> {
+    match write(dir) {
+        Ok(path) => {
+            info!("💾 Recording saved to {:?}", path);
+            if let Err(e) = wav::prune(dir, max_saved_files) {
+                warn!("Failed to prune saved recordings: {}", e);
+            }
+            Some(path)
+        }
+        Err(e) => {
+            warn!("Failed to save recording: {}", e);
+            None
+        }
+    }
+}
+
+/// Level and noise processing applied to a recording before it's decoded
+#[derive(Debug, Clone, Copy)]
+struct Conditioning {
+    gain: Option<GainDecision>,
+    noise_suppression: bool,
+}
+
+impl Conditioning {
+    fn apply(&self, samples: &mut [f32]) {
+        if let Some(ref gain) = self.gain {
+            gain.apply(samples);
+        }
+        if self.noise_suppression {
+            let started = std::time::Instant::now();
+            if let Some(report) = denoise::suppress(samples) {
+                debug!("🔇 Noise suppression: SNR {:.1} -> {:.1} dB ({:+.1} dB) in {:?} for {:.1}s",
+                       report.snr_before_db, report.snr_after_db, report.improvement_db(),
+                       started.elapsed(), samples.len() as f32 / 16000.0);
+            }
+        }
+    }
+
+    /// Apply on the blocking pool; the FFT takes tens of milliseconds for a long recording
+    async fn run(self, mut samples: Vec<f32>) -> Vec<f32> {
+        if self.gain.is_none() && !self.noise_suppression {
+            return samples;
+        }
+        match tokio::task::spawn_blocking(move || {
+            self.apply(&mut samples);
+            samples
+        })
+        .await
+        {
+            Ok(samples) => samples,
+            Err(e) => std::panic::resume_unwind(e.into_panic()),
+        }
+    }
+}
+
+/// A decoded recording on its way to refinement and output
+#[derive(Debug)]
+struct Transcription {
+    recording_id: u64,
+    text: String,
+    /// Set when this is a re-decode of an earlier recording
+    redecode_of: Option<u64>,
+    /// When the recording stopped, in milliseconds since the Unix epoch
+    ended_at_ms: u64,
+    duration_ms: u64,
+    stop_reason: Option<StopReason>,
+    /// Position in a continuous session
+    segment: Option<u32>,
+    decode_ms: u64,
+    /// Where debug.save_audio_dir put the recording, if it did
+    audio_path: Option<PathBuf>,
+    /// The hotkey that started the recording; None for re-decodes
+    trigger: Option<HotkeyAction>,
+    /// Set when the job is cancelled; refinement and output check it
+    cancel: CancellationToken,
+    /// Stage reporting for the busy indicator; dropped when the job ends
+    job: Job,
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode_queue::QueueOverflow;
+    use crate::output::job::SinkFormat;
+    use futures_util::future::BoxFuture;
+
+    /// A backend that takes a while to decode
+    struct SlowBackend;
+
+    impl TranscriberBackend for SlowBackend {
+        fn name(&self) -> &'static str {
+            "slow"
+        }
+
+        fn transcribe<'a>(&'a self, _audio: &'a [f32]) -> BoxFuture<'a, Result<String>> {
+            async {
+                tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+                Ok("hello world".to_string())
+            }
+            .boxed()
+        }
+    }
+
+    type Events = Arc<std::sync::Mutex<Vec<String>>>;
+
+    fn recorders() -> (EmitText, EmitData, Events) {
+        let events: Events = Arc::default();
+        let (text_events, data_events) = (events.clone(), events.clone());
+        let emit_text: EmitText = Arc::new(move |name: &str, _: &str, _: &str, _| text_events.lock().unwrap().push(name.to_string()));
+        let emit_data: EmitData = Arc::new(move |name: &str, _| data_events.lock().unwrap().push(name.to_string()));
+        (emit_text, emit_data, events)
+    }
+
+    /// A retainer holding recording 1, as the audio task leaves it once that's decoding
+    fn retainer() -> Mutex<RecordingRetainer> {
+        let mut retainer = RecordingRetainer::new(16_000, std::time::Duration::from_secs(60));
+        retainer.retain(1, &[0.25; 1600]);
+        Mutex::new(retainer)
+    }
+
+    /// Decode a second of audio on SlowBackend; the receiver is where output picks results up
+    async fn decode(cancel: CancellationToken, emit_text: EmitText, emit_data: EmitData) -> mpsc::Receiver<Transcription> {
+        let backend: Arc<dyn TranscriberBackend> = Arc::new(SlowBackend);
+        let model: LoadedModel = futures_util::future::ready(Ok(backend)).boxed().shared();
+        let queue = DecodeQueue::spawn(4, QueueOverflow::Reject);
+        let pipeline = PipelineState::default();
+        let (tx, rx) = mpsc::channel(4);
+        TomChatApp::queue_transcription(
+            &queue,
+            model,
+            RecordedAudio::Memory(vec![0.1; 16000]),
+            16000.0,
+            1,
+            None,
+            Some(StopReason::Hotkey),
+            None,
+            None,
+            cancel,
+            pipeline.track(1),
+            None,
+            tx,
+            emit_text,
+            emit_data,
+        )
+        .await;
+        rx
+    }
+
+    #[tokio::test]
+    async fn a_finished_decode_goes_to_output() {
+        let (emit_text, emit_data, events) = recorders();
+
+        let mut output = decode(CancellationToken::new(), emit_text, emit_data).await;
+
+        assert_eq!(output.recv().await.unwrap().text, "hello world");
+        assert!(events.lock().unwrap().contains(&"transcription_complete".to_string()));
+    }
+
+    #[tokio::test]
+    async fn a_cancelled_decode_never_reaches_output() {
+        let (emit_text, emit_data, events) = recorders();
+        let cancel = CancellationToken::new();
+
+        let mut output = decode(cancel.clone(), emit_text, emit_data).await;
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        cancel.cancel();
+
+        // The sender is dropped with the job, without anything sent
+        assert!(output.recv().await.is_none());
+        let events = events.lock().unwrap();
+        assert!(events.contains(&"transcription_cancelled".to_string()), "{:?}", events);
+        assert!(!events.contains(&"transcription_complete".to_string()));
+    }
+
+    #[tokio::test]
+    async fn cancelling_with_nothing_in_flight_does_nothing() {
+        let (_, emit_data, events) = recorders();
+        let recording_state = Mutex::new(RecordingState::default());
+        let token = recording_state.lock().await.cancel_token();
+        let pipeline = PipelineState::default();
+        let retainer = retainer();
+        let (process_tx, mut process_rx) = mpsc::channel(1);
+        let (state_tx, _) = watch::channel(false);
+
+        TomChatApp::cancel_latest(&recording_state, &pipeline, &retainer, &process_tx, &state_tx, &emit_data).await;
+        assert!(!token.is_cancelled());
+        assert!(retainer.lock().await.get().is_some(), "the last recording can still be re-decoded");
+
+        // A job still in the pipeline is the one cancelled, and its audio isn't kept
+        let _job = pipeline.track(1);
+        TomChatApp::cancel_latest(&recording_state, &pipeline, &retainer, &process_tx, &state_tx, &emit_data).await;
+        assert!(token.is_cancelled());
+        assert!(retainer.lock().await.get().is_none());
+
+        assert!(process_rx.try_recv().is_err());
+        assert!(events.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn cancelling_a_recording_stops_it_for_the_audio_task() {
+        let (_, emit_data, _) = recorders();
+        let recording_state = Mutex::new(RecordingState::default());
+        recording_state.lock().await.start();
+        let (process_tx, mut process_rx) = mpsc::channel(1);
+        let (state_tx, state_rx) = watch::channel(true);
+
+        TomChatApp::cancel_latest(&recording_state, &PipelineState::default(), &retainer(), &process_tx, &state_tx, &emit_data).await;
+
+        let (reason, token, _) = process_rx.try_recv().unwrap();
+        assert_eq!(reason, StopReason::Cancelled);
+        assert!(token.is_cancelled());
+        assert!(!*state_rx.borrow());
+        assert!(!recording_state.lock().await.is_recording);
+    }
+
+    #[tokio::test]
+    async fn the_stop_signal_carries_the_trigger_of_the_recording_it_stops() {
+        let (_, emit_data, _) = recorders();
+        let recording_state = Mutex::new(RecordingState::default());
+        {
+            let mut state = recording_state.lock().await;
+            state.start();
+            state.trigger = Some(HotkeyAction::RecordRaw);
+        }
+        let (process_tx, mut process_rx) = mpsc::channel(1);
+        let (state_tx, _) = watch::channel(true);
+
+        TomChatApp::cancel_latest(&recording_state, &PipelineState::default(), &retainer(), &process_tx, &state_tx, &emit_data).await;
+        // The next recording starts before the audio task gets to the signal
+        {
+            let mut state = recording_state.lock().await;
+            state.start();
+            state.trigger = Some(HotkeyAction::RecordRefined);
+        }
+
+        let (_, _, trigger) = process_rx.try_recv().unwrap();
+        assert_eq!(trigger, Some(HotkeyAction::RecordRaw));
+    }
+
+    /// A refiner whose Ollama answers every request with `reply`, and the count of requests made
+    fn stub_refiner(reply: &'static str) -> (TextRefiner, Arc<std::sync::atomic::AtomicUsize>) {
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = calls.clone();
+        let url = crate::test_server::serve(move |_, _, stream| {
+            counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            let body = serde_json::json!({ "model": "gemma3:1b", "created_at": "", "response": reply, "done": true });
+            crate::test_server::respond(stream, "200 OK", &[], body.to_string().as_bytes());
+        });
+        let refiner = TextRefiner::unchecked(TextRefinementConfig { ollama_url: url, timeout_ms: 2000, ..Default::default() });
+        (refiner, calls)
+    }
+
+    /// What refinement makes of `text` for a recording started by `trigger`
+    async fn routed(trigger: Option<HotkeyAction>, refiner: &TextRefiner, refine_by_default: bool, text: &str) -> Option<String> {
+        match refiner_for(trigger, Some(refiner), refine_by_default) {
+            Some(refiner) => Some(refiner.refine_text(text).await.unwrap()),
+            None => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn record_raw_never_reaches_the_refiner() {
+        let (refiner, calls) = stub_refiner("Hello, world.");
+
+        assert_eq!(routed(Some(HotkeyAction::RecordRaw), &refiner, true, "hello world").await, None);
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn record_refined_refines_with_refinement_off() {
+        let (refiner, calls) = stub_refiner("Hello, world.");
+
+        let refined = routed(Some(HotkeyAction::RecordRefined), &refiner, false, "hello world").await;
+
+        assert_eq!(refined.as_deref(), Some("Hello, world."));
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn other_recordings_follow_text_refinement_enabled() {
+        let (refiner, calls) = stub_refiner("Hello, world.");
+
+        assert_eq!(routed(Some(HotkeyAction::ToggleRecording), &refiner, false, "hello world").await, None);
+        assert_eq!(routed(None, &refiner, false, "hello world").await, None);
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 0);
+
+        let refined = routed(None, &refiner, true, "hello world").await;
+        assert_eq!(refined.as_deref(), Some("Hello, world."));
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn without_a_refiner_nothing_is_refined() {
+        assert!(refiner_for(Some(HotkeyAction::RecordRefined), None, true).is_none());
+    }
+
+    /// Emitters that format events the way GUI mode prints them, one JSON line each
+    fn gui_lines() -> (EmitStatus, EmitData, Arc<std::sync::Mutex<Vec<serde_json::Value>>>) {
+        let lines: Arc<std::sync::Mutex<Vec<serde_json::Value>>> = Arc::default();
+        let (status_lines, data_lines) = (lines.clone(), lines.clone());
+        let emit_status: EmitStatus = Arc::new(move |event: &str, message: &str| {
+            let line = crate::gui_writer::event_line(event, serde_json::json!({ "message": message }));
+            status_lines.lock().unwrap().push(serde_json::from_str(&line).unwrap());
+        });
+        let emit_data: EmitData = Arc::new(move |event: &str, data: serde_json::Value| {
+            let line = crate::gui_writer::event_line(event, serde_json::json!({ "data": data }));
+            data_lines.lock().unwrap().push(serde_json::from_str(&line).unwrap());
+        });
+        (emit_status, emit_data, lines)
+    }
+
+    #[tokio::test]
+    async fn hotkey_presses_in_gui_mode_start_and_stop_recordings() {
+        let (emit_status, emit_data, lines) = gui_lines();
+        let mut router = HotkeyRouter::default();
+        router.bind(7, HotkeyAction::ToggleRecording, "ctrl+shift+space").unwrap();
+        let mut record_key = RecordKey::new(HotkeyMode::Toggle, std::time::Duration::ZERO);
+        let recording_state = Mutex::new(RecordingState::default());
+        let vad = Mutex::new(
+            VoiceActivityDetector::new("", 16000, 0.5, 500, 0, 0, crate::audio::VadEngine::Energy, 32).unwrap(),
+        );
+        let correction_armed = AtomicBool::new(false);
+        let (process_tx, mut process_rx) = mpsc::channel(4);
+        let (state_tx, state_rx) = watch::channel(false);
+
+        // What the listener would deliver: a press and release to start, the same to stop
+        let (hotkey_tx, mut hotkey_rx) = mpsc::channel(8);
+        for pressed in [true, false, true, false] {
+            hotkey_tx.send(HotkeyEvent { id: 7, hotkey: "ctrl+shift+space".to_string(), pressed }).await.unwrap();
+        }
+        drop(hotkey_tx);
+
+        let mut recording = Vec::new();
+        while let Some(event) = hotkey_rx.recv().await {
+            let action = router.route(event.id).unwrap();
+            TomChatApp::record_key_event(
+                action,
+                event.pressed,
+                &mut record_key,
+                &recording_state,
+                &vad,
+                &correction_armed,
+                &process_tx,
+                &state_tx,
+                &emit_status,
+                &emit_data,
+            )
+            .await;
+            recording.push(*state_rx.borrow());
+        }
+
+        // Releases do nothing in toggle mode
+        assert_eq!(recording, [true, true, false, false]);
+        let lines = lines.lock().unwrap();
+        let events: Vec<&str> = lines.iter().map(|line| line["event"].as_str().unwrap()).collect();
+        assert_eq!(events, ["recording_started", "state_changed", "recording_stopped", "state_changed"]);
+        assert_eq!(lines[2]["data"]["reason"], "hotkey");
+        assert_eq!(lines[3]["data"]["recording"], false);
+
+        let (reason, _, trigger) = process_rx.try_recv().unwrap();
+        assert_eq!((reason, trigger), (StopReason::Hotkey, Some(HotkeyAction::ToggleRecording)));
+    }
+
+    /// When the audio task stub goes down
+    #[derive(Clone, Copy)]
+    enum Kill {
+        AsItStarts,
+        OnItsFirstStop,
+    }
+
+    /// Stands in for the audio task: hands what's buffered at each stop on to `transcribed`,
+    /// leaving it buffered until the stop is done with, as the real one does
+    fn audio_task_stub(
+        inbox: supervisor::Inbox<ProcessSignal>,
+        buffer: Arc<Mutex<VecDeque<f32>>>,
+        transcribed: mpsc::UnboundedSender<Vec<f32>>,
+        kill: Option<Kill>,
+    ) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            if matches!(kill, Some(Kill::AsItStarts)) {
+                panic!("audio task killed");
+            }
+            let mut inbox = inbox.open().await;
+            loop {
+                if inbox.finish() {
+                    buffer.lock().await.clear();
+                }
+                let Some(_) = inbox.recv().await else { return };
+                let audio: Vec<f32> = buffer.lock().await.iter().cloned().collect();
+                if matches!(kill, Some(Kill::OnItsFirstStop)) {
+                    panic!("audio task killed");
+                }
+                let _ = transcribed.send(audio);
+            }
+        })
+    }
+
+    /// Record a tenth of a second by hotkey with the audio task killed once, and return what
+    /// reaches transcription and how many restarts it took
+    async fn record_through_a_kill(kill: Kill) -> (Vec<f32>, usize) {
+        let (process_tx, process_rx) = mpsc::channel(4);
+        let inbox = supervisor::Inbox::new(process_rx);
+        let buffer = Arc::new(Mutex::new(VecDeque::new()));
+        let (transcribed_tx, mut transcribed) = mpsc::unbounded_channel();
+        let restarts = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let (task_buffer, counted) = (buffer.clone(), restarts.clone());
+        let mut kill = Some(kill);
+        tokio::spawn(supervisor::supervise(
+            "audio",
+            MAX_AUDIO_RESTARTS,
+            move || audio_task_stub(inbox.clone(), task_buffer.clone(), transcribed_tx.clone(), kill.take()),
+            move |_, restarts| counted.store(restarts, Ordering::SeqCst),
+        ));
+
+        let (emit_status, emit_data, _) = gui_lines();
+        let mut record_key = RecordKey::new(HotkeyMode::Toggle, std::time::Duration::ZERO);
+        let recording_state = Mutex::new(RecordingState::default());
+        let vad = Mutex::new(
+            VoiceActivityDetector::new("", 16000, 0.5, 500, 0, 0, crate::audio::VadEngine::Energy, 32).unwrap(),
+        );
+        let correction_armed = AtomicBool::new(false);
+        let (state_tx, _state_rx) = watch::channel(false);
+        for start in [true, false] {
+            if !start {
+                buffer.lock().await.extend([0.25; 1600]);
+            }
+            TomChatApp::record_key_event(
+                HotkeyAction::ToggleRecording,
+                true,
+                &mut record_key,
+                &recording_state,
+                &vad,
+                &correction_armed,
+                &process_tx,
+                &state_tx,
+                &emit_status,
+                &emit_data,
+            )
+            .await;
+        }
+
+        (transcribed.recv().await.unwrap(), restarts.load(Ordering::SeqCst))
+    }
+
+    #[tokio::test]
+    async fn a_stop_made_while_the_audio_task_is_down_is_transcribed_once_it_restarts() {
+        assert_eq!(record_through_a_kill(Kill::AsItStarts).await, (vec![0.25; 1600], 1));
+    }
+
+    #[tokio::test]
+    async fn the_recording_the_audio_task_died_on_is_transcribed_once_it_restarts() {
+        assert_eq!(record_through_a_kill(Kill::OnItsFirstStop).await, (vec![0.25; 1600], 1));
+    }
+
+    fn file_sink(path: PathBuf, format: SinkFormat) -> SinkConfig {
+        SinkConfig { kind: SinkKind::File, variant: TextVariant::Processed, format, path: Some(path) }
+    }
+
+    #[tokio::test]
+    async fn a_failing_sink_leaves_the_others_writing() {
+        let dir = std::env::temp_dir().join(format!("tomchat-sinks-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (notes, log) = (dir.join("notes.md"), dir.join("log.jsonl"));
+        let sinks = [
+            // A directory can't be appended to
+            file_sink(dir.clone(), SinkFormat::Text),
+            file_sink(notes.clone(), SinkFormat::Timestamped),
+            file_sink(log.clone(), SinkFormat::Jsonl),
+        ];
+        let meta = UtteranceMeta {
+            recording_id: 3,
+            language: "en".to_string(),
+            profile: None,
+            started_at_ms: 1_700_000_000_000,
+            ended_at_ms: 1_700_000_002_000,
+            duration_ms: 2000,
+        };
+
+        for text in ["first note", "second note"] {
+            let job = OutputJob::new(text.to_string(), None);
+            let mut failed = Vec::new();
+            for sink in &sinks {
+                if let Err(e) = write_sink(sink, &job, &meta).await {
+                    assert_eq!(e.policy().recovery, Recovery::SkipSink);
+                    failed.push(sink.path.clone().unwrap());
+                }
+            }
+            assert_eq!(failed, std::slice::from_ref(&dir));
+        }
+
+        let notes = std::fs::read_to_string(&notes).unwrap();
+        assert_eq!(notes, "2023-11-14T22:13:20Z first note\n2023-11-14T22:13:20Z second note\n");
+        let log: Vec<serde_json::Value> =
+            std::fs::read_to_string(&log).unwrap().lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[1]["text"], "second note");
+        assert_eq!(log[1]["recording_id"], 3);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    #[tokio::test]
+    async fn a_reader_that_went_away_is_a_write_error() {
+        let (mut writer, reader) = tokio::io::duplex(64);
+        write_line(&mut writer, "heard").await.unwrap();
+        drop(reader);
+        let e = write_line(&mut writer, "not heard").await.unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::BrokenPipe);
+    }
 }
@@ -1,17 +1,128 @@
 use anyhow::Result;
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::{Device, Sample, SampleFormat, Stream, StreamConfig, SizedSample};
+use cpal::{BufferSize, Device, Sample, SampleFormat, Stream, StreamConfig, StreamError, SizedSample, SupportedBufferSize};
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::mpsc;
 use tracing::{debug, error, info, warn};
 
+use super::process::{AudioChunk, CallbackProcessor, ChannelMode, ChunkPool, RateMeter, MAX_RATE_DEVIATION, TARGET_RATE};
+
+/// Chunks the pipeline may fall behind by before the callback drops audio; several
+/// seconds of typical 10ms callbacks, so only a stalled pipeline loses any
+pub const AUDIO_CHANNEL_CHUNKS: usize = 512;
+
 pub struct AudioCapture {
     device: Device,
     config: StreamConfig,
     stream: Option<Stream>,
+    /// Requested callback size, 0 = leave it to the driver
+    buffer_duration_ms: u32,
+    channel_mode: ChannelMode,
+    negotiation: Option<BufferNegotiation>,
+    /// Frames delivered in the most recent callback
+    callback_frames: Arc<AtomicUsize>,
+    /// Stream errors (e.g. the device was unplugged) are reported here
+    error_tx: Option<mpsc::UnboundedSender<String>>,
+    output_rate: OutputRate,
+}
+
+/// Rate of the samples the current stream delivers: 16kHz, scaled by how far the
+/// device's measured clock is off its nominal rate. Shared with the pipeline and kept
+/// across device switches.
+#[derive(Debug, Clone)]
+pub struct OutputRate(Arc<AtomicU64>);
+
+impl Default for OutputRate {
+    fn default() -> Self {
+        Self(Arc::new(AtomicU64::new((TARGET_RATE as f64).to_bits())))
+    }
+}
+
+impl OutputRate {
+    pub fn get(&self) -> f64 {
+        f64::from_bits(self.0.load(Ordering::Relaxed))
+    }
+
+    fn set(&self, rate: f64) {
+        self.0.store(rate.to_bits(), Ordering::Relaxed);
+    }
+}
+
+/// How the stream's buffer size was settled, for the effective configuration
+#[derive(Debug, Clone, Serialize)]
+pub struct BufferNegotiation {
+    pub requested_frames: Option<u32>,
+    /// "fixed(N)" or "default"
+    pub used: String,
+    /// Each rejected attempt with the driver's error
+    pub rejected: Vec<String>,
+}
+
+/// Find a device by its index in the startup listing, or by name: exact, then
+/// case-insensitive, then a prefix or substring only one device has, so a saved name
+/// still matches when the system adds a suffix like " (2)". Unnamed devices never match.
+fn match_device_name(names: &[String], wanted: &str) -> Option<usize> {
+    let wanted = wanted.trim();
+    if wanted.is_empty() {
+        return None;
+    }
+    if let Ok(index) = wanted.parse::<usize>() {
+        if index < names.len() {
+            return Some(index);
+        }
+    }
+
+    let named = || names.iter().enumerate().filter(|(_, name)| !name.trim().is_empty());
+    let unique = |matches: Vec<usize>| (matches.len() == 1).then(|| matches[0]);
+    let lowered = wanted.to_lowercase();
+
+    named()
+        .find(|(_, name)| name.as_str() == wanted)
+        .or_else(|| named().find(|(_, name)| name.to_lowercase() == lowered))
+        .map(|(index, _)| index)
+        .or_else(|| unique(named().filter(|(_, name)| name.to_lowercase().starts_with(&lowered)).map(|(index, _)| index).collect()))
+        .or_else(|| unique(named().filter(|(_, name)| name.to_lowercase().contains(&lowered)).map(|(index, _)| index).collect()))
+}
+
+/// Whether a stream error means the device is gone, rather than a glitch the stream survives
+fn is_device_loss(err: &StreamError) -> bool {
+    matches!(err, StreamError::DeviceNotAvailable)
+}
+
+/// Buffer sizes to try in order: the requested size, the nearest one the device
+/// claims to support, then whatever the driver picks
+fn buffer_ladder(requested_frames: Option<u32>, supported: &SupportedBufferSize) -> Vec<BufferSize> {
+    let mut ladder = Vec::new();
+    if let Some(frames) = requested_frames {
+        ladder.push(BufferSize::Fixed(frames));
+        if let SupportedBufferSize::Range { min, max } = supported {
+            let nearest = frames.clamp(*min, *max);
+            if nearest != frames {
+                ladder.push(BufferSize::Fixed(nearest));
+            }
+        }
+    }
+    ladder.push(BufferSize::Default);
+    ladder
+}
+
+fn describe_buffer_size(size: &BufferSize) -> String {
+    match size {
+        BufferSize::Fixed(frames) => format!("fixed({})", frames),
+        BufferSize::Default => "default".to_string(),
+    }
 }
 
 impl AudioCapture {
     pub fn new() -> Result<Self> {
+        Self::with_device(None)
+    }
+
+    /// Capture from the input device whose name contains `device_name`, or the default one
+    pub fn with_device(device_name: Option<&str>) -> Result<Self> {
         let host = cpal::default_host();
         info!("Using audio host: {}", host.id().name());
         
@@ -20,13 +131,26 @@ impl AudioCapture {
         info!("Available input devices:");
         for (i, device) in input_devices.iter().enumerate() {
             if let Ok(name) = device.name() {
-                info!("  {}: {}", i, name);
+                match device.default_input_config() {
+                    Ok(config) => info!("  {}: {} ({} channels)", i, name, config.channels()),
+                    Err(_) => info!("  {}: {}", i, name),
+                }
             }
         }
         
-        let device = host
-            .default_input_device()
-            .ok_or_else(|| anyhow::anyhow!("No input device available"))?;
+        let device = match device_name {
+            Some(wanted) => {
+                let names: Vec<String> = input_devices.iter().map(|device| device.name().unwrap_or_default()).collect();
+                let index = match_device_name(&names, wanted).ok_or_else(|| {
+                    let available: Vec<String> = names.iter().enumerate().map(|(i, name)| format!("{}: {}", i, name)).collect();
+                    anyhow::anyhow!("No input device matching '{}'. Available: {}", wanted, available.join(", "))
+                })?;
+                input_devices.into_iter().nth(index).unwrap()
+            }
+            None => host
+                .default_input_device()
+                .ok_or_else(|| anyhow::anyhow!("No input device available"))?,
+        };
         
         info!("Using input device: {}", device.name().unwrap_or_default());
         
@@ -50,32 +174,170 @@ impl AudioCapture {
             device,
             config,
             stream: None,
+            buffer_duration_ms: 0,
+            channel_mode: ChannelMode::Mix,
+            negotiation: None,
+            callback_frames: Arc::new(AtomicUsize::new(0)),
+            error_tx: None,
+            output_rate: OutputRate::default(),
         })
     }
+
+    pub fn device_name(&self) -> String {
+        self.device.name().unwrap_or_default()
+    }
+
+    /// Channels the stream is opened with (the device's default layout)
+    pub fn channels(&self) -> u16 {
+        self.config.channels
+    }
+
+    /// Switch to another input device, keeping the current one if the new one
+    /// doesn't deliver samples within `probe`
+    pub async fn restart(
+        &mut self,
+        device_name: &str,
+        audio_tx: mpsc::Sender<AudioChunk>,
+        probe: std::time::Duration,
+    ) -> Result<()> {
+        let mut candidate = Self::with_device(Some(device_name))?;
+        candidate.set_buffer_duration_ms(self.buffer_duration_ms);
+        candidate.set_channel_mode(self.channel_mode);
+        candidate.error_tx = self.error_tx.clone();
+        candidate.output_rate = self.output_rate.clone();
+
+        // Probe on a throwaway channel so the pipeline never sees both devices at once
+        let (probe_tx, mut probe_rx) = mpsc::channel(AUDIO_CHANNEL_CHUNKS);
+        candidate.start_capture(probe_tx).await?;
+        let delivered = tokio::time::timeout(probe, probe_rx.recv()).await;
+        candidate.stop_capture();
+        if !matches!(delivered, Ok(Some(_))) {
+            return Err(anyhow::anyhow!(
+                "Input device '{}' delivered no audio within {:?}",
+                candidate.device_name(),
+                probe
+            ));
+        }
+
+        self.stop_capture();
+        if let Err(e) = candidate.start_capture(audio_tx.clone()).await {
+            warn!("Restarting on the new device failed, going back to '{}': {}", self.device_name(), e);
+            self.start_capture(audio_tx).await?;
+            return Err(e);
+        }
+
+        info!("🎙️ Switched input device to '{}'", candidate.device_name());
+        *self = candidate;
+        Ok(())
+    }
+
+    /// Reopen capture after the device was lost: the preferred device if it's back, else the default
+    pub async fn reconnect(&mut self, preferred: Option<&str>, audio_tx: mpsc::Sender<AudioChunk>) -> Result<()> {
+        self.stop_capture();
+
+        let mut candidate = match Self::with_device(preferred) {
+            Ok(candidate) => candidate,
+            Err(e) if preferred.is_some() => {
+                warn!("{} - trying the default input device", e);
+                Self::new()?
+            }
+            Err(e) => return Err(e),
+        };
+        candidate.set_buffer_duration_ms(self.buffer_duration_ms);
+        candidate.set_channel_mode(self.channel_mode);
+        candidate.error_tx = self.error_tx.clone();
+        candidate.output_rate = self.output_rate.clone();
+        candidate.start_capture(audio_tx).await?;
+
+        *self = candidate;
+        Ok(())
+    }
+
+    /// Report stream errors on this channel instead of only logging them
+    pub fn set_error_sender(&mut self, error_tx: mpsc::UnboundedSender<String>) {
+        self.error_tx = Some(error_tx);
+    }
+
+    /// Ask for callbacks of roughly this duration (audio.buffer_duration_ms)
+    pub fn set_buffer_duration_ms(&mut self, buffer_duration_ms: u32) {
+        self.buffer_duration_ms = buffer_duration_ms;
+    }
+
+    /// How the device's channels are reduced to mono (audio.channel_mode)
+    pub fn set_channel_mode(&mut self, channel_mode: ChannelMode) {
+        self.channel_mode = channel_mode;
+    }
+
+    /// Handle on the delivered sample rate, updated whenever a stream is built
+    pub fn output_rate(&self) -> OutputRate {
+        self.output_rate.clone()
+    }
+
+    /// The buffer size the stream ended up with, once capture started
+    pub fn buffer_negotiation(&self) -> Option<&BufferNegotiation> {
+        self.negotiation.as_ref()
+    }
+
+    /// Frames per callback actually delivered by the driver
+    pub fn callback_frames(&self) -> usize {
+        self.callback_frames.load(Ordering::Relaxed)
+    }
     
-    pub async fn start_capture(&mut self, audio_tx: mpsc::UnboundedSender<Vec<f32>>) -> Result<()> {
-        let config = self.config.clone();
-        let sample_format = self.device.default_input_config()?.sample_format();
+    pub async fn start_capture(&mut self, audio_tx: mpsc::Sender<AudioChunk>) -> Result<()> {
+        let default_config = self.device.default_input_config()?;
+        let sample_format = default_config.sample_format();
         
         info!("Starting audio capture with sample format: {:?}", sample_format);
-        
-        let stream = match sample_format {
-            SampleFormat::F32 => self.build_input_stream::<f32>(config, audio_tx)?,
-            SampleFormat::I16 => self.build_input_stream::<i16>(config, audio_tx)?,
-            SampleFormat::U16 => self.build_input_stream::<u16>(config, audio_tx)?,
-            _ => return Err(anyhow::anyhow!("Unsupported sample format: {:?}", sample_format)),
-        };
+
+        let requested_frames = (self.buffer_duration_ms > 0)
+            .then(|| self.config.sample_rate.0 * self.buffer_duration_ms / 1000);
+
+        // Some ALSA devices reject fixed sizes outright, so walk down to Default
+        let mut rejected = Vec::new();
+        let mut built = None;
+        for buffer_size in buffer_ladder(requested_frames, default_config.buffer_size()) {
+            let mut config = self.config.clone();
+            config.buffer_size = buffer_size;
+
+            let result = match sample_format {
+                SampleFormat::F32 => self.build_input_stream::<f32>(config, audio_tx.clone()),
+                SampleFormat::I16 => self.build_input_stream::<i16>(config, audio_tx.clone()),
+                SampleFormat::U16 => self.build_input_stream::<u16>(config, audio_tx.clone()),
+                _ => return Err(anyhow::anyhow!("Unsupported sample format: {:?}", sample_format)),
+            };
+
+            match result {
+                Ok(stream) => {
+                    built = Some((stream, buffer_size));
+                    break;
+                }
+                Err(e) => {
+                    warn!("Buffer size {} rejected: {}", describe_buffer_size(&buffer_size), e);
+                    rejected.push(format!("{}: {}", describe_buffer_size(&buffer_size), e));
+                }
+            }
+        }
+
+        let (stream, buffer_size) = built
+            .ok_or_else(|| anyhow::anyhow!("Could not open the input stream with any buffer size: {}", rejected.join("; ")))?;
         
         stream.play()?;
         self.stream = Some(stream);
-        
-        info!("Audio capture started successfully");
+        self.config.buffer_size = buffer_size;
+
+        let negotiation = BufferNegotiation {
+            requested_frames,
+            used: describe_buffer_size(&buffer_size),
+            rejected,
+        };
+        info!("Audio capture started successfully (buffer: {})", negotiation.used);
+        self.negotiation = Some(negotiation);
         Ok(())
     }
     
     pub fn stop_capture(&mut self) {
-        if let Some(stream) = self.stream.take() {
-            drop(stream);
+        // Dropping the stream stops it
+        if self.stream.take().is_some() {
             info!("Audio capture stopped");
         }
     }
@@ -83,49 +345,71 @@ impl AudioCapture {
     fn build_input_stream<T>(
         &self,
         config: StreamConfig,
-        audio_tx: mpsc::UnboundedSender<Vec<f32>>,
+        audio_tx: mpsc::Sender<AudioChunk>,
     ) -> Result<Stream>
     where
         T: Sample + Send + 'static + SizedSample,
         f32: cpal::FromSample<T>,
     {
         let channels = config.channels as usize;
+        let callback_frames = self.callback_frames.clone();
+        if !self.channel_mode.fits(channels) {
+            warn!("audio.channel_mode {} but the device has {} channel(s), mixing them instead", self.channel_mode, channels);
+        }
+        let mut processor = CallbackProcessor::new(channels, config.sample_rate.0, self.channel_mode);
+        let nominal_rate = config.sample_rate.0;
+        let nominal_output = processor.output_rate();
+        self.output_rate.set(nominal_output);
+        let output_rate = self.output_rate.clone();
+        let mut meter = RateMeter::new(nominal_rate);
+        let mut warned = false;
+        let mut overflowed = false;
+        let error_tx = self.error_tx.clone();
+        let pool = ChunkPool::new();
         
         let stream = self.device.build_input_stream(
             &config,
             move |data: &[T], _: &cpal::InputCallbackInfo| {
-                // Convert samples to f32 and send to processing
-                let samples: Vec<f32> = data.iter().map(|s| cpal::Sample::from_sample(*s)).collect();
-                
-                // Simple stereo to mono - just take left channel  
-                let mono_samples = if channels == 2 {
-                    samples.chunks_exact(2).map(|chunk| chunk[0]).collect()
-                } else {
-                    samples
-                };
-                
-                // Better downsampling with anti-aliasing for 16kHz
-                let final_samples = if config.sample_rate.0 != 16000 {
-                    let ratio = config.sample_rate.0 as usize / 16000; // 44100/16000 = ~2.75, so ratio = 2
-                    if ratio > 1 {
-                        // Average every `ratio` samples to reduce aliasing
-                        mono_samples.chunks(ratio)
-                            .map(|chunk| chunk.iter().sum::<f32>() / chunk.len() as f32)
-                            .collect()
-                    } else {
-                        mono_samples
+                let frames = data.len() / channels.max(1);
+                callback_frames.store(frames, Ordering::Relaxed);
+
+                // Durations follow the device's real clock once it has been measured
+                if let Some(measured) = meter.observe(frames, Instant::now()) {
+                    output_rate.set(nominal_output * measured / nominal_rate as f64);
+                    if !warned && meter.deviation(measured) > MAX_RATE_DEVIATION {
+                        warned = true;
+                        warn!("The input device delivers {:.0} Hz, {:.2}% off its nominal {} Hz; another device rate (16, 32 or 48 kHz) may keep better time",
+                              measured, meter.deviation(measured) * 100.0, nominal_rate);
                     }
-                } else {
-                    mono_samples
-                };
-                
-                // Send to processing pipeline
-                if let Err(_) = audio_tx.send(final_samples) {
-                    error!("Audio receiver dropped, stopping audio capture");
+                }
+
+                // Convert, downmix and resample to exactly 16kHz into a recycled buffer
+                let mut samples = pool.take(processor.max_output(frames));
+                processor.process_into(data, &mut samples);
+
+                // Send to processing pipeline; the buffer comes back when the chunk is dropped.
+                // Never wait here: a full channel loses this chunk rather than stall the device
+                match audio_tx.try_send(pool.chunk(samples)) {
+                    Ok(()) => overflowed = false,
+                    Err(mpsc::error::TrySendError::Full(_)) => {
+                        if !overflowed {
+                            overflowed = true;
+                            warn!("Audio pipeline is {} chunks behind, dropping input until it catches up", AUDIO_CHANNEL_CHUNKS);
+                        }
+                    }
+                    Err(mpsc::error::TrySendError::Closed(_)) => error!("Audio receiver dropped, stopping audio capture"),
                 }
             },
-            |err| {
+            move |err| {
+                // Overruns and other backend hiccups don't stop the stream; only a lost device is worth a reconnect
+                if !is_device_loss(&err) {
+                    warn!("Audio input error: {}", err);
+                    return;
+                }
                 error!("Audio input error: {}", err);
+                if let Some(ref error_tx) = error_tx {
+                    let _ = error_tx.send(err.to_string());
+                }
             },
             None,
         )?;
@@ -138,4 +422,69 @@ impl Drop for AudioCapture {
     fn drop(&mut self) {
         self.stop_capture();
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn a_saved_name_follows_a_renamed_device() {
+        let devices = names(&["Built-in Microphone", "USB Audio Device (2)"]);
+        assert_eq!(match_device_name(&devices, "usb audio device"), Some(1));
+        assert_eq!(match_device_name(&devices, "Microphone"), Some(0));
+    }
+
+    #[test]
+    fn exact_names_win_over_case_and_prefix_matches() {
+        let devices = names(&["usb mic (2)", "USB Mic", "usb mic"]);
+        assert_eq!(match_device_name(&devices, "USB Mic"), Some(1));
+        assert_eq!(match_device_name(&devices, "usb mic"), Some(2));
+        assert_eq!(match_device_name(&devices, "Usb Mic"), Some(1));
+    }
+
+    #[test]
+    fn ambiguous_prefixes_match_nothing() {
+        let devices = names(&["USB Audio Device (1)", "USB Audio Device (2)", "Webcam"]);
+        assert_eq!(match_device_name(&devices, "USB Audio"), None);
+        assert_eq!(match_device_name(&devices, "Device"), None);
+        assert_eq!(match_device_name(&devices, "web"), Some(2));
+    }
+
+    #[test]
+    fn empty_names_match_nothing() {
+        let devices = names(&["", "  ", "USB Audio Device"]);
+        assert_eq!(match_device_name(&devices, ""), None);
+        assert_eq!(match_device_name(&devices, "   "), None);
+        assert_eq!(match_device_name(&devices, "usb"), Some(2));
+        assert_eq!(match_device_name(&names(&["", "Webcam"]), "Microphone"), None);
+    }
+
+    #[test]
+    fn an_index_picks_from_the_listing() {
+        let devices = names(&["Built-in Microphone", "USB Audio Device"]);
+        assert_eq!(match_device_name(&devices, "1"), Some(1));
+        assert_eq!(match_device_name(&devices, " 0 "), Some(0));
+        assert_eq!(match_device_name(&devices, "2"), None);
+    }
+
+    #[test]
+    fn only_a_lost_device_triggers_a_reconnect() {
+        assert!(is_device_loss(&StreamError::DeviceNotAvailable));
+        let overrun = StreamError::BackendSpecific {
+            err: cpal::BackendSpecificError { description: "buffer overrun".to_string() },
+        };
+        assert!(!is_device_loss(&overrun));
+    }
+
+    #[test]
+    fn a_longer_saved_name_does_not_match_its_prefix() {
+        // "USB" must not be picked for a saved "USB Audio Device" that's gone
+        let devices = names(&["Built-in Microphone", "USB"]);
+        assert_eq!(match_device_name(&devices, "USB Audio Device"), None);
+    }
+}
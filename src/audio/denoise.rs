@@ -0,0 +1,263 @@
+//! Spectral-gate noise suppression for a finished 16kHz mono recording, so steady
+//! noise (fans, hum) in pauses doesn't turn into hallucinated words. Kept free of
+//! crate dependencies so benches/audio_callback.rs can include it directly.
+
+use std::f32::consts::PI;
+
+/// 32ms frames at 16kHz, half overlapping; sqrt-Hann in and out adds back up exactly
+const FRAME: usize = 512;
+const HOP: usize = FRAME / 2;
+const BINS: usize = FRAME / 2 + 1;
+/// The quietest tenth of frames is taken as the noise profile
+const NOISE_PERCENTILE: f32 = 0.1;
+/// Subtract a bit more than the measured noise, which leaves fewer tonal leftovers
+const OVER_SUBTRACTION: f32 = 1.5;
+/// Never attenuate a bin by more than this (-20 dB), quiet speech survives
+const GAIN_FLOOR: f32 = 0.1;
+/// Gains fall back by at most this factor per frame, which smooths word endings
+const GAIN_RELEASE: f32 = 0.7;
+
+#[derive(Debug, Clone, Copy)]
+struct Complex {
+    re: f32,
+    im: f32,
+}
+
+impl Complex {
+    fn mul(self, other: Complex) -> Complex {
+        Complex {
+            re: self.re * other.re - self.im * other.im,
+            im: self.re * other.im + self.im * other.re,
+        }
+    }
+
+    fn norm_sqr(self) -> f32 {
+        self.re * self.re + self.im * self.im
+    }
+}
+
+/// In-place radix-2 FFT of FRAME points
+struct Fft {
+    twiddles: Vec<Complex>,
+}
+
+impl Fft {
+    fn new() -> Self {
+        let twiddles = (0..FRAME / 2)
+            .map(|k| {
+                let angle = -2.0 * PI * k as f32 / FRAME as f32;
+                Complex { re: angle.cos(), im: angle.sin() }
+            })
+            .collect();
+        Self { twiddles }
+    }
+
+    fn forward(&self, data: &mut [Complex]) {
+        let bits = FRAME.trailing_zeros();
+        for i in 0..FRAME {
+            let j = i.reverse_bits() >> (usize::BITS - bits);
+            if i < j {
+                data.swap(i, j);
+            }
+        }
+
+        let mut size = 2;
+        while size <= FRAME {
+            let stride = FRAME / size;
+            for start in (0..FRAME).step_by(size) {
+                for k in 0..size / 2 {
+                    let t = data[start + k + size / 2].mul(self.twiddles[k * stride]);
+                    let u = data[start + k];
+                    data[start + k] = Complex { re: u.re + t.re, im: u.im + t.im };
+                    data[start + k + size / 2] = Complex { re: u.re - t.re, im: u.im - t.im };
+                }
+            }
+            size *= 2;
+        }
+    }
+
+    /// Inverse via conjugation, scaled by 1/FRAME
+    fn inverse(&self, data: &mut [Complex]) {
+        for value in data.iter_mut() {
+            value.im = -value.im;
+        }
+        self.forward(data);
+        let scale = 1.0 / FRAME as f32;
+        for value in data.iter_mut() {
+            value.re *= scale;
+            value.im = -value.im * scale;
+        }
+    }
+}
+
+/// Speech-to-noise ratio of the loud frames against the quiet ones, before and after
+#[derive(Debug, Clone, Copy)]
+pub struct NoiseReport {
+    pub snr_before_db: f32,
+    pub snr_after_db: f32,
+}
+
+impl NoiseReport {
+    pub fn improvement_db(&self) -> f32 {
+        self.snr_after_db - self.snr_before_db
+    }
+}
+
+fn ratio_db(signal: f32, noise: f32) -> f32 {
+    10.0 * ((signal + 1e-12) / (noise + 1e-12)).log10()
+}
+
+/// Suppress steady background noise in place. None if the recording is shorter than a frame.
+pub fn suppress(samples: &mut [f32]) -> Option<NoiseReport> {
+    if samples.len() < FRAME {
+        return None;
+    }
+
+    let window: Vec<f32> = (0..FRAME)
+        .map(|n| (0.5 - 0.5 * (2.0 * PI * n as f32 / FRAME as f32).cos()).sqrt())
+        .collect();
+    let fft = Fft::new();
+
+    // Pad so every input sample is covered by two frames
+    let tail = HOP + (HOP - samples.len() % HOP) % HOP;
+    let mut padded = vec![0.0f32; HOP];
+    padded.extend_from_slice(samples);
+    padded.resize(padded.len() + tail, 0.0);
+    let frames = (padded.len() - FRAME) / HOP + 1;
+
+    let mut spectra: Vec<Vec<Complex>> = Vec::with_capacity(frames);
+    for frame in 0..frames {
+        let start = frame * HOP;
+        let mut spectrum: Vec<Complex> = padded[start..start + FRAME]
+            .iter()
+            .zip(&window)
+            .map(|(sample, w)| Complex { re: sample * w, im: 0.0 })
+            .collect();
+        fft.forward(&mut spectrum);
+        spectra.push(spectrum);
+    }
+
+    let energy = |spectrum: &[Complex]| spectrum[..BINS].iter().map(|bin| bin.norm_sqr()).sum::<f32>();
+    let energies: Vec<f32> = spectra.iter().map(|spectrum| energy(spectrum)).collect();
+    let mut order: Vec<usize> = (0..frames).collect();
+    order.sort_by(|a, b| energies[*a].total_cmp(&energies[*b]));
+    let noise_frames = &order[..((frames as f32 * NOISE_PERCENTILE) as usize).max(1)];
+    let loud_frames = &order[frames / 2..];
+
+    let mut noise = vec![0.0f32; BINS];
+    for &frame in noise_frames {
+        for (bin, value) in noise.iter_mut().enumerate() {
+            *value += spectra[frame][bin].norm_sqr().sqrt();
+        }
+    }
+    for value in noise.iter_mut() {
+        *value /= noise_frames.len() as f32;
+    }
+
+    let mean_energy = |frames: &[usize], energies: &[f32]| {
+        frames.iter().map(|&frame| energies[frame]).sum::<f32>() / frames.len() as f32
+    };
+    let snr_before_db = ratio_db(mean_energy(loud_frames, &energies), mean_energy(noise_frames, &energies));
+
+    let mut gains = vec![1.0f32; BINS];
+    for spectrum in spectra.iter_mut() {
+        for bin in 0..BINS {
+            let magnitude = spectrum[bin].norm_sqr().sqrt();
+            let gate = (1.0 - OVER_SUBTRACTION * noise[bin] / (magnitude + 1e-9)).max(GAIN_FLOOR);
+            gains[bin] = gate.max(gains[bin] * GAIN_RELEASE);
+
+            spectrum[bin].re *= gains[bin];
+            spectrum[bin].im *= gains[bin];
+            // Mirror bin, so the frame stays real
+            if bin > 0 && bin < FRAME / 2 {
+                spectrum[FRAME - bin].re *= gains[bin];
+                spectrum[FRAME - bin].im *= gains[bin];
+            }
+        }
+    }
+
+    let energies: Vec<f32> = spectra.iter().map(|spectrum| energy(spectrum)).collect();
+    let snr_after_db = ratio_db(mean_energy(loud_frames, &energies), mean_energy(noise_frames, &energies));
+
+    let mut output = vec![0.0f32; padded.len()];
+    for (frame, spectrum) in spectra.iter_mut().enumerate() {
+        fft.inverse(spectrum);
+        let start = frame * HOP;
+        for (n, value) in spectrum.iter().enumerate() {
+            output[start + n] += value.re * window[n];
+        }
+    }
+    samples.copy_from_slice(&output[HOP..HOP + samples.len()]);
+
+    Some(NoiseReport { snr_before_db, snr_after_db })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 10s at 16kHz: a 300Hz tone every other half second over white noise
+    fn noisy_utterance() -> Vec<f32> {
+        let mut seed = 1u64;
+        (0..160_000)
+            .map(|i| {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                let noise = ((seed >> 33) as f32 / (1u64 << 31) as f32 * 2.0 - 1.0) * 0.03;
+                let t = i as f32 / 16000.0;
+                let tone = if ((t * 2.0) as usize).is_multiple_of(2) { 0.3 * (2.0 * PI * 300.0 * t).sin() } else { 0.0 };
+                tone + noise
+            })
+            .collect()
+    }
+
+    /// Energy of the first difference, dominated by content well above the 300Hz tone
+    fn out_of_band_energy(samples: &[f32]) -> f32 {
+        samples.windows(2).map(|pair| (pair[1] - pair[0]).powi(2)).sum()
+    }
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    #[test]
+    fn removes_steady_noise() {
+        let input = noisy_utterance();
+        let mut output = input.clone();
+        let report = suppress(&mut output).expect("10s is longer than a frame");
+
+        assert_eq!(output.len(), input.len());
+        let (before, after) = (out_of_band_energy(&input), out_of_band_energy(&output));
+        assert!(after < before * 0.5, "left {:.1} of {:.1} out-of-band energy", after, before);
+        assert!(report.improvement_db() > 3.0, "SNR only improved {:+.1} dB", report.improvement_db());
+    }
+
+    #[test]
+    fn keeps_the_tone() {
+        let input = noisy_utterance();
+        let mut output = input.clone();
+        suppress(&mut output);
+
+        // The first half second is tone; it loses little next to the noise it sits on
+        let (before, after) = (rms(&input[1000..7000]), rms(&output[1000..7000]));
+        assert!(after > before * 0.8, "tone fell from {:.3} to {:.3} RMS", before, after);
+    }
+
+    #[test]
+    fn shorter_than_a_frame_is_left_alone() {
+        let mut samples = vec![0.25; FRAME - 1];
+        assert!(suppress(&mut samples).is_none());
+        assert!(samples.iter().all(|&s| s == 0.25));
+    }
+
+    #[test]
+    fn fft_round_trips() {
+        let fft = Fft::new();
+        let input: Vec<f32> = (0..FRAME).map(|n| (n as f32 * 0.37).sin()).collect();
+        let mut data: Vec<Complex> = input.iter().map(|&re| Complex { re, im: 0.0 }).collect();
+        fft.forward(&mut data);
+        fft.inverse(&mut data);
+        for (value, expected) in data.iter().zip(&input) {
+            assert!((value.re - expected).abs() < 1e-4 && value.im.abs() < 1e-4);
+        }
+    }
+}
@@ -0,0 +1,67 @@
+//! Loudness-based voice detection: what `vad.engine = "energy"` runs, and what stands in
+//! for Silero when its model can't be loaded or a frame fails.
+
+use std::collections::VecDeque;
+
+use crate::audio::gain::linear_to_db;
+
+/// Windows averaged per decision, so one loud sample doesn't flip it
+const HISTORY: usize = 3;
+/// Smoothing of the noise floor EMA per unvoiced window
+const FLOOR_ALPHA: f32 = 0.05;
+/// Nothing quieter than this is speech, however quiet the room
+const MIN_SPEECH_DB: f32 = -55.0;
+
+/// Voiced/unvoiced by loudness above the noise floor: the fallback when Silero can't
+/// run. The floor follows the room while nobody talks and holds still while they do,
+/// so a long sentence doesn't become the new floor.
+#[derive(Debug)]
+pub struct EnergyDetector {
+    margin_db: f32,
+    floor_db: Option<f32>,
+    history: VecDeque<f32>,
+}
+
+impl EnergyDetector {
+    /// `threshold` is the Silero speech probability threshold; a stricter one wants
+    /// speech further above the floor
+    pub fn new(threshold: f32) -> Self {
+        Self {
+            margin_db: Self::margin(threshold),
+            floor_db: None,
+            history: VecDeque::with_capacity(HISTORY),
+        }
+    }
+
+    fn margin(threshold: f32) -> f32 {
+        6.0 + 12.0 * threshold.clamp(0.0, 1.0)
+    }
+
+    pub fn set_threshold(&mut self, threshold: f32) {
+        self.margin_db = Self::margin(threshold);
+    }
+
+    pub fn is_voiced(&mut self, window: &[f32]) -> bool {
+        if window.is_empty() {
+            return false;
+        }
+        let mean_square = window.iter().map(|s| s * s).sum::<f32>() / window.len() as f32;
+        if self.history.len() == HISTORY {
+            self.history.pop_front();
+        }
+        self.history.push_back(mean_square);
+        let level_db = linear_to_db((self.history.iter().sum::<f32>() / self.history.len() as f32).sqrt());
+
+        let floor_db = *self.floor_db.get_or_insert(level_db);
+        let voiced = level_db > MIN_SPEECH_DB && level_db > floor_db + self.margin_db;
+        if !voiced {
+            self.floor_db = Some(floor_db + FLOOR_ALPHA * (level_db - floor_db));
+        }
+        voiced
+    }
+
+    /// Forget the recent windows; the floor carries over to the next recording
+    pub fn clear(&mut self) {
+        self.history.clear();
+    }
+}
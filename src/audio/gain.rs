@@ -0,0 +1,197 @@
+//! Gain for quiet microphones: a fixed boost and/or automatic gain towards a
+//! target level, decided over the whole recording and applied before transcription.
+
+use serde::Serialize;
+
+use crate::config::AudioConfig;
+
+/// Auto gain never boosts more than this, so near-silence isn't amplified into noise
+const MAX_AUTO_GAIN_DB: f32 = 30.0;
+/// Peaks are kept at or below this after gain (about -0.2 dBFS)
+const CLIP_CEILING: f32 = 0.98;
+/// Level reported for digital silence
+const SILENCE_DBFS: f32 = -120.0;
+
+pub fn db_to_linear(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+pub fn linear_to_db(linear: f32) -> f32 {
+    if linear <= 0.0 {
+        SILENCE_DBFS
+    } else {
+        (20.0 * linear.log10()).max(SILENCE_DBFS)
+    }
+}
+
+/// Running RMS and peak of the recording so far
+#[derive(Debug, Clone, Default)]
+pub struct GainTracker {
+    sum_squares: f64,
+    samples: usize,
+    peak: f32,
+}
+
+impl GainTracker {
+    pub fn observe(&mut self, chunk: &[f32]) {
+        for &sample in chunk {
+            self.sum_squares += (sample as f64) * (sample as f64);
+            self.peak = self.peak.max(sample.abs());
+        }
+        self.samples += chunk.len();
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn rms_dbfs(&self) -> f32 {
+        if self.samples == 0 {
+            return SILENCE_DBFS;
+        }
+        linear_to_db((self.sum_squares / self.samples as f64).sqrt() as f32)
+    }
+
+    pub fn peak_dbfs(&self) -> f32 {
+        linear_to_db(self.peak)
+    }
+}
+
+/// Gain chosen for one recording, reported to the GUI with the transcribing event
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct GainDecision {
+    pub gain_db: f32,
+    /// Level of the recording before gain
+    pub rms_dbfs: f32,
+    pub peak_dbfs: f32,
+    /// The gain was lowered so the peak wouldn't clip
+    pub limited: bool,
+}
+
+impl GainDecision {
+    /// Apply the gain, clamping anything that would still exceed the ceiling
+    pub fn apply(&self, samples: &mut [f32]) {
+        let linear = db_to_linear(self.gain_db);
+        for sample in samples {
+            *sample = (*sample * linear).clamp(-CLIP_CEILING, CLIP_CEILING);
+        }
+    }
+}
+
+/// audio.gain_db and audio.auto_gain
+#[derive(Debug, Clone)]
+pub struct GainStage {
+    fixed_db: f32,
+    auto: bool,
+    target_dbfs: f32,
+}
+
+impl GainStage {
+    /// None when neither fixed nor automatic gain is configured
+    pub fn from_config(config: &AudioConfig) -> Option<Self> {
+        (config.gain_db != 0.0 || config.auto_gain).then_some(Self {
+            fixed_db: config.gain_db,
+            auto: config.auto_gain,
+            target_dbfs: config.gain_target_dbfs,
+        })
+    }
+
+    pub fn decide(&self, tracker: &GainTracker) -> GainDecision {
+        let rms_dbfs = tracker.rms_dbfs();
+        let peak_dbfs = tracker.peak_dbfs();
+
+        let mut gain_db = self.fixed_db;
+        if self.auto && rms_dbfs > SILENCE_DBFS {
+            gain_db += (self.target_dbfs - (rms_dbfs + self.fixed_db)).min(MAX_AUTO_GAIN_DB);
+        }
+
+        // Clip guard: the loudest sample must stay under the ceiling
+        let headroom_db = linear_to_db(CLIP_CEILING) - peak_dbfs;
+        let limited = gain_db > headroom_db;
+        if limited {
+            gain_db = headroom_db;
+        }
+
+        GainDecision { gain_db, rms_dbfs, peak_dbfs, limited }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(peak_dbfs: f32, seconds: f32) -> Vec<f32> {
+        let amplitude = db_to_linear(peak_dbfs);
+        (0..(16000.0 * seconds) as usize)
+            .map(|i| amplitude * (2.0 * std::f32::consts::PI * 440.0 * i as f32 / 16000.0).sin())
+            .collect()
+    }
+
+    fn rms_dbfs(samples: &[f32]) -> f32 {
+        let mut tracker = GainTracker::default();
+        tracker.observe(samples);
+        tracker.rms_dbfs()
+    }
+
+    fn auto(target_dbfs: f32) -> GainStage {
+        GainStage { fixed_db: 0.0, auto: true, target_dbfs }
+    }
+
+    #[test]
+    fn a_quiet_sine_is_boosted_to_the_target() {
+        let mut samples = sine(-30.0, 1.0);
+        let mut tracker = GainTracker::default();
+        // Fed in callback-sized chunks, as the audio task does
+        for chunk in samples.chunks(160) {
+            tracker.observe(chunk);
+        }
+
+        let decision = auto(-20.0).decide(&tracker);
+        decision.apply(&mut samples);
+
+        // A sine's RMS is 3 dB under its peak
+        assert!((decision.rms_dbfs + 33.0).abs() < 0.1, "measured {} dBFS", decision.rms_dbfs);
+        assert!(!decision.limited);
+        assert!((rms_dbfs(&samples) + 20.0).abs() < 0.1, "boosted to {} dBFS", rms_dbfs(&samples));
+        assert!(samples.iter().all(|sample| sample.abs() < CLIP_CEILING));
+    }
+
+    #[test]
+    fn the_clip_guard_limits_gain_to_the_peak() {
+        // Quiet overall, with one loud click
+        let mut samples = sine(-30.0, 1.0);
+        samples[8000] = 0.5;
+        let mut tracker = GainTracker::default();
+        tracker.observe(&samples);
+
+        let decision = auto(-10.0).decide(&tracker);
+        decision.apply(&mut samples);
+
+        assert!(decision.limited);
+        let peak = samples.iter().fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+        assert!(peak <= CLIP_CEILING && peak > CLIP_CEILING - 0.01, "peak {}", peak);
+    }
+
+    #[test]
+    fn auto_gain_is_capped_and_ignores_silence() {
+        let mut tracker = GainTracker::default();
+        tracker.observe(&sine(-80.0, 0.5));
+        assert_eq!(auto(-20.0).decide(&tracker).gain_db, MAX_AUTO_GAIN_DB);
+
+        let mut silent = GainTracker::default();
+        silent.observe(&[0.0; 1600]);
+        assert_eq!(auto(-20.0).decide(&silent).gain_db, 0.0);
+    }
+
+    #[test]
+    fn fixed_gain_adds_to_auto_gain() {
+        let mut tracker = GainTracker::default();
+        tracker.observe(&sine(-30.0, 0.5));
+        let stage = GainStage { fixed_db: 6.0, auto: false, target_dbfs: -20.0 };
+        assert_eq!(stage.decide(&tracker).gain_db, 6.0);
+
+        // Auto gain makes up the rest to the target
+        let stage = GainStage { fixed_db: 6.0, auto: true, target_dbfs: -20.0 };
+        assert!((stage.decide(&tracker).gain_db - 13.0).abs() < 0.1);
+    }
+}
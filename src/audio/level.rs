@@ -0,0 +1,139 @@
+//! Live input level for the bubble's meter: RMS and peak over each interval
+//! while recording, throttled by time so every device buffer size looks the same.
+
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+use super::gain::linear_to_db;
+
+/// At most one level event per this much time
+pub const LEVEL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct AudioLevel {
+    pub rms_db: f32,
+    pub peak_db: f32,
+    /// Since the recording started
+    pub elapsed_ms: u64,
+}
+
+#[derive(Debug)]
+pub struct LevelMeter {
+    interval: Duration,
+    started: Instant,
+    last_emit: Option<Instant>,
+    sum_squares: f64,
+    samples: usize,
+    peak: f32,
+}
+
+impl LevelMeter {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            started: Instant::now(),
+            last_emit: None,
+            sum_squares: 0.0,
+            samples: 0,
+            peak: 0.0,
+        }
+    }
+
+    /// A new recording starts at `now`; nothing carries over from the last one
+    pub fn start(&mut self, now: Instant) {
+        *self = Self::new(self.interval);
+        self.started = now;
+    }
+
+    /// Add a chunk received at `now`. Returns the level over everything since the last
+    /// report once `interval` has passed; the first chunk of a recording reports right away.
+    pub fn observe(&mut self, chunk: &[f32], now: Instant) -> Option<AudioLevel> {
+        for &sample in chunk {
+            self.sum_squares += (sample as f64) * (sample as f64);
+            self.peak = self.peak.max(sample.abs());
+        }
+        self.samples += chunk.len();
+
+        if self.samples == 0 || self.last_emit.is_some_and(|last| now.duration_since(last) < self.interval) {
+            return None;
+        }
+
+        let level = AudioLevel {
+            rms_db: linear_to_db((self.sum_squares / self.samples as f64).sqrt() as f32),
+            peak_db: linear_to_db(self.peak),
+            elapsed_ms: now.duration_since(self.started).as_millis() as u64,
+        };
+        self.last_emit = Some(now);
+        self.sum_squares = 0.0;
+        self.samples = 0;
+        self.peak = 0.0;
+        Some(level)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 10ms of a constant level
+    fn chunk(level: f32) -> Vec<f32> {
+        vec![level; 160]
+    }
+
+    #[test]
+    fn levels_are_in_dbfs() {
+        assert_eq!(linear_to_db(1.0), 0.0);
+        assert!((linear_to_db(0.5) + 6.02).abs() < 0.01);
+        assert!((linear_to_db(0.1) + 20.0).abs() < 0.01);
+        assert_eq!(linear_to_db(0.0), -120.0);
+        assert_eq!(linear_to_db(1e-9), -120.0);
+    }
+
+    #[test]
+    fn reports_at_most_once_per_interval_whatever_the_chunk_size() {
+        let start = Instant::now();
+        for chunk_ms in [5u64, 10, 21, 43] {
+            let mut meter = LevelMeter::new(LEVEL_INTERVAL);
+            meter.start(start);
+            let reported: Vec<u64> = (0..1000 / chunk_ms)
+                .map(|i| i * chunk_ms)
+                .filter(|&at| meter.observe(&chunk(0.1), start + Duration::from_millis(at)).is_some())
+                .collect();
+            // Never closer than the interval, and late by less than one chunk
+            for gap in reported.windows(2).map(|pair| pair[1] - pair[0]) {
+                assert!((100..100 + chunk_ms).contains(&gap), "{}ms chunks reported {}ms apart", chunk_ms, gap);
+            }
+            assert_eq!(reported[0], 0);
+        }
+    }
+
+    #[test]
+    fn a_report_covers_everything_since_the_last() {
+        let start = Instant::now();
+        let mut meter = LevelMeter::new(LEVEL_INTERVAL);
+        meter.start(start);
+        assert!(meter.observe(&chunk(0.1), start).is_some());
+
+        assert!(meter.observe(&[0.5, -0.5], start + Duration::from_millis(50)).is_none());
+        let level = meter.observe(&[0.0, 0.0], start + Duration::from_millis(100)).unwrap();
+        assert!((level.peak_db + 6.02).abs() < 0.01);
+        // Half the samples at 0.5: RMS is 0.5 / sqrt(2)
+        assert!((level.rms_db + 9.03).abs() < 0.01, "{}", level.rms_db);
+        assert_eq!(level.elapsed_ms, 100);
+    }
+
+    #[test]
+    fn a_new_recording_starts_from_scratch() {
+        let start = Instant::now();
+        let mut meter = LevelMeter::new(LEVEL_INTERVAL);
+        meter.start(start);
+        meter.observe(&chunk(0.9), start);
+        meter.observe(&chunk(0.9), start + Duration::from_millis(50));
+
+        let restart = start + Duration::from_millis(60);
+        meter.start(restart);
+        let level = meter.observe(&chunk(0.1), restart).expect("first chunk reports right away");
+        assert_eq!(level.elapsed_ms, 0);
+        assert!((level.peak_db + 20.0).abs() < 0.01);
+    }
+}
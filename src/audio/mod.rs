@@ -1,5 +1,20 @@
 pub mod capture;
+pub mod denoise;
+pub mod energy;
+pub mod gain;
+pub mod level;
+pub mod noise;
+pub mod preroll;
+pub mod process;
+pub mod reconnect;
 pub mod vad;
+pub mod wav;
 
-pub use capture::AudioCapture;
-pub use vad::{VoiceActivityDetector, VadResult};
\ No newline at end of file
+pub use capture::{AudioCapture, AUDIO_CHANNEL_CHUNKS};
+pub use gain::{GainDecision, GainStage, GainTracker};
+pub use level::{LevelMeter, LEVEL_INTERVAL};
+pub use noise::NoiseAdapter;
+pub use preroll::PreRoll;
+pub use process::{AudioChunk, ChannelMode, TARGET_RATE};
+pub use reconnect::Reconnect;
+pub use vad::{SpeechEdge, SpeechEdges, VadEngine, VoiceActivityDetector, VadResult};
\ No newline at end of file
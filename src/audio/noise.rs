@@ -0,0 +1,202 @@
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+use crate::config::VadSensitivity;
+
+/// Smoothing of the noise floor EMA per frame
+const FLOOR_ALPHA: f32 = 0.05;
+/// Frames this far above the floor are probably someone talking, not ambient noise...
+const TRANSIENT_FACTOR: f32 = 4.0;
+/// ...unless they keep coming this long, in which case the room got louder (a fan, traffic)
+const MAX_TRANSIENT: Duration = Duration::from_secs(2);
+/// Floor above which the VAD gets stricter, and below which it relaxes again.
+/// The gap between the two is the hysteresis band.
+const NOISY_FLOOR_RMS: f32 = 0.02;
+const QUIET_FLOOR_RMS: f32 = 0.006;
+/// The floor must stay past a threshold this long before a step
+const HOLD: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Trend {
+    Noisy,
+    Quiet,
+}
+
+/// Tracks the ambient noise floor between recordings and steps VAD sensitivity to match
+#[derive(Debug)]
+pub struct NoiseAdapter {
+    floor: Option<f32>,
+    level: VadSensitivity,
+    min: VadSensitivity,
+    max: VadSensitivity,
+    trend: Option<(Trend, Instant)>,
+    /// When the current run of frames above the transient gate began
+    loud_since: Option<Instant>,
+}
+
+impl NoiseAdapter {
+    pub fn new(level: VadSensitivity, min: VadSensitivity, max: VadSensitivity) -> Self {
+        Self {
+            floor: None,
+            level: level.clamp(min, max),
+            min,
+            max,
+            trend: None,
+            loud_since: None,
+        }
+    }
+
+    /// Current noise floor estimate (frame RMS)
+    pub fn floor(&self) -> Option<f32> {
+        self.floor
+    }
+
+    /// Feed an idle (non-recording) frame; returns the new level when it steps
+    pub fn observe(&mut self, frame: &[f32], now: Instant) -> Option<VadSensitivity> {
+        if frame.is_empty() {
+            return None;
+        }
+        let rms = (frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32).sqrt();
+
+        // Short bursts (a word, a door) stay out of the floor; a level that persists is the new ambient
+        let loud = matches!(self.floor, Some(floor) if rms > floor * TRANSIENT_FACTOR && rms > QUIET_FLOOR_RMS);
+        if !loud {
+            self.loud_since = None;
+        } else if now.duration_since(*self.loud_since.get_or_insert(now)) < MAX_TRANSIENT {
+            return None;
+        }
+
+        let floor = match self.floor {
+            Some(floor) => floor + FLOOR_ALPHA * (rms - floor),
+            None => rms,
+        };
+        self.floor = Some(floor);
+
+        let trend = if floor > NOISY_FLOOR_RMS {
+            Some(Trend::Noisy)
+        } else if floor < QUIET_FLOOR_RMS {
+            Some(Trend::Quiet)
+        } else {
+            None
+        };
+
+        let since = match (trend, self.trend) {
+            (None, _) => {
+                self.trend = None;
+                return None;
+            }
+            (Some(trend), Some((previous, since))) if trend == previous => since,
+            (Some(trend), _) => {
+                self.trend = Some((trend, now));
+                return None;
+            }
+        };
+        if now.duration_since(since) < HOLD {
+            return None;
+        }
+
+        // Noisier rooms need a stricter (less sensitive, higher threshold) VAD
+        let next = match trend {
+            Some(Trend::Noisy) => self.level.stricter(),
+            _ => self.level.looser(),
+        }
+        .clamp(self.min, self.max);
+
+        // Restart the hold so consecutive steps are spaced out
+        self.trend = trend.map(|trend| (trend, now));
+        if next == self.level {
+            return None;
+        }
+
+        debug!("Noise floor {:.4}: VAD sensitivity {:?} -> {:?}", floor, self.level, next);
+        self.level = next;
+        Some(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sensitivity_lowers_the_threshold() {
+        assert_eq!(VadSensitivity::Normal.to_threshold(), 0.5);
+        let levels = [VadSensitivity::Low, VadSensitivity::Normal, VadSensitivity::High, VadSensitivity::VeryHigh];
+        for pair in levels.windows(2) {
+            assert!(pair[0] < pair[1]);
+            assert!(pair[0].to_threshold() > pair[1].to_threshold());
+            assert_eq!(pair[1].stricter(), pair[0]);
+            assert_eq!(pair[0].looser(), pair[1]);
+        }
+    }
+
+    #[test]
+    fn noisy_room_steps_towards_low() {
+        let mut adapter = NoiseAdapter::new(VadSensitivity::Normal, VadSensitivity::Low, VadSensitivity::VeryHigh);
+        let noisy = vec![0.05; 512];
+        let start = Instant::now();
+        assert_eq!(adapter.observe(&noisy, start), None);
+        assert_eq!(adapter.observe(&noisy, start + HOLD), Some(VadSensitivity::Low));
+        // Already at the least sensitive end
+        assert_eq!(adapter.observe(&noisy, start + HOLD * 2), None);
+    }
+
+    #[test]
+    fn short_spikes_stay_out_of_the_floor() {
+        let mut adapter = NoiseAdapter::new(VadSensitivity::Normal, VadSensitivity::Low, VadSensitivity::VeryHigh);
+        let start = Instant::now();
+        adapter.observe(&[0.005; 512], start);
+        let floor = adapter.floor();
+        for i in 0..10 {
+            adapter.observe(&[0.2; 512], start + Duration::from_millis(100 * i));
+        }
+        assert_eq!(adapter.floor(), floor);
+    }
+
+    #[test]
+    fn a_lasting_rise_becomes_the_new_floor() {
+        let mut adapter = NoiseAdapter::new(VadSensitivity::Normal, VadSensitivity::Low, VadSensitivity::VeryHigh);
+        let start = Instant::now();
+        adapter.observe(&[0.004; 512], start);
+
+        // A fan turns on: 20 frames a second at 0.05, well past the transient gate
+        let mut stepped = None;
+        for i in 1..=400 {
+            if let Some(level) = adapter.observe(&[0.05; 512], start + Duration::from_millis(50 * i)) {
+                stepped = Some(level);
+                break;
+            }
+        }
+        assert_eq!(stepped, Some(VadSensitivity::Low));
+        assert!(adapter.floor().unwrap() > NOISY_FLOOR_RMS);
+    }
+
+    #[test]
+    fn alternating_rooms_follow_the_floor() {
+        let mut adapter = NoiseAdapter::new(VadSensitivity::Normal, VadSensitivity::Low, VadSensitivity::VeryHigh);
+        let mut now = Instant::now();
+        let mut levels = Vec::new();
+        for rms in [0.05, 0.001, 0.05] {
+            for _ in 0..600 {
+                now += Duration::from_millis(50);
+                if let Some(level) = adapter.observe(&[rms; 512], now) {
+                    levels.push(level);
+                }
+            }
+        }
+        assert_eq!(levels.first(), Some(&VadSensitivity::Low));
+        assert!(levels.contains(&VadSensitivity::VeryHigh));
+        assert_eq!(levels.last(), Some(&VadSensitivity::Low));
+    }
+
+    #[test]
+    fn quiet_room_steps_towards_very_high() {
+        let mut adapter = NoiseAdapter::new(VadSensitivity::Normal, VadSensitivity::Low, VadSensitivity::High);
+        let quiet = vec![0.001; 512];
+        let start = Instant::now();
+        adapter.observe(&quiet, start);
+        assert_eq!(adapter.observe(&quiet, start + HOLD), Some(VadSensitivity::High));
+        // Clamped to adaptive_max
+        assert_eq!(adapter.observe(&quiet, start + HOLD * 2), None);
+    }
+}
@@ -0,0 +1,99 @@
+//! The last audio.preroll_ms of audio from before a recording starts, so a word
+//! spoken just before the hotkey press isn't clipped.
+
+use std::collections::VecDeque;
+
+#[derive(Debug)]
+pub struct PreRoll {
+    capacity: usize,
+    samples: VecDeque<f32>,
+}
+
+impl PreRoll {
+    /// Keeps at most `capacity` samples; 0 keeps nothing
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            samples: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Add audio heard while idle, forgetting the oldest beyond the capacity
+    pub fn push(&mut self, chunk: &[f32]) {
+        if self.capacity == 0 {
+            return;
+        }
+        let chunk = &chunk[chunk.len().saturating_sub(self.capacity)..];
+        let overflow = (self.samples.len() + chunk.len()).saturating_sub(self.capacity);
+        self.samples.drain(..overflow);
+        self.samples.extend(chunk);
+    }
+
+    /// Everything held, oldest first, leaving the pre-roll empty
+    pub fn take(&mut self) -> Vec<f32> {
+        self.samples.drain(..).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 10ms of 16kHz audio whose samples all carry `value`
+    fn chunk(value: f32) -> Vec<f32> {
+        vec![value; 160]
+    }
+
+    /// Idle chunks go to the pre-roll, recorded ones to the buffer, and the
+    /// pre-roll is prepended when recording starts, as the audio task does
+    fn record(preroll_ms: usize, chunks: &[f32], start_at: usize) -> (Vec<f32>, PreRoll) {
+        let mut preroll = PreRoll::new(preroll_ms * 16);
+        let mut buffer = Vec::new();
+        for (i, &value) in chunks.iter().enumerate() {
+            if i < start_at {
+                preroll.push(&chunk(value));
+                continue;
+            }
+            if buffer.is_empty() {
+                buffer.extend(preroll.take());
+            }
+            buffer.extend(chunk(value));
+        }
+        (buffer, preroll)
+    }
+
+    #[test]
+    fn speech_just_before_the_press_is_kept() {
+        // A second of silence (0.0), then speech (1.0) starting 300ms before the press at 1.3s
+        let chunks: Vec<f32> = (0..200).map(|i| if i < 100 { 0.0 } else { 1.0 }).collect();
+        let (buffer, mut preroll) = record(500, &chunks, 130);
+
+        // 500ms of pre-roll, the first 200ms of it still silence
+        assert_eq!(buffer.len(), (500 + 700) * 16);
+        assert!(buffer[..200 * 16].iter().all(|&s| s == 0.0));
+        assert!(buffer[200 * 16..].iter().all(|&s| s == 1.0));
+        // Cleared after use
+        assert!(preroll.take().is_empty());
+    }
+
+    #[test]
+    fn zero_keeps_nothing_from_before_the_press() {
+        let chunks = vec![1.0; 50];
+        let (buffer, _) = record(0, &chunks, 30);
+        assert_eq!(buffer.len(), 20 * 160);
+    }
+
+    #[test]
+    fn stays_within_its_capacity() {
+        let mut preroll = PreRoll::new(100);
+        for i in 0..10 {
+            preroll.push(&chunk(i as f32));
+        }
+        assert_eq!(preroll.take(), vec![9.0; 100]);
+
+        // A chunk larger than the capacity keeps its end
+        let long: Vec<f32> = (0..300).map(|i| i as f32).collect();
+        preroll.push(&long);
+        assert_eq!(preroll.take(), (200..300).map(|i| i as f32).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,585 @@
+//! Per-callback sample processing, kept free of crate dependencies so
+//! benches/audio_callback.rs can include it directly.
+
+use cpal::{FromSample, Sample};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+use std::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Rate the speech pipeline expects
+pub const TARGET_RATE: u32 = 16000;
+
+/// Kernel half-width in zero crossings of the low-pass (input samples when upsampling)
+const HALF_TAPS: usize = 16;
+/// Kernel table entries per input sample, linearly interpolated between
+const KERNEL_RESOLUTION: usize = 128;
+/// Low-pass a little below the output Nyquist so the transition band doesn't alias
+const CUTOFF_MARGIN: f64 = 0.95;
+/// Devices whose measured rate is further than this from their nominal one are worth a warning (0.5%)
+pub const MAX_RATE_DEVIATION: f64 = 0.005;
+/// How long a device's frames are counted before its measured rate is trusted
+const RATE_WINDOW: Duration = Duration::from_secs(10);
+/// Spare chunk buffers kept for reuse; about a second of 10ms callbacks in flight
+const POOLED_CHUNKS: usize = 128;
+
+/// The rate a device really delivers, from the frames it sends against the clock.
+/// A device clock running off its nominal rate makes every recording a little
+/// fast or slow, however exact the resampling is.
+#[derive(Debug, Clone)]
+pub struct RateMeter {
+    nominal: u32,
+    /// The first callback; its frames were captured before it, so they aren't counted
+    started: Option<Instant>,
+    frames: u64,
+}
+
+impl RateMeter {
+    pub fn new(nominal: u32) -> Self {
+        Self { nominal, started: None, frames: 0 }
+    }
+
+    /// Count a callback's frames; the measured rate once RATE_WINDOW has passed
+    pub fn observe(&mut self, frames: usize, now: Instant) -> Option<f64> {
+        let Some(started) = self.started else {
+            self.started = Some(now);
+            return None;
+        };
+        self.frames += frames as u64;
+        let elapsed = now.duration_since(started);
+        (elapsed >= RATE_WINDOW).then(|| self.frames as f64 / elapsed.as_secs_f64())
+    }
+
+    /// Relative distance of a measured rate from the nominal one
+    pub fn deviation(&self, measured: f64) -> f64 {
+        (measured - self.nominal as f64).abs() / self.nominal as f64
+    }
+}
+
+/// Streaming windowed-sinc resampler for any rate ratio. Input history is carried
+/// across calls, so callback boundaries don't click.
+#[derive(Debug, Clone)]
+pub struct Resampler {
+    input_rate: u32,
+    /// Input samples per output sample
+    step: f64,
+    /// Kernel half-width in input samples; wider when downsampling, as the cutoff drops
+    half_width: usize,
+    /// |x| -> kernel weight, sampled every 1/KERNEL_RESOLUTION input samples
+    kernel: Vec<f32>,
+    /// Unconsumed input, starting `half_width` samples before `position`
+    history: Vec<f32>,
+    /// Time of the next output sample, in input samples from the start of `history`
+    position: f64,
+}
+
+impl Resampler {
+    pub fn new(input_rate: u32, output_rate: u32) -> Self {
+        let step = input_rate as f64 / output_rate as f64;
+        // Cutoff as a fraction of the input Nyquist: only downsampling needs filtering
+        let cutoff = (1.0 / step).min(1.0) * CUTOFF_MARGIN;
+        let half_width = (HALF_TAPS as f64 * step.max(1.0)).ceil() as usize;
+
+        let kernel = (0..=half_width * KERNEL_RESOLUTION)
+            .map(|i| {
+                let x = i as f64 / KERNEL_RESOLUTION as f64;
+                let sinc = if x == 0.0 { 1.0 } else { (PI * cutoff * x).sin() / (PI * cutoff * x) };
+                // Hann window over the kernel's width
+                let window = 0.5 * (1.0 + (PI * x / half_width as f64).cos());
+                (cutoff * sinc * window) as f32
+            })
+            .collect();
+
+        Self {
+            input_rate,
+            step,
+            half_width,
+            kernel,
+            // Leading silence so the first output sample has a full window behind it
+            history: vec![0.0; half_width],
+            position: half_width as f64,
+        }
+    }
+
+    fn weight(&self, distance: f64) -> f32 {
+        let index = distance * KERNEL_RESOLUTION as f64;
+        let low = index as usize;
+        if low + 1 >= self.kernel.len() {
+            return 0.0;
+        }
+        let frac = (index - low as f64) as f32;
+        self.kernel[low] + (self.kernel[low + 1] - self.kernel[low]) * frac
+    }
+
+    /// Resample `input`, appending to `out`. Output lags input by `half_width` samples.
+    pub fn process_into(&mut self, input: impl Iterator<Item = f32>, out: &mut Vec<f32>) {
+        self.history.extend(input);
+
+        while self.position as usize + self.half_width < self.history.len() {
+            let center = self.position as usize;
+            let frac = self.position - center as f64;
+
+            let mut sum = 0.0f32;
+            let mut weights = 0.0f32;
+            for n in center + 1 - self.half_width..=center + self.half_width {
+                let weight = self.weight((n as f64 - center as f64 - frac).abs());
+                sum += self.history[n] * weight;
+                weights += weight;
+            }
+            // Normalizing keeps unity gain whatever the fractional phase
+            out.push(if weights != 0.0 { sum / weights } else { 0.0 });
+            self.position += self.step;
+        }
+
+        // Drop input no future output sample can reach; capacity is kept, so no reallocation
+        let consumed = (self.position as usize).saturating_sub(self.half_width);
+        self.history.drain(..consumed);
+        self.position -= consumed as f64;
+    }
+
+    /// Output samples per second actually produced: the input rate over the step taken
+    pub fn output_rate(&self) -> f64 {
+        self.input_rate as f64 / self.step
+    }
+
+    /// Upper bound on the output for `frames` more input frames
+    pub fn max_output(&self, frames: usize) -> usize {
+        ((self.history.len() + frames) as f64 / self.step).ceil() as usize + 1
+    }
+}
+
+/// How interleaved channels become the mono signal (audio.channel_mode), written
+/// in config the way `FromStr` reads it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum ChannelMode {
+    /// Average of all channels
+    #[default]
+    Mix,
+    /// A single channel, 0-based
+    Channel(usize),
+}
+
+impl ChannelMode {
+    /// Whether a device with this many channels has the selected one
+    pub fn fits(self, channels: usize) -> bool {
+        match self {
+            ChannelMode::Mix => true,
+            ChannelMode::Channel(index) => index < channels,
+        }
+    }
+}
+
+impl FromStr for ChannelMode {
+    type Err = String;
+
+    /// "mix", "left", "right" or "channel:<n>" with n counted from 1, as on interface labels
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        match s.as_str() {
+            "mix" => Ok(ChannelMode::Mix),
+            "left" => Ok(ChannelMode::Channel(0)),
+            "right" => Ok(ChannelMode::Channel(1)),
+            _ => match s.strip_prefix("channel:").map(|n| n.trim().parse::<usize>()) {
+                Some(Ok(n)) if n >= 1 => Ok(ChannelMode::Channel(n - 1)),
+                _ => Err(format!("'{}' is not mix, left, right or channel:<n> (n from 1)", s)),
+            },
+        }
+    }
+}
+
+impl TryFrom<String> for ChannelMode {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<ChannelMode> for String {
+    fn from(mode: ChannelMode) -> Self {
+        mode.to_string()
+    }
+}
+
+impl fmt::Display for ChannelMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChannelMode::Mix => write!(f, "mix"),
+            ChannelMode::Channel(index) => write!(f, "channel:{}", index + 1),
+        }
+    }
+}
+
+/// Turns one interleaved device callback into 16kHz mono
+#[derive(Debug, Clone)]
+pub struct CallbackProcessor {
+    channels: usize,
+    mode: ChannelMode,
+    /// None when the device already runs at 16kHz
+    resampler: Option<Resampler>,
+}
+
+impl CallbackProcessor {
+    /// A `mode` naming a channel the device doesn't have falls back to mixing
+    pub fn new(channels: usize, sample_rate: u32, mode: ChannelMode) -> Self {
+        let channels = channels.max(1);
+        if !mode.fits(channels) {
+            warn!("⚠️  audio.channel_mode = {} but the device has {} channel(s), mixing instead", mode, channels);
+        }
+        Self {
+            channels,
+            mode: if mode.fits(channels) { mode } else { ChannelMode::Mix },
+            resampler: (sample_rate != TARGET_RATE).then(|| Resampler::new(sample_rate, TARGET_RATE)),
+        }
+    }
+
+    /// Rate of what `process` returns, assuming the device runs at its nominal rate
+    pub fn output_rate(&self) -> f64 {
+        match self.resampler {
+            Some(ref resampler) => resampler.output_rate(),
+            None => TARGET_RATE as f64,
+        }
+    }
+
+    /// Upper bound on the output for a callback of `frames` frames
+    pub fn max_output(&self, frames: usize) -> usize {
+        match self.resampler {
+            Some(ref resampler) => resampler.max_output(frames),
+            None => frames,
+        }
+    }
+
+    /// Convert, downmix and resample into a new Vec
+    pub fn process<T>(&mut self, data: &[T]) -> Vec<f32>
+    where
+        T: Sample,
+        f32: FromSample<T>,
+    {
+        let mut out = Vec::with_capacity(self.max_output(data.len() / self.channels));
+        self.process_into(data, &mut out);
+        out
+    }
+
+    /// Convert, downmix and resample, appending to `out`. Doesn't allocate once the
+    /// resampler's history has grown to the callback size, given `out` has room for
+    /// `max_output` samples.
+    pub fn process_into<T>(&mut self, data: &[T], out: &mut Vec<f32>)
+    where
+        T: Sample,
+        f32: FromSample<T>,
+    {
+        let channels = self.channels;
+        match self.mode {
+            ChannelMode::Mix if channels > 1 => {
+                let scale = 1.0 / channels as f32;
+                let mixed = data
+                    .chunks_exact(channels)
+                    .map(|frame| frame.iter().map(|sample| f32::from_sample(*sample)).sum::<f32>() * scale);
+                Self::resample(&mut self.resampler, mixed, out)
+            }
+            ChannelMode::Mix => Self::resample(&mut self.resampler, data.iter().map(|sample| f32::from_sample(*sample)), out),
+            ChannelMode::Channel(index) => {
+                let selected = data.iter().skip(index).step_by(channels).map(|sample| f32::from_sample(*sample));
+                Self::resample(&mut self.resampler, selected, out)
+            }
+        }
+    }
+
+    fn resample(resampler: &mut Option<Resampler>, mono: impl Iterator<Item = f32>, out: &mut Vec<f32>) {
+        match resampler {
+            Some(resampler) => resampler.process_into(mono, out),
+            // 16kHz passthrough: straight conversion, no resampler state
+            None => out.extend(mono),
+        }
+    }
+}
+
+/// Buffers for the chunks the capture callback sends. A dropped chunk hands its
+/// buffer back, so steady-state capture reuses the same few buffers.
+#[derive(Debug, Clone)]
+pub struct ChunkPool {
+    free: Arc<Mutex<Vec<Vec<f32>>>>,
+}
+
+impl Default for ChunkPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ChunkPool {
+    pub fn new() -> Self {
+        Self { free: Arc::new(Mutex::new(Vec::with_capacity(POOLED_CHUNKS))) }
+    }
+
+    /// An empty buffer with room for `capacity` samples. Never waits on the lock: if the
+    /// pipeline is returning a buffer right now, a fresh one is allocated instead.
+    pub fn take(&self, capacity: usize) -> Vec<f32> {
+        let mut buffer = self.free.try_lock().ok().and_then(|mut free| free.pop()).unwrap_or_default();
+        buffer.reserve(capacity);
+        buffer
+    }
+
+    /// Wrap `samples` (from `take`) to go back to this pool when dropped
+    pub fn chunk(&self, samples: Vec<f32>) -> AudioChunk {
+        AudioChunk { samples, pool: Some(self.clone()) }
+    }
+
+    fn give_back(&self, mut buffer: Vec<f32>) {
+        buffer.clear();
+        if let Ok(mut free) = self.free.try_lock() {
+            if free.len() < POOLED_CHUNKS {
+                free.push(buffer);
+            }
+        }
+    }
+}
+
+/// One callback's worth of 16kHz mono samples
+#[derive(Debug)]
+pub struct AudioChunk {
+    samples: Vec<f32>,
+    pool: Option<ChunkPool>,
+}
+
+impl From<Vec<f32>> for AudioChunk {
+    /// A chunk that isn't pooled, e.g. synthetic audio
+    fn from(samples: Vec<f32>) -> Self {
+        Self { samples, pool: None }
+    }
+}
+
+impl Deref for AudioChunk {
+    type Target = [f32];
+
+    fn deref(&self) -> &[f32] {
+        &self.samples
+    }
+}
+
+impl Drop for AudioChunk {
+    fn drop(&mut self) {
+        if let Some(pool) = self.pool.take() {
+            pool.give_back(std::mem::take(&mut self.samples));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `seconds` of 10ms callbacks from a device really running at `actual` Hz
+    fn measure(meter: &mut RateMeter, actual: f64, seconds: u64) -> Option<f64> {
+        let start = Instant::now();
+        let frames = (actual / 100.0).round() as usize;
+        let mut measured = None;
+        for callback in 0..=seconds * 100 {
+            let at = start + Duration::from_secs_f64(callback as f64 * frames as f64 / actual);
+            measured = meter.observe(frames, at);
+        }
+        measured
+    }
+
+    #[test]
+    fn rate_meter_waits_for_its_window() {
+        assert_eq!(measure(&mut RateMeter::new(44100), 44100.0, 9), None);
+        assert!(measure(&mut RateMeter::new(44100), 44100.0, 10).is_some());
+    }
+
+    #[test]
+    fn rate_meter_finds_a_fast_device_clock() {
+        let mut meter = RateMeter::new(44100);
+        let measured = measure(&mut meter, 44_100.0 * 1.01, 20).unwrap();
+        assert!((measured - 44_541.0).abs() < 1.0, "measured {}", measured);
+        assert!(meter.deviation(measured) > MAX_RATE_DEVIATION);
+
+        let mut meter = RateMeter::new(48000);
+        let measured = measure(&mut meter, 48_000.0, 20).unwrap();
+        assert!(meter.deviation(measured) < 1e-6, "measured {}", measured);
+    }
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+
+    thread_local! {
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    /// Counts allocations per thread, so tests running in parallel don't disturb each other
+    struct CountingAllocator;
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static GLOBAL: CountingAllocator = CountingAllocator;
+
+    fn allocations() -> usize {
+        ALLOCATIONS.with(Cell::get)
+    }
+
+    fn stereo_f32(frames: usize) -> Vec<f32> {
+        (0..frames * 2).map(|i| ((i as f32) * 0.01).sin() * 0.5).collect()
+    }
+
+    /// What the capture callback does with each buffer the device hands it
+    fn callbacks<T>(processor: &mut CallbackProcessor, pool: &ChunkPool, data: &[T], count: usize) -> usize
+    where
+        T: Sample,
+        f32: FromSample<T>,
+    {
+        let frames = data.len() / 2;
+        let mut produced = 0;
+        for _ in 0..count {
+            let mut samples = pool.take(processor.max_output(frames));
+            processor.process_into(data, &mut samples);
+            let chunk = pool.chunk(samples);
+            produced += chunk.len();
+            // The pipeline is done with it
+            drop(chunk);
+        }
+        produced
+    }
+
+    #[test]
+    fn steady_state_callbacks_do_not_allocate() {
+        let f32_input = stereo_f32(480);
+        let i16_input: Vec<i16> = f32_input.iter().map(|s| (s * i16::MAX as f32) as i16).collect();
+
+        for rate in [16000, 44100, 48000] {
+            for mode in [ChannelMode::Mix, ChannelMode::Channel(1)] {
+                let pool = ChunkPool::new();
+                let mut processor = CallbackProcessor::new(2, rate, mode);
+                // The first callbacks size the resampler history and fill the pool
+                callbacks(&mut processor, &pool, &f32_input, 4);
+
+                let before = allocations();
+                let produced = callbacks(&mut processor, &pool, &f32_input, 1000)
+                    + callbacks(&mut processor, &pool, &i16_input, 1000);
+                assert_eq!(allocations() - before, 0, "{} Hz, {}", rate, mode);
+                assert!(produced > 0);
+            }
+        }
+    }
+
+    #[test]
+    fn chunks_in_flight_get_their_own_buffers() {
+        let pool = ChunkPool::new();
+        let mut processor = CallbackProcessor::new(2, 48000, ChannelMode::Mix);
+        let input = stereo_f32(480);
+
+        let held: Vec<AudioChunk> = (0..3)
+            .map(|_| {
+                let mut samples = pool.take(processor.max_output(480));
+                processor.process_into(input.as_slice(), &mut samples);
+                pool.chunk(samples)
+            })
+            .collect();
+        assert!(held.iter().all(|chunk| !chunk.is_empty()));
+        assert_eq!(pool.free.lock().unwrap().len(), 0);
+
+        drop(held);
+        assert_eq!(pool.free.lock().unwrap().len(), 3);
+        assert!(pool.free.lock().unwrap().iter().all(Vec::is_empty));
+    }
+
+    fn sine(rate: u32, frequency: f32, seconds: f32) -> Vec<f32> {
+        (0..(rate as f32 * seconds) as usize)
+            .map(|i| (2.0 * std::f32::consts::PI * frequency * i as f32 / rate as f32).sin() * 0.5)
+            .collect()
+    }
+
+    /// Frequency from upward zero crossings, skipping the resampler's warm-up
+    fn frequency(samples: &[f32], rate: u32) -> f32 {
+        let settled = &samples[rate as usize / 10..];
+        let crossings = settled.windows(2).filter(|pair| pair[0] < 0.0 && pair[1] >= 0.0).count();
+        crossings as f32 * rate as f32 / settled.len() as f32
+    }
+
+    /// Mono input in 10ms callbacks, as a device would deliver it
+    fn resample_in_callbacks(rate: u32, input: &[f32]) -> Vec<f32> {
+        let mut processor = CallbackProcessor::new(1, rate, ChannelMode::Mix);
+        let mut out = Vec::new();
+        for callback in input.chunks(rate as usize / 100) {
+            processor.process_into(callback, &mut out);
+        }
+        out
+    }
+
+    #[test]
+    fn sine_keeps_its_frequency_and_duration() {
+        for rate in [22050, 44100, 48000, 96000] {
+            let out = resample_in_callbacks(rate, &sine(rate, 1000.0, 2.0));
+
+            // Two seconds at 16kHz, less the output lag of the filter window
+            assert!((31_900..=32_000).contains(&out.len()), "{} Hz gave {} samples", rate, out.len());
+            let measured = frequency(&out, TARGET_RATE);
+            assert!((measured - 1000.0).abs() < 2.0, "{} Hz came out at {:.1} Hz", rate, measured);
+        }
+    }
+
+    #[test]
+    fn a_minute_at_44k_lasts_a_minute() {
+        let mut processor = CallbackProcessor::new(1, 44100, ChannelMode::Mix);
+        let input = sine(44100, 440.0, 60.0);
+        let produced: usize = input.chunks(441).map(|callback| processor.process(callback).len()).sum();
+
+        let duration = Duration::from_secs_f64(produced as f64 / processor.output_rate());
+        let error = duration.abs_diff(Duration::from_secs(60));
+        assert!(error <= Duration::from_millis(10), "60s of input came out as {:?}", duration);
+    }
+
+    #[test]
+    fn callback_boundaries_dont_change_the_output() {
+        let input = sine(44100, 440.0, 1.0);
+        let mut whole = CallbackProcessor::new(1, 44100, ChannelMode::Mix);
+
+        let at_once = whole.process(input.as_slice());
+        let in_callbacks = resample_in_callbacks(44100, &input);
+
+        assert_eq!(at_once.len(), in_callbacks.len());
+        let worst = at_once.iter().zip(&in_callbacks).map(|(a, b)| (a - b).abs()).fold(0.0, f32::max);
+        assert!(worst < 1e-5, "chunked output differs by up to {}", worst);
+    }
+
+    #[test]
+    fn target_rate_passes_through_unchanged() {
+        let input = sine(TARGET_RATE, 1000.0, 0.1);
+        let mut processor = CallbackProcessor::new(1, TARGET_RATE, ChannelMode::Mix);
+        assert_eq!(processor.output_rate(), TARGET_RATE as f64);
+        assert_eq!(processor.process(input.as_slice()), input);
+    }
+
+    #[test]
+    fn stays_quiet_above_the_output_nyquist() {
+        // 12kHz can't exist at 16kHz; the low-pass must remove it rather than fold it to 4kHz
+        let out = resample_in_callbacks(48000, &sine(48000, 12000.0, 1.0));
+        let settled = &out[1600..];
+        let rms = (settled.iter().map(|s| s * s).sum::<f32>() / settled.len() as f32).sqrt();
+        assert!(rms < 0.01, "aliased energy left at RMS {}", rms);
+    }
+
+    #[test]
+    fn process_matches_process_into() {
+        let input = stereo_f32(1024);
+        let mut by_vec = CallbackProcessor::new(2, 44100, ChannelMode::Mix);
+        let mut by_buffer = by_vec.clone();
+
+        let mut out = Vec::new();
+        by_buffer.process_into(input.as_slice(), &mut out);
+        assert_eq!(by_vec.process(input.as_slice()), out);
+    }
+}
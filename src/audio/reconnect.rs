@@ -0,0 +1,43 @@
+use std::time::Duration;
+
+/// Delays between attempts to reopen a lost input device; the last one repeats
+const BACKOFF: &[Duration] = &[Duration::from_secs(1), Duration::from_secs(2), Duration::from_secs(5)];
+
+/// Tracks reconnection after the input device disappears
+#[derive(Debug, Default)]
+pub struct Reconnect {
+    /// Attempts made since the device was lost; None while the device is fine
+    attempts: Option<usize>,
+}
+
+impl Reconnect {
+    pub fn is_active(&self) -> bool {
+        self.attempts.is_some()
+    }
+
+    /// The device was lost; returns false if we already knew
+    pub fn lost(&mut self) -> bool {
+        if self.attempts.is_some() {
+            return false;
+        }
+        self.attempts = Some(0);
+        true
+    }
+
+    /// Delay before the next attempt
+    pub fn next_delay(&self) -> Duration {
+        let attempt = self.attempts.unwrap_or(0);
+        BACKOFF[attempt.min(BACKOFF.len() - 1)]
+    }
+
+    pub fn failed(&mut self) {
+        if let Some(ref mut attempts) = self.attempts {
+            *attempts += 1;
+        }
+    }
+
+    /// Attempts it took, resetting for the next loss
+    pub fn succeeded(&mut self) -> usize {
+        self.attempts.take().unwrap_or(0) + 1
+    }
+}
@@ -1,70 +1,405 @@
+//! Speech/silence decisions for auto-stop, continuous segments and the speech ratio.
+//! Silero classifies each frame; when its model is missing or a frame fails, the energy
+//! detector decides instead, and `fallback_reason` says why.
+
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
 use std::path::Path;
 use std::time::{Duration, Instant};
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 use sherpa_rs::silero_vad::{SileroVad, SileroVadConfig};
 
+use crate::audio::energy::EnergyDetector;
+use crate::audio::process::{Resampler, TARGET_RATE};
+
+/// What decides whether a window is voiced (vad.engine)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VadEngine {
+    /// Silero, falling back to energy if it can't load or run
+    #[default]
+    Silero,
+    /// Loudness above the noise floor only; no model needed
+    Energy,
+}
+
+/// What the VAD heard over one recording, in frames (vad.frame_ms) of (debounced) speech
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct VadStats {
+    pub frames: usize,
+    pub voiced_frames: usize,
+    pub longest_voiced_run: usize,
+    #[serde(skip)]
+    voiced_run: usize,
+}
+
+impl VadStats {
+    fn push(&mut self, voiced: bool) {
+        self.frames += 1;
+        if voiced {
+            self.voiced_frames += 1;
+            self.voiced_run += 1;
+            self.longest_voiced_run = self.longest_voiced_run.max(self.voiced_run);
+        } else {
+            self.voiced_run = 0;
+        }
+    }
+
+    /// Share of frames that were speech; 0 when nothing was processed
+    pub fn speech_ratio(&self) -> f32 {
+        if self.frames == 0 {
+            0.0
+        } else {
+            self.voiced_frames as f32 / self.frames as f32
+        }
+    }
+
+    /// Too little speech to be worth transcribing (vad.min_speech_ratio; 0 = never).
+    /// Without frames the VAD didn't run, which says nothing either way.
+    pub fn is_dead_air(&self, min_speech_ratio: f32) -> bool {
+        min_speech_ratio > 0.0 && self.frames > 0 && self.speech_ratio() < min_speech_ratio
+    }
+}
+
+/// Frame lengths each engine can work with. Silero's model takes 512, 1024 or 1536 samples
+/// at 16kHz (half that at 8kHz), 32/64/96ms either way; the energy detector takes any,
+/// and shorter frames end speech sooner.
+const SILERO_FRAME_MS: [u32; 3] = [32, 64, 96];
+const ENERGY_FRAME_MS: [u32; 4] = [10, 20, 30, 32];
+
+/// Samples per VAD frame, or why `frame_ms` doesn't work with `engine` at `sample_rate`
+pub fn frame_size(engine: VadEngine, sample_rate: u32, frame_ms: u32) -> Result<usize> {
+    let supported: &[u32] = match engine {
+        VadEngine::Silero => &SILERO_FRAME_MS,
+        VadEngine::Energy => &ENERGY_FRAME_MS,
+    };
+    if !supported.contains(&frame_ms) {
+        return Err(anyhow::anyhow!(
+            "vad.frame_ms = {} isn't supported by the {} engine (use one of {:?})",
+            frame_ms,
+            format!("{:?}", engine).to_lowercase(),
+            supported
+        ));
+    }
+    if engine == VadEngine::Silero && sample_rate != 8000 && sample_rate != 16000 {
+        return Err(anyhow::anyhow!("The Silero VAD runs at 8 or 16kHz, not {}Hz", sample_rate));
+    }
+    let samples = sample_rate as u64 * frame_ms as u64;
+    if !samples.is_multiple_of(1000) {
+        return Err(anyhow::anyhow!(
+            "vad.frame_ms = {} isn't a whole number of samples at {}Hz",
+            frame_ms,
+            sample_rate
+        ));
+    }
+    Ok((samples / 1000) as usize)
+}
+
+/// Rates the VAD resamples to when it can't frame the input rate, nearest first
+const FALLBACK_RATES: [u32; 2] = [16000, 8000];
+
+/// The rate the VAD frames at: `sample_rate` if the engine can take it, else the
+/// nearest rate it can. Only an unsupported `frame_ms` is an error.
+pub fn vad_rate(engine: VadEngine, sample_rate: u32, frame_ms: u32) -> Result<u32> {
+    let direct = frame_size(engine, sample_rate, frame_ms);
+    if direct.is_ok() {
+        return Ok(sample_rate);
+    }
+    let mut rates = FALLBACK_RATES;
+    rates.sort_by_key(|rate| rate.abs_diff(sample_rate));
+    rates
+        .into_iter()
+        .find(|&rate| frame_size(engine, rate, frame_ms).is_ok())
+        .ok_or_else(|| direct.unwrap_err())
+}
+
+/// A Silero model file that could load: present, a file, and not empty
+fn check_model(path: &Path) -> Result<()> {
+    let metadata = std::fs::metadata(path).map_err(|_| {
+        anyhow::anyhow!("VAD model not found: {}. Run scripts/download-parakeet.sh to download", path.display())
+    })?;
+    if !metadata.is_file() || metadata.len() == 0 {
+        return Err(anyhow::anyhow!("VAD model {} isn't a model file", path.display()));
+    }
+    Ok(())
+}
+
+/// Per-window voiced/unvoiced decision from a model
+trait FrameClassifier: Send {
+    fn is_voiced(&mut self, window: &[f32]) -> Result<bool>;
+    fn clear(&mut self);
+    #[allow(dead_code)]
+    fn flush(&mut self) {}
+}
+
+impl FrameClassifier for SileroVad {
+    fn is_voiced(&mut self, window: &[f32]) -> Result<bool> {
+        self.accept_waveform(window.to_vec());
+        Ok(self.is_speech())
+    }
+
+    fn clear(&mut self) {
+        SileroVad::clear(self);
+    }
+
+    fn flush(&mut self) {
+        SileroVad::flush(self);
+    }
+}
+
+/// The configured engine, with the energy detector standing in for any window it
+/// fails on. The energy detector sees every window either way, so its noise floor is
+/// current when it's needed.
+struct Detector {
+    engine: Option<Box<dyn FrameClassifier>>,
+    energy: EnergyDetector,
+    failing: bool,
+}
+
+impl Detector {
+    fn is_voiced(&mut self, window: &[f32]) -> bool {
+        let energy = self.energy.is_voiced(window);
+        let Some(engine) = self.engine.as_mut() else {
+            return energy;
+        };
+        match engine.is_voiced(window) {
+            Ok(voiced) => {
+                if self.failing {
+                    info!("🎙️ VAD recovered, leaving energy fallback");
+                    self.failing = false;
+                }
+                voiced
+            }
+            Err(e) => {
+                if !self.failing {
+                    warn!("⚠️ VAD failed, falling back to energy detection: {}", e);
+                    self.failing = true;
+                }
+                energy
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        if let Some(engine) = self.engine.as_mut() {
+            engine.clear();
+        }
+        self.energy.clear();
+    }
+}
+
+/// Turns per-window voiced flags into speech that lasts: a run of `min_speech` voiced
+/// windows starts it (so a click isn't speech), and it holds for `hangover` windows
+/// after the last voiced one (so a short gap between words doesn't end it)
+#[derive(Debug, Clone)]
+struct SpeechDebounce {
+    min_speech: usize,
+    hangover: usize,
+    voiced_run: usize,
+    since_voiced: usize,
+    in_speech: bool,
+}
+
+impl SpeechDebounce {
+    fn new(min_speech: usize, hangover: usize) -> Self {
+        Self {
+            min_speech: min_speech.max(1),
+            hangover,
+            voiced_run: 0,
+            since_voiced: 0,
+            in_speech: false,
+        }
+    }
+
+    /// Whether this window counts as speech
+    fn push(&mut self, voiced: bool) -> bool {
+        if voiced {
+            self.voiced_run += 1;
+            self.since_voiced = 0;
+            if self.voiced_run >= self.min_speech {
+                self.in_speech = true;
+            }
+        } else {
+            self.voiced_run = 0;
+            self.since_voiced += 1;
+            if self.since_voiced > self.hangover {
+                self.in_speech = false;
+            }
+        }
+        self.in_speech
+    }
+
+    fn reset(&mut self) {
+        self.voiced_run = 0;
+        self.since_voiced = 0;
+        self.in_speech = false;
+    }
+}
+
+/// Enough of a detector's setup to rebuild its model with another threshold
+pub struct RebuildSpec {
+    /// None for the energy engine, which has nothing to rebuild
+    model_path: Option<String>,
+    window_size: usize,
+}
+
+impl RebuildSpec {
+    /// Load the model with `threshold`; slow, so keep it off the audio path and out of locks
+    pub fn prepare(&self, threshold: f32) -> Result<PreparedThreshold> {
+        let engine = match self.model_path {
+            Some(ref model_path) => Some(Box::new(VoiceActivityDetector::build(model_path, self.window_size, threshold)?) as Box<dyn FrameClassifier>),
+            None => None,
+        };
+        Ok(PreparedThreshold { threshold, engine })
+    }
+}
+
+/// A new threshold with its model already built
+pub struct PreparedThreshold {
+    threshold: f32,
+    engine: Option<Box<dyn FrameClassifier>>,
+}
+
 pub struct VoiceActivityDetector {
-    vad: SileroVad,
+    detector: Detector,
+    model_path: String,
     window_size: usize,
     sample_rate: u32,
+    /// Brings `sample_rate` input to `vad_rate` when the engine can't frame it as is
+    resampler: Option<Resampler>,
+    vad_rate: u32,
     silence_timeout: Duration,
     last_speech_time: Option<Instant>,
     speech_detected: bool,
     pending_samples: Vec<f32>,
+    debounce: SpeechDebounce,
+    stats: VadStats,
+    /// Why Silero was asked for but the energy detector is running instead
+    fallback: Option<String>,
 }
 
 impl VoiceActivityDetector {
+    #[allow(clippy::too_many_arguments)]
     pub fn new<P: AsRef<Path>>(
         model_path: P,
         sample_rate: u32,
-        _sensitivity: i32,  // Silero doesn't use this the same way
+        threshold: f32,
         silence_timeout_ms: u32,
+        min_speech_ms: u32,
+        hangover_ms: u32,
+        engine: VadEngine,
+        frame_ms: u32,
     ) -> Result<Self> {
         let model_path_str = model_path.as_ref().to_string_lossy().to_string();
 
-        if !model_path.as_ref().exists() {
-            return Err(anyhow::anyhow!(
-                "VAD model not found: {}. Run scripts/download-parakeet.sh to download.",
-                model_path_str
-            ));
+        let rate = vad_rate(engine, sample_rate, frame_ms)?;
+        let window_size = frame_size(engine, rate, frame_ms)?;
+        if rate != sample_rate {
+            info!("VAD resampling {}Hz input to {}Hz", sample_rate, rate);
         }
 
-        let window_size: usize = 512;  // Standard window size for Silero VAD
-
-        let config = SileroVadConfig {
-            model: model_path_str,
-            window_size: window_size as i32,
-            threshold: 0.5,  // Speech detection threshold
-            min_silence_duration: 0.25,  // 250ms minimum silence
-            min_speech_duration: 0.1,   // 100ms minimum speech
-            ..Default::default()
+        // Silero loads whatever it's given without complaint, so the file is checked first
+        let (silero, fallback) = match engine {
+            VadEngine::Energy => (None, None),
+            VadEngine::Silero => match check_model(model_path.as_ref()).and_then(|()| Self::build(&model_path_str, window_size, threshold)) {
+                Ok(vad) => (Some(Box::new(vad) as Box<dyn FrameClassifier>), None),
+                Err(e) => {
+                    warn!("⚠️ {}, using energy detection", e);
+                    (None, Some(e.to_string()))
+                }
+            },
         };
 
-        // The second parameter is max_speech_duration in seconds
-        let vad = SileroVad::new(config, 30.0)
-            .map_err(|e| anyhow::anyhow!("Failed to initialize Silero VAD: {}", e))?;
+        // Both in whole windows, rounding the minimum up so it's never shorter than asked
+        let window_ms = frame_ms as f32;
+        let min_speech = (min_speech_ms as f32 / window_ms).ceil() as usize;
+        let hangover = (hangover_ms as f32 / window_ms).round() as usize;
 
         info!(
-            "Silero VAD initialized: {}Hz, window_size: {}, silence_timeout: {}ms",
-            sample_rate, window_size, silence_timeout_ms
+            "{} VAD initialized: {}Hz, window_size: {}, threshold: {}, silence_timeout: {}ms, min_speech: {}ms, hangover: {}ms",
+            if silero.is_some() { "Silero" } else { "Energy" },
+            rate, window_size, threshold, silence_timeout_ms, min_speech_ms, hangover_ms
         );
 
         Ok(Self {
-            vad,
+            detector: Detector {
+                engine: silero,
+                energy: EnergyDetector::new(threshold),
+                failing: false,
+            },
+            model_path: model_path_str,
             window_size,
             sample_rate,
+            resampler: (rate != sample_rate).then(|| Resampler::new(sample_rate, rate)),
+            vad_rate: rate,
             silence_timeout: Duration::from_millis(silence_timeout_ms as u64),
             last_speech_time: None,
             speech_detected: false,
             pending_samples: Vec::new(),
+            debounce: SpeechDebounce::new(min_speech, hangover),
+            stats: VadStats::default(),
+            fallback,
         })
     }
 
+    fn build(model_path: &str, window_size: usize, threshold: f32) -> Result<SileroVad> {
+        let config = SileroVadConfig {
+            model: model_path.to_string(),
+            window_size: window_size as i32,
+            threshold,  // Speech detection threshold
+            min_silence_duration: 0.25,  // 250ms minimum silence
+            min_speech_duration: 0.1,   // 100ms minimum speech
+            ..Default::default()
+        };
+
+        // The second parameter is max_speech_duration in seconds
+        SileroVad::new(config, 30.0)
+            .map_err(|e| anyhow::anyhow!("Failed to initialize Silero VAD: {}", e))
+    }
+
+    /// Change the speech threshold; the detector is rebuilt, so only call this between recordings
+    pub fn set_threshold(&mut self, threshold: f32) -> Result<()> {
+        let prepared = self.rebuild_spec().prepare(threshold)?;
+        self.apply_threshold(prepared);
+        Ok(())
+    }
+
+    /// What a threshold change needs to rebuild the model, so it can be built without
+    /// holding this detector (see `apply_threshold`)
+    pub fn rebuild_spec(&self) -> RebuildSpec {
+        RebuildSpec {
+            model_path: self.detector.engine.is_some().then(|| self.model_path.clone()),
+            window_size: self.window_size,
+        }
+    }
+
+    /// Swap in a detector built by `RebuildSpec::prepare`
+    pub fn apply_threshold(&mut self, prepared: PreparedThreshold) {
+        if let Some(engine) = prepared.engine {
+            self.detector.engine = Some(engine);
+        }
+        self.detector.energy.set_threshold(prepared.threshold);
+        self.reset();
+        info!("VAD threshold set to {}", prepared.threshold);
+    }
+
+    pub fn set_silence_timeout(&mut self, silence_timeout_ms: u32) {
+        self.silence_timeout = Duration::from_millis(silence_timeout_ms as u64);
+        info!("VAD silence timeout set to {}ms", silence_timeout_ms);
+    }
+
     /// Process audio samples and return VAD result
     pub fn process_audio(&mut self, samples: &[f32]) -> VadResult {
-        // Accumulate samples
-        self.pending_samples.extend_from_slice(samples);
+        self.process_audio_at(samples, Instant::now())
+    }
+
+    /// `process_audio` with the chunk arriving at `now`
+    fn process_audio_at(&mut self, samples: &[f32], now: Instant) -> VadResult {
+        // Accumulate samples, at the rate the VAD frames at
+        match self.resampler {
+            Some(ref mut resampler) => resampler.process_into(samples.iter().copied(), &mut self.pending_samples),
+            None => self.pending_samples.extend_from_slice(samples),
+        }
 
         let mut has_speech_in_frame = false;
 
@@ -72,13 +407,13 @@ impl VoiceActivityDetector {
         while self.pending_samples.len() >= self.window_size {
             let window: Vec<f32> = self.pending_samples.drain(..self.window_size).collect();
 
-            // Feed to Silero VAD
-            self.vad.accept_waveform(window);
-
-            // Check if speech detected
-            if self.vad.is_speech() {
+            // Check if speech detected, debounced
+            let voiced = self.detector.is_voiced(&window);
+            let speech = self.debounce.push(voiced);
+            self.stats.push(speech);
+            if speech {
                 has_speech_in_frame = true;
-                self.last_speech_time = Some(Instant::now());
+                self.last_speech_time = Some(now);
 
                 if !self.speech_detected {
                     debug!("Speech detected");
@@ -88,7 +423,6 @@ impl VoiceActivityDetector {
         }
 
         // Determine current state based on timeout
-        let now = Instant::now();
         let silence_duration = self.last_speech_time
             .map(|t| now.duration_since(t))
             .unwrap_or(Duration::MAX);
@@ -106,21 +440,51 @@ impl VoiceActivityDetector {
 
     /// Reset VAD state for new recording session
     pub fn reset(&mut self) {
-        self.vad.clear();
+        self.detector.clear();
         self.pending_samples.clear();
+        if let Some(ref mut resampler) = self.resampler {
+            *resampler = Resampler::new(self.sample_rate, self.vad_rate);
+        }
         self.last_speech_time = None;
         self.speech_detected = false;
+        self.debounce.reset();
+        self.stats = VadStats::default();
         debug!("VAD state reset");
     }
 
+    /// Why the energy detector stands in for the Silero model that was configured, if it does
+    pub fn fallback_reason(&self) -> Option<&str> {
+        self.fallback.as_deref()
+    }
+
+    /// Whether the last complete window was (debounced) speech. Unlike the per-chunk result,
+    /// this holds across chunks that complete no window.
+    pub fn is_speaking(&self) -> bool {
+        self.debounce.in_speech
+    }
+
     /// Check if speech is currently active
+    #[allow(dead_code)]
     pub fn is_speech_active(&self) -> bool {
         self.speech_detected
     }
 
+    /// Statistics since the last reset or take, starting them over
+    pub fn take_stats(&mut self) -> VadStats {
+        std::mem::take(&mut self.stats)
+    }
+
+    /// Time since speech was last heard, if any has been
+    pub fn since_last_speech(&self) -> Option<Duration> {
+        self.last_speech_time.map(|t| t.elapsed())
+    }
+
     /// Flush any remaining samples and finalize
+    #[allow(dead_code)]
     pub fn flush(&mut self) {
-        self.vad.flush();
+        if let Some(engine) = self.detector.engine.as_mut() {
+            engine.flush();
+        }
     }
 }
 
@@ -133,3 +497,487 @@ pub enum VadResult {
     /// Transition from speech to silence - timeout reached, recording should stop
     SilenceDetected,
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpeechEdge {
+    Started,
+    Ended,
+}
+
+impl SpeechEdge {
+    /// GUI event name
+    pub fn event(self) -> &'static str {
+        match self {
+            SpeechEdge::Started => "speech_started",
+            SpeechEdge::Ended => "speech_ended",
+        }
+    }
+}
+
+/// Turns a recording's debounced speaking state, sampled after each chunk, into speech
+/// start/end transitions, so listeners get one event per change instead of one per chunk
+#[derive(Debug, Default)]
+pub struct SpeechEdges {
+    speaking: bool,
+    /// 16kHz samples seen since the recording started
+    samples: usize,
+}
+
+impl SpeechEdges {
+    /// The transition this chunk makes, if any, with the chunk's offset into the recording in ms
+    pub fn observe(&mut self, speaking: bool, chunk_samples: usize) -> Option<(SpeechEdge, u64)> {
+        let offset_ms = (self.samples * 1000 / TARGET_RATE as usize) as u64;
+        self.samples += chunk_samples;
+
+        if speaking == self.speaking {
+            return None;
+        }
+        self.speaking = speaking;
+        Some((if speaking { SpeechEdge::Started } else { SpeechEdge::Ended }, offset_ms))
+    }
+
+    /// Start over for the next recording
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoint::{RecordingState, StopReason};
+
+    /// 32ms windows at 16kHz
+    const CHUNK: usize = 512;
+    const CHUNK_TIME: Duration = Duration::from_millis(32);
+
+    /// Energy engine, so no model is needed: 500ms timeout, 64ms to start speech, 96ms hangover
+    fn detector() -> VoiceActivityDetector {
+        detector_with(64, 96)
+    }
+
+    fn detector_with(min_speech_ms: u32, hangover_ms: u32) -> VoiceActivityDetector {
+        VoiceActivityDetector::new("", 16000, 0.5, 500, min_speech_ms, hangover_ms, VadEngine::Energy, 32).unwrap()
+    }
+
+    #[derive(Clone, Copy)]
+    enum Sound {
+        Quiet,
+        Speech,
+    }
+
+    /// Feeds a recording chunk by chunk on a simulated clock, reacting to the results as
+    /// the audio task does with vad.auto_stop on
+    struct Session {
+        vad: VoiceActivityDetector,
+        state: RecordingState,
+        now: Instant,
+        seed: u32,
+        phase: f32,
+        /// Ms into the recording of each stop that won the latch
+        stops: Vec<(StopReason, u64)>,
+        elapsed_ms: u64,
+    }
+
+    impl Session {
+        fn start() -> Self {
+            let mut state = RecordingState::default();
+            state.start();
+            Self { vad: detector(), state, now: Instant::now(), seed: 1, phase: 0.0, stops: Vec::new(), elapsed_ms: 0 }
+        }
+
+        fn chunk(&mut self, sound: Sound) -> Vec<f32> {
+            (0..CHUNK)
+                .map(|_| {
+                    self.seed = self.seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                    let noise = (self.seed >> 8) as f32 / (1 << 24) as f32 * 0.002 - 0.001;
+                    match sound {
+                        Sound::Quiet => noise,
+                        Sound::Speech => {
+                            self.phase += 2.0 * std::f32::consts::PI * 220.0 / 16000.0;
+                            noise + 0.1 * self.phase.sin()
+                        }
+                    }
+                })
+                .collect()
+        }
+
+        fn play(&mut self, sound: Sound, ms: u64) {
+            for _ in 0..ms / CHUNK_TIME.as_millis() as u64 {
+                let chunk = self.chunk(sound);
+                let result = self.vad.process_audio_at(&chunk, self.now);
+                self.now += CHUNK_TIME;
+                self.elapsed_ms += CHUNK_TIME.as_millis() as u64;
+                if !self.state.is_recording {
+                    continue;
+                }
+                match result {
+                    VadResult::SpeechDetected => self.state.speech_detected = true,
+                    VadResult::SilenceDetected => self.stop(StopReason::VadTimeout),
+                    VadResult::Silence => {}
+                }
+            }
+        }
+
+        fn stop(&mut self, reason: StopReason) {
+            let allowed = reason != StopReason::VadTimeout || self.state.speech_detected;
+            if allowed && self.state.request_stop(reason, self.now) {
+                self.stops.push((reason, self.elapsed_ms));
+            }
+        }
+    }
+
+    #[test]
+    fn silence_after_speech_stops_once_the_timeout_passes() {
+        let mut session = Session::start();
+        session.play(Sound::Quiet, 640);
+        session.play(Sound::Speech, 960);
+        let speech_ended = session.elapsed_ms;
+        session.play(Sound::Quiet, 2000);
+
+        assert_eq!(session.stops.len(), 1, "{:?}", session.stops);
+        let (reason, at) = session.stops[0];
+        assert_eq!(reason, StopReason::VadTimeout);
+        // After the timeout, and no later than the hangover and the energy history add to it
+        let after = at - speech_ended;
+        assert!((500..=500 + 96 + 3 * 32 + 32).contains(&after), "stopped {}ms after speech", after);
+    }
+
+    #[test]
+    fn silence_alone_never_stops() {
+        let mut session = Session::start();
+        session.play(Sound::Quiet, 3000);
+        assert!(session.stops.is_empty());
+        assert!(session.state.is_recording);
+    }
+
+    #[test]
+    fn a_pause_shorter_than_the_timeout_keeps_recording() {
+        let mut session = Session::start();
+        session.play(Sound::Quiet, 640);
+        for _ in 0..3 {
+            session.play(Sound::Speech, 640);
+            session.play(Sound::Quiet, 320);
+        }
+        assert!(session.stops.is_empty(), "{:?}", session.stops);
+        session.play(Sound::Quiet, 1000);
+        assert_eq!(session.stops.iter().map(|(reason, _)| *reason).collect::<Vec<_>>(), vec![StopReason::VadTimeout]);
+    }
+
+    #[test]
+    fn a_click_isnt_speech() {
+        // Two windows to start speech, three of hangover
+        let mut debounce = SpeechDebounce::new(2, 3);
+        let click = [false, true, false, false];
+        assert!(click.iter().all(|&voiced| !debounce.push(voiced)));
+
+        let word = [true, true, false, false, false, false];
+        let speech: Vec<bool> = word.iter().map(|&voiced| debounce.push(voiced)).collect();
+        assert_eq!(speech, [false, true, true, true, true, false]);
+    }
+
+    #[test]
+    fn each_utterance_ends_on_its_own() {
+        // Continuous mode keeps recording after SilenceDetected, so the VAD has to re-arm
+        let mut vad = detector();
+        let mut session = Session::start();
+        let mut ends = Vec::new();
+        let mut play = |session: &mut Session, sound: Sound, ms: u64| {
+            for _ in 0..ms / 32 {
+                let chunk = session.chunk(sound);
+                if vad.process_audio_at(&chunk, session.now) == VadResult::SilenceDetected {
+                    ends.push(session.elapsed_ms);
+                }
+                session.now += CHUNK_TIME;
+                session.elapsed_ms += 32;
+            }
+        };
+        play(&mut session, Sound::Quiet, 640);
+        for _ in 0..3 {
+            play(&mut session, Sound::Speech, 960);
+            play(&mut session, Sound::Quiet, 1280);
+        }
+        assert_eq!(ends.len(), 3, "{:?}", ends);
+    }
+
+    #[test]
+    fn without_debouncing_every_window_counts() {
+        // The defaults, vad.min_speech_ms = 0 and vad.hangover_ms = 0
+        let mut debounce = detector_with(0, 0).debounce;
+        let voiced = [false, true, false, true, true, false, false, true];
+        let speech: Vec<bool> = voiced.iter().map(|&voiced| debounce.push(voiced)).collect();
+        assert_eq!(speech, voiced);
+    }
+
+    #[test]
+    fn debounce_settings_round_to_whole_windows() {
+        let debounce = detector_with(40, 80).debounce;
+        // 40ms needs two 32ms windows; 80ms is nearest to three
+        assert_eq!((debounce.min_speech, debounce.hangover), (2, 3));
+    }
+
+    fn silero(model_path: &Path) -> VoiceActivityDetector {
+        VoiceActivityDetector::new(model_path, 16000, 0.5, 500, 0, 0, VadEngine::Silero, 32).unwrap()
+    }
+
+    #[test]
+    fn a_missing_model_falls_back_and_says_so() {
+        let missing = std::env::temp_dir().join(format!("tomchat-vad-{}-missing.onnx", std::process::id()));
+        let mut vad = silero(&missing);
+        let reason = vad.fallback_reason().unwrap();
+        assert!(reason.contains("not found"), "{}", reason);
+        assert!(vad.rebuild_spec().model_path.is_none());
+
+        // The energy detector does the work instead
+        let mut session = Session::start();
+        let quiet = session.chunk(Sound::Quiet);
+        let speech = session.chunk(Sound::Speech);
+        for _ in 0..20 {
+            vad.process_audio(&quiet);
+        }
+        assert_eq!(vad.process_audio(&speech), VadResult::SpeechDetected);
+    }
+
+    #[test]
+    fn an_empty_model_file_falls_back() {
+        let empty = std::env::temp_dir().join(format!("tomchat-vad-{}-empty.onnx", std::process::id()));
+        std::fs::write(&empty, b"").unwrap();
+        let vad = silero(&empty);
+        std::fs::remove_file(&empty).unwrap();
+        assert!(vad.fallback_reason().unwrap().contains("isn't a model file"));
+
+        // A directory where the file should be
+        assert!(silero(&std::env::temp_dir()).fallback_reason().unwrap().contains("isn't a model file"));
+    }
+
+    #[test]
+    fn the_energy_engine_isnt_a_fallback() {
+        assert_eq!(detector().fallback_reason(), None);
+    }
+
+    /// A model that fails while `failing` is set, and hears nothing otherwise
+    struct Flaky {
+        failing: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    }
+
+    impl FrameClassifier for Flaky {
+        fn is_voiced(&mut self, _window: &[f32]) -> Result<bool> {
+            if self.failing.load(std::sync::atomic::Ordering::Relaxed) {
+                Err(anyhow::anyhow!("inference failed"))
+            } else {
+                Ok(false)
+            }
+        }
+
+        fn clear(&mut self) {}
+    }
+
+    #[test]
+    fn a_failing_frame_is_decided_by_energy() {
+        let failing = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(true));
+        let mut detector = Detector {
+            engine: Some(Box::new(Flaky { failing: failing.clone() })),
+            energy: EnergyDetector::new(0.5),
+            failing: false,
+        };
+        let mut session = Session::start();
+        for _ in 0..20 {
+            let quiet = session.chunk(Sound::Quiet);
+            assert!(!detector.is_voiced(&quiet));
+        }
+        let speech = session.chunk(Sound::Speech);
+        assert!(detector.is_voiced(&speech));
+        assert!(detector.failing);
+
+        // Once the model answers again, it decides
+        failing.store(false, std::sync::atomic::Ordering::Relaxed);
+        assert!(!detector.is_voiced(&speech));
+        assert!(!detector.failing);
+    }
+
+    #[test]
+    fn a_hotkey_stop_takes_precedence() {
+        let mut session = Session::start();
+        session.play(Sound::Quiet, 640);
+        session.play(Sound::Speech, 640);
+        session.play(Sound::Quiet, 320);
+        session.stop(StopReason::Hotkey);
+        session.play(Sound::Quiet, 2000);
+        assert_eq!(session.stops.iter().map(|(reason, _)| *reason).collect::<Vec<_>>(), vec![StopReason::Hotkey]);
+    }
+
+    #[test]
+    fn reset_forgets_the_last_recording() {
+        let mut session = Session::start();
+        session.play(Sound::Quiet, 640);
+        session.play(Sound::Speech, 640);
+        assert!(session.vad.is_speech_active());
+
+        session.vad.reset();
+        session.state.start();
+        session.stops.clear();
+        assert!(!session.vad.is_speech_active());
+        session.play(Sound::Quiet, 2000);
+        assert!(session.stops.is_empty(), "{:?}", session.stops);
+    }
+
+    fn stats(frames: &[bool]) -> VadStats {
+        let mut stats = VadStats::default();
+        for &voiced in frames {
+            stats.push(voiced);
+        }
+        stats
+    }
+
+    #[test]
+    fn the_speech_ratio_counts_voiced_frames() {
+        let stats = stats(&[true, true, false, true, true, true, false]);
+        assert_eq!((stats.frames, stats.voiced_frames, stats.longest_voiced_run), (7, 5, 3));
+        assert!((stats.speech_ratio() - 5.0 / 7.0).abs() < 1e-6);
+        assert_eq!(VadStats::default().speech_ratio(), 0.0);
+    }
+
+    #[test]
+    fn dead_air_is_below_the_ratio_and_needs_frames() {
+        let mut frames = vec![false; 9];
+        frames.push(true);
+        let tenth = stats(&frames);
+        assert!(!tenth.is_dead_air(0.0), "0 turns the check off");
+        assert!(!tenth.is_dead_air(0.1), "at the ratio is enough");
+        assert!(tenth.is_dead_air(0.2));
+        // The VAD didn't run, which says nothing about the recording
+        assert!(!VadStats::default().is_dead_air(0.5));
+    }
+
+    #[test]
+    fn a_silent_take_is_dead_air_and_a_spoken_one_isnt() {
+        let mut session = Session::start();
+        session.play(Sound::Quiet, 2000);
+        let silent = session.vad.take_stats();
+        assert_eq!((silent.frames, silent.voiced_frames), (62, 0));
+        assert!(silent.is_dead_air(0.1));
+        assert_eq!(session.vad.take_stats(), VadStats::default(), "taking starts the stats over");
+
+        session.play(Sound::Quiet, 640);
+        session.play(Sound::Speech, 960);
+        let spoken = session.vad.take_stats();
+        assert_eq!(spoken.frames, 50);
+        assert!(spoken.longest_voiced_run >= 20, "{:?}", spoken);
+        assert!(!spoken.is_dead_air(0.1), "{:?}", spoken);
+    }
+
+    #[test]
+    fn edges_fire_once_per_change_at_the_chunk_offset() {
+        let mut edges = SpeechEdges::default();
+        let speaking = [false, false, true, true, true, false, false, true];
+        let fired: Vec<_> = speaking.iter().filter_map(|&speaking| edges.observe(speaking, CHUNK)).collect();
+        assert_eq!(fired, vec![(SpeechEdge::Started, 64), (SpeechEdge::Ended, 160), (SpeechEdge::Started, 224)]);
+
+        edges.reset();
+        assert_eq!(edges.observe(false, CHUNK), None);
+        assert_eq!(edges.observe(true, CHUNK), Some((SpeechEdge::Started, 32)));
+    }
+
+    #[test]
+    fn chunks_that_complete_no_window_dont_end_speech() {
+        // Half-window chunks: every other one completes no window and returns Silence
+        let mut session = Session::start();
+        let mut edges = SpeechEdges::default();
+        let mut fired = Vec::new();
+        for (sound, ms) in [(Sound::Quiet, 320), (Sound::Speech, 960), (Sound::Quiet, 640)] {
+            for _ in 0..ms / CHUNK_TIME.as_millis() as u64 {
+                let chunk = session.chunk(sound);
+                for half in chunk.chunks(CHUNK / 2) {
+                    session.vad.process_audio_at(half, session.now);
+                    fired.extend(edges.observe(session.vad.is_speaking(), half.len()).map(|(edge, _)| edge));
+                    session.now += CHUNK_TIME / 2;
+                }
+            }
+        }
+        assert_eq!(fired, vec![SpeechEdge::Started, SpeechEdge::Ended]);
+    }
+
+    #[test]
+    fn frame_size_covers_every_rate_and_frame_length() {
+        let rates = [8000, 16000, 22050, 44100, 48000];
+        for engine in [VadEngine::Energy, VadEngine::Silero] {
+            for rate in rates {
+                for frame_ms in [10, 20, 30, 32, 64, 96] {
+                    let supported = match engine {
+                        VadEngine::Energy => ENERGY_FRAME_MS.contains(&frame_ms),
+                        VadEngine::Silero => SILERO_FRAME_MS.contains(&frame_ms) && (rate == 8000 || rate == 16000),
+                    };
+                    let whole = rate * frame_ms % 1000 == 0;
+                    let expected = (supported && whole).then(|| (rate * frame_ms / 1000) as usize);
+                    assert_eq!(frame_size(engine, rate, frame_ms).ok(), expected, "{:?} {}Hz {}ms", engine, rate, frame_ms);
+                }
+            }
+        }
+        assert_eq!(frame_size(VadEngine::Energy, 16000, 20).unwrap(), 320);
+        assert_eq!(frame_size(VadEngine::Silero, 8000, 96).unwrap(), 768);
+        let unsupported = frame_size(VadEngine::Silero, 16000, 20).unwrap_err().to_string();
+        assert!(unsupported.contains("vad.frame_ms = 20") && unsupported.contains("silero"), "{}", unsupported);
+    }
+
+    #[test]
+    fn rates_that_cant_be_framed_resample_to_the_nearest_that_can() {
+        assert_eq!(vad_rate(VadEngine::Energy, 48000, 10).unwrap(), 48000);
+        assert_eq!(vad_rate(VadEngine::Energy, 22050, 10).unwrap(), 16000);
+        assert_eq!(vad_rate(VadEngine::Silero, 48000, 32).unwrap(), 16000);
+        assert_eq!(vad_rate(VadEngine::Silero, 8000, 32).unwrap(), 8000);
+        assert!(vad_rate(VadEngine::Silero, 48000, 30).is_err());
+    }
+
+    #[test]
+    fn leftover_samples_wait_for_the_next_chunk() {
+        let mut vad = VoiceActivityDetector::new("", 16000, 0.5, 500, 0, 0, VadEngine::Energy, 30).unwrap();
+        let now = Instant::now();
+
+        // Less than one 480-sample frame: nothing to classify yet
+        vad.process_audio_at(&[0.0; 479], now);
+        assert_eq!((vad.stats.frames, vad.pending_samples.len()), (0, 479));
+        vad.process_audio_at(&[0.0; 1], now);
+        assert_eq!((vad.stats.frames, vad.pending_samples.len()), (1, 0));
+
+        // Chunks that don't line up with frames lose nothing across the boundaries
+        for _ in 0..50 {
+            vad.process_audio_at(&[0.0; 100], now);
+        }
+        assert_eq!((vad.stats.frames, vad.pending_samples.len()), (1 + 5000 / 480, 5000 % 480));
+    }
+
+    fn sine(rate: u32, ms: u32) -> Vec<f32> {
+        (0..(rate * ms / 1000) as usize)
+            .map(|i| 0.1 * (2.0 * std::f32::consts::PI * 220.0 * i as f32 / rate as f32).sin())
+            .collect()
+    }
+
+    #[test]
+    fn capture_rate_audio_isnt_resampled() {
+        let vad = VoiceActivityDetector::new("", TARGET_RATE, 0.5, 500, 0, 0, VadEngine::Energy, 32).unwrap();
+        assert!(vad.resampler.is_none());
+        assert_eq!(vad.window_size, 512);
+    }
+
+    #[test]
+    fn a_441khz_sine_is_resampled_and_still_speech() {
+        let mut vad = VoiceActivityDetector::new("", 44100, 0.5, 500, 0, 0, VadEngine::Energy, 32).unwrap();
+        assert_eq!((vad.vad_rate, vad.window_size), (16000, 512));
+
+        // Half a second of room for the energy floor, then a second of tone, in uneven
+        // chunks so the resampler carries its phase across calls
+        let mut audio: Vec<f32> = (0..22050).map(|i| if i % 2 == 0 { 0.0005 } else { -0.0005 }).collect();
+        audio.extend(sine(44100, 1000));
+        let now = Instant::now();
+        let results: Vec<VadResult> = audio.chunks(1000).map(|chunk| vad.process_audio_at(chunk, now)).collect();
+        assert!(results.contains(&VadResult::SpeechDetected));
+        assert!(vad.is_speaking());
+
+        // 1.5s in is 1.5s at 16kHz, less what the resampler's kernel holds back
+        let resampled = vad.stats.frames * vad.window_size + vad.pending_samples.len();
+        assert!((24000 - 32..=24000).contains(&resampled), "{} samples after resampling", resampled);
+        assert_eq!(vad.stats.frames, resampled / 512);
+        // The tone's 31 frames, less the energy history ramping up
+        assert!((29..=31).contains(&vad.stats.voiced_frames), "{:?}", vad.stats);
+    }
+}
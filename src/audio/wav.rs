@@ -0,0 +1,193 @@
+//! Debug copies of recordings (debug.save_audio_dir): the audio exactly as captured,
+//! before gain or noise suppression, as 16-bit PCM so any player can open it.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+const SAMPLE_RATE: u32 = 16000;
+const PREFIX: &str = "tomchat-";
+const SPEC: hound::WavSpec = hound::WavSpec {
+    channels: 1,
+    sample_rate: SAMPLE_RATE,
+    bits_per_sample: 16,
+    sample_format: hound::SampleFormat::Int,
+};
+
+/// `<dir>/tomchat-<epoch ms>-<duration ms>ms.wav`, creating `dir`
+fn saved_path(dir: &Path, samples: usize) -> Result<PathBuf> {
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create audio directory {:?}", dir))?;
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis();
+    let duration_ms = samples as u64 * 1000 / SAMPLE_RATE as u64;
+    Ok(dir.join(format!("{}{}-{}ms.wav", PREFIX, timestamp, duration_ms)))
+}
+
+/// Write `samples` to `<dir>/tomchat-<epoch ms>-<duration ms>ms.wav`
+pub fn save(dir: &Path, samples: &[f32]) -> Result<PathBuf> {
+    let path = saved_path(dir, samples.len())?;
+
+    let mut writer = hound::WavWriter::create(&path, SPEC)
+        .with_context(|| format!("Failed to create {:?}", path))?;
+    for &sample in samples {
+        writer.write_sample(to_i16(sample))?;
+    }
+    writer.finalize()?;
+    Ok(path)
+}
+
+/// Move an already written 16 kHz WAV of `samples` samples (e.g. a spill file) into
+/// `dir` under the saved-recording name, so pruning and playback treat it like the rest
+pub fn adopt(dir: &Path, file: &Path, samples: usize) -> Result<PathBuf> {
+    let path = saved_path(dir, samples)?;
+    // Renaming fails across filesystems; copy there instead
+    if std::fs::rename(file, &path).is_err() {
+        std::fs::copy(file, &path).with_context(|| format!("Failed to copy {:?} to {:?}", file, path))?;
+        std::fs::remove_file(file)?;
+    }
+    Ok(path)
+}
+
+/// `samples` as an in-memory WAV file, e.g. for uploading
+pub fn encode(samples: &[f32]) -> Result<Vec<u8>> {
+    let mut bytes = std::io::Cursor::new(Vec::new());
+    let mut writer = hound::WavWriter::new(&mut bytes, SPEC)?;
+    for &sample in samples {
+        writer.write_sample(to_i16(sample))?;
+    }
+    writer.finalize()?;
+    Ok(bytes.into_inner())
+}
+
+/// A WAV file's interleaved samples scaled to -1.0..1.0, with its channel count and rate
+pub fn decode<R: std::io::Read>(reader: R) -> Result<(Vec<f32>, u16, u32)> {
+    let reader = hound::WavReader::new(reader).context("Not a WAV file (only WAV is supported)")?;
+    let spec = reader.spec();
+    let samples = match spec.sample_format {
+        hound::SampleFormat::Float => reader.into_samples::<f32>().collect::<Result<Vec<_>, _>>()?,
+        hound::SampleFormat::Int => {
+            let scale = 1.0 / (1i64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .into_samples::<i32>()
+                .map(|sample| sample.map(|s| s as f32 * scale))
+                .collect::<Result<Vec<_>, _>>()?
+        }
+    };
+    Ok((samples, spec.channels, spec.sample_rate))
+}
+
+fn to_i16(sample: f32) -> i16 {
+    (sample.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16
+}
+
+/// Delete the oldest saved recordings beyond `keep`; 0 keeps them all.
+/// Returns how many were removed.
+pub fn prune(dir: &Path, keep: usize) -> Result<usize> {
+    if keep == 0 {
+        return Ok(0);
+    }
+
+    let mut saved: Vec<(u128, PathBuf)> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter_map(|path| saved_at(&path).map(|timestamp| (timestamp, path)))
+        .collect();
+    if saved.len() <= keep {
+        return Ok(0);
+    }
+
+    saved.sort();
+    let excess = saved.len() - keep;
+    for (_, path) in &saved[..excess] {
+        std::fs::remove_file(path).with_context(|| format!("Failed to remove {:?}", path))?;
+    }
+    Ok(excess)
+}
+
+/// Epoch ms from a name `save` wrote; anything else in the directory is left alone
+fn saved_at(path: &Path) -> Option<u128> {
+    let name = path.file_name()?.to_str()?;
+    let stem = name.strip_prefix(PREFIX)?.strip_suffix(".wav")?;
+    let (timestamp, duration) = stem.split_once('-')?;
+    duration.strip_suffix("ms")?.parse::<u64>().ok()?;
+    timestamp.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tomchat-wav-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn ramp(len: usize) -> Vec<f32> {
+        (0..len).map(|i| (i as f32 / len as f32) * 2.0 - 1.0).collect()
+    }
+
+    #[test]
+    fn saved_file_has_a_16k_mono_pcm_header_and_the_samples() {
+        let dir = temp_dir("save");
+        let samples = ramp(8000);
+        let path = save(&dir, &samples).unwrap();
+
+        let name = path.file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with(PREFIX) && name.ends_with("-500ms.wav"), "{}", name);
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(&bytes[0..4], b"RIFF");
+        assert_eq!(&bytes[8..12], b"WAVE");
+        let reader = hound::WavReader::open(&path).unwrap();
+        assert_eq!(reader.spec(), SPEC);
+        assert_eq!(reader.duration(), 8000);
+
+        let (decoded, channels, rate) = decode(std::fs::File::open(&path).unwrap()).unwrap();
+        assert_eq!((channels, rate), (1, SAMPLE_RATE));
+        for (decoded, original) in decoded.iter().zip(&samples) {
+            assert!((decoded - original).abs() < 1.0 / 16000.0, "{} vs {}", decoded, original);
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn encode_decode_round_trip_clamps_out_of_range_samples() {
+        let (decoded, _, _) = decode(std::io::Cursor::new(encode(&[0.5, 2.0, -2.0]).unwrap())).unwrap();
+        assert!((decoded[0] - 0.5).abs() < 1e-4);
+        assert!((decoded[1] - 1.0).abs() < 1e-4);
+        assert!((decoded[2] + 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn prune_removes_the_oldest_and_leaves_other_files() {
+        let dir = temp_dir("prune");
+        std::fs::create_dir_all(&dir).unwrap();
+        for timestamp in [300, 100, 200] {
+            std::fs::write(dir.join(format!("{}{}-10ms.wav", PREFIX, timestamp)), b"").unwrap();
+        }
+        std::fs::write(dir.join("notes.wav"), b"").unwrap();
+
+        assert_eq!(prune(&dir, 0).unwrap(), 0);
+        assert_eq!(prune(&dir, 2).unwrap(), 1);
+        assert!(!dir.join(format!("{}100-10ms.wav", PREFIX)).exists());
+        assert!(dir.join(format!("{}200-10ms.wav", PREFIX)).exists());
+        assert!(dir.join("notes.wav").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn adopted_files_are_named_like_saved_ones() {
+        let dir = temp_dir("adopt");
+        std::fs::create_dir_all(&dir).unwrap();
+        let spill = dir.join("spill.wav");
+        std::fs::write(&spill, encode(&ramp(1600)).unwrap()).unwrap();
+
+        let path = adopt(&dir.join("saved"), &spill, 1600).unwrap();
+        assert!(!spill.exists());
+        assert!(saved_at(&path).is_some());
+        assert!(path.file_name().unwrap().to_str().unwrap().ends_with("-100ms.wav"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,117 @@
+//! `tomchat benchmark`: time model load, the first decode and repeated decodes of a
+//! fixed sample with the configured model, threads and GPU setting, and report the
+//! real-time factor.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::path::Path;
+use std::time::Instant;
+use tracing::info;
+
+use crate::audio::process::TARGET_RATE;
+use crate::config::Config;
+use crate::speech::SpeechTranscriber;
+use crate::transcribe;
+
+#[derive(Debug, Serialize)]
+struct BenchmarkReport {
+    model_dir: String,
+    threads: usize,
+    use_gpu: bool,
+    audio_secs: f32,
+    load_ms: u64,
+    warmup_ms: u64,
+    passes: usize,
+    mean_ms: u64,
+    min_ms: u64,
+    max_ms: u64,
+    /// Mean decode time over audio duration; below 1 is faster than real time
+    rtf: f32,
+}
+
+/// `seconds` of voiced-sounding audio: a gliding harmonic tone in syllable-length
+/// bursts over low noise. The same every run, so numbers compare across settings.
+fn synthetic_audio(seconds: f32) -> Vec<f32> {
+    let samples = (seconds * TARGET_RATE as f32) as usize;
+    let mut noise_state: u32 = 0x1234_5678;
+    (0..samples)
+        .map(|i| {
+            let t = i as f32 / TARGET_RATE as f32;
+            let pitch = 120.0 + 40.0 * (2.0 * std::f32::consts::PI * 0.7 * t).sin();
+            let voiced = if (i / 3200) % 3 != 2 { 0.15 } else { 0.0 };
+            let tone: f32 = (1..=4)
+                .map(|harmonic| (2.0 * std::f32::consts::PI * pitch * harmonic as f32 * t).sin() / harmonic as f32)
+                .sum();
+            noise_state = noise_state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            let noise = (noise_state >> 8) as f32 / (1u32 << 24) as f32 - 0.5;
+            voiced * tone + 0.01 * noise
+        })
+        .collect()
+}
+
+/// Decode `file` (or `seconds` of synthetic audio) `passes` times after one warmup decode
+pub async fn run(config: &Config, seconds: f32, passes: usize, file: Option<&Path>, json: bool) -> Result<()> {
+    let audio = match file {
+        Some(file) => transcribe::load(file, config)?,
+        None => synthetic_audio(seconds),
+    };
+    let audio_secs = audio.len() as f32 / TARGET_RATE as f32;
+    if audio_secs <= 0.0 {
+        return Err(anyhow::anyhow!("Nothing to benchmark: the sample is empty"));
+    }
+    let passes = passes.max(1);
+
+    let load_started = Instant::now();
+    let transcriber = SpeechTranscriber::new(
+        &config.speech.model_dir,
+        Some(&config.speech.language),
+        config.speech.min_memory_headroom_mb,
+        config.speech.decode_policy(),
+    )?;
+    let load_ms = load_started.elapsed().as_millis() as u64;
+
+    // The first decode pays for onnxruntime's lazy initialization
+    let warmup_started = Instant::now();
+    transcriber.transcribe_audio(&audio).await?;
+    let warmup_ms = warmup_started.elapsed().as_millis() as u64;
+
+    let mut times = Vec::with_capacity(passes);
+    for pass in 1..=passes {
+        let started = Instant::now();
+        transcriber.transcribe_audio(&audio).await?;
+        let elapsed_ms = started.elapsed().as_millis() as u64;
+        info!("Pass {}/{}: {}ms", pass, passes, elapsed_ms);
+        times.push(elapsed_ms);
+    }
+    let mean_ms = times.iter().sum::<u64>() / passes as u64;
+
+    let report = BenchmarkReport {
+        model_dir: config.speech.model_dir.display().to_string(),
+        threads: config.speech.decode_policy().decode_threads(),
+        use_gpu: config.speech.use_gpu,
+        audio_secs,
+        load_ms,
+        warmup_ms,
+        passes,
+        mean_ms,
+        min_ms: times.iter().copied().min().unwrap_or(0),
+        max_ms: times.iter().copied().max().unwrap_or(0),
+        rtf: mean_ms as f32 / 1000.0 / audio_secs,
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        println!("Model:   {}", report.model_dir);
+        println!("Threads: {}{}", report.threads, if report.use_gpu { ", GPU requested" } else { "" });
+        println!("Audio:   {:.1}s", report.audio_secs);
+        println!("Load:    {}ms", report.load_ms);
+        println!("Warmup:  {}ms", report.warmup_ms);
+        println!(
+            "Decode:  mean {}ms, min {}ms, max {}ms over {} pass(es)",
+            report.mean_ms, report.min_ms, report.max_ms, report.passes
+        );
+        println!("RTF:     {:.3} ({:.1}x real time)", report.rtf, 1.0 / report.rtf.max(f32::EPSILON));
+    }
+    Ok(())
+}
@@ -0,0 +1,175 @@
+use anyhow::Result;
+use regex::Regex;
+use serde_json::{json, Value};
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use tracing::info;
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+use crate::config::Config;
+use crate::history::{self, HistoryEntry, StageTimings};
+use crate::paths;
+use crate::redact::redact;
+
+/// Bundle the last dictation into a zip for bug reports
+pub fn export_last(config: &Config, out: &Path, redact_text: bool) -> Result<()> {
+    let entry = history::load_last()?
+        .ok_or_else(|| anyhow::anyhow!("No dictation recorded yet - nothing to export"))?;
+
+    info!("Exporting recording {} to {:?}", entry.recording_id, out);
+
+    let mut zip = ZipWriter::new(File::create(out)?);
+    let options = FileOptions::default();
+    let mut artifacts = serde_json::Map::new();
+
+    // Transcript variants
+    let mut transcript = entry.clone();
+    if redact_text {
+        transcript.raw = redact(&transcript.raw);
+        transcript.refined = transcript.refined.as_deref().map(redact);
+        transcript.processed = redact(&transcript.processed);
+        transcript.audio_path = None;
+    }
+    zip.start_file("transcript.json", options)?;
+    zip.write_all(serde_json::to_string_pretty(&transcript)?.as_bytes())?;
+    artifacts.insert("transcript.json".into(), json!("included"));
+
+    // Effective config, with credentials stripped
+    zip.start_file("config.toml", options)?;
+    zip.write_all(redact(&toml::to_string_pretty(config)?).as_bytes())?;
+    artifacts.insert("config.toml".into(), json!("included"));
+
+    // Audio, only present when recordings are saved
+    let wav_path = entry
+        .audio_path
+        .clone()
+        .filter(|path| path.exists())
+        .unwrap_or_else(|| paths::recordings_dir().join(format!("{}.wav", entry.recording_id)));
+    if wav_path.exists() {
+        zip.start_file("audio.wav", options)?;
+        zip.write_all(&std::fs::read(&wav_path)?)?;
+        artifacts.insert("audio.wav".into(), json!("included"));
+    } else {
+        artifacts.insert("audio.wav".into(), json!("missing: audio saving is disabled or the file was removed"));
+    }
+
+    // Where the recording's stages fell in time, and what they cost
+    match entry.timings {
+        Some(timings) => {
+            zip.start_file("timemap.json", options)?;
+            zip.write_all(serde_json::to_string_pretty(&time_map(entry.timestamp, &timings))?.as_bytes())?;
+            artifacts.insert("timemap.json".into(), json!("included"));
+
+            zip.start_file("metrics.json", options)?;
+            zip.write_all(serde_json::to_string_pretty(&metrics(&entry, &timings))?.as_bytes())?;
+            artifacts.insert("metrics.json".into(), json!("included"));
+        }
+        None => {
+            let note = json!("missing: the dictation predates stage timings");
+            artifacts.insert("timemap.json".into(), note.clone());
+            artifacts.insert("metrics.json".into(), note);
+        }
+    }
+
+    // Log lines about this recording from the daemon's session log
+    let log_path = paths::log_path();
+    match std::fs::read_to_string(&log_path) {
+        Ok(log) => {
+            let mut lines = log_lines_for(&log, entry.recording_id);
+            if redact_text {
+                lines = redact(&lines);
+            }
+            zip.start_file("log.txt", options)?;
+            zip.write_all(lines.as_bytes())?;
+            artifacts.insert("log.txt".into(), json!("included"));
+        }
+        Err(e) => {
+            artifacts.insert("log.txt".into(), json!(format!("missing: could not read {:?}: {}", log_path, e)));
+        }
+    }
+
+    let manifest = json!({
+        "tomchat_version": env!("CARGO_PKG_VERSION"),
+        "recording_id": entry.recording_id,
+        "timestamp": entry.timestamp,
+        "redacted": redact_text,
+        "artifacts": artifacts,
+    });
+    zip.start_file("manifest.json", options)?;
+    zip.write_all(serde_json::to_string_pretty(&manifest)?.as_bytes())?;
+
+    zip.finish()?;
+    info!("✅ Exported bundle: {:?}", out);
+    Ok(())
+}
+
+/// Stage boundaries in milliseconds since the Unix epoch. The entry is stamped when
+/// output starts, so the earlier stages are placed by walking back from there.
+fn time_map(timestamp: u64, timings: &StageTimings) -> Value {
+    let output_ms = timestamp * 1000;
+    let refine_start = output_ms.saturating_sub(timings.refine_ms);
+    let decode_start = refine_start.saturating_sub(timings.decode_ms);
+    let recording_start = decode_start.saturating_sub(timings.audio_ms);
+    json!({
+        "recording": { "start_ms": recording_start, "end_ms": decode_start },
+        "decoding": { "start_ms": decode_start, "end_ms": refine_start },
+        "refining": { "start_ms": refine_start, "end_ms": output_ms },
+        "note": "queue waits are not recorded, so decoding may have started later than shown",
+    })
+}
+
+fn metrics(entry: &HistoryEntry, timings: &StageTimings) -> Value {
+    json!({
+        "audio_ms": timings.audio_ms,
+        "decode_ms": timings.decode_ms,
+        "refine_ms": timings.refine_ms,
+        "real_time_factor": timings.decode_ms as f64 / timings.audio_ms.max(1) as f64,
+        "stop_reason": entry.stop_reason,
+        "raw_chars": entry.raw.chars().count(),
+        "processed_chars": entry.processed.chars().count(),
+        "refined": entry.refined.is_some(),
+    })
+}
+
+/// Lines mentioning the recording, e.g. "Recording 7 queued" or `"recording_id":7`
+fn log_lines_for(log: &str, recording_id: u64) -> String {
+    let pattern = Regex::new(&format!(r#"(?i)recording(_id)?"?[\s:=]*{}\b"#, recording_id)).unwrap();
+    log.lines()
+        .filter(|line| pattern.is_match(line))
+        .map(|line| format!("{}\n", line))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_map_walks_back_from_output() {
+        let timings = StageTimings { audio_ms: 3000, decode_ms: 400, refine_ms: 100 };
+        let map = time_map(10, &timings);
+        assert_eq!(map["refining"]["end_ms"], 10_000);
+        assert_eq!(map["refining"]["start_ms"], 9_900);
+        assert_eq!(map["decoding"]["start_ms"], 9_500);
+        assert_eq!(map["recording"]["start_ms"], 6_500);
+        assert_eq!(map["recording"]["end_ms"], 9_500);
+    }
+
+    #[test]
+    fn log_lines_match_only_the_recording() {
+        let log = "\
+INFO Recording 7 queued behind 1 decode(s)
+INFO Recording 17 queued behind 2 decode(s)
+INFO Re-decoding recording 3 as 7
+DEBUG event {\"recording_id\":7,\"stage\":\"decoding\"}
+INFO Model loaded
+";
+        let lines = log_lines_for(log, 7);
+        assert_eq!(
+            lines,
+            "INFO Recording 7 queued behind 1 decode(s)\nDEBUG event {\"recording_id\":7,\"stage\":\"decoding\"}\n"
+        );
+    }
+}
@@ -0,0 +1,109 @@
+//! Cancellation for a recording's whole journey: recording, decoding, refinement and
+//! output. Each recording gets a token at start; every stage checks it at its boundary
+//! or races its work against it, and whichever stage notices first reports it, once.
+
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+pub use tokio_util::sync::CancellationToken;
+
+/// Where a job is in the pipeline, in order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStage {
+    Recording,
+    Decoding,
+    Refining,
+    Output,
+}
+
+impl JobStage {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            JobStage::Recording => "recording",
+            JobStage::Decoding => "decoding",
+            JobStage::Refining => "refining",
+            JobStage::Output => "output",
+        }
+    }
+}
+
+/// The single "cancelled" event for a job; `recording_id` is None while still recording.
+/// "recording_cancelled" follows when the audio was dropped before decoding, and
+/// "transcription_cancelled" past that point: its text will never be output.
+pub fn report<F: Fn(&str, serde_json::Value) + ?Sized>(emit_data: &F, recording_id: Option<u64>, stage: JobStage) {
+    match recording_id {
+        Some(id) => info!("🚫 Recording {} cancelled while {}", id, stage.as_str()),
+        None => info!("🚫 Recording cancelled while {}", stage.as_str()),
+    }
+    let event = serde_json::json!({
+        "recording_id": recording_id,
+        "stage": stage,
+    });
+    emit_data("cancelled", event.clone());
+    if stage == JobStage::Recording {
+        emit_data("recording_cancelled", event);
+    } else {
+        emit_data("transcription_cancelled", event);
+    }
+}
+
+/// Run a stage's work unless the job is cancelled first; None when it was
+pub async fn unless_cancelled<T>(token: &CancellationToken, work: impl std::future::Future<Output = T>) -> Option<T> {
+    tokio::select! {
+        biased;
+        _ = token.cancelled() => None,
+        value = work => Some(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn reported(recording_id: Option<u64>, stage: JobStage) -> Vec<(String, serde_json::Value)> {
+        let events = Mutex::new(Vec::new());
+        report(&|name: &str, data| events.lock().unwrap().push((name.to_string(), data)), recording_id, stage);
+        events.into_inner().unwrap()
+    }
+
+    #[test]
+    fn a_cancel_while_recording_drops_the_recording() {
+        let names: Vec<String> = reported(None, JobStage::Recording).into_iter().map(|(name, _)| name).collect();
+
+        assert_eq!(names, ["cancelled", "recording_cancelled"]);
+    }
+
+    #[test]
+    fn a_cancel_after_recording_drops_the_transcription() {
+        for stage in [JobStage::Decoding, JobStage::Refining, JobStage::Output] {
+            let events = reported(Some(4), stage);
+            let names: Vec<&str> = events.iter().map(|(name, _)| name.as_str()).collect();
+            assert_eq!(names, ["cancelled", "transcription_cancelled"]);
+            assert_eq!(events[1].1, serde_json::json!({ "recording_id": 4, "stage": stage.as_str() }));
+        }
+    }
+
+    #[tokio::test]
+    async fn cancelled_work_is_dropped_even_when_ready() {
+        let token = CancellationToken::new();
+        assert_eq!(unless_cancelled(&token, async { 1 }).await, Some(1));
+
+        token.cancel();
+        assert_eq!(unless_cancelled(&token, async { 2 }).await, None);
+    }
+
+    #[tokio::test]
+    async fn cancelling_interrupts_work_in_progress() {
+        let token = CancellationToken::new();
+        let canceller = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            canceller.cancel();
+        });
+
+        let work = tokio::time::sleep(std::time::Duration::from_secs(30));
+        assert_eq!(unless_cancelled(&token, work).await, None);
+    }
+}
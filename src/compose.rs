@@ -0,0 +1,255 @@
+use std::time::{Duration, Instant};
+use tracing::{debug, info};
+
+use crate::output::UtteranceMeta;
+
+/// How long a first cancel press stays armed waiting for confirmation
+const CANCEL_CONFIRM_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Spoken commands that act on the draft instead of being appended to it
+const SCRATCH_COMMANDS: &[&str] = &["scratch that", "delete that", "undo that"];
+const PARAGRAPH_COMMANDS: &[&str] = &["new paragraph", "next paragraph"];
+
+/// A document assembled from several dictation takes
+#[derive(Debug, Default, Clone)]
+pub struct ComposeDraft {
+    paragraphs: Vec<Vec<String>>,
+}
+
+impl ComposeDraft {
+    /// Apply one take to the draft, interpreting edit commands
+    pub fn apply(&mut self, take: &str) {
+        let take = take.trim();
+        if take.is_empty() {
+            return;
+        }
+
+        let command = normalize_command(take);
+        if SCRATCH_COMMANDS.contains(&command.as_str()) {
+            self.scratch_last();
+        } else if PARAGRAPH_COMMANDS.contains(&command.as_str()) {
+            self.start_paragraph();
+        } else {
+            if self.paragraphs.is_empty() {
+                self.paragraphs.push(Vec::new());
+            }
+            if let Some(paragraph) = self.paragraphs.last_mut() {
+                paragraph.push(take.to_string());
+            }
+        }
+    }
+
+    fn scratch_last(&mut self) {
+        // Drop empty trailing paragraphs first so "scratch that" removes the last words spoken
+        while matches!(self.paragraphs.last(), Some(p) if p.is_empty()) {
+            self.paragraphs.pop();
+        }
+        if let Some(paragraph) = self.paragraphs.last_mut() {
+            if let Some(removed) = paragraph.pop() {
+                debug!("Scratched from draft: \"{}\"", removed);
+            }
+        }
+    }
+
+    fn start_paragraph(&mut self) {
+        if matches!(self.paragraphs.last(), Some(p) if !p.is_empty()) {
+            self.paragraphs.push(Vec::new());
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paragraphs.iter().all(|p| p.is_empty())
+    }
+
+    /// Render takes joined by spaces and paragraphs by blank lines
+    pub fn render(&self) -> String {
+        self.paragraphs
+            .iter()
+            .filter(|p| !p.is_empty())
+            .map(|p| p.join(" "))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+/// Lowercase and strip punctuation so "Scratch that." matches "scratch that"
+fn normalize_command(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric() || c.is_whitespace())
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Result of a cancel press
+#[derive(Debug, Clone, PartialEq)]
+pub enum CancelOutcome {
+    /// Nothing to cancel
+    Inactive,
+    /// First press - waiting for a confirming second press
+    Armed,
+    /// Second press within the timeout - the draft was discarded
+    Discarded,
+}
+
+/// Compose mode state shared between the hotkey and transcription tasks
+#[derive(Debug, Default)]
+pub struct ComposeSession {
+    draft: Option<ComposeDraft>,
+    /// Spans every take in the draft, for the sinks that record it
+    meta: Option<UtteranceMeta>,
+    cancel_armed_at: Option<Instant>,
+}
+
+impl ComposeSession {
+    pub fn is_active(&self) -> bool {
+        self.draft.is_some()
+    }
+
+    pub fn start(&mut self) {
+        info!("Compose mode started");
+        self.draft = Some(ComposeDraft::default());
+        self.meta = None;
+        self.cancel_armed_at = None;
+    }
+
+    /// End compose mode, returning the assembled document and the takes it spans
+    pub fn finish(&mut self) -> Option<(String, UtteranceMeta)> {
+        self.cancel_armed_at = None;
+        let draft = self.draft.take()?;
+        info!("Compose mode finished");
+        self.meta.take().filter(|_| !draft.is_empty()).map(|meta| (draft.render(), meta))
+    }
+
+    /// Append a take to the draft, returning the updated document if compose mode is active
+    pub fn append(&mut self, take: &str, meta: &UtteranceMeta) -> Option<String> {
+        let draft = self.draft.as_mut()?;
+        draft.apply(take);
+        match self.meta {
+            Some(ref mut span) => {
+                span.recording_id = meta.recording_id;
+                span.ended_at_ms = meta.ended_at_ms;
+                span.duration_ms += meta.duration_ms;
+            }
+            None => self.meta = Some(meta.clone()),
+        }
+        Some(draft.render())
+    }
+
+    /// A first press arms the cancel; a second within CANCEL_CONFIRM_TIMEOUT discards the draft
+    pub fn cancel(&mut self, now: Instant) -> CancelOutcome {
+        if self.draft.is_none() {
+            return CancelOutcome::Inactive;
+        }
+
+        match self.cancel_armed_at {
+            Some(armed_at) if now.duration_since(armed_at) <= CANCEL_CONFIRM_TIMEOUT => {
+                info!("Compose mode cancelled, draft discarded");
+                self.draft = None;
+                self.meta = None;
+                self.cancel_armed_at = None;
+                CancelOutcome::Discarded
+            }
+            _ => {
+                self.cancel_armed_at = Some(now);
+                CancelOutcome::Armed
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn draft(takes: &[&str]) -> String {
+        let mut draft = ComposeDraft::default();
+        for take in takes {
+            draft.apply(take);
+        }
+        draft.render()
+    }
+
+    fn meta(recording_id: u64, started_at_ms: u64, ended_at_ms: u64) -> UtteranceMeta {
+        UtteranceMeta {
+            recording_id,
+            language: "en".to_string(),
+            profile: None,
+            started_at_ms,
+            ended_at_ms,
+            duration_ms: ended_at_ms - started_at_ms,
+        }
+    }
+
+    #[test]
+    fn takes_are_joined_by_single_spaces() {
+        assert_eq!(draft(&["  Dear Sam,", "thanks for the notes.  ", "", "Best"]), "Dear Sam, thanks for the notes. Best");
+    }
+
+    #[test]
+    fn new_paragraph_starts_one_after_some_text() {
+        assert_eq!(draft(&["New paragraph.", "Hi.", "New paragraph", "next paragraph", "Bye."]), "Hi.\n\nBye.");
+    }
+
+    #[test]
+    fn scratch_that_removes_the_last_take() {
+        assert_eq!(draft(&["One.", "Two.", "Scratch that!"]), "One.");
+        // Past an empty paragraph, into the one before
+        assert_eq!(draft(&["One.", "Two.", "new paragraph", "scratch that", "Three."]), "One. Three.");
+        assert_eq!(draft(&["scratch that", "Only."]), "Only.");
+    }
+
+    #[test]
+    fn finishing_returns_the_draft_and_the_takes_it_spans() {
+        let mut session = ComposeSession::default();
+        assert_eq!(session.append("Ignored.", &meta(1, 0, 10)), None);
+
+        session.start();
+        assert_eq!(session.append("First.", &meta(2, 100, 400)).as_deref(), Some("First."));
+        assert_eq!(session.append("Second.", &meta(3, 1000, 1200)).as_deref(), Some("First. Second."));
+        let (text, span) = session.finish().unwrap();
+        assert_eq!(text, "First. Second.");
+        assert_eq!((span.recording_id, span.started_at_ms, span.ended_at_ms, span.duration_ms), (3, 100, 1200, 500));
+        assert!(!session.is_active());
+    }
+
+    #[test]
+    fn a_draft_scratched_empty_finishes_with_nothing() {
+        let mut session = ComposeSession::default();
+        session.start();
+        session.append("Oops.", &meta(1, 0, 10));
+        session.append("scratch that", &meta(2, 20, 30));
+        assert!(session.finish().is_none());
+    }
+
+    #[test]
+    fn cancelling_needs_a_second_press_within_the_timeout() {
+        let mut session = ComposeSession::default();
+        let now = Instant::now();
+        assert_eq!(session.cancel(now), CancelOutcome::Inactive);
+
+        session.start();
+        session.append("Draft.", &meta(1, 0, 10));
+        assert_eq!(session.cancel(now), CancelOutcome::Armed);
+        // Too late: that press arms it again instead
+        let late = now + CANCEL_CONFIRM_TIMEOUT + Duration::from_millis(1);
+        assert_eq!(session.cancel(late), CancelOutcome::Armed);
+        assert!(session.is_active());
+
+        assert_eq!(session.cancel(late + Duration::from_millis(500)), CancelOutcome::Discarded);
+        assert!(!session.is_active());
+        assert_eq!(session.cancel(late + Duration::from_secs(1)), CancelOutcome::Inactive);
+    }
+
+    #[test]
+    fn restarting_forgets_an_armed_cancel() {
+        let mut session = ComposeSession::default();
+        let now = Instant::now();
+        session.start();
+        assert_eq!(session.cancel(now), CancelOutcome::Armed);
+        session.start();
+        assert_eq!(session.cancel(now), CancelOutcome::Armed);
+    }
+}
@@ -1,22 +1,286 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
 
-use crate::text_refinement::TextRefinementConfig;
+use crate::audio::{vad, ChannelMode, VadEngine, TARGET_RATE};
+use crate::decode_queue::QueueOverflow;
+use crate::download;
+use crate::endpoint::{DictationMode, OverflowPolicy};
+use crate::input::hold::HotkeyMode;
+use crate::input::hotkey::{validate_hotkey_string, HotkeyBackend};
+use crate::input::injection::TextBackend;
+use crate::input::tap::TapBinding;
+use crate::input::HotkeyAction;
+use crate::output::job::{default_sinks, SinkConfig, SinkKind};
+use crate::preset::{self, Preset};
+use crate::privacy::{self, PrivacyConfig};
+use crate::push::{default_push_subscribers, PushSubscriberConfig};
+use crate::speech::{hallucination, locate, DecodePolicy, RemoteTranscriptionConfig, SpeechBackend};
+use crate::text_refinement::{PunctuateMode, TextRefinementConfig};
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Config {
-    pub hotkey: HotkeyConfig,
+    /// Speed/accuracy preset layered under explicit settings
+    #[serde(default)]
+    pub preset: Option<Preset>,
+    /// Listed first so local-only mode is the first thing in config dumps
+    #[serde(default)]
+    pub privacy: PrivacyConfig,
+    /// Legacy `[hotkey]` table, migrated into `hotkeys` at load time
+    #[serde(default, skip_serializing)]
+    pub hotkey: Option<LegacyHotkeyConfig>,
+    #[serde(default)]
+    pub hotkeys: HotkeysConfig,
     pub audio: AudioConfig,
     pub vad: VadConfig,
     pub speech: SpeechConfig,
+    #[serde(default)]
+    pub remote_transcription: RemoteTranscriptionConfig,
     pub text: TextConfig,
     pub text_refinement: Option<TextRefinementConfig>,
+    #[serde(default)]
+    pub indicator: IndicatorConfig,
+    #[serde(default)]
+    pub output: OutputConfig,
+    #[serde(default)]
+    pub retranscribe: RetranscribeConfig,
+    #[serde(default)]
+    pub correction: CorrectionConfig,
+    #[serde(default)]
+    pub history: HistoryConfig,
+    #[serde(default)]
+    pub form_fill: FormFillConfig,
+    #[serde(default)]
+    pub gui: GuiConfig,
+    #[serde(default)]
+    pub debug: DebugConfig,
+    #[serde(default)]
+    pub reload: ReloadConfig,
 }
 
+/// Old single-string hotkey configuration
 #[derive(Debug, Deserialize, Serialize)]
-pub struct HotkeyConfig {
+pub struct LegacyHotkeyConfig {
     pub combination: String,
+    #[serde(default)]
+    pub compose: Option<String>,
+    #[serde(default)]
+    pub compose_cancel: Option<String>,
+}
+
+const DEFAULT_TOGGLE_HOTKEY: &str = "caps";
+
+/// A hotkey given either as a plain string or with per-OS overrides
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum HotkeyBinding {
+    Plain(String),
+    PerOs {
+        default: String,
+        #[serde(default)]
+        linux: Option<String>,
+        #[serde(default)]
+        macos: Option<String>,
+        #[serde(default)]
+        windows: Option<String>,
+    },
+}
+
+impl HotkeyBinding {
+    /// The binding for the OS we were built for
+    pub fn resolve(&self) -> &str {
+        match self {
+            HotkeyBinding::Plain(binding) => binding,
+            HotkeyBinding::PerOs { default, linux, macos, windows } => {
+                let os_binding = if cfg!(target_os = "linux") {
+                    linux
+                } else if cfg!(target_os = "macos") {
+                    macos
+                } else if cfg!(target_os = "windows") {
+                    windows
+                } else {
+                    &None
+                };
+                os_binding.as_deref().unwrap_or(default)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct HotkeysConfig {
+    /// auto | global-hotkey | portal
+    #[serde(default)]
+    pub backend: HotkeyBackend,
+    /// toggle: press to start, press again to stop; hold: record while the key is held
+    #[serde(default)]
+    pub mode: HotkeyMode,
+    /// In hold mode, shorter holds are dropped instead of transcribed
+    #[serde(default = "default_min_hold_ms")]
+    pub min_hold_ms: u64,
+    /// Two presses of toggle_recording within this many ms repeat the last dictation (0 = off)
+    #[serde(default)]
+    pub double_tap_ms: u64,
+    /// "cmd" in a binding means the Command/Super key rather than ctrl (default on macOS)
+    #[serde(default = "default_cmd_is_super")]
+    pub cmd_is_super: bool,
+    /// Starts and stops recording
+    #[serde(default)]
+    pub toggle_recording: Option<HotkeyBinding>,
+    /// Starts compose mode, and on the next press injects the assembled draft
+    #[serde(default)]
+    pub compose: Option<HotkeyBinding>,
+    /// Discards the compose draft (press twice to confirm)
+    #[serde(default)]
+    pub compose_cancel: Option<HotkeyBinding>,
+    /// Re-decodes the last recording
+    #[serde(default)]
+    pub retranscribe: Option<HotkeyBinding>,
+    /// Records like toggle_recording, but edits the last injection in place when the take is similar
+    #[serde(default)]
+    pub correction: Option<HotkeyBinding>,
+    /// Types the last dictation again
+    #[serde(default)]
+    pub repeat_last: Option<HotkeyBinding>,
+    /// Cancels the current recording, or the latest one still being decoded, refined or output
+    #[serde(default)]
+    pub cancel: Option<HotkeyBinding>,
+    /// Records like toggle_recording, skipping text refinement
+    #[serde(default)]
+    pub record_raw: Option<HotkeyBinding>,
+    /// Records like toggle_recording, refining the text even when text_refinement is off
+    #[serde(default)]
+    pub record_refined: Option<HotkeyBinding>,
+}
+
+fn default_min_hold_ms() -> u64 {
+    150
+}
+
+fn default_cmd_is_super() -> bool {
+    cfg!(target_os = "macos")
+}
+
+impl Default for HotkeysConfig {
+    fn default() -> Self {
+        Self {
+            backend: HotkeyBackend::default(),
+            mode: HotkeyMode::default(),
+            min_hold_ms: default_min_hold_ms(),
+            double_tap_ms: 0,
+            cmd_is_super: default_cmd_is_super(),
+            toggle_recording: None,
+            compose: None,
+            compose_cancel: None,
+            retranscribe: None,
+            correction: None,
+            repeat_last: None,
+            cancel: None,
+            record_raw: None,
+            record_refined: None,
+        }
+    }
+}
+
+impl HotkeysConfig {
+    pub fn toggle_recording(&self) -> &str {
+        self.toggle_recording
+            .as_ref()
+            .map(HotkeyBinding::resolve)
+            .unwrap_or(DEFAULT_TOGGLE_HOTKEY)
+    }
+
+    pub fn compose(&self) -> Option<&str> {
+        self.compose.as_ref().map(HotkeyBinding::resolve)
+    }
+
+    pub fn compose_cancel(&self) -> Option<&str> {
+        self.compose_cancel.as_ref().map(HotkeyBinding::resolve)
+    }
+
+    pub fn retranscribe(&self) -> Option<&str> {
+        self.retranscribe.as_ref().map(HotkeyBinding::resolve)
+    }
+
+    pub fn correction(&self) -> Option<&str> {
+        self.correction.as_ref().map(HotkeyBinding::resolve)
+    }
+
+    pub fn repeat_last(&self) -> Option<&str> {
+        self.repeat_last.as_ref().map(HotkeyBinding::resolve)
+    }
+
+    pub fn cancel(&self) -> Option<&str> {
+        self.cancel.as_ref().map(HotkeyBinding::resolve)
+    }
+
+    pub fn record_raw(&self) -> Option<&str> {
+        self.record_raw.as_ref().map(HotkeyBinding::resolve)
+    }
+
+    pub fn record_refined(&self) -> Option<&str> {
+        self.record_refined.as_ref().map(HotkeyBinding::resolve)
+    }
+
+    /// Fill in bindings from the old `[hotkey]` table where the new one doesn't set them
+    fn migrate_legacy(&mut self, legacy: LegacyHotkeyConfig) {
+        info!("Migrating legacy [hotkey] config into [hotkeys]");
+        if self.toggle_recording.is_none() {
+            self.toggle_recording = Some(HotkeyBinding::Plain(legacy.combination));
+        }
+        if self.compose.is_none() {
+            self.compose = legacy.compose.map(HotkeyBinding::Plain);
+        }
+        if self.compose_cancel.is_none() {
+            self.compose_cancel = legacy.compose_cancel.map(HotkeyBinding::Plain);
+        }
+    }
+
+    /// Every configured action with its combination for this OS
+    pub fn bindings(&self) -> Vec<(HotkeyAction, &str)> {
+        [
+            (HotkeyAction::ToggleRecording, Some(self.toggle_recording())),
+            (HotkeyAction::Compose, self.compose()),
+            (HotkeyAction::ComposeCancel, self.compose_cancel()),
+            (HotkeyAction::Retranscribe, self.retranscribe()),
+            (HotkeyAction::Correction, self.correction()),
+            (HotkeyAction::RepeatLast, self.repeat_last()),
+            (HotkeyAction::Cancel, self.cancel()),
+            (HotkeyAction::RecordRaw, self.record_raw()),
+            (HotkeyAction::RecordRefined, self.record_refined()),
+        ]
+        .into_iter()
+        .filter_map(|(action, binding)| binding.map(|binding| (action, binding)))
+        .collect()
+    }
+
+    /// The combination for one action on this OS, if it's bound
+    pub fn binding(&self, action: HotkeyAction) -> Option<&str> {
+        self.bindings()
+            .into_iter()
+            .find(|(bound, _)| *bound == action)
+            .map(|(_, binding)| binding)
+    }
+
+    /// Log and check the bindings resolved for this OS
+    fn validate(&self) -> Result<()> {
+        for (action, binding) in self.bindings() {
+            validate_hotkey_string(binding, self.cmd_is_super)
+                .map_err(|e| anyhow::anyhow!("Invalid hotkey for {}: {}", action.as_str(), e))?;
+            info!("Hotkey {} = {}", action.as_str(), binding);
+        }
+        if self.mode == HotkeyMode::Hold && self.double_tap_ms > 0 {
+            warn!("⚠️  hotkeys.double_tap_ms has no effect in hold mode");
+        }
+        // Taps are reported as a press and an immediate release, always shorter than min_hold_ms
+        if self.mode == HotkeyMode::Hold && TapBinding::parse(self.toggle_recording()).is_some() {
+            warn!("⚠️  hotkeys.mode = \"hold\" can't work with a modifier tap ({}); bind a key combination instead", self.toggle_recording());
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -24,23 +288,134 @@ pub struct AudioConfig {
     pub sample_rate: u32,
     pub channels: u16,
     pub buffer_duration_ms: u32,
+    /// Input device name (substring match) or index from the startup listing; the system default if unset
+    #[serde(default)]
+    pub device: Option<String>,
+    /// Move audio older than this many seconds to a file on disk instead of RAM (0 = never)
+    #[serde(default)]
+    pub spill_after_secs: u32,
+    /// "mix" (average all channels), "left", "right" or "channel:<n>" (from 1)
+    #[serde(default)]
+    pub channel_mode: ChannelMode,
+    /// Fixed gain applied to each recording before transcription, in dB
+    #[serde(default)]
+    pub gain_db: f32,
+    /// Boost quiet recordings towards gain_target_dbfs (on top of gain_db)
+    #[serde(default)]
+    pub auto_gain: bool,
+    #[serde(default = "default_gain_target_dbfs")]
+    pub gain_target_dbfs: f32,
+    /// Gate steady background noise out of each recording before transcription
+    #[serde(default)]
+    pub noise_suppression: bool,
+    /// Audio from this long before the hotkey press is kept at the start of a recording
+    #[serde(default = "default_preroll_ms")]
+    pub preroll_ms: u32,
+    /// Longest a recording may get (0 = no limit)
+    #[serde(default = "default_max_recording_secs")]
+    pub max_recording_secs: u32,
+    /// At max_recording_secs: stop and transcribe, or keep only the most recent audio
+    #[serde(default)]
+    pub overflow_policy: OverflowPolicy,
+}
+
+fn default_gain_target_dbfs() -> f32 {
+    -20.0
+}
+
+fn default_preroll_ms() -> u32 {
+    500
+}
+
+fn default_max_recording_secs() -> u32 {
+    120
+}
+
+impl AudioConfig {
+    /// The channel channel_mode picks must exist on the device it starts with. A device
+    /// switched to later that lacks it is mixed instead.
+    pub fn check_channel_mode(&self, device: &str, channels: u16) -> Result<()> {
+        if self.channel_mode.fits(channels as usize) {
+            return Ok(());
+        }
+        Err(anyhow::anyhow!(
+            "Invalid audio.channel_mode: {} but '{}' has {} channel(s), so channel:1-channel:{} are valid",
+            self.channel_mode,
+            device,
+            channels,
+            channels
+        ))
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct VadConfig {
+    #[serde(default)]
+    pub engine: VadEngine,
     pub model_path: PathBuf,
     pub sensitivity: VadSensitivity,
     pub timeout_ms: u32,
     /// If true, auto-stop recording after silence timeout
     #[serde(default = "default_auto_stop")]
     pub auto_stop: bool,
+    /// Step sensitivity with the ambient noise floor measured between recordings
+    #[serde(default)]
+    pub adaptive: bool,
+    #[serde(default = "default_adaptive_min")]
+    pub adaptive_min: VadSensitivity,
+    #[serde(default = "default_adaptive_max")]
+    pub adaptive_max: VadSensitivity,
+    /// Speech must last this long before it counts, so clicks don't (0 = any voiced frame counts)
+    #[serde(default)]
+    pub min_speech_ms: u32,
+    /// Speech lasts this long past the last voiced frame (0 = ends with it)
+    #[serde(default)]
+    pub hangover_ms: u32,
+    #[serde(default)]
+    pub mode: DictationMode,
+    /// Continuous mode drops utterances with less speech than this
+    #[serde(default = "default_min_segment_ms")]
+    pub min_segment_ms: u32,
+    /// Recordings with a smaller share of speech aren't transcribed (0 = always transcribe)
+    #[serde(default)]
+    pub min_speech_ratio: f32,
+    /// Length of the frames the VAD classifies
+    #[serde(default = "default_frame_ms")]
+    pub frame_ms: u32,
+}
+
+fn default_frame_ms() -> u32 {
+    32
+}
+
+impl VadConfig {
+    /// Against the rate capture delivers, which is what the VAD sees
+    fn validate(&self) -> Result<()> {
+        vad::vad_rate(self.engine, TARGET_RATE, self.frame_ms)
+            .map(|_| ())
+            .map_err(|e| anyhow::anyhow!("Invalid [vad] settings: {}", e))
+    }
+}
+
+fn default_min_segment_ms() -> u32 {
+    400
+}
+
+fn default_adaptive_min() -> VadSensitivity {
+    VadSensitivity::Low
+}
+
+fn default_adaptive_max() -> VadSensitivity {
+    VadSensitivity::VeryHigh
 }
 
 fn default_auto_stop() -> bool {
     true
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+/// Ordered from least to most sensitive. A more sensitive VAD takes quieter or less
+/// certain sounds for speech, i.e. it uses a lower Silero speech threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize, clap::ValueEnum)]
 pub enum VadSensitivity {
     Low,
     Normal,
@@ -49,22 +424,31 @@ pub enum VadSensitivity {
 }
 
 impl VadSensitivity {
-    pub fn to_threshold(&self) -> f32 {
+    /// Silero speech probability threshold; Normal keeps Silero's own default of 0.5
+    pub fn to_threshold(self) -> f32 {
         match self {
-            VadSensitivity::Low => 0.3,
+            VadSensitivity::Low => 0.7,
             VadSensitivity::Normal => 0.5,
-            VadSensitivity::High => 0.7,
-            VadSensitivity::VeryHigh => 0.85,
+            VadSensitivity::High => 0.3,
+            VadSensitivity::VeryHigh => 0.15,
+        }
+    }
+
+    /// One step towards a higher speech threshold
+    pub fn stricter(self) -> Self {
+        match self {
+            VadSensitivity::Low | VadSensitivity::Normal => VadSensitivity::Low,
+            VadSensitivity::High => VadSensitivity::Normal,
+            VadSensitivity::VeryHigh => VadSensitivity::High,
         }
     }
 
-    // Keep for backward compatibility
-    pub fn to_webrtc_mode(&self) -> i32 {
+    /// One step towards a lower speech threshold
+    pub fn looser(self) -> Self {
         match self {
-            VadSensitivity::Low => 0,
-            VadSensitivity::Normal => 1,
-            VadSensitivity::High => 2,
-            VadSensitivity::VeryHigh => 3,
+            VadSensitivity::Low => VadSensitivity::Normal,
+            VadSensitivity::Normal => VadSensitivity::High,
+            VadSensitivity::High | VadSensitivity::VeryHigh => VadSensitivity::VeryHigh,
         }
     }
 }
@@ -73,46 +457,745 @@ impl VadSensitivity {
 pub struct SpeechConfig {
     /// Directory containing the Parakeet model files
     pub model_dir: PathBuf,
+    /// Decode with the local model or a remote endpoint ([remote_transcription])
+    #[serde(default)]
+    pub backend: SpeechBackend,
+    /// Downloadable model by name, kept in the cache directory; replaces model_dir
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Download the model at startup if its files are missing
+    #[serde(default)]
+    pub auto_download: bool,
+    /// SHA256 the auto-downloaded archive must have, over the built-in one if it has any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_sha256: Option<String>,
     pub language: String,
+    /// Warn at startup if free memory after loading the model would fall below this
+    #[serde(default = "default_min_memory_headroom_mb")]
+    pub min_memory_headroom_mb: u64,
+    /// Run decoding at lower CPU priority so the desktop stays responsive
+    #[serde(default)]
+    pub background_priority: bool,
+    /// Restrict decoding to these CPU cores (Linux); empty = any core
+    #[serde(default)]
+    pub cpu_affinity: Vec<usize>,
+    /// Run the model on the GPU when built with an accelerator feature
+    #[serde(default)]
+    pub use_gpu: bool,
+    /// Decode threads; 0 = one per physical core
+    #[serde(default)]
+    pub threads: usize,
+    /// Recordings that can wait for the decoder before decode_queue_overflow applies
+    #[serde(default = "default_decode_queue_depth")]
+    pub decode_queue_depth: usize,
+    #[serde(default)]
+    pub decode_queue_overflow: QueueOverflow,
+    /// Decode the recording so far every partial_interval_ms while recording
+    #[serde(default)]
+    pub partials: bool,
+    #[serde(default = "default_partial_interval_ms")]
+    pub partial_interval_ms: u64,
+    /// Reload the model when its files change on disk (e.g. a swapped symlink)
+    #[serde(default)]
+    pub watch_model: bool,
+    /// Sentences that are only one of these phrases are dropped from transcripts
+    #[serde(default = "default_hallucination_blocklist")]
+    pub hallucination_blocklist: Vec<String>,
+}
+
+impl SpeechConfig {
+    pub fn decode_policy(&self) -> DecodePolicy {
+        DecodePolicy {
+            background_priority: self.background_priority,
+            cpu_affinity: self.cpu_affinity.clone(),
+            use_gpu: self.use_gpu,
+            threads: self.threads,
+            beam_size: 0,
+        }
+    }
+}
+
+fn default_min_memory_headroom_mb() -> u64 {
+    512
+}
+
+fn default_decode_queue_depth() -> usize {
+    8
+}
+
+fn default_partial_interval_ms() -> u64 {
+    2000
+}
+
+fn default_hallucination_blocklist() -> Vec<String> {
+    hallucination::DEFAULT_BLOCKLIST.iter().map(|phrase| phrase.to_string()).collect()
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct TextConfig {
     pub typing_delay_ms: u64,
+    /// How keystrokes are sent: enigo, or wtype/ydotool for Wayland windows
+    #[serde(default)]
+    pub backend: TextBackend,
+    /// Log the text (and emit injection_skipped) instead of typing it
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Restore punctuation in unpunctuated transcripts
+    #[serde(default)]
+    pub punctuate: PunctuateMode,
+    /// Format numbers, currencies and units for the typing locale
+    #[serde(default)]
+    pub normalize_numbers: bool,
+    /// Locale for number formatting when it differs from speech.language (e.g. "de-CH")
+    #[serde(default)]
+    pub number_locale: Option<String>,
+    /// Turn "spell alpha bravo seven end spell" into "ab7"
+    #[serde(default)]
+    pub spelling: bool,
+    /// Terms to write as given when the recognizer splits or mis-cases them, or "heard => term" fixes
+    #[serde(default)]
+    pub vocabulary: Vec<String>,
+    /// More vocabulary, one entry per line; re-read when it changes
+    #[serde(default)]
+    pub vocabulary_file: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct IndicatorConfig {
+    /// Set the terminal title to "● REC tomchat" while recording
+    #[serde(default)]
+    pub terminal_title: bool,
+    /// Light the ScrollLock LED while recording (Linux, needs write access to the device)
+    #[serde(default)]
+    pub scroll_lock_led: bool,
+    /// Keyboard event device for the LED; auto-detected from /dev/input/by-path if unset
+    #[serde(default)]
+    pub led_device: Option<PathBuf>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct OutputConfig {
+    /// Where transcriptions go, and which rendition of the text each sink receives
+    #[serde(default = "default_sinks")]
+    pub sinks: Vec<SinkConfig>,
+    /// What "templated" sinks receive; {text}, {raw} and {time} are filled in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        Self {
+            sinks: default_sinks(),
+            template: None,
+        }
+    }
+}
+
+impl OutputConfig {
+    fn validate(&self) -> Result<()> {
+        for sink in &self.sinks {
+            if sink.kind == SinkKind::File && sink.path.is_none() {
+                return Err(anyhow::anyhow!("File sink needs a path, e.g. {{ kind = \"file\", path = \"./dictations.jsonl\" }}"));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RetranscribeConfig {
+    /// Alternate model directory to use for re-decodes (defaults to the main model)
+    #[serde(default)]
+    pub model_dir: Option<PathBuf>,
+    /// Recordings longer than this aren't retained
+    #[serde(default = "default_retain_max_secs")]
+    pub max_secs: u32,
+    /// How long the last recording is kept in memory
+    #[serde(default = "default_retain_ttl_secs")]
+    pub ttl_secs: u64,
+    /// Replace the previous injection with the re-decoded text
+    #[serde(default)]
+    pub replace_previous: bool,
+}
+
+fn default_retain_max_secs() -> u32 {
+    120
+}
+
+fn default_retain_ttl_secs() -> u64 {
+    300
+}
+
+impl Default for RetranscribeConfig {
+    fn default() -> Self {
+        Self {
+            model_dir: None,
+            max_secs: default_retain_max_secs(),
+            ttl_secs: default_retain_ttl_secs(),
+            replace_previous: false,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CorrectionConfig {
+    /// Word-level similarity (0-1) above which a correction take edits the last injection
+    #[serde(default = "default_similarity_threshold")]
+    pub similarity_threshold: f32,
+}
+
+fn default_similarity_threshold() -> f32 {
+    0.6
+}
+
+impl Default for CorrectionConfig {
+    fn default() -> Self {
+        Self {
+            similarity_threshold: default_similarity_threshold(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct HistoryConfig {
+    /// Dictations kept across restarts for repeat_last; 0 disables the state file
+    #[serde(default = "default_session_entries")]
+    pub session_entries: usize,
+    /// Run persisted text through the redaction patterns
+    #[serde(default)]
+    pub redact_session_state: bool,
+}
+
+fn default_session_entries() -> usize {
+    10
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            session_entries: default_session_entries(),
+            redact_session_state: false,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FormFillConfig {
+    /// Split typed text into form fields at the separator phrase
+    #[serde(default)]
+    pub enabled: bool,
+    /// Spoken phrase that moves to the next field
+    #[serde(default = "default_form_separator")]
+    pub separator: String,
+    /// Key pressed between fields: tab, enter, down, right, ...
+    #[serde(default = "default_form_key")]
+    pub key: String,
+    /// Overrides for when a preset is active, keyed by preset name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<Preset, FormFillProfile>,
+}
+
+/// Form fill settings for one preset; unset fields fall back to [form_fill]
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct FormFillProfile {
+    #[serde(default)]
+    pub enabled: Option<bool>,
+    #[serde(default)]
+    pub separator: Option<String>,
+    #[serde(default)]
+    pub key: Option<String>,
+}
+
+impl FormFillConfig {
+    /// Separator and key for the active preset, None when form fill is off for it
+    pub fn for_profile(&self, preset: Option<Preset>) -> Option<(&str, &str)> {
+        let profile = preset.and_then(|preset| self.profiles.get(&preset));
+        if !profile.and_then(|profile| profile.enabled).unwrap_or(self.enabled) {
+            return None;
+        }
+        let separator = profile.and_then(|profile| profile.separator.as_deref()).unwrap_or(&self.separator);
+        let key = profile.and_then(|profile| profile.key.as_deref()).unwrap_or(&self.key);
+        Some((separator, key))
+    }
+}
+
+fn default_form_separator() -> String {
+    "next field".to_string()
+}
+
+fn default_form_key() -> String {
+    "tab".to_string()
+}
+
+impl Default for FormFillConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            separator: default_form_separator(),
+            key: default_form_key(),
+            profiles: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GuiConfig {
+    /// Endpoints events are replicated to. Defaults to the bubble's state file.
+    #[serde(default = "default_push_subscribers")]
+    pub push: Vec<PushSubscriberConfig>,
+    /// Length (in grapheme clusters) of text previews in event messages
+    #[serde(default = "default_preview_graphemes")]
+    pub preview_graphemes: usize,
+    /// Put the full text in event payloads next to the preview. Off by default so
+    /// GUI output and push subscribers only see the truncated preview.
+    #[serde(default)]
+    pub full_text: bool,
+    /// Also send the final text split into sentences ("transcription_rich" events)
+    #[serde(default)]
+    pub rich_transcription: bool,
+}
+
+fn default_preview_graphemes() -> usize {
+    120
+}
+
+impl Default for GuiConfig {
+    fn default() -> Self {
+        Self {
+            push: default_push_subscribers(),
+            preview_graphemes: default_preview_graphemes(),
+            full_text: false,
+            rich_transcription: false,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DebugConfig {
+    /// Save every recording here as a WAV before it's transcribed
+    #[serde(default)]
+    pub save_audio_dir: Option<PathBuf>,
+    /// Oldest saved recordings are deleted beyond this many; 0 keeps them all
+    #[serde(default = "default_max_saved_files")]
+    pub max_saved_files: usize,
+}
+
+fn default_max_saved_files() -> usize {
+    100
+}
+
+impl Default for DebugConfig {
+    fn default() -> Self {
+        Self {
+            save_audio_dir: None,
+            max_saved_files: default_max_saved_files(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ReloadConfig {
+    /// Apply edits to config.toml while running
+    #[serde(default)]
+    pub watch: bool,
+    /// How long the file must stay unchanged before it's read
+    #[serde(default = "default_reload_quiet_ms")]
+    pub quiet_ms: u64,
+}
+
+fn default_reload_quiet_ms() -> u64 {
+    300
+}
+
+impl Default for ReloadConfig {
+    fn default() -> Self {
+        Self {
+            watch: false,
+            quiet_ms: default_reload_quiet_ms(),
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            preset: None,
+            privacy: PrivacyConfig::default(),
+            hotkey: None,
+            hotkeys: HotkeysConfig {
+                toggle_recording: Some(HotkeyBinding::Plain(DEFAULT_TOGGLE_HOTKEY.to_string())),
+                ..Default::default()
+            },
+            audio: AudioConfig::default(),
+            vad: VadConfig::default(),
+            speech: SpeechConfig::default(),
+            remote_transcription: RemoteTranscriptionConfig::default(),
+            text: TextConfig::default(),
+            text_refinement: Some(TextRefinementConfig {
+                enabled: false,
+                ..Default::default()
+            }),
+            indicator: IndicatorConfig::default(),
+            output: OutputConfig::default(),
+            retranscribe: RetranscribeConfig::default(),
+            correction: CorrectionConfig::default(),
+            history: HistoryConfig::default(),
+            form_fill: FormFillConfig::default(),
+            gui: GuiConfig::default(),
+            debug: DebugConfig::default(),
+            reload: ReloadConfig::default(),
+        }
+    }
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        Self {
+            sample_rate: 16000,
+            channels: 1,
+            buffer_duration_ms: 64,
+            device: None,
+            spill_after_secs: 0,
+            channel_mode: ChannelMode::Mix,
+            gain_db: 0.0,
+            auto_gain: false,
+            gain_target_dbfs: default_gain_target_dbfs(),
+            noise_suppression: false,
+            preroll_ms: default_preroll_ms(),
+            max_recording_secs: default_max_recording_secs(),
+            overflow_policy: OverflowPolicy::Stop,
+        }
+    }
+}
+
+impl Default for VadConfig {
+    fn default() -> Self {
+        Self {
+            engine: VadEngine::default(),
+            model_path: PathBuf::from("./models/silero_vad.onnx"),
+            sensitivity: VadSensitivity::Normal,
+            timeout_ms: 1500,
+            auto_stop: default_auto_stop(),
+            adaptive: false,
+            adaptive_min: default_adaptive_min(),
+            adaptive_max: default_adaptive_max(),
+            min_speech_ms: 0,
+            hangover_ms: 0,
+            mode: DictationMode::default(),
+            min_segment_ms: default_min_segment_ms(),
+            min_speech_ratio: 0.0,
+            frame_ms: default_frame_ms(),
+        }
+    }
+}
+
+impl Default for SpeechConfig {
+    fn default() -> Self {
+        Self {
+            model_dir: PathBuf::from("./models/sherpa-onnx-nemo-parakeet-tdt-0.6b-v2-int8"),
+            backend: SpeechBackend::Local,
+            model: None,
+            auto_download: false,
+            model_sha256: None,
+            language: "en".to_string(),
+            min_memory_headroom_mb: default_min_memory_headroom_mb(),
+            background_priority: false,
+            cpu_affinity: Vec::new(),
+            use_gpu: false,
+            threads: 0,
+            decode_queue_depth: default_decode_queue_depth(),
+            decode_queue_overflow: QueueOverflow::default(),
+            partials: false,
+            partial_interval_ms: default_partial_interval_ms(),
+            watch_model: false,
+            hallucination_blocklist: default_hallucination_blocklist(),
+        }
+    }
+}
+
+impl Default for TextConfig {
+    fn default() -> Self {
+        Self {
+            typing_delay_ms: 1,
+            backend: TextBackend::Auto,
+            dry_run: false,
+            punctuate: PunctuateMode::Off,
+            normalize_numbers: false,
+            number_locale: None,
+            spelling: false,
+            vocabulary: Vec::new(),
+            vocabulary_file: None,
+        }
+    }
+}
+
+/// Write one setting back to config.toml, keeping the rest of the file (comments included) as is
+pub fn save_setting(section: &str, key: &str, value: &str) -> Result<()> {
+    let config_path = std::env::current_dir()?.join("config.toml");
+    let mut document: toml_edit::DocumentMut = std::fs::read_to_string(&config_path)?.parse()?;
+
+    if !document.contains_table(section) {
+        document.insert(section, toml_edit::table());
+    }
+    document[section][key] = toml_edit::value(value);
+
+    let tmp = config_path.with_extension("toml.tmp");
+    std::fs::write(&tmp, document.to_string())?;
+    std::fs::rename(&tmp, &config_path)?;
+    info!("Saved {}.{} = {:?} to {:?}", section, key, value, config_path);
+    Ok(())
 }
 
 impl Config {
+    /// Reject settings that need the network when privacy.local_only is set
+    fn validate_local_only(&self) -> Result<()> {
+        if !self.privacy.local_only {
+            return Ok(());
+        }
+        if let Some(ref refinement) = self.text_refinement {
+            if refinement.enabled && !privacy::is_loopback(&refinement.ollama_url) {
+                return Err(anyhow::anyhow!(
+                    "privacy.local_only is set but text_refinement.ollama_url ({}) is not on this machine",
+                    refinement.ollama_url
+                ));
+            }
+        }
+        if self.speech.backend == SpeechBackend::Remote && !privacy::is_loopback(&self.remote_transcription.url) {
+            return Err(anyhow::anyhow!(
+                "privacy.local_only is set but remote_transcription.url ({}) is not on this machine",
+                self.remote_transcription.url
+            ));
+        }
+        for subscriber in &self.gui.push {
+            if !subscriber.url.starts_with("file://") && !privacy::is_loopback(&subscriber.url) {
+                return Err(anyhow::anyhow!(
+                    "privacy.local_only is set but push subscriber {} is not on this machine",
+                    subscriber.url
+                ));
+            }
+        }
+        Ok(())
+    }
+
     pub fn load() -> Result<Self> {
-        let config_path = std::env::current_dir()?.join("config.toml");
-        let config_str = std::fs::read_to_string(&config_path)?;
-        let mut config: Config = toml::from_str(&config_str)?;
+        let config = Self::read()?;
+        privacy::set_local_only(config.privacy.local_only);
+        Ok(config)
+    }
+
+    /// Parse and validate config.toml without changing anything, e.g. for a hot reload
+    pub fn read() -> Result<Self> {
+        let base_dir = std::env::current_dir()?;
+        let config_str = std::fs::read_to_string(base_dir.join("config.toml"))?;
+        Self::parse(&config_str, &base_dir)
+    }
+
+    /// Everything `read` does past reading the file: presets, migrations, environment
+    /// overrides, validation, and relative paths resolved against `base_dir`
+    pub fn parse(config_str: &str, base_dir: &Path) -> Result<Self> {
+        let mut table: toml::Table = toml::from_str(config_str)?;
+
+        // Presets are a base layer: explicit settings in the file win
+        if let Some(preset_value) = table.get("preset").cloned() {
+            let preset: Preset = preset_value.try_into()?;
+            preset::apply(preset, &mut table);
+        }
+
+        let mut config: Config = toml::Value::Table(table).try_into()?;
+
+        if let Some(ref model) = config.speech.model {
+            config.speech.model_dir = download::speech_model_dir(model)?;
+        }
 
         // Override with environment variables if set
-        if let Ok(model_dir) = std::env::var("TOMCHAT_MODEL_DIR") {
-            config.speech.model_dir = PathBuf::from(model_dir);
+        let explicit_model_dir = std::env::var_os("TOMCHAT_MODEL_DIR").map(PathBuf::from);
+        if let Some(ref model_dir) = explicit_model_dir {
+            config.speech.model_dir = model_dir.clone();
+        }
+
+        if let Some(legacy) = config.hotkey.take() {
+            config.hotkeys.migrate_legacy(legacy);
         }
 
         if let Ok(hotkey) = std::env::var("TOMCHAT_HOTKEY") {
-            config.hotkey.combination = hotkey;
+            config.hotkeys.toggle_recording = Some(HotkeyBinding::Plain(hotkey));
         }
 
-        // Expand relative paths to absolute
-        let base_dir = std::env::current_dir()?;
+        config.hotkeys.validate()?;
+        config.vad.validate()?;
+        config.output.validate()?;
+        config.validate_local_only()?;
 
+        // Expand relative paths to absolute
         if config.speech.model_dir.is_relative() {
             config.speech.model_dir = base_dir.join(&config.speech.model_dir);
         }
 
+        if let Some(ref model_dir) = config.retranscribe.model_dir {
+            if model_dir.is_relative() {
+                config.retranscribe.model_dir = Some(base_dir.join(model_dir));
+            }
+        }
+
         if config.vad.model_path.is_relative() {
             config.vad.model_path = base_dir.join(&config.vad.model_path);
         }
 
-        // Validate model directory exists
-        if !config.speech.model_dir.exists() {
-            eprintln!("Model directory not found: {:?}", config.speech.model_dir);
-            eprintln!("Run: scripts/download-parakeet.sh to download the model");
+        // Fall back along the search path when model_dir has no model; auto_download fills model_dir itself
+        let located = if explicit_model_dir.is_some() {
+            locate::require_model(&config.speech.model_dir)
+        } else {
+            locate::locate_model(&config.speech.model_dir, base_dir)
+        };
+        match located {
+            Ok(found) if found != config.speech.model_dir => {
+                info!("No model in {:?}, using {:?}", config.speech.model_dir, found);
+                config.speech.model_dir = found;
+            }
+            Ok(_) => {}
+            Err(_) if config.speech.auto_download => {}
+            Err(e) if explicit_model_dir.is_some() => {
+                return Err(anyhow::Error::new(e).context("TOMCHAT_MODEL_DIR doesn't hold a speech model"));
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                eprintln!("Run: scripts/download-parakeet.sh to download the model, or set speech.auto_download = true");
+            }
         }
 
         Ok(config)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn form_fill_follows_the_active_preset() {
+        let form_fill: FormFillConfig = toml::from_str(
+            r#"
+            separator = "next"
+            [profiles.accurate]
+            enabled = true
+            key = "enter"
+            [profiles.fastest]
+            enabled = false
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(form_fill.for_profile(None), None);
+        assert_eq!(form_fill.for_profile(Some(Preset::Accurate)), Some(("next", "enter")));
+        assert_eq!(form_fill.for_profile(Some(Preset::Fastest)), None);
+
+        let enabled = FormFillConfig { enabled: true, ..form_fill };
+        assert_eq!(enabled.for_profile(Some(Preset::Balanced)), Some(("next", "tab")));
+        assert_eq!(enabled.for_profile(Some(Preset::Fastest)), None);
+    }
+
+    #[test]
+    fn vad_debouncing_is_off_unless_asked_for() {
+        let existing = "model_path = \"./models/silero_vad.onnx\"\nsensitivity = \"Normal\"\ntimeout_ms = 1500";
+        let vad: VadConfig = toml::from_str(existing).unwrap();
+        assert_eq!((vad.min_speech_ms, vad.hangover_ms), (0, 0));
+
+        let opted_in: VadConfig = toml::from_str(&format!("{}\nmin_speech_ms = 64\nhangover_ms = 96", existing)).unwrap();
+        assert_eq!((opted_in.min_speech_ms, opted_in.hangover_ms), (64, 96));
+        assert_eq!((Config::default().vad.min_speech_ms, Config::default().vad.hangover_ms), (0, 0));
+    }
+
+    #[test]
+    fn channel_mode_reads_and_writes_its_config_form() {
+        let audio = |mode: &str| toml::from_str::<AudioConfig>(&format!(
+            "sample_rate = 16000\nchannels = 1\nbuffer_duration_ms = 100\nchannel_mode = \"{}\"",
+            mode
+        ));
+        assert_eq!(audio("Right").unwrap().channel_mode, ChannelMode::Channel(1));
+        assert_eq!(audio("channel:3").unwrap().channel_mode, ChannelMode::Channel(2));
+        let error = audio("channel:0").unwrap_err().to_string();
+        assert!(error.contains("channel_mode"), "{}", error);
+
+        let saved = toml::to_string(&audio("channel:3").unwrap()).unwrap();
+        assert!(saved.contains("channel_mode = \"channel:3\""), "{}", saved);
+    }
+
+    #[test]
+    fn the_selected_channel_must_exist_on_the_device() {
+        let audio = |mode: &str| toml::from_str::<AudioConfig>(&format!(
+            "sample_rate = 16000\nchannels = 1\nbuffer_duration_ms = 100\nchannel_mode = \"{}\"",
+            mode
+        ))
+        .unwrap();
+        // An array mic's fourth channel, counted from 1 like everywhere else
+        assert!(audio("channel:4").check_channel_mode("Array", 7).is_ok());
+        let error = audio("channel:8").check_channel_mode("Array", 7).unwrap_err().to_string();
+        assert!(error.contains("'Array' has 7 channel(s), so channel:1-channel:7"), "{}", error);
+
+        assert!(audio("mix").check_channel_mode("Headset", 1).is_ok());
+        assert!(audio("right").check_channel_mode("Headset", 1).is_err());
+        // A removed 0-based audio.channel is ignored rather than read as 1-based
+        let old: AudioConfig = toml::from_str("sample_rate = 16000\nchannels = 1\nbuffer_duration_ms = 100\nchannel = 3").unwrap();
+        assert_eq!(old.channel_mode, ChannelMode::Mix);
+    }
+
+    /// Register every binding the way startup does, returning what each combination dispatches to
+    fn dispatch(hotkeys: &HotkeysConfig, pressed: &[&str]) -> Vec<Option<HotkeyAction>> {
+        use crate::input::hotkey::{parse_hotkey_string, HotkeyManager};
+        use crate::input::HotkeyRouter;
+
+        let mut manager = HotkeyManager::detached(false);
+        let mut router = HotkeyRouter::default();
+        for (action, combination) in hotkeys.bindings() {
+            let id = manager.register_hotkey(combination).unwrap();
+            router.bind(id, action, combination).unwrap();
+        }
+        pressed
+            .iter()
+            .map(|combination| router.route(parse_hotkey_string(combination, false).unwrap().id()))
+            .collect()
+    }
+
+    #[test]
+    fn each_binding_dispatches_to_its_own_action() {
+        let hotkeys: HotkeysConfig = toml::from_str(
+            r#"
+            toggle_recording = "ctrl+alt+space"
+            cancel = "ctrl+alt+escape"
+            retranscribe = "ctrl+alt+r"
+            record_raw = "ctrl+alt+shift+space"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            dispatch(&hotkeys, &["ctrl+alt+space", "ctrl+alt+escape", "ctrl+alt+r", "ctrl+alt+shift+space", "ctrl+alt+x"]),
+            [
+                Some(HotkeyAction::ToggleRecording),
+                Some(HotkeyAction::Cancel),
+                Some(HotkeyAction::Retranscribe),
+                Some(HotkeyAction::RecordRaw),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn the_legacy_combination_still_toggles_recording() {
+        let legacy: LegacyHotkeyConfig = toml::from_str(r#"combination = "ctrl+shift+d""#).unwrap();
+        let mut hotkeys: HotkeysConfig = toml::from_str(r#"cancel = "ctrl+shift+escape""#).unwrap();
+        hotkeys.migrate_legacy(legacy);
+
+        assert_eq!(
+            dispatch(&hotkeys, &["ctrl+shift+d", "ctrl+shift+escape"]),
+            [Some(HotkeyAction::ToggleRecording), Some(HotkeyAction::Cancel)]
+        );
+
+        // An explicit toggle_recording wins over the legacy table
+        let legacy: LegacyHotkeyConfig = toml::from_str(r#"combination = "ctrl+shift+d""#).unwrap();
+        let mut hotkeys: HotkeysConfig = toml::from_str(r#"toggle_recording = "ctrl+shift+t""#).unwrap();
+        hotkeys.migrate_legacy(legacy);
+        assert_eq!(dispatch(&hotkeys, &["ctrl+shift+t", "ctrl+shift+d"]), [Some(HotkeyAction::ToggleRecording), None]);
+    }
+}
@@ -0,0 +1,220 @@
+use anyhow::Result;
+use tracing::warn;
+
+use crate::config::Config;
+
+/// Description for every config field, keyed by `section.field`, with an
+/// example for optional fields that are unset by default
+const FIELD_DOCS: &[(&str, &str, Option<&str>)] = &[
+    ("preset", "Speed/accuracy preset applied under explicit settings: fastest | balanced | accurate", Some("\"balanced\"")),
+    ("privacy.local_only", "Never send audio or text off this machine: rejects remote Ollama URLs, remote push subscribers, remote transcription endpoints and model downloads", None),
+    ("hotkeys.backend", "How hotkeys are grabbed: auto (portal on Wayland), global-hotkey or portal", None),
+    ("hotkeys.mode", "toggle: press to start recording, press again to stop; hold: record while toggle_recording (or correction) is held and transcribe on release", None),
+    ("hotkeys.min_hold_ms", "In hold mode, a hold shorter than this is treated as an accidental press and its audio is dropped", None),
+    ("hotkeys.double_tap_ms", "Toggle mode: pressing toggle_recording twice within this many milliseconds types the last dictation again. A single press then starts recording only once the window has passed; 0 turns double-tap off and adds no delay", None),
+    ("hotkeys.cmd_is_super", "Whether \"cmd\" in a binding means the Command/Super key (true) or is another name for ctrl (false). Defaults to true on macOS and false elsewhere", None),
+    ("hotkeys.toggle_recording", "Starts and stops recording. Plain string or per-OS table, e.g. { default = \"ctrl+shift+space\", macos = \"ctrl+alt+space\" }. Keys without a name can be given as key:F19 (W3C code name) or code:0x6e (USB HID usage)", None),
+    ("hotkeys.compose", "Starts compose mode; the next press injects the assembled draft", Some("\"ctrl+shift+c\"")),
+    ("hotkeys.compose_cancel", "Discards the compose draft (press twice to confirm)", Some("\"ctrl+shift+x\"")),
+    ("hotkeys.retranscribe", "Re-decodes the last recording", Some("\"ctrl+shift+r\"")),
+    ("hotkeys.correction", "Records like toggle_recording, but edits the last injection when the new take is similar", Some("\"ctrl+shift+e\"")),
+    ("hotkeys.repeat_last", "Types the last dictation again (also after a restart)", Some("\"ctrl+shift+v\"")),
+    ("hotkeys.cancel", "Cancels the current recording, or the latest one still being decoded, refined or typed; nothing reaches any sink", Some("\"ctrl+shift+escape\"")),
+    ("hotkeys.record_raw", "Records like toggle_recording, but types the text without text refinement", Some("\"ctrl+alt+space\"")),
+    ("hotkeys.record_refined", "Records like toggle_recording, but always runs the text through the refiner, even with text_refinement.enabled off", Some("\"ctrl+alt+shift+space\"")),
+    ("audio.sample_rate", "Capture sample rate in Hz", None),
+    ("audio.channels", "Number of capture channels", None),
+    ("audio.buffer_duration_ms", "Requested audio callback size in milliseconds (0 = driver default); falls back if the device rejects it", None),
+    ("audio.device", "Input device name, matched exactly, then ignoring case, then by a prefix or substring only one device has, or its index in the list logged at startup; the system default (with a warning) if unset or missing. Set by the GUI's set_audio_device command", Some("\"USB Microphone\"")),
+    ("audio.channel_mode", "How multichannel input becomes mono: mix (average all channels), left, right, or channel:<n> counting from 1 for interfaces with the mic on one input, or an array mic's beamformed output. The channel must exist on the device at startup (channel counts are in the startup device list); a device switched to later without it is mixed", Some("\"channel:4\"")),
+    ("audio.gain_db", "Fixed gain in dB applied to each recording before transcription, for quiet microphones; lowered if it would clip", None),
+    ("audio.auto_gain", "Measure each recording's RMS level and boost it towards gain_target_dbfs (at most +30 dB, never past clipping)", None),
+    ("audio.gain_target_dbfs", "Level auto_gain aims for, in dBFS", None),
+    ("audio.noise_suppression", "Remove steady background noise (fans, hum) from each recording before transcription, measured from its quietest moments; adds ~10ms per 10s of audio", None),
+    ("audio.preroll_ms", "Audio kept from just before the hotkey press, so the first word isn't clipped when you start talking early (0 = none)", None),
+    ("audio.max_recording_secs", "Longest a recording may get, so a forgotten recording can't grow forever (0 = no limit)", None),
+    ("audio.overflow_policy", "At max_recording_secs: stop (stop and transcribe) or rolling (keep recording, dropping the oldest audio; disables spilling)", None),
+    ("audio.spill_after_secs", "For very long recordings: once this many seconds are buffered, move them to a file under the cache dir (0 = keep everything in memory). With debug.save_audio_dir set, the file is kept there after transcription", None),
+    ("vad.engine", "silero: the Silero model, falling back to energy detection if it fails to load (reported with a vad_degraded event) or errors on a frame; energy: loudness above the adapting noise floor only, no model needed", None),
+    ("vad.model_path", "Silero VAD model file", None),
+    ("vad.sensitivity", "Low, Normal, High or VeryHigh; higher catches quieter speech but also more noise (Silero threshold 0.7, 0.5, 0.3, 0.15)", None),
+    ("vad.timeout_ms", "Stop recording this long after the last speech", None),
+    ("vad.auto_stop", "Auto-stop recording when silence is detected", None),
+    ("vad.adaptive", "Step sensitivity with the ambient noise floor measured between recordings", None),
+    ("vad.adaptive_min", "Least sensitive setting adaptation may choose (used in the noisiest rooms)", None),
+    ("vad.adaptive_max", "Most sensitive setting adaptation may choose (used in the quietest rooms)", None),
+    ("vad.frame_ms", "Length of each frame the VAD classifies: 32, 64 or 96 with the silero engine; 10, 20, 30 or 32 with energy, where shorter frames notice the end of speech sooner. Input at a rate the engine can't frame (e.g. 44.1kHz) is resampled to the nearest one it can", None),
+    ("vad.min_speech_ms", "Voiced frames must add up to this long in a row before they count as speech, so clicks and pops don't; rounded up to whole frames. 0 (the default) counts every voiced frame", Some("64")),
+    ("vad.hangover_ms", "Speech continues this long after the last voiced frame, bridging short gaps between words. 0 (the default) ends it with the frame", Some("96")),
+    ("vad.mode", "single: one recording per press; continuous: the recording stays open until the hotkey is pressed again, and each utterance is transcribed and typed once the VAD hears timeout_ms of silence after it", None),
+    ("vad.min_segment_ms", "In continuous mode, utterances with less speech than this (not counting the silence that ended them) are dropped", None),
+    ("vad.min_speech_ratio", "Skip transcribing a recording when less than this share of it (0.0-1.0) was speech, e.g. 0.05 for an accidental press that caught only silence, which decoders tend to fill with \"Thank you.\"; emits no_speech_detected. The VAD runs for this even with auto_stop off. 0 = always transcribe", None),
+    ("speech.model_dir", "Directory containing the Parakeet model files. If it has none, a folder of the same name is used from ./models, $XDG_DATA_HOME/tomchat/models, the model cache, then /usr/share/tomchat/models. TOMCHAT_MODEL_DIR overrides it, and must hold the model itself", None),
+    ("speech.model", "A downloadable model by name (see tomchat download-model), kept in ~/.cache/tomchat/models (or $XDG_CACHE_HOME/tomchat/models); replaces model_dir", Some("\"parakeet\"")),
+    ("speech.auto_download", "Download the model at startup if its files are missing, with model_download_progress events; needs speech.model, or model_dir left at a downloadable model's folder name", None),
+    ("speech.model_sha256", "SHA256 the auto-downloaded model archive must match before it's unpacked (a mismatch deletes it). Takes precedence over the checksum built in for the model; without either the download is only checked against the size the server reports, with a warning", Some("\"<sha256 of the .tar.bz2>\"")),
+    ("speech.language", "Transcription language", None),
+    ("speech.min_memory_headroom_mb", "Warn at startup if less memory than this remains after loading the model", None),
+    ("speech.background_priority", "Decode at lower CPU priority so the desktop doesn't stutter: nice 10 on Linux, background QoS on macOS, below-normal on Windows; applies to tomchat's decode thread only", None),
+    ("speech.use_gpu", "Run the model on the GPU: CUDA or DirectML when built with --features cuda or directml, CoreML on macOS. Falls back to the CPU with a warning if the GPU can't be used; compare the RTF in the transcription log to see the difference", None),
+    ("speech.threads", "Threads the model decodes with. 0 = one per physical core (hyperthreads don't speed it up), or per core in cpu_affinity when that's set; lower it on big.LITTLE CPUs to keep decoding off the efficiency cores. Before this setting existed decoding always used 4 threads, so machines with more or fewer cores now decode with a different count; set 4 to get the old behavior back", Some("4")),
+    ("speech.decode_queue_depth", "Recordings are decoded one at a time, in the order they were stopped. Up to this many can wait their turn (each emits transcription_queued); past that, decode_queue_overflow applies", None),
+    ("speech.decode_queue_overflow", "When a recording stops with the queue full: reject (it isn't decoded) or drop_oldest (the longest-waiting recording is dropped instead). Either way a decode_queue_full error names the dropped recording", None),
+    ("speech.partials", "While recording, decode what's been said so far every partial_interval_ms and emit it as partial_transcription events; only the final transcript is typed. Partials use a second copy of the model, loaded on the first one, so the final decode never waits for them; they stop once a long recording spills to disk", None),
+    ("speech.partial_interval_ms", "How often a partial transcription is taken while recording; a partial still decoding delays the next", None),
+    ("speech.watch_model", "Reload the model when its files change on disk (e.g. a swapped symlink), once they stop changing and nothing is being recorded; polls, so it also works on network filesystems. The new model loads beside the old one, so memory briefly peaks at both", None),
+    ("speech.hallucination_blocklist", "Sentences that are nothing but one of these phrases (ignoring case and punctuation) are dropped from transcripts: what the model makes of silence, mostly video outros like \"Thanks for watching!\". A recording left with no text counts as empty. [] turns it off", None),
+    ("speech.cpu_affinity", "Restrict decoding to these CPU cores (Linux only); empty = any core", Some("[4, 5, 6, 7]")),
+    ("speech.backend", "local: decode with the model in model_dir; remote: send each recording to the [remote_transcription] endpoint. The local model is only loaded as its fallback", None),
+    ("remote_transcription.url", "OpenAI-compatible transcription endpoint, including /v1/audio/transcriptions; audio is uploaded as a 16kHz WAV", None),
+    ("remote_transcription.api_key", "Bearer token for the endpoint", Some("\"sk-...\"")),
+    ("remote_transcription.model", "Model name sent with each request", None),
+    ("remote_transcription.timeout_ms", "Give up on a request after this long", None),
+    ("remote_transcription.fallback_to_local", "Decode with the local model when the endpoint fails or times out, instead of reporting an error. Turning it off leaves the local model unloaded, and speech.partials off", None),
+    ("text.typing_delay_ms", "Delay between keystrokes when typing", None),
+    ("text.dry_run", "Run the whole pipeline but log the final text and emit an injection_skipped event instead of typing it (also --dry-run); other sinks still run", None),
+    ("text.backend", "How text is typed: enigo (X11, XWayland, macOS, Windows), wtype (Wayland virtual keyboard; not on GNOME) or ydotool (needs the ydotoold daemon; only characters on the keyboard layout). auto uses enigo, or on Wayland wtype then ydotool, whichever is installed", None),
+    ("text.normalize_numbers", "Format numbers, currencies and units for the typing locale, e.g. \"3.5\" -> \"3,5\" and \"5 euros\" -> \"5 €\" in German. English and German spoken numbers become digits (\"twenty five\", \"drei komma fünf\"), except a lone word below ten with no unit after it. Only the processed text changes, raw keeps what the model said", None),
+    ("text.number_locale", "Typing locale for number formatting when it differs from speech.language; de, fr, es, it and nl have their own rules, anything else uses English", Some("\"de-CH\"")),
+    ("text.spelling", "Spelling mode: \"spell alpha bravo seven dash charlie end spell\" types \"ab7-c\" (NATO letters, digit names, dash/underscore/dot/slash, \"capital <letter>\"); \"spell\" only counts at the start of the utterance or after punctuation, and without \"end spell\" it runs to the end of the utterance. Applied before any other text processing, to the processed text only", None),
+    ("text.vocabulary", "Project names and jargon to write exactly as given when the recognizer splits or mis-cases them (\"Tom chat\" -> \"tomchat\"), matched ignoring case, spaces and hyphens across up to three words; \"heard => term\" entries fix consistent mishearings (\"see pal => cpal\"). Applied right after spelling", None),
+    ("text.vocabulary_file", "Text file with more vocabulary entries, one per line (# for comments); re-read whenever it changes", Some("\"./vocabulary.txt\"")),
+    ("text.punctuate", "off, or llm to have the text_refinement model add punctuation to long unpunctuated runs (even with refinement disabled). Refined takes skip it, the refinement prompt already fixes punctuation", None),
+    ("text_refinement.enabled", "Refine transcriptions with Ollama", None),
+    ("text_refinement.model_name", "Ollama model used for refinement", None),
+    ("text_refinement.ollama_url", "Ollama server URL", None),
+    ("text_refinement.device", "Legacy, unused with Ollama", None),
+    ("text_refinement.cpu_threads", "Legacy, unused with Ollama", None),
+    ("text_refinement.quantization", "Legacy, unused with Ollama", None),
+    ("text_refinement.batch_size", "Legacy, unused with Ollama", None),
+    ("text_refinement.prompt_template", "Prompt sent to the model; {text} is the transcription, {app_title} the window title", None),
+    ("text_refinement.max_tokens", "Maximum tokens to generate", None),
+    ("text_refinement.temperature", "Sampling temperature", None),
+    ("text_refinement.timeout_ms", "Give up on refinement after this long", None),
+    ("text_refinement.max_retries", "Retries after a failed refinement", None),
+    ("text_refinement.fallback_on_timeout", "Use the unrefined text when refinement fails", None),
+    ("text_refinement.cold_start_timeout_ms", "Retry budget when Ollama is reloading an evicted model", None),
+    ("text_refinement.keep_alive_secs", "Ollama keep_alive; timeouts after this long are treated as cold starts", None),
+    ("text_refinement.include_window_title", "Send the redacted focused window title to the model as context", None),
+    ("text_refinement.window_title_max_len", "Maximum length of the window title sent to the model", None),
+    ("text_refinement.auto_pull", "Pull model_name through Ollama at startup if it isn't installed", None),
+    ("indicator.terminal_title", "Set the terminal title to \"● REC tomchat\" while recording", None),
+    ("indicator.scroll_lock_led", "Light the ScrollLock LED while recording (needs access to /dev/input)", None),
+    ("indicator.led_device", "Keyboard event device for the LED; auto-detected if unset", Some("\"/dev/input/by-path/platform-i8042-serio-0-event-kbd\"")),
+    ("output.sinks", "Output sinks (typing | stdout | file | clipboard), each with the text variant it receives: raw | refined | processed | templated. Every sink gets each utterance, and one failing doesn't stop the rest. stdout/file sinks take format = text | jsonl | timestamped (UTC time the recording started, then the text); file sinks need a path. clipboard copies without pasting, via wl-copy, xclip or xsel (pbcopy on macOS, clip on Windows)", None),
+    ("output.template", "Text the templated variant receives: {text} is the processed text, {raw} the model's text and {time} the UTC time the recording started. Sinks asking for templated get processed text while this is unset", Some("\"- [{time}] {text}\"")),
+    ("retranscribe.model_dir", "Alternate model directory for re-decodes", Some("\"./models/another-model\"")),
+    ("retranscribe.max_secs", "Don't retain recordings longer than this for re-decode", None),
+    ("retranscribe.ttl_secs", "Forget the retained recording after this long", None),
+    ("retranscribe.replace_previous", "Erase the previous injection before typing the re-decode", None),
+    ("correction.similarity_threshold", "Word-level similarity (0-1) needed to edit the last injection instead of appending", None),
+    ("history.session_entries", "Dictations persisted across restarts for repeat_last; 0 disables the state file", None),
+    ("history.redact_session_state", "Run persisted dictations through the redaction patterns", None),
+    ("form_fill.enabled", "Split typed text into form fields at the separator phrase", None),
+    ("form_fill.separator", "Spoken phrase that moves to the next field (removed from the text)", None),
+    ("form_fill.key", "Key pressed between fields: tab, enter, space, up, down, left, right", None),
+    ("form_fill.profiles", "Per-preset overrides of enabled, separator and key; unset fields use the values above", Some("{ accurate = { enabled = true, key = \"enter\" } }")),
+    ("gui.push", "Event subscribers: file:// paths are overwritten, http(s):// endpoints get a POST", None),
+    ("gui.preview_graphemes", "Text previews in event messages are truncated to this length", None),
+    ("gui.full_text", "Include the full text next to the preview in text-bearing events (otherwise only the preview leaves the process)", None),
+    ("gui.rich_transcription", "Also send the final text as sentences with character offsets in transcription_rich events", None),
+    ("debug.save_audio_dir", "Save each recording here as a 16-bit WAV before transcription, for checking what was captured", Some("\"./debug-audio\"")),
+    ("debug.max_saved_files", "Oldest saved recordings are deleted beyond this many; 0 keeps them all", None),
+    ("reload.watch", "Apply edits to config.toml while running; hotkey bindings and vad.timeout_ms/sensitivity change live, other settings are reported as needing a restart", None),
+    ("reload.quiet_ms", "The file must stay unchanged this long before it's read, so an editor's save (temp file, rename, touches) is applied once", None),
+];
+
+/// Render the default configuration as commented TOML
+pub fn render_default_config() -> Result<String> {
+    let value = toml::Value::try_from(Config::default())?;
+    let root = value
+        .as_table()
+        .ok_or_else(|| anyhow::anyhow!("Config did not serialize to a table"))?;
+
+    let mut out = String::from("# TomChat Configuration\n# Named after Tommy\n");
+
+    // Top-level keys have to come before any table
+    for (key, description, example) in FIELD_DOCS.iter().filter(|(path, _, _)| !path.contains('.')) {
+        out.push_str(&format!("\n# {}\n", description));
+        match (root.get(*key), example) {
+            (Some(value), _) => out.push_str(&format!("{} = {}\n", key, value)),
+            (None, Some(example)) => out.push_str(&format!("# {} = {}\n", key, example)),
+            (None, None) => {}
+        }
+    }
+
+    for (section, section_value) in root {
+        let Some(table) = section_value.as_table() else {
+            continue;
+        };
+        out.push_str(&format!("\n[{}]\n", section));
+
+        let prefix = format!("{}.", section);
+        for (path, description, example) in FIELD_DOCS.iter().filter(|(path, _, _)| path.starts_with(&prefix)) {
+            let key = &path[prefix.len()..];
+            out.push_str(&format!("# {}\n", description));
+            match (table.get(key), example) {
+                (Some(value), _) => out.push_str(&format!("{} = {}\n", key, value)),
+                (None, Some(example)) => out.push_str(&format!("# {} = {}\n", key, example)),
+                (None, None) => {}
+            }
+        }
+
+        // Fields added without a description still get written, but flag them
+        for (key, value) in table {
+            if !FIELD_DOCS.iter().any(|(path, _, _)| *path == format!("{}{}", prefix, key)) {
+                warn!("Config field {}{} has no description", prefix, key);
+                out.push_str(&format!("{} = {}\n", key, value));
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn default_table() -> toml::value::Table {
+        toml::Value::try_from(Config::default()).unwrap().as_table().unwrap().clone()
+    }
+
+    #[test]
+    fn every_default_field_has_a_description() {
+        let mut missing = Vec::new();
+        for (section, value) in default_table() {
+            match value.as_table() {
+                Some(table) => missing.extend(
+                    table
+                        .keys()
+                        .map(|key| format!("{}.{}", section, key))
+                        .filter(|path| !FIELD_DOCS.iter().any(|(doc, _, _)| doc == path)),
+                ),
+                None if !FIELD_DOCS.iter().any(|(doc, _, _)| *doc == section) => missing.push(section),
+                None => {}
+            }
+        }
+        assert!(missing.is_empty(), "config fields without a FIELD_DOCS entry: {:?}", missing);
+    }
+
+    #[test]
+    fn descriptions_name_real_fields() {
+        let table = default_table();
+        for (path, _, example) in FIELD_DOCS {
+            let exists = match path.split_once('.') {
+                Some((section, key)) => table.get(section).and_then(|s| s.get(key)).is_some(),
+                None => table.contains_key(*path),
+            };
+            // Fields that are unset by default are documented with an example instead
+            assert!(exists || example.is_some(), "{} is documented but isn't a config field", path);
+        }
+    }
+
+    #[test]
+    fn rendered_config_round_trips() {
+        let rendered = render_default_config().unwrap();
+        let parsed: Config = toml::from_str(&rendered).unwrap();
+        assert_eq!(toml::Value::try_from(parsed).unwrap(), toml::Value::try_from(Config::default()).unwrap());
+    }
+}
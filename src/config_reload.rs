@@ -0,0 +1,436 @@
+//! Hot reload of config.toml (reload.watch). Editors save through temp files, renames
+//! and metadata touches, so the file is only read once it has been quiet for a while,
+//! and the settings that can change live are applied as one transaction: if a step
+//! fails, the steps before it are put back to the old config.
+
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::{mpsc, OwnedMutexGuard};
+use tracing::{debug, error, info};
+
+use crate::audio::VoiceActivityDetector;
+use crate::config::Config;
+use crate::input::{HotkeyAction, HotkeyManager, HotkeyRouter};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// What a save changes about the file, including a rename over it
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FileStamp {
+    len: u64,
+    modified: Option<SystemTime>,
+    #[cfg(unix)]
+    inode: u64,
+}
+
+impl FileStamp {
+    fn read(path: &Path) -> Option<Self> {
+        let metadata = std::fs::metadata(path).ok()?;
+        Some(Self {
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+            #[cfg(unix)]
+            inode: std::os::unix::fs::MetadataExt::ino(&metadata),
+        })
+    }
+}
+
+/// Turns a burst of writes into one change
+#[derive(Debug)]
+struct Settle {
+    quiet: Duration,
+    /// The file as last reported (or as it was when watching started)
+    reported: Option<FileStamp>,
+    seen: Option<FileStamp>,
+    /// When `seen` was first seen
+    seen_at: Instant,
+}
+
+impl Settle {
+    fn new(initial: Option<FileStamp>, quiet: Duration, now: Instant) -> Self {
+        Self {
+            quiet,
+            reported: initial.clone(),
+            seen: initial,
+            seen_at: now,
+        }
+    }
+
+    /// True once the file differs from the last report and has stayed the same for the
+    /// quiet period. A missing file (mid-rename) is never reported.
+    fn observe(&mut self, stamp: Option<FileStamp>, now: Instant) -> bool {
+        if stamp != self.seen {
+            self.seen = stamp;
+            self.seen_at = now;
+            return false;
+        }
+        if self.seen.is_none() || self.seen == self.reported || now.duration_since(self.seen_at) < self.quiet {
+            return false;
+        }
+        self.reported = self.seen.clone();
+        true
+    }
+}
+
+/// Poll config.toml, sending once per settled change
+pub fn spawn_config_watcher(path: PathBuf, quiet: Duration) -> mpsc::Receiver<()> {
+    let (tx, rx) = mpsc::channel(1);
+    tokio::spawn(async move {
+        info!("👀 Watching {:?} for config changes", path);
+        let mut settle = Settle::new(FileStamp::read(&path), quiet, Instant::now());
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            if settle.observe(FileStamp::read(&path), Instant::now()) {
+                debug!("Config file settled after a change");
+                if tx.send(()).await.is_err() {
+                    return;
+                }
+            }
+        }
+    });
+    rx
+}
+
+type Changed = Box<dyn Fn(&Config, &Config) -> bool + Send + Sync>;
+type Apply<T> = Box<dyn Fn(&mut T, &Config) -> Result<()> + Send + Sync>;
+
+/// One setting that can change while running. `apply` makes the running state match
+/// the given config, so undoing a step is applying it with the old config.
+pub struct ReloadStep<T> {
+    pub key: String,
+    changed: Changed,
+    apply: Apply<T>,
+}
+
+impl<T> ReloadStep<T> {
+    pub fn new(key: impl Into<String>, changed: Changed, apply: Apply<T>) -> Self {
+        Self { key: key.into(), changed, apply }
+    }
+}
+
+/// A reload that failed partway and was undone
+#[derive(Debug)]
+pub struct RolledBack {
+    pub key: String,
+    pub error: anyhow::Error,
+}
+
+/// Apply the steps whose setting changed, in order. Returns the keys applied, or on
+/// the first failure puts every step tried so far back to `old`, newest first.
+pub fn apply<T>(target: &mut T, steps: &[ReloadStep<T>], old: &Config, new: &Config) -> Result<Vec<String>, RolledBack> {
+    let changed: Vec<&ReloadStep<T>> = steps.iter().filter(|step| (step.changed)(old, new)).collect();
+    for (index, step) in changed.iter().enumerate() {
+        if let Err(e) = (step.apply)(target, new) {
+            for undo in changed[..=index].iter().rev() {
+                if let Err(undo_error) = (undo.apply)(target, old) {
+                    error!("❌ Failed to restore {} after a failed reload: {}", undo.key, undo_error);
+                }
+            }
+            return Err(RolledBack { key: step.key.clone(), error: e });
+        }
+    }
+    Ok(changed.iter().map(|step| step.key.clone()).collect())
+}
+
+/// Settings that differ but weren't applied, as "section.key"
+pub fn restart_required(old: &Config, new: &Config, applied: &[String]) -> Vec<String> {
+    let (Ok(old), Ok(new)) = (serde_json::to_value(old), serde_json::to_value(new)) else {
+        return Vec::new();
+    };
+    let mut keys = Vec::new();
+    differing(&old, &new, "", 2, &mut keys);
+    keys.retain(|key| !applied.contains(key));
+    keys
+}
+
+fn differing(old: &serde_json::Value, new: &serde_json::Value, prefix: &str, depth: usize, keys: &mut Vec<String>) {
+    if old == new {
+        return;
+    }
+    match (old, new) {
+        (serde_json::Value::Object(old), serde_json::Value::Object(new)) if depth > 0 => {
+            let names: std::collections::BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+            for name in names {
+                let key = if prefix.is_empty() { name.clone() } else { format!("{}.{}", prefix, name) };
+                let missing = serde_json::Value::Null;
+                differing(old.get(name).unwrap_or(&missing), new.get(name).unwrap_or(&missing), &key, depth - 1, keys);
+            }
+        }
+        _ => keys.push(prefix.to_string()),
+    }
+}
+
+/// The parts of the running app a reload can change, locked for the whole reload
+pub struct Live {
+    pub hotkeys: OwnedMutexGuard<HotkeyManager>,
+    pub router: OwnedMutexGuard<HotkeyRouter>,
+    pub vad: OwnedMutexGuard<VoiceActivityDetector>,
+}
+
+const ACTIONS: [HotkeyAction; 9] = [
+    HotkeyAction::ToggleRecording,
+    HotkeyAction::Compose,
+    HotkeyAction::ComposeCancel,
+    HotkeyAction::Retranscribe,
+    HotkeyAction::Correction,
+    HotkeyAction::RepeatLast,
+    HotkeyAction::Cancel,
+    HotkeyAction::RecordRaw,
+    HotkeyAction::RecordRefined,
+];
+
+/// Everything that can change live, in the order it's applied. Without `hotkeys`
+/// (--no-hotkeys) nothing is listening, so bindings aren't registered with the OS either.
+pub fn runtime_steps(hotkeys: bool) -> Vec<ReloadStep<Live>> {
+    let mut steps = vec![
+        ReloadStep::new(
+            "vad.timeout_ms",
+            Box::new(|old: &Config, new: &Config| old.vad.timeout_ms != new.vad.timeout_ms),
+            Box::new(|live: &mut Live, config: &Config| {
+                live.vad.set_silence_timeout(config.vad.timeout_ms);
+                Ok(())
+            }),
+        ),
+        // With adaptive VAD the noise adapter owns the threshold
+        ReloadStep::new(
+            "vad.sensitivity",
+            Box::new(|old: &Config, new: &Config| {
+                old.vad.sensitivity != new.vad.sensitivity && !old.vad.adaptive && !new.vad.adaptive
+            }),
+            Box::new(|live: &mut Live, config: &Config| live.vad.set_threshold(config.vad.sensitivity.to_threshold())),
+        ),
+    ];
+    if !hotkeys {
+        return steps;
+    }
+    // Each binding is its own key, but any of them applies them all, so a swap between two
+    // actions happens in one go; the steps after the first find nothing left to change
+    for action in ACTIONS {
+        steps.push(ReloadStep::new(
+            format!("hotkeys.{}", action.as_str()),
+            Box::new(move |old: &Config, new: &Config| old.hotkeys.binding(action) != new.hotkeys.binding(action)),
+            Box::new(|live: &mut Live, config: &Config| {
+                let bindings: Vec<_> = ACTIONS.iter().map(|&action| (action, config.hotkeys.binding(action))).collect();
+                rebind_all(&mut live.hotkeys, &mut live.router, &bindings)
+            }),
+        ));
+    }
+    steps
+}
+
+/// Point `action` at `combination` (None unbinds it). On failure the old binding stays in place.
+pub fn rebind(hotkeys: &mut HotkeyManager, router: &mut HotkeyRouter, action: HotkeyAction, combination: Option<&str>) -> Result<()> {
+    rebind_all(hotkeys, router, &[(action, combination)])
+}
+
+/// Point each action at its combination in one go: every old key is let go of before any
+/// new one is registered, so actions can trade keys. On failure every binding stays as it was.
+pub fn rebind_all(hotkeys: &mut HotkeyManager, router: &mut HotkeyRouter, bindings: &[(HotkeyAction, Option<&str>)]) -> Result<()> {
+    let changes: Vec<(HotkeyAction, Option<&str>)> = bindings
+        .iter()
+        .copied()
+        .filter(|&(action, combination)| router.binding(action).map(|(_, bound)| bound) != combination)
+        .collect();
+    if changes.is_empty() {
+        return Ok(());
+    }
+    let changing = |action: HotkeyAction| changes.iter().any(|(changed, _)| *changed == action);
+    for (action, combination) in &changes {
+        let Some(combination) = combination else { continue };
+        if let Some(other) = router.bound_to(combination).filter(|other| *other != *action && !changing(*other)) {
+            return Err(anyhow::anyhow!("Hotkey '{}' is already bound to {}", combination, other.as_str()));
+        }
+    }
+
+    let old: Vec<(u32, String)> = changes
+        .iter()
+        .filter_map(|(action, _)| router.binding(*action).map(|(id, bound)| (id, bound.to_string())))
+        .collect();
+    let new: Vec<(HotkeyAction, &str)> = changes
+        .iter()
+        .filter_map(|(action, combination)| combination.map(|combination| (*action, combination)))
+        .collect();
+    let old_ids: Vec<u32> = old.iter().map(|(id, _)| *id).collect();
+    let new_combinations: Vec<&str> = new.iter().map(|(_, combination)| *combination).collect();
+    let new_ids = hotkeys.replace(&old_ids, &new_combinations)?;
+
+    // Routed on a copy, so two changes landing on one key leave the router untouched
+    let mut next = router.clone();
+    for id in &old_ids {
+        next.unbind(*id);
+    }
+    let routed = new
+        .iter()
+        .zip(&new_ids)
+        .try_for_each(|((action, combination), id)| next.bind(*id, *action, combination));
+    if let Err(e) = routed {
+        let old_combinations: Vec<&str> = old.iter().map(|(_, bound)| bound.as_str()).collect();
+        if let Err(undo) = hotkeys.replace(&new_ids, &old_combinations) {
+            error!("❌ Failed to restore hotkeys after a failed rebind: {}", undo);
+        }
+        return Err(e);
+    }
+    *router = next;
+
+    for (action, combination) in changes {
+        info!("Hotkey {} = {}", action.as_str(), combination.unwrap_or("(none)"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stamp(len: u64, inode: u64) -> Option<FileStamp> {
+        Some(FileStamp {
+            len,
+            modified: Some(SystemTime::UNIX_EPOCH + Duration::from_secs(len)),
+            #[cfg(unix)]
+            inode,
+        })
+    }
+
+    #[test]
+    fn an_editor_save_is_reported_once_it_settles() {
+        let start = Instant::now();
+        let quiet = Duration::from_millis(300);
+        let mut settle = Settle::new(stamp(100, 1), quiet, start);
+        let at = |ms: u64| start + Duration::from_millis(ms);
+
+        // vim: write a temp file, move the original away, rename the temp over it, touch it
+        let saves = [(10, None), (20, stamp(0, 2)), (30, stamp(120, 2)), (60, stamp(121, 2))];
+        for (ms, file) in saves {
+            assert!(!settle.observe(file, at(ms)), "reported mid-save at {}ms", ms);
+        }
+        assert!(!settle.observe(stamp(121, 2), at(300)));
+        assert!(settle.observe(stamp(121, 2), at(360)));
+        // Once per change
+        assert!(!settle.observe(stamp(121, 2), at(1000)));
+    }
+
+    #[test]
+    fn a_missing_file_is_never_reported() {
+        let start = Instant::now();
+        let mut settle = Settle::new(stamp(100, 1), Duration::from_millis(300), start);
+        assert!(!settle.observe(None, start));
+        assert!(!settle.observe(None, start + Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn without_hotkeys_bindings_are_left_to_a_restart() {
+        let keys = |hotkeys: bool| -> Vec<String> { runtime_steps(hotkeys).into_iter().map(|step| step.key).collect() };
+
+        assert!(keys(true).contains(&"hotkeys.toggle_recording".to_string()));
+        assert_eq!(keys(false), ["vad.timeout_ms", "vad.sensitivity"]);
+    }
+
+    #[test]
+    fn a_failed_step_rolls_back_the_ones_before_it() {
+        let old = Config::default();
+        let mut new = Config::default();
+        new.vad.timeout_ms += 1;
+        // Each step logs the timeout it was applied with
+        let step = |key: &str, fail: bool| {
+            ReloadStep::new(
+                key,
+                Box::new(|_: &Config, _: &Config| true),
+                Box::new(move |log: &mut Vec<u32>, config: &Config| {
+                    if fail && config.vad.timeout_ms != Config::default().vad.timeout_ms {
+                        return Err(anyhow::anyhow!("can't register"));
+                    }
+                    log.push(config.vad.timeout_ms);
+                    Ok(())
+                }) as Apply<Vec<u32>>,
+            )
+        };
+        let steps = vec![step("first", false), step("second", false), step("third", true)];
+        let mut log = Vec::new();
+        let rolled_back = apply(&mut log, &steps, &old, &new).unwrap_err();
+        assert_eq!(rolled_back.key, "third");
+
+        let (was, now) = (old.vad.timeout_ms, new.vad.timeout_ms);
+        // Two applied, then all three put back, newest first
+        assert_eq!(log, [now, now, was, was, was]);
+    }
+
+    fn bound(router: &HotkeyRouter, action: HotkeyAction) -> Option<&str> {
+        router.binding(action).map(|(_, combination)| combination)
+    }
+
+    fn live(bindings: &[(HotkeyAction, &str)]) -> (HotkeyManager, HotkeyRouter) {
+        let mut hotkeys = HotkeyManager::detached(false);
+        let mut router = HotkeyRouter::default();
+        for &(action, combination) in bindings {
+            rebind(&mut hotkeys, &mut router, action, Some(combination)).unwrap();
+        }
+        (hotkeys, router)
+    }
+
+    #[test]
+    fn two_actions_can_trade_keys() {
+        let (mut hotkeys, mut router) = live(&[
+            (HotkeyAction::ToggleRecording, "ctrl+shift+space"),
+            (HotkeyAction::Compose, "ctrl+shift+c"),
+        ]);
+        rebind_all(
+            &mut hotkeys,
+            &mut router,
+            &[(HotkeyAction::ToggleRecording, Some("ctrl+shift+c")), (HotkeyAction::Compose, Some("ctrl+shift+space"))],
+        )
+        .unwrap();
+        assert_eq!(bound(&router, HotkeyAction::ToggleRecording), Some("ctrl+shift+c"));
+        assert_eq!(bound(&router, HotkeyAction::Compose), Some("ctrl+shift+space"));
+
+        let (id, _) = router.binding(HotkeyAction::Compose).unwrap();
+        assert_eq!(router.route(id), Some(HotkeyAction::Compose));
+    }
+
+    #[test]
+    fn a_failed_registration_keeps_every_binding() {
+        let (mut hotkeys, mut router) = live(&[
+            (HotkeyAction::ToggleRecording, "ctrl+shift+space"),
+            (HotkeyAction::Compose, "ctrl+shift+c"),
+        ]);
+        let result = rebind_all(
+            &mut hotkeys,
+            &mut router,
+            &[(HotkeyAction::ToggleRecording, Some("ctrl+shift+c")), (HotkeyAction::Compose, Some("ctrl+nosuchkey"))],
+        );
+        assert!(result.is_err());
+        assert_eq!(bound(&router, HotkeyAction::ToggleRecording), Some("ctrl+shift+space"));
+        assert_eq!(bound(&router, HotkeyAction::Compose), Some("ctrl+shift+c"));
+
+        // The old keys are registered again, so a later rebind from them still works
+        rebind(&mut hotkeys, &mut router, HotkeyAction::Compose, Some("ctrl+alt+c")).unwrap();
+        assert_eq!(bound(&router, HotkeyAction::Compose), Some("ctrl+alt+c"));
+    }
+
+    #[test]
+    fn a_key_held_by_an_unchanged_action_is_refused() {
+        let (mut hotkeys, mut router) = live(&[
+            (HotkeyAction::ToggleRecording, "ctrl+shift+space"),
+            (HotkeyAction::Compose, "ctrl+shift+c"),
+        ]);
+        let error = rebind(&mut hotkeys, &mut router, HotkeyAction::ToggleRecording, Some("ctrl+shift+c")).unwrap_err();
+        assert!(error.to_string().contains("already bound to compose"), "{}", error);
+        assert_eq!(bound(&router, HotkeyAction::ToggleRecording), Some("ctrl+shift+space"));
+    }
+
+    #[test]
+    fn two_changes_onto_one_key_leave_the_router_alone() {
+        let (mut hotkeys, mut router) = live(&[
+            (HotkeyAction::ToggleRecording, "ctrl+shift+space"),
+            (HotkeyAction::Compose, "ctrl+shift+c"),
+        ]);
+        let result = rebind_all(
+            &mut hotkeys,
+            &mut router,
+            &[(HotkeyAction::ToggleRecording, Some("ctrl+alt+x")), (HotkeyAction::Compose, Some("Ctrl+Alt+X"))],
+        );
+        assert!(result.is_err());
+        assert_eq!(bound(&router, HotkeyAction::ToggleRecording), Some("ctrl+shift+space"));
+        assert_eq!(bound(&router, HotkeyAction::Compose), Some("ctrl+shift+c"));
+    }
+}
@@ -0,0 +1,130 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::text_diff::{distance, words};
+
+/// Keystrokes that turn the previous injection into the corrected text
+#[derive(Debug, Clone, PartialEq)]
+pub struct EditPlan {
+    /// Backspaces to send, one per grapheme (so "\r\n" counts once)
+    pub delete: usize,
+    /// Text typed after deleting
+    pub insert: String,
+}
+
+/// Compare words ignoring case and trailing punctuation, so "world." matches "world"
+fn normalize(word: &str) -> String {
+    word.trim_matches(|c: char| c.is_ascii_punctuation()).to_lowercase()
+}
+
+/// 1.0 for identical word sequences, 0.0 for nothing in common
+pub fn similarity(previous: &str, new: &str) -> f32 {
+    let normalized = |text: &str| -> Vec<String> {
+        words(text).iter().map(|(_, w)| normalize(w)).filter(|w| !w.is_empty()).collect()
+    };
+    let (a, b) = (normalized(previous), normalized(new));
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - distance(&a, &b) as f32 / longest as f32
+}
+
+/// Plan a correction of `previous` into `new`, or None when they're too different
+/// and the new text should be injected normally.
+///
+/// Deletes back to the start of the first differing word and retypes from there;
+/// whitespace before that word (including newlines) is left alone.
+pub fn plan_correction(previous: &str, new: &str, threshold: f32) -> Option<EditPlan> {
+    if previous.is_empty() || similarity(previous, new) < threshold {
+        return None;
+    }
+
+    let old_words = words(previous);
+    let new_words = words(new);
+
+    // Exact comparison here: a changed capital or comma still has to be retyped
+    let common = old_words
+        .iter()
+        .zip(&new_words)
+        .take_while(|((_, a), (_, b))| a == b)
+        .count();
+
+    let (old_start, new_start) = match (old_words.get(common), new_words.get(common)) {
+        (Some((old, _)), Some((new, _))) => (*old, *new),
+        // One is a prefix of the other: edit from the end of the last shared word
+        _ if common > 0 => {
+            let end = |(offset, word): &(usize, &str)| offset + word.len();
+            (end(&old_words[common - 1]), end(&new_words[common - 1]))
+        }
+        _ => (0, 0),
+    };
+
+    Some(EditPlan {
+        delete: previous[old_start..].graphemes(true).count(),
+        insert: new[new_start..].to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan(previous: &str, new: &str) -> Option<EditPlan> {
+        plan_correction(previous, new, 0.6)
+    }
+
+    #[test]
+    fn similarity_ignores_case_and_punctuation() {
+        assert_eq!(similarity("Hello world.", "hello world"), 1.0);
+        assert_eq!(similarity("", ""), 1.0);
+        assert_eq!(similarity("one two three four", "one two three five"), 0.75);
+        assert_eq!(similarity("alpha beta", "gamma delta"), 0.0);
+    }
+
+    #[test]
+    fn retypes_from_the_first_changed_word() {
+        assert_eq!(
+            plan("send it to Tom tomorrow", "send it to Tim tomorrow"),
+            Some(EditPlan { delete: 12, insert: "Tim tomorrow".into() })
+        );
+    }
+
+    #[test]
+    fn changed_punctuation_is_retyped() {
+        assert_eq!(
+            plan("see you soon.", "see you soon!"),
+            Some(EditPlan { delete: 1, insert: "!".into() })
+        );
+    }
+
+    #[test]
+    fn extending_or_shortening_edits_at_the_end() {
+        assert_eq!(
+            plan("meet at noon", "meet at noon today"),
+            Some(EditPlan { delete: 0, insert: " today".into() })
+        );
+        assert_eq!(
+            plan("meet at noon today", "meet at noon"),
+            Some(EditPlan { delete: 6, insert: "".into() })
+        );
+    }
+
+    #[test]
+    fn newlines_before_the_change_are_kept_and_counted_once() {
+        assert_eq!(
+            plan("first line\r\nsecond lime", "first line\r\nsecond line"),
+            Some(EditPlan { delete: 4, insert: "line".into() })
+        );
+        // A deleted CRLF is one backspace
+        assert_eq!(
+            plan("one two three\r\nfour", "one two three"),
+            Some(EditPlan { delete: 5, insert: "".into() })
+        );
+    }
+
+    #[test]
+    fn unrelated_text_falls_back_to_normal_injection() {
+        assert_eq!(plan("the quick brown fox", "a completely different sentence"), None);
+        assert_eq!(plan("", "anything"), None);
+    }
+}
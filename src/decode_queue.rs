@@ -0,0 +1,237 @@
+//! One decode at a time, in the order recordings were submitted, so results reach
+//! output in the order they were spoken (speech.decode_queue_depth).
+
+use futures_util::future::{BoxFuture, FutureExt};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::panic::AssertUnwindSafe;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+use tracing::{debug, error};
+
+type DecodeJob = (u64, BoxFuture<'static, ()>);
+
+/// What happens to a recording stopped while the queue is full (speech.decode_queue_overflow)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueOverflow {
+    /// The new recording isn't decoded
+    #[default]
+    Reject,
+    /// The recording that has waited longest is dropped to make room
+    DropOldest,
+}
+
+/// A job try_submit put in the queue
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Queued {
+    /// Jobs that will finish before this one, the running one included
+    pub ahead: usize,
+    /// The waiting recording dropped to make room, with QueueOverflow::DropOldest
+    pub dropped: Option<u64>,
+}
+
+/// The queue was full and its overflow policy rejects new recordings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueFull;
+
+#[derive(Default)]
+struct State {
+    waiting: VecDeque<DecodeJob>,
+    running: bool,
+    closed: bool,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    wake: Notify,
+}
+
+/// Stops the worker once the last queue handle is gone
+struct Handle {
+    shared: Arc<Shared>,
+}
+
+impl Drop for Handle {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().closed = true;
+        self.shared.wake.notify_one();
+    }
+}
+
+#[derive(Clone)]
+pub struct DecodeQueue {
+    handle: Arc<Handle>,
+    depth: usize,
+    overflow: QueueOverflow,
+}
+
+impl DecodeQueue {
+    /// Start the worker; at most `depth` jobs wait behind the running one
+    pub fn spawn(depth: usize, overflow: QueueOverflow) -> Self {
+        let shared = Arc::new(Shared { state: Mutex::new(State::default()), wake: Notify::new() });
+        let worker_shared = shared.clone();
+        tokio::spawn(async move {
+            loop {
+                let next = {
+                    let mut state = worker_shared.state.lock().unwrap();
+                    let next = state.waiting.pop_front();
+                    state.running = next.is_some();
+                    if next.is_none() && state.closed {
+                        break;
+                    }
+                    next
+                };
+                let Some((recording_id, job)) = next else {
+                    worker_shared.wake.notified().await;
+                    continue;
+                };
+                debug!("Decoding recording {}", recording_id);
+                // One bad job mustn't take every later recording down with it
+                if AssertUnwindSafe(job).catch_unwind().await.is_err() {
+                    error!("❌ Decode of recording {} panicked", recording_id);
+                }
+            }
+        });
+        Self { handle: Arc::new(Handle { shared }), depth: depth.max(1), overflow }
+    }
+
+    /// Queue a decode behind the ones already submitted without waiting: a full
+    /// queue makes room or refuses the job, as its overflow policy says
+    pub fn try_submit(&self, recording_id: u64, job: BoxFuture<'static, ()>) -> Result<Queued, QueueFull> {
+        let mut state = self.handle.shared.state.lock().unwrap();
+        // A job the worker hasn't picked up yet still counts as the running one
+        let running = usize::from(state.running || !state.waiting.is_empty());
+        let mut dropped = None;
+        if state.waiting.len() + usize::from(state.running) >= self.depth + running {
+            match self.overflow {
+                QueueOverflow::Reject => return Err(QueueFull),
+                QueueOverflow::DropOldest => {
+                    // The front is the next to run once the current job finishes
+                    let skip = usize::from(!state.running);
+                    dropped = state.waiting.remove(skip).map(|(id, _)| id);
+                }
+            }
+        }
+        let ahead = state.waiting.len() + usize::from(state.running);
+        state.waiting.push_back((recording_id, job));
+        drop(state);
+        self.handle.shared.wake.notify_one();
+        Ok(Queued { ahead, dropped })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::{mpsc, oneshot};
+
+    /// A job that reports its id once it has run
+    fn job(id: u64, done: &mpsc::UnboundedSender<u64>) -> BoxFuture<'static, ()> {
+        let done = done.clone();
+        async move {
+            let _ = done.send(id);
+        }
+        .boxed()
+    }
+
+    /// A job that runs until `release` fires, after signalling it started
+    fn blocking_job(id: u64, done: &mpsc::UnboundedSender<u64>) -> (BoxFuture<'static, ()>, oneshot::Receiver<()>, oneshot::Sender<()>) {
+        let (started_tx, started) = oneshot::channel();
+        let (release, released) = oneshot::channel::<()>();
+        let done = done.clone();
+        let job = async move {
+            let _ = started_tx.send(());
+            let _ = released.await;
+            let _ = done.send(id);
+        }
+        .boxed();
+        (job, started, release)
+    }
+
+    async fn finished(done: &mut mpsc::UnboundedReceiver<u64>, count: usize) -> Vec<u64> {
+        let mut ids = Vec::new();
+        for _ in 0..count {
+            ids.push(done.recv().await.unwrap());
+        }
+        ids
+    }
+
+    #[tokio::test]
+    async fn results_come_out_in_submission_order() {
+        let queue = DecodeQueue::spawn(8, QueueOverflow::Reject);
+        let (done_tx, mut done) = mpsc::unbounded_channel();
+
+        // The first decode is the slowest; the others still wait their turn
+        let slow = {
+            let done = done_tx.clone();
+            async move {
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                let _ = done.send(1);
+            }
+            .boxed()
+        };
+        let ahead: Vec<usize> = [(1, slow), (2, job(2, &done_tx)), (3, job(3, &done_tx))]
+            .into_iter()
+            .map(|(id, job)| queue.try_submit(id, job).unwrap().ahead)
+            .collect();
+
+        assert_eq!(ahead, vec![0, 1, 2]);
+        assert_eq!(finished(&mut done, 3).await, vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn a_full_queue_rejects_the_new_recording() {
+        let queue = DecodeQueue::spawn(1, QueueOverflow::Reject);
+        let (done_tx, mut done) = mpsc::unbounded_channel();
+        let (first, started, release) = blocking_job(1, &done_tx);
+
+        queue.try_submit(1, first).unwrap();
+        started.await.unwrap();
+        assert_eq!(queue.try_submit(2, job(2, &done_tx)), Ok(Queued { ahead: 1, dropped: None }));
+        assert_eq!(queue.try_submit(3, job(3, &done_tx)), Err(QueueFull));
+
+        release.send(()).unwrap();
+        assert_eq!(finished(&mut done, 2).await, vec![1, 2]);
+    }
+
+    #[tokio::test]
+    async fn a_full_queue_can_drop_the_oldest_waiting_recording() {
+        let queue = DecodeQueue::spawn(2, QueueOverflow::DropOldest);
+        let (done_tx, mut done) = mpsc::unbounded_channel();
+        let (first, started, release) = blocking_job(1, &done_tx);
+
+        queue.try_submit(1, first).unwrap();
+        started.await.unwrap();
+        queue.try_submit(2, job(2, &done_tx)).unwrap();
+        queue.try_submit(3, job(3, &done_tx)).unwrap();
+        // The running decode is never the one dropped
+        assert_eq!(queue.try_submit(4, job(4, &done_tx)), Ok(Queued { ahead: 2, dropped: Some(2) }));
+
+        release.send(()).unwrap();
+        assert_eq!(finished(&mut done, 3).await, vec![1, 3, 4]);
+    }
+
+    #[tokio::test]
+    async fn a_job_not_yet_picked_up_counts_as_running() {
+        let queue = DecodeQueue::spawn(1, QueueOverflow::Reject);
+        let (done_tx, mut done) = mpsc::unbounded_channel();
+
+        // No await in between, so the worker hasn't taken the first job yet
+        queue.try_submit(1, job(1, &done_tx)).unwrap();
+        assert_eq!(queue.try_submit(2, job(2, &done_tx)).map(|queued| queued.ahead), Ok(1));
+
+        assert_eq!(finished(&mut done, 2).await, vec![1, 2]);
+    }
+
+    #[tokio::test]
+    async fn a_panicking_decode_doesnt_stop_the_queue() {
+        let queue = DecodeQueue::spawn(4, QueueOverflow::Reject);
+        let (done_tx, mut done) = mpsc::unbounded_channel();
+
+        queue.try_submit(1, async { panic!("decoder crashed") }.boxed()).unwrap();
+        queue.try_submit(2, job(2, &done_tx)).unwrap();
+
+        assert_eq!(finished(&mut done, 1).await, vec![2]);
+    }
+}
@@ -0,0 +1,615 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use crate::paths;
+use crate::privacy::{self, NetworkSite};
+use crate::speech::transcriber::MODEL_FILES;
+
+const MAX_RETRIES: u32 = 8;
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// A model tomchat knows how to fetch
+#[derive(Debug, Clone, Copy)]
+pub struct ModelSpec {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub url: &'static str,
+    /// File name the download is saved as inside the target directory
+    pub file_name: &'static str,
+    /// Pinned SHA256 of the official release asset; None skips verification
+    pub sha256: Option<&'static str>,
+    /// tar.bz2 archive that gets extracted and removed after download
+    pub archive: bool,
+    /// A speech model directory, usable as speech.model
+    pub speech: bool,
+}
+
+impl ModelSpec {
+    /// Where the model ends up inside `dir`: the unpacked directory or the file itself
+    pub fn installed_path(&self, dir: &Path) -> PathBuf {
+        dir.join(self.file_name.trim_end_matches(".tar.bz2"))
+    }
+}
+
+/// Official models, same sources as scripts/download-parakeet.sh
+pub const MODELS: &[ModelSpec] = &[
+    ModelSpec {
+        name: "parakeet",
+        description: "Parakeet TDT 0.6B v2 (INT8), ~180MB",
+        url: "https://github.com/k2-fsa/sherpa-onnx/releases/download/asr-models/sherpa-onnx-nemo-parakeet-tdt-0.6b-v2-int8.tar.bz2",
+        file_name: "sherpa-onnx-nemo-parakeet-tdt-0.6b-v2-int8.tar.bz2",
+        sha256: None,
+        archive: true,
+        speech: true,
+    },
+    ModelSpec {
+        name: "silero-vad",
+        description: "Silero VAD, ~2MB",
+        url: "https://github.com/k2-fsa/sherpa-onnx/releases/download/asr-models/silero_vad.onnx",
+        file_name: "silero_vad.onnx",
+        sha256: None,
+        archive: false,
+        speech: false,
+    },
+];
+
+pub fn find_model(name: &str) -> Option<&'static ModelSpec> {
+    MODELS.iter().find(|model| model.name.eq_ignore_ascii_case(name))
+}
+
+/// The speech model `name` as installed in the model cache, for speech.model
+pub fn speech_model_dir(name: &str) -> Result<PathBuf> {
+    let spec = find_model(name).filter(|spec| spec.speech).ok_or_else(|| {
+        let names: Vec<&str> = MODELS.iter().filter(|spec| spec.speech).map(|spec| spec.name).collect();
+        anyhow::anyhow!("Unknown speech.model '{}', expected one of: {}", name, names.join(", "))
+    })?;
+    Ok(spec.installed_path(&paths::models_cache_dir()))
+}
+
+/// Download the speech model into `model_dir` when its files are missing (speech.auto_download),
+/// verified against `sha256` or the model's pinned checksum. Works when the directory is where
+/// a known model unpacks, e.g. the default model_dir.
+pub async fn fetch_missing_speech_model(
+    model_dir: &Path,
+    sha256: Option<&str>,
+    progress: Option<mpsc::UnboundedSender<DownloadProgress>>,
+) -> Result<()> {
+    if MODEL_FILES.iter().all(|name| model_dir.join(name).exists()) {
+        return Ok(());
+    }
+    let (Some(dir), Some(dir_name)) = (model_dir.parent(), model_dir.file_name()) else {
+        return Err(anyhow::anyhow!("Can't download a model into {:?}", model_dir));
+    };
+    let spec = MODELS
+        .iter()
+        .find(|spec| spec.speech && spec.installed_path(dir).file_name() == Some(dir_name))
+        .ok_or_else(|| anyhow::anyhow!("No downloadable model unpacks to {:?}; set speech.model instead", model_dir))?;
+
+    info!("Speech model missing from {:?}, downloading {}", model_dir, spec.name);
+    let options = DownloadOptions { dir: dir.to_path_buf(), connections: 1, sha256: sha256.map(str::to_string) };
+    download_model(spec, &options, progress).await.map(|_| ())
+}
+
+/// Progress snapshot, sent to whoever is rendering it (terminal or GUI)
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadProgress {
+    pub model: String,
+    pub bytes: u64,
+    pub total: Option<u64>,
+    pub percent: Option<f32>,
+    pub eta_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DownloadOptions {
+    pub dir: PathBuf,
+    /// Parallel range requests; 1 = a single resumable stream
+    pub connections: usize,
+    /// Overrides the pinned checksum, e.g. for mirrors
+    pub sha256: Option<String>,
+}
+
+/// Download (resuming any .part file), verify and unpack a model into `options.dir`
+pub async fn download_model(
+    spec: &ModelSpec,
+    options: &DownloadOptions,
+    progress: Option<mpsc::UnboundedSender<DownloadProgress>>,
+) -> Result<PathBuf> {
+    std::fs::create_dir_all(&options.dir)
+        .with_context(|| format!("Failed to create model directory {:?}", options.dir))?;
+
+    let target = options.dir.join(spec.file_name);
+    let installed = spec.installed_path(&options.dir);
+    if installed.exists() {
+        info!("{} already present at {:?}, skipping download", spec.name, installed);
+        return Ok(installed);
+    }
+
+    privacy::check(NetworkSite::ModelDownload, spec.url)?;
+
+    let part = options.dir.join(format!("{}.part", spec.file_name));
+    let client = reqwest::Client::builder()
+        .connect_timeout(Duration::from_secs(15))
+        .build()?;
+
+    info!("📥 Downloading {} from {}", spec.name, spec.url);
+    let (total, accepts_ranges) = probe(&client, spec.url).await;
+
+    let downloaded = Arc::new(AtomicU64::new(0));
+    let reporter = progress.map(|tx| spawn_reporter(spec.name, total, downloaded.clone(), tx));
+
+    let result = match total {
+        Some(size) if options.connections > 1 && accepts_ranges && !part.exists() => {
+            download_parallel(&client, spec.url, &part, size, options.connections, &downloaded).await
+        }
+        _ => {
+            if options.connections > 1 {
+                info!("Using a single connection (no range support, unknown size, or resuming a partial file)");
+            }
+            download_single(&client, spec.url, &part, total, &downloaded).await
+        }
+    };
+
+    if let Some(reporter) = reporter {
+        reporter.abort();
+    }
+    result?;
+
+    // A stream that ended early looks like success; keep the .part to resume from
+    let size = std::fs::metadata(&part)?.len();
+    if let Some(total) = total.filter(|total| *total != size) {
+        if size > total {
+            std::fs::remove_file(&part)?;
+        }
+        return Err(anyhow::anyhow!(
+            "Download of {} incomplete: {} of {} bytes; re-run to resume",
+            spec.name,
+            size,
+            total
+        ));
+    }
+
+    let expected = options.sha256.as_deref().or(spec.sha256);
+    if let Some(expected) = expected {
+        info!("🔍 Verifying checksum...");
+        let actual = sha256_file(&part)?;
+        if !actual.eq_ignore_ascii_case(expected) {
+            // A corrupt partial file would otherwise be resumed forever
+            std::fs::remove_file(&part)?;
+            return Err(anyhow::anyhow!(
+                "Checksum mismatch for {}: expected {}, got {}",
+                spec.name,
+                expected,
+                actual
+            ));
+        }
+    } else {
+        warn!("No pinned checksum for {} - skipping verification", spec.name);
+    }
+
+    std::fs::rename(&part, &target)?;
+
+    if spec.archive {
+        // Unpack beside the target and move it in whole, so an interrupted extraction
+        // never leaves a half-filled model directory that looks installed
+        info!("📦 Extracting {:?}...", target);
+        let staging = options.dir.join(format!(".{}.extracting", spec.name));
+        if staging.exists() {
+            std::fs::remove_dir_all(&staging)?;
+        }
+        std::fs::create_dir_all(&staging)?;
+        let status = std::process::Command::new("tar")
+            .arg("xjf")
+            .arg(&target)
+            .arg("-C")
+            .arg(&staging)
+            .status()
+            .context("Failed to run tar")?;
+        if !status.success() {
+            let _ = std::fs::remove_dir_all(&staging);
+            return Err(anyhow::anyhow!("Failed to extract {:?}", target));
+        }
+        std::fs::rename(spec.installed_path(&staging), &installed)
+            .with_context(|| format!("{:?} didn't unpack to {:?}", target, installed.file_name()))?;
+        std::fs::remove_dir_all(&staging)?;
+        std::fs::remove_file(&target)?;
+    }
+
+    info!("✅ {} ready at {:?}", spec.name, installed);
+    Ok(installed)
+}
+
+/// Content length and range support, if the server tells us
+async fn probe(client: &reqwest::Client, url: &str) -> (Option<u64>, bool) {
+    match client.head(url).send().await {
+        Ok(response) if response.status().is_success() => {
+            let accepts_ranges = response
+                .headers()
+                .get(reqwest::header::ACCEPT_RANGES)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.contains("bytes"))
+                .unwrap_or(false);
+            // Not content_length(): that's the (empty) body's size for a HEAD response
+            let total = response
+                .headers()
+                .get(reqwest::header::CONTENT_LENGTH)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse().ok());
+            (total, accepts_ranges)
+        }
+        _ => (None, false),
+    }
+}
+
+/// One stream into the .part file, resuming from its current length after every drop
+async fn download_single(
+    client: &reqwest::Client,
+    url: &str,
+    part: &Path,
+    total: Option<u64>,
+    downloaded: &AtomicU64,
+) -> Result<()> {
+    let mut backoff = INITIAL_BACKOFF;
+    let mut attempts = 0;
+
+    loop {
+        let offset = std::fs::metadata(part).map(|m| m.len()).unwrap_or(0);
+        downloaded.store(offset, Ordering::Relaxed);
+        if total.is_some_and(|total| offset >= total) {
+            return Ok(());
+        }
+
+        match fetch_into(client, url, part, offset, None, downloaded, &mut 0).await {
+            Ok(()) => return Ok(()),
+            Err(e) if attempts < MAX_RETRIES => {
+                attempts += 1;
+                warn!("Download interrupted at {} bytes: {} - retrying in {:?}", downloaded.load(Ordering::Relaxed), e, backoff);
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+            }
+            Err(e) => return Err(e.context("Download failed after retries; re-run to resume")),
+        }
+    }
+}
+
+/// Split a fresh download into byte ranges fetched concurrently
+async fn download_parallel(
+    client: &reqwest::Client,
+    url: &str,
+    part: &Path,
+    total: u64,
+    connections: usize,
+    downloaded: &Arc<AtomicU64>,
+) -> Result<()> {
+    std::fs::File::create(part)?.set_len(total)?;
+
+    let chunk = total.div_ceil(connections as u64);
+    let mut tasks = Vec::new();
+    for start in (0..total).step_by(chunk as usize) {
+        let end = (start + chunk).min(total) - 1;
+        let client = client.clone();
+        let url = url.to_string();
+        let part = part.to_path_buf();
+        let downloaded = downloaded.clone();
+
+        tasks.push(tokio::spawn(async move {
+            let mut backoff = INITIAL_BACKOFF;
+            let mut position = start;
+            for attempt in 0..=MAX_RETRIES {
+                let mut written = 0;
+                let result = fetch_into(&client, &url, &part, position, Some(end), &downloaded, &mut written).await;
+                // Resume this range from wherever the stream stopped
+                position += written;
+                match result {
+                    Ok(()) => return Ok(()),
+                    Err(e) if attempt < MAX_RETRIES => {
+                        warn!("Range {}-{} interrupted: {} - retrying in {:?}", start, end, e, backoff);
+                        tokio::time::sleep(backoff).await;
+                        backoff = (backoff * 2).min(MAX_BACKOFF);
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            Ok(())
+        }));
+    }
+
+    let mut failure = None;
+    for task in tasks {
+        if let Err(e) = task.await? {
+            failure.get_or_insert(e);
+        }
+    }
+    match failure {
+        // Ranges can't be resumed individually, so don't leave a holey .part behind
+        Some(e) => {
+            let _ = std::fs::remove_file(part);
+            Err(e.context("Parallel download failed"))
+        }
+        None => Ok(()),
+    }
+}
+
+/// Stream `start..=end` (or `start..` when end is None) into the file at `start`,
+/// counting bytes into `written` so a dropped stream can be resumed
+async fn fetch_into(
+    client: &reqwest::Client,
+    url: &str,
+    part: &Path,
+    start: u64,
+    end: Option<u64>,
+    downloaded: &AtomicU64,
+    written: &mut u64,
+) -> Result<()> {
+    let mut request = client.get(url);
+    if start > 0 || end.is_some() {
+        let range = match end {
+            Some(end) => format!("bytes={}-{}", start, end),
+            None => format!("bytes={}-", start),
+        };
+        request = request.header(reqwest::header::RANGE, range);
+    }
+
+    let response = request.send().await?;
+    // Asking to resume at (or past) the end: either the file is already complete,
+    // or it doesn't match what the server has and has to start over
+    if response.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE && start > 0 && end.is_none() {
+        if content_range_total(&response) == Some(start) {
+            return Ok(());
+        }
+        warn!("Server can't resume at {} bytes, restarting from zero", start);
+        std::fs::File::create(part)?;
+        downloaded.store(0, Ordering::Relaxed);
+        return Err(anyhow::anyhow!("Resume offset {} rejected by the server", start));
+    }
+    let mut response = response.error_for_status()?;
+
+    let mut file = std::fs::OpenOptions::new().create(true).write(true).truncate(false).open(part)?;
+    if start > 0 && response.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+        if end.is_some() {
+            return Err(anyhow::anyhow!("Server ignored range request"));
+        }
+        // Server doesn't support resume: start over
+        warn!("Server ignored the resume request, restarting from zero");
+        file.set_len(0)?;
+        downloaded.store(0, Ordering::Relaxed);
+    } else {
+        file.seek(SeekFrom::Start(start))?;
+    }
+
+    while let Some(chunk) = response.chunk().await? {
+        file.write_all(&chunk)?;
+        downloaded.fetch_add(chunk.len() as u64, Ordering::Relaxed);
+        *written += chunk.len() as u64;
+    }
+    file.flush()?;
+
+    Ok(())
+}
+
+/// The full size from a `Content-Range: bytes */<size>` (or `bytes a-b/<size>`) header
+fn content_range_total(response: &reqwest::Response) -> Option<u64> {
+    response
+        .headers()
+        .get(reqwest::header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .rsplit_once('/')?
+        .1
+        .parse()
+        .ok()
+}
+
+fn spawn_reporter(
+    model: &str,
+    total: Option<u64>,
+    downloaded: Arc<AtomicU64>,
+    tx: mpsc::UnboundedSender<DownloadProgress>,
+) -> tokio::task::JoinHandle<()> {
+    let model = model.to_string();
+    tokio::spawn(async move {
+        let started = Instant::now();
+        let initial = downloaded.load(Ordering::Relaxed);
+        let mut interval = tokio::time::interval(PROGRESS_INTERVAL);
+        loop {
+            interval.tick().await;
+            let bytes = downloaded.load(Ordering::Relaxed);
+            let percent = total.map(|total| (bytes as f32 / total.max(1) as f32 * 100.0).min(100.0));
+
+            // Rate over this session only, so a resumed file doesn't look instant
+            let rate = bytes.saturating_sub(initial) as f64 / started.elapsed().as_secs_f64().max(0.001);
+            let eta_secs = total
+                .filter(|_| rate > 0.0)
+                .map(|total| (total.saturating_sub(bytes) as f64 / rate) as u64);
+
+            let update = DownloadProgress { model: model.clone(), bytes, total, percent, eta_secs };
+            if tx.send(update).is_err() {
+                break;
+            }
+        }
+    })
+}
+
+fn sha256_file(path: &Path) -> Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 1024 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{self, respond};
+    use std::io::Write;
+
+    const BODY: &[u8] = &[7; 1000];
+
+    /// Start offset of a `Range: bytes=<start>-` request
+    fn range_start(request: &test_server::Request) -> Option<usize> {
+        request.header("range")?.strip_prefix("bytes=")?.trim_end_matches('-').parse().ok()
+    }
+
+    /// Serves BODY, honouring ranges, but cuts a fresh first download off after 400 bytes
+    fn flaky_server() -> String {
+        test_server::serve(|index, request, stream| {
+            let start = range_start(request).unwrap_or(0);
+            if index == 0 && start == 0 {
+                let head = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", BODY.len());
+                stream.write_all(head.as_bytes()).unwrap();
+                stream.write_all(&BODY[..400]).unwrap();
+            } else if start >= BODY.len() {
+                respond(stream, "416 Range Not Satisfiable", &[("Content-Range", format!("bytes */{}", BODY.len()))], b"");
+            } else if start > 0 {
+                let range = format!("bytes {}-{}/{}", start, BODY.len() - 1, BODY.len());
+                respond(stream, "206 Partial Content", &[("Content-Range", range)], &BODY[start..]);
+            } else {
+                respond(stream, "200 OK", &[], BODY);
+            }
+        })
+    }
+
+    fn temp_part(name: &str, contents: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("tomchat-download-{}-{}.part", name, std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn resumes_after_a_dropped_connection() {
+        let url = flaky_server();
+        let part = temp_part("resume", b"");
+        let downloaded = AtomicU64::new(0);
+
+        download_single(&reqwest::Client::new(), &url, &part, Some(BODY.len() as u64), &downloaded).await.unwrap();
+
+        assert_eq!(std::fs::read(&part).unwrap(), BODY);
+        std::fs::remove_file(part).unwrap();
+    }
+
+    #[tokio::test]
+    async fn resuming_a_complete_file_finishes() {
+        let url = flaky_server();
+        let part = temp_part("complete", BODY);
+        let downloaded = AtomicU64::new(0);
+
+        // Size unknown, so the request goes out and gets a 416
+        let mut written = 0;
+        fetch_into(&reqwest::Client::new(), &url, &part, BODY.len() as u64, None, &downloaded, &mut written).await.unwrap();
+
+        assert_eq!(std::fs::read(&part).unwrap(), BODY);
+        std::fs::remove_file(part).unwrap();
+    }
+
+    #[tokio::test]
+    async fn oversized_partial_file_restarts() {
+        let url = test_server::serve(|_, request, stream| match range_start(request) {
+            Some(_) => respond(stream, "416 Range Not Satisfiable", &[("Content-Range", format!("bytes */{}", BODY.len()))], b""),
+            None => respond(stream, "200 OK", &[], BODY),
+        });
+        let part = temp_part("oversized", &[1; 1200]);
+        let downloaded = AtomicU64::new(0);
+
+        download_single(&reqwest::Client::new(), &url, &part, None, &downloaded).await.unwrap();
+
+        assert_eq!(std::fs::read(&part).unwrap(), BODY);
+        std::fs::remove_file(part).unwrap();
+    }
+
+    #[tokio::test]
+    async fn probe_reads_the_size_from_a_head_response() {
+        let url = test_server::serve(|_, _, stream| respond(stream, "200 OK", &[("Accept-Ranges", "bytes".to_string())], BODY));
+
+        assert_eq!(probe(&reqwest::Client::new(), &url).await, (Some(BODY.len() as u64), true));
+    }
+
+    #[test]
+    fn model_names_map_to_their_release_assets() {
+        let parakeet = find_model("Parakeet").unwrap();
+        assert_eq!(
+            parakeet.url,
+            "https://github.com/k2-fsa/sherpa-onnx/releases/download/asr-models/sherpa-onnx-nemo-parakeet-tdt-0.6b-v2-int8.tar.bz2"
+        );
+        assert_eq!(find_model("silero-vad").unwrap().url, "https://github.com/k2-fsa/sherpa-onnx/releases/download/asr-models/silero_vad.onnx");
+        assert!(find_model("small.en").is_none());
+        for spec in MODELS {
+            assert!(spec.url.ends_with(&format!("/{}", spec.file_name)), "{} downloads {}", spec.name, spec.url);
+            assert_eq!(spec.archive, spec.file_name.ends_with(".tar.bz2"));
+        }
+    }
+
+    #[test]
+    fn speech_models_resolve_into_the_cache() {
+        let dir = speech_model_dir("parakeet").unwrap();
+        assert_eq!(dir, paths::models_cache_dir().join("sherpa-onnx-nemo-parakeet-tdt-0.6b-v2-int8"));
+
+        let unknown = speech_model_dir("small.en").unwrap_err().to_string();
+        assert!(unknown.contains("expected one of: parakeet"), "{}", unknown);
+        // The VAD model downloads, but isn't a speech model
+        assert!(speech_model_dir("silero-vad").is_err());
+    }
+
+    #[tokio::test]
+    async fn an_installed_or_unknown_model_dir_isnt_downloaded() {
+        let dir = std::env::temp_dir().join(format!("tomchat-download-{}-installed", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in MODEL_FILES {
+            std::fs::write(dir.join(name), b"model").unwrap();
+        }
+        let installed = fetch_missing_speech_model(&dir, None, None).await;
+        std::fs::remove_dir_all(&dir).unwrap();
+        installed.unwrap();
+
+        let unknown = fetch_missing_speech_model(&dir, None, None).await.unwrap_err().to_string();
+        assert!(unknown.contains("No downloadable model unpacks"), "{}", unknown);
+    }
+
+    /// A single-file model served locally, downloaded into a fresh directory
+    async fn download_checked(name: &str, sha256: &str) -> (Result<PathBuf>, PathBuf) {
+        let url = test_server::serve(|_, _, stream| respond(stream, "200 OK", &[], BODY));
+        let spec = ModelSpec {
+            name: "test-model",
+            description: "",
+            url: Box::leak(url.into_boxed_str()),
+            file_name: "model.onnx",
+            sha256: None,
+            archive: false,
+            speech: false,
+        };
+        let dir = std::env::temp_dir().join(format!("tomchat-download-{}-{}", std::process::id(), name));
+        let options = DownloadOptions { dir: dir.clone(), connections: 1, sha256: Some(sha256.to_string()) };
+        (download_model(&spec, &options, None).await, dir)
+    }
+
+    #[tokio::test]
+    async fn a_download_is_kept_only_if_its_checksum_matches() {
+        let expected = format!("{:x}", Sha256::digest(BODY));
+        let (matching, dir) = download_checked("verified", &expected.to_uppercase()).await;
+        assert_eq!(std::fs::read(matching.unwrap()).unwrap(), BODY);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let (mismatched, dir) = download_checked("mismatch", &"0".repeat(64)).await;
+        let error = mismatched.unwrap_err().to_string();
+        let leftovers = std::fs::read_dir(&dir).unwrap().count();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(error.contains("Checksum mismatch"), "{}", error);
+        assert_eq!(leftovers, 0, "neither the model nor a .part to resume from is kept");
+    }
+}
@@ -0,0 +1,387 @@
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+use crate::cancel::CancellationToken;
+use crate::input::HotkeyAction;
+
+/// A toggle press this soon after an automatic stop is a late stop for the recording
+/// that just ended, not the start of a new one. Short enough that a deliberate press
+/// to start the next recording gets through.
+pub const HANDOFF_WINDOW: Duration = Duration::from_millis(300);
+/// A recording that gets no audio for this long is stopped by the watchdog
+pub const RECORDING_STALL: Duration = Duration::from_secs(3);
+
+/// What ended a recording
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopReason {
+    Hotkey,
+    VadTimeout,
+    /// The cancel hotkey or IPC command; the recording is discarded
+    Cancelled,
+    /// audio.max_recording_secs reached under the stop policy
+    #[serde(alias = "length_limit")]
+    MaxDuration,
+    /// The input stopped delivering audio mid-recording; what was captured is transcribed
+    Watchdog,
+    /// A pause in a continuous session: the utterance is sent off and recording carries on
+    Segment,
+}
+
+impl StopReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StopReason::Hotkey => "hotkey",
+            StopReason::VadTimeout => "vad_timeout",
+            StopReason::Cancelled => "cancelled",
+            StopReason::MaxDuration => "max_duration",
+            StopReason::Watchdog => "watchdog",
+            StopReason::Segment => "segment",
+        }
+    }
+
+    /// Stops the app made on its own, which a late toggle press may have meant to make
+    fn is_automatic(self) -> bool {
+        matches!(self, StopReason::VadTimeout | StopReason::MaxDuration | StopReason::Watchdog)
+    }
+}
+
+/// How long a recording lasts (vad.mode)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DictationMode {
+    /// One recording per press
+    #[default]
+    Single,
+    /// Recording stays open until the hotkey is pressed again; each utterance
+    /// the VAD ends is transcribed and output on its own
+    Continuous,
+}
+
+/// Numbers the segments of a continuous session, dropping the ones with too little
+/// audio to be an utterance (vad.min_segment_ms)
+#[derive(Debug, Clone)]
+pub struct Segments {
+    min_samples: usize,
+    /// The silence the VAD waited out before ending a segment, which isn't speech
+    tail_samples: usize,
+    next: u32,
+}
+
+impl Segments {
+    pub fn new(min_samples: usize, tail_samples: usize) -> Self {
+        Self { min_samples, tail_samples, next: 0 }
+    }
+
+    /// The index of a piece of `samples` cut off by `reason`, or None when it's too short
+    /// to keep. Any reason but a segment ends the session, so numbering starts over.
+    pub fn cut(&mut self, samples: usize, reason: StopReason) -> Option<u32> {
+        let session_ends = reason != StopReason::Segment;
+        let tail = if session_ends { 0 } else { self.tail_samples };
+        let kept = samples.saturating_sub(tail) >= self.min_samples;
+        let index = self.next;
+        self.next = match (session_ends, kept) {
+            (true, _) => 0,
+            (false, true) => index + 1,
+            (false, false) => index,
+        };
+        kept.then_some(index)
+    }
+
+    /// A piece dropped before it was cut (cancelled, or no speech in it)
+    pub fn skip(&mut self, reason: StopReason) {
+        if reason != StopReason::Segment {
+            self.next = 0;
+        }
+    }
+}
+
+/// What happens when a recording reaches audio.max_recording_secs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    /// Stop and transcribe what was recorded
+    #[default]
+    Stop,
+    /// Keep recording, dropping the oldest audio
+    Rolling,
+}
+
+impl OverflowPolicy {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OverflowPolicy::Stop => "stop",
+            OverflowPolicy::Rolling => "rolling",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitAction {
+    Within,
+    Stop,
+    /// Drop this many of the oldest samples
+    Drop(usize),
+}
+
+/// audio.max_recording_secs, counted in samples of the recording so far
+#[derive(Debug, Clone, Copy)]
+pub struct RecordingLimit {
+    max_samples: usize,
+    policy: OverflowPolicy,
+}
+
+impl RecordingLimit {
+    /// None when there's no limit (0)
+    pub fn new(max_samples: usize, policy: OverflowPolicy) -> Option<Self> {
+        (max_samples > 0).then_some(Self { max_samples, policy })
+    }
+
+    pub fn policy(&self) -> OverflowPolicy {
+        self.policy
+    }
+
+    /// What to do once the recording holds `recorded` samples
+    pub fn check(&self, recorded: usize) -> LimitAction {
+        if recorded <= self.max_samples {
+            return LimitAction::Within;
+        }
+        match self.policy {
+            OverflowPolicy::Stop => LimitAction::Stop,
+            OverflowPolicy::Rolling => LimitAction::Drop(recorded - self.max_samples),
+        }
+    }
+}
+
+/// Recording state with a single stop latch: the first stop trigger for a
+/// recording wins, later ones for the same recording are ignored
+#[derive(Debug, Default)]
+pub struct RecordingState {
+    pub is_recording: bool,
+    pub speech_detected: bool,
+    /// The hotkey that started the current or last recording; decides its post-processing
+    pub trigger: Option<HotkeyAction>,
+    /// Why and when the last recording stopped
+    stopped: Option<(StopReason, Instant)>,
+    /// Cancels the latest recording's job, wherever it is in the pipeline
+    cancel: CancellationToken,
+}
+
+impl RecordingState {
+    pub fn start(&mut self) {
+        self.is_recording = true;
+        self.speech_detected = false;
+        self.trigger = None;
+        self.stopped = None;
+        self.cancel = CancellationToken::new();
+    }
+
+    /// Token for the latest recording, handed to its job on stop
+    pub fn cancel_token(&self) -> CancellationToken {
+        self.cancel.clone()
+    }
+
+    /// Token for a job that doesn't come from a new recording (a re-decode). It becomes the
+    /// one cancel targets, unless a recording is in progress, which then shares it.
+    pub fn begin_job(&mut self) -> CancellationToken {
+        if !self.is_recording {
+            self.cancel = CancellationToken::new();
+        }
+        self.cancel.clone()
+    }
+
+    /// Latch a stop. False when the recording already stopped; the caller must then do nothing.
+    pub fn request_stop(&mut self, reason: StopReason, now: Instant) -> bool {
+        if !self.is_recording {
+            return false;
+        }
+        self.is_recording = false;
+        self.speech_detected = false;
+        self.stopped = Some((reason, now));
+        true
+    }
+
+    /// Abandon the current recording without a stop (e.g. the device went away)
+    pub fn reset(&mut self) {
+        self.is_recording = false;
+        self.speech_detected = false;
+    }
+
+    /// The automatic stop a toggle press at `now` arrived just too late for, if any.
+    /// Only one press is absorbed: a second one starts a recording as usual.
+    pub fn late_stop(&mut self, now: Instant) -> Option<StopReason> {
+        match self.stopped.take() {
+            Some((reason, at)) if reason.is_automatic() && now.duration_since(at) < HANDOFF_WINDOW => Some(reason),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A stop trigger as the app fires it: latch, and queue a job if the latch was won
+    fn fire(state: &mut RecordingState, reason: StopReason, now: Instant, jobs: &mut Vec<StopReason>) {
+        if state.request_stop(reason, now) {
+            jobs.push(reason);
+        }
+    }
+
+    fn recording() -> RecordingState {
+        let mut state = RecordingState::default();
+        state.start();
+        state
+    }
+
+    #[test]
+    fn the_first_trigger_wins_in_either_order() {
+        let automatic = [StopReason::VadTimeout, StopReason::MaxDuration, StopReason::Watchdog];
+        for reason in automatic {
+            for hotkey_first in [true, false] {
+                let mut state = recording();
+                let mut jobs = Vec::new();
+                let now = Instant::now();
+                let order = if hotkey_first { [StopReason::Hotkey, reason] } else { [reason, StopReason::Hotkey] };
+                for trigger in order {
+                    fire(&mut state, trigger, now, &mut jobs);
+                }
+                assert_eq!(jobs, vec![order[0]], "{:?} then {:?}", order[0], order[1]);
+            }
+        }
+    }
+
+    #[test]
+    fn a_press_right_after_an_automatic_stop_is_absorbed_once() {
+        let mut state = recording();
+        let stopped_at = Instant::now();
+        state.request_stop(StopReason::VadTimeout, stopped_at);
+
+        assert_eq!(state.late_stop(stopped_at + Duration::from_millis(100)), Some(StopReason::VadTimeout));
+        // The next press is meant to start a recording
+        assert_eq!(state.late_stop(stopped_at + Duration::from_millis(200)), None);
+    }
+
+    #[test]
+    fn a_press_after_the_window_is_a_new_recording() {
+        let mut state = recording();
+        let stopped_at = Instant::now();
+        state.request_stop(StopReason::MaxDuration, stopped_at);
+        assert_eq!(state.late_stop(stopped_at + HANDOFF_WINDOW), None);
+    }
+
+    #[test]
+    fn hotkey_stops_absorb_nothing() {
+        let mut state = recording();
+        let stopped_at = Instant::now();
+        state.request_stop(StopReason::Hotkey, stopped_at);
+        assert_eq!(state.late_stop(stopped_at + Duration::from_millis(50)), None);
+    }
+
+    #[test]
+    fn old_history_still_reads() {
+        let reason: StopReason = serde_json::from_str("\"length_limit\"").unwrap();
+        assert_eq!(reason, StopReason::MaxDuration);
+        assert_eq!(serde_json::to_string(&StopReason::Watchdog).unwrap(), "\"watchdog\"");
+    }
+
+    /// Cuts a session as the audio task does, by how many ms each piece holds; the index
+    /// each kept piece gets
+    fn session(segments: &mut Segments, pieces: &[(u64, StopReason)]) -> Vec<Option<u32>> {
+        pieces.iter().map(|&(ms, reason)| segments.cut(ms as usize * 16, reason)).collect()
+    }
+
+    /// 400ms minimum, 500ms of silence ends a segment
+    fn segments() -> Segments {
+        Segments::new(400 * 16, 500 * 16)
+    }
+
+    #[test]
+    fn segments_are_numbered_in_order_and_the_final_piece_counts() {
+        let mut segments = segments();
+        let cut = session(&mut segments, &[
+            (2500, StopReason::Segment),
+            (1500, StopReason::Segment),
+            (900, StopReason::Hotkey),
+        ]);
+        assert_eq!(cut, [Some(0), Some(1), Some(2)]);
+    }
+
+    #[test]
+    fn short_segments_are_dropped_without_using_an_index() {
+        let mut segments = segments();
+        let cut = session(&mut segments, &[
+            (2000, StopReason::Segment),
+            // A cough: 300ms of sound and the silence that ended it
+            (800, StopReason::Segment),
+            (1500, StopReason::Segment),
+        ]);
+        assert_eq!(cut, [Some(0), None, Some(1)]);
+    }
+
+    #[test]
+    fn the_silence_tail_only_counts_against_vad_cuts() {
+        // The same 600ms: 100ms of speech after a pause, or 600ms cut short by the hotkey
+        assert_eq!(segments().cut(600 * 16, StopReason::Segment), None);
+        assert_eq!(segments().cut(600 * 16, StopReason::Hotkey), Some(0));
+    }
+
+    #[test]
+    fn a_new_session_starts_at_zero() {
+        let mut segments = segments();
+        session(&mut segments, &[(2000, StopReason::Segment), (2000, StopReason::Segment)]);
+        // Ended by a stop too short to keep
+        assert_eq!(segments.cut(100 * 16, StopReason::Hotkey), None);
+        assert_eq!(segments.cut(2000 * 16, StopReason::Segment), Some(0));
+
+        // Or by a cancel, which drops its piece before it's cut
+        segments.skip(StopReason::Cancelled);
+        assert_eq!(segments.cut(2000 * 16, StopReason::Segment), Some(0));
+        segments.skip(StopReason::Segment);
+        assert_eq!(segments.cut(2000 * 16, StopReason::Segment), Some(1));
+    }
+
+    /// Feed numbered 10ms chunks into a buffer the way the audio task does, until the
+    /// limit says stop; the buffer and how many chunks went in
+    fn record(limit: Option<RecordingLimit>, chunks: usize) -> (std::collections::VecDeque<usize>, usize) {
+        let mut buffer = std::collections::VecDeque::new();
+        for chunk in 0..chunks {
+            buffer.extend((0..160).map(|i| chunk * 160 + i));
+            match limit.map_or(LimitAction::Within, |limit| limit.check(buffer.len())) {
+                LimitAction::Within => {}
+                LimitAction::Stop => return (buffer, chunk + 1),
+                LimitAction::Drop(excess) => {
+                    buffer.drain(..excess.min(buffer.len()));
+                }
+            }
+        }
+        (buffer, chunks)
+    }
+
+    #[test]
+    fn no_limit_keeps_everything() {
+        assert!(RecordingLimit::new(0, OverflowPolicy::Stop).is_none());
+        let (buffer, fed) = record(None, 100);
+        assert_eq!((buffer.len(), fed), (16_000, 100));
+    }
+
+    #[test]
+    fn stop_policy_stops_at_the_first_chunk_over_the_limit() {
+        // One second
+        let limit = RecordingLimit::new(16_000, OverflowPolicy::Stop);
+        let (buffer, fed) = record(limit, 300);
+        assert_eq!(fed, 101);
+        assert_eq!(buffer.len(), 16_160);
+        assert_eq!(buffer.front(), Some(&0));
+    }
+
+    #[test]
+    fn rolling_policy_keeps_the_newest_audio() {
+        let limit = RecordingLimit::new(16_000, OverflowPolicy::Rolling);
+        let (buffer, fed) = record(limit, 300);
+        assert_eq!(fed, 300);
+        assert_eq!(buffer.len(), 16_000);
+        assert_eq!(buffer.back(), Some(&(300 * 160 - 1)));
+        assert_eq!(buffer.front(), Some(&(300 * 160 - 16_000)));
+    }
+}
@@ -0,0 +1,257 @@
+use std::fmt;
+use std::path::PathBuf;
+
+/// Failures in the transcription → refinement → output pipeline
+#[derive(Debug)]
+pub enum PipelineError {
+    /// The speech model failed to decode the recording
+    ModelDecode { source: anyhow::Error },
+    /// Loading the speech model ran out of memory
+    ModelOutOfMemory { required_mb: u64, available_mb: u64, source: anyhow::Error },
+    /// The recording was too short to contain speech
+    AudioTooShort { duration_ms: u64 },
+    /// Ollama didn't answer within the timeout
+    RefinementTimeout { elapsed_ms: u64 },
+    /// Ollama returned an error
+    RefinementBackend { status: String },
+    /// The text injection backend failed
+    InjectionBackend { backend: &'static str, source: anyhow::Error },
+    /// An output sink couldn't write its destination
+    SinkWrite { sink: &'static str, path: PathBuf, source: std::io::Error },
+    /// The clipboard sink couldn't hand the text to a clipboard tool
+    Clipboard { source: anyhow::Error },
+    /// A pipeline task exited, so a stopped recording can't be processed
+    PipelineUnavailable { task: &'static str },
+    /// Recordings stopped faster than they decode, and the queue had no room for this one
+    DecodeQueueFull { recording_id: u64 },
+}
+
+/// What the pipeline does after an error
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recovery {
+    /// Drop the job
+    Abort,
+    /// Continue with the unrefined text
+    UseUnrefined,
+    /// Skip this sink and carry on with the remaining ones
+    SkipSink,
+    /// Keep the recording buffered instead of dropping it
+    KeepAudio,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    ModelDecode,
+    ModelOutOfMemory,
+    AudioTooShort,
+    RefinementTimeout,
+    RefinementBackend,
+    InjectionBackend,
+    SinkWrite,
+    Clipboard,
+    PipelineUnavailable,
+    DecodeQueueFull,
+}
+
+pub struct ErrorPolicy {
+    pub kind: ErrorKind,
+    /// Event code reported to the GUI
+    pub code: &'static str,
+    pub recovery: Recovery,
+    /// What the user can do about it
+    pub hint: &'static str,
+}
+
+/// How each kind of pipeline error is reported and recovered from
+pub const ERROR_POLICIES: &[ErrorPolicy] = &[
+    ErrorPolicy {
+        kind: ErrorKind::ModelDecode,
+        code: "model_decode_failed",
+        recovery: Recovery::Abort,
+        hint: "Check the model files, or re-run scripts/download-parakeet.sh",
+    },
+    ErrorPolicy {
+        kind: ErrorKind::ModelOutOfMemory,
+        code: "model_out_of_memory",
+        recovery: Recovery::Abort,
+        hint: "Close other applications or switch to a smaller model",
+    },
+    ErrorPolicy {
+        kind: ErrorKind::AudioTooShort,
+        code: "audio_too_short",
+        recovery: Recovery::Abort,
+        hint: "Hold the recording a little longer before stopping",
+    },
+    ErrorPolicy {
+        kind: ErrorKind::RefinementTimeout,
+        code: "refinement_timeout",
+        recovery: Recovery::UseUnrefined,
+        hint: "Increase text_refinement.timeout_ms or use a smaller Ollama model",
+    },
+    ErrorPolicy {
+        kind: ErrorKind::RefinementBackend,
+        code: "refinement_backend_error",
+        recovery: Recovery::UseUnrefined,
+        hint: "Make sure Ollama is running (ollama serve) and the model is pulled",
+    },
+    ErrorPolicy {
+        kind: ErrorKind::InjectionBackend,
+        code: "injection_failed",
+        recovery: Recovery::SkipSink,
+        hint: "Check that the focused window accepts input and X11/Wayland permissions allow typing",
+    },
+    ErrorPolicy {
+        kind: ErrorKind::SinkWrite,
+        code: "sink_write_failed",
+        recovery: Recovery::SkipSink,
+        hint: "Check the sink path exists and is writable",
+    },
+    ErrorPolicy {
+        kind: ErrorKind::Clipboard,
+        code: "clipboard_failed",
+        recovery: Recovery::SkipSink,
+        hint: "Install wl-clipboard (Wayland) or xclip (X11)",
+    },
+    ErrorPolicy {
+        kind: ErrorKind::PipelineUnavailable,
+        code: "pipeline_unavailable",
+        recovery: Recovery::KeepAudio,
+        hint: "The audio task kept crashing (see the log); restart TomChat",
+    },
+    ErrorPolicy {
+        kind: ErrorKind::DecodeQueueFull,
+        code: "decode_queue_full",
+        recovery: Recovery::Abort,
+        hint: "Raise speech.decode_queue_depth, or set speech.decode_queue_overflow = \"drop_oldest\" to keep the newest recordings",
+    },
+];
+
+impl PipelineError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            PipelineError::ModelDecode { .. } => ErrorKind::ModelDecode,
+            PipelineError::ModelOutOfMemory { .. } => ErrorKind::ModelOutOfMemory,
+            PipelineError::AudioTooShort { .. } => ErrorKind::AudioTooShort,
+            PipelineError::RefinementTimeout { .. } => ErrorKind::RefinementTimeout,
+            PipelineError::RefinementBackend { .. } => ErrorKind::RefinementBackend,
+            PipelineError::InjectionBackend { .. } => ErrorKind::InjectionBackend,
+            PipelineError::SinkWrite { .. } => ErrorKind::SinkWrite,
+            PipelineError::Clipboard { .. } => ErrorKind::Clipboard,
+            PipelineError::PipelineUnavailable { .. } => ErrorKind::PipelineUnavailable,
+            PipelineError::DecodeQueueFull { .. } => ErrorKind::DecodeQueueFull,
+        }
+    }
+
+    pub fn policy(&self) -> &'static ErrorPolicy {
+        let kind = self.kind();
+        ERROR_POLICIES
+            .iter()
+            .find(|policy| policy.kind == kind)
+            .expect("every error kind has a policy")
+    }
+
+    /// Structured payload for the GUI error event
+    pub fn to_event(&self) -> serde_json::Value {
+        let policy = self.policy();
+        serde_json::json!({
+            "code": policy.code,
+            "message": self.to_string(),
+            "hint": policy.hint,
+        })
+    }
+}
+
+impl fmt::Display for PipelineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PipelineError::ModelDecode { source } => write!(f, "Speech model failed to decode audio: {}", source),
+            PipelineError::ModelOutOfMemory { required_mb, available_mb, source } => write!(
+                f,
+                "Insufficient memory to load the speech model (~{} MB needed, {} MB available): {}",
+                required_mb, available_mb, source
+            ),
+            PipelineError::AudioTooShort { duration_ms } => write!(f, "Recording too short to transcribe ({}ms)", duration_ms),
+            PipelineError::RefinementTimeout { elapsed_ms } => write!(f, "Text refinement timed out after {}ms", elapsed_ms),
+            PipelineError::RefinementBackend { status } => write!(f, "Ollama generation failed: {}", status),
+            PipelineError::InjectionBackend { backend, source } => write!(f, "Text injection via {} failed: {}", backend, source),
+            PipelineError::SinkWrite { sink, path, source } => write!(f, "{} sink failed to write {:?}: {}", sink, path, source),
+            PipelineError::Clipboard { source } => write!(f, "Copying to the clipboard failed: {}", source),
+            PipelineError::PipelineUnavailable { task } => write!(f, "The {} task is not running, recording not processed", task),
+            PipelineError::DecodeQueueFull { recording_id } => write!(f, "Decode queue full, recording {} dropped", recording_id),
+        }
+    }
+}
+
+impl std::error::Error for PipelineError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PipelineError::ModelDecode { source }
+            | PipelineError::ModelOutOfMemory { source, .. }
+            | PipelineError::InjectionBackend { source, .. }
+            | PipelineError::Clipboard { source } => Some(source.as_ref()),
+            PipelineError::SinkWrite { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn io_error() -> std::io::Error {
+        std::io::Error::new(std::io::ErrorKind::PermissionDenied, "denied")
+    }
+
+    #[test]
+    fn every_error_maps_to_its_policy() {
+        let cases: Vec<(PipelineError, &str, Recovery)> = vec![
+            (PipelineError::ModelDecode { source: anyhow::anyhow!("onnx") }, "model_decode_failed", Recovery::Abort),
+            (
+                PipelineError::ModelOutOfMemory { required_mb: 2400, available_mb: 900, source: anyhow::anyhow!("bad_alloc") },
+                "model_out_of_memory",
+                Recovery::Abort,
+            ),
+            (PipelineError::AudioTooShort { duration_ms: 40 }, "audio_too_short", Recovery::Abort),
+            (PipelineError::RefinementTimeout { elapsed_ms: 5000 }, "refinement_timeout", Recovery::UseUnrefined),
+            (PipelineError::RefinementBackend { status: "500".to_string() }, "refinement_backend_error", Recovery::UseUnrefined),
+            (
+                PipelineError::InjectionBackend { backend: "enigo", source: anyhow::anyhow!("no display") },
+                "injection_failed",
+                Recovery::SkipSink,
+            ),
+            (
+                PipelineError::SinkWrite { sink: "file", path: PathBuf::from("/nope"), source: io_error() },
+                "sink_write_failed",
+                Recovery::SkipSink,
+            ),
+            (PipelineError::Clipboard { source: anyhow::anyhow!("no xclip") }, "clipboard_failed", Recovery::SkipSink),
+            (PipelineError::PipelineUnavailable { task: "audio" }, "pipeline_unavailable", Recovery::KeepAudio),
+            (PipelineError::DecodeQueueFull { recording_id: 3 }, "decode_queue_full", Recovery::Abort),
+        ];
+        for (error, code, recovery) in cases {
+            let policy = error.policy();
+            assert_eq!(policy.kind, error.kind());
+            assert_eq!(policy.code, code);
+            assert_eq!(policy.recovery, recovery, "{}", code);
+        }
+    }
+
+    #[test]
+    fn each_kind_has_exactly_one_policy() {
+        for policy in ERROR_POLICIES {
+            let matching = ERROR_POLICIES.iter().filter(|other| other.kind == policy.kind).count();
+            assert_eq!(matching, 1, "{:?}", policy.kind);
+        }
+        let codes: std::collections::HashSet<_> = ERROR_POLICIES.iter().map(|policy| policy.code).collect();
+        assert_eq!(codes.len(), ERROR_POLICIES.len());
+    }
+
+    #[test]
+    fn event_carries_code_message_and_hint() {
+        let event = PipelineError::RefinementTimeout { elapsed_ms: 1200 }.to_event();
+        assert_eq!(event["code"], "refinement_timeout");
+        assert_eq!(event["message"], "Text refinement timed out after 1200ms");
+        assert_eq!(event["hint"], "Increase text_refinement.timeout_ms or use a smaller Ollama model");
+    }
+}
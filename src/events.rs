@@ -0,0 +1,83 @@
+use serde_json::Value;
+use std::sync::Arc;
+use unicode_segmentation::UnicodeSegmentation;
+use tokio::sync::broadcast;
+
+/// Every status event the app produces, for in-process consumers
+#[derive(Debug, Clone)]
+pub struct BusEvent {
+    pub name: String,
+    pub payload: Value,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+}
+
+impl BusEvent {
+    pub fn new(name: &str, payload: Value) -> Self {
+        Self {
+            name: name.to_string(),
+            payload,
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+        }
+    }
+
+    /// Coarse category subscribers filter on
+    pub fn category(&self) -> &'static str {
+        let name = self.name.as_str();
+        if name == "state_changed" || name.starts_with("recording_") {
+            "recording"
+        } else if name.starts_with("transcri") || name == "partial_transcription" {
+            "transcription"
+        } else if name.starts_with("compose_") {
+            "compose"
+        } else if name.starts_with("refinement_") {
+            "refinement"
+        } else if name.ends_with("_error") || name.ends_with("_warning") {
+            "error"
+        } else {
+            "status"
+        }
+    }
+}
+
+/// Single-line preview of `text`, at most `max_graphemes` long including the ellipsis.
+/// Control characters and newlines become spaces and grapheme clusters are never split.
+pub fn preview(text: &str, max_graphemes: usize) -> String {
+    let flattened: String = text
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let graphemes: Vec<&str> = flattened.graphemes(true).collect();
+    if graphemes.len() <= max_graphemes {
+        return flattened;
+    }
+
+    let keep = max_graphemes.saturating_sub(1);
+    let mut truncated: String = graphemes[..keep].concat();
+    truncated.truncate(truncated.trim_end().len());
+    truncated.push('…');
+    truncated
+}
+
+/// Emits an event about a piece of text: the message carries a short preview,
+/// the full text goes in a separate field. Fields of the last argument (an object,
+/// or null for none) are added alongside.
+pub type EmitText = Arc<dyn Fn(&str, &str, &str, Value) + Send + Sync>;
+
+/// Emits a status event with a human-readable message
+pub type EmitStatus = Arc<dyn Fn(&str, &str) + Send + Sync>;
+/// Emits an event with a structured payload
+pub type EmitData = Arc<dyn Fn(&str, Value) + Send + Sync>;
+
+pub type EventBus = broadcast::Sender<BusEvent>;
+
+pub fn event_bus() -> EventBus {
+    broadcast::channel(256).0
+}
@@ -0,0 +1,97 @@
+/// Split dictated text into form fields at the separator phrase.
+///
+/// Returns None when the separator never appears, so normal injection applies.
+/// Matching is case-insensitive on whole words, and punctuation the model or
+/// refiner put around the separator ("John, next field. Smith") is dropped.
+/// Empty fields are kept so two separators in a row still advance a field.
+pub fn split_fields(text: &str, separator: &str) -> Option<Vec<String>> {
+    let separator: Vec<String> = separator.split_whitespace().map(str::to_lowercase).collect();
+    if separator.is_empty() {
+        return None;
+    }
+
+    // Words are compared case-folded but sliced by their spans in the original text,
+    // since folding can change byte lengths ("İ" -> "i̇")
+    let words = word_spans(text);
+    let mut fields = Vec::new();
+    let mut field_start = 0;
+    let mut i = 0;
+    while i + separator.len() <= words.len() {
+        let window = &words[i..i + separator.len()];
+        if window.iter().zip(&separator).all(|((_, _, word), wanted)| word == wanted) {
+            fields.push(trim_field(&text[field_start..window[0].0]));
+            field_start = window[separator.len() - 1].1;
+            i += separator.len();
+        } else {
+            i += 1;
+        }
+    }
+
+    if fields.is_empty() {
+        return None;
+    }
+    fields.push(trim_field(&text[field_start..]));
+    Some(fields)
+}
+
+/// Whitespace-separated words as (start, end, lowercase word without surrounding punctuation)
+fn word_spans(text: &str) -> Vec<(usize, usize, String)> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (index, c) in text.char_indices().chain(std::iter::once((text.len(), ' '))) {
+        match (start, c.is_whitespace()) {
+            (None, false) => start = Some(index),
+            (Some(from), true) => {
+                let word = text[from..index].trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase();
+                words.push((from, index, word));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    words
+}
+
+fn trim_field(field: &str) -> String {
+    field
+        .trim_matches(|c: char| c.is_whitespace() || matches!(c, ',' | '.' | ';' | ':'))
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(text: &str) -> Option<Vec<String>> {
+        split_fields(text, "next field")
+    }
+
+    #[test]
+    fn splits_at_the_separator() {
+        assert_eq!(split("John next field Smith").unwrap(), ["John", "Smith"]);
+        assert_eq!(split("John, Next Field. Smith.").unwrap(), ["John", "Smith"]);
+    }
+
+    #[test]
+    fn no_separator_means_no_form_fill() {
+        assert_eq!(split("John Smith"), None);
+        assert_eq!(split("the next fielder"), None);
+        assert_eq!(split_fields("John next field Smith", "  "), None);
+    }
+
+    #[test]
+    fn repeated_separators_keep_empty_fields() {
+        assert_eq!(split("John next field next field Smith").unwrap(), ["John", "", "Smith"]);
+        assert_eq!(split("next field Smith").unwrap(), ["", "Smith"]);
+    }
+
+    #[test]
+    fn non_ascii_text_keeps_its_spans() {
+        // Lowercasing "İ" grows it by a byte, which used to disable splitting
+        assert_eq!(split("İstanbul next field Straße").unwrap(), ["İstanbul", "Straße"]);
+        assert_eq!(
+            split_fields("Zoë NÄCHSTES Feld Müller", "nächstes feld").unwrap(),
+            ["Zoë", "Müller"]
+        );
+    }
+}
@@ -0,0 +1,182 @@
+use std::collections::VecDeque;
+use std::io::Write;
+use std::sync::{Arc, Condvar, Mutex};
+use tokio::sync::Notify;
+use tracing::{error, warn};
+
+/// Lines queued for stdout before we start dropping
+const QUEUE_CAPACITY: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    /// Dropped first: audio levels, heartbeats
+    Low,
+    Normal,
+    /// Never dropped: state transitions and transcription results
+    Critical,
+}
+
+impl Priority {
+    pub fn for_event(event: &str) -> Self {
+        match event {
+            "audio_level" | "heartbeat" => Priority::Low,
+            "state_changed" | "recording_started" | "recording_stopped"
+            | "transcription_complete" | "transcription_result" | "transcription_error"
+            | "pipeline_error" => Priority::Critical,
+            _ => Priority::Normal,
+        }
+    }
+}
+
+/// The JSON line the GUI reads: `fields` (an object) plus the event name and a timestamp
+pub fn event_line(event: &str, fields: serde_json::Value) -> String {
+    let mut line = match fields {
+        serde_json::Value::Object(map) => map,
+        other => serde_json::Map::from_iter([("data".to_string(), other)]),
+    };
+    line.insert("event".into(), event.into());
+    line.insert(
+        "timestamp".into(),
+        // A clock set before 1970 isn't worth losing the event over
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs())
+            .into(),
+    );
+    serde_json::Value::Object(line).to_string()
+}
+
+enum Line {
+    Event(Priority, String),
+    /// Formatted at write time so it carries the latest drop count
+    Heartbeat,
+}
+
+impl Line {
+    fn priority(&self) -> Priority {
+        match self {
+            Line::Event(priority, _) => *priority,
+            Line::Heartbeat => Priority::Low,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Queue {
+    lines: VecDeque<Line>,
+    dropped: u64,
+    closed: bool,
+}
+
+/// Writes GUI event lines to stdout from a dedicated thread so a stalled
+/// consumer can never block the pipeline
+#[derive(Clone)]
+pub struct GuiWriter {
+    queue: Arc<(Mutex<Queue>, Condvar)>,
+    disconnected: Arc<Notify>,
+}
+
+impl GuiWriter {
+    pub fn spawn() -> Self {
+        let writer = Self {
+            queue: Arc::new((Mutex::new(Queue::default()), Condvar::new())),
+            disconnected: Arc::new(Notify::new()),
+        };
+
+        let thread_writer = writer.clone();
+        std::thread::spawn(move || thread_writer.write_loop());
+
+        writer
+    }
+
+    /// Queue an event line, evicting lower-priority lines if the queue is full
+    pub fn send(&self, event: &str, line: String) {
+        self.push(Line::Event(Priority::for_event(event), line));
+    }
+
+    /// Queue an event built by [`event_line`]
+    pub fn emit(&self, event: &str, fields: serde_json::Value) {
+        self.send(event, event_line(event, fields));
+    }
+
+    /// Queue a heartbeat reporting how many events were dropped since the last one
+    pub fn heartbeat(&self) {
+        self.push(Line::Heartbeat);
+    }
+
+    /// Resolves when stdout is gone (e.g. EPIPE because the consumer died)
+    pub async fn disconnected(&self) {
+        self.disconnected.notified().await
+    }
+
+    fn push(&self, line: Line) {
+        let (lock, condvar) = &*self.queue;
+        let mut queue = lock.lock().unwrap();
+        if queue.closed {
+            return;
+        }
+
+        if queue.lines.len() >= QUEUE_CAPACITY {
+            let priority = line.priority();
+            // Evict the oldest line of the lowest priority below this one
+            let victim = [Priority::Low, Priority::Normal]
+                .into_iter()
+                .filter(|p| *p <= priority)
+                .find_map(|p| queue.lines.iter().position(|l| l.priority() == p));
+
+            match victim {
+                Some(index) => {
+                    queue.lines.remove(index);
+                    queue.dropped += 1;
+                }
+                // Nothing cheaper to drop - drop the new line unless it's critical
+                None if priority != Priority::Critical => {
+                    queue.dropped += 1;
+                    return;
+                }
+                None => {}
+            }
+        }
+
+        queue.lines.push_back(line);
+        condvar.notify_one();
+    }
+
+    fn write_loop(&self) {
+        let (lock, condvar) = &*self.queue;
+        let stdout = std::io::stdout();
+
+        loop {
+            let (line, dropped) = {
+                let mut queue = lock.lock().unwrap();
+                while queue.lines.is_empty() {
+                    queue = condvar.wait(queue).unwrap();
+                }
+                let line = queue.lines.pop_front().unwrap();
+                let dropped = if matches!(line, Line::Heartbeat) {
+                    std::mem::take(&mut queue.dropped)
+                } else {
+                    0
+                };
+                (line, dropped)
+            };
+
+            let text = match line {
+                Line::Event(_, text) => text,
+                Line::Heartbeat => event_line("heartbeat", serde_json::json!({ "data": { "dropped_events": dropped } })),
+            };
+
+            let mut out = stdout.lock();
+            if let Err(e) = writeln!(out, "{}", text).and_then(|_| out.flush()) {
+                if e.kind() == std::io::ErrorKind::BrokenPipe {
+                    warn!("GUI consumer closed stdout, shutting down");
+                } else {
+                    error!("Failed to write GUI event: {}", e);
+                }
+                lock.lock().unwrap().closed = true;
+                self.disconnected.notify_one();
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
+
+use crate::endpoint::StopReason;
+use crate::paths;
+use crate::redact::redact;
+
+/// Bumped whenever the session state layout changes; older files are discarded
+const SESSION_STATE_VERSION: u32 = 1;
+/// Oldest entries are dropped until the state file fits
+const MAX_SESSION_STATE_BYTES: usize = 256 * 1024;
+
+/// One completed dictation as it went through the pipeline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub recording_id: u64,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    pub raw: String,
+    pub refined: Option<String>,
+    pub processed: String,
+    /// What ended the recording; None for re-decodes and older entries
+    #[serde(default)]
+    pub stop_reason: Option<StopReason>,
+    /// None for older entries
+    #[serde(default)]
+    pub timings: Option<StageTimings>,
+    /// The saved recording (debug.save_audio_dir), if any
+    #[serde(default)]
+    pub audio_path: Option<PathBuf>,
+}
+
+/// How long the dictation took at each stage, for comparing against a replay
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct StageTimings {
+    /// Length of the recording
+    pub audio_ms: u64,
+    pub decode_ms: u64,
+    /// Punctuation, number normalization and refinement
+    pub refine_ms: u64,
+}
+
+fn last_entry_path() -> PathBuf {
+    paths::data_dir().join("last_dictation.json")
+}
+
+/// Persist the most recent dictation so it can be exported for bug reports
+pub fn save_last(entry: &HistoryEntry) -> Result<()> {
+    let path = last_entry_path();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, serde_json::to_string_pretty(entry)?)?;
+    debug!("Saved last dictation to {:?}", path);
+    Ok(())
+}
+
+pub fn load_last() -> Result<Option<HistoryEntry>> {
+    let path = last_entry_path();
+    if !path.exists() {
+        return Ok(None);
+    }
+    let contents = std::fs::read_to_string(path)?;
+    Ok(Some(serde_json::from_str(&contents)?))
+}
+
+/// Remove the saved last dictation; returns whether there was one
+pub fn clear_last() -> Result<bool> {
+    remove_if_exists(&last_entry_path())
+}
+
+pub fn session_state_path() -> PathBuf {
+    paths::state_dir().join("session.json")
+}
+
+/// Remove the persisted session history; returns whether there was one
+pub fn clear_session_state() -> Result<bool> {
+    remove_if_exists(&session_state_path())
+}
+
+fn remove_if_exists(path: &Path) -> Result<bool> {
+    match std::fs::remove_file(path) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SessionState {
+    version: u32,
+    entries: Vec<HistoryEntry>,
+}
+
+/// The last few dictations, persisted so repeat works across restarts
+#[derive(Debug)]
+pub struct SessionHistory {
+    entries: VecDeque<HistoryEntry>,
+    capacity: usize,
+    /// Persist redacted copies instead of the raw text
+    redact: bool,
+    dirty: bool,
+}
+
+impl SessionHistory {
+    pub fn new(capacity: usize, redact: bool) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+            redact,
+            dirty: false,
+        }
+    }
+
+    /// Load the persisted ring; a missing, stale or corrupt file just starts empty
+    pub fn restore(capacity: usize, redact: bool) -> Self {
+        let mut history = Self::new(capacity, redact);
+        if capacity == 0 {
+            return history;
+        }
+
+        let path = session_state_path();
+        let state = match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str::<SessionState>(&contents),
+            Err(_) => return history,
+        };
+        match state {
+            Ok(state) if state.version == SESSION_STATE_VERSION => {
+                let skip = state.entries.len().saturating_sub(capacity);
+                history.entries.extend(state.entries.into_iter().skip(skip));
+                info!("Restored {} dictation(s) from {:?}", history.entries.len(), path);
+            }
+            Ok(state) => warn!("Discarding session state version {} (expected {})", state.version, SESSION_STATE_VERSION),
+            Err(e) => warn!("Discarding unreadable session state {:?}: {}", path, e),
+        }
+        history
+    }
+
+    pub fn push(&mut self, entry: HistoryEntry) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+        self.dirty = true;
+    }
+
+    pub fn last(&self) -> Option<&HistoryEntry> {
+        self.entries.back()
+    }
+
+    /// Write the ring to the state file if it changed (write + rename, so it's never half-written)
+    pub fn persist(&mut self) -> Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+
+        let mut entries: Vec<HistoryEntry> = self.entries.iter().cloned().collect();
+        if self.redact {
+            for entry in &mut entries {
+                entry.raw = redact(&entry.raw);
+                entry.refined = entry.refined.as_deref().map(redact);
+                entry.processed = redact(&entry.processed);
+            }
+        }
+
+        let mut state = SessionState { version: SESSION_STATE_VERSION, entries };
+        let mut contents = serde_json::to_string(&state)?;
+        while contents.len() > MAX_SESSION_STATE_BYTES && !state.entries.is_empty() {
+            state.entries.remove(0);
+            contents = serde_json::to_string(&state)?;
+        }
+
+        let path = session_state_path();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, contents)?;
+        std::fs::rename(&tmp, &path)?;
+
+        self.dirty = false;
+        debug!("Persisted {} dictation(s) to {:?}", state.entries.len(), path);
+        Ok(())
+    }
+}
@@ -0,0 +1,171 @@
+use anyhow::Result;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tokio::sync::watch;
+use tracing::{debug, info};
+
+use crate::config::IndicatorConfig;
+
+const RECORDING_TITLE: &str = "● REC tomchat";
+
+/// Linux input event constants (see linux/input-event-codes.h)
+const EV_SYN: u16 = 0x00;
+const EV_LED: u16 = 0x11;
+const SYN_REPORT: u16 = 0x00;
+const LED_SCROLLL: u16 = 0x02;
+
+/// Something that can show whether we are recording
+pub trait IndicatorSink: Send {
+    fn name(&self) -> &'static str;
+    fn set_recording(&mut self, recording: bool) -> Result<()>;
+}
+
+/// Where indicator bytes end up - abstracted so sinks don't depend on real devices
+pub trait IndicatorBackend: Send {
+    fn write(&mut self, bytes: &[u8]) -> Result<()>;
+}
+
+/// Writes to a device path, opening it fresh for each write
+pub struct DeviceBackend {
+    path: PathBuf,
+}
+
+impl DeviceBackend {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self { path: path.as_ref().to_path_buf() }
+    }
+}
+
+impl IndicatorBackend for DeviceBackend {
+    fn write(&mut self, bytes: &[u8]) -> Result<()> {
+        let mut device = OpenOptions::new().write(true).open(&self.path)?;
+        device.write_all(bytes)?;
+        device.flush()?;
+        Ok(())
+    }
+}
+
+/// Sets the controlling terminal's title while recording
+pub struct TerminalTitleIndicator<B: IndicatorBackend> {
+    backend: B,
+}
+
+impl<B: IndicatorBackend> TerminalTitleIndicator<B> {
+    pub fn new(backend: B) -> Self {
+        Self { backend }
+    }
+}
+
+impl<B: IndicatorBackend> IndicatorSink for TerminalTitleIndicator<B> {
+    fn name(&self) -> &'static str {
+        "terminal title"
+    }
+
+    fn set_recording(&mut self, recording: bool) -> Result<()> {
+        self.backend.write(title_sequence(recording).as_bytes())
+    }
+}
+
+/// Escape sequence for the terminal title. The previous title is saved on the
+/// xterm title stack when recording starts and popped again when it stops.
+pub fn title_sequence(recording: bool) -> String {
+    if recording {
+        format!("\x1b[22;0t\x1b]0;{}\x07", RECORDING_TITLE)
+    } else {
+        "\x1b[23;0t".to_string()
+    }
+}
+
+/// Toggles the ScrollLock LED through the evdev LED interface
+pub struct ScrollLockIndicator<B: IndicatorBackend> {
+    backend: B,
+}
+
+impl<B: IndicatorBackend> ScrollLockIndicator<B> {
+    pub fn new(backend: B) -> Self {
+        Self { backend }
+    }
+}
+
+impl<B: IndicatorBackend> IndicatorSink for ScrollLockIndicator<B> {
+    fn name(&self) -> &'static str {
+        "ScrollLock LED"
+    }
+
+    fn set_recording(&mut self, recording: bool) -> Result<()> {
+        let mut bytes = input_event(EV_LED, LED_SCROLLL, recording as i32);
+        bytes.extend(input_event(EV_SYN, SYN_REPORT, 0));
+        self.backend.write(&bytes)
+    }
+}
+
+/// Encode a `struct input_event` with a zero timestamp (the kernel fills it in)
+pub fn input_event(event_type: u16, code: u16, value: i32) -> Vec<u8> {
+    // struct timeval is two native longs
+    let time_len = 2 * std::mem::size_of::<usize>();
+    let mut bytes = vec![0u8; time_len];
+    bytes.extend_from_slice(&event_type.to_ne_bytes());
+    bytes.extend_from_slice(&code.to_ne_bytes());
+    bytes.extend_from_slice(&value.to_ne_bytes());
+    bytes
+}
+
+/// Find a keyboard event device under /dev/input/by-path
+fn find_keyboard_device() -> Option<PathBuf> {
+    std::fs::read_dir("/dev/input/by-path")
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .find(|path| {
+            path.file_name()
+                .map(|name| name.to_string_lossy().ends_with("-event-kbd"))
+                .unwrap_or(false)
+        })
+}
+
+/// Build the indicator sinks enabled in config
+pub fn build_indicators(config: &IndicatorConfig) -> Vec<Box<dyn IndicatorSink>> {
+    let mut sinks: Vec<Box<dyn IndicatorSink>> = Vec::new();
+
+    if config.terminal_title {
+        sinks.push(Box::new(TerminalTitleIndicator::new(DeviceBackend::new("/dev/tty"))));
+    }
+
+    if config.scroll_lock_led {
+        match config.led_device.clone().or_else(find_keyboard_device) {
+            Some(device) => {
+                debug!("Using keyboard LED device: {:?}", device);
+                sinks.push(Box::new(ScrollLockIndicator::new(DeviceBackend::new(device))));
+            }
+            None => debug!("No keyboard event device found for ScrollLock LED indicator"),
+        }
+    }
+
+    sinks
+}
+
+/// Drive indicator sinks from recording state changes until the sender is dropped
+pub async fn run_indicators(mut sinks: Vec<Box<dyn IndicatorSink>>, mut state_rx: watch::Receiver<bool>) {
+    if sinks.is_empty() {
+        return;
+    }
+
+    info!("Recording indicators enabled: {}",
+          sinks.iter().map(|s| s.name()).collect::<Vec<_>>().join(", "));
+
+    while state_rx.changed().await.is_ok() {
+        let recording = *state_rx.borrow_and_update();
+        for sink in sinks.iter_mut() {
+            // Indicators are best-effort
+            if let Err(e) = sink.set_recording(recording) {
+                debug!("Failed to update {} indicator: {}", sink.name(), e);
+            }
+        }
+    }
+
+    // Never leave the indicator stuck on
+    for sink in sinks.iter_mut() {
+        let _ = sink.set_recording(false);
+    }
+}
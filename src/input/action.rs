@@ -0,0 +1,151 @@
+//! What a hotkey does. Actions are the stable names used anywhere outside this
+//! process (config keys, events); the numeric ids the backends hand out are only
+//! routing details for this run and are never stored or sent.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HotkeyAction {
+    ToggleRecording,
+    Compose,
+    ComposeCancel,
+    Retranscribe,
+    Correction,
+    RepeatLast,
+    Cancel,
+    /// Records like ToggleRecording, output without refinement
+    RecordRaw,
+    /// Records like ToggleRecording, output through the refiner even when text_refinement is off
+    RecordRefined,
+}
+
+impl HotkeyAction {
+    /// Same as the [hotkeys] config key
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HotkeyAction::ToggleRecording => "toggle_recording",
+            HotkeyAction::Compose => "compose",
+            HotkeyAction::ComposeCancel => "compose_cancel",
+            HotkeyAction::Retranscribe => "retranscribe",
+            HotkeyAction::Correction => "correction",
+            HotkeyAction::RepeatLast => "repeat_last",
+            HotkeyAction::Cancel => "cancel",
+            HotkeyAction::RecordRaw => "record_raw",
+            HotkeyAction::RecordRefined => "record_refined",
+        }
+    }
+}
+
+/// Registration ids to actions, rebuilt from config at every start
+#[derive(Debug, Clone, Default)]
+pub struct HotkeyRouter {
+    routes: HashMap<u32, (HotkeyAction, String)>,
+}
+
+impl HotkeyRouter {
+    /// Route `id` to `action`. Two actions on one combination would make presses ambiguous.
+    pub fn bind(&mut self, id: u32, action: HotkeyAction, combination: &str) -> Result<()> {
+        if let Some((existing, _)) = self.routes.get(&id) {
+            if *existing != action {
+                return Err(anyhow::anyhow!(
+                    "Hotkey '{}' is bound to both {} and {}",
+                    combination,
+                    existing.as_str(),
+                    action.as_str()
+                ));
+            }
+        }
+        self.routes.insert(id, (action, combination.to_string()));
+        Ok(())
+    }
+
+    pub fn unbind(&mut self, id: u32) {
+        self.routes.remove(&id);
+    }
+
+    pub fn route(&self, id: u32) -> Option<HotkeyAction> {
+        self.routes.get(&id).map(|(action, _)| *action)
+    }
+
+    /// The action bound to `combination`, as written in config
+    pub fn bound_to(&self, combination: &str) -> Option<HotkeyAction> {
+        self.routes
+            .values()
+            .find(|(_, bound)| bound.eq_ignore_ascii_case(combination))
+            .map(|(action, _)| *action)
+    }
+
+    /// The id and combination `action` is bound to
+    pub fn binding(&self, action: HotkeyAction) -> Option<(u32, &str)> {
+        self.routes
+            .iter()
+            .find(|(_, (bound, _))| *bound == action)
+            .map(|(id, (_, combination))| (*id, combination.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::hotkey::parse_hotkey_string;
+
+    /// Register `bindings` as a fresh start does
+    fn start(bindings: &[(HotkeyAction, &str)]) -> HotkeyRouter {
+        let mut router = HotkeyRouter::default();
+        for &(action, combination) in bindings {
+            let id = parse_hotkey_string(combination, false).unwrap().id();
+            router.bind(id, action, combination).unwrap();
+        }
+        router
+    }
+
+    #[test]
+    fn persisted_actions_survive_a_rebind_across_restarts() {
+        let first = start(&[
+            (HotkeyAction::ToggleRecording, "ctrl+shift+space"),
+            (HotkeyAction::Compose, "ctrl+shift+c"),
+        ]);
+        let (compose_id, _) = first.binding(HotkeyAction::Compose).unwrap();
+        // What the last run stored: the action by name, never its id
+        let persisted = serde_json::to_string(&HotkeyAction::Compose).unwrap();
+        assert_eq!(persisted, "\"compose\"");
+
+        // Compose's old combination now belongs to toggle_recording
+        let second = start(&[
+            (HotkeyAction::ToggleRecording, "ctrl+shift+c"),
+            (HotkeyAction::Compose, "ctrl+alt+c"),
+        ]);
+        let restored: HotkeyAction = serde_json::from_str(&persisted).unwrap();
+        assert_eq!(restored, HotkeyAction::Compose);
+        assert_eq!(second.binding(restored).map(|(_, combination)| combination), Some("ctrl+alt+c"));
+
+        // A stale id would now toggle recording, which is why ids are never stored
+        assert_eq!(second.route(compose_id), Some(HotkeyAction::ToggleRecording));
+        let (new_id, _) = second.binding(HotkeyAction::Compose).unwrap();
+        assert_ne!(new_id, compose_id);
+        assert_eq!(second.route(new_id), Some(HotkeyAction::Compose));
+    }
+
+    #[test]
+    fn one_combination_cant_route_to_two_actions() {
+        let mut router = start(&[(HotkeyAction::ToggleRecording, "ctrl+shift+space")]);
+        let (id, _) = router.binding(HotkeyAction::ToggleRecording).unwrap();
+        assert!(router.bind(id, HotkeyAction::Cancel, "ctrl+shift+space").is_err());
+        // Binding the same action again is fine
+        router.bind(id, HotkeyAction::ToggleRecording, "ctrl+shift+space").unwrap();
+
+        assert_eq!(router.bound_to("CTRL+Shift+Space"), Some(HotkeyAction::ToggleRecording));
+        router.unbind(id);
+        assert_eq!(router.route(id), None);
+    }
+
+    #[test]
+    fn names_match_the_config_keys() {
+        for action in [HotkeyAction::ToggleRecording, HotkeyAction::RecordRaw, HotkeyAction::ComposeCancel] {
+            assert_eq!(serde_json::to_string(&action).unwrap(), format!("\"{}\"", action.as_str()));
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TapOutcome {
+    Single,
+    Double,
+    /// Waiting to see whether a second press follows
+    Pending,
+}
+
+/// Tells a single press of a key from a double tap (hotkeys.double_tap_ms). A first press
+/// is held back for the window; a zero window turns detection off and adds no delay.
+#[derive(Debug)]
+pub struct DoubleTap {
+    window: Duration,
+    pending: Option<Instant>,
+}
+
+impl DoubleTap {
+    pub fn new(window: Duration) -> Self {
+        Self { window, pending: None }
+    }
+
+    pub fn press(&mut self, now: Instant) -> TapOutcome {
+        if self.window.is_zero() {
+            return TapOutcome::Single;
+        }
+        match self.pending.take() {
+            Some(first) if now.duration_since(first) <= self.window => TapOutcome::Double,
+            // A first press whose window ran out unnoticed starts over
+            _ => {
+                self.pending = Some(now);
+                TapOutcome::Pending
+            }
+        }
+    }
+
+    /// When a held-back press becomes a single tap
+    pub fn deadline(&self) -> Option<Instant> {
+        self.pending.map(|first| first + self.window)
+    }
+
+    /// The held-back press, once its window has passed
+    pub fn expire(&mut self, now: Instant) -> Option<TapOutcome> {
+        let deadline = self.deadline()?;
+        if now < deadline {
+            return None;
+        }
+        self.pending = None;
+        Some(TapOutcome::Single)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOW: Duration = Duration::from_millis(350);
+
+    fn ms(start: Instant, millis: u64) -> Instant {
+        start + Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn a_zero_window_makes_every_press_a_single_tap() {
+        let mut taps = DoubleTap::new(Duration::ZERO);
+        let start = Instant::now();
+
+        assert_eq!(taps.press(start), TapOutcome::Single);
+        assert_eq!(taps.press(ms(start, 10)), TapOutcome::Single);
+        assert_eq!(taps.deadline(), None);
+    }
+
+    #[test]
+    fn a_second_press_inside_the_window_is_a_double_tap() {
+        let mut taps = DoubleTap::new(WINDOW);
+        let start = Instant::now();
+
+        assert_eq!(taps.press(start), TapOutcome::Pending);
+        assert_eq!(taps.deadline(), Some(ms(start, 350)));
+        assert_eq!(taps.press(ms(start, 350)), TapOutcome::Double);
+        // Nothing is left held back
+        assert_eq!(taps.deadline(), None);
+        assert_eq!(taps.expire(ms(start, 1000)), None);
+    }
+
+    #[test]
+    fn a_lone_press_becomes_a_single_tap_once_the_window_passes() {
+        let mut taps = DoubleTap::new(WINDOW);
+        let start = Instant::now();
+
+        taps.press(start);
+        assert_eq!(taps.expire(ms(start, 349)), None);
+        assert_eq!(taps.expire(ms(start, 350)), Some(TapOutcome::Single));
+        assert_eq!(taps.expire(ms(start, 351)), None);
+    }
+
+    #[test]
+    fn a_press_after_the_window_starts_a_new_tap() {
+        let mut taps = DoubleTap::new(WINDOW);
+        let start = Instant::now();
+
+        taps.press(start);
+        assert_eq!(taps.press(ms(start, 351)), TapOutcome::Pending);
+        assert_eq!(taps.deadline(), Some(ms(start, 701)));
+    }
+
+    #[test]
+    fn three_quick_presses_are_a_double_then_a_new_first_press() {
+        let mut taps = DoubleTap::new(WINDOW);
+        let start = Instant::now();
+
+        let outcomes: Vec<_> = [0, 100, 200].into_iter().map(|at| taps.press(ms(start, at))).collect();
+
+        assert_eq!(outcomes, [TapOutcome::Pending, TapOutcome::Double, TapOutcome::Pending]);
+        assert_eq!(taps.expire(ms(start, 550)), Some(TapOutcome::Single));
+    }
+}
@@ -0,0 +1,173 @@
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+use super::action::HotkeyAction;
+
+/// How the recording hotkeys behave (hotkeys.mode)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HotkeyMode {
+    /// One press starts recording, the next stops it
+    #[default]
+    Toggle,
+    /// Recording lasts as long as the key is held
+    Hold,
+}
+
+/// What a recording hotkey event asks for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordGesture {
+    /// Start or stop, whichever applies (toggle mode)
+    Toggle,
+    Start,
+    Stop,
+    /// Released too soon after the press: drop the recording instead of transcribing it
+    Discard(Duration),
+    Ignore,
+}
+
+/// Turns press/release events of toggle_recording and correction into gestures
+#[derive(Debug)]
+pub struct RecordKey {
+    mode: HotkeyMode,
+    min_hold: Duration,
+    /// The key holding the current recording, and when it went down. Only its
+    /// release ends the recording; the other key is ignored until then.
+    held: Option<(HotkeyAction, Instant)>,
+}
+
+impl RecordKey {
+    pub fn new(mode: HotkeyMode, min_hold: Duration) -> Self {
+        Self {
+            mode,
+            min_hold,
+            held: None,
+        }
+    }
+
+    pub fn event(&mut self, key: HotkeyAction, pressed: bool, recording: bool, now: Instant) -> RecordGesture {
+        match (self.mode, pressed) {
+            (HotkeyMode::Toggle, true) => RecordGesture::Toggle,
+            (HotkeyMode::Toggle, false) => RecordGesture::Ignore,
+            (HotkeyMode::Hold, true) => {
+                // Key repeat, another key already holding, or a recording started some other way
+                if self.held.is_some() || recording {
+                    return RecordGesture::Ignore;
+                }
+                self.held = Some((key, now));
+                RecordGesture::Start
+            }
+            (HotkeyMode::Hold, false) => {
+                let pressed_at = match self.held {
+                    Some((holder, pressed_at)) if holder == key => pressed_at,
+                    _ => return RecordGesture::Ignore,
+                };
+                self.held = None;
+                let held = now.duration_since(pressed_at);
+                if held < self.min_hold {
+                    RecordGesture::Discard(held)
+                } else {
+                    RecordGesture::Stop
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIN_HOLD: Duration = Duration::from_millis(150);
+    const TOGGLE: HotkeyAction = HotkeyAction::ToggleRecording;
+    const CORRECTION: HotkeyAction = HotkeyAction::Correction;
+
+    /// Feed (key, pressed, ms since start) events, tracking whether a recording runs
+    /// the way the app does, and collect the gestures
+    fn run(mode: HotkeyMode, events: &[(HotkeyAction, bool, u64)]) -> Vec<RecordGesture> {
+        let mut record_key = RecordKey::new(mode, MIN_HOLD);
+        let start = Instant::now();
+        let mut recording = false;
+        events
+            .iter()
+            .map(|&(key, pressed, ms)| {
+                let gesture = record_key.event(key, pressed, recording, start + Duration::from_millis(ms));
+                match gesture {
+                    RecordGesture::Start => recording = true,
+                    RecordGesture::Stop | RecordGesture::Discard(_) => recording = false,
+                    RecordGesture::Toggle => recording = !recording,
+                    RecordGesture::Ignore => {}
+                }
+                gesture
+            })
+            .collect()
+    }
+
+    #[test]
+    fn toggle_mode_acts_on_presses_only() {
+        let gestures = run(HotkeyMode::Toggle, &[(TOGGLE, true, 0), (TOGGLE, false, 50), (TOGGLE, true, 900), (TOGGLE, false, 950)]);
+
+        assert_eq!(gestures, [RecordGesture::Toggle, RecordGesture::Ignore, RecordGesture::Toggle, RecordGesture::Ignore]);
+    }
+
+    #[test]
+    fn hold_mode_records_while_the_key_is_down() {
+        let gestures = run(HotkeyMode::Hold, &[(TOGGLE, true, 0), (TOGGLE, false, 2000)]);
+
+        assert_eq!(gestures, [RecordGesture::Start, RecordGesture::Stop]);
+    }
+
+    #[test]
+    fn key_repeat_and_stray_releases_are_ignored() {
+        let gestures = run(
+            HotkeyMode::Hold,
+            &[(TOGGLE, false, 0), (TOGGLE, true, 100), (TOGGLE, true, 600), (TOGGLE, true, 650), (TOGGLE, false, 1000), (TOGGLE, false, 1100)],
+        );
+
+        assert_eq!(
+            gestures,
+            [RecordGesture::Ignore, RecordGesture::Start, RecordGesture::Ignore, RecordGesture::Ignore, RecordGesture::Stop, RecordGesture::Ignore]
+        );
+    }
+
+    #[test]
+    fn a_short_hold_discards_the_recording() {
+        let gestures = run(HotkeyMode::Hold, &[(TOGGLE, true, 0), (TOGGLE, false, 100), (TOGGLE, true, 200), (TOGGLE, false, 350)]);
+
+        assert_eq!(
+            gestures,
+            [RecordGesture::Start, RecordGesture::Discard(Duration::from_millis(100)), RecordGesture::Start, RecordGesture::Stop]
+        );
+    }
+
+    #[test]
+    fn overlapping_holds_belong_to_the_first_key() {
+        // Correction goes down mid-recording and comes up before the toggle key
+        let gestures = run(
+            HotkeyMode::Hold,
+            &[(TOGGLE, true, 0), (CORRECTION, true, 500), (CORRECTION, false, 550), (TOGGLE, false, 1000)],
+        );
+
+        assert_eq!(gestures, [RecordGesture::Start, RecordGesture::Ignore, RecordGesture::Ignore, RecordGesture::Stop]);
+    }
+
+    #[test]
+    fn the_second_key_cant_shorten_the_first_hold() {
+        // Released in the other order, the toggle key's hold still ends the recording
+        let gestures = run(
+            HotkeyMode::Hold,
+            &[(CORRECTION, true, 0), (TOGGLE, true, 40), (CORRECTION, false, 1000), (TOGGLE, false, 1040)],
+        );
+
+        assert_eq!(gestures, [RecordGesture::Start, RecordGesture::Ignore, RecordGesture::Stop, RecordGesture::Ignore]);
+    }
+
+    #[test]
+    fn a_recording_started_elsewhere_isnt_taken_over() {
+        let mut record_key = RecordKey::new(HotkeyMode::Hold, MIN_HOLD);
+        let now = Instant::now();
+
+        assert_eq!(record_key.event(TOGGLE, true, true, now), RecordGesture::Ignore);
+        assert_eq!(record_key.event(TOGGLE, false, true, now + Duration::from_secs(1)), RecordGesture::Ignore);
+    }
+}
@@ -3,107 +3,387 @@ use global_hotkey::{
     hotkey::{Code, HotKey, Modifiers},
     GlobalHotKeyEvent, GlobalHotKeyManager,
 };
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use tokio::sync::mpsc;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
+
+use crate::cancel::CancellationToken;
+use crate::events::EmitStatus;
+
+use super::tap::TapBinding;
+#[cfg(feature = "modifier-taps")]
+use super::tap::TapDetector;
+
+/// Ids for modifier-only bindings, kept clear of global-hotkey's hash-based ids
+const TAP_HOTKEY_ID_BASE: u32 = 0xFFFF_0000;
+
+/// How global hotkeys are grabbed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HotkeyBackend {
+    /// Portal on Wayland sessions, global-hotkey otherwise
+    #[default]
+    Auto,
+    /// X11 key grabs via the global-hotkey crate
+    GlobalHotkey,
+    /// org.freedesktop.portal.GlobalShortcuts (needs the portal-hotkeys feature)
+    Portal,
+}
+
+impl HotkeyBackend {
+    /// Pick a concrete backend for this session
+    pub fn resolve(self) -> HotkeyBackend {
+        match self {
+            HotkeyBackend::Auto => {
+                let wayland = std::env::var("XDG_SESSION_TYPE")
+                    .map(|session| session.eq_ignore_ascii_case("wayland"))
+                    .unwrap_or(false);
+                if wayland && cfg!(feature = "portal-hotkeys") {
+                    HotkeyBackend::Portal
+                } else {
+                    HotkeyBackend::GlobalHotkey
+                }
+            }
+            backend => backend,
+        }
+    }
+}
+
+/// Where global-hotkey keys are grabbed from the OS; a trait so tests can make it fail
+pub trait KeyGrab: Send {
+    fn register(&self, hotkey: HotKey) -> Result<()>;
+    fn unregister(&self, hotkey: HotKey) -> Result<()>;
+}
+
+impl KeyGrab for GlobalHotKeyManager {
+    fn register(&self, hotkey: HotKey) -> Result<()> {
+        Ok(GlobalHotKeyManager::register(self, hotkey)?)
+    }
+
+    fn unregister(&self, hotkey: HotKey) -> Result<()> {
+        Ok(GlobalHotKeyManager::unregister(self, hotkey)?)
+    }
+}
 
 pub struct HotkeyManager {
-    manager: GlobalHotKeyManager,
-    hotkeys: HashMap<u32, String>,
+    manager: Option<Box<dyn KeyGrab>>,
+    #[cfg_attr(not(feature = "portal-hotkeys"), allow(dead_code))]
+    backend: HotkeyBackend,
+    /// Shared with the listener, so bindings can change while it runs
+    hotkeys: Arc<Mutex<HashMap<u32, String>>>,
+    taps: Vec<(u32, String, TapBinding)>,
+    on_event: Option<EmitStatus>,
+    listening: bool,
+    /// hotkeys.cmd_is_super
+    cmd_is_super: bool,
 }
 
 #[allow(dead_code)]
 impl HotkeyManager {
-    pub fn new() -> Result<Self> {
-        let manager = GlobalHotKeyManager::new()
-            .map_err(|e| anyhow::anyhow!("Failed to create hotkey manager: {}", e))?;
+    pub fn new(backend: HotkeyBackend, cmd_is_super: bool) -> Result<Self> {
+        let backend = backend.resolve();
+        if backend == HotkeyBackend::Portal && !cfg!(feature = "portal-hotkeys") {
+            return Err(anyhow::anyhow!("Portal hotkey backend requires building with --features portal-hotkeys"));
+        }
+
+        // The portal registers shortcuts itself, no key grabs needed
+        let manager = match backend {
+            HotkeyBackend::Portal => None,
+            _ => Some(Box::new(
+                GlobalHotKeyManager::new()
+                    .map_err(|e| anyhow::anyhow!("Failed to create hotkey manager: {}", e))?,
+            ) as Box<dyn KeyGrab>),
+        };
+        info!("Hotkey backend: {:?}", backend);
 
         Ok(Self {
             manager,
-            hotkeys: HashMap::new(),
+            backend,
+            hotkeys: Arc::new(Mutex::new(HashMap::new())),
+            taps: Vec::new(),
+            on_event: None,
+            listening: false,
+            cmd_is_super,
         })
     }
 
+    /// A manager that keeps the books without grabbing keys, for tests
+    #[cfg(test)]
+    pub fn detached(cmd_is_super: bool) -> Self {
+        Self::with_grab(None, cmd_is_super)
+    }
+
+    /// A global-hotkey manager grabbing keys through `grab`, for tests
+    #[cfg(test)]
+    pub fn with_grab(grab: Option<Box<dyn KeyGrab>>, cmd_is_super: bool) -> Self {
+        Self {
+            manager: grab,
+            backend: HotkeyBackend::GlobalHotkey,
+            hotkeys: Arc::new(Mutex::new(HashMap::new())),
+            taps: Vec::new(),
+            on_event: None,
+            listening: false,
+            cmd_is_super,
+        }
+    }
+
+    /// Surface backend events (e.g. portal permission prompts)
+    pub fn set_event_callback(&mut self, callback: EmitStatus) {
+        self.on_event = Some(callback);
+    }
+
     pub fn register_hotkey(&mut self, hotkey_string: &str) -> Result<u32> {
-        let hotkey = parse_hotkey_string(hotkey_string)?;
+        // Modifier-only taps can't be registered with global-hotkey
+        if let Some(binding) = TapBinding::parse(hotkey_string) {
+            return self.register_tap(hotkey_string, binding);
+        }
+
+        let hotkey = parse_hotkey_string(hotkey_string, self.cmd_is_super)?;
         let id = hotkey.id();
+        if self.listening && self.manager.is_none() {
+            return Err(anyhow::anyhow!(
+                "Portal shortcuts are bound at startup, restart to use '{}'",
+                hotkey_string
+            ));
+        }
 
         info!("Registering hotkey: {} (ID: {})", hotkey_string, id);
 
-        self.manager
-            .register(hotkey)
-            .map_err(|e| anyhow::anyhow!("Failed to register hotkey '{}': {}", hotkey_string, e))?;
+        // With the portal, shortcuts are bound when listening starts
+        if let Some(ref manager) = self.manager {
+            manager
+                .register(hotkey)
+                .map_err(|e| anyhow::anyhow!("Failed to register hotkey '{}': {}", hotkey_string, e))?;
+        }
+
+        // Kept in canonical form, so "cmd" shows as what it was resolved to
+        let canonical = format_hotkey(&hotkey);
+        self.hotkeys.lock().unwrap().insert(id, canonical.clone());
+
+        // The resolved key, so users can confirm the right physical key was grabbed
+        info!("✅ Hotkey registered successfully: {} ({})", hotkey_string, canonical);
+        if let Some(ref on_event) = self.on_event {
+            on_event("hotkey_registered", &format!("{} -> {}", hotkey_string, canonical));
+        }
+        Ok(id)
+    }
+
+    fn register_tap(&mut self, hotkey_string: &str, binding: TapBinding) -> Result<u32> {
+        if !cfg!(feature = "modifier-taps") {
+            return Err(anyhow::anyhow!(
+                "Modifier-only hotkey '{}' requires building with --features modifier-taps",
+                hotkey_string
+            ));
+        }
+
+        // The tap listener takes its detectors when it starts
+        if self.listening {
+            return Err(anyhow::anyhow!(
+                "Modifier taps are bound at startup, restart to use '{}'",
+                hotkey_string
+            ));
+        }
 
-        self.hotkeys.insert(id, hotkey_string.to_string());
+        let id = TAP_HOTKEY_ID_BASE + self.taps.len() as u32;
+        self.taps.push((id, hotkey_string.to_string(), binding));
 
-        info!("✅ Hotkey registered successfully: {}", hotkey_string);
+        info!("✅ Modifier tap registered: {} (ID: {})", hotkey_string, id);
         Ok(id)
     }
 
     pub fn unregister_hotkey(&mut self, id: u32) -> Result<()> {
-        if let Some(hotkey_string) = self.hotkeys.remove(&id) {
-            let hotkey = parse_hotkey_string(&hotkey_string)?;
-            self.manager
-                .unregister(hotkey)
-                .map_err(|e| anyhow::anyhow!("Failed to unregister hotkey: {}", e))?;
-            
+        if let Some(index) = self.taps.iter().position(|(tap_id, _, _)| *tap_id == id) {
+            let (_, hotkey_string, _) = self.taps.remove(index);
+            info!("Modifier tap unregistered: {}", hotkey_string);
+            return Ok(());
+        }
+
+        let registered = self.hotkeys.lock().unwrap().get(&id).cloned();
+        if let Some(hotkey_string) = registered {
+            let hotkey = parse_hotkey_string(&hotkey_string, self.cmd_is_super)?;
+            if let Some(ref manager) = self.manager {
+                manager
+                    .unregister(hotkey)
+                    .map_err(|e| anyhow::anyhow!("Failed to unregister hotkey: {}", e))?;
+            }
+            // Only forgotten once it's really gone, so a failed unregister leaves it usable
+            self.hotkeys.lock().unwrap().remove(&id);
+
             info!("Hotkey unregistered: {}", hotkey_string);
         }
         Ok(())
     }
 
-    pub async fn start_listening(self, tx: mpsc::Sender<HotkeyEvent>) -> Result<()> {
+    /// Replace the hotkey registered as `id` with `hotkey_string`, returning the new id.
+    /// If that fails, the old key stays registered and nothing changes.
+    pub fn rebind(&mut self, id: u32, hotkey_string: &str) -> Result<u32> {
+        // Another spelling of the same key ("Ctrl+A" for "ctrl+a") is already in place
+        let same_key =
+            TapBinding::parse(hotkey_string).is_none() && parse_hotkey_string(hotkey_string, self.cmd_is_super)?.id() == id;
+        if same_key && self.hotkeys.lock().unwrap().contains_key(&id) {
+            return Ok(id);
+        }
+        Ok(self.replace(&[id], &[hotkey_string])?[0])
+    }
+
+    /// Let go of every id in `release`, then register each of `register`, returning the new
+    /// ids in order. Releasing first is what lets two bindings trade keys. If any step
+    /// fails, the new keys are released and the old ones registered again.
+    pub fn replace(&mut self, release: &[u32], register: &[&str]) -> Result<Vec<u32>> {
+        // The tap listener keeps its detectors, so a tap couldn't be put back
+        if self.listening {
+            if let Some((_, hotkey_string, _)) = self.taps.iter().find(|(id, _, _)| release.contains(id)) {
+                return Err(anyhow::anyhow!("Modifier taps are bound at startup, restart to change '{}'", hotkey_string));
+            }
+        }
+
+        let mut released = Vec::new();
+        for &id in release {
+            let Some(hotkey_string) = self.registered(id) else {
+                continue;
+            };
+            if let Err(e) = self.unregister_hotkey(id) {
+                self.restore(&[], &released);
+                return Err(e);
+            }
+            released.push(hotkey_string);
+        }
+
+        let mut registered = Vec::new();
+        for hotkey_string in register {
+            match self.register_hotkey(hotkey_string) {
+                Ok(id) => registered.push(id),
+                Err(e) => {
+                    self.restore(&registered, &released);
+                    return Err(e);
+                }
+            }
+        }
+        Ok(registered)
+    }
+
+    /// The combination registered as `id`
+    fn registered(&self, id: u32) -> Option<String> {
+        match self.taps.iter().find(|(tap_id, _, _)| *tap_id == id) {
+            Some((_, hotkey_string, _)) => Some(hotkey_string.clone()),
+            None => self.hotkeys.lock().unwrap().get(&id).cloned(),
+        }
+    }
+
+    /// Undo a `replace` that failed partway
+    fn restore(&mut self, registered: &[u32], released: &[String]) {
+        for &id in registered {
+            if let Err(e) = self.unregister_hotkey(id) {
+                warn!("⚠️  Couldn't release hotkey {} after a failed rebind: {}", id, e);
+            }
+        }
+        for hotkey_string in released {
+            if let Err(e) = self.register_hotkey(hotkey_string) {
+                warn!("⚠️  Couldn't restore '{}' after a failed rebind: {}", hotkey_string, e);
+            }
+        }
+    }
+
+    /// Start delivering presses to `tx` until `shutdown` is cancelled. The manager stays
+    /// usable afterwards, for re-registering global-hotkey bindings at runtime.
+    pub fn start_listening(
+        &mut self,
+        tx: mpsc::Sender<HotkeyEvent>,
+        shutdown: CancellationToken,
+    ) -> tokio::task::JoinHandle<Result<()>> {
         info!("🎯 Starting hotkey listener...");
-        
-        let receiver = GlobalHotKeyEvent::receiver();
-        
-        // Run the hotkey event loop
-        tokio::task::spawn_blocking(move || {
-            loop {
-                if let Ok(event) = receiver.try_recv() {
-                    let id = event.id;
-                    match event.state {
-                        global_hotkey::HotKeyState::Pressed => {
-                            if let Some(hotkey_string) = self.hotkeys.get(&id) {
-                                debug!("🔑 Hotkey pressed: {} (ID: {})", hotkey_string, id);
-                                
-                                let event = HotkeyEvent {
-                                    id,
-                                    hotkey: hotkey_string.clone(),
-                                    pressed: true,
-                                };
-                                
-                                if let Err(_) = tx.blocking_send(event) {
-                                    error!("Failed to send hotkey event - receiver dropped");
-                                    break;
-                                }
-                            }
-                        }
-                        global_hotkey::HotKeyState::Released => {
-                            if let Some(hotkey_string) = self.hotkeys.get(&id) {
-                                debug!("🔑 Hotkey released: {} (ID: {})", hotkey_string, id);
-                                
-                                let event = HotkeyEvent {
-                                    id,
-                                    hotkey: hotkey_string.clone(),
-                                    pressed: false,
-                                };
-                                
-                                if let Err(_) = tx.blocking_send(event) {
-                                    error!("Failed to send hotkey event - receiver dropped");
-                                    break;
-                                }
-                            }
-                        }
-                    }
+        self.listening = true;
+
+        #[cfg(feature = "modifier-taps")]
+        if !self.taps.is_empty() {
+            let detectors = self
+                .taps
+                .iter()
+                .map(|(id, hotkey, binding)| (*id, hotkey.clone(), TapDetector::new(*binding)))
+                .collect();
+            let tap_tx = tx.clone();
+            std::thread::spawn(move || super::tap::listener::listen(detectors, tap_tx));
+        }
+
+        #[cfg(feature = "portal-hotkeys")]
+        if self.backend == HotkeyBackend::Portal {
+            let hotkeys = self.hotkeys.lock().unwrap().clone();
+            let on_event = self.on_event.clone();
+            return tokio::spawn(async move {
+                tokio::select! {
+                    result = super::portal::listen(hotkeys, tx, on_event) => result,
+                    _ = shutdown.cancelled() => Ok(()),
+                }
+            });
+        }
+
+        let hotkeys = self.hotkeys.clone();
+
+        // The blocking thread can't wait on the token itself; dropping `stop_tx`
+        // disconnects `stop_rx`, which wakes it
+        let (stop_tx, stop_rx) = crossbeam_channel::bounded::<()>(0);
+        tokio::spawn(async move {
+            shutdown.cancelled().await;
+            drop(stop_tx);
+        });
+
+        tokio::spawn(async move {
+            tokio::task::spawn_blocking(move || forward_events(GlobalHotKeyEvent::receiver(), &stop_rx, &hotkeys, &tx))
+                .await?;
+            Ok(())
+        })
+    }
+}
+
+/// Pass global-hotkey events for registered ids on to `tx`, blocking between them,
+/// until `stop` disconnects or nobody is listening any more
+fn forward_events(
+    receiver: &crossbeam_channel::Receiver<GlobalHotKeyEvent>,
+    stop: &crossbeam_channel::Receiver<()>,
+    hotkeys: &Mutex<HashMap<u32, String>>,
+    tx: &mpsc::Sender<HotkeyEvent>,
+) {
+    loop {
+        let event = crossbeam_channel::select! {
+            recv(receiver) -> event => match event {
+                Ok(event) => event,
+                Err(_) => {
+                    warn!("⚠️  Hotkey event channel closed");
+                    return;
                 }
-                
-                // Small sleep to prevent busy waiting
-                std::thread::sleep(std::time::Duration::from_millis(10));
+            },
+            recv(stop) -> _ => {
+                debug!("Hotkey listener stopped");
+                return;
             }
-        }).await?;
+        };
 
-        Ok(())
+        let id = event.id;
+        let pressed = match event.state {
+            global_hotkey::HotKeyState::Pressed => true,
+            global_hotkey::HotKeyState::Released => false,
+        };
+        let Some(hotkey_string) = hotkeys.lock().unwrap().get(&id).cloned() else {
+            continue;
+        };
+        debug!(
+            "🔑 Hotkey {}: {} (ID: {})",
+            if pressed { "pressed" } else { "released" },
+            hotkey_string,
+            id
+        );
+
+        let event = HotkeyEvent {
+            id,
+            hotkey: hotkey_string,
+            pressed,
+        };
+        if tx.blocking_send(event).is_err() {
+            error!("Failed to send hotkey event - receiver dropped");
+            return;
+        }
     }
 }
 
@@ -115,129 +395,687 @@ pub struct HotkeyEvent {
     pub pressed: bool,
 }
 
-fn parse_hotkey_string(hotkey_string: &str) -> Result<HotKey> {
-    let parts: Vec<&str> = hotkey_string.split('+').map(|s| s.trim()).collect();
-    
-    if parts.is_empty() {
+/// Check a hotkey string parses, without registering it
+pub fn validate_hotkey_string(hotkey_string: &str, cmd_is_super: bool) -> Result<()> {
+    if TapBinding::parse(hotkey_string).is_some() {
+        return Ok(());
+    }
+    parse_hotkey_string(hotkey_string, cmd_is_super).map(|_| ())
+}
+
+/// "cmd" is the Command key (SUPER) when `cmd_is_super`, otherwise another name for ctrl
+pub fn parse_hotkey_string(hotkey_string: &str, cmd_is_super: bool) -> Result<HotKey> {
+    let trimmed = hotkey_string.trim();
+    if trimmed.is_empty() {
         return Err(anyhow::anyhow!("Empty hotkey string"));
     }
-    
+
     let mut modifiers = Modifiers::empty();
     let mut key_code = None;
-    
-    for part in parts {
-        match part.to_lowercase().as_str() {
-            "ctrl" | "cmd" => modifiers |= Modifiers::CONTROL,
-            "shift" => modifiers |= Modifiers::SHIFT,
-            "alt" => modifiers |= Modifiers::ALT,
-            "super" | "win" | "meta" => modifiers |= Modifiers::SUPER,
-            key => {
+
+    for part in trimmed.split('+').map(str::trim) {
+        if part.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Empty key in hotkey '{}' (use \"plus\" to bind the + key)",
+                hotkey_string
+            ));
+        }
+
+        let modifier = match part.to_lowercase().as_str() {
+            "ctrl" | "control" => Some(Modifiers::CONTROL),
+            "cmd" | "command" if cmd_is_super => Some(Modifiers::SUPER),
+            "cmd" | "command" => Some(Modifiers::CONTROL),
+            "shift" => Some(Modifiers::SHIFT),
+            "alt" | "option" | "opt" => Some(Modifiers::ALT),
+            "super" | "win" | "meta" => Some(Modifiers::SUPER),
+            // global-hotkey matches modifiers without regard to side
+            sided @ ("lctrl" | "rctrl" | "lalt" | "ralt" | "altgr" | "lsuper" | "rsuper" | "lwin" | "rwin") => {
+                return Err(anyhow::anyhow!(
+                    "'{}' in hotkey '{}': left and right modifiers can't be told apart, use '{}'",
+                    part,
+                    hotkey_string,
+                    sided.trim_start_matches(&['l', 'r'][..]).replace("gr", "").replace("win", "super")
+                ));
+            }
+            _ => None,
+        };
+
+        match modifier {
+            Some(modifier) => {
+                if modifiers.contains(modifier) {
+                    return Err(anyhow::anyhow!("Duplicate modifier '{}' in hotkey '{}'", part, hotkey_string));
+                }
+                modifiers |= modifier;
+            }
+            None => {
                 if key_code.is_some() {
-                    return Err(anyhow::anyhow!("Multiple keys specified in hotkey: {}", hotkey_string));
+                    return Err(anyhow::anyhow!("Multiple keys in hotkey '{}': '{}' is the second", hotkey_string, part));
+                }
+                // "+" is Shift+= on the keyboard, so "ctrl+plus" means Ctrl+Shift+=
+                if part.eq_ignore_ascii_case("plus") {
+                    modifiers |= Modifiers::SHIFT;
                 }
-                key_code = Some(parse_key_code(key)?);
+                key_code = Some(
+                    parse_key_code(part)
+                        .map_err(|e| anyhow::anyhow!("{} in hotkey '{}'", e, hotkey_string))?,
+                );
             }
         }
     }
-    
-    let key_code = key_code.unwrap_or(Code::Unidentified);
-    
+
+    // A modifier-only combination registers fine but can never fire
+    let key_code = key_code.ok_or_else(|| {
+        anyhow::anyhow!("Hotkey '{}' has no key, only modifiers (add one, e.g. \"{}+space\")", hotkey_string, trimmed)
+    })?;
+
     Ok(HotKey::new(Some(modifiers), key_code))
 }
 
+const LETTERS: [Code; 26] = [
+    Code::KeyA, Code::KeyB, Code::KeyC, Code::KeyD, Code::KeyE, Code::KeyF, Code::KeyG,
+    Code::KeyH, Code::KeyI, Code::KeyJ, Code::KeyK, Code::KeyL, Code::KeyM, Code::KeyN,
+    Code::KeyO, Code::KeyP, Code::KeyQ, Code::KeyR, Code::KeyS, Code::KeyT, Code::KeyU,
+    Code::KeyV, Code::KeyW, Code::KeyX, Code::KeyY, Code::KeyZ,
+];
+const DIGITS: [Code; 10] = [
+    Code::Digit1, Code::Digit2, Code::Digit3, Code::Digit4, Code::Digit5,
+    Code::Digit6, Code::Digit7, Code::Digit8, Code::Digit9, Code::Digit0,
+];
+const F1_TO_F12: [Code; 12] = [
+    Code::F1, Code::F2, Code::F3, Code::F4, Code::F5, Code::F6,
+    Code::F7, Code::F8, Code::F9, Code::F10, Code::F11, Code::F12,
+];
+const F13_TO_F24: [Code; 12] = [
+    Code::F13, Code::F14, Code::F15, Code::F16, Code::F17, Code::F18,
+    Code::F19, Code::F20, Code::F21, Code::F22, Code::F23, Code::F24,
+];
+const NUMPAD_DIGITS: [Code; 10] = [
+    Code::Numpad0, Code::Numpad1, Code::Numpad2, Code::Numpad3, Code::Numpad4,
+    Code::Numpad5, Code::Numpad6, Code::Numpad7, Code::Numpad8, Code::Numpad9,
+];
+
+/// Friendly names for everything but letters, digits, F-keys and numpad digits; the
+/// first name of each entry is the one error messages list
+pub const NAMED_KEYS: &[(&[&str], Code)] = &[
+    (&["space"], Code::Space),
+    (&["enter", "return"], Code::Enter),
+    (&["tab"], Code::Tab),
+    (&["backspace"], Code::Backspace),
+    (&["delete", "del"], Code::Delete),
+    (&["escape", "esc"], Code::Escape),
+    (&["lshift"], Code::ShiftLeft),
+    (&["rshift"], Code::ShiftRight),
+    // Navigation
+    (&["up"], Code::ArrowUp),
+    (&["down"], Code::ArrowDown),
+    (&["left"], Code::ArrowLeft),
+    (&["right"], Code::ArrowRight),
+    (&["insert", "ins"], Code::Insert),
+    (&["home"], Code::Home),
+    (&["end"], Code::End),
+    (&["pageup", "pgup"], Code::PageUp),
+    (&["pagedown", "pgdn", "pgdown"], Code::PageDown),
+    // Locks and system keys
+    (&["capslock", "caps", "caps_lock", "capslk"], Code::CapsLock),
+    (&["numlock"], Code::NumLock),
+    (&["scrolllock"], Code::ScrollLock),
+    (&["pause", "break"], Code::Pause),
+    (&["printscreen", "prtsc", "print"], Code::PrintScreen),
+    (&["menu", "contextmenu"], Code::ContextMenu),
+    // Punctuation, by US layout position
+    (&["grave", "`", "backquote", "backtick"], Code::Backquote),
+    (&["minus", "-"], Code::Minus),
+    (&["equal", "=", "equals", "plus"], Code::Equal),
+    (&["bracketleft", "[", "lbracket"], Code::BracketLeft),
+    (&["bracketright", "]", "rbracket"], Code::BracketRight),
+    (&["backslash", "\\"], Code::Backslash),
+    (&["semicolon", ";"], Code::Semicolon),
+    (&["quote", "'", "apostrophe"], Code::Quote),
+    (&["comma", ","], Code::Comma),
+    (&["period", ".", "dot"], Code::Period),
+    (&["slash", "/"], Code::Slash),
+    // Numpad
+    (&["numpadadd", "kpplus"], Code::NumpadAdd),
+    (&["numpadsubtract", "kpminus"], Code::NumpadSubtract),
+    (&["numpadmultiply", "kpmultiply"], Code::NumpadMultiply),
+    (&["numpaddivide", "kpdivide"], Code::NumpadDivide),
+    (&["numpaddecimal", "kpdecimal"], Code::NumpadDecimal),
+    (&["numpadenter", "kpenter"], Code::NumpadEnter),
+    // Media
+    (&["playpause", "mediaplaypause"], Code::MediaPlayPause),
+    (&["mediastop"], Code::MediaStop),
+    (&["nexttrack", "medianext"], Code::MediaTrackNext),
+    (&["prevtrack", "mediaprev"], Code::MediaTrackPrevious),
+    (&["mute", "volumemute"], Code::AudioVolumeMute),
+    (&["volumedown"], Code::AudioVolumeDown),
+    (&["volumeup"], Code::AudioVolumeUp),
+];
+
+/// The canonical string for `hotkey`, e.g. "ctrl+shift+space"; parses back to the same key
+pub fn format_hotkey(hotkey: &HotKey) -> String {
+    let mut parts: Vec<String> = [
+        (Modifiers::CONTROL, "ctrl"),
+        (Modifiers::SHIFT, "shift"),
+        (Modifiers::ALT, "alt"),
+        (Modifiers::SUPER, "super"),
+    ]
+    .into_iter()
+    .filter(|(modifier, _)| hotkey.mods.contains(*modifier))
+    .map(|(_, name)| name.to_string())
+    .collect();
+    parts.push(key_name(hotkey.key));
+    parts.join("+")
+}
+
+/// The name parse_key_code reads as `code`
+fn key_name(code: Code) -> String {
+    if let Some(index) = LETTERS.iter().position(|letter| *letter == code) {
+        return ((b'a' + index as u8) as char).to_string();
+    }
+    if let Some(index) = DIGITS.iter().position(|digit| *digit == code) {
+        return ((index + 1) % 10).to_string();
+    }
+    if let Some(index) = F1_TO_F12.iter().position(|key| *key == code) {
+        return format!("f{}", index + 1);
+    }
+    if let Some(index) = F13_TO_F24.iter().position(|key| *key == code) {
+        return format!("f{}", index + 13);
+    }
+    if let Some(index) = NUMPAD_DIGITS.iter().position(|key| *key == code) {
+        return format!("numpad{}", index);
+    }
+    match NAMED_KEYS.iter().find(|(_, named)| *named == code) {
+        Some((names, _)) => names[0].to_string(),
+        None => format!("key:{}", code),
+    }
+}
+
+/// Key names beyond NAMED_KEYS, offered as suggestions for `key:` escapes
+const RAW_KEY_NAMES: &[&str] = &[
+    "F13", "F14", "F15", "F16", "F17", "F18", "F19", "F20", "F21", "F22", "F23", "F24",
+    "IntlBackslash", "IntlRo", "IntlYen", "Lang1", "Lang2", "Lang3", "Lang4", "Lang5",
+    "Convert", "NonConvert", "KanaMode", "ContextMenu", "PrintScreen", "ScrollLock", "Pause",
+    "NumLock", "NumpadEnter", "NumpadAdd", "NumpadSubtract", "NumpadMultiply", "NumpadDivide",
+    "MediaPlayPause", "MediaStop", "MediaTrackNext", "MediaTrackPrevious",
+    "AudioVolumeMute", "AudioVolumeDown", "AudioVolumeUp", "Help", "Undo", "Copy", "Paste", "Cut",
+];
+
+/// `code:0x..` takes a USB HID usage ID (keyboard page), as shown by most macropad tools
+fn code_from_hid_usage(usage: u32) -> Option<Code> {
+    let index = |base: u32| (usage - base) as usize;
+    match usage {
+        0x04..=0x1D => Some(LETTERS[index(0x04)]),
+        0x1E..=0x27 => Some(DIGITS[index(0x1E)]),
+        0x28 => Some(Code::Enter),
+        0x29 => Some(Code::Escape),
+        0x2A => Some(Code::Backspace),
+        0x2B => Some(Code::Tab),
+        0x2C => Some(Code::Space),
+        0x2D => Some(Code::Minus),
+        0x2E => Some(Code::Equal),
+        0x2F => Some(Code::BracketLeft),
+        0x30 => Some(Code::BracketRight),
+        // 0x32 is the non-US "#" key, which sits where Backslash does on ISO layouts
+        0x31 | 0x32 => Some(Code::Backslash),
+        0x33 => Some(Code::Semicolon),
+        0x34 => Some(Code::Quote),
+        0x35 => Some(Code::Backquote),
+        0x36 => Some(Code::Comma),
+        0x37 => Some(Code::Period),
+        0x38 => Some(Code::Slash),
+        0x39 => Some(Code::CapsLock),
+        0x3A..=0x45 => Some(F1_TO_F12[index(0x3A)]),
+        0x46 => Some(Code::PrintScreen),
+        0x47 => Some(Code::ScrollLock),
+        0x48 => Some(Code::Pause),
+        0x49 => Some(Code::Insert),
+        0x4A => Some(Code::Home),
+        0x4B => Some(Code::PageUp),
+        0x4C => Some(Code::Delete),
+        0x4D => Some(Code::End),
+        0x4E => Some(Code::PageDown),
+        0x4F => Some(Code::ArrowRight),
+        0x50 => Some(Code::ArrowLeft),
+        0x51 => Some(Code::ArrowDown),
+        0x52 => Some(Code::ArrowUp),
+        0x53 => Some(Code::NumLock),
+        0x54 => Some(Code::NumpadDivide),
+        0x55 => Some(Code::NumpadMultiply),
+        0x56 => Some(Code::NumpadSubtract),
+        0x57 => Some(Code::NumpadAdd),
+        0x58 => Some(Code::NumpadEnter),
+        // Numpad 1-9 then 0, like the main row
+        0x59..=0x61 => Some(NUMPAD_DIGITS[index(0x58)]),
+        0x62 => Some(NUMPAD_DIGITS[0]),
+        0x63 => Some(Code::NumpadDecimal),
+        0x64 => Some(Code::IntlBackslash),
+        0x65 => Some(Code::ContextMenu),
+        0x66 => Some(Code::Power),
+        0x67 => Some(Code::NumpadEqual),
+        0x68..=0x73 => Some(F13_TO_F24[index(0x68)]),
+        0x87 => Some(Code::IntlRo),
+        0x88 => Some(Code::KanaMode),
+        0x89 => Some(Code::IntlYen),
+        0x8A => Some(Code::Convert),
+        0x8B => Some(Code::NonConvert),
+        0x90 => Some(Code::Lang1),
+        0x91 => Some(Code::Lang2),
+        0x92 => Some(Code::Lang3),
+        0x93 => Some(Code::Lang4),
+        0x94 => Some(Code::Lang5),
+        _ => None,
+    }
+}
+
+/// Character-level edit distance, for "did you mean" hints
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(!ca.eq_ignore_ascii_case(cb));
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Up to three of `known` close to `name`
+fn nearby_keys(name: &str, known: &[&str]) -> String {
+    let mut candidates: Vec<(usize, &str)> = known
+        .iter()
+        .map(|candidate| (edit_distance(name, candidate), *candidate))
+        .filter(|(distance, _)| *distance <= 3)
+        .collect();
+    candidates.sort();
+    if candidates.is_empty() {
+        return String::new();
+    }
+    let names: Vec<&str> = candidates.iter().take(3).map(|(_, name)| *name).collect();
+    format!(" (did you mean {}?)", names.join(", "))
+}
+
+/// `key:<W3C code name>` or `code:<HID usage>` escapes for keys without a friendly name
+fn parse_raw_key(key: &str) -> Option<Result<Code>> {
+    if let Some(name) = key.strip_prefix("key:").or_else(|| key.strip_prefix("KEY:")) {
+        let name = name.trim();
+        return Some(name.parse::<Code>().ok().filter(|code| *code != Code::Unidentified).ok_or_else(|| {
+            anyhow::anyhow!("Unknown key name '{}'{}", name, nearby_keys(name, RAW_KEY_NAMES))
+        }));
+    }
+
+    let value = key.strip_prefix("code:").or_else(|| key.strip_prefix("CODE:"))?.trim();
+    let usage = match value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => value.parse(),
+    };
+    Some(match usage {
+        Ok(usage) => code_from_hid_usage(usage).ok_or_else(|| {
+            anyhow::anyhow!(
+                "No key for HID usage {:#04x} (supported: 0x04-0x73, 0x87-0x8b, 0x90-0x94)",
+                usage
+            )
+        }),
+        Err(_) => Err(anyhow::anyhow!("Invalid key code '{}' (expected e.g. code:0x6e)", value)),
+    })
+}
+
 fn parse_key_code(key: &str) -> Result<Code> {
-    match key.to_lowercase().as_str() {
-        // Letters
-        "a" => Ok(Code::KeyA),
-        "b" => Ok(Code::KeyB),
-        "c" => Ok(Code::KeyC),
-        "d" => Ok(Code::KeyD),
-        "e" => Ok(Code::KeyE),
-        "f" => Ok(Code::KeyF),
-        "g" => Ok(Code::KeyG),
-        "h" => Ok(Code::KeyH),
-        "i" => Ok(Code::KeyI),
-        "j" => Ok(Code::KeyJ),
-        "k" => Ok(Code::KeyK),
-        "l" => Ok(Code::KeyL),
-        "m" => Ok(Code::KeyM),
-        "n" => Ok(Code::KeyN),
-        "o" => Ok(Code::KeyO),
-        "p" => Ok(Code::KeyP),
-        "q" => Ok(Code::KeyQ),
-        "r" => Ok(Code::KeyR),
-        "s" => Ok(Code::KeyS),
-        "t" => Ok(Code::KeyT),
-        "u" => Ok(Code::KeyU),
-        "v" => Ok(Code::KeyV),
-        "w" => Ok(Code::KeyW),
-        "x" => Ok(Code::KeyX),
-        "y" => Ok(Code::KeyY),
-        "z" => Ok(Code::KeyZ),
-        
-        // Numbers
-        "0" => Ok(Code::Digit0),
-        "1" => Ok(Code::Digit1),
-        "2" => Ok(Code::Digit2),
-        "3" => Ok(Code::Digit3),
-        "4" => Ok(Code::Digit4),
-        "5" => Ok(Code::Digit5),
-        "6" => Ok(Code::Digit6),
-        "7" => Ok(Code::Digit7),
-        "8" => Ok(Code::Digit8),
-        "9" => Ok(Code::Digit9),
-        
-        // Special keys
-        "space" => Ok(Code::Space),
-        "enter" | "return" => Ok(Code::Enter),
-        "tab" => Ok(Code::Tab),
-        "backspace" => Ok(Code::Backspace),
-        "delete" => Ok(Code::Delete),
-        "escape" | "esc" => Ok(Code::Escape),
-        "lshift" => Ok(Code::ShiftLeft),
-        "rshift" => Ok(Code::ShiftRight),
-        
-        // Function keys
-        "f1" => Ok(Code::F1),
-        "f2" => Ok(Code::F2),
-        "f3" => Ok(Code::F3),
-        "f4" => Ok(Code::F4),
-        "f5" => Ok(Code::F5),
-        "f6" => Ok(Code::F6),
-        "f7" => Ok(Code::F7),
-        "f8" => Ok(Code::F8),
-        "f9" => Ok(Code::F9),
-        "f10" => Ok(Code::F10),
-        "f11" => Ok(Code::F11),
-        "f12" => Ok(Code::F12),
-        "f13" => Ok(Code::F13),
-        "f14" => Ok(Code::F14),
-        "f15" => Ok(Code::F15),
-        "f16" => Ok(Code::F16),
-        "f17" => Ok(Code::F17),
-        "f18" => Ok(Code::F18),
-        "f19" => Ok(Code::F19),
-        "f20" => Ok(Code::F20),
-        
-        // Arrow keys
-        "up" => Ok(Code::ArrowUp),
-        "down" => Ok(Code::ArrowDown),
-        "left" => Ok(Code::ArrowLeft),
-        "right" => Ok(Code::ArrowRight),
-        
-        // Special keys that might be useful for left-hand operation
-        "capslock" | "caps" | "caps_lock" | "capslk" => Ok(Code::CapsLock),
-        "insert" => Ok(Code::Insert),
-        "home" => Ok(Code::Home),
-        "end" => Ok(Code::End),
-        "pageup" => Ok(Code::PageUp),
-        "pagedown" => Ok(Code::PageDown),
-        
-        // Additional keys for better compatibility
-        "`" | "grave" => Ok(Code::Backquote),
-        "-" | "minus" => Ok(Code::Minus),
-        "=" | "equals" => Ok(Code::Equal),
-        
-        _ => Err(anyhow::anyhow!("Unknown key: {}", key)),
-    }
-}
\ No newline at end of file
+    if let Some(raw) = parse_raw_key(key) {
+        return raw;
+    }
+
+    let name = key.to_lowercase();
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        if c.is_ascii_lowercase() {
+            return Ok(LETTERS[(c as u8 - b'a') as usize]);
+        }
+        if let Some(digit) = c.to_digit(10) {
+            // DIGITS runs 1-9 then 0, like the HID usages
+            return Ok(DIGITS[(digit as usize + 9) % 10]);
+        }
+    }
+    if let Some(number) = name.strip_prefix('f').and_then(|number| number.parse::<usize>().ok()) {
+        match number {
+            1..=12 => return Ok(F1_TO_F12[number - 1]),
+            13..=24 => return Ok(F13_TO_F24[number - 13]),
+            _ => {}
+        }
+    }
+    if let Some(digit) = name
+        .strip_prefix("numpad")
+        .or_else(|| name.strip_prefix("kp"))
+        .and_then(|digit| digit.parse::<usize>().ok())
+        .filter(|digit| *digit <= 9)
+    {
+        return Ok(NUMPAD_DIGITS[digit]);
+    }
+
+    NAMED_KEYS
+        .iter()
+        .find(|(names, _)| names.contains(&name.as_str()))
+        .map(|(_, code)| *code)
+        .ok_or_else(|| {
+            let known: Vec<&str> = NAMED_KEYS.iter().flat_map(|(names, _)| names.iter().copied()).collect();
+            let supported: Vec<&str> = NAMED_KEYS.iter().map(|(names, _)| names[0]).collect();
+            anyhow::anyhow!(
+                "Unknown key '{}'{}. Supported: a-z, 0-9, f1-f24, numpad0-numpad9, {} (others as key:<name>)",
+                key,
+                nearby_keys(key, &known),
+                supported.join(", ")
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(hotkey: &str) -> Result<HotKey> {
+        parse_hotkey_string(hotkey, false)
+    }
+
+    fn error(hotkey: &str) -> String {
+        parse(hotkey).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn modifier_only_is_an_error() {
+        assert!(error("ctrl+shift").contains("no key, only modifiers"));
+        assert!(error("alt").contains("no key"));
+    }
+
+    #[test]
+    fn empty_and_blank_strings_are_errors() {
+        assert!(error("").contains("Empty hotkey"));
+        assert!(error(" \u{3000}\t").contains("Empty hotkey"));
+        assert!(error("ctrl++space").contains("Empty key"));
+        assert!(error("ctrl+").contains("Empty key"));
+    }
+
+    #[test]
+    fn duplicate_modifiers_name_the_token() {
+        assert!(error("ctrl+control+space").contains("Duplicate modifier 'control'"));
+        assert!(error("shift+a+b").contains("'b' is the second"));
+    }
+
+    #[test]
+    fn unknown_keys_name_the_token() {
+        let message = error("ctrl+spcae");
+        assert!(message.contains("Unknown key 'spcae'"), "{}", message);
+        assert!(message.contains("did you mean space"), "{}", message);
+    }
+
+    #[test]
+    fn sided_modifiers_are_rejected_with_a_hint() {
+        assert!(error("lctrl+space").contains("use 'ctrl'"));
+        assert!(error("rwin+space").contains("use 'super'"));
+    }
+
+    #[test]
+    fn surrounding_unicode_whitespace_is_trimmed() {
+        assert_eq!(parse("\u{a0}ctrl + shift + space\u{2003}").unwrap(), parse("ctrl+shift+space").unwrap());
+    }
+
+    #[test]
+    fn plus_is_shift_equal() {
+        assert_eq!(
+            parse("ctrl+plus").unwrap(),
+            HotKey::new(Some(Modifiers::CONTROL | Modifiers::SHIFT), Code::Equal)
+        );
+    }
+
+    #[test]
+    fn every_alias_parses_to_its_entry() {
+        for (names, code) in NAMED_KEYS {
+            for name in *names {
+                let hotkey = parse(&format!("ctrl+{}", name)).unwrap_or_else(|e| panic!("{}: {}", name, e));
+                assert_eq!(hotkey.key, *code, "{}", name);
+            }
+        }
+        for (name, code) in [("a", Code::KeyA), ("0", Code::Digit0), ("f24", Code::F24), ("numpad7", Code::Numpad7), ("kp3", Code::Numpad3)] {
+            assert_eq!(parse(name).unwrap().key, code, "{}", name);
+        }
+        assert_eq!(parse("key:IntlRo").unwrap().key, Code::IntlRo);
+        assert_eq!(parse("code:0x2c").unwrap().key, Code::Space);
+    }
+
+    #[test]
+    fn hid_usages_cover_punctuation_arrows_and_numpad() {
+        let cases = [
+            (0x2D, Code::Minus),
+            (0x31, Code::Backslash),
+            (0x35, Code::Backquote),
+            (0x38, Code::Slash),
+            (0x4F, Code::ArrowRight),
+            (0x52, Code::ArrowUp),
+            (0x58, Code::NumpadEnter),
+            (0x59, Code::Numpad1),
+            (0x61, Code::Numpad9),
+            (0x62, Code::Numpad0),
+            (0x67, Code::NumpadEqual),
+            (0x6E, Code::F19),
+        ];
+        for (usage, code) in cases {
+            assert_eq!(parse(&format!("code:{:#04x}", usage)).unwrap().key, code, "{:#04x}", usage);
+        }
+        // Every usage in the advertised ranges resolves
+        for usage in (0x04..=0x73).chain(0x87..=0x8B).chain(0x90..=0x94) {
+            assert!(code_from_hid_usage(usage).is_some(), "{:#04x}", usage);
+        }
+        assert!(error("code:0x74").contains("No key for HID usage 0x74"));
+    }
+
+    const MODIFIERS: [Modifiers; 4] = [Modifiers::CONTROL, Modifiers::SHIFT, Modifiers::ALT, Modifiers::SUPER];
+
+    /// Every combination of ctrl, shift, alt and super, none included
+    fn modifier_sets() -> impl Iterator<Item = Modifiers> {
+        (0..16u8).map(|bits| {
+            MODIFIERS
+                .iter()
+                .enumerate()
+                .filter(|(bit, _)| bits & (1 << bit) != 0)
+                .fold(Modifiers::empty(), |mods, (_, modifier)| mods | *modifier)
+        })
+    }
+
+    #[test]
+    fn formatted_hotkeys_parse_back_to_themselves() {
+        let mut keys: Vec<Code> = vec![Code::KeyA, Code::Digit0, Code::F1, Code::F24, Code::Numpad5];
+        keys.extend(NAMED_KEYS.iter().map(|(_, code)| *code));
+        keys.extend([Code::IntlRo, Code::Help, Code::Lang1]);
+
+        for mods in modifier_sets() {
+            for &key in &keys {
+                let hotkey = HotKey::new(Some(mods), key);
+                let formatted = format_hotkey(&hotkey);
+                for cmd_is_super in [false, true] {
+                    let parsed = parse_hotkey_string(&formatted, cmd_is_super).unwrap_or_else(|e| panic!("{}: {}", formatted, e));
+                    assert_eq!(parsed, hotkey, "{}", formatted);
+                    assert_eq!(format_hotkey(&parsed), formatted);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn modifiers_format_in_a_fixed_order() {
+        assert_eq!(format_hotkey(&parse("super+alt+shift+ctrl+space").unwrap()), "ctrl+shift+alt+super+space");
+        assert_eq!(format_hotkey(&parse("Control+Option+K").unwrap()), "ctrl+alt+k");
+        assert_eq!(format_hotkey(&parse("win+`").unwrap()), "super+grave");
+        assert_eq!(format_hotkey(&parse("ctrl+plus").unwrap()), "ctrl+shift+equal");
+    }
+
+    #[test]
+    fn cmd_follows_cmd_is_super() {
+        for name in ["cmd", "command"] {
+            let as_super = parse_hotkey_string(&format!("{}+shift+space", name), true).unwrap();
+            assert_eq!(as_super.mods, Modifiers::SUPER | Modifiers::SHIFT);
+            assert_eq!(format_hotkey(&as_super), "shift+super+space");
+
+            let as_ctrl = parse_hotkey_string(&format!("{}+shift+space", name), false).unwrap();
+            assert_eq!(as_ctrl, parse("ctrl+shift+space").unwrap());
+        }
+        // With cmd as super, cmd and ctrl are two different keys
+        assert_ne!(parse_hotkey_string("cmd+c", true).unwrap().id(), parse_hotkey_string("ctrl+c", true).unwrap().id());
+        assert!(parse_hotkey_string("cmd+ctrl+c", false).unwrap_err().to_string().contains("Duplicate modifier"));
+    }
+
+    fn event(id: u32, state: global_hotkey::HotKeyState) -> GlobalHotKeyEvent {
+        GlobalHotKeyEvent { id, state }
+    }
+
+    #[test]
+    fn forward_events_passes_on_registered_keys_until_stopped() {
+        let (events, receiver) = crossbeam_channel::unbounded();
+        let (stop_tx, stop) = crossbeam_channel::bounded::<()>(0);
+        let hotkeys = Arc::new(Mutex::new(HashMap::from([(7, "ctrl+shift+space".to_string())])));
+        let (tx, mut rx) = mpsc::channel(8);
+
+        let forwarder = {
+            let hotkeys = hotkeys.clone();
+            std::thread::spawn(move || forward_events(&receiver, &stop, &hotkeys, &tx))
+        };
+        events.send(event(7, global_hotkey::HotKeyState::Pressed)).unwrap();
+        // Ids nobody registered, like another app's, are skipped
+        events.send(event(8, global_hotkey::HotKeyState::Pressed)).unwrap();
+        events.send(event(7, global_hotkey::HotKeyState::Released)).unwrap();
+
+        let first = rx.blocking_recv().unwrap();
+        assert_eq!((first.id, first.hotkey.as_str(), first.pressed), (7, "ctrl+shift+space", true));
+        let second = rx.blocking_recv().unwrap();
+        assert_eq!((second.id, second.pressed), (7, false));
+
+        drop(stop_tx);
+        forwarder.join().unwrap();
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn forward_events_returns_once_nobody_listens() {
+        let (events, receiver) = crossbeam_channel::unbounded();
+        let (_stop_tx, stop) = crossbeam_channel::bounded::<()>(0);
+        let hotkeys = Mutex::new(HashMap::from([(7, "f24".to_string())]));
+        let (tx, rx) = mpsc::channel(8);
+        drop(rx);
+
+        events.send(event(7, global_hotkey::HotKeyState::Pressed)).unwrap();
+        forward_events(&receiver, &stop, &hotkeys, &tx);
+    }
+
+    #[tokio::test]
+    async fn the_listener_exits_when_cancelled() {
+        let mut manager = HotkeyManager::detached(false);
+        let (tx, _rx) = mpsc::channel(8);
+        let shutdown = CancellationToken::new();
+
+        let listener = manager.start_listening(tx, shutdown.clone());
+        shutdown.cancel();
+
+        let result = tokio::time::timeout(std::time::Duration::from_secs(2), listener).await;
+        assert!(result.expect("listener still running").unwrap().is_ok());
+    }
+
+    /// Grabs keys into a shared set, refusing the ones it's told to
+    #[derive(Clone, Default)]
+    struct FakeGrab {
+        grabbed: Arc<Mutex<Vec<u32>>>,
+        refuse_register: Arc<Mutex<Vec<u32>>>,
+        refuse_unregister: Arc<Mutex<Vec<u32>>>,
+    }
+
+    impl FakeGrab {
+        fn manager(&self) -> HotkeyManager {
+            HotkeyManager::with_grab(Some(Box::new(self.clone())), false)
+        }
+
+        fn grabbed(&self) -> Vec<u32> {
+            let mut grabbed = self.grabbed.lock().unwrap().clone();
+            grabbed.sort();
+            grabbed
+        }
+    }
+
+    impl KeyGrab for FakeGrab {
+        fn register(&self, hotkey: HotKey) -> Result<()> {
+            if self.refuse_register.lock().unwrap().contains(&hotkey.id()) {
+                return Err(anyhow::anyhow!("already grabbed by another client"));
+            }
+            self.grabbed.lock().unwrap().push(hotkey.id());
+            Ok(())
+        }
+
+        fn unregister(&self, hotkey: HotKey) -> Result<()> {
+            if self.refuse_unregister.lock().unwrap().contains(&hotkey.id()) {
+                return Err(anyhow::anyhow!("not grabbed"));
+            }
+            self.grabbed.lock().unwrap().retain(|&id| id != hotkey.id());
+            Ok(())
+        }
+    }
+
+    fn sorted(mut ids: Vec<u32>) -> Vec<u32> {
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn rebind_moves_the_grab_to_the_new_key() {
+        let grab = FakeGrab::default();
+        let mut manager = grab.manager();
+        let old = manager.register_hotkey("ctrl+shift+space").unwrap();
+
+        let new = manager.rebind(old, "ctrl+alt+r").unwrap();
+
+        assert_eq!(grab.grabbed(), [new]);
+        assert_eq!(manager.registered(old), None);
+        assert!(manager.registered(new).is_some());
+    }
+
+    #[test]
+    fn a_refused_registration_keeps_the_old_key() {
+        let grab = FakeGrab::default();
+        let mut manager = grab.manager();
+        let old = manager.register_hotkey("ctrl+shift+space").unwrap();
+        grab.refuse_register.lock().unwrap().push(parse("ctrl+alt+r").unwrap().id());
+
+        let error = manager.rebind(old, "ctrl+alt+r").unwrap_err();
+
+        assert!(error.to_string().contains("already grabbed by another client"), "{}", error);
+        assert_eq!(grab.grabbed(), [old]);
+        assert!(manager.registered(old).is_some());
+    }
+
+    #[test]
+    fn a_refused_release_changes_nothing() {
+        let grab = FakeGrab::default();
+        let mut manager = grab.manager();
+        let old = manager.register_hotkey("ctrl+shift+space").unwrap();
+        grab.refuse_unregister.lock().unwrap().push(old);
+
+        assert!(manager.rebind(old, "ctrl+alt+r").is_err());
+
+        assert_eq!(grab.grabbed(), [old]);
+        assert!(manager.registered(old).is_some());
+    }
+
+    #[test]
+    fn a_swap_failing_halfway_puts_both_keys_back() {
+        let grab = FakeGrab::default();
+        let mut manager = grab.manager();
+        let first = manager.register_hotkey("ctrl+shift+space").unwrap();
+        let second = manager.register_hotkey("ctrl+shift+c").unwrap();
+        grab.refuse_register.lock().unwrap().push(parse("ctrl+alt+r").unwrap().id());
+
+        // The first new key is grabbed before the second is refused
+        let result = manager.replace(&[first, second], &["ctrl+shift+space", "ctrl+alt+r"]);
+
+        assert!(result.is_err());
+        assert_eq!(grab.grabbed(), sorted(vec![first, second]));
+        assert!(manager.registered(first).is_some());
+        assert!(manager.registered(second).is_some());
+    }
+}
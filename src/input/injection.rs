@@ -1,55 +1,446 @@
 use anyhow::Result;
 use enigo::{Enigo, Key, Settings, Direction, Keyboard};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
+use unicode_segmentation::UnicodeSegmentation;
 
-pub struct TextInjector {
+use crate::cancel::CancellationToken;
+use crate::events::EmitText;
+use crate::output::job::clean_text;
+
+/// How keystrokes reach the focused window (text.backend)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextBackend {
+    /// wtype or ydotool on Wayland sessions, enigo otherwise
+    #[default]
+    Auto,
+    /// X11 (and XWayland windows), macOS and Windows
+    Enigo,
+    /// Wayland virtual-keyboard protocol; not supported by GNOME
+    Wtype,
+    /// uinput via the ydotoold daemon; types through the active keyboard layout
+    Ydotool,
+}
+
+impl TextBackend {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TextBackend::Auto => "auto",
+            TextBackend::Enigo => "enigo",
+            TextBackend::Wtype => "wtype",
+            TextBackend::Ydotool => "ydotool",
+        }
+    }
+
+    /// Pick a concrete backend for this session
+    pub fn resolve(self) -> Result<TextBackend> {
+        let wayland = std::env::var("XDG_SESSION_TYPE")
+            .map(|session| session.eq_ignore_ascii_case("wayland"))
+            .unwrap_or(false);
+        // Mutter has no virtual-keyboard protocol, so wtype can't type there
+        let gnome = std::env::var("XDG_CURRENT_DESKTOP")
+            .map(|desktop| desktop.split(':').any(|name| name.eq_ignore_ascii_case("gnome")))
+            .unwrap_or(false);
+        let (backend, warning) = self.choose(wayland, gnome, is_installed)?;
+        if let Some(warning) = warning {
+            warn!("⚠️  {}", warning);
+        }
+        Ok(backend)
+    }
+
+    /// The backend to use, given the session and which helper programs exist, with a
+    /// warning when it's unlikely to reach Wayland windows
+    fn choose(
+        self,
+        wayland: bool,
+        gnome: bool,
+        installed: impl Fn(&str) -> bool,
+    ) -> Result<(TextBackend, Option<String>)> {
+        match self {
+            TextBackend::Auto if wayland => {
+                let helpers = if gnome {
+                    [TextBackend::Ydotool].as_slice()
+                } else {
+                    [TextBackend::Wtype, TextBackend::Ydotool].as_slice()
+                };
+                if let Some(backend) = helpers.iter().find(|backend| installed(backend.as_str())) {
+                    return Ok((*backend, None));
+                }
+                let install = if gnome {
+                    "install ydotool and start its ydotoold daemon (wtype doesn't work on GNOME)"
+                } else {
+                    "install wtype, or ydotool and start its ydotoold daemon"
+                };
+                Ok((
+                    TextBackend::Enigo,
+                    Some(format!(
+                        "Wayland session without wtype or ydotool: text only reaches XWayland windows; {}",
+                        install
+                    )),
+                ))
+            }
+            TextBackend::Auto | TextBackend::Enigo => Ok((TextBackend::Enigo, None)),
+            TextBackend::Wtype | TextBackend::Ydotool => {
+                let program = self.as_str();
+                if !installed(program) {
+                    let install = match self {
+                        TextBackend::Wtype => "install the wtype package",
+                        _ => "install the ydotool package and start its ydotoold daemon",
+                    };
+                    return Err(anyhow::anyhow!(
+                        "text.backend is \"{}\" but {} isn't installed (or not on PATH); {}",
+                        program,
+                        program,
+                        install
+                    ));
+                }
+                let warning = (self == TextBackend::Wtype && gnome).then(|| {
+                    "GNOME doesn't support the virtual-keyboard protocol wtype needs; try text.backend = \"ydotool\"".to_string()
+                });
+                Ok((self, warning))
+            }
+        }
+    }
+}
+
+/// Whether `program` is an executable on PATH
+fn is_installed(program: &str) -> bool {
+    std::env::var_os("PATH").is_some_and(|paths| {
+        std::env::split_paths(&paths).any(|dir| is_executable(&dir.join(program)))
+    })
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata().is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file() || path.with_extension("exe").is_file()
+}
+
+/// Sends keystrokes to the focused window
+pub trait InjectionBackend: Send {
+    fn name(&self) -> &'static str;
+    /// Type text as given; newlines and tabs come out as Return and Tab
+    fn text(&mut self, text: &str) -> Result<()>;
+    /// Press and release one key
+    fn key(&mut self, key: Key) -> Result<()>;
+    /// Press and release one key `count` times
+    fn repeat_key(&mut self, key: Key, count: usize) -> Result<()> {
+        for _ in 0..count {
+            self.key(key)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn create_backend(backend: TextBackend) -> Result<Box<dyn InjectionBackend>> {
+    Ok(match backend.resolve()? {
+        TextBackend::Wtype => Box::new(WtypeBackend),
+        TextBackend::Ydotool => Box::new(YdotoolBackend),
+        TextBackend::Auto | TextBackend::Enigo => Box::new(EnigoBackend::new()?),
+    })
+}
+
+struct EnigoBackend {
     enigo: Enigo,
-    #[allow(dead_code)]
+}
+
+impl EnigoBackend {
+    fn new() -> Result<Self> {
+        let enigo = Enigo::new(&Settings::default())
+            .map_err(|e| anyhow::anyhow!("Failed to initialize text injector: {}", e))?;
+        Ok(Self { enigo })
+    }
+}
+
+impl InjectionBackend for EnigoBackend {
+    fn name(&self) -> &'static str {
+        "enigo"
+    }
+
+    fn text(&mut self, text: &str) -> Result<()> {
+        self.enigo.text(text)
+            .map_err(|e| anyhow::anyhow!("Failed to type \"{}\": {}", text, e))
+    }
+
+    fn key(&mut self, key: Key) -> Result<()> {
+        self.enigo.key(key, Direction::Click)
+            .map_err(|e| anyhow::anyhow!("Failed to press {:?}: {}", key, e))
+    }
+}
+
+/// Run a helper program, turning a non-zero exit into an error carrying its stderr
+fn run_helper(program: &str, args: &[&str]) -> Result<()> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| anyhow::anyhow!("Failed to run {}: {}", program, e))?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "{} failed ({}): {}",
+            program,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+/// Types through `wtype` (Wayland virtual-keyboard protocol), any Unicode included
+struct WtypeBackend;
+
+impl WtypeBackend {
+    /// XKB keysym name for a key
+    fn keysym(key: Key) -> Result<String> {
+        Ok(match key {
+            Key::Return => "Return".to_string(),
+            Key::Tab => "Tab".to_string(),
+            Key::Space => "space".to_string(),
+            Key::Backspace => "BackSpace".to_string(),
+            Key::UpArrow => "Up".to_string(),
+            Key::DownArrow => "Down".to_string(),
+            Key::LeftArrow => "Left".to_string(),
+            Key::RightArrow => "Right".to_string(),
+            Key::Unicode(c) if c.is_ascii_alphanumeric() => c.to_string(),
+            key => return Err(anyhow::anyhow!("Key {:?} isn't supported by the wtype backend", key)),
+        })
+    }
+
+    /// One wtype invocation pressing `keysym` `count` times
+    fn repeat_args(keysym: &str, count: usize) -> Vec<String> {
+        ["-k", keysym].repeat(count).into_iter().map(str::to_string).collect()
+    }
+}
+
+impl InjectionBackend for WtypeBackend {
+    fn name(&self) -> &'static str {
+        "wtype"
+    }
+
+    fn text(&mut self, text: &str) -> Result<()> {
+        for segment in segment_text(text) {
+            match segment {
+                TextSegment::Key(key) => self.key(key)?,
+                TextSegment::Text(run) => run_helper("wtype", &["--", &run])?,
+            }
+        }
+        Ok(())
+    }
+
+    fn key(&mut self, key: Key) -> Result<()> {
+        run_helper("wtype", &["-k", &Self::keysym(key)?])
+    }
+
+    fn repeat_key(&mut self, key: Key, count: usize) -> Result<()> {
+        if count == 0 {
+            return Ok(());
+        }
+        let args = Self::repeat_args(&Self::keysym(key)?, count);
+        run_helper("wtype", &args.iter().map(String::as_str).collect::<Vec<_>>())
+    }
+}
+
+/// Types through `ydotool`, which talks to the ydotoold daemon's socket. Text goes
+/// through the active keyboard layout, so characters it lacks can't be typed.
+struct YdotoolBackend;
+
+impl YdotoolBackend {
+    /// Linux input event code for a key
+    fn code(key: Key) -> Result<u16> {
+        Ok(match key {
+            Key::Return => 28,
+            Key::Tab => 15,
+            Key::Space => 57,
+            Key::Backspace => 14,
+            Key::UpArrow => 103,
+            Key::DownArrow => 108,
+            Key::LeftArrow => 105,
+            Key::RightArrow => 106,
+            Key::Unicode(c) => {
+                let c = c.to_ascii_lowercase();
+                ["qwertyuiop", "asdfghjkl", "zxcvbnm"]
+                    .iter()
+                    .zip([16, 30, 44])
+                    .find_map(|(row, first)| row.find(c).map(|index| first + index as u16))
+                    .ok_or_else(|| anyhow::anyhow!("Key {:?} isn't supported by the ydotool backend", key))?
+            }
+            key => return Err(anyhow::anyhow!("Key {:?} isn't supported by the ydotool backend", key)),
+        })
+    }
+
+    /// Arguments pressing the chord `codes` `times` times in one ydotool invocation
+    fn key_args(codes: &[u16], times: usize) -> Vec<String> {
+        // Press in order, release in reverse
+        let chord: Vec<String> = codes
+            .iter()
+            .map(|code| format!("{}:1", code))
+            .chain(codes.iter().rev().map(|code| format!("{}:0", code)))
+            .collect();
+        let events = chord.iter().cycle().take(chord.len() * times).cloned();
+        std::iter::once("key".to_string()).chain(events).collect()
+    }
+
+    fn key_events(codes: &[u16], times: usize) -> Result<()> {
+        let args = Self::key_args(codes, times);
+        run_helper("ydotool", &args.iter().map(String::as_str).collect::<Vec<_>>())
+    }
+}
+
+impl InjectionBackend for YdotoolBackend {
+    fn name(&self) -> &'static str {
+        "ydotool"
+    }
+
+    fn text(&mut self, text: &str) -> Result<()> {
+        for segment in segment_text(text) {
+            match segment {
+                TextSegment::Key(key) => self.key(key)?,
+                TextSegment::Text(run) => run_helper("ydotool", &["type", "--", &run])?,
+            }
+        }
+        Ok(())
+    }
+
+    fn key(&mut self, key: Key) -> Result<()> {
+        Self::key_events(&[Self::code(key)?], 1)
+    }
+
+    fn repeat_key(&mut self, key: Key, count: usize) -> Result<()> {
+        if count == 0 {
+            return Ok(());
+        }
+        Self::key_events(&[Self::code(key)?], count)
+    }
+}
+
+/// Stands in for a real backend with text.dry_run: keystrokes are only logged
+struct DryRunBackend;
+
+impl InjectionBackend for DryRunBackend {
+    fn name(&self) -> &'static str {
+        "dry-run"
+    }
+
+    fn text(&mut self, text: &str) -> Result<()> {
+        debug!("Dry run, not typing \"{}\"", text);
+        Ok(())
+    }
+
+    fn key(&mut self, key: Key) -> Result<()> {
+        debug!("Dry run, not pressing {:?}", key);
+        Ok(())
+    }
+
+    fn repeat_key(&mut self, key: Key, count: usize) -> Result<()> {
+        debug!("Dry run, not pressing {:?} {} times", key, count);
+        Ok(())
+    }
+}
+
+pub struct TextInjector {
+    /// Called on the blocking pool, so it's shared with the task making each call
+    backend: Arc<Mutex<Box<dyn InjectionBackend>>>,
     typing_delay: Duration,
+    /// Log and report text instead of typing it (--dry-run / text.dry_run)
+    dry_run: bool,
+    on_event: Option<EmitText>,
 }
 
-#[allow(dead_code)]
 impl TextInjector {
-    pub fn new(typing_delay_ms: u64) -> Result<Self> {
-        let settings = Settings::default();
-        let enigo = Enigo::new(&settings)
-            .map_err(|e| anyhow::anyhow!("Failed to initialize text injector: {}", e))?;
+    pub fn new(typing_delay_ms: u64, backend: TextBackend, dry_run: bool) -> Result<Self> {
+        let backend: Box<dyn InjectionBackend> = if dry_run {
+            info!("🧪 Dry run: transcriptions are logged instead of typed");
+            Box::new(DryRunBackend)
+        } else {
+            create_backend(backend)?
+        };
 
-        info!("📝 Text injector initialized with {}ms typing delay", typing_delay_ms);
+        info!("📝 Text injector initialized ({}) with {}ms typing delay", backend.name(), typing_delay_ms);
 
         Ok(Self {
-            enigo,
+            backend: Arc::new(Mutex::new(backend)),
             typing_delay: Duration::from_millis(typing_delay_ms),
+            dry_run,
+            on_event: None,
         })
     }
 
-    pub async fn inject_text(&mut self, text: &str) -> Result<()> {
-        if text.is_empty() {
-            return Ok(());
+    /// Where injection_skipped events go in dry-run mode
+    pub fn set_event_callback(&mut self, callback: EmitText) {
+        self.on_event = Some(callback);
+    }
+
+    /// In dry-run mode, log and report `text` in place of typing it; true if it was skipped
+    fn skip(&self, text: &str) -> bool {
+        if !self.dry_run {
+            return false;
+        }
+        info!("🧪 Dry run, would inject: \"{}\"", text);
+        if let Some(ref callback) = self.on_event {
+            callback("injection_skipped", "Dry run, not injected", text, serde_json::Value::Null);
+        }
+        true
+    }
+
+    /// The backend keystrokes go through, for error reports
+    pub fn backend_name(&self) -> &'static str {
+        self.backend.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).name()
+    }
+
+    /// Make a backend call on the blocking pool: wtype and ydotool start a process per call,
+    /// and enigo can wait on the display server
+    async fn on_backend(&self, call: impl FnOnce(&mut dyn InjectionBackend) -> Result<()> + Send + 'static) -> Result<()> {
+        let backend = self.backend.clone();
+        tokio::task::spawn_blocking(move || {
+            // A call that panicked left no state behind worth refusing the next one over
+            let mut backend = backend.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            call(&mut **backend)
+        })
+        .await
+        .map_err(|e| anyhow::anyhow!("Keystroke call failed: {}", e))?
+    }
+
+    /// Type `text` segment by segment, after `settle`; stops between segments once the job
+    /// is cancelled. False if it stopped before the end.
+    pub async fn inject_text(&mut self, text: &str, cancel: &CancellationToken) -> Result<bool> {
+        if text.is_empty() || self.skip(text) {
+            return Ok(true);
         }
 
         info!("📝 Injecting text: \"{}\"", text);
+        self.type_segments(text, cancel).await
+    }
 
-        // Small delay to ensure target application is ready
-        tokio::time::sleep(Duration::from_millis(100)).await;
+    /// Type the text in runs of plain graphemes, splitting only at special keys
+    async fn type_segments(&mut self, text: &str, cancel: &CancellationToken) -> Result<bool> {
+        for segment in segment_text(text) {
+            if cancel.is_cancelled() {
+                info!("Typing cancelled");
+                return Ok(false);
+            }
+            self.type_segment(segment).await?;
 
-        // Type the text character by character with delays
-        for char in text.chars() {
-            self.type_character(char)?;
-            
-            // Add delay between characters if configured
+            // Add delay between segments if configured
             if !self.typing_delay.is_zero() {
                 tokio::time::sleep(self.typing_delay).await;
             }
         }
 
         debug!("✅ Text injection completed");
-        Ok(())
+        Ok(true)
     }
 
     pub async fn inject_text_fast(&mut self, text: &str) -> Result<()> {
-        if text.is_empty() {
+        if text.is_empty() || self.skip(text) {
             return Ok(());
         }
 
@@ -59,84 +450,389 @@ impl TextInjector {
         tokio::time::sleep(Duration::from_millis(50)).await;
 
         // Type the entire string at once (faster)
-        self.enigo
-            .text(text)
-            .map_err(|e| anyhow::anyhow!("Failed to inject text: {}", e))?;
+        let text = text.to_string();
+        self.on_backend(move |backend| backend.text(&text)).await?;
 
         debug!("✅ Fast text injection completed");
         Ok(())
     }
 
-    fn type_character(&mut self, c: char) -> Result<()> {
-        match c {
-            // Special characters that need specific handling
-            '\n' => {
-                self.enigo.key(Key::Return, Direction::Click)
-                    .map_err(|e| anyhow::anyhow!("Failed to type Enter: {}", e))?;
+    async fn type_segment(&mut self, segment: TextSegment) -> Result<()> {
+        match segment {
+            TextSegment::Key(key) => self.on_backend(move |backend| backend.key(key)).await,
+            // Runs of regular graphemes go out in a single call so clusters
+            // (combining accents, ZWJ emoji, flags) are never split
+            TextSegment::Text(run) => self.on_backend(move |backend| backend.text(&run)).await,
+        }
+    }
+
+    /// Give the focused window a moment after the hotkey before anything is typed into it
+    pub async fn settle(&mut self) {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    /// Press and release a single key, e.g. Tab between form fields
+    pub async fn press_key(&mut self, key: Key) -> Result<()> {
+        self.on_backend(move |backend| backend.key(key)).await?;
+        if !self.typing_delay.is_zero() {
+            tokio::time::sleep(self.typing_delay).await;
+        }
+        Ok(())
+    }
+
+    /// Type form fields with `key` pressed between them, each cleaned up like formatted
+    /// injection; stops between fields once the job is cancelled
+    pub async fn inject_fields(&mut self, fields: &[String], key: Key, cancel: &CancellationToken) -> Result<()> {
+        self.settle().await;
+        for (i, field) in fields.iter().enumerate() {
+            if cancel.is_cancelled() {
+                break;
             }
-            '\t' => {
-                self.enigo.key(Key::Tab, Direction::Click)
-                    .map_err(|e| anyhow::anyhow!("Failed to type Tab: {}", e))?;
+            if i > 0 {
+                self.press_key(key).await?;
             }
-            ' ' => {
-                self.enigo.key(Key::Space, Direction::Click)
-                    .map_err(|e| anyhow::anyhow!("Failed to type Space: {}", e))?;
+            self.inject_text_fast(&clean_text(field)).await?;
+        }
+        Ok(())
+    }
+
+    /// Erase the last `count` characters typed, e.g. to replace a previous injection
+    pub async fn delete_chars(&mut self, count: usize) -> Result<()> {
+        self.on_backend(move |backend| backend.repeat_key(Key::Backspace, count)).await
+    }
+}
+
+/// Key names accepted in config for single keypresses
+pub fn parse_key_name(name: &str) -> Option<Key> {
+    match name.trim().to_lowercase().as_str() {
+        "tab" => Some(Key::Tab),
+        "enter" | "return" => Some(Key::Return),
+        "space" => Some(Key::Space),
+        "down" => Some(Key::DownArrow),
+        "up" => Some(Key::UpArrow),
+        "right" => Some(Key::RightArrow),
+        "left" => Some(Key::LeftArrow),
+        _ => None,
+    }
+}
+
+/// A unit of per-character injection: either a run of plain text or a special key
+#[derive(Debug, Clone, PartialEq)]
+enum TextSegment {
+    Text(String),
+    Key(Key),
+}
+
+/// Split text into grapheme-safe runs, breaking only at keys that need a key event
+fn segment_text(text: &str) -> Vec<TextSegment> {
+    let mut segments = Vec::new();
+    let mut run = String::new();
+
+    for grapheme in text.graphemes(true) {
+        let key = match grapheme {
+            "\n" | "\r\n" => Some(Key::Return),
+            "\t" => Some(Key::Tab),
+            _ => None,
+        };
+
+        match key {
+            Some(key) => {
+                if !run.is_empty() {
+                    segments.push(TextSegment::Text(std::mem::take(&mut run)));
+                }
+                segments.push(TextSegment::Key(key));
             }
-            // Regular characters
-            _ => {
-                self.enigo.text(&c.to_string())
-                    .map_err(|e| anyhow::anyhow!("Failed to type character '{}': {}", c, e))?;
+            None => run.push_str(grapheme),
+        }
+    }
+
+    if !run.is_empty() {
+        segments.push(TextSegment::Text(run));
+    }
+
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Records every call instead of sending keystrokes
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl Recorder {
+        fn calls(&self) -> Vec<String> {
+            self.0.lock().unwrap().clone()
+        }
+    }
+
+    impl InjectionBackend for Recorder {
+        fn name(&self) -> &'static str {
+            "recorder"
+        }
+
+        fn text(&mut self, text: &str) -> Result<()> {
+            self.0.lock().unwrap().push(format!("text:{}", text));
+            Ok(())
+        }
+
+        fn key(&mut self, key: Key) -> Result<()> {
+            self.0.lock().unwrap().push(format!("key:{:?}", key));
+            Ok(())
+        }
+
+        fn repeat_key(&mut self, key: Key, count: usize) -> Result<()> {
+            self.0.lock().unwrap().push(format!("repeat:{:?}x{}", key, count));
+            Ok(())
+        }
+    }
+
+    fn injector(recorder: &Recorder, typing_delay_ms: u64) -> TextInjector {
+        TextInjector {
+            backend: Arc::new(Mutex::new(Box::new(recorder.clone()))),
+            typing_delay: Duration::from_millis(typing_delay_ms),
+            dry_run: false,
+            on_event: None,
+        }
+    }
+
+    fn text(run: &str) -> TextSegment {
+        TextSegment::Text(run.to_string())
+    }
+
+    #[test]
+    fn combining_accents_stay_in_one_run() {
+        // "e" + COMBINING ACUTE ACCENT, twice
+        let input = "cafe\u{301} de\u{301}ja\u{300}";
+        assert_eq!(segment_text(input), vec![text(input)]);
+    }
+
+    #[test]
+    fn zwj_emoji_and_flags_are_not_split() {
+        let family = "👩\u{200d}👩\u{200d}👧";
+        let input = format!("{}\n🇩🇪", family);
+        assert_eq!(
+            segment_text(&input),
+            vec![text(family), TextSegment::Key(Key::Return), text("🇩🇪")]
+        );
+    }
+
+    #[test]
+    fn cjk_runs_break_only_at_special_keys() {
+        assert_eq!(
+            segment_text("你好\t世界\r\n再见"),
+            vec![
+                text("你好"),
+                TextSegment::Key(Key::Tab),
+                text("世界"),
+                TextSegment::Key(Key::Return),
+                text("再见"),
+            ]
+        );
+    }
+
+    #[test]
+    fn leading_and_repeated_keys_make_no_empty_runs() {
+        assert_eq!(
+            segment_text("\n\nok\t"),
+            vec![
+                TextSegment::Key(Key::Return),
+                TextSegment::Key(Key::Return),
+                text("ok"),
+                TextSegment::Key(Key::Tab),
+            ]
+        );
+        assert!(segment_text("").is_empty());
+    }
+
+    #[tokio::test]
+    async fn form_fields_are_separated_by_the_key() {
+        let recorder = Recorder::default();
+        let mut injector = injector(&recorder, 0);
+        let fields = ["John ,".to_string(), String::new(), "Smith".to_string()];
+        injector.inject_fields(&fields, Key::Tab, &CancellationToken::new()).await.unwrap();
+        assert_eq!(recorder.calls(), vec!["text:John,", "key:Tab", "key:Tab", "text:Smith"]);
+    }
+
+    #[tokio::test]
+    async fn cancelled_form_fill_types_nothing() {
+        let recorder = Recorder::default();
+        let mut injector = injector(&recorder, 0);
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        injector.inject_fields(&["John".to_string(), "Smith".to_string()], Key::Tab, &cancel).await.unwrap();
+        assert!(recorder.calls().is_empty());
+    }
+
+    /// Cancels the job once the recorder has seen `after` calls
+    struct CancelAfter {
+        recorder: Recorder,
+        cancel: CancellationToken,
+        after: usize,
+    }
+
+    impl CancelAfter {
+        fn record(&self) {
+            if self.recorder.calls().len() >= self.after {
+                self.cancel.cancel();
             }
         }
-        Ok(())
     }
 
-    pub async fn inject_with_formatting(&mut self, text: &str) -> Result<()> {
-        if text.is_empty() {
-            return Ok(());
+    impl InjectionBackend for CancelAfter {
+        fn name(&self) -> &'static str {
+            "cancel-after"
+        }
+
+        fn text(&mut self, text: &str) -> Result<()> {
+            self.recorder.text(text)?;
+            self.record();
+            Ok(())
         }
 
-        info!("📝 Injecting formatted text: \"{}\"", text);
+        fn key(&mut self, key: Key) -> Result<()> {
+            self.recorder.key(key)?;
+            self.record();
+            Ok(())
+        }
+    }
 
-        // Add a small delay
-        tokio::time::sleep(Duration::from_millis(50)).await;
+    fn cancelling_injector(recorder: &Recorder, cancel: &CancellationToken, after: usize) -> TextInjector {
+        let mut injector = injector(recorder, 0);
+        injector.backend = Arc::new(Mutex::new(Box::new(CancelAfter { recorder: recorder.clone(), cancel: cancel.clone(), after })));
+        injector
+    }
+
+    #[tokio::test]
+    async fn cancel_stops_typing_between_segments() {
+        let recorder = Recorder::default();
+        let cancel = CancellationToken::new();
+        let mut injector = cancelling_injector(&recorder, &cancel, 2);
+        assert!(!injector.inject_text("one\ntwo\nthree", &cancel).await.unwrap());
+        assert_eq!(recorder.calls(), vec!["text:one", "key:Return"]);
+    }
 
-        // Clean up the text (remove extra whitespace, fix punctuation)
-        let cleaned_text = self.clean_text(text);
+    #[tokio::test]
+    async fn cancelled_before_typing_types_nothing() {
+        let recorder = Recorder::default();
+        let mut injector = injector(&recorder, 0);
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        assert!(!injector.inject_text("hello\nworld", &cancel).await.unwrap());
+        assert!(recorder.calls().is_empty());
+    }
 
-        // Type the cleaned text
-        self.inject_text_fast(&cleaned_text).await?;
+    #[tokio::test]
+    async fn inject_text_calls_the_backend_once_per_segment() {
+        let recorder = Recorder::default();
+        let mut injector = injector(&recorder, 1);
+        assert!(injector.inject_text("e\u{301}t\u{e9} 👍🏽\n日本", &CancellationToken::new()).await.unwrap());
+        assert_eq!(
+            recorder.calls(),
+            vec!["text:e\u{301}t\u{e9} 👍🏽", "key:Return", "text:日本"]
+        );
+    }
 
-        Ok(())
+    #[tokio::test]
+    async fn delete_chars_is_one_backend_call() {
+        let recorder = Recorder::default();
+        let mut injector = injector(&recorder, 0);
+        injector.delete_chars(12).await.unwrap();
+        assert_eq!(recorder.calls(), vec!["repeat:Backspacex12"]);
     }
 
-    fn clean_text(&self, text: &str) -> String {
-        text.trim()
-            .split_whitespace()
-            .collect::<Vec<_>>()
-            .join(" ")
-            .replace(" ,", ",")
-            .replace(" .", ".")
-            .replace(" !", "!")
-            .replace(" ?", "?")
-            .replace(" ;", ";")
-            .replace(" :", ":")
+    #[test]
+    fn repeated_keys_go_out_in_one_helper_invocation() {
+        assert_eq!(WtypeBackend::repeat_args("BackSpace", 3), ["-k", "BackSpace", "-k", "BackSpace", "-k", "BackSpace"]);
+        assert_eq!(YdotoolBackend::key_args(&[14], 2), ["key", "14:1", "14:0", "14:1", "14:0"]);
+        // Chords release in reverse
+        assert_eq!(YdotoolBackend::key_args(&[29, 30], 1), ["key", "29:1", "30:1", "30:0", "29:0"]);
     }
 
-    pub async fn clear_and_inject(&mut self, text: &str) -> Result<()> {
-        // Select all text (Ctrl+A)
-        self.enigo.key(Key::Control, Direction::Press)
-            .map_err(|e| anyhow::anyhow!("Failed to press Ctrl: {}", e))?;
-        self.enigo.key(Key::Unicode('a'), Direction::Click)
-            .map_err(|e| anyhow::anyhow!("Failed to press A: {}", e))?;
-        self.enigo.key(Key::Control, Direction::Release)
-            .map_err(|e| anyhow::anyhow!("Failed to release Ctrl: {}", e))?;
+    /// `choose` with only `programs` installed
+    fn choose(backend: TextBackend, wayland: bool, gnome: bool, programs: &[&str]) -> Result<(TextBackend, Option<String>)> {
+        backend.choose(wayland, gnome, |program| programs.contains(&program))
+    }
 
-        tokio::time::sleep(Duration::from_millis(10)).await;
+    #[test]
+    fn auto_picks_a_helper_on_wayland_and_enigo_elsewhere() {
+        let cases: &[(bool, bool, &[&str], TextBackend)] = &[
+            // wayland, gnome, installed, chosen
+            (false, false, &["wtype", "ydotool"], TextBackend::Enigo),
+            (true, false, &["wtype", "ydotool"], TextBackend::Wtype),
+            (true, false, &["ydotool"], TextBackend::Ydotool),
+            // GNOME has no virtual-keyboard protocol, so wtype is passed over
+            (true, true, &["wtype", "ydotool"], TextBackend::Ydotool),
+        ];
+        for &(wayland, gnome, installed, chosen) in cases {
+            let (backend, warning) = choose(TextBackend::Auto, wayland, gnome, installed).unwrap();
+            assert_eq!(backend, chosen, "wayland: {}, gnome: {}, installed: {:?}", wayland, gnome, installed);
+            assert_eq!(warning, None);
+        }
+    }
 
-        // Delete selected text and inject new text
-        self.inject_text_fast(text).await?;
+    #[test]
+    fn auto_without_a_helper_falls_back_to_enigo_and_says_what_to_install() {
+        let (backend, warning) = choose(TextBackend::Auto, true, false, &[]).unwrap();
+        assert_eq!(backend, TextBackend::Enigo);
+        assert!(warning.unwrap().contains("install wtype, or ydotool"));
 
-        Ok(())
+        let (backend, warning) = choose(TextBackend::Auto, true, true, &["wtype"]).unwrap();
+        assert_eq!(backend, TextBackend::Enigo);
+        assert!(warning.unwrap().contains("wtype doesn't work on GNOME"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn an_explicit_backend_is_kept_when_installed() {
+        assert_eq!(choose(TextBackend::Enigo, true, false, &["wtype"]).unwrap(), (TextBackend::Enigo, None));
+        assert_eq!(choose(TextBackend::Ydotool, true, true, &["ydotool"]).unwrap(), (TextBackend::Ydotool, None));
+        assert_eq!(choose(TextBackend::Wtype, false, false, &["wtype"]).unwrap(), (TextBackend::Wtype, None));
+
+        let (backend, warning) = choose(TextBackend::Wtype, true, true, &["wtype"]).unwrap();
+        assert_eq!(backend, TextBackend::Wtype);
+        assert!(warning.unwrap().contains("try text.backend = \"ydotool\""));
+    }
+
+    #[test]
+    fn an_explicit_helper_that_isnt_installed_is_an_error() {
+        let error = choose(TextBackend::Wtype, true, false, &["ydotool"]).unwrap_err().to_string();
+        assert!(error.contains("install the wtype package"), "{}", error);
+
+        let error = choose(TextBackend::Ydotool, true, false, &[]).unwrap_err().to_string();
+        assert!(error.contains("start its ydotoold daemon"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn dry_run_reports_text_without_typing_it() {
+        let recorder = Recorder::default();
+        let mut injector = injector(&recorder, 0);
+        injector.dry_run = true;
+        let skipped: Arc<Mutex<Vec<(String, String)>>> = Arc::default();
+        let events = skipped.clone();
+        injector.set_event_callback(Arc::new(move |event: &str, _: &str, text: &str, _| {
+            events.lock().unwrap().push((event.to_string(), text.to_string()));
+        }));
+        let cancel = CancellationToken::new();
+
+        injector.inject_text("one\ntwo", &cancel).await.unwrap();
+        injector.inject_text_fast("five").await.unwrap();
+        injector.inject_fields(&["seven".to_string(), "eight".to_string()], Key::Tab, &cancel).await.unwrap();
+
+        let texts: Vec<String> = skipped.lock().unwrap().iter().map(|(event, text)| {
+            assert_eq!(event, "injection_skipped");
+            text.clone()
+        }).collect();
+        assert_eq!(texts, ["one\ntwo", "five", "seven", "eight"]);
+        // Only inject_fields' Tab reaches the backend, which is DryRunBackend outside tests
+        assert_eq!(recorder.calls(), vec!["key:Tab"]);
+    }
+
+    #[test]
+    fn the_dry_run_backend_accepts_everything() {
+        let mut backend = DryRunBackend;
+        backend.text("héllo\n").unwrap();
+        backend.key(Key::Return).unwrap();
+        backend.repeat_key(Key::Backspace, 3).unwrap();
+    }
+}
@@ -1,5 +1,13 @@
+pub mod action;
+pub mod gesture;
+pub mod hold;
 pub mod hotkey;
 pub mod injection;
+pub mod portal;
+pub mod tap;
 
+pub use action::{HotkeyAction, HotkeyRouter};
+pub use hold::{RecordGesture, RecordKey};
 pub use hotkey::{HotkeyEvent, HotkeyManager};
+pub use injection::parse_key_name;
 pub use injection::TextInjector;
\ No newline at end of file
@@ -0,0 +1,275 @@
+//! Global shortcuts through org.freedesktop.portal.GlobalShortcuts, for
+//! Wayland sessions where global-hotkey can't grab keys.
+
+/// Translate our hotkey syntax ("ctrl+shift+space") into the XDG shortcut
+/// trigger syntax ("CTRL+SHIFT+space") the portal uses as a preference.
+/// Keys go through the same parser as global-hotkey, so every name and the
+/// key:/code: escapes mean the same physical key on both backends.
+#[cfg(feature = "portal-hotkeys")]
+fn portal_trigger(hotkey_string: &str) -> String {
+    use global_hotkey::hotkey::Modifiers;
+
+    // Only a preference: the desktop lets the user pick a key if it can't be read
+    let Ok(hotkey) = super::hotkey::parse_hotkey_string(hotkey_string, false) else {
+        return hotkey_string.to_string();
+    };
+    let mut parts: Vec<String> = [
+        (Modifiers::CONTROL, "CTRL"),
+        (Modifiers::SHIFT, "SHIFT"),
+        (Modifiers::ALT, "ALT"),
+        (Modifiers::SUPER, "LOGO"),
+    ]
+    .into_iter()
+    .filter(|(modifier, _)| hotkey.mods.contains(*modifier))
+    .map(|(_, name)| name.to_string())
+    .collect();
+    parts.push(keysym(hotkey.key));
+    parts.join("+")
+}
+
+/// The XKB keysym name for a physical key
+#[cfg(feature = "portal-hotkeys")]
+fn keysym(code: global_hotkey::hotkey::Code) -> String {
+    use global_hotkey::hotkey::Code;
+
+    let name = match code {
+        Code::Enter => "Return",
+        Code::Escape => "Escape",
+        Code::Backspace => "BackSpace",
+        Code::Tab => "Tab",
+        Code::Space => "space",
+        Code::CapsLock => "Caps_Lock",
+        Code::ShiftLeft => "Shift_L",
+        Code::ShiftRight => "Shift_R",
+        Code::PrintScreen => "Print",
+        Code::ScrollLock => "Scroll_Lock",
+        Code::Pause => "Pause",
+        Code::Insert => "Insert",
+        Code::Home => "Home",
+        Code::PageUp => "Page_Up",
+        Code::Delete => "Delete",
+        Code::End => "End",
+        Code::PageDown => "Page_Down",
+        Code::ArrowUp => "Up",
+        Code::ArrowDown => "Down",
+        Code::ArrowLeft => "Left",
+        Code::ArrowRight => "Right",
+        Code::Minus => "minus",
+        Code::Equal => "equal",
+        Code::BracketLeft => "bracketleft",
+        Code::BracketRight => "bracketright",
+        Code::Backslash => "backslash",
+        Code::Semicolon => "semicolon",
+        Code::Quote => "apostrophe",
+        Code::Backquote => "grave",
+        Code::Comma => "comma",
+        Code::Period => "period",
+        Code::Slash => "slash",
+        Code::NumLock => "Num_Lock",
+        Code::NumpadAdd => "KP_Add",
+        Code::NumpadSubtract => "KP_Subtract",
+        Code::NumpadMultiply => "KP_Multiply",
+        Code::NumpadDivide => "KP_Divide",
+        Code::NumpadDecimal => "KP_Decimal",
+        Code::NumpadEnter => "KP_Enter",
+        Code::NumpadEqual => "KP_Equal",
+        Code::IntlBackslash => "less",
+        Code::IntlYen => "yen",
+        Code::ContextMenu => "Menu",
+        Code::Convert => "Henkan",
+        Code::NonConvert => "Muhenkan",
+        Code::KanaMode => "Hiragana_Katakana",
+        Code::Lang1 => "Hangul",
+        Code::Lang2 => "Hangul_Hanja",
+        Code::Lang3 => "Katakana",
+        Code::Lang4 => "Hiragana",
+        Code::Lang5 => "Zenkaku_Hankaku",
+        Code::Power => "XF86PowerOff",
+        Code::MediaPlayPause => "XF86AudioPlay",
+        Code::MediaStop => "XF86AudioStop",
+        Code::MediaTrackNext => "XF86AudioNext",
+        Code::MediaTrackPrevious => "XF86AudioPrev",
+        Code::AudioVolumeMute => "XF86AudioMute",
+        Code::AudioVolumeDown => "XF86AudioLowerVolume",
+        Code::AudioVolumeUp => "XF86AudioRaiseVolume",
+        Code::Copy => "XF86Copy",
+        Code::Paste => "XF86Paste",
+        Code::Cut => "XF86Cut",
+        Code::Undo => "Undo",
+        Code::Help => "Help",
+        // KeyA -> a, Digit1 -> 1, Numpad1 -> KP_1; F1-F24 are already keysym names
+        code => {
+            let name = code.to_string();
+            return match (name.strip_prefix("Key"), name.strip_prefix("Digit"), name.strip_prefix("Numpad")) {
+                (Some(letter), _, _) => letter.to_lowercase(),
+                (_, Some(digit), _) => digit.to_string(),
+                (_, _, Some(digit)) => format!("KP_{}", digit),
+                _ => name,
+            };
+        }
+    };
+    name.to_string()
+}
+
+/// Human-readable description shown in the desktop's shortcut settings
+#[cfg(feature = "portal-hotkeys")]
+fn describe(hotkey_string: &str) -> String {
+    format!("TomChat ({})", hotkey_string)
+}
+
+#[cfg(feature = "portal-hotkeys")]
+pub async fn listen(
+    hotkeys: std::collections::HashMap<u32, String>,
+    tx: tokio::sync::mpsc::Sender<super::HotkeyEvent>,
+    on_event: Option<crate::events::EmitStatus>,
+) -> anyhow::Result<()> {
+    use ashpd::desktop::global_shortcuts::{GlobalShortcuts, NewShortcut};
+    use futures_util::StreamExt;
+    use tracing::{debug, info, warn};
+
+    let emit = |event: &str, message: &str| {
+        if let Some(ref callback) = on_event {
+            callback(event, message);
+        }
+    };
+
+    let proxy = GlobalShortcuts::new().await?;
+    let session = proxy.create_session().await?;
+
+    let shortcuts: Vec<NewShortcut> = hotkeys
+        .iter()
+        .map(|(id, hotkey)| {
+            NewShortcut::new(id.to_string(), describe(hotkey))
+                .preferred_trigger(Some(portal_trigger(hotkey).as_str()))
+        })
+        .collect();
+
+    // The desktop may show an interactive dialog before granting the shortcuts
+    info!("Requesting global shortcuts from the desktop portal...");
+    emit("hotkey_permission_pending", "Waiting for shortcut permission from the desktop");
+
+    // No window of our own to parent the dialog to
+    let request = proxy.bind_shortcuts(&session, &shortcuts, &ashpd::WindowIdentifier::default()).await?;
+    if let Err(e) = request.response() {
+        warn!("Desktop portal denied global shortcuts: {}", e);
+        emit("hotkey_permission_denied", "Global shortcuts were denied by the desktop");
+        return Err(anyhow::anyhow!("Global shortcut permission denied: {}", e));
+    }
+    emit("hotkey_permission_granted", "Global shortcuts granted");
+
+    let mut activated = proxy.receive_activated().await?;
+    let mut deactivated = proxy.receive_deactivated().await?;
+
+    loop {
+        let (shortcut_id, pressed) = tokio::select! {
+            Some(event) = activated.next() => (event.shortcut_id().to_string(), true),
+            Some(event) = deactivated.next() => (event.shortcut_id().to_string(), false),
+            else => break,
+        };
+
+        let Some((id, hotkey)) = shortcut_id
+            .parse::<u32>()
+            .ok()
+            .and_then(|id| hotkeys.get(&id).map(|hotkey| (id, hotkey.clone())))
+        else {
+            continue;
+        };
+
+        debug!("🔑 Portal shortcut {}: {} (ID: {})", if pressed { "activated" } else { "deactivated" }, hotkey, id);
+        if tx.send(super::HotkeyEvent { id, hotkey, pressed }).await.is_err() {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(all(test, feature = "portal-hotkeys"))]
+mod tests {
+    use super::*;
+    use crate::input::hotkey::NAMED_KEYS;
+
+    #[test]
+    fn triggers_use_xkb_names() {
+        assert_eq!(portal_trigger("ctrl+shift+space"), "CTRL+SHIFT+space");
+        assert_eq!(portal_trigger("cmd+alt+k"), "CTRL+ALT+k");
+        assert_eq!(portal_trigger("super+f19"), "LOGO+F19");
+        assert_eq!(portal_trigger("caps"), "Caps_Lock");
+    }
+
+    #[test]
+    fn raw_escapes_reach_the_portal() {
+        assert_eq!(portal_trigger("ctrl+code:0x52"), "CTRL+Up");
+        assert_eq!(portal_trigger("code:0x6e"), "F19");
+        assert_eq!(portal_trigger("code:0x59"), "KP_1");
+        assert_eq!(portal_trigger("key:Lang1"), "Hangul");
+        assert_eq!(portal_trigger("alt+code:0x2d"), "ALT+minus");
+    }
+
+    #[test]
+    fn every_named_key_has_its_keysym() {
+        let keysyms = [
+            ("space", "space"),
+            ("enter", "Return"),
+            ("tab", "Tab"),
+            ("backspace", "BackSpace"),
+            ("delete", "Delete"),
+            ("escape", "Escape"),
+            ("lshift", "Shift_L"),
+            ("rshift", "Shift_R"),
+            ("up", "Up"),
+            ("down", "Down"),
+            ("left", "Left"),
+            ("right", "Right"),
+            ("insert", "Insert"),
+            ("home", "Home"),
+            ("end", "End"),
+            ("pageup", "Page_Up"),
+            ("pagedown", "Page_Down"),
+            ("capslock", "Caps_Lock"),
+            ("numlock", "Num_Lock"),
+            ("scrolllock", "Scroll_Lock"),
+            ("pause", "Pause"),
+            ("printscreen", "Print"),
+            ("menu", "Menu"),
+            ("grave", "grave"),
+            ("minus", "minus"),
+            ("equal", "equal"),
+            ("bracketleft", "bracketleft"),
+            ("bracketright", "bracketright"),
+            ("backslash", "backslash"),
+            ("semicolon", "semicolon"),
+            ("quote", "apostrophe"),
+            ("comma", "comma"),
+            ("period", "period"),
+            ("slash", "slash"),
+            ("numpadadd", "KP_Add"),
+            ("numpadsubtract", "KP_Subtract"),
+            ("numpadmultiply", "KP_Multiply"),
+            ("numpaddivide", "KP_Divide"),
+            ("numpaddecimal", "KP_Decimal"),
+            ("numpadenter", "KP_Enter"),
+            ("playpause", "XF86AudioPlay"),
+            ("mediastop", "XF86AudioStop"),
+            ("nexttrack", "XF86AudioNext"),
+            ("prevtrack", "XF86AudioPrev"),
+            ("mute", "XF86AudioMute"),
+            ("volumedown", "XF86AudioLowerVolume"),
+            ("volumeup", "XF86AudioRaiseVolume"),
+        ];
+        let named: Vec<&str> = NAMED_KEYS.iter().map(|(names, _)| names[0]).collect();
+        assert_eq!(named, keysyms.map(|(name, _)| name), "a named key was added without its keysym");
+
+        for ((names, _), (_, keysym)) in NAMED_KEYS.iter().zip(keysyms) {
+            // "plus" brings its own SHIFT, so only the key is compared
+            for name in *names {
+                let trigger = portal_trigger(&format!("ctrl+{}", name));
+                assert!(trigger.ends_with(&format!("+{}", keysym)), "{}: {}", name, trigger);
+            }
+        }
+        // The names outside the table
+        for (name, keysym) in [("a", "a"), ("0", "0"), ("f12", "F12"), ("f24", "F24"), ("numpad0", "KP_0"), ("kp9", "KP_9")] {
+            assert_eq!(portal_trigger(name), keysym, "{}", name);
+        }
+    }
+}
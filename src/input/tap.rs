@@ -0,0 +1,303 @@
+#[cfg(any(feature = "modifier-taps", test))]
+use std::time::{Duration, Instant};
+
+#[cfg(any(feature = "modifier-taps", test))]
+/// Longest press that still counts as a tap rather than a hold
+const TAP_MAX_HOLD: Duration = Duration::from_millis(300);
+#[cfg(any(feature = "modifier-taps", test))]
+/// Maximum gap between the two taps of a double-tap
+const DOUBLE_TAP_WINDOW: Duration = Duration::from_millis(400);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TapModifier {
+    Ctrl,
+    Shift,
+    Alt,
+    Super,
+}
+
+impl TapModifier {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "ctrl" | "control" => Some(TapModifier::Ctrl),
+            "shift" => Some(TapModifier::Shift),
+            "alt" => Some(TapModifier::Alt),
+            "super" | "win" | "meta" | "cmd" => Some(TapModifier::Super),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(any(feature = "modifier-taps", test))]
+/// A key as seen by the tap detector
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TapKey {
+    Modifier(TapModifier),
+    Other,
+}
+
+/// A modifier-only binding such as "ctrl" (single tap) or "double-ctrl"
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TapBinding {
+    pub modifier: TapModifier,
+    pub taps: u8,
+}
+
+impl TapBinding {
+    pub fn parse(hotkey_string: &str) -> Option<Self> {
+        let name = hotkey_string.trim().to_lowercase();
+        match name.strip_prefix("double-") {
+            Some(modifier) => TapModifier::parse(modifier).map(|modifier| Self { modifier, taps: 2 }),
+            None => TapModifier::parse(&name).map(|modifier| Self { modifier, taps: 1 }),
+        }
+    }
+}
+
+#[cfg(any(feature = "modifier-taps", test))]
+/// Detects taps of a lone modifier, ignoring presses that were part of a combo
+#[derive(Debug)]
+pub struct TapDetector {
+    binding: TapBinding,
+    pressed_at: Option<Instant>,
+    interrupted: bool,
+    last_tap: Option<Instant>,
+}
+
+#[cfg(any(feature = "modifier-taps", test))]
+impl TapDetector {
+    pub fn new(binding: TapBinding) -> Self {
+        Self {
+            binding,
+            pressed_at: None,
+            interrupted: false,
+            last_tap: None,
+        }
+    }
+
+    pub fn key_down(&mut self, key: TapKey, now: Instant) {
+        if key == TapKey::Modifier(self.binding.modifier) {
+            // Ignore auto-repeat while held
+            if self.pressed_at.is_none() {
+                self.pressed_at = Some(now);
+                self.interrupted = false;
+            }
+        } else {
+            // Any other key turns a held modifier into a combo (Ctrl+C)
+            // and breaks up a double-tap in progress
+            if self.pressed_at.is_some() {
+                self.interrupted = true;
+            }
+            self.last_tap = None;
+        }
+    }
+
+    /// Returns true when the configured gesture completes
+    pub fn key_up(&mut self, key: TapKey, now: Instant) -> bool {
+        if key != TapKey::Modifier(self.binding.modifier) {
+            return false;
+        }
+
+        let Some(pressed_at) = self.pressed_at.take() else {
+            return false;
+        };
+
+        let is_tap = !self.interrupted && now.duration_since(pressed_at) <= TAP_MAX_HOLD;
+        if !is_tap {
+            self.last_tap = None;
+            return false;
+        }
+
+        if self.binding.taps == 1 {
+            return true;
+        }
+
+        match self.last_tap {
+            Some(last) if now.duration_since(last) <= DOUBLE_TAP_WINDOW => {
+                self.last_tap = None;
+                true
+            }
+            _ => {
+                self.last_tap = Some(now);
+                false
+            }
+        }
+    }
+}
+
+#[cfg(feature = "modifier-taps")]
+pub mod listener {
+    use super::{TapDetector, TapKey, TapModifier};
+    use crate::input::HotkeyEvent;
+    use std::time::Instant;
+    use tokio::sync::mpsc;
+    use tracing::{debug, error};
+
+    fn map_key(key: rdev::Key) -> TapKey {
+        match key {
+            rdev::Key::ControlLeft | rdev::Key::ControlRight => TapKey::Modifier(TapModifier::Ctrl),
+            rdev::Key::ShiftLeft | rdev::Key::ShiftRight => TapKey::Modifier(TapModifier::Shift),
+            rdev::Key::Alt | rdev::Key::AltGr => TapKey::Modifier(TapModifier::Alt),
+            rdev::Key::MetaLeft | rdev::Key::MetaRight => TapKey::Modifier(TapModifier::Super),
+            _ => TapKey::Other,
+        }
+    }
+
+    /// Listen for low-level key events and emit synthetic hotkey events for taps.
+    /// Blocks the calling thread.
+    pub fn listen(mut taps: Vec<(u32, String, TapDetector)>, tx: mpsc::Sender<HotkeyEvent>) {
+        let result = rdev::listen(move |event| {
+            let now = Instant::now();
+            match event.event_type {
+                rdev::EventType::KeyPress(key) => {
+                    let key = map_key(key);
+                    for (_, _, detector) in taps.iter_mut() {
+                        detector.key_down(key, now);
+                    }
+                }
+                rdev::EventType::KeyRelease(key) => {
+                    let key = map_key(key);
+                    for (id, hotkey, detector) in taps.iter_mut() {
+                        if detector.key_up(key, now) {
+                            debug!("🔑 Modifier tap: {} (ID: {})", hotkey, id);
+                            for pressed in [true, false] {
+                                let event = HotkeyEvent { id: *id, hotkey: hotkey.clone(), pressed };
+                                if tx.blocking_send(event).is_err() {
+                                    error!("Failed to send hotkey event - receiver dropped");
+                                }
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        });
+
+        if let Err(e) = result {
+            error!("Modifier tap listener failed: {:?}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CTRL: TapKey = TapKey::Modifier(TapModifier::Ctrl);
+    const SHIFT: TapKey = TapKey::Modifier(TapModifier::Shift);
+
+    enum Step {
+        Down(TapKey, u64),
+        Up(TapKey, u64),
+    }
+    use Step::{Down, Up};
+
+    /// Feed (key, ms since start) events and return the times at which the gesture fired
+    fn run(binding: &str, steps: &[Step]) -> Vec<u64> {
+        let mut detector = TapDetector::new(TapBinding::parse(binding).unwrap());
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+        let mut fired = Vec::new();
+        for step in steps {
+            match *step {
+                Down(key, ms) => detector.key_down(key, at(ms)),
+                Up(key, ms) => {
+                    if detector.key_up(key, at(ms)) {
+                        fired.push(ms);
+                    }
+                }
+            }
+        }
+        fired
+    }
+
+    #[test]
+    fn parses_single_and_double_taps() {
+        assert_eq!(TapBinding::parse("ctrl"), Some(TapBinding { modifier: TapModifier::Ctrl, taps: 1 }));
+        assert_eq!(TapBinding::parse(" Double-Shift "), Some(TapBinding { modifier: TapModifier::Shift, taps: 2 }));
+        assert_eq!(TapBinding::parse("double-cmd"), Some(TapBinding { modifier: TapModifier::Super, taps: 2 }));
+        assert_eq!(TapBinding::parse("ctrl+c"), None);
+        assert_eq!(TapBinding::parse("double-a"), None);
+    }
+
+    #[test]
+    fn single_tap_fires_on_release() {
+        assert_eq!(run("ctrl", &[Down(CTRL, 0), Up(CTRL, 100)]), vec![100]);
+    }
+
+    #[test]
+    fn long_hold_is_not_a_tap() {
+        assert!(run("ctrl", &[Down(CTRL, 0), Up(CTRL, 301)]).is_empty());
+    }
+
+    #[test]
+    fn combo_is_not_a_tap() {
+        // Ctrl+C
+        let steps = [Down(CTRL, 0), Down(TapKey::Other, 50), Up(TapKey::Other, 80), Up(CTRL, 100)];
+        assert!(run("ctrl", &steps).is_empty());
+    }
+
+    #[test]
+    fn other_modifiers_break_the_tap_too() {
+        // Ctrl+Shift
+        let steps = [Down(CTRL, 0), Down(SHIFT, 30), Up(SHIFT, 60), Up(CTRL, 90)];
+        assert!(run("ctrl", &steps).is_empty());
+    }
+
+    #[test]
+    fn auto_repeat_keeps_the_first_press_time() {
+        let steps = [Down(CTRL, 0), Down(CTRL, 250), Down(CTRL, 280), Up(CTRL, 320)];
+        assert!(run("ctrl", &steps).is_empty());
+    }
+
+    #[test]
+    fn double_tap_fires_on_the_second_release() {
+        let steps = [Down(CTRL, 0), Up(CTRL, 80), Down(CTRL, 200), Up(CTRL, 280)];
+        assert_eq!(run("double-ctrl", &steps), vec![280]);
+    }
+
+    #[test]
+    fn double_tap_window_expires() {
+        let steps = [Down(CTRL, 0), Up(CTRL, 80), Down(CTRL, 450), Up(CTRL, 500)];
+        assert!(run("double-ctrl", &steps).is_empty());
+    }
+
+    #[test]
+    fn key_between_taps_breaks_the_double_tap() {
+        let steps = [
+            Down(CTRL, 0),
+            Up(CTRL, 80),
+            Down(TapKey::Other, 120),
+            Up(TapKey::Other, 140),
+            Down(CTRL, 200),
+            Up(CTRL, 280),
+        ];
+        assert!(run("double-ctrl", &steps).is_empty());
+    }
+
+    #[test]
+    fn combo_as_second_press_breaks_the_double_tap() {
+        let steps = [Down(CTRL, 0), Up(CTRL, 80), Down(CTRL, 200), Down(TapKey::Other, 220), Up(CTRL, 260)];
+        assert!(run("double-ctrl", &steps).is_empty());
+    }
+
+    #[test]
+    fn triple_tap_fires_once_then_starts_over() {
+        let steps = [
+            Down(CTRL, 0),
+            Up(CTRL, 50),
+            Down(CTRL, 100),
+            Up(CTRL, 150),
+            Down(CTRL, 200),
+            Up(CTRL, 250),
+            Down(CTRL, 300),
+            Up(CTRL, 350),
+        ];
+        assert_eq!(run("double-ctrl", &steps), vec![150, 350]);
+    }
+
+    #[test]
+    fn other_modifier_taps_are_ignored() {
+        let steps = [Down(SHIFT, 0), Up(SHIFT, 50), Down(SHIFT, 100), Up(SHIFT, 150)];
+        assert!(run("double-ctrl", &steps).is_empty());
+    }
+}
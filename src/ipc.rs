@@ -0,0 +1,55 @@
+use serde::Deserialize;
+use std::io::BufRead;
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+use crate::input::HotkeyAction;
+
+/// Commands the GUI sends as JSON lines on stdin, e.g.
+/// `{"command": "set_audio_device", "device": "USB Microphone"}`
+#[derive(Debug, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum IpcCommand {
+    /// Switch capture to another input device and remember it in config.toml
+    SetAudioDevice { device: String },
+    /// Same as the cancel hotkey
+    Cancel,
+    /// Bind an action to another key combination and remember it in config.toml,
+    /// e.g. `{"command": "rebind_hotkey", "action": "toggle_recording", "combination": "ctrl+alt+r"}`
+    RebindHotkey { action: HotkeyAction, combination: String },
+    /// Answered with a pipeline_state event
+    Status,
+    /// Start compose mode, as the compose hotkey does when it's off
+    ComposeStart,
+    /// End compose mode and output the draft, as the compose hotkey does when it's on
+    ComposeEnd,
+    /// Same as the compose_cancel hotkey: a second one in time discards the draft
+    ComposeCancel,
+    /// Re-decode the last recording as the retranscribe hotkey does, optionally with another
+    /// speech model (by name, as speech.model), beam width or language,
+    /// e.g. `{"command": "retranscribe", "model": "parakeet", "beam_size": 4}`
+    Retranscribe { model: Option<String>, beam_size: Option<u32>, language: Option<String> },
+}
+
+/// Read commands from stdin on a dedicated thread (stdin reads block)
+pub fn spawn_stdin_reader() -> mpsc::Receiver<IpcCommand> {
+    let (tx, rx) = mpsc::channel(16);
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<IpcCommand>(&line) {
+                Ok(command) => {
+                    debug!("IPC command: {:?}", command);
+                    if tx.blocking_send(command).is_err() {
+                        break;
+                    }
+                }
+                Err(e) => warn!("Ignoring invalid IPC command {:?}: {}", line, e),
+            }
+        }
+    });
+    rx
+}
@@ -0,0 +1,113 @@
+use anyhow::Result;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tracing::info;
+
+use crate::audio::{AudioCapture, AudioChunk, VadResult, VoiceActivityDetector, AUDIO_CHANNEL_CHUNKS, TARGET_RATE};
+use crate::config::Config;
+
+const METER_INTERVAL: Duration = Duration::from_millis(100);
+const METER_WIDTH: usize = 40;
+/// RMS that fills the meter; speech at normal distance is usually well below
+const METER_FULL_RMS: f32 = 0.3;
+
+/// Run capture and VAD only, printing a live meter until Ctrl+C
+pub async fn run(config: &Config) -> Result<()> {
+    let mut capture = AudioCapture::with_device(config.audio.device.as_deref())?;
+    capture.set_buffer_duration_ms(config.audio.buffer_duration_ms);
+    config.audio.check_channel_mode(&capture.device_name(), capture.channels())?;
+    capture.set_channel_mode(config.audio.channel_mode);
+    let mut vad = VoiceActivityDetector::new(
+        &config.vad.model_path,
+        TARGET_RATE,
+        config.vad.sensitivity.to_threshold(),
+        config.vad.timeout_ms,
+        config.vad.min_speech_ms,
+        config.vad.hangover_ms,
+        config.vad.engine,
+        config.vad.frame_ms,
+    )?;
+
+    let (audio_tx, mut audio_rx) = mpsc::channel::<AudioChunk>(AUDIO_CHANNEL_CHUNKS);
+    capture.start_capture(audio_tx).await?;
+
+    if let Some(negotiation) = capture.buffer_negotiation() {
+        info!("Buffer: requested {:?} frames, using {}", negotiation.requested_frames, negotiation.used);
+    }
+    info!(
+        "🎧 Listening (sensitivity {:?}, timeout {}ms) - press Ctrl+C to stop",
+        config.vad.sensitivity, config.vad.timeout_ms
+    );
+
+    let started = Instant::now();
+    let mut meter = tokio::time::interval(METER_INTERVAL);
+    let mut window_sum = 0.0f32;
+    let mut window_samples = 0usize;
+    let mut speech_samples = 0usize;
+    let mut total_samples = 0usize;
+    let mut speaking = false;
+    let mut auto_stops = 0u32;
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+
+    loop {
+        tokio::select! {
+            Some(chunk) = audio_rx.recv() => {
+                window_sum += chunk.iter().map(|s| s * s).sum::<f32>();
+                window_samples += chunk.len();
+                total_samples += chunk.len();
+
+                match vad.process_audio(&chunk) {
+                    VadResult::SpeechDetected => speaking = true,
+                    // The point where a real recording would auto-stop
+                    VadResult::SilenceDetected => {
+                        speaking = false;
+                        auto_stops += 1;
+                    }
+                    VadResult::Silence => {}
+                }
+                if speaking {
+                    speech_samples += chunk.len();
+                }
+            }
+
+            _ = meter.tick() => {
+                let rms = if window_samples > 0 { (window_sum / window_samples as f32).sqrt() } else { 0.0 };
+                window_sum = 0.0;
+                window_samples = 0;
+
+                let filled = ((rms / METER_FULL_RMS).min(1.0) * METER_WIDTH as f32) as usize;
+                let since_speech = vad
+                    .since_last_speech()
+                    .map(|elapsed| format!("{:>6}ms", elapsed.as_millis()))
+                    .unwrap_or_else(|| "       -".to_string());
+                eprint!(
+                    "\r[{}{}] {:.3} {} since speech {}",
+                    "#".repeat(filled),
+                    " ".repeat(METER_WIDTH - filled),
+                    rms,
+                    if speaking { "SPEECH " } else { "silence" },
+                    since_speech,
+                );
+            }
+
+            _ = &mut ctrl_c => break,
+        }
+    }
+    eprintln!();
+    capture.stop_capture();
+
+    let speech_ratio = if total_samples > 0 { speech_samples as f32 / total_samples as f32 } else { 0.0 };
+    info!("📊 Listened for {:.1}s", started.elapsed().as_secs_f32());
+    info!(
+        "   Speech: {:.1}s ({:.0}%), silence: {:.1}s ({:.0}%)",
+        speech_samples as f32 / TARGET_RATE as f32,
+        speech_ratio * 100.0,
+        (total_samples - speech_samples) as f32 / TARGET_RATE as f32,
+        (1.0 - speech_ratio) * 100.0,
+    );
+    info!("   Auto-stop would have triggered {} time(s)", auto_stops);
+    info!("   Driver callback size: {} frames", capture.callback_frames());
+
+    Ok(())
+}
@@ -1,17 +1,60 @@
 mod audio;
+mod benchmark;
 mod speech;
 mod input;
 mod config;
+mod config_reload;
 mod app;
+mod cancel;
 mod text_refinement;
+mod indicator;
+mod compose;
+mod endpoint;
+mod output;
+mod error;
+mod retained;
+mod segments;
+mod paths;
+mod history;
+mod redact;
+mod bundle;
+mod events;
+mod privacy;
+mod push;
+mod gui_writer;
+mod window;
+mod config_doc;
+mod preset;
+mod download;
+mod correction;
+mod decode_queue;
+mod numbers;
+mod spelling;
+mod text_diff;
+mod vocabulary;
+mod listen;
+mod soak;
+mod spill;
+mod form_fill;
+mod ipc;
+mod pipeline_state;
+mod partials;
+mod supervisor;
+mod replay;
+mod transcribe;
+mod systemd;
+#[cfg(test)]
+mod test_server;
 
 use anyhow::Result;
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
 use tracing::{info, error};
-use tracing_subscriber::{self, EnvFilter};
+use tracing_subscriber::fmt::{self, writer::BoxMakeWriter};
+use tracing_subscriber::{self, prelude::*, EnvFilter};
 
 use crate::app::TomChatApp;
-use crate::config::Config;
+use crate::config::{Config, VadSensitivity};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -23,22 +66,222 @@ struct Args {
     /// Enable test mode - automatically triggers recording cycle for testing
     #[arg(long)]
     test_mode: bool,
+
+    /// Don't grab global hotkeys, for a GUI that handles its own shortcuts
+    #[arg(long)]
+    no_hotkeys: bool,
+
+    /// Log transcriptions instead of typing them (same as text.dry_run)
+    #[arg(long)]
+    dry_run: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Bundle the last dictation (transcript, audio, config, timings, log lines) into a zip for bug reports
+    ExportLast {
+        /// Output zip path
+        #[arg(long, default_value = "tomchat-bug.zip")]
+        out: PathBuf,
+
+        /// Run transcript text through the redaction patterns
+        #[arg(long)]
+        redact: bool,
+    },
+
+    /// Print the full default configuration as commented TOML
+    PrintDefaultConfig,
+
+    /// Show what the running instance is doing: queued jobs, the active stage, the model
+    Status,
+
+    /// Run only audio capture and VAD with a live meter, for tuning [vad] settings
+    Listen {
+        /// Override vad.sensitivity
+        #[arg(long, value_enum)]
+        sensitivity: Option<VadSensitivity>,
+
+        /// Override vad.timeout_ms
+        #[arg(long)]
+        timeout_ms: Option<u32>,
+
+        /// Override audio.device (substring of the input device name)
+        #[arg(long)]
+        device: Option<String>,
+    },
+
+    /// Churn recordings and model reloads while checking RSS and fd counts for leaks
+    #[command(hide = true)]
+    Soak {
+        #[arg(long, default_value_t = 1000)]
+        cycles: usize,
+
+        /// Sample resources every N cycles
+        #[arg(long, default_value_t = 25)]
+        sample_every: usize,
+
+        /// Reload the speech model every N cycles (alternates with retranscribe.model_dir if set); 0 = never
+        #[arg(long, default_value_t = 100)]
+        model_swap_every: usize,
+
+        /// Re-read config.toml every N cycles; 0 = never
+        #[arg(long, default_value_t = 50)]
+        config_reload_every: usize,
+
+        /// Drop and reconnect live audio capture every N cycles; 0 = never (no input device needed)
+        #[arg(long, default_value_t = 100)]
+        device_loss_every: usize,
+    },
+
+    /// Re-run an export-last bundle's audio with its settings and diff the result against its transcript
+    Replay {
+        /// Bundle zip from export-last
+        bundle: PathBuf,
+    },
+
+    /// Transcribe a WAV file with the configured model and print the text
+    Transcribe {
+        /// WAV file, any rate and channel count
+        file: PathBuf,
+
+        /// Print JSON with sentences, word timings and durations instead of plain text
+        #[arg(long)]
+        json: bool,
+
+        /// Write to this file instead of stdout
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Time model load and repeated decodes, and report the real-time factor
+    Benchmark {
+        /// Length of the synthetic sample
+        #[arg(long, default_value_t = 10.0)]
+        seconds: f32,
+
+        /// Timed decodes after the warmup one
+        #[arg(long, default_value_t = 5)]
+        passes: usize,
+
+        /// Decode this WAV file instead of the synthetic sample
+        #[arg(long)]
+        file: Option<PathBuf>,
+
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Transcribe (or delete) long recordings left on disk by a crash
+    Recover {
+        /// Delete the orphaned recordings instead of transcribing them
+        #[arg(long)]
+        delete: bool,
+    },
+
+    /// Manage saved dictations
+    History {
+        #[command(subcommand)]
+        action: HistoryCommand,
+    },
+
+    /// Download a model, resuming any interrupted download
+    DownloadModel {
+        /// Model to fetch (parakeet, silero-vad); omit to list them
+        model: Option<String>,
+
+        /// Target directory
+        #[arg(long, default_value = "./models")]
+        dir: PathBuf,
+
+        /// Parallel range requests, for fast links
+        #[arg(long, default_value_t = 1)]
+        connections: usize,
+
+        /// Expected SHA256, overriding the pinned one (e.g. for mirrors)
+        #[arg(long)]
+        sha256: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum HistoryCommand {
+    /// Delete saved dictations
+    Clear {
+        /// Only delete the session state used by repeat_last
+        #[arg(long)]
+        session_state: bool,
+    },
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = Args::parse();
+
+    // Needs no config file, and nothing else may write to stdout
+    if let Some(Command::PrintDefaultConfig) = args.command {
+        print!("{}", config_doc::render_default_config()?);
+        return Ok(());
+    }
+    if let Some(Command::Status) = args.command {
+        return pipeline_state::print_status();
+    }
     
     // Initialize logging - in GUI mode, suppress normal logs to avoid interfering with JSON output
-    if args.gui_mode {
-        tracing_subscriber::fmt()
-            .with_env_filter(EnvFilter::new("error"))
-            .with_writer(std::io::stderr)
-            .init();
+    let console_filter = if args.gui_mode { "error" } else { "tomchat=info,warn,error" };
+    // stdout carries the GUI events, transcript or report
+    let console_writer = if args.gui_mode
+        || matches!(args.command, Some(Command::Transcribe { .. } | Command::Benchmark { .. }))
+    {
+        BoxMakeWriter::new(std::io::stderr)
     } else {
-        tracing_subscriber::fmt()
-            .with_env_filter(EnvFilter::new("tomchat=info,warn,error"))
-            .init();
+        BoxMakeWriter::new(std::io::stdout)
+    };
+    let console = fmt::layer()
+        .with_writer(console_writer)
+        .with_filter(EnvFilter::new(console_filter));
+    // The daemon also keeps a session log for `tomchat export-last`
+    let session_log = args.command.is_none().then(session_log_layer).flatten();
+    tracing_subscriber::registry().with(console).with(session_log).init();
+
+    if let Some(Command::History { action: HistoryCommand::Clear { session_state } }) = args.command {
+        if history::clear_session_state()? {
+            info!("🗑️  Removed {:?}", history::session_state_path());
+        }
+        if !session_state && history::clear_last()? {
+            info!("🗑️  Removed last dictation");
+        }
+        return Ok(());
+    }
+
+    // Downloads come before config load: the models usually don't exist yet
+    if let Some(Command::DownloadModel { model, dir, connections, sha256 }) = args.command {
+        // The config is optional here, but local-only mode must still be honored:
+        // a config that exists but won't load still gets its privacy setting read
+        if let Err(load_error) = Config::load() {
+            match std::fs::read_to_string(std::env::current_dir()?.join("config.toml")) {
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(anyhow::anyhow!("Can't read config.toml to check privacy.local_only: {}", e)),
+                Ok(text) => match privacy::local_only_setting(&text) {
+                    Some(local_only) => privacy::set_local_only(local_only),
+                    None => {
+                        return Err(anyhow::anyhow!(
+                            "config.toml doesn't load ({:#}), so privacy.local_only can't be checked; fix it before downloading",
+                            load_error
+                        ))
+                    }
+                },
+            }
+        }
+        return download_model(model, dir, connections, sha256, args.gui_mode).await;
+    }
+
+    // Runs on the bundle's config; a local one only supplies default paths
+    if let Some(Command::Replay { bundle }) = args.command {
+        return replay::run(&bundle).await;
     }
 
     // Print banner
@@ -48,9 +291,12 @@ async fn main() -> Result<()> {
     info!("   =====================================");
 
     // Load configuration
-    let config = match Config::load() {
+    let mut config = match Config::load() {
         Ok(config) => {
             info!("✅ Configuration loaded successfully");
+            if config.privacy.local_only {
+                info!("🔒 Local-only mode: all network calls off this machine are disabled");
+            }
             config
         }
         Err(e) => {
@@ -60,11 +306,43 @@ async fn main() -> Result<()> {
         }
     };
 
+    if let Some(command) = args.command {
+        return match command {
+            Command::ExportLast { out, redact } => bundle::export_last(&config, &out, redact),
+            Command::Listen { sensitivity, timeout_ms, device } => {
+                if let Some(sensitivity) = sensitivity {
+                    config.vad.sensitivity = sensitivity;
+                }
+                if let Some(timeout_ms) = timeout_ms {
+                    config.vad.timeout_ms = timeout_ms;
+                }
+                if device.is_some() {
+                    config.audio.device = device;
+                }
+                listen::run(&config).await
+            }
+            Command::Soak { cycles, sample_every, model_swap_every, config_reload_every, device_loss_every } => {
+                let options = soak::SoakOptions { cycles, sample_every, model_swap_every, config_reload_every, device_loss_every };
+                soak::run(&config, &options).await
+            }
+            Command::Recover { delete } => spill::recover(&config, delete).await,
+            Command::Transcribe { file, json, output } => transcribe::run(&config, &file, json, output.as_deref()).await,
+            Command::Benchmark { seconds, passes, file, json } => benchmark::run(&config, seconds, passes, file.as_deref(), json).await,
+            Command::PrintDefaultConfig | Command::Status | Command::DownloadModel { .. } | Command::History { .. } | Command::Replay { .. } => {
+                unreachable!("handled before config load")
+            }
+        };
+    }
+
+    if args.dry_run {
+        config.text.dry_run = true;
+    }
+
     // Initialize and run the application
-    match TomChatApp::new(config).await {
+    match TomChatApp::new(config, args.gui_mode).await {
         Ok(mut app) => {
-            app.set_gui_mode(args.gui_mode);
             app.set_test_mode(args.test_mode);
+            app.set_hotkeys_enabled(!args.no_hotkeys);
             info!("🚀 Starting TomChat...");
             
             // Set up graceful shutdown
@@ -91,3 +369,72 @@ async fn main() -> Result<()> {
     info!("👋 TomChat goodbye!");
     Ok(())
 }
+
+/// Writes the daemon's log to paths::log_path(), replacing the previous session's
+fn session_log_layer<S>() -> Option<impl tracing_subscriber::Layer<S>>
+where
+    S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+{
+    let path = paths::log_path();
+    let file = std::fs::create_dir_all(paths::state_dir()).and_then(|_| std::fs::File::create(&path));
+    match file {
+        Ok(file) => Some(
+            fmt::layer()
+                .with_ansi(false)
+                .with_writer(std::sync::Mutex::new(file))
+                .with_filter(EnvFilter::new("tomchat=debug,warn,error")),
+        ),
+        Err(e) => {
+            eprintln!("Could not open session log {:?}: {}", path, e);
+            None
+        }
+    }
+}
+
+async fn download_model(
+    model: Option<String>,
+    dir: PathBuf,
+    connections: usize,
+    sha256: Option<String>,
+    gui_mode: bool,
+) -> Result<()> {
+    let Some(model) = model else {
+        println!("Available models:");
+        for spec in download::MODELS {
+            println!("  {:<12} {}", spec.name, spec.description);
+        }
+        return Ok(());
+    };
+
+    let spec = download::find_model(&model)
+        .ok_or_else(|| anyhow::anyhow!("Unknown model '{}' - run download-model without arguments to list models", model))?;
+    let options = download::DownloadOptions {
+        dir,
+        connections: connections.max(1),
+        sha256,
+    };
+
+    // Progress as JSON lines for the GUI, a single updating line on a terminal
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<download::DownloadProgress>();
+    let renderer = tokio::spawn(async move {
+        while let Some(progress) = rx.recv().await {
+            if gui_mode {
+                println!("{}", gui_writer::event_line("download_progress", serde_json::json!({ "data": progress })));
+            } else {
+                let mb = progress.bytes as f64 / 1_048_576.0;
+                match (progress.percent, progress.eta_secs) {
+                    (Some(percent), Some(eta)) => eprint!("\r   {:5.1}%  {:8.1} MB  ETA {:>4}s ", percent, mb, eta),
+                    (Some(percent), None) => eprint!("\r   {:5.1}%  {:8.1} MB ", percent, mb),
+                    _ => eprint!("\r   {:8.1} MB ", mb),
+                }
+            }
+        }
+        if !gui_mode {
+            eprintln!();
+        }
+    });
+
+    let result = download::download_model(spec, &options, Some(tx)).await;
+    let _ = renderer.await;
+    result.map(|_| ())
+}
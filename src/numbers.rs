@@ -0,0 +1,571 @@
+use regex::{Captures, Match, Regex};
+
+/// How a locale writes numbers, money and units
+struct LocaleRules {
+    decimal: char,
+    group: char,
+    /// "€5" vs "5 €"
+    currency_first: bool,
+    currency_space: bool,
+    percent_space: bool,
+    /// Spoken currency word -> symbol (English words are always recognized too)
+    currencies: &'static [(&'static str, &'static str)],
+    /// Spoken unit word -> abbreviation (English words are always recognized too)
+    units: &'static [(&'static str, &'static str)],
+    /// Number words to read spoken numbers; None leaves them as words
+    words: Option<&'static NumberWords>,
+}
+
+/// The words a language counts with, enough for "twenty five" or "dreiundvierzig"
+struct NumberWords {
+    /// Single words below 100 that take no part of another: zero to nineteen
+    small: &'static [(&'static str, u64)],
+    tens: &'static [(&'static str, u64)],
+    hundred: &'static str,
+    thousands: &'static [(&'static str, u64)],
+    /// Between the integer and its decimals: "point", "komma"
+    decimal: &'static str,
+    /// "one hundred and five", "fünfundzwanzig"
+    joiner: &'static str,
+}
+
+const EN_WORDS: NumberWords = NumberWords {
+    small: &[
+        ("zero", 0), ("one", 1), ("two", 2), ("three", 3), ("four", 4), ("five", 5), ("six", 6), ("seven", 7),
+        ("eight", 8), ("nine", 9), ("ten", 10), ("eleven", 11), ("twelve", 12), ("thirteen", 13), ("fourteen", 14),
+        ("fifteen", 15), ("sixteen", 16), ("seventeen", 17), ("eighteen", 18), ("nineteen", 19),
+    ],
+    tens: &[
+        ("twenty", 20), ("thirty", 30), ("forty", 40), ("fifty", 50), ("sixty", 60), ("seventy", 70), ("eighty", 80),
+        ("ninety", 90),
+    ],
+    hundred: "hundred",
+    thousands: &[("thousand", 1_000), ("million", 1_000_000), ("billion", 1_000_000_000)],
+    decimal: "point",
+    joiner: "and",
+};
+
+const DE_WORDS: NumberWords = NumberWords {
+    small: &[
+        ("null", 0), ("eins", 1), ("ein", 1), ("eine", 1), ("zwei", 2), ("zwo", 2), ("drei", 3), ("vier", 4),
+        ("fünf", 5), ("sechs", 6), ("sieben", 7), ("acht", 8), ("neun", 9), ("zehn", 10), ("elf", 11),
+        ("zwölf", 12), ("dreizehn", 13), ("vierzehn", 14), ("fünfzehn", 15), ("sechzehn", 16), ("siebzehn", 17),
+        ("achtzehn", 18), ("neunzehn", 19),
+    ],
+    tens: &[
+        ("zwanzig", 20), ("dreißig", 30), ("vierzig", 40), ("fünfzig", 50), ("sechzig", 60), ("siebzig", 70),
+        ("achtzig", 80), ("neunzig", 90),
+    ],
+    hundred: "hundert",
+    thousands: &[
+        ("tausend", 1_000), ("million", 1_000_000), ("millionen", 1_000_000), ("milliarde", 1_000_000_000),
+        ("milliarden", 1_000_000_000),
+    ],
+    decimal: "komma",
+    joiner: "und",
+};
+
+const EN_CURRENCIES: &[(&str, &str)] = &[
+    ("euros", "€"), ("euro", "€"), ("dollars", "$"), ("dollar", "$"), ("pounds", "£"), ("pound", "£"),
+];
+const EN_UNITS: &[(&str, &str)] = &[
+    ("kilometers", "km"), ("kilometres", "km"), ("kilometer", "km"), ("kilometre", "km"),
+    ("kilograms", "kg"), ("kilogram", "kg"), ("centimeters", "cm"), ("centimetres", "cm"),
+    ("centimeter", "cm"), ("centimetre", "cm"), ("percent", "%"), ("per cent", "%"),
+];
+
+const EN: LocaleRules = LocaleRules {
+    decimal: '.',
+    group: ',',
+    currency_first: true,
+    currency_space: false,
+    percent_space: false,
+    currencies: &[],
+    units: &[],
+    words: Some(&EN_WORDS),
+};
+
+const DE: LocaleRules = LocaleRules {
+    decimal: ',',
+    group: '.',
+    currency_first: false,
+    currency_space: true,
+    percent_space: true,
+    currencies: &[("pfund", "£")],
+    units: &[("kilogramm", "kg"), ("zentimeter", "cm"), ("prozent", "%")],
+    words: Some(&DE_WORDS),
+};
+
+const FR: LocaleRules = LocaleRules {
+    decimal: ',',
+    // Narrow no-break space, as French typography uses
+    group: '\u{202F}',
+    currency_first: false,
+    currency_space: true,
+    percent_space: true,
+    currencies: &[("livres", "£"), ("livre", "£")],
+    units: &[("kilogrammes", "kg"), ("kilogramme", "kg"), ("centimètres", "cm"), ("centimètre", "cm"), ("pour cent", "%")],
+    words: None,
+};
+
+const ES: LocaleRules = LocaleRules {
+    decimal: ',',
+    group: '.',
+    currency_first: false,
+    currency_space: true,
+    percent_space: true,
+    currencies: &[("dólares", "$"), ("dolares", "$"), ("dólar", "$"), ("dolar", "$"), ("libras", "£"), ("libra", "£")],
+    units: &[("kilómetros", "km"), ("kilómetro", "km"), ("kilogramos", "kg"), ("kilogramo", "kg"), ("centímetros", "cm"), ("por ciento", "%")],
+    words: None,
+};
+
+const IT: LocaleRules = LocaleRules {
+    decimal: ',',
+    group: '.',
+    currency_first: false,
+    currency_space: true,
+    percent_space: false,
+    currencies: &[("dollari", "$"), ("dollaro", "$"), ("sterline", "£"), ("sterlina", "£")],
+    units: &[("chilometri", "km"), ("chilometro", "km"), ("chilogrammi", "kg"), ("centimetri", "cm"), ("per cento", "%")],
+    words: None,
+};
+
+const NL: LocaleRules = LocaleRules {
+    decimal: ',',
+    group: '.',
+    currency_first: true,
+    currency_space: true,
+    percent_space: false,
+    currencies: &[("pond", "£")],
+    units: &[("procent", "%")],
+    words: None,
+};
+
+/// Unsupported locales get the English rules
+fn rules_for(locale: &str) -> &'static LocaleRules {
+    // "de-AT" and "de_CH" share the base language's rules
+    let language = locale.split(['-', '_']).next().unwrap_or("").to_lowercase();
+    match language.as_str() {
+        "de" => &DE,
+        "fr" => &FR,
+        "es" => &ES,
+        "it" => &IT,
+        "nl" => &NL,
+        _ => &EN,
+    }
+}
+
+/// Digit runs that start and end with a digit, so sentence-final periods stay out
+const NUMBER: &str = r"\d[\d.,\u{202F}]*\d|\d";
+
+/// The integer spoken in the words starting at `words[0]`: its value, how many words
+/// it takes, and whether it's a single word below ten
+fn read_integer(spoken: &NumberWords, text: &str, words: &[Match]) -> Option<(u64, usize, bool)> {
+    let mut number = SpokenNumber::default();
+    let mut used = 0;
+    let mut lone_digit = false;
+    for (index, word) in words.iter().enumerate() {
+        // Only whitespace between the words of one number
+        if index > 0 && !text[words[index - 1].end()..word.start()].trim().is_empty() {
+            break;
+        }
+        let Some(parts) = spoken.parts(&word.as_str().to_lowercase()) else { break };
+        // A number starts with a digit word, unless it's a compound like "hundertzwanzig"
+        if index == 0 && parts.len() == 1 && !matches!(parts[0], Part::Value(_)) {
+            return None;
+        }
+        let before = number.clone();
+        if !parts.iter().all(|part| number.push(*part)) {
+            number = before;
+            break;
+        }
+        if number.complete == number.taken {
+            used = index + 1;
+            lone_digit = index == 0 && parts.len() == 1 && number.value() < 10;
+        }
+    }
+    // A trailing "and" isn't counted in `used`, so it stays a word
+    (used > 0).then(|| (number.value(), used, lone_digit))
+}
+
+/// One piece of a spoken number
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Part {
+    Value(u64),
+    Hundred,
+    Thousands(u64),
+    Joiner,
+}
+
+impl NumberWords {
+    fn part(&self, word: &str) -> Option<Part> {
+        self.small
+            .iter()
+            .chain(self.tens)
+            .find(|(spoken, _)| *spoken == word)
+            .map(|(_, value)| Part::Value(*value))
+            .or_else(|| (word == self.hundred).then_some(Part::Hundred))
+            .or_else(|| self.thousands.iter().find(|(spoken, _)| *spoken == word).map(|(_, scale)| Part::Thousands(*scale)))
+            .or_else(|| (word == self.joiner).then_some(Part::Joiner))
+    }
+
+    /// A word as number parts: "twenty-five", or German compounds like
+    /// "zweihundertdreiundvierzig", longest pieces first
+    fn parts(&self, word: &str) -> Option<Vec<Part>> {
+        let mut parts = Vec::new();
+        for piece in word.split('-') {
+            if !self.split_compound(piece, &mut parts) {
+                return None;
+            }
+        }
+        Some(parts)
+    }
+
+    fn split_compound(&self, rest: &str, parts: &mut Vec<Part>) -> bool {
+        if rest.is_empty() {
+            return true;
+        }
+        let mut ends: Vec<usize> = rest.char_indices().map(|(i, c)| i + c.len_utf8()).collect();
+        ends.reverse();
+        for end in ends {
+            if let Some(part) = self.part(&rest[..end]) {
+                parts.push(part);
+                if self.split_compound(&rest[end..], parts) {
+                    return true;
+                }
+                parts.pop();
+            }
+        }
+        false
+    }
+}
+
+/// Adds up parts as long as they read as one number; stops at the first that doesn't
+#[derive(Debug, Clone, Default)]
+struct SpokenNumber {
+    total: u64,
+    current: u64,
+    /// Scale of the last thousand/million, which the next one must be below
+    last_scale: Option<u64>,
+    joined: bool,
+    /// Parts taken, and how many were in the number so far when it was last complete
+    taken: usize,
+    complete: usize,
+}
+
+impl SpokenNumber {
+    fn value(&self) -> u64 {
+        self.total + self.current
+    }
+
+    /// Take `part` if it continues the number
+    fn push(&mut self, part: Part) -> bool {
+        let below_hundred = self.current % 100;
+        let fits = match part {
+            Part::Value(value) => {
+                let fits = match below_hundred {
+                    // Start, or after a hundred or thousand: "(one hundred and) five"
+                    0 => self.taken == 0 || self.current > 0 || self.total > 0,
+                    // "twenty five"
+                    20..=90 if below_hundred.is_multiple_of(10) => value < 10 && !self.joined,
+                    // "fünf und zwanzig"
+                    1..=9 => self.joined && value >= 20 && value % 10 == 0,
+                    _ => false,
+                };
+                if fits {
+                    self.current += value;
+                }
+                fits
+            }
+            // Only after a single digit ("five hundred") or at the start of a compound
+            Part::Hundred if self.current < 10 && !self.joined => {
+                self.current = self.current.max(1) * 100;
+                true
+            }
+            Part::Thousands(scale) if self.last_scale.is_none_or(|last| scale < last) && !self.joined => {
+                self.total += self.current.max(1) * scale;
+                self.current = 0;
+                self.last_scale = Some(scale);
+                true
+            }
+            // "hundred and five" or "fünfund-"; the number isn't complete until what follows
+            Part::Joiner if !self.joined && self.value() > 0 && (below_hundred == 0 || below_hundred < 10) => {
+                self.joined = true;
+                self.taken += 1;
+                return true;
+            }
+            _ => false,
+        };
+        if fits {
+            self.joined = false;
+            self.taken += 1;
+            self.complete = self.taken;
+        }
+        fits
+    }
+}
+
+/// Reformats the English-style numbers speech models emit ("3.5", "1,000", "5 euros")
+/// for the typing locale
+pub struct NumberNormalizer {
+    rules: &'static LocaleRules,
+    number: Regex,
+    english_grouped: Regex,
+    english_decimal: Regex,
+    /// Already grouped the locale's way, like German "1.000"
+    locale_grouped: Regex,
+    currency: Regex,
+    unit: Regex,
+    word: Regex,
+    /// First words of the currencies and units, after which "five" becomes "5"
+    quantity_words: Vec<String>,
+}
+
+impl NumberNormalizer {
+    pub fn new(locale: &str) -> Self {
+        let rules = rules_for(locale);
+
+        // Longest alternatives first so "euros" wins over "euro"
+        let alternatives = |extra: &[(&str, &str)], english: &[(&str, &str)]| {
+            let mut words: Vec<&str> = extra.iter().chain(english).map(|(word, _)| *word).collect();
+            words.sort_by_key(|word| std::cmp::Reverse(word.len()));
+            words.iter().map(|word| regex::escape(word)).collect::<Vec<_>>().join("|")
+        };
+
+        Self {
+            rules,
+            number: Regex::new(&format!(r"\b(?:{})\b", NUMBER)).unwrap(),
+            english_grouped: Regex::new(r"^\d{1,3}(?:,\d{3})+(?:\.\d+)?$").unwrap(),
+            english_decimal: Regex::new(r"^\d+(?:\.\d+)?$").unwrap(),
+            locale_grouped: Regex::new(&format!(
+                r"^\d{{1,3}}(?:{}\d{{3}})+(?:{}\d+)?$",
+                regex::escape(&rules.group.to_string()),
+                regex::escape(&rules.decimal.to_string())
+            ))
+            .unwrap(),
+            currency: Regex::new(&format!(
+                r"(?i)\b({})\s+({})\b",
+                NUMBER,
+                alternatives(rules.currencies, EN_CURRENCIES)
+            ))
+            .unwrap(),
+            unit: Regex::new(&format!(r"(?i)\b({})\s+({})\b", NUMBER, alternatives(rules.units, EN_UNITS))).unwrap(),
+            word: Regex::new(r"\p{L}+(?:-\p{L}+)*").unwrap(),
+            quantity_words: [rules.currencies, EN_CURRENCIES, rules.units, EN_UNITS]
+                .iter()
+                .flat_map(|words| words.iter())
+                .filter_map(|(spoken, _)| spoken.split_whitespace().next().map(str::to_string))
+                .collect(),
+        }
+    }
+
+    /// "1,234.5" -> "1.234,5" for German; anything not clearly English-formatted, or
+    /// already formatted for the locale, is left alone
+    fn format_number(&self, number: &str) -> String {
+        if self.locale_grouped.is_match(number) {
+            return number.to_string();
+        }
+        let grouped = self.english_grouped.is_match(number);
+        if !grouped && !self.english_decimal.is_match(number) {
+            return number.to_string();
+        }
+
+        let (integer, fraction) = match number.split_once('.') {
+            Some((integer, fraction)) => (integer.replace(',', ""), Some(fraction)),
+            None => (number.replace(',', ""), None),
+        };
+
+        let mut out = String::new();
+        for (i, digit) in integer.chars().enumerate() {
+            // Keep grouping only where the model grouped
+            if grouped && i > 0 && (integer.len() - i) % 3 == 0 {
+                out.push(self.rules.group);
+            }
+            out.push(digit);
+        }
+        if let Some(fraction) = fraction {
+            out.push(self.rules.decimal);
+            out.push_str(fraction);
+        }
+        out
+    }
+
+    fn lookup(&self, word: &str, own: &'static [(&'static str, &'static str)], english: &'static [(&'static str, &'static str)]) -> &'static str {
+        let word = word.to_lowercase();
+        own.iter()
+            .chain(english)
+            .find(|(spoken, _)| *spoken == word)
+            .map(|(_, symbol)| *symbol)
+            .unwrap_or("")
+    }
+
+    /// The spoken number starting at `words[0]`, and how many words it takes. A lone
+    /// word below ten ("one of them") stays a word unless money or a unit follows.
+    fn read_number(&self, spoken: &NumberWords, text: &str, words: &[Match]) -> Option<(String, usize)> {
+        let (integer, used, lone_digit) = read_integer(spoken, text, words)?;
+
+        let next = |index: usize| {
+            words.get(index).filter(|word| text[words[index - 1].end()..word.start()].trim().is_empty())
+        };
+        let mut number = integer.to_string();
+        let mut used = used;
+        if next(used).is_some_and(|word| word.as_str().to_lowercase() == spoken.decimal) {
+            let after = used + 1;
+            // "three point one four" digit by digit, or "drei komma fünfundzwanzig"
+            let mut digits = String::new();
+            let mut taken = 0;
+            while let Some(Part::Value(digit @ 0..=9)) = next(after + taken)
+                .and_then(|word| spoken.parts(&word.as_str().to_lowercase()))
+                .filter(|parts| parts.len() == 1)
+                .map(|parts| parts[0])
+            {
+                digits.push_str(&digit.to_string());
+                taken += 1;
+            }
+            if taken < 2 && next(after).is_some() {
+                if let Some((fraction, fraction_used, _)) = read_integer(spoken, text, &words[after..]) {
+                    digits = fraction.to_string();
+                    taken = fraction_used;
+                }
+            }
+            if taken > 0 {
+                number.push(self.rules.decimal);
+                number.push_str(&digits);
+                used = after + taken;
+                return Some((number, used));
+            }
+        }
+
+        if lone_digit {
+            let quantity = next(used).is_some_and(|word| self.quantity_words.contains(&word.as_str().to_lowercase()));
+            if !quantity {
+                return None;
+            }
+        }
+        Some((number, used))
+    }
+
+    /// "twenty five" -> "25", "drei komma fünf" -> "3,5"
+    fn spoken_numbers(&self, spoken: &NumberWords, text: &str) -> String {
+        let words: Vec<Match> = self.word.find_iter(text).collect();
+        let mut out = String::with_capacity(text.len());
+        let mut copied = 0;
+        let mut index = 0;
+        while index < words.len() {
+            match self.read_number(spoken, text, &words[index..]) {
+                Some((number, used)) => {
+                    out.push_str(&text[copied..words[index].start()]);
+                    out.push_str(&number);
+                    copied = words[index + used - 1].end();
+                    index += used;
+                }
+                None => index += 1,
+            }
+        }
+        out.push_str(&text[copied..]);
+        out
+    }
+
+    pub fn normalize(&self, text: &str) -> String {
+        let text = self.number.replace_all(text, |caps: &Captures| self.format_number(&caps[0]));
+        // Spoken numbers come out in the locale's format already, so after reformatting
+        let text = match self.rules.words {
+            Some(spoken) => self.spoken_numbers(spoken, &text),
+            None => text.into_owned(),
+        };
+
+        let rules = self.rules;
+        let text = self.currency.replace_all(&text, |caps: &Captures| {
+            let symbol = self.lookup(&caps[2], rules.currencies, EN_CURRENCIES);
+            let space = if rules.currency_space { " " } else { "" };
+            if rules.currency_first {
+                format!("{}{}{}", symbol, space, &caps[1])
+            } else {
+                format!("{}{}{}", &caps[1], space, symbol)
+            }
+        });
+
+        let text = self.unit.replace_all(&text, |caps: &Captures| {
+            let symbol = self.lookup(&caps[2], rules.units, EN_UNITS);
+            let space = if symbol != "%" || rules.percent_space { " " } else { "" };
+            format!("{}{}{}", &caps[1], space, symbol)
+        });
+
+        text.into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(locale: &str, cases: &[(&str, &str)]) {
+        let normalizer = NumberNormalizer::new(locale);
+        for (input, expected) in cases {
+            assert_eq!(normalizer.normalize(input), *expected, "{}: {:?}", locale, input);
+        }
+    }
+
+    #[test]
+    fn english() {
+        check("en", &[
+            ("it costs 1,234.5 dollars", "it costs $1,234.5"),
+            ("about 3.5 kilometers", "about 3.5 km"),
+            ("up 12 percent", "up 12%"),
+            ("twenty five people", "25 people"),
+            ("twenty-five people", "25 people"),
+            ("one hundred and five", "105"),
+            ("two thousand three hundred forty", "2340"),
+            ("three point one four", "3.14"),
+            ("five euros", "€5"),
+            ("one of them", "one of them"),
+            ("one kilometer", "1 km"),
+            ("five and six", "five and six"),
+            ("a hundred times", "a hundred times"),
+            ("the end.", "the end."),
+        ]);
+    }
+
+    #[test]
+    fn german() {
+        check("de", &[
+            ("es kostet 1,234.5 euro", "es kostet 1.234,5 €"),
+            ("drei komma fünf", "3,5"),
+            ("fünfundzwanzig Leute", "25 Leute"),
+            ("zweihundertdreiundvierzig", "243"),
+            ("zwei Millionen", "2000000"),
+            ("eine Idee", "eine Idee"),
+            ("drei Kilogramm", "3 kg"),
+            ("zehn Prozent", "10 %"),
+            ("drei komma fünfundzwanzig Euro", "3,25 €"),
+            // Already German: 1.000 is a thousand, not one
+            ("1.000 Leute", "1.000 Leute"),
+            ("1.000.000", "1.000.000"),
+            ("3,5", "3,5"),
+        ]);
+        check("de-AT", &[("3.5 euro", "3,5 €")]);
+    }
+
+    #[test]
+    fn french() {
+        check("fr", &[
+            ("1,000 euros", "1\u{202F}000 €"),
+            ("3.5 pour cent", "3,5 %"),
+            ("5 livres", "5 £"),
+            // No French number words yet, so they stay words
+            ("vingt-cinq", "vingt-cinq"),
+        ]);
+    }
+
+    #[test]
+    fn spanish_italian_dutch() {
+        check("es", &[("3.5 dólares", "3,5 $"), ("1,000 kilómetros", "1.000 km")]);
+        check("it", &[("3.5 per cento", "3,5%"), ("5 dollari", "5 $")]);
+        check("nl", &[("5 euro", "€ 5"), ("12 procent", "12%")]);
+    }
+
+    #[test]
+    fn unsupported_locales_use_english() {
+        check("ja", &[("1,234.5 dollars", "$1,234.5"), ("twenty five", "25")]);
+    }
+}
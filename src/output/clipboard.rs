@@ -0,0 +1,54 @@
+//! Clipboard sink: hands the text to the platform's clipboard tool without pasting it.
+
+use anyhow::Result;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Clipboard tools to try, in order, with their arguments
+fn tools() -> &'static [(&'static str, &'static [&'static str])] {
+    if cfg!(target_os = "macos") {
+        &[("pbcopy", &[])]
+    } else if cfg!(windows) {
+        &[("clip", &[])]
+    } else {
+        let wayland = std::env::var("XDG_SESSION_TYPE")
+            .map(|session| session.eq_ignore_ascii_case("wayland"))
+            .unwrap_or(false);
+        if wayland {
+            &[("wl-copy", &[]), ("xclip", &["-selection", "clipboard"]), ("xsel", &["--clipboard", "--input"])]
+        } else {
+            &[("xclip", &["-selection", "clipboard"]), ("xsel", &["--clipboard", "--input"])]
+        }
+    }
+}
+
+/// Put `text` on the clipboard; returns the tool that did it
+pub fn copy(text: &str) -> Result<&'static str> {
+    for (program, args) in tools() {
+        // wl-copy and xclip fork to keep serving the selection, so nothing may
+        // hold on to a pipe of theirs or waiting would hang
+        let mut child = match Command::new(program)
+            .args(*args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(anyhow::anyhow!("Failed to run {}: {}", program, e)),
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        let status = child.wait()?;
+        if !status.success() {
+            return Err(anyhow::anyhow!("{} failed ({})", program, status));
+        }
+        return Ok(program);
+    }
+    Err(anyhow::anyhow!(
+        "No clipboard tool found (tried {})",
+        tools().iter().map(|(program, _)| *program).collect::<Vec<_>>().join(", ")
+    ))
+}
@@ -0,0 +1,55 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Recently output jobs, so a recording processed twice (e.g. a stop signal racing
+/// a restart) isn't typed twice. Keyed on recording id + text: the same phrase
+/// dictated again is a new recording and always goes through.
+#[derive(Debug)]
+pub struct DedupGuard {
+    window: Duration,
+    recent: VecDeque<(u64, u64, Instant)>,
+}
+
+impl DedupGuard {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            recent: VecDeque::new(),
+        }
+    }
+
+    /// Record this job; false if the same recording with the same text was output within the window
+    pub fn admit(&mut self, recording_id: u64, text: &str, now: Instant) -> bool {
+        while let Some((_, _, at)) = self.recent.front() {
+            if now.duration_since(*at) <= self.window {
+                break;
+            }
+            self.recent.pop_front();
+        }
+
+        let hash = text_hash(text);
+        if let Some((_, _, at)) = self.recent.iter().find(|(id, h, _)| *id == recording_id && *h == hash) {
+            warn!(
+                "Dropping duplicate output for recording {} (already output {}ms ago)",
+                recording_id,
+                now.duration_since(*at).as_millis()
+            );
+            return false;
+        }
+        if self.recent.iter().any(|(id, _, _)| *id == recording_id) {
+            warn!("Recording id {} was reused for different text within {:?}", recording_id, self.window);
+        }
+
+        self.recent.push_back((recording_id, hash, now));
+        true
+    }
+}
+
+fn text_hash(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
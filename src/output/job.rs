@@ -0,0 +1,343 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tracing::warn;
+
+/// Which rendition of a transcription a sink consumes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextVariant {
+    /// Text exactly as the speech model produced it
+    Raw,
+    /// Text after Ollama refinement
+    Refined,
+    /// Refined (or raw) text after whitespace/punctuation cleanup
+    Processed,
+    /// Processed text rendered through output.template
+    Templated,
+}
+
+impl TextVariant {
+    /// Fall back to a variant that will actually exist given enabled features
+    pub fn resolve(self, refinement_enabled: bool, templating_enabled: bool) -> TextVariant {
+        match self {
+            TextVariant::Refined if !refinement_enabled => {
+                warn!("Sink requests \"refined\" text but refinement is disabled, using \"processed\"");
+                TextVariant::Processed
+            }
+            TextVariant::Templated if !templating_enabled => {
+                warn!("Sink requests \"templated\" text but no template is configured, using \"processed\"");
+                TextVariant::Processed
+            }
+            variant => variant,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SinkKind {
+    /// Type the text into the focused window
+    Typing,
+    /// Print one line per utterance to stdout
+    Stdout,
+    /// Append one line per utterance to `path`
+    File,
+    /// Copy the text to the clipboard without pasting it
+    Clipboard,
+}
+
+/// Line format for the stdout and file sinks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SinkFormat {
+    /// Just the text
+    #[default]
+    Text,
+    /// A JSON object per utterance with the text and its metadata
+    Jsonl,
+    /// The text after the time recording started, e.g. "2026-10-16T14:03:12Z Buy milk"
+    Timestamped,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SinkConfig {
+    pub kind: SinkKind,
+    #[serde(default = "default_variant")]
+    pub variant: TextVariant,
+    /// text | jsonl | timestamped (stdout and file sinks)
+    #[serde(default)]
+    pub format: SinkFormat,
+    /// Destination of the file sink
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+}
+
+fn default_variant() -> TextVariant {
+    TextVariant::Processed
+}
+
+pub fn default_sinks() -> Vec<SinkConfig> {
+    vec![SinkConfig {
+        kind: SinkKind::Typing,
+        variant: TextVariant::Processed,
+        format: SinkFormat::Text,
+        path: None,
+    }]
+}
+
+/// Per-utterance metadata carried into structured sink output
+#[derive(Debug, Clone, Serialize)]
+pub struct UtteranceMeta {
+    pub recording_id: u64,
+    pub language: String,
+    /// Active preset, if any
+    pub profile: Option<String>,
+    /// Milliseconds since the Unix epoch when recording started
+    pub started_at_ms: u64,
+    pub ended_at_ms: u64,
+    pub duration_ms: u64,
+}
+
+/// Every rendition of one transcription, handed to all sinks
+#[derive(Debug, Clone)]
+pub struct OutputJob {
+    pub raw: String,
+    pub refined: Option<String>,
+    pub processed: String,
+    pub templated: Option<String>,
+}
+
+impl OutputJob {
+    #[cfg(test)]
+    pub fn new(raw: String, refined: Option<String>) -> Self {
+        let text = raw.clone();
+        Self::post_processed(raw, text, refined)
+    }
+
+    /// Text that went through the pipeline already and is output again as it is
+    pub fn replayed(raw: String, refined: Option<String>, processed: String) -> Self {
+        Self {
+            raw,
+            refined,
+            processed,
+            templated: None,
+        }
+    }
+
+    /// Like `new`, for raw text that went through text rewrites (vocabulary, punctuation,
+    /// numbers) before refinement; `text` is what was fed onward, `raw` stays as decoded
+    pub fn post_processed(raw: String, text: String, refined: Option<String>) -> Self {
+        let processed = clean_text(refined.as_deref().unwrap_or(&text));
+        Self {
+            raw,
+            refined,
+            processed,
+            templated: None,
+        }
+    }
+
+    /// Fill in output.template for the templated variant
+    pub fn apply_template(&mut self, template: &str, meta: &UtteranceMeta) {
+        let time = format_utc(meta.started_at_ms);
+        let fields = [("{text}", self.processed.as_str()), ("{raw}", self.raw.as_str()), ("{time}", time.as_str())];
+
+        // One pass, so braces in the dictated text are never expanded
+        let mut out = String::with_capacity(template.len() + self.processed.len());
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            out.push_str(&rest[..start]);
+            rest = &rest[start..];
+            match fields.iter().find(|(name, _)| rest.starts_with(name)) {
+                Some((name, value)) => {
+                    out.push_str(value);
+                    rest = &rest[name.len()..];
+                }
+                None => {
+                    out.push('{');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        out.push_str(rest);
+        self.templated = Some(out);
+    }
+
+    /// The line a stdout/file sink writes for this job (without the newline)
+    pub fn render_line(&self, sink: &SinkConfig, meta: &UtteranceMeta) -> String {
+        let text = self.variant(sink.variant);
+        match sink.format {
+            SinkFormat::Text => text.to_string(),
+            SinkFormat::Timestamped => format!("{} {}", format_utc(meta.started_at_ms), text),
+            SinkFormat::Jsonl => serde_json::json!({
+                "event": "utterance",
+                "text": text,
+                "raw_text": self.raw,
+                "language": meta.language,
+                "profile": meta.profile,
+                "recording_id": meta.recording_id,
+                "started_at_ms": meta.started_at_ms,
+                "ended_at_ms": meta.ended_at_ms,
+                "duration_ms": meta.duration_ms,
+            })
+            .to_string(),
+        }
+    }
+
+    /// Text for a variant, falling back to the processed text if it wasn't produced
+    pub fn variant(&self, variant: TextVariant) -> &str {
+        match variant {
+            TextVariant::Raw => &self.raw,
+            TextVariant::Refined => self.refined.as_deref().unwrap_or(&self.processed),
+            TextVariant::Processed => &self.processed,
+            TextVariant::Templated => self.templated.as_deref().unwrap_or(&self.processed),
+        }
+    }
+}
+
+/// RFC 3339 UTC time, to the second, for milliseconds since the Unix epoch
+fn format_utc(epoch_ms: u64) -> String {
+    let secs = epoch_ms / 1000;
+    let (days, time) = (secs / 86_400, secs % 86_400);
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's civil_from_days)
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+/// Remove extra whitespace and spaces before punctuation
+pub fn clean_text(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace(" ,", ",")
+        .replace(" .", ".")
+        .replace(" !", "!")
+        .replace(" ?", "?")
+        .replace(" ;", ";")
+        .replace(" :", ":")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta() -> UtteranceMeta {
+        UtteranceMeta {
+            recording_id: 7,
+            language: "en".to_string(),
+            profile: None,
+            // 2026-10-16T14:03:12Z
+            started_at_ms: 1_792_159_392_000,
+            ended_at_ms: 1_792_159_394_000,
+            duration_ms: 2_000,
+        }
+    }
+
+    fn sink(variant: TextVariant) -> SinkConfig {
+        SinkConfig {
+            kind: SinkKind::Stdout,
+            variant,
+            format: SinkFormat::Text,
+            path: None,
+        }
+    }
+
+    fn job() -> OutputJob {
+        let mut job = OutputJob::new("buy  milk ,please".to_string(), Some("Buy milk , please.".to_string()));
+        job.apply_template("- {text} ({raw})", &meta());
+        job
+    }
+
+    #[test]
+    fn each_sink_gets_its_configured_variant() {
+        let job = job();
+        let cases = [
+            (TextVariant::Raw, "buy  milk ,please"),
+            (TextVariant::Refined, "Buy milk , please."),
+            (TextVariant::Processed, "Buy milk, please."),
+            (TextVariant::Templated, "- Buy milk, please. (buy  milk ,please)"),
+        ];
+        for (variant, expected) in cases {
+            assert_eq!(job.render_line(&sink(variant), &meta()), expected, "{:?}", variant);
+        }
+    }
+
+    #[test]
+    fn missing_variants_fall_back_to_processed() {
+        let job = OutputJob::new("hello  there".to_string(), None);
+        assert_eq!(job.processed, "hello there");
+        assert_eq!(job.variant(TextVariant::Refined), "hello there");
+        assert_eq!(job.variant(TextVariant::Templated), "hello there");
+        assert_eq!(job.variant(TextVariant::Raw), "hello  there");
+    }
+
+    #[test]
+    fn rewrites_reach_processed_but_not_raw() {
+        let job = OutputJob::post_processed("so  it works".to_string(), "So, it works.".to_string(), None);
+        assert_eq!(job.variant(TextVariant::Raw), "so  it works");
+        assert_eq!(job.processed, "So, it works.");
+
+        let job = OutputJob::post_processed("so it works".to_string(), "So, it works.".to_string(), Some("So it works!".to_string()));
+        assert_eq!(job.variant(TextVariant::Raw), "so it works");
+        assert_eq!(job.processed, "So it works!");
+    }
+
+    #[test]
+    fn resolve_only_falls_back_for_disabled_features() {
+        assert_eq!(TextVariant::Refined.resolve(false, true), TextVariant::Processed);
+        assert_eq!(TextVariant::Refined.resolve(true, false), TextVariant::Refined);
+        assert_eq!(TextVariant::Templated.resolve(true, false), TextVariant::Processed);
+        assert_eq!(TextVariant::Templated.resolve(false, true), TextVariant::Templated);
+        assert_eq!(TextVariant::Raw.resolve(false, false), TextVariant::Raw);
+    }
+
+    #[test]
+    fn template_fills_placeholders_once() {
+        let mut job = OutputJob::new("say {raw} and {text}".to_string(), None);
+        job.apply_template("[{time}] {text} {unknown}", &meta());
+        assert_eq!(
+            job.variant(TextVariant::Templated),
+            "[2026-10-16T14:03:12Z] say {raw} and {text} {unknown}"
+        );
+    }
+
+    #[test]
+    fn format_utc_gives_rfc_3339_seconds() {
+        let cases = [
+            (0, "1970-01-01T00:00:00Z"),
+            (999, "1970-01-01T00:00:00Z"),
+            (951_782_400_000, "2000-02-29T00:00:00Z"),
+            (1_700_000_000_123, "2023-11-14T22:13:20Z"),
+            (1_735_689_599_000, "2024-12-31T23:59:59Z"),
+            (4_107_542_400_000, "2100-03-01T00:00:00Z"),
+        ];
+        for (epoch_ms, expected) in cases {
+            assert_eq!(format_utc(epoch_ms), expected, "{}", epoch_ms);
+        }
+    }
+
+    #[test]
+    fn timestamped_lines_lead_with_the_start_time() {
+        let mut sink = sink(TextVariant::Raw);
+        sink.format = SinkFormat::Timestamped;
+        let line = job().render_line(&sink, &meta());
+        assert_eq!(line, "2026-10-16T14:03:12Z buy  milk ,please");
+    }
+}
@@ -0,0 +1,6 @@
+pub mod clipboard;
+pub mod dedup;
+pub mod job;
+
+pub use dedup::DedupGuard;
+pub use job::{OutputJob, SinkConfig, SinkKind, TextVariant, UtteranceMeta};
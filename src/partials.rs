@@ -0,0 +1,145 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// When to decode a snapshot of the recording so far (speech.partials). At most one
+/// partial is in flight, and one that finishes after its recording stopped is stale.
+#[derive(Debug)]
+pub struct PartialSchedule {
+    interval_samples: usize,
+    /// Samples recorded since the last snapshot. Counted as they arrive rather than read
+    /// off the buffer, which stops growing once a rolling limit drops the oldest audio.
+    since_snapshot: usize,
+    /// Samples recorded since the recording started, dropped ones included
+    recorded: usize,
+    /// Bumped when a recording (or continuous segment) ends
+    generation: Arc<AtomicU64>,
+    in_flight: Arc<AtomicBool>,
+}
+
+/// A partial decode that was started; dropping it frees the slot for the next one
+#[derive(Debug)]
+pub struct PartialTicket {
+    recorded: usize,
+    generation: u64,
+    current: Arc<AtomicU64>,
+    in_flight: Arc<AtomicBool>,
+}
+
+impl PartialSchedule {
+    pub fn new(interval_samples: usize) -> Self {
+        Self {
+            interval_samples: interval_samples.max(1),
+            since_snapshot: 0,
+            recorded: 0,
+            generation: Arc::new(AtomicU64::new(0)),
+            in_flight: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Count `added` newly recorded samples; a ticket if a snapshot is due and no partial
+    /// is running. A due snapshot skipped while one runs is taken on the next call after it finishes.
+    pub fn poll(&mut self, added: usize) -> Option<PartialTicket> {
+        self.recorded += added;
+        self.since_snapshot += added;
+        if self.since_snapshot < self.interval_samples || self.in_flight.swap(true, Ordering::AcqRel) {
+            return None;
+        }
+        self.since_snapshot = 0;
+        Some(PartialTicket {
+            recorded: self.recorded,
+            generation: self.generation.load(Ordering::Acquire),
+            current: self.generation.clone(),
+            in_flight: self.in_flight.clone(),
+        })
+    }
+
+    /// The recording stopped: partials still running are stale, and the next starts over
+    pub fn stop(&mut self) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        self.since_snapshot = 0;
+        self.recorded = 0;
+    }
+}
+
+impl PartialTicket {
+    /// How far into the recording the snapshot reaches, in ms; a rolling limit may have
+    /// dropped the start of it
+    pub fn audio_ms(&self) -> u64 {
+        (self.recorded / 16) as u64
+    }
+
+    /// False once the recording this snapshot came from has stopped
+    pub fn is_current(&self) -> bool {
+        self.current.load(Ordering::Acquire) == self.generation
+    }
+}
+
+impl Drop for PartialTicket {
+    fn drop(&mut self) {
+        self.in_flight.store(false, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 100ms chunks against a 1s interval
+    const CHUNK: usize = 1600;
+
+    fn schedule() -> PartialSchedule {
+        PartialSchedule::new(16000)
+    }
+
+    #[test]
+    fn a_snapshot_is_due_every_interval() {
+        let mut schedule = schedule();
+        assert!((0..9).all(|_| schedule.poll(CHUNK).is_none()));
+        let first = schedule.poll(CHUNK).expect("due after a second");
+        assert_eq!(first.audio_ms(), 1000);
+        drop(first);
+
+        assert!((0..9).all(|_| schedule.poll(CHUNK).is_none()));
+        assert_eq!(schedule.poll(CHUNK).unwrap().audio_ms(), 2000);
+    }
+
+    #[test]
+    fn one_partial_at_a_time_and_a_skipped_one_follows_it() {
+        let mut schedule = schedule();
+        let running = schedule.poll(16000).unwrap();
+        // Due again, but the first is still decoding
+        assert!(schedule.poll(16000).is_none());
+        assert!(schedule.poll(CHUNK).is_none());
+        drop(running);
+        assert_eq!(schedule.poll(CHUNK).unwrap().audio_ms(), 2200);
+    }
+
+    #[test]
+    fn snapshots_keep_coming_once_a_rolling_limit_is_reached() {
+        // The buffer holds its limit while each chunk pushes the oldest out; only what was
+        // added counts
+        let mut schedule = schedule();
+        let mut fired = Vec::new();
+        for _ in 0..50 {
+            if let Some(ticket) = schedule.poll(CHUNK) {
+                fired.push(ticket.audio_ms());
+            }
+        }
+        assert_eq!(fired, vec![1000, 2000, 3000, 4000, 5000]);
+    }
+
+    #[test]
+    fn stopping_makes_a_running_partial_stale_and_starts_over() {
+        let mut schedule = schedule();
+        let running = schedule.poll(16000).unwrap();
+        assert!(running.is_current());
+        schedule.stop();
+        assert!(!running.is_current());
+        drop(running);
+
+        assert!(schedule.poll(8000).is_none(), "the next recording waits a full interval");
+        let next = schedule.poll(8000).unwrap();
+        assert!(next.is_current());
+        assert_eq!(next.audio_ms(), 1000);
+    }
+}
@@ -0,0 +1,63 @@
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+/// `<xdg>/tomchat`, else `<home>/<under_home>/tomchat`, else the temp dir's
+fn base_dir(xdg: Option<OsString>, home: Option<OsString>, under_home: &str) -> PathBuf {
+    xdg.map(PathBuf::from)
+        .or_else(|| home.map(|home| PathBuf::from(home).join(under_home)))
+        .unwrap_or_else(std::env::temp_dir)
+        .join("tomchat")
+}
+
+/// Directory for tomchat's persistent data ($XDG_DATA_HOME/tomchat or ~/.local/share/tomchat)
+pub fn data_dir() -> PathBuf {
+    base_dir(std::env::var_os("XDG_DATA_HOME"), std::env::var_os("HOME"), ".local/share")
+}
+
+/// Directory for state that should survive restarts ($XDG_STATE_HOME/tomchat or ~/.local/state/tomchat)
+pub fn state_dir() -> PathBuf {
+    base_dir(std::env::var_os("XDG_STATE_HOME"), std::env::var_os("HOME"), ".local/state")
+}
+
+/// Directory for disposable data ($XDG_CACHE_HOME/tomchat or ~/.cache/tomchat)
+pub fn cache_dir() -> PathBuf {
+    base_dir(std::env::var_os("XDG_CACHE_HOME"), std::env::var_os("HOME"), ".cache")
+}
+
+/// Where models fetched for speech.model are kept
+pub fn models_cache_dir() -> PathBuf {
+    cache_dir().join("models")
+}
+
+/// Where saved recordings live
+pub fn recordings_dir() -> PathBuf {
+    data_dir().join("recordings")
+}
+
+/// Log of the current (or last) daemon session, read by `tomchat export-last`
+pub fn log_path() -> PathBuf {
+    state_dir().join("tomchat.log")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_xdg_variable_wins_over_home() {
+        let dir = base_dir(Some("/xdg/cache".into()), Some("/home/tom".into()), ".cache");
+        assert_eq!(dir, PathBuf::from("/xdg/cache/tomchat"));
+    }
+
+    #[test]
+    fn without_it_the_directory_is_under_home() {
+        assert_eq!(base_dir(None, Some("/home/tom".into()), ".cache"), PathBuf::from("/home/tom/.cache/tomchat"));
+        assert_eq!(base_dir(None, Some("/home/tom".into()), ".local/share"), PathBuf::from("/home/tom/.local/share/tomchat"));
+        assert_eq!(base_dir(None, None, ".cache"), std::env::temp_dir().join("tomchat"));
+    }
+
+    #[test]
+    fn models_are_cached_under_the_cache_dir() {
+        assert_eq!(models_cache_dir(), cache_dir().join("models"));
+    }
+}
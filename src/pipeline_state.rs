@@ -0,0 +1,285 @@
+//! How busy the pipeline is: jobs waiting and the one being worked on, for the GUI's
+//! busy indicator ("pipeline_state") and `tomchat status`. Each job carries a [`Job`]
+//! through its stages and reports transitions on it; dropping the job, however it
+//! ends, takes it off the books.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+use crate::cancel::JobStage;
+use crate::paths;
+
+/// How often the state is re-sent while something is in flight, so elapsed times move
+pub const STATE_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Default)]
+struct Inner {
+    /// Stage per recording and when it was entered; None while waiting for the next one
+    jobs: BTreeMap<u64, (Option<JobStage>, Instant)>,
+    model_loaded: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PipelineState {
+    inner: Arc<Mutex<Inner>>,
+    changed: Arc<Notify>,
+}
+
+impl PipelineState {
+    /// Start tracking a job, waiting for its first stage
+    pub fn track(&self, recording_id: u64) -> Job {
+        self.update(|inner| {
+            inner.jobs.insert(recording_id, (None, Instant::now()));
+        });
+        Job {
+            state: self.clone(),
+            recording_id,
+        }
+    }
+
+    pub fn set_model_loaded(&self, loaded: bool) {
+        self.update(|inner| inner.model_loaded = loaded);
+    }
+
+    fn update(&self, change: impl FnOnce(&mut Inner)) {
+        change(&mut self.inner.lock().unwrap());
+        self.changed.notify_one();
+    }
+
+    /// Resolves on the next change (or at once if there was one since the last call)
+    pub async fn changed(&self) {
+        self.changed.notified().await
+    }
+
+    /// The job furthest along is the active one (the oldest, when several are decoding:
+    /// they take the model in turn); everything else counts as queued
+    pub fn snapshot(&self) -> PipelineSnapshot {
+        let inner = self.inner.lock().unwrap();
+        let active = inner
+            .jobs
+            .iter()
+            .filter_map(|(id, (stage, since))| stage.map(|stage| (*id, stage, *since)))
+            .max_by_key(|(id, stage, _)| (*stage, Reverse(*id)))
+            .map(|(recording_id, stage, since)| ActiveJob {
+                recording_id,
+                stage,
+                elapsed_ms: since.elapsed().as_millis() as u64,
+            });
+        PipelineSnapshot {
+            queued: inner.jobs.len() - usize::from(active.is_some()),
+            active,
+            model_loaded: inner.model_loaded,
+        }
+    }
+}
+
+/// One recording's place in the pipeline
+#[derive(Debug)]
+pub struct Job {
+    state: PipelineState,
+    recording_id: u64,
+}
+
+impl Job {
+    pub fn enter(&self, stage: JobStage) {
+        self.set(Some(stage));
+    }
+
+    /// Done with a stage, waiting for the next
+    pub fn wait(&self) {
+        self.set(None);
+    }
+
+    fn set(&self, stage: Option<JobStage>) {
+        let recording_id = self.recording_id;
+        self.state.update(|inner| {
+            inner.jobs.insert(recording_id, (stage, Instant::now()));
+        });
+    }
+}
+
+impl Drop for Job {
+    fn drop(&mut self) {
+        let recording_id = self.recording_id;
+        self.state.update(|inner| {
+            inner.jobs.remove(&recording_id);
+        });
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveJob {
+    pub recording_id: u64,
+    pub stage: JobStage,
+    /// Time spent in `stage` so far
+    pub elapsed_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineSnapshot {
+    pub queued: usize,
+    pub active: Option<ActiveJob>,
+    pub model_loaded: bool,
+}
+
+impl PipelineSnapshot {
+    pub fn is_idle(&self) -> bool {
+        self.queued == 0 && self.active.is_none()
+    }
+}
+
+/// The last state a running instance wrote, for `tomchat status`
+#[derive(Debug, Serialize, Deserialize)]
+struct StatusFile {
+    pid: u32,
+    /// Milliseconds since the Unix epoch
+    updated_at_ms: u64,
+    #[serde(flatten)]
+    state: PipelineSnapshot,
+}
+
+fn status_path() -> PathBuf {
+    paths::state_dir().join("pipeline_state.json")
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64
+}
+
+/// Record the state for `tomchat status`; replaced atomically so a reader never sees half of it
+pub async fn write_status(snapshot: &PipelineSnapshot) -> Result<()> {
+    write_status_to(&status_path(), snapshot).await
+}
+
+async fn write_status_to(path: &Path, snapshot: &PipelineSnapshot) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let status = StatusFile {
+        pid: std::process::id(),
+        updated_at_ms: now_ms(),
+        state: snapshot.clone(),
+    };
+    let tmp = path.with_extension("json.tmp");
+    tokio::fs::write(&tmp, serde_json::to_string(&status)?).await?;
+    tokio::fs::rename(&tmp, path).await?;
+    Ok(())
+}
+
+/// Remove the state file on shutdown, unless another instance has taken it over since
+pub async fn remove_status() -> Result<()> {
+    remove_status_at(&status_path()).await
+}
+
+async fn remove_status_at(path: &Path) -> Result<()> {
+    match read_status(path) {
+        Some(status) if status.pid == std::process::id() => Ok(tokio::fs::remove_file(path).await?),
+        _ => Ok(()),
+    }
+}
+
+fn read_status(path: &Path) -> Option<StatusFile> {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+}
+
+/// `tomchat status`: what the running instance is doing, if one is
+pub fn print_status() -> Result<()> {
+    let status = read_status(&status_path());
+    let mut system = sysinfo::System::new();
+    let Some(status) = status.filter(|status| system.refresh_process(sysinfo::Pid::from_u32(status.pid))) else {
+        println!("TomChat is not running");
+        return Ok(());
+    };
+
+    println!("TomChat is running (pid {})", status.pid);
+    println!("Model: {}", if status.state.model_loaded { "loaded" } else { "not loaded" });
+    println!("Queued jobs: {}", status.state.queued);
+    match status.state.active {
+        Some(active) => {
+            // Elapsed as of the last write, plus the time since
+            let elapsed_ms = active.elapsed_ms + now_ms().saturating_sub(status.updated_at_ms);
+            println!(
+                "Active: recording {} {} for {:.1}s",
+                active.recording_id,
+                active.stage.as_str(),
+                elapsed_ms as f32 / 1000.0
+            );
+        }
+        None => println!("Active: idle"),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_status_path(name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("tomchat-state-{}-{}", name, std::process::id()))
+            .join("pipeline_state.json")
+    }
+
+    #[test]
+    fn the_furthest_job_is_active_and_the_rest_queued() {
+        let pipeline = PipelineState::default();
+        let first = pipeline.track(1);
+        let second = pipeline.track(2);
+        first.enter(JobStage::Decoding);
+        second.enter(JobStage::Refining);
+
+        let snapshot = pipeline.snapshot();
+        assert_eq!(snapshot.queued, 1);
+        assert_eq!(snapshot.active.map(|active| active.recording_id), Some(2));
+
+        drop(second);
+        drop(first);
+        assert!(pipeline.snapshot().is_idle());
+    }
+
+    #[tokio::test]
+    async fn status_round_trips_and_is_removed_on_shutdown() {
+        let path = temp_status_path("round-trip");
+        let pipeline = PipelineState::default();
+        pipeline.set_model_loaded(true);
+        let _job = pipeline.track(7);
+
+        write_status_to(&path, &pipeline.snapshot()).await.unwrap();
+        let status = read_status(&path).expect("status was written");
+        assert_eq!(status.pid, std::process::id());
+        assert_eq!(status.state.queued, 1);
+        assert!(status.state.model_loaded);
+        assert!(!path.with_extension("json.tmp").exists());
+
+        remove_status_at(&path).await.unwrap();
+        assert!(!path.exists());
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn another_instances_status_is_left_alone() {
+        let path = temp_status_path("other");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let status = StatusFile {
+            pid: std::process::id() + 1,
+            updated_at_ms: now_ms(),
+            state: PipelineState::default().snapshot(),
+        };
+        std::fs::write(&path, serde_json::to_string(&status).unwrap()).unwrap();
+
+        remove_status_at(&path).await.unwrap();
+        assert!(path.exists());
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
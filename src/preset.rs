@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+use toml::{Table, Value};
+use tracing::info;
+
+use crate::config::Config;
+
+/// Speed/accuracy trade-off applied as a base layer under the user's settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Preset {
+    Fastest,
+    Balanced,
+    Accurate,
+}
+
+impl Preset {
+    pub fn name(self) -> &'static str {
+        match self {
+            Preset::Fastest => "fastest",
+            Preset::Balanced => "balanced",
+            Preset::Accurate => "accurate",
+        }
+    }
+
+    /// Settings this preset implies, as `(section, key, value)`
+    fn settings(self) -> Vec<(&'static str, &'static str, Value)> {
+        match self {
+            Preset::Fastest => vec![
+                ("text", "typing_delay_ms", Value::Integer(0)),
+                ("vad", "timeout_ms", Value::Integer(800)),
+                ("text_refinement", "enabled", Value::Boolean(false)),
+            ],
+            Preset::Balanced => vec![
+                ("text", "typing_delay_ms", Value::Integer(1)),
+                ("vad", "timeout_ms", Value::Integer(1500)),
+                ("text_refinement", "timeout_ms", Value::Integer(8000)),
+            ],
+            Preset::Accurate => vec![
+                ("text", "typing_delay_ms", Value::Integer(1)),
+                ("vad", "timeout_ms", Value::Integer(2000)),
+                ("text_refinement", "timeout_ms", Value::Integer(15000)),
+                ("text_refinement", "cold_start_timeout_ms", Value::Integer(60000)),
+            ],
+        }
+    }
+}
+
+/// Fill in preset settings wherever the user's config doesn't set them.
+/// Explicit values always win; sections the file leaves out start from their defaults.
+pub fn apply(preset: Preset, user: &mut Table) {
+    let mut applied = Vec::new();
+    let mut overridden = Vec::new();
+    let mut defaulted = Vec::new();
+
+    for (section, key, value) in preset.settings() {
+        if !user.contains_key(section) {
+            user.insert(section.to_string(), Value::Table(default_section(section)));
+            defaulted.push(section);
+        } else if !defaulted.contains(&section) && user[section].get(key).is_some() {
+            overridden.push(format!("{}.{}", section, key));
+            continue;
+        }
+
+        if let Some(Value::Table(table)) = user.get_mut(section) {
+            table.insert(key.to_string(), value);
+            applied.push(format!("{}.{}", section, key));
+        }
+    }
+
+    info!("Preset {:?} active: {}", preset, applied.join(", "));
+    if !overridden.is_empty() {
+        info!("Preset settings overridden by config: {}", overridden.join(", "));
+    }
+}
+
+/// A section of the default config, for presets touching a section the file leaves out
+fn default_section(section: &str) -> Table {
+    Value::try_from(Config::default())
+        .ok()
+        .and_then(|defaults| defaults.get(section).and_then(Value::as_table).cloned())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn effective(preset: Preset, file: &str) -> Config {
+        let mut table: Table = toml::from_str(file).unwrap();
+        apply(preset, &mut table);
+        Value::Table(table).try_into().unwrap()
+    }
+
+    fn minimal_file() -> String {
+        let mut table = Value::try_from(Config::default()).unwrap().as_table().unwrap().clone();
+        table.remove("text_refinement");
+        for section in ["text", "vad"] {
+            let section = table.get_mut(section).unwrap().as_table_mut().unwrap();
+            section.remove("typing_delay_ms");
+            section.remove("timeout_ms");
+        }
+        toml::to_string(&table).unwrap()
+    }
+
+    #[test]
+    fn presets_set_their_values() {
+        let fastest = effective(Preset::Fastest, &minimal_file());
+        assert_eq!(fastest.text.typing_delay_ms, 0);
+        assert_eq!(fastest.vad.timeout_ms, 800);
+        assert!(!fastest.text_refinement.unwrap().enabled);
+
+        let balanced = effective(Preset::Balanced, &minimal_file());
+        assert_eq!(balanced.vad.timeout_ms, 1500);
+        assert_eq!(balanced.text_refinement.unwrap().timeout_ms, 8000);
+
+        let accurate = effective(Preset::Accurate, &minimal_file());
+        assert_eq!(accurate.vad.timeout_ms, 2000);
+        let refinement = accurate.text_refinement.unwrap();
+        assert_eq!(refinement.timeout_ms, 15000);
+        assert_eq!(refinement.cold_start_timeout_ms, 60000);
+    }
+
+    #[test]
+    fn missing_sections_are_layered_under_defaults() {
+        // No text_refinement section: the preset still reaches it, refinement stays off
+        let accurate = effective(Preset::Accurate, &minimal_file());
+        let refinement = accurate.text_refinement.unwrap();
+        assert!(!refinement.enabled);
+        assert_eq!(refinement.model_name, Config::default().text_refinement.unwrap().model_name);
+    }
+
+    #[test]
+    fn explicit_settings_win() {
+        let mut refinement = default_section("text_refinement");
+        refinement.insert("enabled".into(), Value::Boolean(true));
+        refinement.insert("timeout_ms".into(), Value::Integer(1234));
+        refinement.remove("cold_start_timeout_ms");
+        let file = format!("{}\n[text_refinement]\n{}", minimal_file(), toml::to_string(&refinement).unwrap())
+            .replace("[vad]\n", "[vad]\ntimeout_ms = 900\n");
+        let accurate = effective(Preset::Accurate, &file);
+        assert_eq!(accurate.vad.timeout_ms, 900);
+        let refinement = accurate.text_refinement.unwrap();
+        assert_eq!(refinement.timeout_ms, 1234);
+        assert_eq!(refinement.cold_start_timeout_ms, 60000);
+    }
+}
@@ -0,0 +1,147 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use url::Url;
+
+/// Set once from config; read by every network call site
+static LOCAL_ONLY: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct PrivacyConfig {
+    /// Refuse every network call that could leave this machine
+    #[serde(default)]
+    pub local_only: bool,
+}
+
+/// Every place tomchat talks to the network. New call sites must be added here
+/// and go through `check` before opening a connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetworkSite {
+    /// Text refinement requests and model pulls
+    Ollama,
+    /// http(s) push subscribers
+    PushSubscriber,
+    /// `download-model`
+    ModelDownload,
+    /// speech.backend = "remote"
+    RemoteTranscription,
+}
+
+#[cfg(test)]
+const NETWORK_SITES: &[NetworkSite] = &[
+    NetworkSite::Ollama,
+    NetworkSite::PushSubscriber,
+    NetworkSite::ModelDownload,
+    NetworkSite::RemoteTranscription,
+];
+
+impl NetworkSite {
+    pub fn name(self) -> &'static str {
+        match self {
+            NetworkSite::Ollama => "ollama",
+            NetworkSite::PushSubscriber => "push subscriber",
+            NetworkSite::ModelDownload => "model download",
+            NetworkSite::RemoteTranscription => "remote transcription",
+        }
+    }
+
+    /// Whether local-only mode still allows this site to reach loopback hosts
+    fn allows_loopback(self) -> bool {
+        match self {
+            NetworkSite::Ollama | NetworkSite::PushSubscriber | NetworkSite::RemoteTranscription => true,
+            NetworkSite::ModelDownload => false,
+        }
+    }
+}
+
+pub fn set_local_only(enabled: bool) {
+    LOCAL_ONLY.store(enabled, Ordering::Relaxed);
+}
+
+pub fn local_only() -> bool {
+    LOCAL_ONLY.load(Ordering::Relaxed)
+}
+
+/// privacy.local_only from a config file that may not load as a whole;
+/// None when even that can't be read, so callers can fail closed
+pub fn local_only_setting(config: &str) -> Option<bool> {
+    let table: toml::Table = toml::from_str(config).ok()?;
+    match table.get("privacy").map(|privacy| privacy.get("local_only")) {
+        None | Some(None) => Some(false),
+        Some(Some(value)) => value.as_bool(),
+    }
+}
+
+/// Whether the URL's host is this machine
+pub fn is_loopback(url: &str) -> bool {
+    let Ok(url) = Url::parse(url) else {
+        return false;
+    };
+    match url.host() {
+        Some(url::Host::Domain(domain)) => domain.eq_ignore_ascii_case("localhost"),
+        Some(url::Host::Ipv4(ip)) => ip.is_loopback(),
+        Some(url::Host::Ipv6(ip)) => ip.is_loopback(),
+        None => false,
+    }
+}
+
+/// Runtime guard in front of every connection; errors when local-only mode forbids it
+pub fn check(site: NetworkSite, url: &str) -> Result<()> {
+    if permitted(site, url, local_only()) {
+        return Ok(());
+    }
+    Err(anyhow::anyhow!(
+        "privacy.local_only is set: refusing {} connection to {}",
+        site.name(),
+        url
+    ))
+}
+
+fn permitted(site: NetworkSite, url: &str, local_only: bool) -> bool {
+    !local_only || (site.allows_loopback() && is_loopback(url))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_site_honors_local_only() {
+        for &site in NETWORK_SITES {
+            assert!(permitted(site, "https://example.com/x", false), "{}", site.name());
+            assert!(!permitted(site, "https://example.com/x", true), "{}", site.name());
+            assert!(!permitted(site, "http://10.0.0.2:11434", true), "{}", site.name());
+            assert_eq!(permitted(site, "http://127.0.0.1:11434", true), site.allows_loopback(), "{}", site.name());
+        }
+    }
+
+    #[test]
+    fn registry_lists_every_site() {
+        // Adding a variant breaks this match until it's registered too
+        for site in NETWORK_SITES {
+            match site {
+                NetworkSite::Ollama
+                | NetworkSite::PushSubscriber
+                | NetworkSite::ModelDownload
+                | NetworkSite::RemoteTranscription => {}
+            }
+        }
+        assert_eq!(NETWORK_SITES.len(), 4);
+    }
+
+    #[test]
+    fn loopback_hosts() {
+        assert!(is_loopback("http://localhost:11434"));
+        assert!(is_loopback("http://[::1]:8080/push"));
+        assert!(!is_loopback("http://localhost.example.com"));
+        assert!(!is_loopback("not a url"));
+    }
+
+    #[test]
+    fn local_only_is_read_from_configs_that_dont_load() {
+        assert_eq!(local_only_setting("[privacy]\nlocal_only = true\n[audio]\nbroken = 1"), Some(true));
+        assert_eq!(local_only_setting("[audio]\nsample_rate = 16000"), Some(false));
+        assert_eq!(local_only_setting("[privacy]\nlocal_only = \"yes\""), None);
+        assert_eq!(local_only_setting("[privacy\nlocal_only = true"), None);
+    }
+}
@@ -0,0 +1,211 @@
+use anyhow::Result;
+use futures_util::future::join_all;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use tracing::{debug, info, warn};
+
+use crate::events::{BusEvent, EmitData, EventBus};
+use crate::privacy::{self, NetworkSite};
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// A subscriber failing for longer than this is reported as down
+const DOWN_AFTER: Duration = Duration::from_secs(60);
+
+/// Where the bubble has always read recording state from
+const LEGACY_STATE_FILE: &str = "file:///tmp/tomchat_bubble_state.json";
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PushSubscriberConfig {
+    /// `file://` path to overwrite, or `http(s)://` endpoint to POST to
+    pub url: String,
+    /// Event categories (recording, transcription, compose, refinement, error, status)
+    /// or event names to deliver; empty = all
+    #[serde(default)]
+    pub events: Vec<String>,
+}
+
+/// The single state file the bubble used before push subscribers existed
+pub fn default_push_subscribers() -> Vec<PushSubscriberConfig> {
+    vec![PushSubscriberConfig {
+        url: LEGACY_STATE_FILE.to_string(),
+        events: vec!["state_changed".to_string()],
+    }]
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub struct DeliveryMetrics {
+    pub delivered: u64,
+    pub failed: u64,
+    /// Events skipped while backing off
+    pub skipped: u64,
+}
+
+struct Subscriber {
+    config: PushSubscriberConfig,
+    metrics: DeliveryMetrics,
+    backoff: Duration,
+    retry_at: Option<Instant>,
+    failing_since: Option<Instant>,
+    reported_down: bool,
+}
+
+impl Subscriber {
+    fn new(config: PushSubscriberConfig) -> Self {
+        Self {
+            config,
+            metrics: DeliveryMetrics::default(),
+            backoff: INITIAL_BACKOFF,
+            retry_at: None,
+            failing_since: None,
+            reported_down: false,
+        }
+    }
+
+    fn wants(&self, event: &BusEvent) -> bool {
+        self.config.events.is_empty()
+            || self.config.events.iter().any(|e| *e == event.name || e == event.category())
+    }
+
+    fn on_success(&mut self) {
+        self.metrics.delivered += 1;
+        self.backoff = INITIAL_BACKOFF;
+        self.retry_at = None;
+        if self.reported_down {
+            info!("Push subscriber {} recovered", self.config.url);
+        }
+        self.failing_since = None;
+        self.reported_down = false;
+    }
+
+    /// Returns true when the subscriber has just crossed the "down" threshold
+    fn on_failure(&mut self, now: Instant) -> bool {
+        self.metrics.failed += 1;
+        self.retry_at = Some(now + self.backoff);
+        self.backoff = (self.backoff * 2).min(MAX_BACKOFF);
+
+        let failing_since = *self.failing_since.get_or_insert(now);
+        if !self.reported_down && now.duration_since(failing_since) > DOWN_AFTER {
+            self.reported_down = true;
+            return true;
+        }
+        false
+    }
+}
+
+fn event_body(event: &BusEvent) -> serde_json::Value {
+    let mut body = match event.payload {
+        serde_json::Value::Object(ref map) => map.clone(),
+        ref other => {
+            let mut map = serde_json::Map::new();
+            map.insert("message".into(), other.clone());
+            map
+        }
+    };
+    body.insert("event".into(), event.name.clone().into());
+    body.insert("timestamp".into(), event.timestamp.into());
+    serde_json::Value::Object(body)
+}
+
+async fn deliver(client: &reqwest::Client, url: &str, event: &BusEvent) -> Result<()> {
+    let body = event_body(event);
+
+    if let Some(path) = url.strip_prefix("file://") {
+        tokio::fs::write(path, body.to_string()).await?;
+    } else {
+        privacy::check(NetworkSite::PushSubscriber, url)?;
+        client
+            .post(url)
+            .timeout(Duration::from_secs(2))
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?;
+    }
+    Ok(())
+}
+
+/// Fan bus events out to every configured subscriber until the bus closes
+pub async fn run_push(configs: Vec<PushSubscriberConfig>, bus: EventBus, emit_data: EmitData) {
+    if configs.is_empty() {
+        return;
+    }
+
+    let mut rx = bus.subscribe();
+    let mut subscribers: Vec<Subscriber> = configs.into_iter().map(Subscriber::new).collect();
+    let client = reqwest::Client::new();
+
+    loop {
+        let event = match rx.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                warn!("Push subscribers lagged, {} events missed", missed);
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+
+        // Don't echo our own reports back out
+        if event.name == "push_subscriber_down" {
+            continue;
+        }
+
+        // Deliver to everyone at once so a slow endpoint doesn't hold up the rest
+        let now = Instant::now();
+        let deliveries = subscribers.iter_mut().filter(|s| s.wants(&event)).map(|subscriber| {
+            let (client, event, emit_data) = (&client, &event, &emit_data);
+            async move {
+                if matches!(subscriber.retry_at, Some(retry_at) if now < retry_at) {
+                    subscriber.metrics.skipped += 1;
+                    return;
+                }
+
+                match deliver(client, &subscriber.config.url, event).await {
+                    Ok(()) => subscriber.on_success(),
+                    Err(e) => {
+                        debug!("Push to {} failed: {}", subscriber.config.url, e);
+                        if subscriber.on_failure(now) {
+                            warn!("Push subscriber {} has been failing for over a minute", subscriber.config.url);
+                            emit_data("push_subscriber_down", serde_json::json!({
+                                "url": subscriber.config.url,
+                                "metrics": subscriber.metrics,
+                            }));
+                        }
+                    }
+                }
+            }
+        });
+        join_all(deliveries).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn subscriber(events: &[&str]) -> Subscriber {
+        Subscriber::new(PushSubscriberConfig {
+            url: "file:///dev/null".to_string(),
+            events: events.iter().map(|e| e.to_string()).collect(),
+        })
+    }
+
+    #[test]
+    fn legacy_state_file_only_gets_state_changes() {
+        let legacy = Subscriber::new(default_push_subscribers().remove(0));
+        assert!(legacy.wants(&BusEvent::new("state_changed", serde_json::json!({ "recording": true }))));
+        assert!(!legacy.wants(&BusEvent::new("recording_started", serde_json::json!({}))));
+        assert!(!legacy.wants(&BusEvent::new("recording_stopped", serde_json::json!({}))));
+    }
+
+    #[test]
+    fn filters_by_category_or_name() {
+        let by_category = subscriber(&["recording"]);
+        assert!(by_category.wants(&BusEvent::new("recording_started", serde_json::json!({}))));
+        assert!(!by_category.wants(&BusEvent::new("transcription_result", serde_json::json!({}))));
+
+        let everything = subscriber(&[]);
+        assert!(everything.wants(&BusEvent::new("transcription_result", serde_json::json!({}))));
+    }
+}
@@ -0,0 +1,29 @@
+use regex::Regex;
+use std::sync::OnceLock;
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Patterns for personal data that shouldn't leave the machine in bug reports
+fn patterns() -> &'static [Regex] {
+    static PATTERNS: OnceLock<Vec<Regex>> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        [
+            // Email addresses
+            r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}",
+            // URLs with embedded credentials
+            r"[a-z][a-z0-9+.-]*://[^\s/:@]+:[^\s/@]+@\S+",
+            // Long digit runs: phone, card and account numbers
+            r"\d[\d\s-]{5,}\d",
+        ]
+        .iter()
+        .map(|pattern| Regex::new(pattern).expect("valid redaction pattern"))
+        .collect()
+    })
+}
+
+/// Replace anything matching a redaction pattern
+pub fn redact(text: &str) -> String {
+    patterns()
+        .iter()
+        .fold(text.to_string(), |text, pattern| pattern.replace_all(&text, REDACTED).into_owned())
+}
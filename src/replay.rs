@@ -0,0 +1,250 @@
+//! `tomchat replay bug.zip`: run an exported bundle's audio through this machine's
+//! pipeline with the bundle's settings, then compare the text and timings with what
+//! the reporter got. The recording goes through the real audio task, headless: typing
+//! is a dry run and nothing reaches the history files.
+
+use anyhow::{Context, Result};
+use std::io::{BufRead, Read, Write};
+use std::path::{Path, PathBuf};
+use tokio::sync::{mpsc, oneshot};
+use tracing::info;
+
+use crate::app::{ReplayFeed, TomChatApp};
+use crate::audio::{wav, ChannelMode};
+use crate::config::{Config, IndicatorConfig};
+use crate::history::{HistoryEntry, StageTimings};
+use crate::input::hold::HotkeyMode;
+use crate::output::SinkKind;
+use crate::privacy;
+use crate::text_diff::{self, TextDiff};
+
+struct Bundle {
+    config: Config,
+    transcript: HistoryEntry,
+    audio: Vec<f32>,
+}
+
+fn read_entry(archive: &mut zip::ZipArchive<std::fs::File>, name: &str) -> Result<Option<Vec<u8>>> {
+    let mut file = match archive.by_name(name) {
+        Ok(file) => file,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+    Ok(Some(bytes))
+}
+
+fn open(path: &Path) -> Result<Bundle> {
+    let file = std::fs::File::open(path).with_context(|| format!("Failed to open bundle {:?}", path))?;
+    let mut archive = zip::ZipArchive::new(file).with_context(|| format!("{:?} is not a bundle zip", path))?;
+
+    let config = read_entry(&mut archive, "config.toml")?
+        .ok_or_else(|| anyhow::anyhow!("Bundle has no config.toml"))?;
+    // Through the same migrations and checks as a local config.toml; relative paths resolve here
+    let config = Config::parse(std::str::from_utf8(&config)?, &std::env::current_dir()?)
+        .context("Bundled config.toml doesn't load")?;
+    let transcript = read_entry(&mut archive, "transcript.json")?
+        .ok_or_else(|| anyhow::anyhow!("Bundle has no transcript.json"))?;
+    let transcript: HistoryEntry = serde_json::from_slice(&transcript)?;
+    let audio = read_entry(&mut archive, "audio.wav")?.ok_or_else(|| {
+        anyhow::anyhow!("Bundle has no audio: debug.save_audio_dir was off when the dictation was recorded")
+    })?;
+
+    Ok(Bundle { config, transcript, audio: samples(&audio)? })
+}
+
+/// 16kHz mono, as saved recordings and spill files are written
+fn samples(bytes: &[u8]) -> Result<Vec<f32>> {
+    let (samples, channels, sample_rate) = wav::decode(std::io::Cursor::new(bytes))?;
+    if channels != 1 || sample_rate != 16000 {
+        return Err(anyhow::anyhow!(
+            "Bundled audio is {} channel(s) at {}Hz, expected mono 16kHz",
+            channels,
+            sample_rate
+        ));
+    }
+    Ok(samples)
+}
+
+/// The reporter's paths rarely exist here: ask for a local one, defaulting to this
+/// machine's config.toml
+fn substitute(key: &str, bundled: &Path, local: Option<&Path>) -> Result<PathBuf> {
+    if bundled.exists() {
+        return Ok(bundled.to_path_buf());
+    }
+    eprint!("{} {:?} doesn't exist here. Path to use", key, bundled);
+    if let Some(local) = local {
+        eprint!(" [{}]", local.display());
+    }
+    eprint!(": ");
+    std::io::stderr().flush()?;
+
+    let mut line = String::new();
+    std::io::stdin().lock().read_line(&mut line)?;
+    match (line.trim(), local) {
+        ("", Some(local)) => Ok(local.to_path_buf()),
+        ("", None) => Err(anyhow::anyhow!("No path given for {}", key)),
+        (path, _) => Ok(PathBuf::from(path)),
+    }
+}
+
+/// Headless: no device, hotkeys, indicators or push subscribers, nothing typed, and no
+/// saved audio or reloads. Toggle mode, so the replay's presses start and stop it.
+fn headless(config: &mut Config) {
+    config.audio.device = None;
+    config.audio.channel_mode = ChannelMode::default();
+    config.hotkeys.mode = HotkeyMode::Toggle;
+    config.text.dry_run = true;
+    config.output.sinks.retain(|sink| sink.kind == SinkKind::Typing);
+    config.indicator = IndicatorConfig::default();
+    config.gui.push.clear();
+    config.debug.save_audio_dir = None;
+    config.reload.watch = false;
+    config.speech.watch_model = false;
+}
+
+/// Replay the bundle at `path` and print how the result differs from the bundled transcript
+pub async fn run(path: &Path) -> Result<()> {
+    let Bundle { mut config, transcript, audio } = open(path)?;
+    info!("🔁 Replaying recording {} ({:.1}s of audio)", transcript.recording_id, audio.len() as f32 / 16000.0);
+
+    let local = Config::read().ok();
+    config.speech.model_dir = substitute(
+        "speech.model_dir",
+        &config.speech.model_dir,
+        local.as_ref().map(|local| local.speech.model_dir.as_path()),
+    )?;
+    headless(&mut config);
+    privacy::set_local_only(config.privacy.local_only);
+
+    let (results_tx, mut results_rx) = mpsc::channel(16);
+    let (done_tx, done_rx) = oneshot::channel();
+    let mut app = TomChatApp::new(config, false).await?;
+    app.set_hotkeys_enabled(false);
+    app.set_replay(ReplayFeed { audio, results: results_tx, done: done_tx });
+
+    tokio::select! {
+        result = app.run() => {
+            result?;
+            return Err(anyhow::anyhow!("TomChat stopped before the replay finished"));
+        }
+        _ = done_rx => {}
+    }
+    // Continuous sessions end in several segments; the bundle holds the last one too
+    let mut replayed = None;
+    while let Ok(entry) = results_rx.try_recv() {
+        replayed = Some(entry);
+    }
+    match replayed {
+        Some(replayed) => print_report(&transcript, &replayed),
+        None => println!("Replayed: nothing, the recording was dropped (the log says why)"),
+    }
+    Ok(())
+}
+
+fn print_report(bundled: &HistoryEntry, replayed: &HistoryEntry) {
+    println!("Bundled:  {}", bundled.processed);
+    println!("Replayed: {}", replayed.processed);
+    if bundled.processed == replayed.processed {
+        println!("Text: identical");
+    } else {
+        match text_diff::diff(&bundled.processed, &replayed.processed) {
+            TextDiff::Edits { ops } => println!("Diff:     {}", text_diff::markup(&bundled.processed, &ops)),
+            TextDiff::Rewrite => println!("Diff:     completely different"),
+        }
+        if bundled.raw != replayed.raw {
+            println!("Raw text differs too, so the decode changed (not only refinement):");
+            println!("  bundled:  {}", bundled.raw);
+            println!("  replayed: {}", replayed.raw);
+        }
+    }
+
+    println!();
+    println!("{:<8} {:>10} {:>10}", "Stage", "Bundled", "Replayed");
+    let ms = |ms: Option<u64>| ms.map_or("-".to_string(), |ms| format!("{}ms", ms));
+    let row = |stage: &str, timing: fn(&StageTimings) -> u64| {
+        let (bundled, replayed) = (bundled.timings.as_ref().map(timing), replayed.timings.as_ref().map(timing));
+        println!("{:<8} {:>10} {:>10}", stage, ms(bundled), ms(replayed));
+    };
+    row("audio", |t| t.audio_ms);
+    row("decode", |t| t.decode_ms);
+    row("refine", |t| t.refine_ms);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::{SinkConfig, TextVariant};
+
+    /// An export-last bundle of this repo's config.toml, with `edit` applied to it
+    fn bundle(name: &str, edit: impl Fn(&str) -> String) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("tomchat-replay-{}-{}.zip", name, std::process::id()));
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+        let options = zip::write::FileOptions::default();
+        let entry = HistoryEntry {
+            recording_id: 7,
+            timestamp: 0,
+            raw: "hello world".into(),
+            refined: None,
+            processed: "Hello world.".into(),
+            stop_reason: None,
+            timings: None,
+            audio_path: None,
+        };
+        zip.start_file("config.toml", options).unwrap();
+        zip.write_all(edit(include_str!("../config.toml")).as_bytes()).unwrap();
+        zip.start_file("transcript.json", options).unwrap();
+        zip.write_all(serde_json::to_string(&entry).unwrap().as_bytes()).unwrap();
+        zip.start_file("audio.wav", options).unwrap();
+        zip.write_all(&wav::encode(&vec![0.0; 1600]).unwrap()).unwrap();
+        zip.finish().unwrap();
+        path
+    }
+
+    #[test]
+    fn a_bundled_legacy_hotkey_table_is_migrated() {
+        let path = bundle("legacy", |config| {
+            let config = config.replace("toggle_recording = \"caps\"\n", "");
+            format!("{}\n[hotkey]\ncombination = \"ctrl+alt+r\"\n", config)
+        });
+        let opened = open(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let Bundle { config, transcript, audio } = opened.unwrap();
+        assert!(config.hotkey.is_none());
+        assert_eq!(config.hotkeys.toggle_recording(), "ctrl+alt+r");
+        assert_eq!(transcript.recording_id, 7);
+        assert_eq!(audio.len(), 1600);
+    }
+
+    #[test]
+    fn a_bundled_config_is_validated_like_a_local_one() {
+        let path = bundle("invalid", |config| config.replace("toggle_recording = \"caps\"", "toggle_recording = \"ctrl+nosuchkey\""));
+        let opened = open(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(opened.is_err());
+    }
+
+    #[test]
+    fn a_replay_types_nothing_and_leaves_no_trace() {
+        let path = bundle("headless", |config| config.to_string());
+        let mut config = open(&path).unwrap().config;
+        std::fs::remove_file(&path).unwrap();
+        config.hotkeys.mode = HotkeyMode::Hold;
+        config.output.sinks = vec![
+            SinkConfig { kind: SinkKind::Typing, variant: TextVariant::Processed, format: Default::default(), path: None },
+            SinkConfig { kind: SinkKind::File, variant: TextVariant::Processed, format: Default::default(), path: Some("out.txt".into()) },
+        ];
+        config.debug.save_audio_dir = Some("recordings".into());
+        config.reload.watch = true;
+
+        headless(&mut config);
+        assert!(config.text.dry_run);
+        assert_eq!(config.hotkeys.mode, HotkeyMode::Toggle);
+        assert_eq!(config.output.sinks.iter().map(|sink| sink.kind).collect::<Vec<_>>(), [SinkKind::Typing]);
+        assert_eq!(config.debug.save_audio_dir, None);
+        assert!(!config.reload.watch);
+    }
+}
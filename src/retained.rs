@@ -0,0 +1,146 @@
+use anyhow::Result;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+use crate::download;
+
+/// The most recent recording, kept briefly so it can be re-decoded without re-speaking
+#[derive(Debug)]
+pub struct RetainedRecording {
+    pub recording_id: u64,
+    pub audio: Vec<f32>,
+    captured_at: Instant,
+}
+
+/// Holds at most one recording, bounded in length and lifetime
+#[derive(Debug)]
+pub struct RecordingRetainer {
+    current: Option<RetainedRecording>,
+    max_samples: usize,
+    ttl: Duration,
+}
+
+impl RecordingRetainer {
+    pub fn new(max_samples: usize, ttl: Duration) -> Self {
+        Self {
+            current: None,
+            max_samples,
+            ttl,
+        }
+    }
+
+    /// Keep a copy of this recording, replacing the previous one
+    pub fn retain(&mut self, recording_id: u64, audio: &[f32]) {
+        if audio.len() > self.max_samples {
+            debug!("Recording {} too long to retain for re-decode", recording_id);
+            self.current = None;
+            return;
+        }
+
+        self.current = Some(RetainedRecording {
+            recording_id,
+            audio: audio.to_vec(),
+            captured_at: Instant::now(),
+        });
+    }
+
+    /// The retained recording, if it hasn't expired
+    pub fn get(&mut self) -> Option<&RetainedRecording> {
+        self.expire();
+        self.current.as_ref()
+    }
+
+    /// Drop the recording once its TTL has passed
+    pub fn expire(&mut self) {
+        if matches!(self.current, Some(ref r) if r.captured_at.elapsed() > self.ttl) {
+            debug!("Retained recording expired");
+            self.current = None;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.current = None;
+    }
+}
+
+/// What to change for one re-decode, as the retranscribe IPC command asks; the hotkey
+/// changes nothing
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RedecodeOverrides {
+    /// A speech model by name, as speech.model takes it
+    pub model: Option<String>,
+    pub beam_size: Option<u32>,
+    pub language: Option<String>,
+}
+
+/// A model loaded for re-decodes, and how it was set up
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedecodeModel {
+    pub model_dir: PathBuf,
+    pub language: String,
+    pub beam_size: usize,
+}
+
+impl RedecodeOverrides {
+    /// The model to re-decode with, or None for the transcriber the recording went through.
+    /// `configured` is the retranscribe.model_dir one, if set, and `main` the main model;
+    /// whatever isn't overridden comes from the first of them there is.
+    pub fn model(&self, configured: Option<&RedecodeModel>, main: &RedecodeModel) -> Result<Option<RedecodeModel>> {
+        if *self == Self::default() {
+            return Ok(configured.cloned());
+        }
+        let base = configured.unwrap_or(main);
+        Ok(Some(RedecodeModel {
+            model_dir: match self.model {
+                Some(ref name) => download::speech_model_dir(name)?,
+                None => base.model_dir.clone(),
+            },
+            language: self.language.clone().unwrap_or_else(|| base.language.clone()),
+            beam_size: self.beam_size.map_or(base.beam_size, |width| width as usize),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cleared_recordings_are_gone_until_the_next_one() {
+        let mut retainer = RecordingRetainer::new(16_000, Duration::from_secs(60));
+        retainer.retain(1, &[0.5; 800]);
+        assert_eq!(retainer.get().map(|r| r.recording_id), Some(1));
+        retainer.clear();
+        assert!(retainer.get().is_none());
+        retainer.retain(2, &[0.5; 800]);
+        assert_eq!(retainer.get().map(|r| r.recording_id), Some(2));
+    }
+
+    fn model(dir: &str, language: &str, beam_size: usize) -> RedecodeModel {
+        RedecodeModel { model_dir: PathBuf::from(dir), language: language.to_string(), beam_size }
+    }
+
+    #[test]
+    fn overrides_fill_in_from_the_configured_model_then_the_main_one() {
+        let main = model("./models/main", "en", 0);
+        let alternate = model("./models/alternate", "en", 0);
+        assert_eq!(RedecodeOverrides::default().model(None, &main).unwrap(), None);
+        assert_eq!(RedecodeOverrides::default().model(Some(&alternate), &main).unwrap(), Some(alternate.clone()));
+
+        let overrides = RedecodeOverrides { model: None, beam_size: Some(4), language: Some("de".to_string()) };
+        assert_eq!(overrides.model(Some(&alternate), &main).unwrap(), Some(model("./models/alternate", "de", 4)));
+        assert_eq!(overrides.model(None, &main).unwrap(), Some(model("./models/main", "de", 4)));
+    }
+
+    #[test]
+    fn a_model_override_names_a_known_speech_model() {
+        let main = model("./models/main", "en", 0);
+        let named = RedecodeOverrides { model: Some("parakeet".to_string()), ..Default::default() };
+        let loaded = named.model(None, &main).unwrap().unwrap();
+        assert_eq!(loaded.model_dir, download::speech_model_dir("parakeet").unwrap());
+
+        let unknown = RedecodeOverrides { model: Some("silero-vad".to_string()), ..Default::default() };
+        assert!(unknown.model(None, &main).is_err());
+    }
+}
@@ -0,0 +1,110 @@
+use serde::Serialize;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Words ending in a period that don't end a sentence (compared lowercased)
+const ABBREVIATIONS: &[&str] = &[
+    "e.g.", "i.e.", "etc.", "vs.", "cf.", "approx.", "dr.", "mr.", "mrs.", "ms.", "prof.", "st.", "jr.", "sr.",
+];
+/// Abbreviations only when a number follows: "No. 5", but "I said no. Then..."
+const NUMBER_ABBREVIATIONS: &[&str] = &["no.", "nr."];
+
+/// One sentence of the final text, for click-to-copy chips
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Sentence {
+    /// Exact slice of the text, trailing whitespace included, so the pieces concatenate back to it
+    pub text: String,
+    /// Character (not byte) offsets into the full text
+    pub start: usize,
+    pub end: usize,
+}
+
+/// "Dr." or "e.g." or an initial like "J.", or "No." before the `next` piece's number
+fn ends_with_abbreviation(piece: &str, next: &str) -> bool {
+    let Some(last_word) = piece.split_whitespace().last() else {
+        return false;
+    };
+    let lowered = last_word.to_lowercase();
+    if ABBREVIATIONS.contains(&lowered.as_str()) {
+        return true;
+    }
+    if NUMBER_ABBREVIATIONS.contains(&lowered.as_str()) {
+        return next.trim_start().starts_with(|c: char| c.is_ascii_digit());
+    }
+    let mut chars = last_word.chars();
+    matches!((chars.next(), chars.next(), chars.next()), (Some(c), Some('.'), None) if c.is_uppercase())
+}
+
+/// Unicode sentence boundaries, minus the ones right after an abbreviation.
+/// Returns byte ranges that cover `text` exactly.
+pub fn split_sentences(text: &str) -> Vec<(usize, usize)> {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for (start, piece) in text.split_sentence_bound_indices() {
+        let end = start + piece.len();
+        match ranges.last_mut() {
+            Some(last) if ends_with_abbreviation(&text[last.0..last.1], piece) => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+    ranges
+}
+
+/// Sentences of `text` with character offsets
+pub fn sentences(text: &str) -> Vec<Sentence> {
+    let mut char_offset = 0;
+    split_sentences(text)
+        .into_iter()
+        .map(|(start, end)| {
+            let piece = &text[start..end];
+            let chars = piece.chars().count();
+            let sentence = Sentence {
+                text: piece.to_string(),
+                start: char_offset,
+                end: char_offset + chars,
+            };
+            char_offset += chars;
+            sentence
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pieces(text: &str) -> Vec<&str> {
+        split_sentences(text).into_iter().map(|(start, end)| &text[start..end]).collect()
+    }
+
+    #[test]
+    fn no_ends_a_sentence_unless_a_number_follows() {
+        assert_eq!(pieces("I said no. Then we left."), vec!["I said no. ", "Then we left."]);
+        assert_eq!(pieces("Room No. 5 is free. Take it."), vec!["Room No. 5 is free. ", "Take it."]);
+        assert_eq!(pieces("See nr. 12 below."), vec!["See nr. 12 below."]);
+    }
+
+    #[test]
+    fn abbreviations_and_initials_dont_split() {
+        assert_eq!(pieces("Ask Dr. Smith. He knows."), vec!["Ask Dr. Smith. ", "He knows."]);
+        assert_eq!(pieces("Fruit, e.g. apples, is fine. Yes."), vec!["Fruit, e.g. apples, is fine. ", "Yes."]);
+        assert_eq!(pieces("Written by J. Doe. Great book."), vec!["Written by J. Doe. ", "Great book."]);
+    }
+
+    #[test]
+    fn pieces_concatenate_back_to_the_text() {
+        let inputs = ["", "no punctuation at all", "One.  Two!\nThree? ", "Ünïcödé wörds. Ñext one…  Done."];
+        for text in inputs {
+            assert_eq!(pieces(text).concat(), text);
+        }
+    }
+
+    #[test]
+    fn offsets_count_characters() {
+        let text = "Grüße aus Köln. Schön hier.";
+        let sentences = sentences(text);
+        assert_eq!(sentences.len(), 2);
+        assert_eq!((sentences[0].start, sentences[0].end), (0, 16));
+        assert_eq!((sentences[1].start, sentences[1].end), (16, text.chars().count()));
+        let rebuilt: String = sentences.iter().map(|sentence| sentence.text.as_str()).collect();
+        assert_eq!(rebuilt, text);
+    }
+}
@@ -0,0 +1,229 @@
+//! Long-running churn test: cycles recordings through VAD and the speech model
+//! while watching RSS and open file descriptors for leaks. Device loss is injected
+//! as a stream error on a live capture, which is then rebuilt the way the app does.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use sysinfo::System;
+use tokio::sync::mpsc;
+use tracing::{debug, error, info};
+
+use crate::audio::{AudioCapture, AudioChunk, VoiceActivityDetector, AUDIO_CHANNEL_CHUNKS, TARGET_RATE};
+use crate::compose::ComposeSession;
+use crate::config::Config;
+use crate::output::UtteranceMeta;
+use crate::retained::RecordingRetainer;
+use crate::speech::memory::MB;
+use crate::speech::SpeechTranscriber;
+
+/// Cycles run before the baseline is taken, so allocator warm-up isn't counted
+const WARMUP_FRACTION: f64 = 0.1;
+/// Growth over the baseline that counts as a leak
+const MAX_RSS_GROWTH_BYTES: u64 = 64 * MB;
+const MAX_FD_GROWTH: usize = 16;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SoakSample {
+    pub cycle: usize,
+    pub elapsed_secs: f32,
+    pub rss_bytes: u64,
+    pub open_fds: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct SoakOptions {
+    pub cycles: usize,
+    /// Sample resources every N cycles
+    pub sample_every: usize,
+    /// Reload the speech model every N cycles (0 = never)
+    pub model_swap_every: usize,
+    /// Re-read config.toml every N cycles (0 = never)
+    pub config_reload_every: usize,
+    /// Inject a device loss into live capture every N cycles (0 = never, no capture opened)
+    pub device_loss_every: usize,
+}
+
+/// Live capture that gets "lost" and reconnected on request
+struct CaptureChurn {
+    capture: AudioCapture,
+    audio_tx: mpsc::Sender<AudioChunk>,
+    error_tx: mpsc::UnboundedSender<String>,
+    error_rx: mpsc::UnboundedReceiver<String>,
+    reconnects: usize,
+}
+
+impl CaptureChurn {
+    async fn start(config: &Config) -> Result<Self> {
+        // Nobody listens to the audio, it only has to keep flowing
+        let (audio_tx, mut audio_rx) = mpsc::channel::<AudioChunk>(AUDIO_CHANNEL_CHUNKS);
+        tokio::spawn(async move { while audio_rx.recv().await.is_some() {} });
+
+        let (error_tx, error_rx) = mpsc::unbounded_channel();
+        let mut capture = AudioCapture::with_device(config.audio.device.as_deref())?;
+        capture.set_buffer_duration_ms(config.audio.buffer_duration_ms);
+        capture.set_channel_mode(config.audio.channel_mode);
+        capture.set_error_sender(error_tx.clone());
+        capture.start_capture(audio_tx.clone()).await?;
+        Ok(Self { capture, audio_tx, error_tx, error_rx, reconnects: 0 })
+    }
+
+    /// Report a stream error on the capture's error channel, as its error callback
+    /// would, then tear the stream down and reconnect like the app's recovery loop
+    async fn lose_device(&mut self, preferred: Option<&str>) -> Result<()> {
+        self.error_tx.send("injected device loss".to_string())?;
+        let message = self.error_rx.recv().await.unwrap_or_default();
+        debug!("Soak: input device lost ({}), reconnecting", message);
+        self.capture.stop_capture();
+        self.capture.reconnect(preferred, self.audio_tx.clone()).await?;
+        // The old stream may have reported errors while it was torn down
+        while self.error_rx.try_recv().is_ok() {}
+        self.reconnects += 1;
+        Ok(())
+    }
+}
+
+/// Run the soak loop; errors (after dumping the timeline) if resources grow past the envelope
+pub async fn run(config: &Config, options: &SoakOptions) -> Result<()> {
+    let mut transcriber = load_transcriber(config, 0)?;
+    let mut vad = VoiceActivityDetector::new(
+        &config.vad.model_path,
+        TARGET_RATE,
+        config.vad.sensitivity.to_threshold(),
+        config.vad.timeout_ms,
+        config.vad.min_speech_ms,
+        config.vad.hangover_ms,
+        config.vad.engine,
+        config.vad.frame_ms,
+    )?;
+    let mut retainer = RecordingRetainer::new(16000 * 30, Duration::from_secs(1));
+    let mut compose = ComposeSession::default();
+    let mut capture = match options.device_loss_every {
+        0 => None,
+        _ => Some(CaptureChurn::start(config).await?),
+    };
+
+    let mut system = System::new();
+    let pid = sysinfo::get_current_pid().map_err(|e| anyhow::anyhow!("No pid: {}", e))?;
+    let started = Instant::now();
+    let warmup = ((options.cycles as f64 * WARMUP_FRACTION) as usize).max(1);
+    let mut baseline: Option<SoakSample> = None;
+    let mut timeline = Vec::new();
+
+    info!("🧪 Soak test: {} cycles, baseline after {}", options.cycles, warmup);
+
+    for cycle in 0..options.cycles {
+        // One recording: VAD over synthetic audio, then decode, retain, compose
+        let audio = synthetic_audio(cycle);
+        vad.reset();
+        for chunk in audio.chunks(1024) {
+            vad.process_audio(chunk);
+        }
+        match cycle % 3 {
+            // Every third take is "cancelled" before decoding
+            0 => retainer.clear(),
+            _ => {
+                let _ = transcriber.transcribe(&audio).await;
+                retainer.retain(cycle as u64, &audio);
+            }
+        }
+        if cycle % 10 == 0 {
+            compose.start();
+        }
+        let take = UtteranceMeta {
+            recording_id: cycle as u64,
+            language: config.speech.language.clone(),
+            profile: None,
+            started_at_ms: 0,
+            ended_at_ms: 0,
+            duration_ms: 0,
+        };
+        compose.append("soak test take", &take);
+        if cycle % 10 == 9 {
+            compose.finish();
+        }
+        retainer.expire();
+
+        if options.model_swap_every > 0 && cycle > 0 && cycle % options.model_swap_every == 0 {
+            drop(transcriber);
+            transcriber = load_transcriber(config, cycle / options.model_swap_every)?;
+        }
+        if let Some(ref mut capture) = capture {
+            if cycle > 0 && cycle % options.device_loss_every == 0 {
+                capture.lose_device(config.audio.device.as_deref()).await?;
+            }
+        }
+        if options.config_reload_every > 0 && cycle % options.config_reload_every == 0 {
+            if let Err(e) = Config::load() {
+                error!("Config reload failed during soak: {}", e);
+            }
+        }
+
+        if cycle % options.sample_every.max(1) == 0 || cycle + 1 == options.cycles {
+            let sample = sample(&mut system, pid, cycle, started);
+            info!(
+                "cycle {:>6}: rss {} MB, fds {}",
+                cycle,
+                sample.rss_bytes / MB,
+                sample.open_fds
+            );
+            timeline.push(sample.clone());
+
+            if cycle >= warmup {
+                let base = baseline.get_or_insert_with(|| sample.clone());
+                let rss_growth = sample.rss_bytes.saturating_sub(base.rss_bytes);
+                let fd_growth = sample.open_fds.saturating_sub(base.open_fds);
+                if rss_growth > MAX_RSS_GROWTH_BYTES || fd_growth > MAX_FD_GROWTH {
+                    error!("Soak timeline: {}", serde_json::to_string(&timeline)?);
+                    return Err(anyhow::anyhow!(
+                        "Resource envelope exceeded at cycle {}: RSS +{} MB, fds +{}",
+                        cycle,
+                        rss_growth / MB,
+                        fd_growth
+                    ));
+                }
+            }
+        }
+    }
+
+    info!(
+        "✅ Soak test passed: {} cycles, {} device reconnects in {:.1}s",
+        options.cycles,
+        capture.map_or(0, |capture| capture.reconnects),
+        started.elapsed().as_secs_f32()
+    );
+    Ok(())
+}
+
+/// Alternates between the main and re-decode models when both are configured
+fn load_transcriber(config: &Config, swap: usize) -> Result<SpeechTranscriber> {
+    let model_dir = match (&config.retranscribe.model_dir, swap % 2) {
+        (Some(alternate), 1) => alternate,
+        _ => &config.speech.model_dir,
+    };
+    SpeechTranscriber::new(model_dir, Some(&config.speech.language), 0, config.speech.decode_policy())
+}
+
+/// 0.5-2s of tone bursts and silence, varying per cycle
+fn synthetic_audio(cycle: usize) -> Vec<f32> {
+    let samples = 8000 + (cycle % 4) * 8000;
+    (0..samples)
+        .map(|i| {
+            let t = i as f32 / 16000.0;
+            let burst = if (i / 4000) % 2 == 0 { 0.2 } else { 0.0 };
+            burst * (2.0 * std::f32::consts::PI * (200.0 + cycle as f32 % 300.0) * t).sin()
+        })
+        .collect()
+}
+
+fn sample(system: &mut System, pid: sysinfo::Pid, cycle: usize, started: Instant) -> SoakSample {
+    system.refresh_process(pid);
+    let rss_bytes = system.process(pid).map(|process| process.memory()).unwrap_or(0);
+    let open_fds = std::fs::read_dir("/proc/self/fd").map(|dir| dir.count()).unwrap_or(0);
+    SoakSample {
+        cycle,
+        elapsed_secs: started.elapsed().as_secs_f32(),
+        rss_bytes,
+        open_fds,
+    }
+}
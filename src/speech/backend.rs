@@ -0,0 +1,221 @@
+use anyhow::Result;
+use futures_util::future::{BoxFuture, FutureExt};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use super::remote::{RemoteTranscriber, RemoteTranscriptionConfig};
+use super::transcriber::SpeechTranscriber;
+use super::variant::ModelInfo;
+use crate::audio::TARGET_RATE;
+use crate::config::SpeechConfig;
+use crate::error::PipelineError;
+
+/// Recordings shorter than this can't contain a word
+const MIN_AUDIO_MS: u64 = 100;
+
+/// Where decoding happens (speech.backend)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpeechBackend {
+    /// The Parakeet model in speech.model_dir
+    #[default]
+    Local,
+    /// An OpenAI-compatible transcription endpoint ([remote_transcription])
+    Remote,
+}
+
+/// Something that turns 16kHz mono audio into text
+pub trait TranscriberBackend: Send + Sync {
+    /// Short name for logs
+    fn name(&self) -> &'static str;
+
+    fn transcribe<'a>(&'a self, audio: &'a [f32]) -> BoxFuture<'a, Result<String>>;
+
+    /// The local model in use, if the backend has one
+    fn model_info(&self) -> Option<ModelInfo> {
+        None
+    }
+
+    /// Set when the local model was loaded with less memory headroom than configured
+    fn low_memory_warning(&self) -> Option<String> {
+        None
+    }
+
+    /// Load the local model's files again (speech.watch_model)
+    fn reload(&self) -> BoxFuture<'_, Result<ModelInfo>> {
+        let name = self.name();
+        async move { Err(anyhow::anyhow!("The {} backend has no model to reload", name)) }.boxed()
+    }
+}
+
+/// Transcribe a recording, rejecting one too short to hold a word
+pub async fn transcribe_recording(backend: &dyn TranscriberBackend, audio: &[f32]) -> Result<String, PipelineError> {
+    let duration_ms = audio.len() as u64 * 1000 / TARGET_RATE as u64;
+    if duration_ms < MIN_AUDIO_MS {
+        return Err(PipelineError::AudioTooShort { duration_ms });
+    }
+
+    backend
+        .transcribe(audio)
+        .await
+        .map_err(|source| PipelineError::ModelDecode { source })
+}
+
+/// Whether the local model has to be loaded: it decodes, or backs up the remote endpoint
+pub fn uses_local_model(speech: &SpeechConfig, remote: &RemoteTranscriptionConfig) -> bool {
+    speech.backend == SpeechBackend::Local || remote.fallback_to_local
+}
+
+/// Put together the backend speech.backend selects; `load_local` is slow and only
+/// called when the local model decodes or is the remote endpoint's fallback
+pub fn select(
+    remote: Option<RemoteTranscriber>,
+    fallback_to_local: bool,
+    load_local: impl FnOnce() -> Result<SpeechTranscriber>,
+) -> Result<Box<dyn TranscriberBackend>> {
+    Ok(match remote {
+        None => Box::new(load_local()?),
+        Some(remote) if fallback_to_local => {
+            info!("Transcribing with the remote backend, local model as fallback");
+            Box::new(WithFallback { primary: Box::new(remote), fallback: Box::new(load_local()?) })
+        }
+        Some(remote) => {
+            info!("Transcribing with the remote backend; the local model isn't loaded");
+            Box::new(remote)
+        }
+    })
+}
+
+/// The backend the config selects, for the one-shot commands
+pub fn from_config(speech: &SpeechConfig, remote: &RemoteTranscriptionConfig) -> Result<Box<dyn TranscriberBackend>> {
+    select(RemoteTranscriber::from_config(speech, remote)?, remote.fallback_to_local, || {
+        let mut transcriber = SpeechTranscriber::new(
+            &speech.model_dir,
+            Some(&speech.language),
+            speech.min_memory_headroom_mb,
+            speech.decode_policy(),
+        )?;
+        transcriber.set_hallucination_blocklist(&speech.hallucination_blocklist);
+        Ok(transcriber)
+    })
+}
+
+/// Decodes go to `primary`, and to `fallback` when that fails. The model details
+/// are the fallback's, since only a local model has any.
+struct WithFallback {
+    primary: Box<dyn TranscriberBackend>,
+    fallback: Box<dyn TranscriberBackend>,
+}
+
+impl TranscriberBackend for WithFallback {
+    fn name(&self) -> &'static str {
+        self.primary.name()
+    }
+
+    fn transcribe<'a>(&'a self, audio: &'a [f32]) -> BoxFuture<'a, Result<String>> {
+        async move {
+            match self.primary.transcribe(audio).await {
+                Ok(text) => Ok(text),
+                Err(e) => {
+                    warn!("⚠️  {} transcription failed, decoding with the {} backend: {:#}", self.primary.name(), self.fallback.name(), e);
+                    self.fallback.transcribe(audio).await
+                }
+            }
+        }
+        .boxed()
+    }
+
+    fn model_info(&self) -> Option<ModelInfo> {
+        self.fallback.model_info()
+    }
+
+    fn low_memory_warning(&self) -> Option<String> {
+        self.fallback.low_memory_warning()
+    }
+
+    fn reload(&self) -> BoxFuture<'_, Result<ModelInfo>> {
+        self.fallback.reload()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{self, respond};
+
+    /// A backend that answers with fixed text
+    struct Fixed(&'static str);
+
+    impl TranscriberBackend for Fixed {
+        fn name(&self) -> &'static str {
+            "fixed"
+        }
+
+        fn transcribe<'a>(&'a self, _audio: &'a [f32]) -> BoxFuture<'a, Result<String>> {
+            async move { Ok(self.0.to_string()) }.boxed()
+        }
+    }
+
+    fn remote(url: String) -> RemoteTranscriber {
+        let config = RemoteTranscriptionConfig { url, ..Default::default() };
+        RemoteTranscriber::new(config, None).unwrap()
+    }
+
+    #[test]
+    fn remote_without_fallback_never_loads_the_local_model() {
+        let backend = select(Some(remote("http://127.0.0.1:9".to_string())), false, || {
+            panic!("the local model was loaded")
+        })
+        .unwrap();
+
+        assert_eq!(backend.name(), "remote");
+        assert!(backend.model_info().is_none());
+    }
+
+    #[test]
+    fn the_local_model_is_needed_to_decode_or_fall_back() {
+        let mut speech = SpeechConfig::default();
+        let mut remote = RemoteTranscriptionConfig::default();
+        for (backend, fallback_to_local, needed) in [
+            (SpeechBackend::Local, false, true),
+            (SpeechBackend::Local, true, true),
+            (SpeechBackend::Remote, true, true),
+            (SpeechBackend::Remote, false, false),
+        ] {
+            speech.backend = backend;
+            remote.fallback_to_local = fallback_to_local;
+            assert_eq!(uses_local_model(&speech, &remote), needed, "{:?}, fallback {}", backend, fallback_to_local);
+        }
+    }
+
+    #[tokio::test]
+    async fn a_failed_remote_decode_falls_back() {
+        let url = test_server::serve(|_, _, stream| respond(stream, "500 Internal Server Error", &[], b"boom"));
+        let backend = WithFallback { primary: Box::new(remote(url)), fallback: Box::new(Fixed("decoded locally")) };
+
+        assert_eq!(backend.transcribe(&[0.1; 1600]).await.unwrap(), "decoded locally");
+    }
+
+    #[tokio::test]
+    async fn a_working_remote_isnt_second_guessed() {
+        let url = test_server::serve(|_, _, stream| respond(stream, "200 OK", &[], br#"{"text": "decoded remotely"}"#));
+        let backend = WithFallback { primary: Box::new(remote(url)), fallback: Box::new(Fixed("decoded locally")) };
+
+        assert_eq!(backend.name(), "remote");
+        assert_eq!(backend.transcribe(&[0.1; 1600]).await.unwrap(), "decoded remotely");
+    }
+
+    #[tokio::test]
+    async fn recordings_too_short_for_a_word_are_rejected() {
+        let short = transcribe_recording(&Fixed("hi"), &[0.0; 800]).await;
+        assert!(matches!(short, Err(PipelineError::AudioTooShort { duration_ms: 50 })));
+
+        assert_eq!(transcribe_recording(&Fixed("hi"), &[0.0; 1600]).await.unwrap(), "hi");
+    }
+
+    #[tokio::test]
+    async fn backends_without_a_model_cant_reload() {
+        assert!(Fixed("").reload().await.is_err());
+        assert!(Fixed("").low_memory_warning().is_none());
+    }
+}
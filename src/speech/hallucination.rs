@@ -0,0 +1,112 @@
+//! Phrases the model produces from silence or noise rather than speech, mostly video
+//! outros it picked up in training (speech.hallucination_blocklist).
+
+use crate::segments::split_sentences;
+
+pub const DEFAULT_BLOCKLIST: &[&str] = &[
+    "thanks for watching",
+    "thank you for watching",
+    "thanks for watching and see you next time",
+    "please subscribe",
+    "please like and subscribe",
+    "like and subscribe",
+    "don't forget to like and subscribe",
+    "subscribe to my channel",
+    "see you in the next video",
+    "see you next time",
+];
+
+/// Lowercase words without punctuation, so "Thanks for watching!" matches "thanks for watching"
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric() || *c == '\'')
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        })
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Drops sentences that are exactly a blocklisted phrase
+#[derive(Debug, Clone, Default)]
+pub struct HallucinationFilter {
+    phrases: Vec<String>,
+}
+
+impl HallucinationFilter {
+    pub fn new<S: AsRef<str>>(phrases: &[S]) -> Self {
+        Self {
+            phrases: phrases
+                .iter()
+                .map(|phrase| normalize(phrase.as_ref()))
+                .filter(|phrase| !phrase.is_empty())
+                .collect(),
+        }
+    }
+
+    /// `text` without its blocklisted sentences, with the sentences that were dropped
+    pub fn filter(&self, text: &str) -> (String, Vec<String>) {
+        if self.phrases.is_empty() {
+            return (text.to_string(), Vec::new());
+        }
+        let mut kept = String::new();
+        let mut dropped = Vec::new();
+        for (start, end) in split_sentences(text) {
+            let sentence = &text[start..end];
+            if self.phrases.contains(&normalize(sentence)) {
+                dropped.push(sentence.trim().to_string());
+            } else {
+                kept.push_str(sentence);
+            }
+        }
+        (kept.trim().to_string(), dropped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn defaults() -> HallucinationFilter {
+        HallucinationFilter::new(DEFAULT_BLOCKLIST)
+    }
+
+    #[test]
+    fn a_recording_that_is_only_an_outro_comes_out_empty() {
+        let (kept, dropped) = defaults().filter("Thanks for watching!");
+        assert_eq!(kept, "");
+        assert_eq!(dropped, vec!["Thanks for watching!"]);
+
+        let (kept, dropped) = defaults().filter("Thank you for watching. Please like and subscribe!");
+        assert_eq!(kept, "");
+        assert_eq!(dropped.len(), 2);
+    }
+
+    #[test]
+    fn only_whole_sentences_are_dropped() {
+        let (kept, dropped) = defaults().filter("Send the report today. THANKS FOR WATCHING! Then call Bob.");
+        assert_eq!(kept, "Send the report today. Then call Bob.");
+        assert_eq!(dropped, vec!["THANKS FOR WATCHING!"]);
+
+        // A blocklisted phrase inside a longer sentence is real speech
+        let text = "Thanks for watching the kids while I was out.";
+        assert_eq!(defaults().filter(text), (text.to_string(), Vec::new()));
+    }
+
+    #[test]
+    fn phrases_match_without_case_or_punctuation() {
+        let filter = HallucinationFilter::new(&["Don't forget to like & subscribe!"]);
+        assert_eq!(filter.filter("don't forget to like subscribe").0, "");
+        assert_eq!(filter.filter("Don't forget to like, and subscribe.").0, "Don't forget to like, and subscribe.");
+    }
+
+    #[test]
+    fn an_empty_blocklist_keeps_everything() {
+        let filter = HallucinationFilter::new::<&str>(&[]);
+        assert_eq!(filter.filter("Thanks for watching!"), ("Thanks for watching!".to_string(), Vec::new()));
+        assert_eq!(HallucinationFilter::new(&["", " ! "]).filter("Thanks for watching!").0, "Thanks for watching!");
+    }
+}
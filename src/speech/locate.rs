@@ -0,0 +1,171 @@
+//! Where to look for the speech model when speech.model_dir doesn't hold one:
+//! the same folder name under ./models, the data and cache directories, and the
+//! system-wide install location, in that order.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use super::transcriber::MODEL_FILES;
+use crate::paths;
+
+/// Where packages install models
+const SYSTEM_MODELS_DIR: &str = "/usr/share/tomchat/models";
+
+/// No directory on the search path holds a complete model
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelNotFound {
+    pub tried: Vec<PathBuf>,
+}
+
+impl fmt::Display for ModelNotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "No speech model found. Looked in:")?;
+        for path in &self.tried {
+            write!(f, "\n  {}", path.display())?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ModelNotFound {}
+
+/// The directories searched for a folder named like speech.model_dir, in order
+fn model_roots(working_dir: &Path) -> Vec<PathBuf> {
+    vec![
+        working_dir.join("models"),
+        paths::data_dir().join("models"),
+        paths::models_cache_dir(),
+        PathBuf::from(SYSTEM_MODELS_DIR),
+    ]
+}
+
+/// `configured` first, then a folder of the same name in each models directory
+pub fn search_path(configured: &Path, working_dir: &Path) -> Vec<PathBuf> {
+    search_path_in(configured, &model_roots(working_dir))
+}
+
+fn search_path_in(configured: &Path, roots: &[PathBuf]) -> Vec<PathBuf> {
+    let mut candidates = vec![configured.to_path_buf()];
+    if let Some(name) = configured.file_name() {
+        for dir in roots {
+            let candidate = dir.join(name);
+            if !candidates.contains(&candidate) {
+                candidates.push(candidate);
+            }
+        }
+    }
+    candidates
+}
+
+fn is_complete(dir: &Path) -> bool {
+    MODEL_FILES.iter().all(|name| dir.join(name).is_file())
+}
+
+fn first_complete(tried: Vec<PathBuf>) -> Result<PathBuf, ModelNotFound> {
+    match tried.iter().find(|dir| is_complete(dir)) {
+        Some(found) => Ok(found.clone()),
+        None => Err(ModelNotFound { tried }),
+    }
+}
+
+/// The first directory on the search path that has every model file
+pub fn locate_model(configured: &Path, working_dir: &Path) -> Result<PathBuf, ModelNotFound> {
+    first_complete(search_path(configured, working_dir))
+}
+
+/// A directory the user named explicitly (TOMCHAT_MODEL_DIR) has to hold the
+/// model itself; quietly loading some other model instead would hide the mistake
+pub fn require_model(dir: &Path) -> Result<PathBuf, ModelNotFound> {
+    first_complete(vec![dir.to_path_buf()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NAME: &str = "parakeet-test";
+
+    /// A scratch directory with one models root per search tier
+    fn tiers(test: &str) -> (PathBuf, Vec<PathBuf>) {
+        let base = std::env::temp_dir().join(format!("tomchat-locate-{}-{}", test, std::process::id()));
+        let _ = std::fs::remove_dir_all(&base);
+        let roots = ["working", "data", "cache", "system"].iter().map(|tier| base.join(tier)).collect();
+        (base, roots)
+    }
+
+    fn install(dir: &Path, files: &[&str]) {
+        std::fs::create_dir_all(dir).unwrap();
+        for file in files {
+            std::fs::write(dir.join(file), b"model").unwrap();
+        }
+    }
+
+    #[test]
+    fn each_tier_is_used_when_the_earlier_ones_are_empty() {
+        let (base, roots) = tiers("tiers");
+        let configured = base.join("configured").join(NAME);
+        let mut found = Vec::new();
+        // Fill the tiers from the back, so each pass has a new first match
+        for root in roots.iter().rev() {
+            install(&root.join(NAME), &MODEL_FILES);
+            found.push(first_complete(search_path_in(&configured, &roots)).unwrap());
+        }
+        install(&configured, &MODEL_FILES);
+        found.push(first_complete(search_path_in(&configured, &roots)).unwrap());
+        std::fs::remove_dir_all(&base).unwrap();
+
+        let mut expected: Vec<PathBuf> = roots.iter().rev().map(|root| root.join(NAME)).collect();
+        expected.push(configured);
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn an_incomplete_model_is_skipped() {
+        let (base, roots) = tiers("incomplete");
+        let configured = base.join("configured").join(NAME);
+        install(&configured, &MODEL_FILES[..1]);
+        install(&roots[2].join(NAME), &MODEL_FILES);
+
+        let found = first_complete(search_path_in(&configured, &roots));
+        std::fs::remove_dir_all(&base).unwrap();
+        assert_eq!(found.unwrap(), roots[2].join(NAME));
+    }
+
+    #[test]
+    fn not_found_lists_every_path_tried() {
+        let (base, roots) = tiers("missing");
+        let configured = base.join("configured").join(NAME);
+
+        let error = first_complete(search_path_in(&configured, &roots)).unwrap_err();
+        assert_eq!(error.tried.len(), 5);
+        assert_eq!(error.tried[0], configured);
+        let message = error.to_string();
+        for root in &roots {
+            assert!(message.contains(&root.join(NAME).display().to_string()), "{}", message);
+        }
+    }
+
+    #[test]
+    fn a_root_that_is_the_configured_dir_is_tried_once() {
+        let (base, roots) = tiers("duplicate");
+        let configured = roots[0].join(NAME);
+
+        assert_eq!(search_path_in(&configured, &roots).len(), 4);
+        assert!(!base.exists());
+    }
+
+    #[test]
+    fn a_required_model_has_no_fallback() {
+        let (base, roots) = tiers("required");
+        let explicit = base.join("explicit").join(NAME);
+        install(&roots[0].join(NAME), &MODEL_FILES);
+
+        let missing = require_model(&explicit);
+        install(&explicit, &MODEL_FILES);
+        let present = require_model(&explicit);
+        std::fs::remove_dir_all(&base).unwrap();
+
+        assert_eq!(missing.unwrap_err().tried, vec![explicit.clone()]);
+        assert_eq!(present.unwrap(), explicit);
+    }
+}
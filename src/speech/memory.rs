@@ -0,0 +1,67 @@
+use std::path::Path;
+use sysinfo::System;
+use tracing::debug;
+
+/// Overhead factor applied to on-disk model size to account for runtime state
+const MODEL_STATE_FACTOR: f64 = 1.2;
+
+#[derive(Debug, Clone)]
+pub struct MemoryEstimate {
+    pub required_bytes: u64,
+    pub available_bytes: u64,
+}
+
+impl MemoryEstimate {
+    /// Estimate memory needed for the given model files against what the system has free
+    pub fn for_model_files<P: AsRef<Path>>(paths: &[P]) -> Self {
+        let model_bytes: u64 = paths
+            .iter()
+            .filter_map(|p| std::fs::metadata(p).ok())
+            .map(|m| m.len())
+            .sum();
+
+        let mut system = System::new();
+        system.refresh_memory();
+
+        let estimate = Self {
+            required_bytes: (model_bytes as f64 * MODEL_STATE_FACTOR) as u64,
+            available_bytes: system.available_memory(),
+        };
+
+        debug!(
+            "Model memory estimate: {} MB required, {} MB available",
+            estimate.required_bytes / MB,
+            estimate.available_bytes / MB
+        );
+
+        estimate
+    }
+
+    /// Memory left over after loading the model (negative if it won't fit)
+    pub fn headroom_mb(&self) -> i64 {
+        (self.available_bytes as i64 - self.required_bytes as i64) / MB as i64
+    }
+
+    pub fn is_low(&self, min_headroom_mb: u64) -> bool {
+        self.headroom_mb() < min_headroom_mb as i64
+    }
+
+    pub fn warning_message(&self) -> String {
+        format!(
+            "Low memory: model needs ~{} MB but only {} MB is available. \
+             Close other applications or use a smaller model to avoid the OOM killer.",
+            self.required_bytes / MB,
+            self.available_bytes / MB
+        )
+    }
+}
+
+pub const MB: u64 = 1024 * 1024;
+
+/// Whether a model load error looks like an allocation failure
+pub fn is_allocation_failure(message: &str) -> bool {
+    let message = message.to_lowercase();
+    ["bad_alloc", "out of memory", "failed to allocate", "allocation failed"]
+        .iter()
+        .any(|needle| message.contains(needle))
+}
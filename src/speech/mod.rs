@@ -1,3 +1,15 @@
+pub mod backend;
+pub mod hallucination;
+pub mod locate;
+pub mod memory;
+pub mod priority;
+pub mod remote;
 pub mod transcriber;
+pub mod variant;
+pub mod watch;
 
-pub use transcriber::SpeechTranscriber;
\ No newline at end of file
+pub use backend::{SpeechBackend, TranscriberBackend};
+pub use priority::DecodePolicy;
+pub use remote::{RemoteTranscriber, RemoteTranscriptionConfig};
+pub use transcriber::SpeechTranscriber;
+pub use watch::spawn_model_watcher;
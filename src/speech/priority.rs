@@ -0,0 +1,244 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::panic::AssertUnwindSafe;
+use std::sync::mpsc;
+use sysinfo::System;
+use tracing::{debug, warn};
+
+/// Where decoding runs and how politely it shares the CPU with the desktop. Best-effort:
+/// the OS may refuse, and the GPU may be unavailable.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DecodePolicy {
+    /// Lower scheduling priority of the decode thread (nice 10 on Linux, background on macOS,
+    /// below-normal on Windows; unsupported elsewhere)
+    pub background_priority: bool,
+    /// Restrict decode threads to these CPU cores; empty = no restriction
+    pub cpu_affinity: Vec<usize>,
+    /// Load the model onto the GPU provider this build supports, if any
+    pub use_gpu: bool,
+    /// Decode threads; 0 = auto
+    pub threads: usize,
+    /// Beam search width; 0 or 1 decodes greedily
+    pub beam_size: usize,
+}
+
+impl DecodePolicy {
+    /// No thread priority or affinity to apply
+    pub fn is_default(&self) -> bool {
+        !self.background_priority && self.cpu_affinity.is_empty()
+    }
+
+    /// Apply to the calling thread, which must be one of ours: never a runtime or
+    /// blocking-pool thread that goes on to run other work. Threads it spawns afterwards
+    /// (e.g. onnxruntime's intra-op pool when the model is loaded here) inherit both
+    /// settings on Linux.
+    pub fn apply_to_current_thread(&self) {
+        if self.is_default() {
+            return;
+        }
+        if self.background_priority {
+            match lower_priority() {
+                Ok(()) => debug!("Decode thread priority lowered"),
+                Err(e) => warn!("⚠️  Couldn't lower decode thread priority: {}", e),
+            }
+        }
+        if !self.cpu_affinity.is_empty() {
+            match set_affinity(&self.cpu_affinity) {
+                Ok(()) => debug!("Decode thread pinned to cores {:?}", self.cpu_affinity),
+                Err(e) => warn!("⚠️  Couldn't restrict decode threads to cores {:?}: {}", self.cpu_affinity, e),
+            }
+        }
+    }
+
+    /// sherpa-onnx decoding method for the beam width. sherpa-rs doesn't expose the width
+    /// itself, so any beam wider than one turns on its modified beam search
+    pub fn decoding_method(&self) -> &'static str {
+        match self.beam_size {
+            0 | 1 => "greedy_search",
+            _ => "modified_beam_search",
+        }
+    }
+
+    /// Threads to decode with: as configured, or one per physical core we may run on
+    pub fn decode_threads(&self) -> usize {
+        if self.threads > 0 {
+            return self.threads;
+        }
+        let logical = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        auto_threads(System::new().physical_core_count(), logical, self.cpu_affinity.len())
+    }
+
+    /// onnxruntime execution provider for the GPU, if asked for and this build has one
+    pub fn gpu_provider(&self) -> Option<&'static str> {
+        if !self.use_gpu {
+            return None;
+        }
+        if cfg!(feature = "cuda") {
+            Some("cuda")
+        } else if cfg!(feature = "directml") {
+            Some("directml")
+        } else if cfg!(target_os = "macos") {
+            Some("coreml")
+        } else {
+            None
+        }
+    }
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// A thread of our own that runs decodes under the decode policy, so lowered
+/// priority or pinning never sticks to a tokio blocking-pool thread
+pub struct DecodeThread {
+    jobs: mpsc::Sender<Job>,
+}
+
+impl DecodeThread {
+    pub fn spawn(policy: DecodePolicy) -> Result<Self> {
+        let (jobs, queue) = mpsc::channel::<Job>();
+        std::thread::Builder::new().name("tomchat-decode".to_string()).spawn(move || {
+            policy.apply_to_current_thread();
+            // Ends when the owner drops its sender
+            for job in queue {
+                job();
+            }
+        })?;
+        Ok(Self { jobs })
+    }
+
+    /// Run `work` on the decode thread, after any decode already queued
+    pub async fn run<T: Send + 'static>(&self, work: impl FnOnce() -> T + Send + 'static) -> Result<T> {
+        let (done, result) = tokio::sync::oneshot::channel();
+        let job: Job = Box::new(move || {
+            // A panicking decode drops `done`, which the caller sees as an error
+            if let Ok(value) = std::panic::catch_unwind(AssertUnwindSafe(work)) {
+                let _ = done.send(value);
+            }
+        });
+        self.jobs.send(job).map_err(|_| anyhow::anyhow!("Decode thread has exited"))?;
+        result.await.map_err(|_| anyhow::anyhow!("Decode panicked"))
+    }
+}
+
+/// Hyperthreads share a core's execution units, so matrix-heavy decoding gains nothing
+/// from them; fall back to logical cores when the physical count is unknown. This replaced
+/// a fixed 4 threads, which `speech.threads = 4` restores.
+fn auto_threads(physical: Option<usize>, logical: usize, pinned: usize) -> usize {
+    let cores = physical.filter(|&physical| physical > 0).unwrap_or(logical).min(logical);
+    match pinned {
+        0 => cores,
+        pinned => cores.min(pinned),
+    }
+    .max(1)
+}
+
+#[cfg(target_os = "linux")]
+fn lower_priority() -> std::io::Result<()> {
+    // On Linux PRIO_PROCESS with our thread id only affects this thread
+    let tid = unsafe { libc::syscall(libc::SYS_gettid) } as libc::id_t;
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, tid, 10) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn lower_priority() -> std::io::Result<()> {
+    // Background policy for the calling thread only
+    if unsafe { libc::setpriority(libc::PRIO_DARWIN_THREAD, 0, libc::PRIO_DARWIN_BG) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Elsewhere setpriority only takes whole processes, which would slow the UI too
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn lower_priority() -> std::io::Result<()> {
+    Err(std::io::Error::other("per-thread priority isn't supported on this OS"))
+}
+
+#[cfg(windows)]
+fn lower_priority() -> std::io::Result<()> {
+    use windows_sys::Win32::System::Threading::{GetCurrentThread, SetThreadPriority, THREAD_PRIORITY_BELOW_NORMAL};
+
+    if unsafe { SetThreadPriority(GetCurrentThread(), THREAD_PRIORITY_BELOW_NORMAL) } == 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn set_affinity(cores: &[usize]) -> std::io::Result<()> {
+    let available = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    if let Some(core) = cores.iter().find(|core| **core >= available) {
+        return Err(std::io::Error::other(format!("core {} doesn't exist ({} available)", core, available)));
+    }
+
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        for core in cores {
+            libc::CPU_SET(*core, &mut set);
+        }
+        if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_affinity(_cores: &[usize]) -> std::io::Result<()> {
+    Err(std::io::Error::other("CPU affinity is only supported on Linux"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn decodes_share_one_thread_of_their_own() {
+        let decoder = DecodeThread::spawn(DecodePolicy::default()).unwrap();
+        let first = decoder.run(|| std::thread::current().id()).await.unwrap();
+        let second = decoder.run(|| std::thread::current().id()).await.unwrap();
+        assert_eq!(first, second);
+        assert_ne!(first, std::thread::current().id());
+        assert!(decoder.run(|| panic!("decode failed")).await.is_err());
+        assert_eq!(decoder.run(|| 7).await.unwrap(), 7);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn background_priority_stays_on_the_decode_thread() {
+        let nice = || unsafe { libc::getpriority(libc::PRIO_PROCESS, libc::syscall(libc::SYS_gettid) as libc::id_t) };
+        let before = nice();
+        let policy = DecodePolicy { background_priority: true, ..Default::default() };
+        let decoder = DecodeThread::spawn(policy).unwrap();
+        assert_eq!(decoder.run(nice).await.unwrap(), (before + 10).min(19));
+        assert_eq!(nice(), before);
+    }
+
+    #[test]
+    fn auto_threads_prefers_physical_cores() {
+        // (physical, logical, pinned) -> threads
+        let cases = [
+            ((Some(8), 16, 0), 8),   // hyperthreaded desktop
+            ((Some(4), 4, 0), 4),    // no SMT
+            ((None, 12, 0), 12),     // topology unknown: logical cores
+            ((Some(0), 6, 0), 6),    // a zero count is as good as unknown
+            ((Some(8), 4, 0), 4),    // a cgroup or container limit below the core count
+            ((Some(8), 16, 2), 2),   // pinned to two cores
+            ((Some(2), 4, 6), 2),    // pinned to more cores than exist
+            ((None, 0, 0), 1),       // never zero
+        ];
+        for ((physical, logical, pinned), expected) in cases {
+            assert_eq!(auto_threads(physical, logical, pinned), expected, "{:?}", (physical, logical, pinned));
+        }
+    }
+
+    #[test]
+    fn configured_threads_win_over_auto_detection() {
+        let policy = DecodePolicy { threads: 3, cpu_affinity: vec![0], ..Default::default() };
+        assert_eq!(policy.decode_threads(), 3);
+        assert!(DecodePolicy::default().decode_threads() >= 1);
+    }
+}
@@ -0,0 +1,236 @@
+//! Decoding on another machine through an OpenAI-compatible `/v1/audio/transcriptions`
+//! endpoint (speech.backend = "remote"), e.g. a Whisper server on a desktop.
+
+use anyhow::{Context, Result};
+use futures_util::future::{BoxFuture, FutureExt};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tracing::{debug, info};
+
+use super::backend::{SpeechBackend, TranscriberBackend};
+use super::hallucination::{HallucinationFilter, DEFAULT_BLOCKLIST};
+use crate::audio::wav;
+use crate::config::SpeechConfig;
+use crate::privacy::{self, NetworkSite};
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RemoteTranscriptionConfig {
+    /// Full endpoint URL, including /v1/audio/transcriptions
+    #[serde(default = "default_url")]
+    pub url: String,
+    /// Sent as a bearer token
+    #[serde(default)]
+    pub api_key: Option<String>,
+    #[serde(default = "default_model")]
+    pub model: String,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+    /// Decode with the local model when the endpoint fails or times out
+    #[serde(default = "default_fallback_to_local")]
+    pub fallback_to_local: bool,
+}
+
+fn default_url() -> String {
+    "http://localhost:8000/v1/audio/transcriptions".to_string()
+}
+
+fn default_model() -> String {
+    "whisper-1".to_string()
+}
+
+fn default_timeout_ms() -> u64 {
+    30_000
+}
+
+fn default_fallback_to_local() -> bool {
+    true
+}
+
+impl Default for RemoteTranscriptionConfig {
+    fn default() -> Self {
+        Self {
+            url: default_url(),
+            api_key: None,
+            model: default_model(),
+            timeout_ms: default_timeout_ms(),
+            fallback_to_local: default_fallback_to_local(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct TranscriptionResponse {
+    text: String,
+}
+
+pub struct RemoteTranscriber {
+    config: RemoteTranscriptionConfig,
+    language: Option<String>,
+    client: reqwest::Client,
+    hallucinations: HallucinationFilter,
+}
+
+impl RemoteTranscriber {
+    pub fn new(config: RemoteTranscriptionConfig, language: Option<&str>) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(config.timeout_ms))
+            .build()?;
+        Ok(Self {
+            config,
+            language: language.map(str::to_string),
+            client,
+            hallucinations: HallucinationFilter::new(DEFAULT_BLOCKLIST),
+        })
+    }
+
+    /// The remote backend, if speech.backend asks for it
+    pub fn from_config(speech: &SpeechConfig, config: &RemoteTranscriptionConfig) -> Result<Option<Self>> {
+        match speech.backend {
+            SpeechBackend::Local => Ok(None),
+            SpeechBackend::Remote => {
+                let mut remote = Self::new(config.clone(), Some(&speech.language))?;
+                remote.set_hallucination_blocklist(&speech.hallucination_blocklist);
+                Ok(Some(remote))
+            }
+        }
+    }
+
+    /// Sentences dropped from every result when they're only one of these phrases
+    pub fn set_hallucination_blocklist<S: AsRef<str>>(&mut self, phrases: &[S]) {
+        self.hallucinations = HallucinationFilter::new(phrases);
+    }
+
+    /// Whisper makes things up from silence too, so its results get the same filter
+    async fn transcribe_audio(&self, audio: &[f32]) -> Result<String> {
+        if audio.is_empty() {
+            return Ok(String::new());
+        }
+
+        let start = Instant::now();
+        let text = self.post(audio).await?;
+        let (cleaned, dropped) = self.hallucinations.filter(text.trim());
+        for sentence in dropped {
+            debug!("Dropped blocklisted sentence: \"{}\"", sentence);
+        }
+        info!("Remote transcription complete in {:.2}s: \"{}\"", start.elapsed().as_secs_f32(), cleaned);
+        Ok(cleaned)
+    }
+
+    async fn post(&self, audio: &[f32]) -> Result<String> {
+        privacy::check(NetworkSite::RemoteTranscription, &self.config.url)?;
+
+        let file = reqwest::multipart::Part::bytes(wav::encode(audio)?)
+            .file_name("audio.wav")
+            .mime_str("audio/wav")?;
+        let mut form = reqwest::multipart::Form::new()
+            .part("file", file)
+            .text("model", self.config.model.clone())
+            .text("response_format", "json");
+        if let Some(ref language) = self.language {
+            form = form.text("language", language.clone());
+        }
+
+        let mut request = self.client.post(&self.config.url).multipart(form);
+        if let Some(ref api_key) = self.config.api_key {
+            request = request.bearer_auth(api_key);
+        }
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("Transcription request to {} failed", self.config.url))?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!("{} answered {}: {}", self.config.url, status, body.trim()));
+        }
+        let response: TranscriptionResponse = response
+            .json()
+            .await
+            .with_context(|| format!("{} didn't return a transcription", self.config.url))?;
+        Ok(response.text)
+    }
+}
+
+impl TranscriberBackend for RemoteTranscriber {
+    fn name(&self) -> &'static str {
+        "remote"
+    }
+
+    fn transcribe<'a>(&'a self, audio: &'a [f32]) -> BoxFuture<'a, Result<String>> {
+        self.transcribe_audio(audio).boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{self, respond};
+    use std::sync::mpsc;
+
+    fn remote(url: String, timeout_ms: u64) -> RemoteTranscriber {
+        let config = RemoteTranscriptionConfig {
+            url: format!("{}/v1/audio/transcriptions", url),
+            api_key: Some("secret".to_string()),
+            model: "test-model".to_string(),
+            timeout_ms,
+            ..Default::default()
+        };
+        RemoteTranscriber::new(config, Some("en")).unwrap()
+    }
+
+    #[tokio::test]
+    async fn uploads_the_recording_and_filters_the_text() {
+        let (requests, received) = mpsc::channel();
+        let url = test_server::serve(move |_, request, stream| {
+            let body = String::from_utf8_lossy(&request.body).into_owned();
+            requests.send((request.method.clone(), request.path.clone(), request.header("authorization").map(str::to_string), body)).unwrap();
+            respond(stream, "200 OK", &[("Content-Type", "application/json".to_string())], br#"{"text": " Hello there. Thanks for watching! "}"#);
+        });
+
+        let text = remote(url, 5_000).transcribe(&[0.1; 1600]).await.unwrap();
+
+        assert_eq!(text, "Hello there.");
+        let (method, path, authorization, body) = received.recv().unwrap();
+        assert_eq!((method.as_str(), path.as_str()), ("POST", "/v1/audio/transcriptions"));
+        assert_eq!(authorization.as_deref(), Some("Bearer secret"));
+        for part in ["name=\"file\"; filename=\"audio.wav\"", "RIFF", "name=\"model\"", "test-model", "name=\"language\"", "en"] {
+            assert!(body.contains(part), "missing {:?}", part);
+        }
+    }
+
+    #[tokio::test]
+    async fn an_error_status_fails_with_the_servers_message() {
+        let url = test_server::serve(|_, _, stream| respond(stream, "503 Service Unavailable", &[], b"model still loading"));
+
+        let error = remote(url, 5_000).transcribe(&[0.1; 1600]).await.unwrap_err().to_string();
+
+        assert!(error.contains("503") && error.contains("model still loading"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn a_slow_server_times_out() {
+        let url = test_server::serve(|_, _, stream| {
+            std::thread::sleep(Duration::from_millis(1_000));
+            respond(stream, "200 OK", &[], br#"{"text": "too late"}"#);
+        });
+
+        assert!(remote(url, 100).transcribe(&[0.1; 1600]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn a_malformed_response_is_an_error() {
+        let url = test_server::serve(|_, _, stream| respond(stream, "200 OK", &[], b"<html>not json</html>"));
+
+        let error = remote(url, 5_000).transcribe(&[0.1; 1600]).await.unwrap_err().to_string();
+
+        assert!(error.contains("didn't return a transcription"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn empty_audio_isnt_sent() {
+        // Nothing listens here, so a request would fail
+        let text = remote("http://127.0.0.1:9".to_string(), 5_000).transcribe(&[]).await.unwrap();
+
+        assert_eq!(text, "");
+    }
+}
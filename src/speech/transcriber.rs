@@ -1,25 +1,99 @@
 use anyhow::Result;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use tracing::{error, info, debug};
+use tracing::{error, info, debug, warn};
+use futures_util::future::{BoxFuture, FutureExt};
 use sherpa_rs::transducer::{TransducerConfig, TransducerRecognizer};
 
+use super::backend::{self, TranscriberBackend};
+use super::hallucination::{HallucinationFilter, DEFAULT_BLOCKLIST};
+use super::memory::{self, MemoryEstimate};
+use super::priority::{DecodePolicy, DecodeThread};
+use super::variant::{ModelInfo, ModelVariant};
+use crate::error::PipelineError;
+
+/// Files loaded from the model directory
+pub const MODEL_FILES: [&str; 4] = ["encoder.int8.onnx", "decoder.int8.onnx", "joiner.int8.onnx", "tokens.txt"];
+
 pub struct SpeechTranscriber {
     recognizer: Arc<RwLock<TransducerRecognizer>>,
     sample_rate: u32,
+    memory_estimate: MemoryEstimate,
+    min_memory_headroom_mb: u64,
+    /// Changes when the model is reloaded
+    info: std::sync::Mutex<ModelInfo>,
+    decode_policy: DecodePolicy,
+    decoder: DecodeThread,
+    model_dir: PathBuf,
+    language: Option<String>,
+    hallucinations: HallucinationFilter,
 }
 
 impl SpeechTranscriber {
-    pub fn new<P: AsRef<Path>>(model_dir: P, _language: Option<&str>) -> Result<Self> {
-        let model_path = model_dir.as_ref();
+    pub fn new<P: AsRef<Path>>(
+        model_dir: P,
+        language: Option<&str>,
+        min_memory_headroom_mb: u64,
+        decode_policy: DecodePolicy,
+    ) -> Result<Self> {
+        let (recognizer, info, memory_estimate) =
+            Self::load(model_dir.as_ref(), language, min_memory_headroom_mb, &decode_policy)?;
+
+        Ok(Self {
+            recognizer: Arc::new(RwLock::new(recognizer)),
+            sample_rate: 16_000,
+            memory_estimate,
+            min_memory_headroom_mb,
+            info: std::sync::Mutex::new(info),
+            decoder: DecodeThread::spawn(decode_policy.clone())?,
+            decode_policy,
+            model_dir: model_dir.as_ref().to_path_buf(),
+            language: language.map(str::to_string),
+            hallucinations: HallucinationFilter::new(DEFAULT_BLOCKLIST),
+        })
+    }
+
+    /// Load the model files from disk again and swap them in. Loading happens beside the
+    /// current model, which keeps serving decodes until the swap, so memory peaks at both
+    /// models for the length of the load; the headroom check sees the old one as in use.
+    /// The old model is freed before this returns.
+    pub async fn reload(&self) -> Result<ModelInfo> {
+        let model_dir = self.model_dir.clone();
+        let language = self.language.clone();
+        let min_memory_headroom_mb = self.min_memory_headroom_mb;
+        let decode_policy = self.decode_policy.clone();
+        let (recognizer, info, _) = tokio::task::spawn_blocking(move || {
+            Self::load(&model_dir, language.as_deref(), min_memory_headroom_mb, &decode_policy)
+        })
+        .await??;
+
+        // A decode in progress finishes on the old model first
+        let previous = std::mem::replace(&mut *self.recognizer.write().await, recognizer);
+        *self.info.lock().unwrap() = info.clone();
+        tokio::task::spawn_blocking(move || {
+            let _previous = previous;
+        })
+        .await?;
+        Ok(info)
+    }
+
+    fn load(
+        model_path: &Path,
+        language: Option<&str>,
+        min_memory_headroom_mb: u64,
+        decode_policy: &DecodePolicy,
+    ) -> Result<(TransducerRecognizer, ModelInfo, MemoryEstimate)> {
         info!("Loading Parakeet model from: {:?}", model_path);
 
+        let variant = ModelVariant::detect(model_path);
+        info!("Detected model variant: {}", variant.display_name());
+        for warning in variant.config_warnings(language) {
+            warn!("⚠️  {}", warning);
+        }
+
         // Build paths to the ONNX model files
-        let encoder_path = model_path.join("encoder.int8.onnx");
-        let decoder_path = model_path.join("decoder.int8.onnx");
-        let joiner_path = model_path.join("joiner.int8.onnx");
-        let tokens_path = model_path.join("tokens.txt");
+        let [encoder_path, decoder_path, joiner_path, tokens_path] = MODEL_FILES.map(|name| model_path.join(name));
 
         // Verify files exist
         for path in [&encoder_path, &decoder_path, &joiner_path, &tokens_path] {
@@ -31,28 +105,97 @@ impl SpeechTranscriber {
             }
         }
 
-        let config = TransducerConfig {
+        // Check we have room for the model before loading it
+        let memory_estimate = MemoryEstimate::for_model_files(&[&encoder_path, &decoder_path, &joiner_path]);
+        if memory_estimate.is_low(min_memory_headroom_mb) {
+            warn!("⚠️  {}", memory_estimate.warning_message());
+        }
+
+        let threads = decode_policy.decode_threads();
+        info!("Decoding with {} thread(s), {}", threads, decode_policy.decoding_method());
+
+        let config = |provider: Option<&str>| TransducerConfig {
             encoder: encoder_path.to_string_lossy().to_string(),
             decoder: decoder_path.to_string_lossy().to_string(),
             joiner: joiner_path.to_string_lossy().to_string(),
             tokens: tokens_path.to_string_lossy().to_string(),
-            num_threads: 4,  // Use multiple threads for faster inference
+            num_threads: threads as i32,
             sample_rate: 16_000,
             feature_dim: 80,
+            decoding_method: decode_policy.decoding_method().to_string(),
             debug: false,
-            model_type: "nemo_transducer".to_string(),
+            model_type: variant.model_type().to_string(),
+            provider: provider.map(str::to_string),
             ..Default::default()
         };
 
-        let recognizer = TransducerRecognizer::new(config)
-            .map_err(|e| anyhow::anyhow!("Failed to create Parakeet recognizer: {}", e))?;
+        if !decode_policy.is_default() {
+            info!("Decode policy: background priority {}, cores {:?}", decode_policy.background_priority, decode_policy.cpu_affinity);
+        }
+
+        // Load on a fresh thread carrying the decode policy, so onnxruntime's
+        // worker threads (created here) inherit it without affecting our caller
+        let create = |provider: Option<&str>| {
+            let config = config(provider);
+            std::thread::scope(|scope| {
+                scope
+                    .spawn(|| {
+                        decode_policy.apply_to_current_thread();
+                        TransducerRecognizer::new(config)
+                    })
+                    .join()
+            })
+            .map_err(|_| anyhow::anyhow!("Parakeet model loader thread panicked"))
+        };
+
+        let gpu_provider = decode_policy.gpu_provider();
+        if decode_policy.use_gpu && gpu_provider.is_none() {
+            warn!("⚠️  speech.use_gpu is set but this build has no GPU support (build with --features cuda or directml); decoding on CPU");
+        }
+        let (loaded, active_provider) = match gpu_provider {
+            Some(provider) => match create(Some(provider))? {
+                Ok(recognizer) => {
+                    info!("🚀 GPU offload active ({})", provider);
+                    (Ok(recognizer), Some(provider))
+                }
+                Err(e) => {
+                    warn!("⚠️  Couldn't load the model on the GPU ({}), falling back to CPU: {}", provider, e);
+                    (create(None)?, None)
+                }
+            },
+            None => (create(None)?, None),
+        };
+        if !decode_policy.use_gpu {
+            info!("Decoding on CPU");
+        }
+
+        let recognizer = loaded.map_err(|e| {
+            if memory::is_allocation_failure(&e.to_string()) {
+                anyhow::Error::new(PipelineError::ModelOutOfMemory {
+                    required_mb: memory_estimate.required_bytes / memory::MB,
+                    available_mb: memory_estimate.available_bytes / memory::MB,
+                    source: anyhow::anyhow!("{}", e),
+                })
+            } else {
+                anyhow::anyhow!("Failed to create Parakeet recognizer: {}", e)
+            }
+        })?;
 
         info!("Parakeet model loaded successfully");
 
-        Ok(Self {
-            recognizer: Arc::new(RwLock::new(recognizer)),
-            sample_rate: 16_000,
-        })
+        Ok((recognizer, ModelInfo::read(model_path, variant, active_provider), memory_estimate))
+    }
+
+    /// Sentences dropped from every result when they're only one of these phrases
+    pub fn set_hallucination_blocklist<S: AsRef<str>>(&mut self, phrases: &[S]) {
+        self.hallucinations = HallucinationFilter::new(phrases);
+    }
+
+    /// Warning message if the model was loaded with less headroom than configured
+    pub fn low_memory_warning(&self) -> Option<String> {
+        self.memory_estimate
+            .is_low(self.min_memory_headroom_mb)
+            .then(|| self.memory_estimate.warning_message())
     }
 
     pub async fn transcribe_audio(&self, audio_data: &[f32]) -> Result<String> {
@@ -66,18 +209,17 @@ impl SpeechTranscriber {
 
         let start = std::time::Instant::now();
 
-        // Get mutable access to recognizer
-        let mut recognizer = self.recognizer.write().await;
-
-        // Transcribe - sherpa-rs expects f32 samples
-        let result = recognizer.transcribe(self.sample_rate, audio_data);
+        let result = self.decode_locally(audio_data).await?;
 
         let elapsed = start.elapsed();
         let audio_duration = audio_data.len() as f32 / self.sample_rate as f32;
         let rtf = elapsed.as_secs_f32() / audio_duration;
 
-        // Clean up result - lowercase and trim
-        let cleaned = result.trim().to_string();
+        // Trim, then drop sentences the model made up from silence
+        let (cleaned, dropped) = self.hallucinations.filter(result.trim());
+        for sentence in dropped {
+            debug!("Dropped blocklisted sentence: \"{}\"", sentence);
+        }
 
         info!("Transcription complete in {:.2}s (RTF: {:.2}x): \"{}\"",
               elapsed.as_secs_f32(), rtf, cleaned);
@@ -85,6 +227,25 @@ impl SpeechTranscriber {
         Ok(cleaned)
     }
 
+    /// Decode on the decode thread, under the decode policy, so the runtime threads stay responsive
+    async fn decode_locally(&self, audio_data: &[f32]) -> Result<String> {
+        let recognizer = self.recognizer.clone();
+        let sample_rate = self.sample_rate;
+        let samples = audio_data.to_vec();
+        self.decoder
+            .run(move || {
+                // Transcribe - sherpa-rs expects f32 samples
+                recognizer.blocking_write().transcribe(sample_rate, &samples)
+            })
+            .await
+    }
+
+    /// Transcribe, rejecting recordings too short to hold a word
+    pub async fn transcribe(&self, audio_data: &[f32]) -> Result<String, PipelineError> {
+        backend::transcribe_recording(self, audio_data).await
+    }
+
+    #[allow(dead_code)]
     pub async fn transcribe_streaming(&self, audio_chunks: Vec<Vec<f32>>) -> Result<Vec<String>> {
         let mut results = Vec::new();
 
@@ -104,7 +265,29 @@ impl SpeechTranscriber {
         Ok(results)
     }
 
-    pub async fn get_model_info(&self) -> String {
-        "Parakeet TDT 0.6B v2 (INT8 quantized)".to_string()
+    pub fn get_model_info(&self) -> ModelInfo {
+        self.info.lock().unwrap().clone()
+    }
+}
+
+impl TranscriberBackend for SpeechTranscriber {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    fn transcribe<'a>(&'a self, audio: &'a [f32]) -> BoxFuture<'a, Result<String>> {
+        self.transcribe_audio(audio).boxed()
+    }
+
+    fn model_info(&self) -> Option<ModelInfo> {
+        Some(self.get_model_info())
+    }
+
+    fn low_memory_warning(&self) -> Option<String> {
+        SpeechTranscriber::low_memory_warning(self)
+    }
+
+    fn reload(&self) -> BoxFuture<'_, Result<ModelInfo>> {
+        SpeechTranscriber::reload(self).boxed()
     }
 }
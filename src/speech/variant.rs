@@ -0,0 +1,226 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use super::transcriber::MODEL_FILES;
+
+/// Transducer model families sherpa-onnx can run, detected from the model directory.
+/// They differ in the sherpa model_type they need and in language coverage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ModelVariant {
+    /// NVIDIA Parakeet TDT 0.6B v2, English only
+    ParakeetTdtV2,
+    /// NVIDIA Parakeet TDT 0.6B v3, multilingual (European languages)
+    ParakeetTdtV3,
+    /// Other NeMo transducers
+    NemoTransducer,
+    /// k2/icefall zipformer transducers
+    Zipformer,
+    Unknown,
+}
+
+impl ModelVariant {
+    /// Guess the family from the directory name (the sherpa-onnx release archives are named after the model)
+    pub fn detect(model_dir: &Path) -> Self {
+        let name = model_dir
+            .file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        if name.contains("parakeet") && name.contains("v3") {
+            ModelVariant::ParakeetTdtV3
+        } else if name.contains("parakeet") {
+            ModelVariant::ParakeetTdtV2
+        } else if name.contains("nemo") {
+            ModelVariant::NemoTransducer
+        } else if name.contains("zipformer") {
+            ModelVariant::Zipformer
+        } else {
+            ModelVariant::Unknown
+        }
+    }
+
+    /// sherpa-onnx model_type for the transducer config
+    pub fn model_type(self) -> &'static str {
+        match self {
+            ModelVariant::ParakeetTdtV2 | ModelVariant::ParakeetTdtV3 | ModelVariant::NemoTransducer | ModelVariant::Unknown => {
+                "nemo_transducer"
+            }
+            // Empty lets sherpa-onnx pick from the model's own metadata
+            ModelVariant::Zipformer => "",
+        }
+    }
+
+    /// Whether the family transcribes more than English
+    pub fn multilingual(self) -> bool {
+        matches!(self, ModelVariant::ParakeetTdtV3)
+    }
+
+    pub fn display_name(self) -> &'static str {
+        match self {
+            ModelVariant::ParakeetTdtV2 => "Parakeet TDT 0.6B v2",
+            ModelVariant::ParakeetTdtV3 => "Parakeet TDT 0.6B v3",
+            ModelVariant::NemoTransducer => "NeMo transducer",
+            ModelVariant::Zipformer => "Zipformer transducer",
+            ModelVariant::Unknown => "Unknown transducer",
+        }
+    }
+
+    /// Settings that contradict what this family supports
+    pub fn config_warnings(self, language: Option<&str>) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if let Some(language) = language {
+            if self == ModelVariant::ParakeetTdtV2 && !language.eq_ignore_ascii_case("en") {
+                warnings.push(format!(
+                    "speech.language = \"{}\" but {} only transcribes English; use the v3 model for other languages",
+                    language,
+                    self.display_name()
+                ));
+            }
+        }
+        if self == ModelVariant::Unknown {
+            warnings.push("Couldn't tell the model family from its directory name; assuming a NeMo transducer".to_string());
+        }
+        warnings
+    }
+}
+
+/// What was actually loaded, for the GUI's `model_info` event and the startup banner
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ModelInfo {
+    pub model_dir: PathBuf,
+    pub variant: ModelVariant,
+    pub name: &'static str,
+    pub model_type: &'static str,
+    pub multilingual: bool,
+    /// Encoder, decoder, joiner and tokens together
+    pub size_bytes: u64,
+    /// Entries in tokens.txt
+    pub vocab_size: usize,
+    /// Execution provider the model runs on, None for CPU
+    pub gpu_provider: Option<&'static str>,
+}
+
+impl ModelInfo {
+    pub fn read(model_dir: &Path, variant: ModelVariant, gpu_provider: Option<&'static str>) -> Self {
+        let size_bytes = MODEL_FILES
+            .iter()
+            .filter_map(|name| std::fs::metadata(model_dir.join(name)).ok())
+            .map(|metadata| metadata.len())
+            .sum();
+        let vocab_size = std::fs::read_to_string(model_dir.join("tokens.txt"))
+            .map(|tokens| tokens.lines().filter(|line| !line.trim().is_empty()).count())
+            .unwrap_or(0);
+
+        Self {
+            model_dir: model_dir.to_path_buf(),
+            variant,
+            name: variant.display_name(),
+            model_type: variant.model_type(),
+            multilingual: variant.multilingual(),
+            size_bytes,
+            vocab_size,
+            gpu_provider,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn families_are_detected_from_release_names() {
+        let cases = [
+            ("sherpa-onnx-nemo-parakeet-tdt-0.6b-v2-int8", ModelVariant::ParakeetTdtV2),
+            ("sherpa-onnx-nemo-parakeet-tdt-0.6b-v3-int8", ModelVariant::ParakeetTdtV3),
+            ("Parakeet-TDT-V3", ModelVariant::ParakeetTdtV3),
+            ("sherpa-onnx-nemo-fast-conformer-transducer-en-24500", ModelVariant::NemoTransducer),
+            ("sherpa-onnx-zipformer-gigaspeech-2023-12-12", ModelVariant::Zipformer),
+            ("my-model", ModelVariant::Unknown),
+        ];
+        for (dir, variant) in cases {
+            assert_eq!(ModelVariant::detect(&Path::new("/models").join(dir)), variant, "{}", dir);
+        }
+    }
+
+    #[test]
+    fn only_v3_is_multilingual() {
+        assert!(ModelVariant::ParakeetTdtV3.multilingual());
+        assert!(!ModelVariant::ParakeetTdtV2.multilingual());
+        assert_eq!(ModelVariant::Zipformer.model_type(), "");
+        assert_eq!(ModelVariant::ParakeetTdtV3.model_type(), "nemo_transducer");
+    }
+
+    #[test]
+    fn contradicting_language_is_warned_about() {
+        assert_eq!(ModelVariant::ParakeetTdtV2.config_warnings(Some("de")).len(), 1);
+        assert!(ModelVariant::ParakeetTdtV2.config_warnings(Some("EN")).is_empty());
+        assert!(ModelVariant::ParakeetTdtV3.config_warnings(Some("de")).is_empty());
+        assert_eq!(ModelVariant::Unknown.config_warnings(None).len(), 1);
+    }
+
+    #[test]
+    fn model_info_reads_sizes_and_vocabulary() {
+        let dir = std::env::temp_dir().join(format!("tomchat-variant-{}-parakeet-v3", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("encoder.int8.onnx"), [0u8; 100]).unwrap();
+        std::fs::write(dir.join("tokens.txt"), "a 0\nb 1\n\nc 2\n").unwrap();
+
+        let variant = ModelVariant::detect(&dir);
+        let info = ModelInfo::read(&dir, variant, None);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(info.variant, ModelVariant::ParakeetTdtV3);
+        assert!(info.multilingual);
+        assert_eq!(info.vocab_size, 3);
+        assert_eq!(info.size_bytes, 100 + 13);
+    }
+
+    #[test]
+    fn model_info_serializes_as_the_gui_expects() {
+        let info = ModelInfo {
+            model_dir: PathBuf::from("/models/sherpa-onnx-nemo-parakeet-tdt-0.6b-v3-int8"),
+            variant: ModelVariant::ParakeetTdtV3,
+            name: ModelVariant::ParakeetTdtV3.display_name(),
+            model_type: "nemo_transducer",
+            multilingual: true,
+            size_bytes: 670_000_000,
+            vocab_size: 8193,
+            gpu_provider: Some("cuda"),
+        };
+
+        assert_eq!(
+            serde_json::to_value(&info).unwrap(),
+            serde_json::json!({
+                "model_dir": "/models/sherpa-onnx-nemo-parakeet-tdt-0.6b-v3-int8",
+                "variant": "parakeet-tdt-v3",
+                "name": "Parakeet TDT 0.6B v3",
+                "model_type": "nemo_transducer",
+                "multilingual": true,
+                "size_bytes": 670_000_000,
+                "vocab_size": 8193,
+                "gpu_provider": "cuda",
+            })
+        );
+    }
+
+    #[test]
+    fn variants_serialize_in_kebab_case_and_cpu_as_null() {
+        let names: Vec<serde_json::Value> = [
+            ModelVariant::ParakeetTdtV2,
+            ModelVariant::NemoTransducer,
+            ModelVariant::Zipformer,
+            ModelVariant::Unknown,
+        ]
+        .iter()
+        .map(|variant| serde_json::to_value(variant).unwrap())
+        .collect();
+        assert_eq!(names, ["parakeet-tdt-v2", "nemo-transducer", "zipformer", "unknown"]);
+
+        let info = ModelInfo::read(Path::new("/nonexistent/zipformer"), ModelVariant::Zipformer, None);
+        let value = serde_json::to_value(&info).unwrap();
+        assert_eq!(value["gpu_provider"], serde_json::Value::Null);
+        assert_eq!((value["size_bytes"].as_u64(), value["vocab_size"].as_u64()), (Some(0), Some(0)));
+    }
+}
@@ -0,0 +1,73 @@
+//! Opt-in watch on the speech model files (speech.watch_model), so swapping a
+//! symlinked model directory or file takes effect without a restart. Polls file
+//! metadata rather than relying on inotify, which misses changes on network filesystems.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tokio::sync::mpsc;
+use tracing::{debug, info};
+
+use super::transcriber::MODEL_FILES;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// One model file as resolved through any symlinks
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FileIdentity {
+    path: PathBuf,
+    len: u64,
+    modified: Option<SystemTime>,
+    #[cfg(unix)]
+    inode: u64,
+}
+
+impl FileIdentity {
+    fn read(path: &Path) -> Option<Self> {
+        let metadata = std::fs::metadata(path).ok()?;
+        Some(Self {
+            path: std::fs::canonicalize(path).ok()?,
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+            #[cfg(unix)]
+            inode: std::os::unix::fs::MetadataExt::ino(&metadata),
+        })
+    }
+}
+
+/// All model files; None while any of them is missing (e.g. mid-swap)
+fn model_identity(model_dir: &Path) -> Option<Vec<FileIdentity>> {
+    MODEL_FILES.iter().map(|name| FileIdentity::read(&model_dir.join(name))).collect()
+}
+
+/// Poll the model files, sending once per change. A change is only reported after it
+/// has held for a whole poll interval, so a file that's still being written isn't loaded.
+pub fn spawn_model_watcher(model_dir: PathBuf) -> mpsc::Receiver<()> {
+    let (tx, rx) = mpsc::channel(1);
+    tokio::spawn(async move {
+        info!("👀 Watching {:?} for model changes", model_dir);
+        let mut loaded = model_identity(&model_dir);
+        let mut pending: Option<Vec<FileIdentity>> = None;
+
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            let current = model_identity(&model_dir);
+            if current.is_none() || current == loaded {
+                pending = None;
+                continue;
+            }
+
+            if current == pending {
+                info!("🔄 Speech model files changed on disk");
+                loaded = current;
+                pending = None;
+                if tx.send(()).await.is_err() {
+                    return;
+                }
+            } else {
+                debug!("Model files are changing, waiting for them to settle");
+                pending = current;
+            }
+        }
+    });
+    rx
+}
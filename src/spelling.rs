@@ -0,0 +1,183 @@
+//! Spelling mode: "spell alpha bravo seven dash charlie end spell" -> "ab7-c", for
+//! IDs, flags and other strings that don't survive normal dictation.
+
+const START: &str = "spell";
+const END: [&str; 2] = ["end", "spell"];
+const CAPITAL: &str = "capital";
+
+const NATO: &[(&str, char)] = &[
+    ("alpha", 'a'), ("alfa", 'a'), ("bravo", 'b'), ("charlie", 'c'), ("delta", 'd'), ("echo", 'e'),
+    ("foxtrot", 'f'), ("golf", 'g'), ("hotel", 'h'), ("india", 'i'), ("juliet", 'j'), ("juliett", 'j'),
+    ("kilo", 'k'), ("lima", 'l'), ("mike", 'm'), ("november", 'n'), ("oscar", 'o'), ("papa", 'p'),
+    ("quebec", 'q'), ("romeo", 'r'), ("sierra", 's'), ("tango", 't'), ("uniform", 'u'), ("victor", 'v'),
+    ("whiskey", 'w'), ("whisky", 'w'), ("x-ray", 'x'), ("xray", 'x'), ("yankee", 'y'), ("zulu", 'z'),
+];
+
+const DIGITS: &[(&str, char)] = &[
+    ("zero", '0'), ("one", '1'), ("two", '2'), ("three", '3'), ("four", '4'),
+    ("five", '5'), ("six", '6'), ("seven", '7'), ("eight", '8'), ("nine", '9'),
+];
+
+const SYMBOLS: &[(&str, char)] = &[
+    ("dash", '-'), ("hyphen", '-'), ("underscore", '_'), ("dot", '.'), ("period", '.'), ("slash", '/'),
+];
+
+/// A word as the recognizer wrote it, where it is in the text, and its bare lowercase form
+struct Token<'a> {
+    raw: &'a str,
+    /// Byte offset into the text
+    start: usize,
+    word: String,
+}
+
+impl<'a> Token<'a> {
+    /// `raw` must be a slice of `text`, as from `split_whitespace`
+    fn new(text: &str, raw: &'a str) -> Self {
+        let trimmed = raw.trim_matches(|c: char| matches!(c, ',' | '.' | '!' | '?' | ';' | ':' | '"' | '\''));
+        // A lone symbol is kept as-is, the recognizer may already have written "-" for "dash"
+        let word = if trimmed.is_empty() { raw } else { trimmed };
+        Self { raw, start: raw.as_ptr() as usize - text.as_ptr() as usize, word: word.to_lowercase() }
+    }
+
+    fn end(&self) -> usize {
+        self.start + self.raw.len()
+    }
+
+    /// Trailing punctuation after the word, e.g. the "." of "spell."
+    fn trailing(&self) -> &'a str {
+        let end = self.raw.trim_end_matches([',', '.', '!', '?', ';', ':']);
+        &self.raw[end.len()..]
+    }
+
+    /// Whether a new phrase starts after this word
+    fn ends_phrase(&self) -> bool {
+        self.raw.ends_with(['.', '!', '?', ':', ';', ','])
+    }
+}
+
+fn lookup(table: &[(&str, char)], word: &str) -> Option<char> {
+    table.iter().find(|(name, _)| *name == word).map(|(_, c)| *c)
+}
+
+fn letter(word: &str) -> Option<char> {
+    lookup(NATO, word).or_else(|| {
+        let mut chars = word.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) if c.is_ascii_alphabetic() => Some(c),
+            _ => None,
+        }
+    })
+}
+
+/// What a single spoken word spells, if anything
+fn spelled(word: &str) -> Option<String> {
+    if let Some(c) = letter(word).or_else(|| lookup(DIGITS, word)).or_else(|| lookup(SYMBOLS, word)) {
+        return Some(c.to_string());
+    }
+    // Digits and symbols the recognizer already wrote as such
+    let literal = !word.is_empty() && word.chars().all(|c| c.is_ascii_digit() || matches!(c, '-' | '_' | '.' | '/'));
+    literal.then(|| word.to_string())
+}
+
+/// Whether the word after "spell" starts a spelling, so "how do you spell it" is left alone
+fn starts_spelling(word: &str) -> bool {
+    word == CAPITAL || spelled(word).is_some()
+}
+
+/// Replace spelled runs with the characters they spell. A run starts at "spell" opening
+/// the utterance or a phrase (after punctuation), so "you can spell a word" is left alone,
+/// and ends at "end spell" or the end of the utterance; words it doesn't know are kept
+/// literally inside it. Everything outside the runs, whitespace included, is kept as is.
+pub fn apply(text: &str) -> String {
+    let tokens: Vec<Token> = text.split_whitespace().map(|raw| Token::new(text, raw)).collect();
+    let mut out = String::with_capacity(text.len());
+    let mut copied = 0;
+
+    let mut i = 0;
+    while i < tokens.len() {
+        let phrase_start = i == 0 || tokens[i - 1].ends_phrase();
+        let starts = phrase_start
+            && tokens[i].word == START
+            && tokens.get(i + 1).is_some_and(|next| starts_spelling(&next.word));
+        if !starts {
+            i += 1;
+            continue;
+        }
+        out.push_str(&text[copied..tokens[i].start]);
+
+        i += 1;
+        let mut spelled_text = String::new();
+        let mut trailing = "";
+        while i < tokens.len() {
+            let word = tokens[i].word.as_str();
+            if word == END[0] && tokens.get(i + 1).is_some_and(|next| next.word == END[1]) {
+                trailing = tokens[i + 1].trailing();
+                copied = tokens[i + 1].end();
+                i += 2;
+                break;
+            }
+            // Unterminated: the utterance's own final punctuation stays
+            trailing = tokens[i].trailing();
+            copied = tokens[i].end();
+
+            if word == CAPITAL {
+                if let Some(c) = tokens.get(i + 1).and_then(|next| letter(&next.word)) {
+                    spelled_text.push(c.to_ascii_uppercase());
+                    trailing = tokens[i + 1].trailing();
+                    copied = tokens[i + 1].end();
+                    i += 2;
+                    continue;
+                }
+            }
+
+            match spelled(word) {
+                Some(chars) => spelled_text.push_str(&chars),
+                None => spelled_text.push_str(word),
+            }
+            i += 1;
+        }
+
+        out.push_str(&spelled_text);
+        out.push_str(trailing);
+    }
+
+    out.push_str(&text[copied..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spells_letters_digits_and_symbols() {
+        let cases = [
+            ("spell alpha bravo seven dash charlie", "ab7-c"),
+            ("spell capital kilo underscore two dot x end spell", "K_2.x"),
+            ("spell x-ray slash 42 end spell.", "x/42."),
+            ("Spell Alpha, Bravo.", "ab."),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(apply(input), expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn only_spells_at_a_phrase_boundary() {
+        assert_eq!(apply("you can spell a word"), "you can spell a word");
+        assert_eq!(apply("how do you spell it"), "how do you spell it");
+        assert_eq!(apply("The flag is: spell dash dash force end spell now."), "The flag is: --force now.");
+        assert_eq!(apply("Sure, spell delta echo end spell"), "Sure, de");
+    }
+
+    #[test]
+    fn text_around_a_run_keeps_its_whitespace() {
+        assert_eq!(apply("ID:  spell alpha one end spell  then\nmore"), "ID:  a1  then\nmore");
+        assert_eq!(apply("  leading and  double  spaces "), "  leading and  double  spaces ");
+    }
+
+    #[test]
+    fn unknown_words_inside_a_run_are_kept() {
+        assert_eq!(apply("spell alpha banana end spell"), "abanana");
+    }
+}
@@ -0,0 +1,293 @@
+//! On-disk spill for very long recordings: once the in-memory buffer passes a
+//! threshold, older audio is appended to a WAV under the cache dir and decoded
+//! from there in chunks, each ending in a pause, when the recording stops.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+use tracing::{info, warn};
+
+use crate::audio::energy::EnergyDetector;
+use crate::config::Config;
+use crate::paths;
+use crate::speech::{backend, TranscriberBackend};
+
+const SAMPLE_RATE: u32 = 16000;
+/// Spilled audio is decoded at most this many seconds at a time
+const CHUNK_SECS: usize = 30;
+/// Chunks end in a pause found within their last few seconds
+const SEARCH_SECS: usize = 5;
+/// 30 ms at 16 kHz, the unit pauses are measured in
+const FRAME: usize = 480;
+
+/// Where spill files live; anything here at startup is left over from a crash
+pub fn spill_dir() -> PathBuf {
+    paths::cache_dir().join("spill")
+}
+
+/// A recording's spill file, being written by a thread of its own so the audio
+/// task never waits on the disk
+pub struct Spill {
+    path: PathBuf,
+    samples: usize,
+    batches: mpsc::Sender<Vec<f32>>,
+    writer: JoinHandle<Result<()>>,
+}
+
+impl Spill {
+    pub fn create(recording_id: u64) -> Result<Self> {
+        let dir = spill_dir();
+        std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create spill directory {:?}", dir))?;
+
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let path = dir.join(format!("{}-{}.wav", timestamp, recording_id));
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: SAMPLE_RATE,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec)
+            .with_context(|| format!("Failed to create spill file {:?}", path))?;
+
+        // The header is rewritten after every batch so a crash leaves a readable file
+        let (batches, queue) = mpsc::channel::<Vec<f32>>();
+        let writer = std::thread::Builder::new().name("tomchat-spill".to_string()).spawn(move || {
+            for batch in queue {
+                for sample in batch {
+                    writer.write_sample(sample)?;
+                }
+                writer.flush()?;
+            }
+            writer.finalize()?;
+            Ok(())
+        })?;
+
+        info!("💾 Recording is long, spilling older audio to {:?}", path);
+        Ok(Self { path, samples: 0, batches, writer })
+    }
+
+    /// Queue samples for the file; fails once the writer has stopped on an error
+    pub fn append(&mut self, samples: impl IntoIterator<Item = f32>) -> Result<()> {
+        let batch: Vec<f32> = samples.into_iter().collect();
+        self.samples += batch.len();
+        self.batches
+            .send(batch)
+            .map_err(|_| anyhow::anyhow!("Spill writer for {:?} stopped", self.path))
+    }
+
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    /// Wait for everything queued to reach the file; blocks, so call it off the runtime
+    pub fn finish(self) -> Result<PathBuf> {
+        drop(self.batches);
+        self.writer
+            .join()
+            .map_err(|_| anyhow::anyhow!("Spill writer panicked"))?
+            .with_context(|| format!("Failed to write spill file {:?}", self.path))?;
+        Ok(self.path)
+    }
+
+    /// Drop the recording, file included, without waiting for the writer
+    pub fn discard(self) {
+        tokio::task::spawn_blocking(move || {
+            let path = self.path.clone();
+            let _ = self.finish();
+            if let Err(e) = std::fs::remove_file(&path) {
+                warn!("Failed to remove spill file {:?}: {}", path, e);
+            }
+        });
+    }
+}
+
+/// Where to end a chunk: in the longest pause in its last SEARCH_SECS, so words
+/// aren't cut in half, or at the quietest frame there when nobody paused
+fn split_point(samples: &[f32]) -> usize {
+    let frames: Vec<&[f32]> = samples.chunks(FRAME).collect();
+    let search_from = frames.len().saturating_sub(SEARCH_SECS * SAMPLE_RATE as usize / FRAME);
+
+    // Run the detector over the whole chunk so its noise floor has settled by the search window
+    let mut detector = EnergyDetector::new(0.5);
+    let voiced: Vec<bool> = frames.iter().map(|frame| detector.is_voiced(frame)).collect();
+
+    let mut longest: Option<(usize, usize)> = None;
+    let mut run_start = None;
+    for index in search_from..=frames.len() {
+        match (voiced.get(index) == Some(&false), run_start) {
+            (true, None) => run_start = Some(index),
+            (false, Some(start)) => {
+                if longest.is_none_or(|(from, to)| index - start > to - from) {
+                    longest = Some((start, index));
+                }
+                run_start = None;
+            }
+            _ => {}
+        }
+    }
+
+    let frame = match longest {
+        Some((from, to)) => (from + to) / 2,
+        None => (search_from..frames.len())
+            .min_by(|a, b| energy(frames[*a]).total_cmp(&energy(frames[*b])))
+            .unwrap_or(frames.len()),
+    };
+    (frame * FRAME).min(samples.len())
+}
+
+fn energy(frame: &[f32]) -> f32 {
+    frame.iter().map(|s| s * s).sum()
+}
+
+/// Decode a finished spill file CHUNK_SECS at a time, each chunk ending in a pause,
+/// running `prepare` on each chunk on the blocking pool
+pub async fn transcribe(
+    transcriber: &dyn TranscriberBackend,
+    path: &Path,
+    prepare: impl Fn(&mut [f32]) + Copy + Send + 'static,
+) -> Result<String> {
+    let chunk_samples = CHUNK_SECS * SAMPLE_RATE as usize;
+    let mut samples = hound::WavReader::open(path)
+        .with_context(|| format!("Failed to open spill file {:?}", path))?
+        .into_samples::<f32>();
+
+    let mut texts = Vec::new();
+    let mut pending: Vec<f32> = Vec::with_capacity(chunk_samples);
+    loop {
+        let wanted = chunk_samples - pending.len();
+        let read = samples.by_ref().take(wanted).collect::<Result<Vec<f32>, _>>()?;
+        let at_end = read.len() < wanted;
+        pending.extend(read);
+        if pending.is_empty() {
+            break;
+        }
+
+        // What's after the pause starts the next chunk
+        let end = if at_end { pending.len() } else { split_point(&pending) };
+        let carry = pending.split_off(end.max(1));
+        pending = tokio::task::spawn_blocking(move || {
+            prepare(&mut pending);
+            pending
+        })
+        .await?;
+        texts.push(transcriber.transcribe(&pending).await?);
+        pending = carry;
+        if at_end && pending.is_empty() {
+            break;
+        }
+    }
+
+    Ok(texts
+        .iter()
+        .map(|text| text.trim())
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join(" "))
+}
+
+/// Spill files left behind by a previous run
+pub fn orphans() -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(spill_dir()) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "wav"))
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// `tomchat recover`: transcribe orphaned spill files to stdout (or just delete them)
+pub async fn recover(config: &Config, delete: bool) -> Result<()> {
+    let orphans = orphans();
+    if orphans.is_empty() {
+        info!("No orphaned recordings to recover");
+        return Ok(());
+    }
+
+    if delete {
+        for path in &orphans {
+            std::fs::remove_file(path)?;
+            info!("🗑️  Removed {:?}", path);
+        }
+        return Ok(());
+    }
+
+    let transcriber = backend::from_config(&config.speech, &config.remote_transcription)?;
+    for path in &orphans {
+        info!("Recovering {:?}", path);
+        match transcribe(&*transcriber, path, |_: &mut [f32]| {}).await {
+            Ok(text) => {
+                println!("{}", text);
+                std::fs::remove_file(path)?;
+            }
+            // Keep the file so nothing is lost; the user can retry or delete it
+            Err(e) => warn!("Failed to recover {:?}: {}", path, e),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn speech(secs: f32) -> Vec<f32> {
+        (0..(secs * SAMPLE_RATE as f32) as usize)
+            .map(|i| 0.3 * (i as f32 * 2.0 * std::f32::consts::PI * 220.0 / SAMPLE_RATE as f32).sin())
+            .collect()
+    }
+
+    fn silence(secs: f32) -> Vec<f32> {
+        vec![0.0005; (secs * SAMPLE_RATE as f32) as usize]
+    }
+
+    #[test]
+    fn chunks_end_in_the_pause() {
+        let mut samples = silence(1.0);
+        samples.extend(speech(26.0));
+        samples.extend(silence(0.6));
+        samples.extend(speech(2.4));
+        let pause = 27 * SAMPLE_RATE as usize..(27.6 * SAMPLE_RATE as f32) as usize;
+        let split = split_point(&samples);
+        assert!(pause.contains(&split), "split at {:.2}s", split as f32 / SAMPLE_RATE as f32);
+    }
+
+    #[test]
+    fn the_longest_pause_wins() {
+        let mut samples = silence(1.0);
+        samples.extend(speech(25.5));
+        samples.extend(silence(0.15));
+        samples.extend(speech(1.0));
+        samples.extend(silence(0.9));
+        samples.extend(speech(1.45));
+        let long_pause = (27.65 * SAMPLE_RATE as f32) as usize..(28.55 * SAMPLE_RATE as f32) as usize;
+        assert!(long_pause.contains(&split_point(&samples)));
+    }
+
+    #[test]
+    fn without_a_pause_the_split_stays_near_the_end() {
+        let mut samples = silence(1.0);
+        samples.extend(speech(29.0));
+        let split = split_point(&samples);
+        assert!(split >= samples.len() - SEARCH_SECS * SAMPLE_RATE as usize && split <= samples.len());
+    }
+
+    #[tokio::test]
+    async fn spilled_audio_reaches_the_file() {
+        let mut spill = Spill::create(u64::MAX - std::process::id() as u64).unwrap();
+        spill.append(speech(0.5)).unwrap();
+        spill.append(silence(0.25)).unwrap();
+        assert_eq!(spill.samples(), 12_000);
+        let path = tokio::task::spawn_blocking(move || spill.finish()).await.unwrap().unwrap();
+        let reader = hound::WavReader::open(&path).unwrap();
+        assert_eq!(reader.len(), 12_000);
+        std::fs::remove_file(path).unwrap();
+    }
+}
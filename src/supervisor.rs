@@ -0,0 +1,145 @@
+//! Keeps the audio task running: a task that returns or fails is started again, and the
+//! new one picks up the stops the last one left behind.
+
+use std::sync::Arc;
+use tokio::sync::{mpsc, Mutex, OwnedMutexGuard};
+use tokio::task::JoinHandle;
+use tracing::error;
+
+/// A task's end of a channel, kept outside the task so a restarted one picks up where the
+/// last left off: messages sent while none was running wait in the channel, and the one
+/// the last task was working on when it went down is handed over again
+pub struct Inbox<T> {
+    rx: Arc<Mutex<mpsc::Receiver<T>>>,
+    in_flight: Arc<std::sync::Mutex<Option<T>>>,
+}
+
+impl<T> Clone for Inbox<T> {
+    fn clone(&self) -> Self {
+        Self { rx: self.rx.clone(), in_flight: self.in_flight.clone() }
+    }
+}
+
+impl<T: Clone> Inbox<T> {
+    pub fn new(rx: mpsc::Receiver<T>) -> Self {
+        Self { rx: Arc::new(Mutex::new(rx)), in_flight: Arc::default() }
+    }
+
+    /// Claim the channel for a task that just started
+    pub async fn open(&self) -> OpenInbox<T> {
+        OpenInbox {
+            rx: self.rx.clone().lock_owned().await,
+            resume: self.in_flight.lock().unwrap().clone(),
+            in_flight: self.in_flight.clone(),
+        }
+    }
+}
+
+/// An Inbox claimed by a running task; dropping it (the task ending) lets the next one in
+pub struct OpenInbox<T> {
+    rx: OwnedMutexGuard<mpsc::Receiver<T>>,
+    in_flight: Arc<std::sync::Mutex<Option<T>>>,
+    resume: Option<T>,
+}
+
+impl<T: Clone> OpenInbox<T> {
+    /// The next message: first one a previous task didn't finish, then the channel's.
+    /// Cancel safe, so it can sit in a select
+    pub async fn recv(&mut self) -> Option<T> {
+        if let Some(message) = self.resume.take() {
+            return Some(message);
+        }
+        let message = self.rx.recv().await?;
+        *self.in_flight.lock().unwrap() = Some(message.clone());
+        Some(message)
+    }
+
+    /// The last message has been dealt with; true if there was one
+    pub fn finish(&mut self) -> bool {
+        self.resume.is_none() && self.in_flight.lock().unwrap().take().is_some()
+    }
+}
+
+/// Run the task `spawn` starts, starting it again whenever it returns or fails, up to
+/// `max_restarts` times. Release builds abort on panic, so there it's a task that returns
+/// that gets restarted. `on_restart` hears why and how many restarts there have been.
+pub async fn supervise(name: &str, max_restarts: usize, mut spawn: impl FnMut() -> JoinHandle<()>, on_restart: impl Fn(&str, usize)) {
+    let mut restarts = 0;
+    loop {
+        let reason = match spawn().await {
+            Ok(()) => "exited".to_string(),
+            Err(e) if e.is_panic() => format!("panicked: {}", panic_message(e.into_panic().as_ref())),
+            Err(e) => e.to_string(),
+        };
+        restarts += 1;
+        if restarts > max_restarts {
+            error!("💥 The {} task {}, giving up after {} restarts", name, reason, max_restarts);
+            return;
+        }
+        error!("💥 The {} task {}, restarting ({}/{})", name, reason, restarts, max_restarts);
+        on_restart(&reason, restarts);
+    }
+}
+
+/// The text a panic was raised with
+fn panic_message(panic: &(dyn std::any::Any + Send)) -> String {
+    panic
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn a_restarted_task_gets_the_message_the_last_one_dropped() {
+        let (tx, rx) = mpsc::channel(4);
+        let inbox = Inbox::new(rx);
+        tx.send(1).await.unwrap();
+        tx.send(2).await.unwrap();
+
+        let mut first = inbox.open().await;
+        assert_eq!(first.recv().await, Some(1));
+        drop(first);
+
+        let mut second = inbox.open().await;
+        assert!(!second.finish(), "the message handed over isn't done with yet");
+        assert_eq!(second.recv().await, Some(1));
+        assert!(second.finish());
+        assert_eq!(second.recv().await, Some(2));
+        assert!(second.finish());
+        assert!(!second.finish());
+        drop(second);
+
+        tx.send(3).await.unwrap();
+        assert_eq!(inbox.open().await.recv().await, Some(3));
+    }
+
+    #[tokio::test]
+    async fn a_task_that_returns_or_panics_is_restarted_until_the_limit() {
+        let restarts = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut started = 0;
+        let heard = restarts.clone();
+        supervise(
+            "test",
+            2,
+            || {
+                started += 1;
+                let panics = started % 2 == 1;
+                tokio::spawn(async move {
+                    if panics {
+                        panic!("boom");
+                    }
+                })
+            },
+            move |reason, count| heard.lock().unwrap().push((reason.to_string(), count)),
+        )
+        .await;
+
+        assert_eq!(started, 3);
+        assert_eq!(*restarts.lock().unwrap(), [("panicked: boom".to_string(), 1), ("exited".to_string(), 2)]);
+    }
+}
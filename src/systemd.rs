@@ -0,0 +1,150 @@
+//! sd_notify for running as a systemd service (Type=notify): READY=1 once started,
+//! STATUS= on idle/recording/transcribing, and WATCHDOG=1 with the heartbeat when
+//! WatchdogSec is set. Everything is a no-op without NOTIFY_SOCKET, and outside
+//! Linux builds with the `systemd` feature.
+
+use std::time::Duration;
+
+use crate::events::EventBus;
+
+#[cfg_attr(not(all(feature = "systemd", target_os = "linux")), allow(dead_code))]
+enum Notify<'a> {
+    Ready,
+    Status(&'a str),
+    Watchdog,
+}
+
+#[cfg(all(feature = "systemd", target_os = "linux"))]
+fn send(notify: Notify) {
+    use sd_notify::NotifyState;
+
+    if std::env::var_os("NOTIFY_SOCKET").is_none() {
+        return;
+    }
+    let state = match notify {
+        Notify::Ready => NotifyState::Ready,
+        Notify::Status(status) => NotifyState::Status(status),
+        Notify::Watchdog => NotifyState::Watchdog,
+    };
+    if let Err(e) = sd_notify::notify(false, &[state]) {
+        tracing::debug!("sd_notify failed: {}", e);
+    }
+}
+
+#[cfg(not(all(feature = "systemd", target_os = "linux")))]
+fn send(_notify: Notify) {}
+
+pub fn ready() {
+    send(Notify::Ready);
+    send(Notify::Status("Idle"));
+}
+
+pub fn watchdog_ping() {
+    send(Notify::Watchdog);
+}
+
+/// How often to ping: half of WatchdogSec, None when the watchdog is off
+pub fn watchdog_interval() -> Option<Duration> {
+    #[cfg(all(feature = "systemd", target_os = "linux"))]
+    {
+        let mut usec = 0;
+        if std::env::var_os("NOTIFY_SOCKET").is_some() && sd_notify::watchdog_enabled(false, &mut usec) {
+            return Some(Duration::from_micros(usec / 2));
+        }
+    }
+    None
+}
+
+/// The STATUS= line for an event that changes what we're doing, if it does
+fn status_for(name: &str, payload: &serde_json::Value) -> Option<&'static str> {
+    match name {
+        "state_changed" if payload["recording"] == true => Some("Recording"),
+        "transcribing" => Some("Transcribing"),
+        "state_changed" | "transcription_complete" | "transcription_error" | "pipeline_error" | "cancelled" => Some("Idle"),
+        _ => None,
+    }
+}
+
+/// Keep STATUS= in step with the event bus
+pub async fn follow_status(bus: EventBus) {
+    if std::env::var_os("NOTIFY_SOCKET").is_none() {
+        return;
+    }
+    let mut rx = bus.subscribe();
+    loop {
+        match rx.recv().await {
+            Ok(event) => {
+                if let Some(status) = status_for(&event.name, &event.payload) {
+                    send(Notify::Status(status));
+                }
+            }
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(tokio::sync::broadcast::error::RecvError::Closed) => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn events_map_to_status_lines() {
+        assert_eq!(status_for("state_changed", &json!({ "recording": true })), Some("Recording"));
+        assert_eq!(status_for("state_changed", &json!({ "recording": false })), Some("Idle"));
+        assert_eq!(status_for("transcribing", &json!({})), Some("Transcribing"));
+        for name in ["transcription_complete", "transcription_error", "pipeline_error", "cancelled"] {
+            assert_eq!(status_for(name, &json!({})), Some("Idle"), "{}", name);
+        }
+        assert_eq!(status_for("audio_level", &json!({})), None);
+    }
+
+    /// One test owns NOTIFY_SOCKET and WATCHDOG_USEC, which are process-wide
+    #[cfg(all(feature = "systemd", target_os = "linux"))]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn notifications_reach_a_mock_notify_socket() {
+        use std::os::unix::net::UnixDatagram;
+
+        let path = std::env::temp_dir().join(format!("tomchat-notify-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let socket = UnixDatagram::bind(&path).unwrap();
+        socket.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+        let socket = std::sync::Arc::new(socket);
+        let received = move || {
+            let mut buf = [0u8; 256];
+            let len = socket.recv(&mut buf).unwrap();
+            String::from_utf8_lossy(&buf[..len]).into_owned()
+        };
+
+        std::env::set_var("NOTIFY_SOCKET", &path);
+        std::env::set_var("WATCHDOG_USEC", "2000000");
+        std::env::set_var("WATCHDOG_PID", std::process::id().to_string());
+
+        // The heartbeat pings at half of WatchdogSec
+        assert_eq!(watchdog_interval(), Some(Duration::from_secs(1)));
+        watchdog_ping();
+        assert_eq!(received(), "WATCHDOG=1\n");
+
+        ready();
+        assert_eq!(received(), "READY=1\n");
+        assert_eq!(received(), "STATUS=Idle\n");
+
+        let bus = crate::events::event_bus();
+        let following = tokio::spawn(follow_status(bus.clone()));
+        while bus.receiver_count() == 0 {
+            tokio::task::yield_now().await;
+        }
+        bus.send(crate::events::BusEvent::new("audio_level", json!({}))).unwrap();
+        bus.send(crate::events::BusEvent::new("state_changed", json!({ "recording": true }))).unwrap();
+        // Blocking on the socket; the follower runs on another worker meanwhile
+        assert_eq!(tokio::task::spawn_blocking(received).await.unwrap(), "STATUS=Recording\n");
+        following.abort();
+
+        std::env::remove_var("NOTIFY_SOCKET");
+        std::env::remove_var("WATCHDOG_USEC");
+        std::env::remove_var("WATCHDOG_PID");
+        assert_eq!(watchdog_interval(), None);
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,273 @@
+//! Word-level diffs: the edit distance correction mode compares takes with, and the
+//! "what the refiner changed" operations sent to the GUI.
+
+use serde::Serialize;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Above this many table cells (words x words) the diff isn't computed at all
+const MAX_CELLS: usize = 250_000;
+/// More operations than this isn't an edit anyone would read
+const MAX_OPS: usize = 200;
+/// Less than this share of the refined words kept from the raw text counts as a rewrite
+const MIN_KEPT: f32 = 0.3;
+
+/// A word and the byte offset it starts at
+pub fn words(text: &str) -> Vec<(usize, &str)> {
+    text.split_word_bound_indices()
+        .filter(|(_, word)| !word.trim().is_empty())
+        .collect()
+}
+
+/// Word-level Levenshtein distance
+pub fn distance<T: PartialEq>(a: &[T], b: &[T]) -> usize {
+    table(a, b)[a.len()][b.len()]
+}
+
+/// Distances between every pair of prefixes, so the edits can be walked back
+fn table<T: PartialEq>(a: &[T], b: &[T]) -> Vec<Vec<usize>> {
+    let mut rows = vec![(0..=b.len()).collect::<Vec<usize>>()];
+    for (i, word_a) in a.iter().enumerate() {
+        let previous = &rows[i];
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, word_b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(word_a != word_b);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        rows.push(current);
+    }
+    rows
+}
+
+/// Words with the whitespace after them (and any leading whitespace on its own),
+/// so joining the tokens gives back the text exactly
+fn tokens(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut in_space = true;
+    for (i, c) in text.char_indices() {
+        if !c.is_whitespace() && in_space && i > 0 {
+            tokens.push(&text[start..i]);
+            start = i;
+        }
+        in_space = c.is_whitespace();
+    }
+    if start < text.len() {
+        tokens.push(&text[start..]);
+    }
+    tokens
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OpKind {
+    Equal,
+    Replace,
+    Insert,
+    Delete,
+}
+
+/// One run of the diff. Word ranges are [start, end) indices into the raw and refined
+/// text; `text` is what replaces the raw words (empty for equal and delete).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DiffOp {
+    pub op: OpKind,
+    pub raw: [usize; 2],
+    pub refined: [usize; 2],
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TextDiff {
+    Edits { ops: Vec<DiffOp> },
+    /// Changed too much (or too long) for a word diff to mean anything
+    Rewrite,
+}
+
+/// Word diff from `raw` to `refined`
+pub fn diff(raw: &str, refined: &str) -> TextDiff {
+    let a = tokens(raw);
+    let b = tokens(refined);
+    if (a.len() + 1) * (b.len() + 1) > MAX_CELLS {
+        return TextDiff::Rewrite;
+    }
+
+    // Walk back from the end, one step per word
+    let table = table(&a, &b);
+    let (mut i, mut j) = (a.len(), b.len());
+    let mut steps = Vec::new();
+    while i > 0 || j > 0 {
+        let step = if i > 0 && j > 0 && a[i - 1] == b[j - 1] && table[i][j] == table[i - 1][j - 1] {
+            OpKind::Equal
+        } else if i > 0 && j > 0 && table[i][j] == table[i - 1][j - 1] + 1 {
+            OpKind::Replace
+        } else if i > 0 && table[i][j] == table[i - 1][j] + 1 {
+            OpKind::Delete
+        } else {
+            OpKind::Insert
+        };
+        match step {
+            OpKind::Equal | OpKind::Replace => (i, j) = (i - 1, j - 1),
+            OpKind::Delete => i -= 1,
+            OpKind::Insert => j -= 1,
+        }
+        steps.push(step);
+    }
+    steps.reverse();
+
+    // Runs of equal words, and runs of anything else between them
+    let mut ops: Vec<DiffOp> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    for step in steps {
+        let equal = step == OpKind::Equal;
+        let extends = ops.last().is_some_and(|last| (last.op == OpKind::Equal) == equal);
+        if !extends {
+            ops.push(DiffOp { op: step, raw: [i, i], refined: [j, j], text: String::new() });
+        }
+        let last = ops.last_mut().unwrap();
+        if step != OpKind::Insert {
+            i += 1;
+            last.raw[1] = i;
+        }
+        if step != OpKind::Delete {
+            j += 1;
+            last.refined[1] = j;
+        }
+    }
+    for op in ops.iter_mut().filter(|op| op.op != OpKind::Equal) {
+        op.op = match (op.raw[0] == op.raw[1], op.refined[0] == op.refined[1]) {
+            (true, _) => OpKind::Insert,
+            (_, true) => OpKind::Delete,
+            _ => OpKind::Replace,
+        };
+        op.text = b[op.refined[0]..op.refined[1]].concat();
+    }
+
+    let kept: usize = ops.iter().filter(|op| op.op == OpKind::Equal).map(|op| op.refined[1] - op.refined[0]).sum();
+    if ops.len() > MAX_OPS || (!b.is_empty() && (kept as f32) < MIN_KEPT * b.len() as f32) {
+        return TextDiff::Rewrite;
+    }
+    TextDiff::Edits { ops }
+}
+
+/// Rebuild the refined text from the raw text and the edits
+pub fn apply(raw: &str, ops: &[DiffOp]) -> String {
+    let a = tokens(raw);
+    ops.iter()
+        .map(|op| match op.op {
+            OpKind::Equal => a[op.raw[0]..op.raw[1]].concat(),
+            _ => op.text.clone(),
+        })
+        .collect()
+}
+
+/// The edits inline, `[-removed-]{+added+}` as in `git diff --word-diff`
+pub fn markup(raw: &str, ops: &[DiffOp]) -> String {
+    let a = tokens(raw);
+    ops.iter()
+        .map(|op| {
+            let removed = a[op.raw[0]..op.raw[1]].concat();
+            match op.op {
+                OpKind::Equal => removed,
+                OpKind::Insert => format!("{{+{}+}}", op.text),
+                OpKind::Delete => format!("[-{}-]", removed),
+                OpKind::Replace => format!("[-{}-]{{+{}+}}", removed, op.text),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic pseudo-random texts, so failures reproduce
+    struct Texts(u64);
+
+    impl Texts {
+        fn next(&mut self, below: usize) -> usize {
+            self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            ((self.0 >> 33) % below as u64) as usize
+        }
+
+        fn text(&mut self) -> String {
+            const WORDS: [&str; 8] = ["the", "cat", "sat", "on", "mat,", "Hello.", "naïve", "🙂"];
+            const SPACES: [&str; 4] = [" ", " ", "  ", "\n"];
+            let mut text = String::new();
+            if self.next(6) == 0 {
+                text.push(' ');
+            }
+            for i in 0..self.next(12) {
+                if i > 0 {
+                    text.push_str(SPACES[self.next(SPACES.len())]);
+                }
+                text.push_str(WORDS[self.next(WORDS.len())]);
+            }
+            text
+        }
+
+        /// `raw` with a few words replaced, dropped or added
+        fn edit(&mut self, raw: &str) -> String {
+            let mut words: Vec<String> = raw.split(' ').map(str::to_string).collect();
+            for _ in 0..self.next(4) {
+                let at = self.next(words.len() + 1);
+                match self.next(3) {
+                    0 if at < words.len() => words[at] = "dog".to_string(),
+                    1 if at < words.len() => {
+                        words.remove(at);
+                    }
+                    _ => words.insert(at, "very".to_string()),
+                }
+            }
+            words.join(" ")
+        }
+    }
+
+    #[test]
+    fn applying_the_ops_to_raw_gives_refined_exactly() {
+        let mut texts = Texts(7);
+        for _ in 0..2000 {
+            let raw = texts.text();
+            let refined = if texts.next(2) == 0 { texts.edit(&raw) } else { texts.text() };
+            if let TextDiff::Edits { ops } = diff(&raw, &refined) {
+                assert_eq!(apply(&raw, &ops), refined, "raw {:?}", raw);
+            }
+        }
+    }
+
+    #[test]
+    fn small_edits_are_reported_word_by_word() {
+        let TextDiff::Edits { ops } = diff("the cat sat on the mat", "the dog sat on the mat") else {
+            panic!("a one-word change is not a rewrite");
+        };
+        assert_eq!(ops.len(), 3);
+        assert_eq!(ops[1].op, OpKind::Replace);
+        assert_eq!((ops[1].raw, ops[1].refined), ([1, 2], [1, 2]));
+        assert_eq!(ops[1].text, "dog ");
+        assert_eq!(markup("the cat sat on the mat", &ops), "the [-cat -]{+dog +}sat on the mat");
+    }
+
+    #[test]
+    fn identical_text_is_one_equal_run() {
+        let TextDiff::Edits { ops } = diff("same  words\nhere", "same  words\nhere") else { panic!() };
+        assert_eq!(ops.len(), 1);
+        assert_eq!(ops[0].op, OpKind::Equal);
+        assert!(matches!(diff("", ""), TextDiff::Edits { ops } if ops.is_empty()));
+    }
+
+    #[test]
+    fn unrelated_or_huge_text_is_a_rewrite() {
+        assert_eq!(diff("one two three four five", "alpha beta gamma delta epsilon"), TextDiff::Rewrite);
+        let long = "word ".repeat(600);
+        assert_eq!(diff(&long, &long), TextDiff::Rewrite);
+    }
+
+    #[test]
+    fn distance_counts_word_edits() {
+        let a: Vec<&str> = words("the cat sat").into_iter().map(|(_, word)| word).collect();
+        let b: Vec<&str> = words("the dog sat down").into_iter().map(|(_, word)| word).collect();
+        assert_eq!(distance(&a, &b), 2);
+        assert_eq!(words("Hi, you")[1], (2, ","));
+    }
+}
@@ -22,6 +22,33 @@ pub struct TextRefinementConfig {
     #[serde(default)]
     pub max_retries: u32,
     pub fallback_on_timeout: bool,
+    /// Timeout for the single retry when Ollama is reloading an evicted model
+    #[serde(default = "default_cold_start_timeout_ms")]
+    pub cold_start_timeout_ms: u64,
+    /// How long Ollama keeps the model loaded after a request (its keep_alive)
+    #[serde(default = "default_keep_alive_secs")]
+    pub keep_alive_secs: u64,
+    /// Give the model the focused window's title as context (sanitized, off by default)
+    #[serde(default)]
+    pub include_window_title: bool,
+    /// Maximum length of the window title passed to the model
+    #[serde(default = "default_window_title_max_len")]
+    pub window_title_max_len: usize,
+    /// Pull model_name through Ollama at startup if it isn't installed
+    #[serde(default)]
+    pub auto_pull: bool,
+}
+
+fn default_window_title_max_len() -> usize {
+    80
+}
+
+fn default_cold_start_timeout_ms() -> u64 {
+    30000
+}
+
+fn default_keep_alive_secs() -> u64 {
+    300
 }
 
 impl Default for TextRefinementConfig {
@@ -53,6 +80,11 @@ Corrected:"#.to_string(),
             timeout_ms: 8000, // 8 seconds for Ollama
             max_retries: 1,
             fallback_on_timeout: true,
+            cold_start_timeout_ms: default_cold_start_timeout_ms(),
+            keep_alive_secs: default_keep_alive_secs(),
+            include_window_title: false,
+            window_title_max_len: default_window_title_max_len(),
+            auto_pull: false,
         }
     }
 }
\ No newline at end of file
@@ -1,5 +1,7 @@
 pub mod refiner;
 pub mod config;
+pub mod punctuation;
 
 pub use refiner::TextRefiner;
-pub use config::TextRefinementConfig;
\ No newline at end of file
+pub use config::TextRefinementConfig;
+pub use punctuation::PunctuateMode;
\ No newline at end of file
@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+
+/// Punctuation restoration for models that return unpunctuated runs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PunctuateMode {
+    #[default]
+    Off,
+    /// Ask the refinement model to add punctuation only (works with refinement disabled)
+    Llm,
+}
+
+pub const PUNCTUATION_PROMPT: &str = r#"Add punctuation and capitalization to this speech transcript.
+Do not add, remove, reorder or change any words. Reply with the punctuated text only.
+
+Text: {text}
+Punctuated:"#;
+
+/// Shorter texts are left alone: there's too little to gain
+const MIN_WORDS: usize = 8;
+/// Text with at least one sentence-final mark per this many words is already punctuated
+const WORDS_PER_SENTENCE: usize = 25;
+
+/// Whether `text` is a long run with too little sentence-final punctuation
+pub fn needs_punctuation(text: &str) -> bool {
+    let words = text.split_whitespace().count();
+    if words < MIN_WORDS {
+        return false;
+    }
+    let terminals = text.chars().filter(|c| matches!(c, '.' | '?' | '!')).count();
+    terminals * WORDS_PER_SENTENCE < words
+}
+
+/// Letters and digits only, lowercased: what punctuation restoration must not change
+fn word_content(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .map(|word| word.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect::<String>())
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// Clean up the model's reply; None if it changed the words (then the original is kept)
+pub fn clean_response(original: &str, response: &str) -> Option<String> {
+    let mut text = response.trim();
+
+    // Small models like to echo the label or wrap the answer in quotes
+    for label in ["Punctuated:", "Text:", "Output:"] {
+        if let Some(rest) = text.strip_prefix(label) {
+            text = rest.trim_start();
+        }
+    }
+    // Only the first paragraph; anything after is commentary
+    text = text.split("\n\n").next().unwrap_or("").trim();
+    if text.len() >= 2 && text.starts_with('"') && text.ends_with('"') {
+        text = text[1..text.len() - 1].trim();
+    }
+
+    (!text.is_empty() && word_content(text) == word_content(original)).then(|| text.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_long_unpunctuated_runs_need_punctuation() {
+        assert!(!needs_punctuation("turn the lights off"));
+        assert!(needs_punctuation("so we met on tuesday and then we went over the plan again"));
+        assert!(!needs_punctuation("So we met on Tuesday. Then we went over the plan again."));
+    }
+
+    #[test]
+    fn labels_quotes_and_commentary_are_stripped() {
+        let original = "so we met on tuesday";
+        let cases = [
+            "So we met on Tuesday.",
+            "  Punctuated: So we met on Tuesday.",
+            "\"So we met on Tuesday.\"",
+            "Output: \"So we met on Tuesday.\"\n\nI added a period at the end.",
+        ];
+        for response in cases {
+            assert_eq!(clean_response(original, response).as_deref(), Some("So we met on Tuesday."), "{:?}", response);
+        }
+    }
+
+    #[test]
+    fn changed_words_are_rejected() {
+        let original = "so we met on tuesday";
+        assert_eq!(clean_response(original, "So we met on Wednesday."), None);
+        assert_eq!(clean_response(original, "We met on Tuesday."), None);
+        assert_eq!(clean_response(original, ""), None);
+    }
+}
@@ -1,19 +1,50 @@
 use anyhow::Result;
+use futures_util::StreamExt;
 use ollama_rs::{Ollama, generation::completion::request::GenerationRequest};
 use ollama_rs::models::ModelOptions;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tracing::{debug, info, warn};
 use url::Url;
 
 use super::config::TextRefinementConfig;
+use super::punctuation::{self, PUNCTUATION_PROMPT};
+use crate::error::PipelineError;
+use crate::events::preview;
+use crate::privacy::{self, NetworkSite};
+use crate::redact::redact;
+use crate::window;
+
+/// Callback used to surface refinement events (e.g. to the GUI)
+pub type EventCallback = Arc<dyn Fn(&str, &str) + Send + Sync>;
+
+/// Error fragments Ollama returns when the model was evicted and must be reloaded
+const COLD_START_ERRORS: &[&str] = &[
+    "model not loaded",
+    "model is not loaded",
+    "loading model",
+    "model is loading",
+    "llm server loading model",
+    "server busy",
+];
+
+/// Whether an Ollama error indicates the model was evicted and is reloading
+pub fn is_cold_start_error(message: &str) -> bool {
+    let message = message.to_lowercase();
+    COLD_START_ERRORS.iter().any(|needle| message.contains(needle))
+}
 
 pub struct TextRefiner {
     ollama: Arc<Ollama>,
     config: TextRefinementConfig,
+    last_success: Mutex<Option<Instant>>,
+    on_event: Option<EventCallback>,
 }
 
 impl TextRefiner {
-    pub async fn new(config: TextRefinementConfig) -> Result<Self> {
+    /// Connect to Ollama, making sure the model is installed (pulling it if `auto_pull`);
+    /// `on_event` receives pull progress as "refinement_model_pull" events
+    pub async fn new(config: TextRefinementConfig, on_event: Option<EventCallback>) -> Result<Self> {
         if !config.enabled {
             return Err(anyhow::anyhow!("Text refinement is disabled"));
         }
@@ -22,9 +53,13 @@ impl TextRefiner {
         info!("Using model: {}", config.model_name);
         info!("Ollama URL: {}", config.ollama_url);
         
+        privacy::check(NetworkSite::Ollama, &config.ollama_url)?;
+
         // Create Ollama client
         let url = Url::parse(&config.ollama_url)?;
         let ollama = Ollama::from_url(url);
+
+        Self::ensure_model(&ollama, &config, on_event.as_ref()).await?;
         
         // Test connection and model availability
         Self::test_connection(&ollama, &config.model_name).await?;
@@ -34,9 +69,104 @@ impl TextRefiner {
         Ok(Self {
             ollama: Arc::new(ollama),
             config,
+            // The connection test just loaded the model
+            last_success: Mutex::new(Some(Instant::now())),
+            on_event,
         })
     }
 
+    /// Check Ollama's installed models for ours, pulling it if allowed
+    async fn ensure_model(ollama: &Ollama, config: &TextRefinementConfig, on_event: Option<&EventCallback>) -> Result<()> {
+        let models = ollama
+            .list_local_models()
+            .await
+            .map_err(|e| anyhow::anyhow!("Ollama connection failed: {}", e))?;
+
+        // Ollama lists untagged models as name:latest
+        let wanted = &config.model_name;
+        let installed = models.iter().any(|model| {
+            model.name == *wanted || (!wanted.contains(':') && model.name == format!("{}:latest", wanted))
+        });
+        if installed {
+            debug!("Model {} is installed", wanted);
+            return Ok(());
+        }
+
+        if !config.auto_pull {
+            return Err(anyhow::anyhow!(
+                "Model {} is not installed in Ollama (set text_refinement.auto_pull = true to fetch it)",
+                wanted
+            ));
+        }
+
+        info!("📥 Pulling {} through Ollama...", wanted);
+        let emit = |message: &str| {
+            if let Some(callback) = on_event {
+                callback("refinement_model_pull", message);
+            }
+        };
+        emit(&format!("Pulling {}", wanted));
+
+        let mut stream = ollama
+            .pull_model_stream(wanted.clone(), false)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to start pulling {}: {}", wanted, e))?;
+
+        let mut last_reported = None;
+        while let Some(status) = stream.next().await {
+            let status = status.map_err(|e| anyhow::anyhow!("Pulling {} failed: {}", wanted, e))?;
+            let message = match (status.completed, status.total) {
+                (Some(completed), Some(total)) if total > 0 => {
+                    let percent = completed * 100 / total;
+                    // One event per percent is plenty
+                    if last_reported == Some((status.message.clone(), percent)) {
+                        continue;
+                    }
+                    last_reported = Some((status.message.clone(), percent));
+                    format!("{} {}%", status.message, percent)
+                }
+                _ => status.message.clone(),
+            };
+            debug!("Pull {}: {}", wanted, message);
+            emit(&message);
+        }
+
+        info!("✅ Pulled {}", wanted);
+        emit(&format!("Pulled {}", wanted));
+        Ok(())
+    }
+
+    /// A refiner talking to `config.ollama_url` without checking the model is there
+    #[cfg(test)]
+    pub fn unchecked(config: TextRefinementConfig) -> Self {
+        Self {
+            ollama: Arc::new(Ollama::from_url(Url::parse(&config.ollama_url).unwrap())),
+            config,
+            last_success: Mutex::new(None),
+            on_event: None,
+        }
+    }
+
+    pub fn set_event_callback(&mut self, callback: EventCallback) {
+        self.on_event = Some(callback);
+    }
+
+    fn emit(&self, event: &str, message: &str) {
+        if let Some(ref callback) = self.on_event {
+            callback(event, message);
+        }
+    }
+
+    /// Whether Ollama has likely unloaded the model since our last successful request
+    fn outside_keep_alive(&self) -> bool {
+        let keep_alive = Duration::from_secs(self.config.keep_alive_secs);
+        self.last_success
+            .lock()
+            .unwrap()
+            .map(|t| t.elapsed() > keep_alive)
+            .unwrap_or(true)
+    }
+
     async fn test_connection(ollama: &Ollama, model_name: &str) -> Result<()> {
         // Test basic connection with a simple prompt
         let test_request = GenerationRequest::new(
@@ -59,11 +189,39 @@ impl TextRefiner {
         }
     }
 
-    pub async fn refine_text(&self, input_text: &str) -> Result<String> {
+    /// Window title safe to send to Ollama: redacted, single-line and length-capped
+    async fn window_title_context(&self) -> Option<String> {
+        let title = window::active_window_title().await?;
+        Some(preview(&redact(&title), self.config.window_title_max_len))
+    }
+
+    async fn build_prompt(&self, input_text: &str) -> String {
+        let template = &self.config.prompt_template;
+        let title = if self.config.include_window_title {
+            self.window_title_context().await
+        } else {
+            None
+        };
+
+        let mut prompt = template
+            .replace("{app_title}", title.as_deref().unwrap_or(""))
+            .replace("{text}", input_text);
+
+        // Templates without the placeholder get the title as a leading context line
+        if let Some(title) = title {
+            if !template.contains("{app_title}") {
+                prompt = format!("Active window: {}\n\n{}", title, prompt);
+            }
+        }
+
+        prompt
+    }
+
+    pub async fn refine_text(&self, input_text: &str) -> Result<String, PipelineError> {
         debug!("🔧 Refining text: \"{}\"", input_text);
 
         // Create prompt from template
-        let prompt = self.config.prompt_template.replace("{text}", input_text);
+        let prompt = self.build_prompt(input_text).await;
         
         // Calculate dynamic max_tokens based on input length
         // Generally, corrections shouldn't be much longer than original
@@ -88,40 +246,172 @@ impl TextRefiner {
 
         // Generate refined text with timeout
         let refined_result = tokio::time::timeout(
-            std::time::Duration::from_millis(self.config.timeout_ms),
-            self.ollama.generate(request),
+            Duration::from_millis(self.config.timeout_ms),
+            self.ollama.generate(request.clone()),
         ).await;
 
+        // An evicted model either errors while reloading or blows through the normal
+        // timeout - retry once with the cold start budget before falling back
+        let cold_start = match &refined_result {
+            Ok(Err(e)) => is_cold_start_error(&e.to_string()),
+            Err(_timeout) => self.outside_keep_alive(),
+            Ok(Ok(_)) => false,
+        };
+
+        let (refined_result, timeout_ms) = if cold_start {
+            warn!("Ollama model appears to be reloading, retrying with {}ms timeout",
+                  self.config.cold_start_timeout_ms);
+            self.emit("refinement_cold_start", "Refinement model is reloading, this may take a moment");
+
+            let result = tokio::time::timeout(
+                Duration::from_millis(self.config.cold_start_timeout_ms),
+                self.ollama.generate(request),
+            ).await;
+            (result, self.config.cold_start_timeout_ms)
+        } else {
+            (refined_result, self.config.timeout_ms)
+        };
+
         match refined_result {
             Ok(Ok(response)) => {
+                *self.last_success.lock().unwrap() = Some(Instant::now());
                 let refined_text = response.response.trim().to_string();
                 info!("✨ Refined: \"{}\" → \"{}\"", input_text, refined_text);
                 Ok(refined_text)
             }
-            Ok(Err(e)) => {
-                warn!("Text refinement failed: {}, using original text", e);
-                if self.config.fallback_on_timeout {
-                    Ok(input_text.to_string())
-                } else {
-                    Err(anyhow::anyhow!("Ollama generation failed: {}", e))
+            // The caller reports these and decides whether to fall back (text_refinement.fallback_on_timeout)
+            Ok(Err(e)) => Err(PipelineError::RefinementBackend { status: e.to_string() }),
+            Err(_timeout) => Err(PipelineError::RefinementTimeout { elapsed_ms: timeout_ms }),
+        }
+    }
+
+    /// A refiner for the punctuation pass on this one's connection, so the model is
+    /// checked (and pulled) once
+    pub fn punctuator(&self) -> Self {
+        Self {
+            ollama: self.ollama.clone(),
+            config: self.config.clone(),
+            last_success: Mutex::new(*self.last_success.lock().unwrap()),
+            on_event: None,
+        }
+    }
+
+    /// Punctuation-only pass bounded by text_refinement.timeout_ms, with no cold start
+    /// retry; any failure keeps the input
+    pub async fn punctuate(&self, input_text: &str) -> String {
+        if !punctuation::needs_punctuation(input_text) {
+            debug!("Text is already punctuated, skipping restoration");
+            return input_text.to_string();
+        }
+
+        let input_word_count = input_text.split_whitespace().count();
+        let options = ModelOptions::default()
+            .temperature(0.0)
+            .num_predict((input_word_count * 2 + 20) as i32);
+        let request = GenerationRequest::new(
+            self.config.model_name.clone(),
+            PUNCTUATION_PROMPT.replace("{text}", input_text),
+        ).options(options);
+
+        let result = tokio::time::timeout(
+            Duration::from_millis(self.config.timeout_ms),
+            self.ollama.generate(request),
+        ).await;
+
+        match result {
+            Ok(Ok(response)) => {
+                *self.last_success.lock().unwrap() = Some(Instant::now());
+                match punctuation::clean_response(input_text, &response.response) {
+                    Some(punctuated) => {
+                        info!("✏️  Punctuated: \"{}\" → \"{}\"", input_text, punctuated);
+                        punctuated
+                    }
+                    None => {
+                        warn!("Punctuation pass changed the words, keeping the original");
+                        input_text.to_string()
+                    }
                 }
             }
+            Ok(Err(e)) => {
+                warn!("Punctuation restoration failed: {}, keeping the original", e);
+                input_text.to_string()
+            }
             Err(_timeout) => {
-                warn!("Text refinement timed out after {}ms, using original text", self.config.timeout_ms);
-                if self.config.fallback_on_timeout {
-                    Ok(input_text.to_string())
-                } else {
-                    Err(anyhow::anyhow!("Text refinement timed out"))
-                }
+                warn!("Punctuation restoration timed out after {}ms, keeping the original", self.config.timeout_ms);
+                input_text.to_string()
             }
         }
     }
 
+    #[allow(dead_code)]
     pub fn is_enabled(&self) -> bool {
         self.config.enabled
     }
 
+    #[allow(dead_code)]
     pub async fn get_model_info(&self) -> String {
         format!("Ollama model: {} at {}", self.config.model_name, self.config.ollama_url)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{self, respond};
+
+    const UNPUNCTUATED: &str = "so we met on tuesday and then we went over the plan again";
+
+    fn refiner(url: String, timeout_ms: u64) -> TextRefiner {
+        TextRefiner::unchecked(TextRefinementConfig { ollama_url: url, timeout_ms, ..Default::default() })
+    }
+
+    fn generated(text: &str) -> Vec<u8> {
+        serde_json::json!({ "model": "gemma3:1b", "created_at": "", "response": text, "done": true })
+            .to_string()
+            .into_bytes()
+    }
+
+    #[tokio::test]
+    async fn punctuate_uses_the_cleaned_reply() {
+        let url = test_server::serve(|_, _, stream| {
+            let reply = "Punctuated: \"So we met on Tuesday, and then we went over the plan again.\"";
+            respond(stream, "200 OK", &[], &generated(reply));
+        });
+
+        let punctuated = refiner(url, 2000).punctuate(UNPUNCTUATED).await;
+
+        assert_eq!(punctuated, "So we met on Tuesday, and then we went over the plan again.");
+    }
+
+    #[tokio::test]
+    async fn punctuate_keeps_the_input_after_one_timeout() {
+        let url = test_server::serve(|_, _, stream| {
+            std::thread::sleep(Duration::from_millis(600));
+            respond(stream, "200 OK", &[], &generated("Too late."));
+        });
+        let started = Instant::now();
+
+        let punctuated = refiner(url, 100).punctuate(UNPUNCTUATED).await;
+
+        assert_eq!(punctuated, UNPUNCTUATED);
+        assert!(started.elapsed() < Duration::from_millis(500), "took {:?}", started.elapsed());
+    }
+
+    #[tokio::test]
+    async fn punctuator_shares_the_connection() {
+        let requests = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let seen = requests.clone();
+        let url = test_server::serve(move |_, request, stream| {
+            seen.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            assert_eq!(request.path, "/api/generate");
+            respond(stream, "200 OK", &[], &generated(UNPUNCTUATED));
+        });
+        let refiner = refiner(url, 2000);
+
+        let punctuator = refiner.punctuator();
+        assert!(Arc::ptr_eq(&refiner.ollama, &punctuator.ollama));
+        punctuator.punctuate(UNPUNCTUATED).await;
+
+        // Just the generation, no model listing or pull
+        assert_eq!(requests.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+}
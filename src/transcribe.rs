@@ -0,0 +1,82 @@
+//! `tomchat transcribe <file>`: run the configured model over a WAV file and print the
+//! text. No capture, hotkeys or typing; the same file gives the same text, which makes
+//! it a regression check for transcription quality.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::path::Path;
+use std::time::Instant;
+use tracing::info;
+
+use crate::audio::process::{CallbackProcessor, TARGET_RATE};
+use crate::audio::wav;
+use crate::config::Config;
+use crate::segments::{self, Sentence};
+use crate::speech::backend;
+
+#[derive(Serialize)]
+struct FileTranscript {
+    file: String,
+    text: String,
+    duration_ms: u64,
+    decode_ms: u64,
+    sentences: Vec<Sentence>,
+}
+
+/// The file as 16kHz mono, downmixed and resampled the way live capture is
+pub fn load(path: &Path, config: &Config) -> Result<Vec<f32>> {
+    if !path.is_file() {
+        return Err(anyhow::anyhow!("No such file: {:?}", path));
+    }
+    let file = std::fs::File::open(path).with_context(|| format!("Failed to open {:?}", path))?;
+    let (samples, channels, sample_rate) =
+        wav::decode(std::io::BufReader::new(file)).with_context(|| format!("Can't read {:?}", path))?;
+    if samples.is_empty() {
+        return Err(anyhow::anyhow!("{:?} has no audio", path));
+    }
+
+    let channels = channels as usize;
+    let mut processor = CallbackProcessor::new(channels, sample_rate, config.audio.channel_mode);
+    let mut audio = processor.process(&samples);
+    // The resampler holds back its last few samples; push them out with a little silence
+    if sample_rate != TARGET_RATE {
+        audio.extend(processor.process(&vec![0.0f32; channels * sample_rate as usize / 100]));
+        let frames = samples.len() / channels;
+        audio.truncate((frames as f64 * TARGET_RATE as f64 / sample_rate as f64).round() as usize);
+    }
+    info!("{:?}: {} channel(s) at {}Hz, {:.1}s", path, channels, sample_rate, audio.len() as f32 / TARGET_RATE as f32);
+    Ok(audio)
+}
+
+/// Transcribe `path`, printing the text (or JSON with `json`) to stdout or `output`
+pub async fn run(config: &Config, path: &Path, json: bool, output: Option<&Path>) -> Result<()> {
+    let audio = load(path, config)?;
+
+    let transcriber = backend::from_config(&config.speech, &config.remote_transcription)?;
+
+    let decode_started = Instant::now();
+    let text = backend::transcribe_recording(&*transcriber, &audio).await.map_err(|e| anyhow::anyhow!("Transcription failed: {}", e))?;
+    let decode_ms = decode_started.elapsed().as_millis() as u64;
+
+    let rendered = if json {
+        let transcript = FileTranscript {
+            file: path.display().to_string(),
+            sentences: segments::sentences(&text),
+            text,
+            duration_ms: audio.len() as u64 * 1000 / TARGET_RATE as u64,
+            decode_ms,
+        };
+        serde_json::to_string_pretty(&transcript)?
+    } else {
+        text
+    };
+
+    match output {
+        Some(output) => {
+            std::fs::write(output, format!("{}\n", rendered)).with_context(|| format!("Failed to write {:?}", output))?;
+            info!("📝 Transcript written to {:?}", output);
+        }
+        None => println!("{}", rendered),
+    }
+    Ok(())
+}
@@ -0,0 +1,239 @@
+//! Custom vocabulary (text.vocabulary, text.vocabulary_file): project names and jargon
+//! the recognizer splits up or mis-cases ("Tom chat" -> "tomchat"), plus explicit fixes
+//! for words it mishears ("see pal => cpal"). The file is re-read whenever it changes.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tracing::{info, warn};
+
+/// A term may come out of the recognizer split into at most this many words
+const MAX_SPLIT: usize = 3;
+/// Separates what the recognizer writes from the term in a fix
+const FIX_ARROW: &str = "=>";
+
+/// Letters and digits only, lowercased: "Tom chat," and "tom-chat" both become "tomchat"
+fn key(text: &str) -> String {
+    text.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+/// Split a word into its leading punctuation, the word, and its trailing punctuation
+fn split_punctuation(word: &str) -> (&str, &str, &str) {
+    let start = word.find(char::is_alphanumeric).unwrap_or(word.len());
+    let end = word.rfind(char::is_alphanumeric).map_or(start, |i| i + word[i..].chars().next().unwrap().len_utf8());
+    (&word[..start], &word[start..end], &word[end..])
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+struct Rules {
+    /// Key of what may be heard -> the term to write
+    terms: HashMap<String, String>,
+    /// Most words a rule can span
+    max_words: usize,
+}
+
+impl Rules {
+    fn build<'a>(entries: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut rules = Rules { terms: HashMap::new(), max_words: MAX_SPLIT };
+        for entry in entries {
+            let entry = entry.trim();
+            if entry.is_empty() || entry.starts_with('#') {
+                continue;
+            }
+            let (heard, term) = match entry.split_once(FIX_ARROW) {
+                Some((heard, term)) => (heard.trim(), term.trim()),
+                None => (entry, entry),
+            };
+            if key(heard).is_empty() || term.is_empty() {
+                continue;
+            }
+            rules.max_words = rules.max_words.max(heard.split_whitespace().count());
+            rules.terms.insert(key(heard), term.to_string());
+        }
+        rules
+    }
+
+    /// Rewrite the runs that match a term; everything else, whitespace included, is kept as is
+    fn apply(&self, text: &str) -> String {
+        if self.terms.is_empty() {
+            return text.to_string();
+        }
+        let words = words(text);
+        let mut out = String::with_capacity(text.len());
+        // Text before this offset is in `out` already
+        let mut copied = 0;
+        let mut i = 0;
+        while i < words.len() {
+            // Longest run first, so "Tom chat app" can win over "Tom chat"
+            let longest = self.max_words.min(words.len() - i);
+            let found = (1..=longest).rev().find_map(|n| {
+                let run: Vec<&str> = words[i..i + n].iter().map(|&(_, word)| word).collect();
+                // Punctuation inside the run means the words were meant apart
+                let joined = run.iter().enumerate().all(|(j, word)| {
+                    let (lead, _, trail) = split_punctuation(word);
+                    (j == 0 || lead.is_empty()) && (j == n - 1 || trail.is_empty())
+                });
+                if !joined {
+                    return None;
+                }
+                let term = self.terms.get(&key(&run.concat()))?;
+                let (lead, _, _) = split_punctuation(run[0]);
+                let (_, _, trail) = split_punctuation(run[n - 1]);
+                Some((n, format!("{}{}{}", lead, term, trail)))
+            });
+            match found {
+                Some((n, replaced)) => {
+                    let (start, _) = words[i];
+                    let (last_start, last) = words[i + n - 1];
+                    out.push_str(&text[copied..start]);
+                    out.push_str(&replaced);
+                    copied = last_start + last.len();
+                    i += n;
+                }
+                None => i += 1,
+            }
+        }
+        out.push_str(&text[copied..]);
+        out
+    }
+}
+
+/// The whitespace-separated words of `text`, each with its byte offset
+fn words(text: &str) -> Vec<(usize, &str)> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (offset, c) in text.char_indices() {
+        match (c.is_whitespace(), start) {
+            (true, Some(from)) => {
+                words.push((from, &text[from..offset]));
+                start = None;
+            }
+            (false, None) => start = Some(offset),
+            _ => {}
+        }
+    }
+    if let Some(from) = start {
+        words.push((from, &text[from..]));
+    }
+    words
+}
+
+/// The configured terms, with the file's merged in as of its last change
+#[derive(Debug)]
+pub struct Vocabulary {
+    listed: Vec<String>,
+    file: Option<PathBuf>,
+    /// Modification time of the file as last read; None if it couldn't be
+    read_at: Option<SystemTime>,
+    /// Set while the file can't be read, so that's reported once
+    unreadable: bool,
+    rules: Rules,
+}
+
+impl Vocabulary {
+    pub fn new(listed: Vec<String>, file: Option<PathBuf>) -> Self {
+        let rules = Rules::build(listed.iter().map(String::as_str));
+        let mut vocabulary = Self { listed, file, read_at: None, unreadable: false, rules };
+        vocabulary.refresh();
+        vocabulary
+    }
+
+    fn modified(path: &Path) -> Option<SystemTime> {
+        std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+    }
+
+    /// Re-read the file if it changed since it was last read
+    fn refresh(&mut self) {
+        let Some(ref path) = self.file else {
+            return;
+        };
+        let modified = Self::modified(path);
+        if modified.is_some() && modified == self.read_at {
+            return;
+        }
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                if !self.unreadable {
+                    warn!("⚠️ Can't read text.vocabulary_file {:?}, using text.vocabulary only: {}", path, e);
+                    self.unreadable = true;
+                    self.read_at = None;
+                    self.rules = Rules::build(self.listed.iter().map(String::as_str));
+                }
+                return;
+            }
+        };
+        self.unreadable = false;
+        self.read_at = modified;
+        self.rules = Rules::build(self.listed.iter().map(String::as_str).chain(contents.lines()));
+        info!("📖 Vocabulary: {} term(s) loaded", self.rules.terms.len());
+    }
+
+    /// Write the vocabulary's terms where the recognizer split, mis-cased or misheard them
+    pub fn apply(&mut self, text: &str) -> String {
+        self.refresh();
+        self.rules.apply(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(entries: &[&str]) -> Rules {
+        Rules::build(entries.iter().copied())
+    }
+
+    #[test]
+    fn build_skips_comments_and_blanks_and_reads_fixes() {
+        let rules = rules(&["", "  # project names", "TomChat", "see pal => cpal", "=> nothing", "heard =>"]);
+        assert_eq!(rules.terms.len(), 2);
+        assert_eq!(rules.terms["tomchat"], "TomChat");
+        assert_eq!(rules.terms["seepal"], "cpal");
+        assert_eq!(rules.max_words, MAX_SPLIT);
+
+        let long = Rules::build(["one two three four => 1234"]);
+        assert_eq!(long.max_words, 4);
+    }
+
+    #[test]
+    fn split_and_misheard_terms_are_rewritten() {
+        let rules = rules(&["TomChat", "TomChat app", "see pal => cpal"]);
+        assert_eq!(rules.apply("I use Tom chat daily"), "I use TomChat daily");
+        assert_eq!(rules.apply("the tom-chat app works"), "the TomChat app works");
+        assert_eq!(rules.apply("built on see pal."), "built on cpal.");
+        assert_eq!(rules.apply("(Tom chat), again"), "(TomChat), again");
+    }
+
+    #[test]
+    fn punctuation_inside_a_run_keeps_the_words_apart() {
+        let rules = rules(&["TomChat"]);
+        assert_eq!(rules.apply("Tom, chat with me"), "Tom, chat with me");
+    }
+
+    #[test]
+    fn whitespace_between_words_is_kept() {
+        let rules = rules(&["TomChat", "see pal => cpal"]);
+        assert_eq!(rules.apply("Dear Tom,\n\nsee  pal\tworks.\n"), "Dear Tom,\n\ncpal\tworks.\n");
+        assert_eq!(rules.apply("  leading and  double  spaces  "), "  leading and  double  spaces  ");
+        assert_eq!(rules.apply("first line\nTom chat\n"), "first line\nTomChat\n");
+        assert_eq!(Rules::default().apply("a\n b"), "a\n b");
+    }
+
+    #[test]
+    fn the_file_is_reread_when_it_changes() {
+        let path = std::env::temp_dir().join(format!("tomchat-vocabulary-{}.txt", std::process::id()));
+        std::fs::write(&path, "see pal => cpal\n").unwrap();
+        let mut vocabulary = Vocabulary::new(vec!["TomChat".into()], Some(path.clone()));
+        assert_eq!(vocabulary.apply("Tom chat on see pal"), "TomChat on cpal");
+
+        std::fs::write(&path, "see pal => CPAL\n").unwrap();
+        // Filesystems with coarse timestamps could otherwise miss the rewrite
+        let later = SystemTime::now() + std::time::Duration::from_secs(10);
+        std::fs::File::options().write(true).open(&path).unwrap().set_modified(later).unwrap();
+        assert_eq!(vocabulary.apply("Tom chat on see pal"), "TomChat on CPAL");
+
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(vocabulary.apply("Tom chat on see pal"), "TomChat on see pal");
+    }
+}
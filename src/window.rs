@@ -0,0 +1,17 @@
+use tokio::process::Command;
+use tracing::debug;
+
+/// Title of the focused window via xdotool (X11/XWayland only)
+pub async fn active_window_title() -> Option<String> {
+    let output = Command::new("xdotool")
+        .args(["getactivewindow", "getwindowname"])
+        .output()
+        .await
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let title = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    debug!("Active window: {:?}", title);
+    (!title.is_empty()).then_some(title)
+}